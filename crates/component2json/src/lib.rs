@@ -30,6 +30,15 @@ pub struct ToolMetadata {
     pub schema: Value,
 }
 
+/// An export of a component that is not exposed as a tool.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedExport {
+    /// Export name, with items of an interface written as `interface#item`
+    pub name: String,
+    /// Why the export is not a tool
+    pub reason: String,
+}
+
 /// Error type for tool name validation
 #[derive(Debug, thiserror::Error)]
 pub enum ValidationError {
@@ -146,6 +155,57 @@ pub fn component_exports_to_tools(
     tools
 }
 
+/// Return the exports of a component that [`component_exports_to_tools`] does
+/// not turn into tools, with the reason each one is skipped.
+pub fn component_skipped_exports(component: &Component, engine: &Engine) -> Vec<SkippedExport> {
+    let mut skipped = Vec::new();
+    for (export_name, export_item) in component.component_type().exports(engine) {
+        gather_skipped_exports(export_name.to_string(), &export_item, engine, &mut skipped);
+    }
+    skipped
+}
+
+fn gather_skipped_exports(
+    name: String,
+    item: &ComponentItem,
+    engine: &Engine,
+    skipped: &mut Vec<SkippedExport>,
+) {
+    let reason = match item {
+        ComponentItem::ComponentFunc(_) => return,
+        ComponentItem::Component(component) => {
+            for (export_name, export_item) in component.exports(engine) {
+                gather_skipped_exports(
+                    format!("{name}#{export_name}"),
+                    &export_item,
+                    engine,
+                    skipped,
+                );
+            }
+            return;
+        }
+        ComponentItem::ComponentInstance(instance) => {
+            for (export_name, export_item) in instance.exports(engine) {
+                gather_skipped_exports(
+                    format!("{name}#{export_name}"),
+                    &export_item,
+                    engine,
+                    skipped,
+                );
+            }
+            return;
+        }
+        ComponentItem::CoreFunc(_) => "core function; only component functions become tools",
+        ComponentItem::Module(_) => "core module",
+        ComponentItem::Type(_) => "type definition",
+        ComponentItem::Resource(_) => "resource type; only its functions become tools",
+    };
+    skipped.push(SkippedExport {
+        name,
+        reason: reason.to_string(),
+    });
+}
+
 /// Given a component and a wasmtime engine, return a full JSON schema of the component's exports.
 ///
 /// The `output` parameter determines whether to include the output schema for functions.
@@ -183,6 +243,66 @@ pub fn extract_package_docs(wasm_bytes: &[u8]) -> Option<Value> {
     None
 }
 
+/// Renders package-docs as Markdown.
+///
+/// The output starts with the package documentation, followed by a section per
+/// world and interface with its own documentation and that of its exported
+/// functions. Returns `None` if the package-docs contain no documentation text.
+pub fn package_docs_to_markdown(package_docs: &Value) -> Option<String> {
+    fn docs_of(value: &Value) -> Option<&str> {
+        value
+            .get("docs")
+            .and_then(|d| d.as_str())
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+    }
+
+    fn push_section(sections: &mut Vec<String>, heading: String, item: &Value, funcs_key: &str) {
+        let mut section = Vec::new();
+        if let Some(docs) = docs_of(item) {
+            section.push(docs.to_string());
+        }
+        if let Some(funcs) = item.get(funcs_key).and_then(|v| v.as_object()) {
+            for (name, func) in funcs {
+                if let Some(docs) = docs_of(func) {
+                    section.push(format!("### `{name}`\n\n{docs}"));
+                }
+            }
+        }
+        if !section.is_empty() {
+            sections.push(heading);
+            sections.extend(section);
+        }
+    }
+
+    let mut sections = Vec::new();
+    if let Some(docs) = docs_of(package_docs) {
+        sections.push(docs.to_string());
+    }
+    if let Some(worlds) = package_docs.get("worlds").and_then(|v| v.as_object()) {
+        for (name, world) in worlds {
+            push_section(
+                &mut sections,
+                format!("## World `{name}`"),
+                world,
+                "func_exports",
+            );
+        }
+    }
+    if let Some(interfaces) = package_docs.get("interfaces").and_then(|v| v.as_object()) {
+        for (name, interface) in interfaces {
+            push_section(
+                &mut sections,
+                format!("## Interface `{name}`"),
+                interface,
+                "funcs",
+            );
+        }
+    }
+
+    (!sections.is_empty()).then(|| sections.join("\n\n"))
+}
+
 /// Finds documentation for a specific exported function in package-docs.
 ///
 /// Searches through all worlds in the package-docs structure to find documentation
@@ -205,6 +325,74 @@ fn find_function_docs(package_docs: &Value, function_name: &str) -> Option<Strin
     None
 }
 
+/// Info string of the fenced blocks in function docs that hold example
+/// invocations.
+pub const EXAMPLE_FENCE_TAG: &str = "example";
+
+/// An example invocation of a tool, taken from its function docs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolExample {
+    /// What the example does; empty if the block gives no description
+    pub description: String,
+    /// Arguments of the call, as a JSON object
+    pub arguments: Value,
+}
+
+/// Splits the example blocks off function docs.
+///
+/// An example is a fenced block tagged `example`, optionally followed by a
+/// description, holding the call's arguments as a JSON object:
+///
+/// ````text
+/// ```example Fetch the front page
+/// {"url": "https://example.com"}
+/// ```
+/// ````
+///
+/// Returns the docs without the example blocks and the examples in the order
+/// they appear. Blocks whose body is not a JSON object are left in the docs.
+pub fn extract_tool_examples(docs: &str) -> (String, Vec<ToolExample>) {
+    let mut text = Vec::new();
+    let mut examples = Vec::new();
+    let mut lines = docs.lines();
+    while let Some(line) = lines.next() {
+        let description = line
+            .trim()
+            .strip_prefix("```")
+            .and_then(|info| info.strip_prefix(EXAMPLE_FENCE_TAG))
+            .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace));
+        let Some(description) = description else {
+            text.push(line);
+            continue;
+        };
+        let mut block = Vec::new();
+        let mut closed = false;
+        for body_line in lines.by_ref() {
+            if body_line.trim() == "```" {
+                closed = true;
+                break;
+            }
+            block.push(body_line);
+        }
+        match serde_json::from_str::<Value>(&block.join("\n")) {
+            Ok(arguments) if closed && arguments.is_object() => examples.push(ToolExample {
+                description: description.trim().to_string(),
+                arguments,
+            }),
+            _ => {
+                text.push(line);
+                text.extend(block);
+                if closed {
+                    text.push("```");
+                }
+            }
+        }
+    }
+    // Removed blocks leave the blank lines around them behind
+    text.dedup_by(|line, previous| line.trim().is_empty() && previous.trim().is_empty());
+    (text.join("\n").trim().to_string(), examples)
+}
+
 /// Given a component with package-docs and a wasmtime engine, return structured tool metadata with normalized names and documentation.
 ///
 /// The `output` parameter determines whether to include the output schema for functions.
@@ -496,15 +684,22 @@ fn component_func_to_schema_with_docs(
     tool_obj.insert("name".to_string(), json!(name));
 
     // Look up documentation or fall back to auto-generated
-    let description = if let (Some(func_id), Some(docs)) = (function_id, package_docs) {
-        find_function_docs(docs, &func_id.function_name)
-            .unwrap_or_else(|| format!("Auto-generated schema for function '{name}'"))
-    } else {
-        format!("Auto-generated schema for function '{name}'")
+    let docs = function_id
+        .zip(package_docs)
+        .and_then(|(func_id, docs)| find_function_docs(docs, &func_id.function_name));
+    let (description, examples) = match docs {
+        Some(docs) => extract_tool_examples(&docs),
+        None => (
+            format!("Auto-generated schema for function '{name}'"),
+            Vec::new(),
+        ),
     };
 
     tool_obj.insert("description".to_string(), json!(description));
     tool_obj.insert("inputSchema".to_string(), input_schema);
+    if !examples.is_empty() {
+        tool_obj.insert("_meta".to_string(), json!({ "examples": examples }));
+    }
 
     if output {
         let results: Vec<_> = func.results().collect();
@@ -1018,7 +1213,7 @@ mod tests {
 
     use super::*;
 
-    fn result_schema<'a>(schema: &'a Value) -> &'a Value {
+    fn result_schema(schema: &Value) -> &Value {
         schema
             .get("properties")
             .and_then(|props| props.get("result"))
@@ -1242,6 +1437,32 @@ mod tests {
         assert_eq!(tools.len(), 0);
     }
 
+    #[test]
+    fn test_skipped_exports_have_reasons() {
+        let engine = Engine::default();
+        let wat = r#"(component
+            (core module $m)
+            (export "m" (core module $m))
+            (type $point (record (field "x" u32)))
+            (export "point" (type $point))
+        )"#;
+        let component = Component::new(&engine, wat).unwrap();
+        assert!(component_exports_to_tools(&component, &engine, false).is_empty());
+        assert_eq!(
+            component_skipped_exports(&component, &engine),
+            [
+                SkippedExport {
+                    name: "m".to_string(),
+                    reason: "core module".to_string(),
+                },
+                SkippedExport {
+                    name: "point".to_string(),
+                    reason: "type definition".to_string(),
+                },
+            ]
+        );
+    }
+
     #[test]
     fn test_root_component_exports() {
         let mut config = wasmtime::Config::new();
@@ -2149,6 +2370,44 @@ mod tests {
         assert!(docs.is_none());
     }
 
+    #[test]
+    fn test_package_docs_to_markdown() {
+        let wasm_bytes = std::fs::read("testdata/fetch-rs.wasm").unwrap();
+        let docs = extract_package_docs(&wasm_bytes).unwrap();
+
+        let markdown = package_docs_to_markdown(&docs).unwrap();
+        assert_eq!(
+            markdown,
+            "## World `fetch`\n\nAn example world for the component to target.\n\n### `fetch`\n\nFetch data from a URL and return the response body as a String"
+        );
+
+        assert!(package_docs_to_markdown(&json!({ "worlds": { "empty": {} } })).is_none());
+    }
+
+    #[test]
+    fn test_extract_tool_examples() {
+        let docs = "Fetch a URL.\n\n```example Fetch the front page\n{\"url\": \"https://example.com\"}\n```\n\n```example\n{\"url\": \"https://example.org\",\n \"method\": \"HEAD\"}\n```\n\n```example\nnot json\n```";
+        let (text, examples) = extract_tool_examples(docs);
+        assert_eq!(text, "Fetch a URL.\n\n```example\nnot json\n```");
+        assert_eq!(
+            examples,
+            vec![
+                ToolExample {
+                    description: "Fetch the front page".to_string(),
+                    arguments: json!({ "url": "https://example.com" }),
+                },
+                ToolExample {
+                    description: String::new(),
+                    arguments: json!({ "url": "https://example.org", "method": "HEAD" }),
+                },
+            ]
+        );
+
+        let (text, examples) = extract_tool_examples("```examples\n{}\n```");
+        assert_eq!(text, "```examples\n{}\n```");
+        assert!(examples.is_empty());
+    }
+
     #[test]
     fn test_find_function_docs() {
         let docs = json!({
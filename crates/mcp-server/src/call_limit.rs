@@ -0,0 +1,200 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Server-wide ceiling on concurrent tool calls.
+//!
+//! Every tool call takes a slot from a [`CallLimiter`] before it runs, and
+//! an `invoke-batch` takes one for each of the calls it makes. When
+//! all slots are taken the call waits for one, up to the configured queue
+//! timeout, and is then refused with a [`SERVER_AT_CAPACITY`] error so that
+//! a client firing large batches gets pushed back instead of queueing
+//! unbounded work.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rmcp::model::{ErrorCode, ErrorData};
+use serde_json::json;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// JSON-RPC error code of a call refused because the server is at capacity.
+pub const SERVER_AT_CAPACITY: ErrorCode = ErrorCode(-32001);
+
+/// Default time a call waits for a free slot before it is refused.
+pub const DEFAULT_CALL_QUEUE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How many tool calls may run at once and how long a call waits for a slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallLimitConfig {
+    /// Tool calls allowed to run at the same time
+    pub max_concurrent_calls: usize,
+    /// How long a call waits for a free slot before it is refused
+    pub queue_timeout: Duration,
+    /// Let the built-in tools that only read, `unload-component`,
+    /// `deactivate-component`, and extra tools annotated as read-only run
+    /// without a slot, so an operator can still inspect and stop components
+    /// while component calls fill every slot. Tools that load components or
+    /// change permissions, and each call of an `invoke-batch`, always take a
+    /// slot
+    pub exempt_builtin_tools: bool,
+}
+
+impl CallLimitConfig {
+    /// Allow `max_concurrent_calls` calls at once, with the default queue
+    /// timeout and no tool exempt.
+    pub fn new(max_concurrent_calls: usize) -> Self {
+        Self {
+            max_concurrent_calls,
+            queue_timeout: DEFAULT_CALL_QUEUE_TIMEOUT,
+            exempt_builtin_tools: false,
+        }
+    }
+}
+
+/// Tool calls currently holding or waiting for a slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallLoad {
+    /// Tool calls allowed to run at the same time
+    pub limit: usize,
+    /// Tool calls running
+    pub in_flight: usize,
+    /// Tool calls waiting for a slot
+    pub queued: usize,
+}
+
+/// Hands out the slots of a [`CallLimitConfig`].
+pub(crate) struct CallLimiter {
+    config: CallLimitConfig,
+    semaphore: Arc<Semaphore>,
+    in_flight: Arc<AtomicUsize>,
+    queued: AtomicUsize,
+}
+
+/// A slot held by a running call, given back when dropped.
+pub(crate) struct CallSlot {
+    _permit: OwnedSemaphorePermit,
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl Drop for CallSlot {
+    fn drop(&mut self) {
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Counts a call as queued until dropped, also when the waiting call is
+/// cancelled.
+struct Queued<'a>(&'a AtomicUsize);
+
+impl<'a> Queued<'a> {
+    fn new(queued: &'a AtomicUsize) -> Self {
+        queued.fetch_add(1, Ordering::Relaxed);
+        Self(queued)
+    }
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl CallLimiter {
+    pub(crate) fn new(config: CallLimitConfig) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(config.max_concurrent_calls.max(1))),
+            config,
+            in_flight: Arc::default(),
+            queued: AtomicUsize::new(0),
+        }
+    }
+
+    /// Whether calls to the tools [`CallLimitConfig::exempt_builtin_tools`]
+    /// names run without a slot.
+    pub(crate) fn exempts_builtin_tools(&self) -> bool {
+        self.config.exempt_builtin_tools
+    }
+
+    /// Wait for a free slot, failing with [`SERVER_AT_CAPACITY`] once the
+    /// queue timeout is over.
+    pub(crate) async fn acquire(&self, tool_name: &str) -> Result<CallSlot, ErrorData> {
+        let queued = Queued::new(&self.queued);
+        let permit = tokio::time::timeout(
+            self.config.queue_timeout,
+            Arc::clone(&self.semaphore).acquire_owned(),
+        )
+        .await;
+        drop(queued);
+        match permit {
+            Ok(Ok(permit)) => {
+                self.in_flight.fetch_add(1, Ordering::Relaxed);
+                Ok(CallSlot {
+                    _permit: permit,
+                    in_flight: Arc::clone(&self.in_flight),
+                })
+            }
+            _ => Err(ErrorData::new(
+                SERVER_AT_CAPACITY,
+                format!(
+                    "Server at capacity: {tool_name} waited {} ms for one of {} call slots",
+                    self.config.queue_timeout.as_millis(),
+                    self.config.max_concurrent_calls
+                ),
+                Some(json!({
+                    "reason": "server_at_capacity",
+                    "max_concurrent_calls": self.config.max_concurrent_calls,
+                    "queue_timeout_ms": self.config.queue_timeout.as_millis() as u64,
+                })),
+            )),
+        }
+    }
+
+    pub(crate) fn load(&self) -> CallLoad {
+        CallLoad {
+            limit: self.config.max_concurrent_calls,
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+            queued: self.queued.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_calls_over_the_limit_wait_then_are_refused() {
+        let limiter = Arc::new(CallLimiter::new(CallLimitConfig {
+            max_concurrent_calls: 1,
+            queue_timeout: Duration::from_millis(50),
+            exempt_builtin_tools: true,
+        }));
+
+        let slot = limiter.acquire("fetch").await.unwrap();
+        assert_eq!(
+            limiter.load(),
+            CallLoad {
+                limit: 1,
+                in_flight: 1,
+                queued: 0
+            }
+        );
+
+        let waiting = tokio::spawn({
+            let limiter = Arc::clone(&limiter);
+            async move { limiter.acquire("fetch").await.map(|_| ()) }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(limiter.load().queued, 1);
+
+        let error = waiting.await.unwrap().unwrap_err();
+        assert_eq!(error.code, SERVER_AT_CAPACITY);
+        assert_eq!(error.data.unwrap()["reason"], "server_at_capacity");
+        assert_eq!(limiter.load().queued, 0);
+
+        drop(slot);
+        assert_eq!(limiter.load().in_flight, 0);
+        let _slot = limiter.acquire("fetch").await.unwrap();
+    }
+}
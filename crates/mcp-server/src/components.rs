@@ -2,49 +2,82 @@
 // Licensed under the MIT license.
 
 use std::borrow::Cow;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use anyhow::Result;
 use futures::stream::{self, StreamExt};
-use rmcp::model::{CallToolRequestParam, CallToolResult, Content, Tool};
-use rmcp::{Peer, RoleServer};
+use rmcp::model::{CallToolRequestParam, CallToolResult, Content, Meta, Tool};
 use serde_json::{json, Value};
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, instrument};
+use wassette::registry::{self, RegistryEntry, Resolution};
 use wassette::schema::{canonicalize_output_schema, ensure_structured_result};
-use wassette::{ComponentLoadOutcome, LifecycleManager, LoadResult};
+use wassette::{
+    validate_component_id, CapturedOutput, ComponentCallOutput, ComponentLoadOutcome,
+    ComponentUnloadOutcome, DownloadCancelled, LifecycleManager, LoadResult, ProgressReporter,
+    UnloadOptions,
+};
+
+use crate::call_limit::CallLimiter;
+use crate::logging::{self, StderrLog};
+
+/// Tools converted from the schemas in the component registry, kept for as
+/// long as the registry serves the same schema so listing tools again does
+/// not convert them again.
+#[derive(Default)]
+pub(crate) struct ToolCache {
+    /// Keyed by the address of the schema, which the entry keeps alive
+    tools: Mutex<HashMap<usize, (Arc<Value>, Tool)>>,
+}
 
-#[instrument(skip(lifecycle_manager))]
-pub(crate) async fn get_component_tools(lifecycle_manager: &LifecycleManager) -> Result<Vec<Tool>> {
+#[instrument(skip_all)]
+pub(crate) async fn get_component_tools(
+    lifecycle_manager: &LifecycleManager,
+    tool_cache: &ToolCache,
+) -> Result<Vec<Tool>> {
     debug!("Listing components");
-    // Use known components (loaded or present on disk) for fast listing
-    let component_ids = lifecycle_manager.list_components_known().await;
-
-    info!(count = component_ids.len(), "Found components");
-    let mut tools = Vec::new();
-
-    for id in component_ids {
-        debug!(component_id = %id, "Getting component details");
-        if let Some(schema) = lifecycle_manager.get_component_schema(&id).await {
-            if let Some(arr) = schema.get("tools").and_then(|v| v.as_array()) {
-                let tool_count = arr.len();
-                debug!(component_id = %id, tool_count, "Found tools in component");
-                for tool_json in arr {
-                    if let Some(tool) = parse_tool_schema(tool_json) {
-                        tools.push(tool);
-                    }
-                }
-            }
+    // Ordered by component ID, then tool name, so the list is identical
+    // between runs
+    let schemas = lifecycle_manager.list_component_tools().await;
+
+    let mut cached = tool_cache.tools.lock().unwrap();
+    let mut kept = HashMap::with_capacity(schemas.len());
+    let mut tools = Vec::with_capacity(schemas.len());
+    for (component_id, schema) in schemas {
+        if lifecycle_manager.is_component_hidden(&component_id) {
+            debug!(%component_id, "Skipping hidden component");
+            continue;
         }
+        let key = Arc::as_ptr(&schema) as usize;
+        let tool = match cached.remove(&key) {
+            Some((_, tool)) => tool,
+            None => match parse_tool_schema(&schema) {
+                Some(tool) => with_tool_id(tool, &component_id),
+                None => continue,
+            },
+        };
+        tools.push(tool.clone());
+        kept.insert(key, (schema, tool));
     }
+    // Schemas no longer in the registry are dropped with their tools
+    *cached = kept;
     info!(total_tools = tools.len(), "Total tools collected");
     Ok(tools)
 }
 
-#[instrument(skip(lifecycle_manager))]
-pub(crate) async fn handle_load_component(
+/// Load the component at `path`. A load whose request is cancelled while the
+/// component is downloading stops the download and reports a `cancelled`
+/// status instead of a failure. With `progress`, the download and
+/// compilation are reported to it rather than printed to a terminal.
+#[instrument(skip(lifecycle_manager, cancellation, progress))]
+pub async fn handle_load_component(
     req: &CallToolRequestParam,
     lifecycle_manager: &LifecycleManager,
-    server_peer: Peer<RoleServer>,
+    cancellation: &CancellationToken,
+    progress: Option<&dyn ProgressReporter>,
 ) -> Result<CallToolResult> {
     let args = extract_args_from_request(req)?;
     let path = args
@@ -58,7 +91,7 @@ pub(crate) async fn handle_load_component(
         "Component load operation started"
     );
 
-    match lifecycle_manager.load_component(path).await {
+    match load_component(lifecycle_manager, path, cancellation, progress).await {
         Ok(outcome) => {
             info!(
                 path = %path,
@@ -66,9 +99,12 @@ pub(crate) async fn handle_load_component(
                 operation = "load-component",
                 "Component loaded successfully"
             );
-            handle_tool_list_notification(Some(server_peer), &outcome.component_id, "load").await;
             create_load_component_success_result(&outcome)
         }
+        Err(e) if e.downcast_ref::<DownloadCancelled>().is_some() => {
+            info!(path = %path, operation = "load-component", "Component load cancelled");
+            create_cancelled_result(json!({ "path": path }))
+        }
         Err(e) => {
             error!(
                 path = %path,
@@ -85,33 +121,163 @@ pub(crate) async fn handle_load_component(
     }
 }
 
+async fn load_component(
+    lifecycle_manager: &LifecycleManager,
+    uri: &str,
+    cancellation: &CancellationToken,
+    progress: Option<&dyn ProgressReporter>,
+) -> Result<ComponentLoadOutcome> {
+    match progress {
+        Some(progress) => {
+            lifecycle_manager
+                .load_component_with_progress(uri, cancellation, progress)
+                .await
+        }
+        None => {
+            lifecycle_manager
+                .load_component_with_cancellation(uri, cancellation)
+                .await
+        }
+    }
+}
+
+/// Resolve a registry name to its URI and load it, reporting to `progress`
+/// like [`handle_load_component`].
+#[instrument(skip(lifecycle_manager, cancellation, progress))]
+pub async fn handle_install_component(
+    req: &CallToolRequestParam,
+    lifecycle_manager: &LifecycleManager,
+    cancellation: &CancellationToken,
+    progress: Option<&dyn ProgressReporter>,
+) -> Result<CallToolResult> {
+    let args = extract_args_from_request(req)?;
+    let name = args
+        .get("name")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'name'"))?;
+
+    debug!(
+        name = %name,
+        operation = "install-component",
+        "Component install operation started"
+    );
+
+    let entry = match registry::resolve(name) {
+        Resolution::Found(entry) => entry,
+        Resolution::Ambiguous(candidates) => {
+            // Only offer candidates the server is allowed to load; if a single
+            // one remains, that is the component the caller meant.
+            let (mut allowed, rejected): (Vec<_>, Vec<_>) =
+                candidates.into_iter().partition(|candidate| {
+                    lifecycle_manager
+                        .check_component_source(&candidate.uri)
+                        .is_ok()
+                });
+            match allowed.len() {
+                // Ambiguous names have several candidates, so at least one was rejected
+                0 => {
+                    return Err(lifecycle_manager
+                        .check_component_source(&rejected[0].uri)
+                        .unwrap_err()
+                        .into())
+                }
+                1 => allowed.remove(0),
+                _ => {
+                    info!(
+                        name = %name,
+                        candidates = allowed.len(),
+                        operation = "install-component",
+                        "Registry name is ambiguous"
+                    );
+                    return create_install_candidates_result(name, &allowed);
+                }
+            }
+        }
+        Resolution::NotFound => {
+            return Err(anyhow::anyhow!(
+                "Component '{}' not found in registry. Use 'search-components' to list available components.",
+                name
+            ));
+        }
+    };
+
+    match load_component(lifecycle_manager, &entry.uri, cancellation, progress).await {
+        Ok(outcome) => {
+            info!(
+                name = %name,
+                uri = %entry.uri,
+                component_id = %outcome.component_id,
+                operation = "install-component",
+                "Component installed successfully"
+            );
+            let mut payload = load_outcome_to_json(&outcome);
+            payload["registry_entry"] = json!(entry);
+
+            Ok(CallToolResult {
+                content: vec![Content::text(serde_json::to_string(&payload)?)],
+                structured_content: None,
+                is_error: None,
+                meta: None,
+            })
+        }
+        Err(e) if e.downcast_ref::<DownloadCancelled>().is_some() => {
+            info!(name = %name, uri = %entry.uri, operation = "install-component", "Component install cancelled");
+            create_cancelled_result(json!({ "name": name, "uri": entry.uri }))
+        }
+        Err(e) => {
+            error!(
+                name = %name,
+                uri = %entry.uri,
+                operation = "install-component",
+                error = %e,
+                "Component install operation failed"
+            );
+            Err(anyhow::anyhow!(
+                "Failed to install component '{}' from {}. Error: {}",
+                entry.name,
+                entry.uri,
+                e
+            ))
+        }
+    }
+}
+
 #[instrument(skip(lifecycle_manager))]
-pub(crate) async fn handle_unload_component(
+pub async fn handle_unload_component(
     req: &CallToolRequestParam,
     lifecycle_manager: &LifecycleManager,
-    server_peer: Peer<RoleServer>,
 ) -> Result<CallToolResult> {
     let args = extract_args_from_request(req)?;
     let id = args
         .get("id")
         .and_then(|v| v.as_str())
         .ok_or_else(|| anyhow::anyhow!("Missing 'id' in arguments"))?;
+    validate_component_id(id)?;
+    let options = UnloadOptions {
+        skip_hooks: args
+            .get("skip_hooks")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+    };
 
     debug!(
         component_id = %id,
+        skip_hooks = options.skip_hooks,
         operation = "unload-component",
         "Component unload operation started"
     );
 
-    match lifecycle_manager.unload_component(id).await {
-        Ok(()) => {
+    match lifecycle_manager
+        .unload_component_with_options(id, options)
+        .await
+    {
+        Ok(outcome) => {
             info!(
                 component_id = %id,
                 operation = "unload-component",
                 "Component unloaded successfully"
             );
-            handle_tool_list_notification(Some(server_peer), id, "unload").await;
-            create_component_success_result("unload", id)
+            create_unload_component_success_result(&outcome)
         }
         Err(e) => {
             error!(
@@ -125,12 +291,65 @@ pub(crate) async fn handle_unload_component(
     }
 }
 
+#[instrument(skip(lifecycle_manager))]
+pub async fn handle_deactivate_component(
+    req: &CallToolRequestParam,
+    lifecycle_manager: &LifecycleManager,
+) -> Result<CallToolResult> {
+    let args = extract_args_from_request(req)?;
+
+    let component_id = args
+        .get("component_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'component_id'"))?;
+    validate_component_id(component_id)?;
+
+    info!(component_id, "Deactivating component");
+    let deactivated = lifecycle_manager.deactivate_component(component_id).await?;
+
+    let result = json!({
+        "status": if deactivated { "component deactivated" } else { "component not loaded" },
+        "component_id": component_id
+    });
+
+    Ok(CallToolResult {
+        content: vec![Content::text(serde_json::to_string(&result)?)],
+        structured_content: None,
+        is_error: None,
+        meta: None,
+    })
+}
+
 #[instrument(skip(lifecycle_manager))]
 pub async fn handle_component_call(
     req: &CallToolRequestParam,
     lifecycle_manager: &LifecycleManager,
+) -> Result<CallToolResult> {
+    call_component(req, lifecycle_manager, false, None).await
+}
+
+/// [`handle_component_call`], recording where the time of the call went in
+/// the result's `_meta.wassette.timing` when `call_timing` is set.
+///
+/// What the component wrote to captured stdout and stderr is appended to
+/// the result as text blocks after the result itself. With `stderr_log`,
+/// each line the component writes to stderr is also sent to the client as
+/// a log message while the call runs.
+///
+/// Arguments that exceed the manager's
+/// [`ParameterLimits`](wassette::ParameterLimits) fail the call with
+/// [`ParameterLimitExceeded`](wassette::ParameterLimitExceeded) before they
+/// are passed on.
+#[instrument(skip(lifecycle_manager, stderr_log))]
+pub(crate) async fn call_component(
+    req: &CallToolRequestParam,
+    lifecycle_manager: &LifecycleManager,
+    call_timing: bool,
+    stderr_log: Option<StderrLog<'_>>,
 ) -> Result<CallToolResult> {
     let args = extract_args_from_request(req)?;
+    let args = Value::Object(args);
+    lifecycle_manager.parameter_limits().check_value(&args)?;
 
     let component_id = lifecycle_manager
         .get_component_id_for_tool(&req.name)
@@ -147,39 +366,47 @@ pub async fn handle_component_call(
         .get_tool_schema_for_component(&component_id, &req.name)
         .await;
 
-    let result = lifecycle_manager
-        .execute_component_call(&component_id, &req.name, &serde_json::to_string(&args)?)
-        .await;
+    let parameters = serde_json::to_string(&args)?;
+    let result = match stderr_log.and_then(|log| log.forward(&component_id, &req.name)) {
+        Some((stderr_lines, forwarding)) => {
+            let result = lifecycle_manager
+                .execute_component_call_with_stderr(
+                    &component_id,
+                    &req.name,
+                    &parameters,
+                    stderr_lines,
+                )
+                .await;
+            logging::drain(forwarding).await;
+            result
+        }
+        None => {
+            lifecycle_manager
+                .execute_component_call_with_output(&component_id, &req.name, &parameters)
+                .await
+        }
+    };
 
     match result {
-        Ok(result_str) => {
+        Ok(ComponentCallOutput {
+            result,
+            timing,
+            output,
+        }) => {
             debug!(
                 function_name = %req.name,
                 component_id = %component_id,
                 "Component function invocation completed successfully"
             );
 
-            let parsed_value = parse_structured_result(&result_str);
-            let display_value = unwrap_result_wrapper(&parsed_value);
-            let response_text = value_to_text(&display_value)?;
-
-            let normalized_schema = tool_schema
-                .as_ref()
-                .and_then(|schema| schema.get("outputSchema"))
-                .and_then(normalize_output_schema);
-
-            let structured_content = normalized_schema.as_ref().map(|schema| {
-                align_structured_result_with_schema(Some(schema), parsed_value.clone())
+            let mut call_result = component_call_result(result, tool_schema.as_deref())?;
+            call_result.content.extend(captured_output_content(&output));
+            call_result.meta = call_timing.then(|| {
+                let mut meta = Meta::new();
+                meta.insert(TIMING_META_KEY.to_string(), json!({ "timing": timing }));
+                meta
             });
-
-            let contents = vec![Content::text(response_text)];
-
-            Ok(CallToolResult {
-                content: contents,
-                structured_content,
-                is_error: Some(false),
-                meta: None,
-            })
+            Ok(call_result)
         }
         Err(e) => {
             error!(
@@ -193,8 +420,236 @@ pub async fn handle_component_call(
     }
 }
 
-fn parse_structured_result(result: &str) -> Value {
-    serde_json::from_str(result).unwrap_or_else(|_| Value::String(result.to_string()))
+/// The tool call result for the JSON `result` of a component call: the
+/// unwrapped result as text, and the result as structured content if the
+/// tool declares an output schema.
+fn component_call_result(result: Value, tool_schema: Option<&Value>) -> Result<CallToolResult> {
+    let response_text = value_to_text(&unwrap_result_wrapper(&result))?;
+
+    let normalized_schema = tool_schema
+        .and_then(|schema| schema.get("outputSchema"))
+        .and_then(normalize_output_schema);
+
+    let structured_content = normalized_schema
+        .as_ref()
+        .map(|schema| align_structured_result_with_schema(Some(schema), result));
+
+    Ok(CallToolResult {
+        content: vec![Content::text(response_text)],
+        structured_content,
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+/// Text blocks holding the non-empty captured streams of a call, each
+/// headed by the stream's name.
+fn captured_output_content(output: &CapturedOutput) -> Vec<Content> {
+    [("stdout", &output.stdout), ("stderr", &output.stderr)]
+        .into_iter()
+        .filter(|(_, text)| !text.is_empty())
+        .map(|(stream, text)| Content::text(format!("[{stream}]\n{text}")))
+        .collect()
+}
+
+/// Most argument objects a single `invoke-batch` call accepts.
+const MAX_BATCH_ITEMS: usize = 100;
+/// Calls an `invoke-batch` runs at once unless it asks otherwise.
+const DEFAULT_BATCH_CONCURRENCY: usize = 4;
+/// Most calls an `invoke-batch` runs at once.
+const MAX_BATCH_CONCURRENCY: usize = 16;
+
+/// Call one component tool with each of a list of argument objects, a few at
+/// a time. A failing call is reported in its slot of the results and does
+/// not stop the others. Calls still running when the batch's timeout passes
+/// or the request is cancelled are reported as failed.
+pub async fn handle_invoke_batch(
+    req: &CallToolRequestParam,
+    lifecycle_manager: &LifecycleManager,
+    cancellation: &CancellationToken,
+) -> Result<CallToolResult> {
+    invoke_batch(req, lifecycle_manager, cancellation, None).await
+}
+
+/// [`handle_invoke_batch`], with each call of the batch taking a slot from
+/// `call_limiter` while it runs, so a batch counts against the server-wide
+/// ceiling like the same number of separate calls.
+#[instrument(skip(lifecycle_manager, cancellation, call_limiter))]
+pub(crate) async fn invoke_batch(
+    req: &CallToolRequestParam,
+    lifecycle_manager: &LifecycleManager,
+    cancellation: &CancellationToken,
+    call_limiter: Option<&CallLimiter>,
+) -> Result<CallToolResult> {
+    let args = extract_args_from_request(req)?;
+
+    let tool = args
+        .get("tool")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'tool'"))?;
+    let items = args
+        .get("arguments")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'arguments'"))?;
+    if items.is_empty() || items.len() > MAX_BATCH_ITEMS {
+        return Err(anyhow::anyhow!(
+            "'arguments' must hold between 1 and {} argument objects, got {}",
+            MAX_BATCH_ITEMS,
+            items.len()
+        ));
+    }
+    let concurrency = args
+        .get("concurrency")
+        .and_then(|v| v.as_u64())
+        .map_or(DEFAULT_BATCH_CONCURRENCY, |n| {
+            (n as usize).clamp(1, MAX_BATCH_CONCURRENCY)
+        });
+    let deadline = args
+        .get("timeout_ms")
+        .and_then(|v| v.as_u64())
+        .map(|ms| Instant::now() + Duration::from_millis(ms));
+
+    if crate::tools::is_builtin_tool(tool) {
+        return Err(anyhow::anyhow!(
+            "'{}' is a built-in tool; invoke-batch only calls component tools",
+            tool
+        ));
+    }
+    let component_id = lifecycle_manager
+        .get_component_id_for_tool(tool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to find component for tool '{}': {}", tool, e))?;
+    if let Some(expected) = args.get("component_id").and_then(|v| v.as_str()) {
+        if expected != component_id {
+            return Err(anyhow::anyhow!(
+                "Tool '{}' belongs to component '{}', not '{}'",
+                tool,
+                component_id,
+                expected
+            ));
+        }
+    }
+
+    info!(
+        component_id = %component_id,
+        tool = %tool,
+        items = items.len(),
+        concurrency,
+        "Batch invocation started"
+    );
+    let start_time = Instant::now();
+    let batch = BatchCall {
+        lifecycle_manager,
+        component_id: &component_id,
+        tool,
+        deadline,
+        cancellation,
+        call_limiter,
+    };
+    let calls: Vec<_> = items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| batch.invoke(index, item))
+        .collect();
+    let results: Vec<Value> = stream::iter(calls).buffered(concurrency).collect().await;
+    let failed = results
+        .iter()
+        .filter(|result| result.get("error").is_some())
+        .count();
+
+    let payload = json!({
+        "component_id": component_id,
+        "tool": tool,
+        "results": results,
+        "summary": {
+            "total": results.len(),
+            "succeeded": results.len() - failed,
+            "failed": failed,
+            "duration_ms": start_time.elapsed().as_millis() as u64,
+        }
+    });
+    Ok(CallToolResult {
+        content: vec![Content::text(serde_json::to_string(&payload)?)],
+        structured_content: None,
+        is_error: None,
+        meta: None,
+    })
+}
+
+/// What the calls of one `invoke-batch` share.
+struct BatchCall<'a> {
+    lifecycle_manager: &'a LifecycleManager,
+    component_id: &'a str,
+    tool: &'a str,
+    /// When calls still running are reported as timed out
+    deadline: Option<Instant>,
+    cancellation: &'a CancellationToken,
+    call_limiter: Option<&'a CallLimiter>,
+}
+
+impl BatchCall<'_> {
+    /// Call the batch's tool with the argument object at `index`, reporting
+    /// the result or the error in that slot.
+    async fn invoke(&self, index: usize, arguments: &Value) -> Value {
+        let BatchCall {
+            lifecycle_manager,
+            component_id,
+            tool,
+            deadline,
+            cancellation,
+            call_limiter,
+        } = *self;
+        let start_time = Instant::now();
+        let outcome = if !arguments.is_object() {
+            Err(anyhow::anyhow!("Arguments must be a JSON object"))
+        } else {
+            let parameters = arguments.to_string();
+            let call = async {
+                let _slot = match call_limiter {
+                    Some(limiter) => Some(
+                        limiter
+                            .acquire(tool)
+                            .await
+                            .map_err(|e| anyhow::anyhow!("{}", e.message))?,
+                    ),
+                    None => None,
+                };
+                lifecycle_manager
+                    .execute_component_call_timed(component_id, tool, &parameters)
+                    .await
+                    .map(|(result, _)| result)
+            };
+            let timed_out = async {
+                match deadline {
+                    Some(deadline) => tokio::time::sleep_until(deadline).await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                biased;
+                _ = cancellation.cancelled() => Err(anyhow::anyhow!("The batch was cancelled before this call finished")),
+                _ = timed_out => Err(anyhow::anyhow!("The batch timed out before this call finished")),
+                result = call => result,
+            }
+        };
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+
+        match outcome {
+            Ok(result) => json!({
+                "index": index,
+                "result": unwrap_result_wrapper(&result),
+                "duration_ms": duration_ms,
+            }),
+            Err(e) => {
+                debug!(component_id = %component_id, tool = %tool, index, error = %e, "Batch item failed");
+                json!({
+                    "index": index,
+                    "error": e.to_string(),
+                    "duration_ms": duration_ms,
+                })
+            }
+        }
+    }
 }
 
 fn align_structured_result_with_schema(
@@ -235,14 +690,27 @@ fn value_to_text(value: &Value) -> Result<String> {
 
 #[instrument(skip(lifecycle_manager))]
 pub async fn handle_list_components(
+    req: &CallToolRequestParam,
     lifecycle_manager: &LifecycleManager,
 ) -> Result<CallToolResult> {
     info!("Listing loaded components");
 
+    let args = extract_args_from_request(req)?;
+    let sort = match args.get("sort").and_then(|v| v.as_str()) {
+        None => None,
+        Some(sort @ ("id" | "last-used")) => Some(sort),
+        Some(other) => {
+            return Err(anyhow::anyhow!(
+                "Unknown sort order '{}', expected 'id' or 'last-used'",
+                other
+            ))
+        }
+    };
+
     // Use known components (loaded or present on disk) for fast listing
     let component_ids = lifecycle_manager.list_components_known().await;
 
-    let components_info = stream::iter(component_ids)
+    let mut components_info = stream::iter(component_ids)
         .map(|id| async move {
             debug!(component_id = %id, "Getting component details");
             if let Some(schema) = lifecycle_manager.get_component_schema(&id).await {
@@ -252,16 +720,31 @@ pub async fn handle_list_components(
                     .map(|arr| arr.len())
                     .unwrap_or(0);
 
+                let health = lifecycle_manager.get_component_health(&id).await;
+                let usage = lifecycle_manager.get_component_usage(&id);
+                let docs = lifecycle_manager.get_component_docs(&id).await;
+                let load_state = lifecycle_manager.get_component_load_state(&id).await;
+
                 json!({
                     "id": id,
                     "tools_count": tools_count,
-                    "schema": schema
+                    "schema": schema,
+                    "health": health,
+                    "last_used": usage.map(|u| u.last_used),
+                    "docs_excerpt": docs.as_deref().and_then(docs_excerpt),
+                    "load_state": load_state,
+                    "hidden": lifecycle_manager.is_component_hidden(&id)
                 })
             } else {
                 json!({
                     "id": id,
                     "tools_count": 0,
-                    "schema": null
+                    "schema": null,
+                    "health": null,
+                    "last_used": lifecycle_manager.get_component_usage(&id).map(|u| u.last_used),
+                    "docs_excerpt": null,
+                    "load_state": lifecycle_manager.get_component_load_state(&id).await,
+                    "hidden": lifecycle_manager.is_component_hidden(&id)
                 })
             }
         })
@@ -269,6 +752,18 @@ pub async fn handle_list_components(
         .collect::<Vec<_>>()
         .await;
 
+    match sort {
+        Some("last-used") => components_info.sort_by(|a, b| {
+            // Most recently used first; never-used components last, by ID.
+            b["last_used"]
+                .as_u64()
+                .cmp(&a["last_used"].as_u64())
+                .then_with(|| a["id"].as_str().cmp(&b["id"].as_str()))
+        }),
+        Some(_) => components_info.sort_by(|a, b| a["id"].as_str().cmp(&b["id"].as_str())),
+        None => {}
+    }
+
     let result_text = serde_json::to_string(&json!({
         "components": components_info,
         "total": components_info.len()
@@ -284,6 +779,324 @@ pub async fn handle_list_components(
     })
 }
 
+/// First paragraph of a component's docs that is not a heading.
+fn docs_excerpt(docs: &str) -> Option<&str> {
+    docs.split("\n\n")
+        .map(str::trim)
+        .find(|paragraph| !paragraph.is_empty() && !paragraph.starts_with('#'))
+}
+
+#[instrument(skip(lifecycle_manager))]
+pub async fn handle_get_component_docs(
+    req: &CallToolRequestParam,
+    lifecycle_manager: &LifecycleManager,
+) -> Result<CallToolResult> {
+    let args = extract_args_from_request(req)?;
+
+    let component_id = args
+        .get("component_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'component_id'"))?;
+    validate_component_id(component_id)?;
+
+    info!("Getting docs for component {}", component_id);
+
+    // Ensure the component is available (compile lazily if needed)
+    lifecycle_manager
+        .ensure_component_loaded(component_id)
+        .await
+        .map_err(|e| anyhow::anyhow!("Component not found: {} ({})", component_id, e))?;
+
+    let result = match lifecycle_manager.get_component_docs(component_id).await {
+        Some(docs) => json!({
+            "status": "docs found",
+            "component_id": component_id,
+            "docs": docs
+        }),
+        None => json!({
+            "status": "no docs",
+            "component_id": component_id
+        }),
+    };
+
+    Ok(CallToolResult {
+        content: vec![Content::text(serde_json::to_string(&result)?)],
+        structured_content: None,
+        is_error: None,
+        meta: None,
+    })
+}
+
+/// Version of the load status document returned by `get-load-status` and
+/// `wassette component load-status`. Raised when a field is removed or
+/// changes meaning, so scripts can detect breaking changes.
+pub const LOAD_STATUS_SCHEMA_VERSION: u32 = 1;
+
+/// The load status of one component, or of every known component if
+/// `component_id` is `None`. Components that failed to load, were disabled
+/// by their health probe, or drifted from their wasm file are also listed
+/// under `failures` with the reason. Components that were served tool
+/// schemas from cached metadata that did not match the compiled component
+/// carry a `stale_schema`.
+pub async fn load_status_report(
+    lifecycle_manager: &LifecycleManager,
+    component_id: Option<&str>,
+) -> Result<Value> {
+    let ids = match component_id {
+        Some(component_id) => {
+            validate_component_id(component_id)?;
+            vec![component_id.to_string()]
+        }
+        None => lifecycle_manager.list_components_known().await,
+    };
+    let mut components = Vec::new();
+    let mut failures = Vec::new();
+    for id in ids {
+        let load_state = lifecycle_manager.get_component_load_state(&id).await;
+        if load_state.is_none() && component_id.is_some() {
+            return Err(anyhow::anyhow!("Component not found: {}", id));
+        }
+        if let Some(reason) = load_state.as_ref().and_then(|state| state.reason()) {
+            failures.push(json!({
+                "component_id": id,
+                "error": reason
+            }));
+        }
+        let mut component = json!({
+            "id": id,
+            "load_state": load_state
+        });
+        if let Some(stale) = lifecycle_manager.get_component_stale_schema(&id).await {
+            component["stale_schema"] = json!(stale);
+        }
+        components.push(component);
+    }
+
+    let mut result = match component_id {
+        Some(_) => components.remove(0),
+        None => json!({
+            "total": components.len(),
+            "components": components
+        }),
+    };
+    result["schemaVersion"] = json!(LOAD_STATUS_SCHEMA_VERSION);
+    result["failures"] = json!(failures);
+    Ok(result)
+}
+
+#[instrument(skip(lifecycle_manager))]
+pub async fn handle_get_load_status(
+    req: &CallToolRequestParam,
+    lifecycle_manager: &LifecycleManager,
+) -> Result<CallToolResult> {
+    let args = extract_args_from_request(req)?;
+    let component_id = args.get("component_id").and_then(|v| v.as_str());
+    let result = load_status_report(lifecycle_manager, component_id).await?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(serde_json::to_string(&result)?)],
+        structured_content: None,
+        is_error: None,
+        meta: None,
+    })
+}
+
+#[instrument(skip(lifecycle_manager))]
+pub async fn handle_get_permission_denials(
+    req: &CallToolRequestParam,
+    lifecycle_manager: &LifecycleManager,
+) -> Result<CallToolResult> {
+    let args = extract_args_from_request(req)?;
+
+    let component_id = args
+        .get("component_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'component_id'"))?;
+    validate_component_id(component_id)?;
+    let limit = match args.get("limit") {
+        None | Some(Value::Null) => None,
+        Some(value) => Some(
+            value
+                .as_u64()
+                .ok_or_else(|| anyhow::anyhow!("'limit' must be a non-negative integer"))?,
+        ),
+    };
+
+    if lifecycle_manager
+        .get_component_load_state(component_id)
+        .await
+        .is_none()
+    {
+        return Err(anyhow::anyhow!("Component not found: {}", component_id));
+    }
+
+    let mut denials = lifecycle_manager.permission_denials(component_id);
+    if let Some(limit) = limit {
+        denials
+            .recent
+            .truncate(usize::try_from(limit).unwrap_or(usize::MAX));
+    }
+    let result = json!({
+        "component_id": component_id,
+        "total": denials.total,
+        "by_type": denials.by_type,
+        "recent": denials.recent
+    });
+
+    Ok(CallToolResult {
+        content: vec![Content::text(serde_json::to_string(&result)?)],
+        structured_content: None,
+        is_error: None,
+        meta: None,
+    })
+}
+
+#[instrument(skip(lifecycle_manager))]
+pub async fn handle_list_component_stats(
+    req: &CallToolRequestParam,
+    lifecycle_manager: &LifecycleManager,
+) -> Result<CallToolResult> {
+    let args = extract_args_from_request(req)?;
+
+    let result = match args.get("component_id").and_then(|v| v.as_str()) {
+        Some(component_id) => {
+            validate_component_id(component_id)?;
+            if lifecycle_manager
+                .get_component_load_state(component_id)
+                .await
+                .is_none()
+            {
+                return Err(anyhow::anyhow!("Component not found: {}", component_id));
+            }
+            let stats = lifecycle_manager
+                .get_component_stats(component_id)
+                .unwrap_or_default();
+            let mut result = serde_json::to_value(stats)?;
+            result["component_id"] = json!(component_id);
+            result
+        }
+        None => {
+            let components = lifecycle_manager.get_all_stats();
+            json!({
+                "total": components.len(),
+                "components": components
+            })
+        }
+    };
+
+    Ok(CallToolResult {
+        content: vec![Content::text(serde_json::to_string(&result)?)],
+        structured_content: None,
+        is_error: None,
+        meta: None,
+    })
+}
+
+#[instrument(skip(lifecycle_manager))]
+pub async fn handle_get_changes(
+    req: &CallToolRequestParam,
+    lifecycle_manager: &LifecycleManager,
+) -> Result<CallToolResult> {
+    let args = extract_args_from_request(req)?;
+
+    let since = match args.get("since") {
+        None | Some(Value::Null) => 0,
+        Some(value) => value
+            .as_u64()
+            .ok_or_else(|| anyhow::anyhow!("'since' must be a non-negative integer"))?,
+    };
+
+    let changes = lifecycle_manager.changes_since(since);
+    let result = json!({
+        "last_seq": lifecycle_manager.last_change_seq(),
+        "changes": changes
+    });
+
+    Ok(CallToolResult {
+        content: vec![Content::text(serde_json::to_string(&result)?)],
+        structured_content: None,
+        is_error: None,
+        meta: None,
+    })
+}
+
+#[instrument(skip(lifecycle_manager))]
+pub async fn handle_set_component_priority(
+    req: &CallToolRequestParam,
+    lifecycle_manager: &LifecycleManager,
+) -> Result<CallToolResult> {
+    let args = extract_args_from_request(req)?;
+
+    let component_id = args
+        .get("component_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'component_id'"))?;
+    validate_component_id(component_id)?;
+    let priority = match args.get("priority") {
+        None | Some(Value::Null) => None,
+        Some(value) => Some(
+            value
+                .as_i64()
+                .and_then(|priority| i32::try_from(priority).ok())
+                .ok_or_else(|| anyhow::anyhow!("'priority' must be an integer"))?,
+        ),
+    };
+
+    info!(component_id, ?priority, "Setting component priority");
+    lifecycle_manager
+        .set_component_priority(component_id, priority)
+        .await?;
+
+    let result = json!({
+        "status": "component priority set",
+        "component_id": component_id,
+        "priority": lifecycle_manager.get_component_priority(component_id)
+    });
+
+    Ok(CallToolResult {
+        content: vec![Content::text(serde_json::to_string(&result)?)],
+        structured_content: None,
+        is_error: None,
+        meta: None,
+    })
+}
+
+#[instrument(skip(lifecycle_manager))]
+pub async fn handle_set_component_visibility(
+    req: &CallToolRequestParam,
+    lifecycle_manager: &LifecycleManager,
+) -> Result<CallToolResult> {
+    let args = extract_args_from_request(req)?;
+
+    let component_id = args
+        .get("component_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'component_id'"))?;
+    validate_component_id(component_id)?;
+    let hidden = args
+        .get("hidden")
+        .and_then(|v| v.as_bool())
+        .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'hidden'"))?;
+
+    info!(component_id, hidden, "Setting component visibility");
+    lifecycle_manager
+        .set_component_hidden(component_id, hidden)
+        .await?;
+
+    let result = json!({
+        "status": if hidden { "component hidden" } else { "component shown" },
+        "component_id": component_id,
+        "hidden": hidden
+    });
+
+    Ok(CallToolResult {
+        content: vec![Content::text(serde_json::to_string(&result)?)],
+        structured_content: None,
+        is_error: None,
+        meta: None,
+    })
+}
+
 pub(crate) fn extract_args_from_request(
     req: &CallToolRequestParam,
 ) -> Result<serde_json::Map<String, Value>> {
@@ -301,15 +1114,44 @@ pub(crate) fn extract_args_from_request(
     }
 }
 
-/// Create successful result for component operations
-fn create_component_success_result(
-    operation_name: &str,
-    component_id: &str,
-) -> Result<CallToolResult> {
-    let status_text = serde_json::to_string(&json!({
-        "status": format!("component {}ed successfully", operation_name),
-        "id": component_id
-    }))?;
+fn load_outcome_to_json(outcome: &ComponentLoadOutcome) -> Value {
+    let status = match outcome.status {
+        LoadResult::New => "component loaded successfully",
+        LoadResult::Replaced => "component reloaded successfully",
+    };
+
+    let mut result = json!({
+        "status": status,
+        "id": &outcome.component_id,
+        "tools": &outcome.tool_names,
+        "added_tools": &outcome.added_tools,
+        "removed_tools": &outcome.removed_tools,
+        "changed_tools": &outcome.changed_tools,
+    });
+    if let Some(id_assignment) = outcome.id_assignment {
+        // The ID of a component loaded from a URL cannot be predicted
+        result["id_assignment"] = json!(id_assignment);
+    }
+    if let Some(health) = &outcome.health {
+        result["health"] = json!(health);
+    }
+    if let Some(on_load) = &outcome.on_load {
+        result["on_load"] = json!(on_load);
+    }
+    if let Some(collisions) = &outcome.tool_collisions {
+        result["tool_collisions"] = json!(collisions);
+    }
+    if !outcome.warnings.is_empty() {
+        result["warnings"] = json!(outcome.warnings);
+    }
+    if !outcome.skipped_exports.is_empty() {
+        result["skipped_exports"] = json!(outcome.skipped_exports);
+    }
+    result
+}
+
+fn create_load_component_success_result(outcome: &ComponentLoadOutcome) -> Result<CallToolResult> {
+    let status_text = serde_json::to_string(&load_outcome_to_json(outcome))?;
 
     let contents = vec![Content::text(status_text)];
 
@@ -321,24 +1163,62 @@ fn create_component_success_result(
     })
 }
 
-fn create_load_component_success_result(outcome: &ComponentLoadOutcome) -> Result<CallToolResult> {
-    let status = match outcome.status {
-        LoadResult::New => "component loaded successfully",
-        LoadResult::Replaced => "component reloaded successfully",
-    };
+fn create_unload_component_success_result(
+    outcome: &ComponentUnloadOutcome,
+) -> Result<CallToolResult> {
+    let mut result = json!({
+        "status": "component unloaded successfully",
+        "id": &outcome.component_id,
+    });
+    if let Some(on_unload) = &outcome.on_unload {
+        result["on_unload"] = json!(on_unload);
+    }
+    if !outcome.warnings.is_empty() {
+        result["warnings"] = json!(outcome.warnings);
+    }
+
+    Ok(CallToolResult {
+        content: vec![Content::text(serde_json::to_string(&result)?)],
+        structured_content: None,
+        is_error: None,
+        meta: None,
+    })
+}
 
+/// Create the result returned when a load was cancelled before its download
+/// finished; `details` names what was being loaded
+fn create_cancelled_result(mut details: Value) -> Result<CallToolResult> {
+    details["status"] = json!("cancelled");
+    details["message"] = json!("The download was cancelled; nothing was installed");
+
+    Ok(CallToolResult {
+        content: vec![Content::text(serde_json::to_string(&details)?)],
+        structured_content: None,
+        is_error: Some(true),
+        meta: None,
+    })
+}
+
+/// Create the result returned when a registry name matches several entries
+fn create_install_candidates_result(
+    name: &str,
+    candidates: &[RegistryEntry],
+) -> Result<CallToolResult> {
     let status_text = serde_json::to_string(&json!({
-        "status": status,
-        "id": &outcome.component_id,
-        "tools": &outcome.tool_names,
+        "status": "ambiguous component name",
+        "name": name,
+        "message": format!(
+            "'{}' matches {} registry entries; call install-component again with one of the candidate names",
+            name,
+            candidates.len()
+        ),
+        "candidates": candidates,
     }))?;
 
-    let contents = vec![Content::text(status_text)];
-
     Ok(CallToolResult {
-        content: contents,
+        content: vec![Content::text(status_text)],
         structured_content: None,
-        is_error: None,
+        is_error: Some(true),
         meta: None,
     })
 }
@@ -368,80 +1248,21 @@ fn create_component_error_result(
     }
 }
 
-/// Handle tool list change notification
-async fn handle_tool_list_notification(
-    server_peer: Option<Peer<RoleServer>>,
-    component_id: &str,
-    operation_name: &str,
-) {
-    if let Some(peer) = server_peer {
-        if let Err(e) = peer.notify_tool_list_changed().await {
-            error!(error = %e, "Failed to send tool list change notification");
-        } else {
-            info!(
-                component_id = %component_id,
-                "Sent tool list changed notification after {}ing component", operation_name
-            );
-        }
-    } else {
-        info!(component_id = %component_id, "Component {}ed successfully in CLI mode", operation_name);
-    }
-}
-
-/// CLI-specific version of handle_load_component that doesn't require server peer notifications
-#[instrument(skip(lifecycle_manager))]
-pub async fn handle_load_component_cli(
-    req: &CallToolRequestParam,
-    lifecycle_manager: &LifecycleManager,
-) -> Result<CallToolResult> {
-    let args = extract_args_from_request(req)?;
-    let path = args
-        .get("path")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'path'"))?;
-
-    info!(path, "Loading component (CLI mode)");
-
-    match lifecycle_manager.load_component(path).await {
-        Ok(outcome) => {
-            handle_tool_list_notification(None, &outcome.component_id, "load").await;
-            create_load_component_success_result(&outcome)
-        }
-        Err(e) => {
-            error!(error = %e, path, "Failed to load component");
-            Err(anyhow::anyhow!(
-                "Failed to load component: {}. Error: {}",
-                path,
-                e
-            ))
-        }
-    }
-}
-
-/// CLI-specific version of handle_unload_component that doesn't require server peer notifications
-#[instrument(skip(lifecycle_manager))]
-pub async fn handle_unload_component_cli(
-    req: &CallToolRequestParam,
-    lifecycle_manager: &LifecycleManager,
-) -> Result<CallToolResult> {
-    let args = extract_args_from_request(req)?;
-    let id = args
-        .get("id")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| anyhow::anyhow!("Missing 'id' in arguments"))?;
-
-    info!(component_id = %id, "Unloading component (CLI mode)");
-
-    match lifecycle_manager.unload_component(id).await {
-        Ok(()) => {
-            handle_tool_list_notification(None, id, "unload").await;
-            create_component_success_result("unload", id)
-        }
-        Err(e) => {
-            error!(error = %e, "Failed to unload component");
-            Ok(create_component_error_result("unload", id, &e))
-        }
-    }
+/// Key of the stable tool identifier in a component tool's `_meta`.
+pub const TOOL_ID_META_KEY: &str = "wassette/toolId";
+
+/// Key of the object in a call result's `_meta` that holds the call's
+/// timing, when enabled.
+pub const TIMING_META_KEY: &str = "wassette";
+
+/// Record `<component id>/<tool name>` in the tool's `_meta`, so clients can
+/// follow a tool across reloads even when its description changes.
+fn with_tool_id(mut tool: Tool, component_id: &str) -> Tool {
+    let tool_id = format!("{component_id}/{}", tool.name);
+    tool.meta
+        .get_or_insert_with(Meta::new)
+        .insert(TOOL_ID_META_KEY.to_string(), json!(tool_id));
+    tool
 }
 
 #[instrument]
@@ -470,6 +1291,11 @@ pub(crate) fn parse_tool_schema(tool_json: &Value) -> Option<Tool> {
             _ => None,
         });
 
+    // Carries the examples taken from the component's docs
+    let meta = tool_json
+        .get("_meta")
+        .and_then(|meta| serde_json::from_value::<Meta>(meta.clone()).ok());
+
     debug!(
         tool_name = %name,
         has_output_schema = output_schema_arc.is_some(),
@@ -484,7 +1310,7 @@ pub(crate) fn parse_tool_schema(tool_json: &Value) -> Option<Tool> {
         annotations: None,
         title: None,
         icons: None,
-        meta: None,
+        meta,
     })
 }
 
@@ -524,6 +1350,14 @@ mod tests {
         assert_eq!(schema_json, expected);
     }
 
+    #[test]
+    fn test_docs_excerpt_skips_headings() {
+        let docs =
+            "## World `fetch`\n\nAn example world.\nSecond line.\n\n### `fetch`\n\nFetch a URL";
+        assert_eq!(docs_excerpt(docs), Some("An example world.\nSecond line."));
+        assert_eq!(docs_excerpt("## World `empty`"), None);
+    }
+
     #[test]
     fn test_extract_args_from_request() {
         let req = CallToolRequestParam {
@@ -550,6 +1384,357 @@ mod tests {
         assert!(args.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_install_component_ambiguous_name_returns_candidates() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = LifecycleManager::new_unloaded(&tempdir).await?;
+
+        let req = CallToolRequestParam {
+            name: "install-component".into(),
+            arguments: Some(serde_json::Map::from_iter([(
+                "name".to_string(),
+                json!("weather"),
+            )])),
+        };
+
+        let result =
+            handle_install_component(&req, &lifecycle_manager, &CancellationToken::new(), None)
+                .await?;
+        assert_eq!(result.is_error, Some(true));
+
+        let content_json = serde_json::to_value(&result.content)?;
+        let response: Value = serde_json::from_str(content_json[0]["text"].as_str().unwrap())?;
+        assert_eq!(response["status"], "ambiguous component name");
+        assert_eq!(response["candidates"].as_array().unwrap().len(), 2);
+        assert!(lifecycle_manager.list_components().await.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_install_component_unknown_name() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = LifecycleManager::new_unloaded(&tempdir).await?;
+
+        let req = CallToolRequestParam {
+            name: "install-component".into(),
+            arguments: Some(serde_json::Map::from_iter([(
+                "name".to_string(),
+                json!("xyz123notfound"),
+            )])),
+        };
+
+        let err =
+            handle_install_component(&req, &lifecycle_manager, &CancellationToken::new(), None)
+                .await
+                .unwrap_err();
+        assert!(err.to_string().contains("not found in registry"));
+
+        let req = CallToolRequestParam {
+            name: "install-component".into(),
+            arguments: None,
+        };
+        let err =
+            handle_install_component(&req, &lifecycle_manager, &CancellationToken::new(), None)
+                .await
+                .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Missing required argument: 'name'"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_load_status_reports_failure_reason() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = LifecycleManager::new_unloaded(&tempdir).await?;
+        std::fs::write(tempdir.path().join("broken.wasm"), b"not a component")?;
+        assert!(lifecycle_manager
+            .ensure_component_loaded("broken")
+            .await
+            .is_err());
+
+        let req = CallToolRequestParam {
+            name: "get-load-status".into(),
+            arguments: None,
+        };
+        let result = handle_get_load_status(&req, &lifecycle_manager).await?;
+        let content_json = serde_json::to_value(&result.content)?;
+        let response: Value = serde_json::from_str(content_json[0]["text"].as_str().unwrap())?;
+        assert_eq!(response["total"], 1);
+        assert_eq!(response["components"][0]["id"], "broken");
+        assert_eq!(response["components"][0]["load_state"]["state"], "failed");
+        assert!(response["components"][0]["load_state"]["reason"].is_string());
+        assert_eq!(response["schemaVersion"], LOAD_STATUS_SCHEMA_VERSION);
+        assert_eq!(response["failures"][0]["component_id"], "broken");
+        assert_eq!(
+            response["failures"][0]["error"],
+            response["components"][0]["load_state"]["reason"]
+        );
+
+        let req = CallToolRequestParam {
+            name: "get-load-status".into(),
+            arguments: Some(serde_json::Map::from_iter([(
+                "component_id".to_string(),
+                json!("missing"),
+            )])),
+        };
+        let err = handle_get_load_status(&req, &lifecycle_manager)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Component not found"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tool_list_reuses_converted_schemas() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = LifecycleManager::new_unloaded(&tempdir).await?;
+        let component = std::env::current_dir()?.join("../component2json/testdata/fetch-rs.wasm");
+        let component_id = lifecycle_manager
+            .load_component(&format!("file://{}", component.display()))
+            .await?
+            .component_id;
+
+        let tool_cache = ToolCache::default();
+        let first = get_component_tools(&lifecycle_manager, &tool_cache).await?;
+        let second = get_component_tools(&lifecycle_manager, &tool_cache).await?;
+        assert!(!first.is_empty());
+        assert_eq!(first, second);
+        // The second list hands out the tools converted for the first
+        for (a, b) in first.iter().zip(&second) {
+            assert!(Arc::ptr_eq(&a.input_schema, &b.input_schema));
+        }
+
+        lifecycle_manager.unload_component(&component_id).await?;
+        assert!(get_component_tools(&lifecycle_manager, &tool_cache)
+            .await?
+            .is_empty());
+        assert!(tool_cache.tools.lock().unwrap().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_hidden_component_is_left_out_of_tool_list() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = LifecycleManager::new_unloaded(&tempdir).await?;
+        let component = std::env::current_dir()?.join("../component2json/testdata/fetch-rs.wasm");
+        let outcome = lifecycle_manager
+            .load_component(&format!("file://{}", component.display()))
+            .await?;
+        let component_id = outcome.component_id;
+        assert!(
+            !get_component_tools(&lifecycle_manager, &ToolCache::default())
+                .await?
+                .is_empty()
+        );
+
+        let req = CallToolRequestParam {
+            name: "set-component-visibility".into(),
+            arguments: Some(serde_json::Map::from_iter([
+                ("component_id".to_string(), json!(component_id)),
+                ("hidden".to_string(), json!(true)),
+            ])),
+        };
+        handle_set_component_visibility(&req, &lifecycle_manager).await?;
+        assert!(
+            get_component_tools(&lifecycle_manager, &ToolCache::default())
+                .await?
+                .is_empty()
+        );
+        // The tools are still registered, so calls by name keep working
+        assert!(!lifecycle_manager.list_tools().await.is_empty());
+
+        let req = CallToolRequestParam {
+            name: "list-components".into(),
+            arguments: None,
+        };
+        let result = handle_list_components(&req, &lifecycle_manager).await?;
+        let content_json = serde_json::to_value(&result.content)?;
+        let response: Value = serde_json::from_str(content_json[0]["text"].as_str().unwrap())?;
+        assert_eq!(response["components"][0]["hidden"], true);
+
+        let restarted = LifecycleManager::new_unloaded(&tempdir).await?;
+        assert!(restarted.is_component_hidden(&component_id));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_changes_returns_changes_after_seq() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = LifecycleManager::new_unloaded(&tempdir).await?;
+        let component = std::env::current_dir()?.join("../component2json/testdata/fetch-rs.wasm");
+        let outcome = lifecycle_manager
+            .load_component(&format!("file://{}", component.display()))
+            .await?;
+        lifecycle_manager
+            .set_component_hidden(&outcome.component_id, true)
+            .await?;
+
+        let req = CallToolRequestParam {
+            name: "get-changes".into(),
+            arguments: Some(serde_json::Map::from_iter([(
+                "since".to_string(),
+                json!(1),
+            )])),
+        };
+        let result = handle_get_changes(&req, &lifecycle_manager).await?;
+        let content_json = serde_json::to_value(&result.content)?;
+        let response: Value = serde_json::from_str(content_json[0]["text"].as_str().unwrap())?;
+        assert_eq!(response["last_seq"], 2);
+        assert_eq!(response["changes"].as_array().unwrap().len(), 1);
+        assert_eq!(response["changes"][0]["seq"], 2);
+        assert_eq!(
+            response["changes"][0]["type"],
+            "component_visibility_changed"
+        );
+
+        let req = CallToolRequestParam {
+            name: "get-changes".into(),
+            arguments: Some(serde_json::Map::from_iter([(
+                "since".to_string(),
+                json!(-1),
+            )])),
+        };
+        assert!(handle_get_changes(&req, &lifecycle_manager).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invoke_batch_reports_each_item_in_order() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = LifecycleManager::new_unloaded(&tempdir).await?;
+        let component = std::env::current_dir()?.join("../component2json/testdata/fetch-rs.wasm");
+        let component_id = lifecycle_manager
+            .load_component(&format!("file://{}", component.display()))
+            .await?
+            .component_id;
+
+        let batch = |component_id: &str| CallToolRequestParam {
+            name: "invoke-batch".into(),
+            arguments: Some(serde_json::Map::from_iter([
+                ("component_id".to_string(), json!(component_id)),
+                ("tool".to_string(), json!("fetch")),
+                (
+                    "arguments".to_string(),
+                    json!([{"url": "https://example.com/"}, "not-an-object", {"url": "https://example.org/"}]),
+                ),
+            ])),
+        };
+        let result = handle_invoke_batch(
+            &batch(&component_id),
+            &lifecycle_manager,
+            &CancellationToken::new(),
+        )
+        .await?;
+        let content_json = serde_json::to_value(&result.content)?;
+        let response: Value = serde_json::from_str(content_json[0]["text"].as_str().unwrap())?;
+        let results = response["results"].as_array().unwrap();
+        let indexes: Vec<u64> = results
+            .iter()
+            .map(|r| r["index"].as_u64().unwrap())
+            .collect();
+        assert_eq!(indexes, [0, 1, 2]);
+        // Without a network grant the fetches come back as the guest's
+        // error results; the item that is not an object fails on its own
+        assert_eq!(results[0]["result"]["err"], "ErrorCode::HttpRequestDenied");
+        assert_eq!(results[1]["error"], "Arguments must be a JSON object");
+        assert_eq!(response["summary"]["succeeded"], 2);
+        assert_eq!(response["summary"]["failed"], 1);
+
+        let cancelled = CancellationToken::new();
+        cancelled.cancel();
+        let result =
+            handle_invoke_batch(&batch(&component_id), &lifecycle_manager, &cancelled).await?;
+        let content_json = serde_json::to_value(&result.content)?;
+        let response: Value = serde_json::from_str(content_json[0]["text"].as_str().unwrap())?;
+        assert!(response["results"][0]["error"]
+            .as_str()
+            .unwrap()
+            .contains("cancelled"));
+
+        assert!(handle_invoke_batch(
+            &batch("other"),
+            &lifecycle_manager,
+            &CancellationToken::new()
+        )
+        .await
+        .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invoke_batch_takes_a_call_slot_per_item() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = LifecycleManager::new_unloaded(&tempdir).await?;
+        let component = std::env::current_dir()?.join("../component2json/testdata/fetch-rs.wasm");
+        lifecycle_manager
+            .load_component(&format!("file://{}", component.display()))
+            .await?;
+        let limiter = CallLimiter::new(crate::CallLimitConfig {
+            max_concurrent_calls: 1,
+            queue_timeout: Duration::from_millis(50),
+            exempt_builtin_tools: true,
+        });
+        let req = CallToolRequestParam {
+            name: "invoke-batch".into(),
+            arguments: Some(serde_json::Map::from_iter([
+                ("tool".to_string(), json!("fetch")),
+                (
+                    "arguments".to_string(),
+                    json!([{"url": "https://example.com/"}, {"url": "https://example.org/"}]),
+                ),
+            ])),
+        };
+        let run = || async {
+            let result = invoke_batch(
+                &req,
+                &lifecycle_manager,
+                &CancellationToken::new(),
+                Some(&limiter),
+            )
+            .await?;
+            let content_json = serde_json::to_value(&result.content)?;
+            anyhow::Ok(serde_json::from_str::<Value>(
+                content_json[0]["text"].as_str().unwrap(),
+            )?)
+        };
+
+        // With the only slot taken, every call of the batch waits and is refused
+        let slot = limiter.acquire("fetch").await.unwrap();
+        let response = run().await?;
+        assert_eq!(response["summary"]["failed"], 2);
+        assert!(response["results"][0]["error"]
+            .as_str()
+            .unwrap()
+            .contains("Server at capacity"));
+
+        drop(slot);
+        let response = run().await?;
+        assert_eq!(response["summary"]["succeeded"], 2);
+        assert_eq!(limiter.load().in_flight, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_tool_schema_keeps_examples() {
+        let examples =
+            json!([{ "description": "", "arguments": { "url": "https://example.com" } }]);
+        let tool_json = json!({
+            "name": "fetch",
+            "_meta": { "examples": examples }
+        });
+
+        let tool = with_tool_id(parse_tool_schema(&tool_json).unwrap(), "fetch_rs");
+        let meta = tool.meta.unwrap();
+        assert_eq!(meta.get("examples"), Some(&examples));
+        assert_eq!(meta.get(TOOL_ID_META_KEY), Some(&json!("fetch_rs/fetch")));
+    }
+
     #[test]
     fn test_parse_tool_schema_minimal() {
         let tool_json = json!({
@@ -562,17 +1747,66 @@ mod tests {
         assert_eq!(tool.description, Some("No description available".into()));
     }
 
+    fn string_tool_schema() -> Value {
+        json!({"name": "echo", "outputSchema": {"type": "string"}})
+    }
+
+    fn result_text(result: &CallToolResult) -> &str {
+        &result.content[0].as_text().unwrap().text
+    }
+
     #[test]
-    fn test_parse_structured_result_with_object() {
-        let json_str = r#"{"ok":{"message":"hello"}}"#;
-        let parsed = parse_structured_result(json_str);
-        assert_eq!(parsed, json!({"ok": {"message": "hello"}}));
+    fn test_component_call_result_with_json_looking_string() -> Result<()> {
+        let schema = string_tool_schema();
+        let result = component_call_result(json!({"result": r#"{"a":1}"#}), Some(&schema))?;
+        assert_eq!(result_text(&result), r#"{"a":1}"#);
+        // The string stays a string rather than being taken for an object
+        assert_eq!(
+            result.structured_content,
+            Some(json!({"result": r#"{"a":1}"#}))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_component_call_result_with_plain_string() -> Result<()> {
+        let schema = string_tool_schema();
+        let result = component_call_result(json!({"result": "plain text"}), Some(&schema))?;
+        assert_eq!(result_text(&result), "plain text");
+        assert_eq!(
+            result.structured_content,
+            Some(json!({"result": "plain text"}))
+        );
+        Ok(())
     }
 
     #[test]
-    fn test_parse_structured_result_with_text() {
-        let parsed = parse_structured_result("plain text");
-        assert_eq!(parsed, json!("plain text"));
+    fn test_component_call_result_with_object() -> Result<()> {
+        let schema = json!({
+            "name": "lookup",
+            "outputSchema": {"type": "object", "properties": {"a": {"type": "number"}}}
+        });
+        let result = component_call_result(json!({"result": {"a": 1}}), Some(&schema))?;
+        assert_eq!(result_text(&result), r#"{"a":1}"#);
+        assert_eq!(result.structured_content, Some(json!({"result": {"a": 1}})));
+
+        let result = component_call_result(json!({"result": {"a": 1}}), None)?;
+        assert_eq!(result.structured_content, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_captured_output_content_skips_empty_streams() {
+        assert!(captured_output_content(&CapturedOutput::default()).is_empty());
+        let content = captured_output_content(&CapturedOutput {
+            stdout: String::new(),
+            stderr: "warning: cache miss".to_string(),
+        });
+        assert_eq!(content.len(), 1);
+        assert_eq!(
+            content[0].as_text().unwrap().text,
+            "[stderr]\nwarning: cache miss"
+        );
     }
 
     #[test]
@@ -0,0 +1,345 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! The checks `wassette doctor` runs against a component directory, as a
+//! versioned document a CI job can gate on.
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::debug;
+use wassette::{ComponentManifest, LifecycleManager};
+
+use crate::components::load_status_report;
+
+/// Version of the document returned by [`doctor_report`]. Raised when a
+/// field is removed or changes meaning, or a check changes its ID, so
+/// scripts can detect breaking changes.
+pub const DOCTOR_SCHEMA_VERSION: u32 = 1;
+
+/// How bad the outcome of a check is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Nothing to do
+    Ok,
+    /// The server works, but not the way it is probably meant to
+    Warning,
+    /// The server cannot do what it is configured to do
+    Error,
+}
+
+/// The outcome of one check. Checks about a single component carry its ID.
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorCheck {
+    /// Stable ID of the check, such as `component-load`
+    pub id: &'static str,
+    /// How bad the outcome is
+    pub severity: Severity,
+    /// What was found
+    pub message: String,
+    /// What to do about it, for warnings and errors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+    /// The component the outcome is about
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component_id: Option<String>,
+}
+
+impl DoctorCheck {
+    fn ok(id: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            id,
+            severity: Severity::Ok,
+            message: message.into(),
+            remediation: None,
+            component_id: None,
+        }
+    }
+
+    fn problem(
+        id: &'static str,
+        severity: Severity,
+        message: impl Into<String>,
+        remediation: impl Into<String>,
+    ) -> Self {
+        Self {
+            id,
+            severity,
+            message: message.into(),
+            remediation: Some(remediation.into()),
+            component_id: None,
+        }
+    }
+
+    fn for_component(mut self, component_id: &str) -> Self {
+        self.component_id = Some(component_id.to_string());
+        self
+    }
+}
+
+/// The document `wassette doctor` prints.
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorReport {
+    /// Always [`DOCTOR_SCHEMA_VERSION`]
+    #[serde(rename = "schemaVersion")]
+    pub schema_version: u32,
+    /// Every check that ran, in a fixed order
+    pub checks: Vec<DoctorCheck>,
+    /// The `failures` of the load status report
+    pub failures: Value,
+}
+
+impl DoctorReport {
+    /// Whether any check has [`Severity::Error`].
+    pub fn has_errors(&self) -> bool {
+        self.checks
+            .iter()
+            .any(|check| check.severity == Severity::Error)
+    }
+}
+
+/// Check the component directory, the state manifest, that no wasm file was
+/// replaced behind the metadata's back, and that every known component
+/// loads. Components that are not loaded yet are compiled, so their failures
+/// are recorded in their load state.
+pub async fn doctor_report(lifecycle_manager: &LifecycleManager) -> Result<DoctorReport> {
+    let mut checks = vec![check_component_dir(lifecycle_manager).await];
+    checks.extend(check_state_manifest(lifecycle_manager).await);
+    // Before compiling, which saves metadata for the file on disk
+    checks.extend(check_drift(lifecycle_manager).await);
+
+    for component_id in lifecycle_manager.list_components_known().await {
+        if let Err(error) = lifecycle_manager
+            .ensure_component_loaded(&component_id)
+            .await
+        {
+            debug!(%component_id, error = %format!("{error:#}"), "Component failed to load");
+        }
+    }
+    let load_status = load_status_report(lifecycle_manager, None).await?;
+    let failures = load_status["failures"].clone();
+    let load_failures = failures.as_array().map(Vec::as_slice).unwrap_or_default();
+    if load_failures.is_empty() {
+        checks.push(DoctorCheck::ok(
+            "component-load",
+            format!("All {} components load", load_status["total"]),
+        ));
+    }
+    for failure in load_failures {
+        let component_id = failure["component_id"].as_str().unwrap_or_default();
+        checks.push(
+            DoctorCheck::problem(
+                "component-load",
+                Severity::Error,
+                format!(
+                    "Component {component_id} does not load: {}",
+                    failure["error"].as_str().unwrap_or_default()
+                ),
+                format!("Load the component again with `wassette component load <uri>`, or remove it with `wassette component unload {component_id}`"),
+            )
+            .for_component(component_id),
+        );
+    }
+
+    Ok(DoctorReport {
+        schema_version: DOCTOR_SCHEMA_VERSION,
+        checks,
+        failures,
+    })
+}
+
+async fn check_component_dir(lifecycle_manager: &LifecycleManager) -> DoctorCheck {
+    let root = lifecycle_manager.component_root();
+    match tokio::fs::metadata(root).await {
+        Ok(metadata) if metadata.is_dir() && !metadata.permissions().readonly() => DoctorCheck::ok(
+            "component-dir",
+            format!("Component directory {} is writable", root.display()),
+        ),
+        Ok(_) => DoctorCheck::problem(
+            "component-dir",
+            Severity::Error,
+            format!(
+                "Component directory {} is not a writable directory",
+                root.display()
+            ),
+            "Make the directory writable, or pass another one with --component-dir",
+        ),
+        Err(error) => DoctorCheck::problem(
+            "component-dir",
+            Severity::Error,
+            format!(
+                "Component directory {} cannot be read: {error}",
+                root.display()
+            ),
+            "Create the directory, or pass another one with --component-dir",
+        ),
+    }
+}
+
+async fn check_state_manifest(lifecycle_manager: &LifecycleManager) -> Vec<DoctorCheck> {
+    let path = lifecycle_manager.manifest_path();
+    let manifest = match ComponentManifest::load(path).await {
+        Ok(manifest) => manifest,
+        Err(error) => {
+            return vec![DoctorCheck::problem(
+                "state-manifest",
+                Severity::Warning,
+                format!(
+                    "State manifest {} cannot be read: {error:#}",
+                    path.display()
+                ),
+                "Fix or remove the file; loading a component writes a new one",
+            )];
+        }
+    };
+
+    let missing: Vec<DoctorCheck> = manifest
+        .components
+        .keys()
+        .filter(|component_id| {
+            !lifecycle_manager
+                .component_root()
+                .join(format!("{component_id}.wasm"))
+                .exists()
+        })
+        .map(|component_id| {
+            DoctorCheck::problem(
+                "state-manifest",
+                Severity::Warning,
+                format!("Component {component_id} is in the state manifest but not in the component directory"),
+                "Fetch it again with `wassette component restore`",
+            )
+            .for_component(component_id)
+        })
+        .collect();
+    if missing.is_empty() {
+        vec![DoctorCheck::ok(
+            "state-manifest",
+            format!(
+                "All {} components in the state manifest are installed",
+                manifest.components.len()
+            ),
+        )]
+    } else {
+        missing
+    }
+}
+
+async fn check_drift(lifecycle_manager: &LifecycleManager) -> Vec<DoctorCheck> {
+    let drifted = lifecycle_manager.check_metadata_drift().await;
+    if drifted.is_empty() {
+        return vec![DoctorCheck::ok(
+            "component-drift",
+            "Every component file matches its metadata",
+        )];
+    }
+    drifted
+        .into_iter()
+        .map(|(component_id, reason)| {
+            DoctorCheck::problem(
+                "component-drift",
+                Severity::Warning,
+                format!("The file of component {component_id} was replaced outside wassette: {reason}. A running server keeps serving its old tools"),
+                "Load the component again, restart the server, or run it with --auto-reload-on-drift",
+            )
+            .for_component(&component_id)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wassette::ManifestEntry;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_doctor_reports_load_failures_as_errors() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = LifecycleManager::new_unloaded(&tempdir).await?;
+
+        let report = doctor_report(&lifecycle_manager).await?;
+        assert!(!report.has_errors());
+        let document = serde_json::to_value(&report)?;
+        assert_eq!(document["schemaVersion"], DOCTOR_SCHEMA_VERSION);
+        assert_eq!(document["failures"], json!([]));
+        for check in document["checks"].as_array().unwrap() {
+            assert_eq!(check["severity"], "ok");
+            assert!(check.get("remediation").is_none());
+        }
+
+        std::fs::write(tempdir.path().join("broken.wasm"), b"not a component")?;
+        let report = doctor_report(&lifecycle_manager).await?;
+        assert!(report.has_errors());
+        let document = serde_json::to_value(&report)?;
+        let check = document["checks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|check| check["id"] == "component-load")
+            .unwrap();
+        assert_eq!(check["severity"], "error");
+        assert_eq!(check["component_id"], "broken");
+        assert!(check["remediation"].is_string());
+        assert_eq!(document["failures"][0]["component_id"], "broken");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_doctor_warns_about_replaced_component_files() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = LifecycleManager::new_unloaded(&tempdir).await?;
+        let component = std::env::current_dir()?.join("../component2json/testdata/fetch-rs.wasm");
+        let component_id = lifecycle_manager
+            .load_component(&format!("file://{}", component.display()))
+            .await?
+            .component_id;
+        let wasm_path = tempdir.path().join(format!("{component_id}.wasm"));
+        let mut replaced = std::fs::read(&wasm_path)?;
+        replaced.extend_from_slice(b"trailing bytes");
+        std::fs::write(&wasm_path, replaced)?;
+
+        // A separate doctor run, as after the file was replaced under a
+        // running server
+        let doctor = LifecycleManager::new_unloaded(&tempdir).await?;
+        let report = doctor_report(&doctor).await?;
+        let check = report
+            .checks
+            .iter()
+            .find(|check| check.id == "component-drift")
+            .unwrap();
+        assert_eq!(check.severity, Severity::Warning);
+        assert_eq!(check.component_id.as_deref(), Some(component_id.as_str()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_doctor_warns_about_components_missing_from_the_manifest() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = LifecycleManager::new_unloaded(&tempdir).await?;
+        let mut manifest = ComponentManifest::default();
+        manifest.record(
+            "gone",
+            ManifestEntry {
+                source_uri: "oci://example.com/gone:1".to_string(),
+                digest: "sha256:00".to_string(),
+                policy_source: None,
+                load_order: None,
+            },
+        );
+        manifest.save(lifecycle_manager.manifest_path()).await?;
+
+        let report = doctor_report(&lifecycle_manager).await?;
+        assert!(!report.has_errors());
+        let check = report
+            .checks
+            .iter()
+            .find(|check| check.id == "state-manifest")
+            .unwrap();
+        assert_eq!(check.severity, Severity::Warning);
+        assert_eq!(check.component_id.as_deref(), Some("gone"));
+        Ok(())
+    }
+}
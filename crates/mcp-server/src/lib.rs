@@ -3,11 +3,28 @@
 
 pub use wassette::LifecycleManager;
 
+pub mod call_limit;
 pub mod components;
+pub mod doctor;
+pub mod logging;
+pub mod notifications;
+pub mod progress;
 pub mod prompts;
+pub mod redaction;
 pub mod resources;
+pub mod results;
+pub mod server;
+pub mod server_info;
 pub mod tools;
 
+pub use call_limit::{CallLimitConfig, CallLoad};
+pub use doctor::{doctor_report, DoctorReport};
+pub use logging::ComponentLogging;
+pub use notifications::{forward_tool_list_changes, Peers};
 pub use prompts::{handle_prompts_get, handle_prompts_list};
-pub use resources::handle_resources_list;
+pub use redaction::{RedactionRules, Redactor};
+pub use resources::{handle_resources_list, handle_resources_read};
+pub use results::{ResultStore, ResultStoreConfig};
+pub use server::{ExtraTool, ExtraToolFuture, McpServer, McpServerBuilder};
+pub use server_info::{server_info_report, ServerDetails};
 pub use tools::{handle_tools_call, handle_tools_list};
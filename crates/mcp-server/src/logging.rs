@@ -0,0 +1,175 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Forwards what components write to stderr to the calling client as MCP
+//! log messages, while the tool call runs.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rmcp::model::{LoggingLevel, LoggingMessageNotificationParam};
+use rmcp::{Peer, RoleServer};
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::debug;
+use wassette::StderrLines;
+
+/// Stderr lines of a call waiting to be sent; further lines are dropped.
+const STDERR_QUEUE_LINES: usize = 256;
+
+/// How long a finished call waits for its remaining stderr lines to be sent.
+const STDERR_DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The level component stderr is logged at, and the least severe level
+/// clients asked for with `logging/setLevel`.
+#[derive(Clone)]
+pub struct ComponentLogging {
+    level: LoggingLevel,
+    minimum: Arc<Mutex<LoggingLevel>>,
+}
+
+impl Default for ComponentLogging {
+    fn default() -> Self {
+        Self::new(LoggingLevel::Info)
+    }
+}
+
+impl ComponentLogging {
+    /// Log component stderr lines at `level`. Every level is sent until a
+    /// client sets a minimum.
+    pub fn new(level: LoggingLevel) -> Self {
+        Self {
+            level,
+            minimum: Arc::new(Mutex::new(LoggingLevel::Debug)),
+        }
+    }
+
+    /// The level component stderr lines are logged at.
+    pub fn level(&self) -> LoggingLevel {
+        self.level
+    }
+
+    /// Send only messages at `minimum` or more severe. The last client to
+    /// set a level decides for every session.
+    pub(crate) fn set_minimum(&self, minimum: LoggingLevel) {
+        *self.minimum.lock().unwrap() = minimum;
+    }
+
+    pub(crate) fn enabled(&self) -> bool {
+        severity(self.level) >= severity(*self.minimum.lock().unwrap())
+    }
+}
+
+/// Where the stderr of one tool call is sent: the client that made it.
+#[derive(Clone, Copy)]
+pub(crate) struct StderrLog<'a> {
+    pub(crate) logging: &'a ComponentLogging,
+    pub(crate) peer: &'a Peer<RoleServer>,
+}
+
+impl StderrLog<'_> {
+    /// Start sending the stderr lines of a call of `tool` on `component_id`.
+    /// Returns the channel to pass to the call and the task sending the
+    /// lines, which ends once the call drops the channel; `None` if the
+    /// client does not want messages at the configured level.
+    pub(crate) fn forward(
+        &self,
+        component_id: &str,
+        tool: &str,
+    ) -> Option<(StderrLines, JoinHandle<()>)> {
+        if !self.logging.enabled() {
+            return None;
+        }
+        let (lines, mut received) = mpsc::channel(STDERR_QUEUE_LINES);
+        let peer = self.peer.clone();
+        let level = self.logging.level;
+        let component_id = component_id.to_string();
+        let tool = tool.to_string();
+        let task = tokio::spawn(async move {
+            while let Some(line) = received.recv().await {
+                let message = log_message(level, &component_id, &tool, line);
+                if let Err(error) = peer.notify_logging_message(message).await {
+                    debug!(%component_id, %error, "Failed to send component stderr to the client");
+                    break;
+                }
+            }
+        });
+        Some((lines, task))
+    }
+}
+
+/// Wait a moment for the lines a finished call left in its channel.
+pub(crate) async fn drain(task: JoinHandle<()>) {
+    if tokio::time::timeout(STDERR_DRAIN_TIMEOUT, task)
+        .await
+        .is_err()
+    {
+        debug!("Gave up sending the remaining component stderr lines");
+    }
+}
+
+fn log_message(
+    level: LoggingLevel,
+    component_id: &str,
+    tool: &str,
+    line: String,
+) -> LoggingMessageNotificationParam {
+    LoggingMessageNotificationParam {
+        level,
+        logger: Some(component_id.to_string()),
+        data: json!({
+            "component_id": component_id,
+            "tool": tool,
+            "message": line,
+        }),
+    }
+}
+
+fn severity(level: LoggingLevel) -> u8 {
+    match level {
+        LoggingLevel::Debug => 0,
+        LoggingLevel::Info => 1,
+        LoggingLevel::Notice => 2,
+        LoggingLevel::Warning => 3,
+        LoggingLevel::Error => 4,
+        LoggingLevel::Critical => 5,
+        LoggingLevel::Alert => 6,
+        LoggingLevel::Emergency => 7,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimum_level_filters_component_stderr() {
+        let logging = ComponentLogging::new(LoggingLevel::Notice);
+        assert!(logging.enabled());
+        logging.set_minimum(LoggingLevel::Notice);
+        assert!(logging.enabled());
+        logging.set_minimum(LoggingLevel::Warning);
+        assert!(!logging.enabled());
+
+        // Clones share the minimum, as sessions of one server do
+        let clone = logging.clone();
+        clone.set_minimum(LoggingLevel::Info);
+        assert!(logging.enabled());
+    }
+
+    #[test]
+    fn test_log_message_names_component_and_tool() {
+        let message = log_message(
+            LoggingLevel::Info,
+            "fetch-rs",
+            "fetch",
+            "retrying".to_string(),
+        );
+        assert_eq!(message.logger.as_deref(), Some("fetch-rs"));
+        assert_eq!(
+            message.data,
+            json!({"component_id": "fetch-rs", "tool": "fetch", "message": "retrying"})
+        );
+    }
+}
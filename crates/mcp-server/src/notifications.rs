@@ -0,0 +1,90 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Translates [`LifecycleEvent`]s into MCP notifications for connected clients.
+
+use std::sync::{Arc, Mutex};
+
+use rmcp::{Peer, RoleServer};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+use tracing::{debug, info, warn};
+use wassette::LifecycleEvent;
+
+/// The clients connected to a server, one peer per session.
+///
+/// A session's peer is added on its first request and dropped once its
+/// transport has closed, so clients that disconnect stop being notified and
+/// clients that connect later are notified too.
+#[derive(Clone, Default)]
+pub struct Peers {
+    peers: Arc<Mutex<Vec<Peer<RoleServer>>>>,
+}
+
+impl Peers {
+    /// Add the peer of a session, unless it is known already. Peers of
+    /// sessions that skipped initialization, such as stateless HTTP
+    /// requests, cannot be told apart and are not added.
+    pub(crate) fn insert(&self, peer: &Peer<RoleServer>) {
+        let Some(info) = peer.peer_info() else {
+            return;
+        };
+        let mut peers = self.peers.lock().unwrap();
+        peers.retain(|peer| !peer.is_transport_closed());
+        // Peers of one session share its client info
+        if !peers.iter().any(|known| {
+            known
+                .peer_info()
+                .is_some_and(|known| std::ptr::eq(known, info))
+        }) {
+            peers.push(peer.clone());
+        }
+    }
+
+    /// The peers whose transport is still open.
+    pub fn live(&self) -> Vec<Peer<RoleServer>> {
+        let mut peers = self.peers.lock().unwrap();
+        peers.retain(|peer| !peer.is_transport_closed());
+        peers.clone()
+    }
+}
+
+/// Send `notifications/tools/list_changed` to every connected peer whenever
+/// a lifecycle event changes the tool list.
+///
+/// `peers` is consulted for every event because clients connect and
+/// disconnect while the server runs. Runs until the lifecycle manager is
+/// dropped.
+pub async fn forward_tool_list_changes<F>(mut events: Receiver<LifecycleEvent>, peers: F)
+where
+    F: Fn() -> Vec<Peer<RoleServer>>,
+{
+    loop {
+        let component_id = match events.recv().await {
+            Ok(event) if event.changes_tool_list() => event.component_id().to_string(),
+            Ok(_) => continue,
+            Err(RecvError::Lagged(skipped)) => {
+                // Some events were dropped; the tool list may have changed.
+                debug!(skipped, "Lifecycle event subscriber lagged");
+                String::new()
+            }
+            Err(RecvError::Closed) => break,
+        };
+
+        let peers = peers();
+        if peers.is_empty() {
+            debug!(%component_id, "No client connected, skipping tool list notification");
+            continue;
+        }
+        let mut sent = 0;
+        for peer in peers {
+            match peer.notify_tool_list_changed().await {
+                Ok(()) => sent += 1,
+                // The client went away since the peers were listed
+                Err(_) if peer.is_transport_closed() => {}
+                Err(e) => warn!(error = %e, "Failed to send tool list change notification"),
+            }
+        }
+        info!(%component_id, clients = sent, "Sent tool list changed notification");
+    }
+}
@@ -0,0 +1,149 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Sends the progress of `load-component` and `install-component` calls to
+//! clients that asked for it with a progress token.
+//!
+//! Progress runs from 0 to 100: the download takes it to 90, and compiling
+//! the component to 95. A download of unknown size is reported once, at 0.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rmcp::model::{ProgressNotificationParam, ProgressToken};
+use rmcp::{Peer, RoleServer};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::debug;
+use wassette::{LoadProgress, ProgressReporter};
+
+/// How long a finished call waits for its remaining notifications to be sent.
+const PROGRESS_DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Progress a completed download is reported at.
+const DOWNLOADED: u64 = 90;
+
+/// Progress reported once compilation starts.
+const COMPILING: u64 = 95;
+
+/// Where the progress of one tool call is sent: the client that made it,
+/// under the token from the request's `_meta`.
+#[derive(Clone)]
+pub(crate) struct ProgressTarget<'a> {
+    pub(crate) peer: &'a Peer<RoleServer>,
+    pub(crate) token: ProgressToken,
+}
+
+impl ProgressTarget<'_> {
+    /// Start sending progress to the client. Returns the reporter to pass
+    /// to the load and the task sending its notifications, which ends once
+    /// the reporter is dropped.
+    pub(crate) fn forward(&self) -> (ClientProgress, JoinHandle<()>) {
+        let (notifications, mut received) = mpsc::unbounded_channel();
+        let peer = self.peer.clone();
+        let task = tokio::spawn(async move {
+            while let Some(notification) = received.recv().await {
+                if let Err(error) = peer.notify_progress(notification).await {
+                    debug!(%error, "Failed to send load progress to the client");
+                    break;
+                }
+            }
+        });
+        let reporter = ClientProgress {
+            token: self.token.clone(),
+            sent: Mutex::new(None),
+            notifications,
+        };
+        (reporter, task)
+    }
+}
+
+/// Turns the progress of a load into progress notifications, sending one
+/// only when the progress has grown, so at most one per percent.
+pub(crate) struct ClientProgress {
+    token: ProgressToken,
+    sent: Mutex<Option<u64>>,
+    notifications: mpsc::UnboundedSender<ProgressNotificationParam>,
+}
+
+impl ClientProgress {
+    fn notification(&self, progress: LoadProgress) -> Option<ProgressNotificationParam> {
+        let (value, message) = match progress {
+            LoadProgress::Downloading { .. } => match progress.percent() {
+                Some(percent) => (
+                    percent * DOWNLOADED / 100,
+                    format!("Downloading component: {percent}%"),
+                ),
+                None => (0, "Downloading component".to_string()),
+            },
+            LoadProgress::Compiling => (COMPILING, "Compiling component".to_string()),
+        };
+        let mut sent = self.sent.lock().unwrap();
+        if sent.is_some_and(|last| value <= last) {
+            return None;
+        }
+        *sent = Some(value);
+        Some(ProgressNotificationParam {
+            progress_token: self.token.clone(),
+            progress: value as f64,
+            total: Some(100.0),
+            message: Some(message),
+        })
+    }
+}
+
+impl ProgressReporter for ClientProgress {
+    fn report(&self, progress: LoadProgress) {
+        if let Some(notification) = self.notification(progress) {
+            let _ = self.notifications.send(notification);
+        }
+    }
+}
+
+/// Stop reporting and wait a moment for the notifications a finished call
+/// left unsent.
+pub(crate) async fn finish(reporter: ClientProgress, task: JoinHandle<()>) {
+    drop(reporter);
+    if tokio::time::timeout(PROGRESS_DRAIN_TIMEOUT, task)
+        .await
+        .is_err()
+    {
+        debug!("Gave up sending the remaining load progress");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rmcp::model::NumberOrString;
+
+    use super::*;
+
+    #[test]
+    fn test_load_progress_becomes_increasing_notifications() {
+        let (notifications, _received) = mpsc::unbounded_channel();
+        let reporter = ClientProgress {
+            token: ProgressToken(NumberOrString::Number(7)),
+            sent: Mutex::new(None),
+            notifications,
+        };
+        let downloading = |downloaded| LoadProgress::Downloading {
+            downloaded,
+            total: Some(200),
+        };
+
+        let progress = |step| reporter.notification(step).map(|sent| sent.progress);
+        assert_eq!(progress(downloading(0)), Some(0.0));
+        assert_eq!(progress(downloading(1)), None);
+        assert_eq!(progress(downloading(100)), Some(45.0));
+        assert_eq!(progress(downloading(200)), Some(90.0));
+
+        let compiling = reporter.notification(LoadProgress::Compiling).unwrap();
+        assert_eq!(compiling.progress, 95.0);
+        assert_eq!(compiling.total, Some(100.0));
+        assert_eq!(compiling.message.as_deref(), Some("Compiling component"));
+        assert_eq!(
+            compiling.progress_token,
+            ProgressToken(NumberOrString::Number(7))
+        );
+    }
+}
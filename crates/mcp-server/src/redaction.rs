@@ -0,0 +1,328 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Redaction of sensitive tool arguments before they are logged.
+//!
+//! [`RedactionRules`] select the arguments whose values must not appear in
+//! logs or error messages: by exact field name, by a regular expression
+//! matched against field names, or by a JSON pointer to the value. Fields on
+//! the allowlist are never redacted. Rules apply at any depth, so a
+//! `password` inside a nested object or an array of objects is redacted as
+//! well. The default rules redact every field whose name contains
+//! `password`, `secret`, `token`, or `key`, in any case.
+
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+use anyhow::{Context, Result};
+use regex::RegexSet;
+use serde_json::{Map, Value};
+
+/// Text a redacted value is replaced with.
+pub const REDACTED: &str = "<redacted>";
+
+/// Pattern of the default rules.
+pub const DEFAULT_REDACTION_PATTERN: &str = "(?i)password|secret|token|key";
+
+/// Which tool arguments are redacted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedactionRules {
+    /// Field names redacted wherever they appear, compared exactly
+    pub fields: Vec<String>,
+    /// Regular expressions; a field whose name matches one is redacted
+    pub patterns: Vec<String>,
+    /// JSON pointers to redacted values, such as `/auth/0/value`. A `*`
+    /// segment matches any field name or array index.
+    pub pointers: Vec<String>,
+    /// Field names, or JSON pointers starting with `/`, that are never
+    /// redacted even when another rule matches them
+    pub allow: Vec<String>,
+}
+
+impl RedactionRules {
+    /// Rules that redact nothing.
+    pub fn none() -> Self {
+        Self {
+            fields: Vec::new(),
+            patterns: Vec::new(),
+            pointers: Vec::new(),
+            allow: Vec::new(),
+        }
+    }
+
+    /// Check the rules and prepare them for use. Fails if a pattern is not a
+    /// valid regular expression or a pointer does not start with `/`.
+    pub fn compile(&self) -> Result<Redactor> {
+        let patterns = if self.patterns.is_empty() {
+            None
+        } else {
+            Some(RegexSet::new(&self.patterns).context("Invalid redaction pattern")?)
+        };
+        let pointers = self
+            .pointers
+            .iter()
+            .map(|pointer| parse_pointer(pointer))
+            .collect::<Result<_>>()?;
+        let mut allow_fields = HashSet::new();
+        let mut allow_pointers = Vec::new();
+        for entry in &self.allow {
+            if entry.starts_with('/') {
+                allow_pointers.push(parse_pointer(entry)?);
+            } else {
+                allow_fields.insert(entry.clone());
+            }
+        }
+        Ok(Redactor {
+            fields: self.fields.iter().cloned().collect(),
+            patterns,
+            pointers,
+            allow_fields,
+            allow_pointers,
+        })
+    }
+}
+
+impl Default for RedactionRules {
+    fn default() -> Self {
+        Self {
+            patterns: vec![DEFAULT_REDACTION_PATTERN.to_string()],
+            ..Self::none()
+        }
+    }
+}
+
+/// Compiled [`RedactionRules`].
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    fields: HashSet<String>,
+    patterns: Option<RegexSet>,
+    pointers: Vec<Vec<String>>,
+    allow_fields: HashSet<String>,
+    allow_pointers: Vec<Vec<String>>,
+}
+
+static DEFAULT_REDACTOR: LazyLock<Redactor> = LazyLock::new(|| {
+    RedactionRules::default()
+        .compile()
+        .expect("default redaction rules are valid")
+});
+
+impl Redactor {
+    /// The redactor for the default rules.
+    pub fn default_rules() -> &'static Redactor {
+        &DEFAULT_REDACTOR
+    }
+
+    /// Replace every redacted value in `value` with [`REDACTED`].
+    pub fn redact(&self, value: &mut Value) {
+        self.walk(value, &mut Vec::new(), &mut Vec::new());
+    }
+
+    /// `arguments` with every redacted value replaced.
+    pub fn redact_arguments(&self, arguments: &Map<String, Value>) -> Map<String, Value> {
+        let mut value = Value::Object(arguments.clone());
+        self.redact(&mut value);
+        match value {
+            Value::Object(map) => map,
+            _ => unreachable!("redaction keeps objects as objects"),
+        }
+    }
+
+    /// `text` with every string value redacted from `arguments` replaced by
+    /// [`REDACTED`], so an error message that echoes an argument does not
+    /// leak it.
+    pub fn redact_text(&self, arguments: Option<&Map<String, Value>>, text: &str) -> String {
+        let Some(arguments) = arguments else {
+            return text.to_string();
+        };
+        let mut secrets = Vec::new();
+        self.walk(
+            &mut Value::Object(arguments.clone()),
+            &mut Vec::new(),
+            &mut secrets,
+        );
+        // Replace longer values first so a value containing another one is
+        // not left partly visible.
+        secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+        secrets
+            .iter()
+            .filter(|secret| !secret.is_empty())
+            .fold(text.to_string(), |text, secret| {
+                text.replace(secret.as_str(), REDACTED)
+            })
+    }
+
+    fn walk(&self, value: &mut Value, path: &mut Vec<String>, secrets: &mut Vec<String>) {
+        let children: Vec<(Option<&str>, &mut Value)> = match value {
+            Value::Object(map) => map
+                .iter_mut()
+                .map(|(key, child)| (Some(key.as_str()), child))
+                .collect(),
+            Value::Array(items) => items.iter_mut().map(|child| (None, child)).collect(),
+            _ => return,
+        };
+        for (index, (name, child)) in children.into_iter().enumerate() {
+            path.push(name.map_or_else(|| index.to_string(), str::to_string));
+            if self.is_redacted(name, path) {
+                collect_strings(child, secrets);
+                *child = Value::String(REDACTED.to_string());
+            } else {
+                self.walk(child, path, secrets);
+            }
+            path.pop();
+        }
+    }
+
+    fn is_redacted(&self, name: Option<&str>, path: &[String]) -> bool {
+        if name.is_some_and(|name| self.allow_fields.contains(name))
+            || self
+                .allow_pointers
+                .iter()
+                .any(|pointer| pointer_matches(pointer, path))
+        {
+            return false;
+        }
+        name.is_some_and(|name| {
+            self.fields.contains(name)
+                || self
+                    .patterns
+                    .as_ref()
+                    .is_some_and(|patterns| patterns.is_match(name))
+        }) || self
+            .pointers
+            .iter()
+            .any(|pointer| pointer_matches(pointer, path))
+    }
+}
+
+/// Split a JSON pointer into its unescaped segments.
+fn parse_pointer(pointer: &str) -> Result<Vec<String>> {
+    let Some(rest) = pointer.strip_prefix('/') else {
+        anyhow::bail!("Redaction pointer '{pointer}' must start with '/'");
+    };
+    Ok(rest
+        .split('/')
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn pointer_matches(pointer: &[String], path: &[String]) -> bool {
+    pointer.len() == path.len()
+        && pointer
+            .iter()
+            .zip(path)
+            .all(|(segment, name)| segment == "*" || segment == name)
+}
+
+fn collect_strings(value: &Value, strings: &mut Vec<String>) {
+    match value {
+        Value::String(s) => strings.push(s.clone()),
+        Value::Array(items) => items.iter().for_each(|item| collect_strings(item, strings)),
+        Value::Object(map) => map.values().for_each(|item| collect_strings(item, strings)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn redacted(rules: &RedactionRules, mut value: Value) -> Value {
+        rules.compile().unwrap().redact(&mut value);
+        value
+    }
+
+    #[test]
+    fn test_default_rules_redact_nested_objects_and_arrays() {
+        let value = redacted(
+            &RedactionRules::default(),
+            json!({
+                "url": "https://example.com",
+                "auth": {"user": "alice", "Password": "hunter2"},
+                "headers": [
+                    {"name": "accept", "value": "text/html"},
+                    {"name": "x", "api_key": "k-123"}
+                ],
+                "tokens": ["a", "b"]
+            }),
+        );
+        assert_eq!(
+            value,
+            json!({
+                "url": "https://example.com",
+                "auth": {"user": "alice", "Password": REDACTED},
+                "headers": [
+                    {"name": "accept", "value": "text/html"},
+                    {"name": "x", "api_key": REDACTED}
+                ],
+                "tokens": REDACTED
+            })
+        );
+    }
+
+    #[test]
+    fn test_fields_pointers_and_allowlist() {
+        let rules = RedactionRules {
+            fields: vec!["cookie".to_string()],
+            pointers: vec!["/headers/*/value".to_string(), "/body".to_string()],
+            allow: vec!["key_id".to_string(), "/auth/token_type".to_string()],
+            ..RedactionRules::default()
+        };
+        let value = redacted(
+            &rules,
+            json!({
+                "key_id": "kid-1",
+                "cookie": "c=1",
+                "Cookie": "kept",
+                "body": {"a": 1},
+                "headers": [{"name": "authorization", "value": "Bearer abc"}],
+                "auth": {"token_type": "bearer", "token": "abc"},
+                "nested": {"body": "kept"}
+            }),
+        );
+        assert_eq!(
+            value,
+            json!({
+                "key_id": "kid-1",
+                "cookie": REDACTED,
+                "Cookie": "kept",
+                "body": REDACTED,
+                "headers": [{"name": "authorization", "value": REDACTED}],
+                "auth": {"token_type": "bearer", "token": REDACTED},
+                "nested": {"body": "kept"}
+            })
+        );
+
+        let value = redacted(&RedactionRules::none(), json!({"password": "p"}));
+        assert_eq!(value, json!({"password": "p"}));
+    }
+
+    #[test]
+    fn test_redact_text_hides_redacted_values() {
+        let arguments = json!({"url": "https://example.com", "auth": [{"secret": "s3cr3t"}]});
+        let text = Redactor::default_rules().redact_text(
+            arguments.as_object(),
+            "Request to https://example.com with s3cr3t failed",
+        );
+        assert_eq!(
+            text,
+            "Request to https://example.com with <redacted> failed"
+        );
+    }
+
+    #[test]
+    fn test_invalid_rules_are_rejected() {
+        let rules = RedactionRules {
+            patterns: vec!["(".to_string()],
+            ..RedactionRules::none()
+        };
+        assert!(rules.compile().is_err());
+        let rules = RedactionRules {
+            pointers: vec!["headers".to_string()],
+            ..RedactionRules::none()
+        };
+        assert!(rules.compile().is_err());
+    }
+}
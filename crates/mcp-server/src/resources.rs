@@ -2,13 +2,39 @@
 // Licensed under the MIT license.
 
 use anyhow::Result;
-use rmcp::model::{ListResourcesRequest, ListResourcesResult};
+use rmcp::model::{ListResourcesRequest, ListResourcesResult, ReadResourceRequestParam};
 
-pub async fn handle_resources_list(req: serde_json::Value) -> Result<serde_json::Value> {
+use crate::results::ResultStore;
+
+/// Handles a request to list resources. The only resources are the large
+/// tool results held in `results`, if the server serves them as resources.
+pub async fn handle_resources_list(
+    req: serde_json::Value,
+    results: Option<&ResultStore>,
+) -> Result<serde_json::Value> {
     let _parsed_req: ListResourcesRequest = serde_json::from_value(req)?;
+    let resources = match results {
+        Some(results) => results.list().await,
+        None => vec![],
+    };
     let response = ListResourcesResult {
-        resources: vec![],
+        resources,
         next_cursor: None,
     };
     Ok(serde_json::to_value(response)?)
 }
+
+/// Handles a request to read a resource.
+pub async fn handle_resources_read(
+    req: ReadResourceRequestParam,
+    results: Option<&ResultStore>,
+) -> Result<serde_json::Value> {
+    let read = match results {
+        Some(results) => results.read(&req.uri).await,
+        None => None,
+    };
+    match read {
+        Some(result) => Ok(serde_json::to_value(result?)?),
+        None => anyhow::bail!("Resource not found: {}", req.uri),
+    }
+}
@@ -0,0 +1,408 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Large tool results served as MCP resources instead of inline text.
+//!
+//! When a tool result's text is larger than the configured threshold, the
+//! text is written to a file in the results directory, readable only by the
+//! current user, and registered as a
+//! `wassette://results/<uuid>` resource. The client gets a short summary and
+//! a `resource_link` content item, and reads the full result with
+//! `resources/read` until it expires.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{Context, Result};
+use rmcp::model::{
+    AnnotateAble, CallToolResult, Content, RawResource, ReadResourceResult, Resource,
+    ResourceContents,
+};
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+/// URI prefix of the resources large results are served as.
+pub const RESULT_URI_PREFIX: &str = "wassette://results/";
+
+/// Name of the results directory inside the component directory.
+pub const RESULTS_DIR: &str = "results";
+
+/// Prefix of the file names results are written under. Only files with this
+/// prefix are deleted when the store starts, so a results directory shared
+/// with other files keeps them.
+const RESULT_FILE_PREFIX: &str = "result-";
+
+/// Default number of bytes above which a result is served as a resource.
+pub const DEFAULT_RESULT_THRESHOLD_BYTES: usize = 1024 * 1024;
+
+/// Default time a result stays readable after the call that produced it.
+pub const DEFAULT_RESULT_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// Where and when large results are turned into resources.
+#[derive(Debug, Clone)]
+pub struct ResultStoreConfig {
+    /// Directory the results are written to
+    pub dir: PathBuf,
+    /// Results whose text is larger than this many bytes become resources
+    pub threshold_bytes: usize,
+    /// How long a result can be read before it is deleted
+    pub retention: Duration,
+}
+
+impl ResultStoreConfig {
+    /// Config with the default threshold and retention, writing to the
+    /// [`RESULTS_DIR`] directory inside `component_dir`.
+    pub fn new(component_dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: component_dir.as_ref().join(RESULTS_DIR),
+            threshold_bytes: DEFAULT_RESULT_THRESHOLD_BYTES,
+            retention: DEFAULT_RESULT_RETENTION,
+        }
+    }
+}
+
+struct StoredResult {
+    path: PathBuf,
+    tool_name: String,
+    size: usize,
+    mime_type: &'static str,
+    expires_at: Instant,
+}
+
+impl StoredResult {
+    fn resource(&self, id: &str) -> RawResource {
+        RawResource {
+            uri: format!("{RESULT_URI_PREFIX}{id}"),
+            name: format!("{} result", self.tool_name),
+            title: None,
+            description: Some(format!("Full result of {}", self.tool_name)),
+            mime_type: Some(self.mime_type.to_string()),
+            size: u32::try_from(self.size).ok(),
+            icons: None,
+        }
+    }
+}
+
+/// Large tool results written to disk and served as resources.
+#[derive(Clone)]
+pub struct ResultStore {
+    config: Arc<ResultStoreConfig>,
+    results: Arc<RwLock<HashMap<String, StoredResult>>>,
+}
+
+impl ResultStore {
+    /// Create the results directory, accessible only to the current user,
+    /// and delete results left behind by earlier runs that are older than
+    /// the retention period.
+    pub async fn new(config: ResultStoreConfig) -> Result<Self> {
+        let mut builder = tokio::fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        builder.mode(0o700);
+        builder.create(&config.dir).await.with_context(|| {
+            format!(
+                "Failed to create results directory at {}",
+                config.dir.display()
+            )
+        })?;
+
+        let mut entries = tokio::fs::read_dir(&config.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let ours = entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.starts_with(RESULT_FILE_PREFIX));
+            if !ours {
+                continue;
+            }
+            let expired = entry
+                .metadata()
+                .await
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .is_some_and(|age| age > config.retention);
+            if expired {
+                let _ = tokio::fs::remove_file(entry.path()).await;
+            }
+        }
+
+        Ok(Self {
+            config: Arc::new(config),
+            results: Arc::default(),
+        })
+    }
+
+    /// The config the store was created with.
+    pub fn config(&self) -> &ResultStoreConfig {
+        &self.config
+    }
+
+    /// Replace a result whose text is larger than the threshold with a
+    /// summary and a link to the resource holding the text. Error results
+    /// and results under the threshold are returned unchanged, and so are
+    /// results that cannot be written to the results directory.
+    pub async fn spill(&self, tool_name: &str, result: CallToolResult) -> CallToolResult {
+        if result.is_error == Some(true) {
+            return result;
+        }
+        let texts: Vec<&str> = result
+            .content
+            .iter()
+            .filter_map(|content| content.as_text().map(|text| text.text.as_str()))
+            .collect();
+        let size: usize = texts.iter().map(|text| text.len()).sum();
+        if size <= self.config.threshold_bytes {
+            return result;
+        }
+
+        let body = texts.join("\n");
+        let mime_type = if serde_json::from_str::<serde_json::Value>(&body).is_ok() {
+            "application/json"
+        } else {
+            "text/plain"
+        };
+        let id = uuid::Uuid::new_v4().to_string();
+        let path = self.config.dir.join(format!("{RESULT_FILE_PREFIX}{id}"));
+        if let Err(error) = write_private(&path, body.as_bytes()).await {
+            warn!(%error, path = %path.display(), "Failed to write large result, returning it inline");
+            return result;
+        }
+
+        let stored = StoredResult {
+            path,
+            tool_name: tool_name.to_string(),
+            size,
+            mime_type,
+            expires_at: Instant::now() + self.config.retention,
+        };
+        let link = stored.resource(&id);
+        info!(tool_name, size, uri = %link.uri, "Serving large tool result as a resource");
+        self.results.write().await.insert(id, stored);
+
+        let summary = format!(
+            "The result of {tool_name} is {size} bytes, more than the {} bytes returned inline. \
+             It is available as the resource {} for {} seconds.",
+            self.config.threshold_bytes,
+            link.uri,
+            self.config.retention.as_secs()
+        );
+        CallToolResult {
+            content: vec![Content::text(summary), Content::resource_link(link)],
+            structured_content: None,
+            is_error: result.is_error,
+            meta: result.meta,
+        }
+    }
+
+    /// Resources for the results that have not expired.
+    pub async fn list(&self) -> Vec<Resource> {
+        let now = Instant::now();
+        let results = self.results.read().await;
+        let mut resources: Vec<Resource> = results
+            .iter()
+            .filter(|(_, result)| result.expires_at > now)
+            .map(|(id, result)| result.resource(id).no_annotation())
+            .collect();
+        resources.sort_by(|a, b| a.uri.cmp(&b.uri));
+        resources
+    }
+
+    /// Read a result by URI. Returns `None` for URIs outside
+    /// `wassette://results/`, so other resource handlers can be tried.
+    pub async fn read(&self, uri: &str) -> Option<Result<ReadResourceResult>> {
+        let id = uri.strip_prefix(RESULT_URI_PREFIX)?;
+        let found = {
+            let results = self.results.read().await;
+            results
+                .get(id)
+                .filter(|result| result.expires_at > Instant::now())
+                .map(|result| (result.path.clone(), result.mime_type))
+        };
+        let Some((path, mime_type)) = found else {
+            return Some(Err(anyhow::anyhow!("Result not found or expired: {}", uri)));
+        };
+
+        Some(
+            tokio::fs::read_to_string(&path)
+                .await
+                .with_context(|| format!("Failed to read result from {}", path.display()))
+                .map(|text| ReadResourceResult {
+                    contents: vec![ResourceContents::TextResourceContents {
+                        uri: uri.to_string(),
+                        mime_type: Some(mime_type.to_string()),
+                        text,
+                        meta: None,
+                    }],
+                }),
+        )
+    }
+
+    /// Delete the results whose retention period is over. Returns how many
+    /// were deleted.
+    pub async fn remove_expired(&self) -> usize {
+        let now = Instant::now();
+        let expired: Vec<StoredResult> = {
+            let mut results = self.results.write().await;
+            let ids: Vec<String> = results
+                .iter()
+                .filter(|(_, result)| result.expires_at <= now)
+                .map(|(id, _)| id.clone())
+                .collect();
+            ids.iter().filter_map(|id| results.remove(id)).collect()
+        };
+        for result in &expired {
+            if let Err(error) = tokio::fs::remove_file(&result.path).await {
+                warn!(%error, path = %result.path.display(), "Failed to delete expired result");
+            }
+        }
+        expired.len()
+    }
+
+    /// Delete expired results in the background until the store is dropped.
+    pub fn spawn_cleanup(&self) -> tokio::task::JoinHandle<()> {
+        let results = Arc::downgrade(&self.results);
+        let config = Arc::clone(&self.config);
+        let period = (config.retention / 2).clamp(Duration::from_secs(1), Duration::from_secs(60));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                let Some(results) = results.upgrade() else {
+                    break;
+                };
+                let store = ResultStore {
+                    config: Arc::clone(&config),
+                    results,
+                };
+                let removed = store.remove_expired().await;
+                if removed > 0 {
+                    debug!(removed, "Deleted expired tool results");
+                }
+            }
+        })
+    }
+}
+
+/// Write `contents` to a new file at `path` that only the current user can
+/// read.
+async fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path).await?;
+    file.write_all(contents).await?;
+    file.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store_config(dir: &std::path::Path, retention: Duration) -> ResultStoreConfig {
+        ResultStoreConfig {
+            dir: dir.to_path_buf(),
+            threshold_bytes: 16,
+            retention,
+        }
+    }
+
+    fn text_result(text: &str) -> CallToolResult {
+        CallToolResult {
+            content: vec![Content::text(text)],
+            structured_content: None,
+            is_error: Some(false),
+            meta: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_large_result_is_served_as_resource() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let store = ResultStore::new(store_config(dir.path(), DEFAULT_RESULT_RETENTION)).await?;
+
+        let small = store.spill("report", text_result("short")).await;
+        assert_eq!(small.content[0].as_text().unwrap().text, "short");
+
+        let body = r#"{"rows": [1, 2, 3, 4, 5, 6, 7, 8]}"#;
+        let spilled = store.spill("report", text_result(body)).await;
+        assert_eq!(spilled.content.len(), 2);
+        let link = spilled.content[1].as_resource_link().unwrap();
+        assert!(link.uri.starts_with(RESULT_URI_PREFIX));
+        assert_eq!(link.mime_type.as_deref(), Some("application/json"));
+        assert!(spilled.content[0]
+            .as_text()
+            .unwrap()
+            .text
+            .contains(&link.uri));
+
+        assert_eq!(store.list().await.len(), 1);
+        let read = store.read(&link.uri).await.unwrap()?;
+        match &read.contents[0] {
+            ResourceContents::TextResourceContents { text, .. } => assert_eq!(text, body),
+            other => panic!("unexpected contents: {other:?}"),
+        }
+        assert!(store.read("wassette://other/1").await.is_none());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_results_are_private_to_the_user() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir()?;
+        let results_dir = dir.path().join(RESULTS_DIR);
+        let store = ResultStore::new(store_config(&results_dir, DEFAULT_RESULT_RETENTION)).await?;
+        store
+            .spill("report", text_result("a result longer than sixteen bytes"))
+            .await;
+
+        let mode = |path: &Path| -> Result<u32> {
+            Ok(std::fs::metadata(path)?.permissions().mode() & 0o777)
+        };
+        assert_eq!(mode(&results_dir)?, 0o700);
+        let file = std::fs::read_dir(&results_dir)?.next().unwrap()?;
+        assert!(file
+            .file_name()
+            .to_str()
+            .unwrap()
+            .starts_with(RESULT_FILE_PREFIX));
+        assert_eq!(mode(&file.path())?, 0o600);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_startup_only_deletes_expired_results() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(dir.path().join("result-left-behind"), "old")?;
+        std::fs::write(dir.path().join("notes.txt"), "not a result")?;
+
+        ResultStore::new(store_config(dir.path(), Duration::ZERO)).await?;
+        assert!(!dir.path().join("result-left-behind").exists());
+        assert!(dir.path().join("notes.txt").exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_expired_results_are_deleted() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let store = ResultStore::new(store_config(dir.path(), Duration::ZERO)).await?;
+
+        let spilled = store
+            .spill("report", text_result("a result longer than sixteen bytes"))
+            .await;
+        let uri = spilled.content[1].as_resource_link().unwrap().uri.clone();
+
+        assert!(store.list().await.is_empty());
+        assert!(store.read(&uri).await.unwrap().is_err());
+        assert_eq!(store.remove_expired().await, 1);
+        assert_eq!(std::fs::read_dir(dir.path())?.count(), 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,779 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! The MCP server handler, for the `wassette` binary and for embedders.
+//!
+//! [`McpServerBuilder`] puts together the same MCP surface the `wassette`
+//! binary serves: component tools, the built-in tools, prompts, and large
+//! results as resources. Embedders can add tools of their own with
+//! [`McpServerBuilder::with_extra_tool`]; extra tools are listed and
+//! dispatched next to the built-in tools and follow the same
+//! `disable_builtin_tools` and read-only settings.
+
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use rmcp::model::{
+    CallToolRequestParam, CallToolResult, ErrorData, JsonObject, ListPromptsResult,
+    ListResourcesResult, ListToolsResult, LoggingLevel, PaginatedRequestParam,
+    ReadResourceRequestParam, ReadResourceResult, ResourcesCapability, ServerCapabilities,
+    ServerInfo, SetLevelRequestParam, Tool, ToolsCapability,
+};
+use rmcp::service::{NotificationContext, RequestContext, RoleServer};
+use rmcp::ServerHandler;
+use wassette::{LifecycleManager, ParameterLimitExceeded};
+
+use crate::call_limit::{CallLimitConfig, CallLimiter, CallLoad, CallSlot};
+use crate::components::ToolCache;
+use crate::logging::{ComponentLogging, StderrLog};
+use crate::notifications::Peers;
+use crate::progress::ProgressTarget;
+use crate::redaction::{RedactionRules, Redactor};
+use crate::server_info::ServerDetails;
+use crate::tools::{
+    call_tool, is_builtin_tool, is_slot_exempt_builtin_tool, list_tools, CallToolContext,
+};
+use crate::{
+    forward_tool_list_changes, handle_prompts_list, handle_resources_list, handle_resources_read,
+    ResultStore,
+};
+
+/// Future returned by an extra tool's handler.
+pub type ExtraToolFuture = Pin<Box<dyn Future<Output = Result<CallToolResult>> + Send>>;
+
+type ExtraToolHandler =
+    Arc<dyn Fn(CallToolRequestParam, LifecycleManager) -> ExtraToolFuture + Send + Sync>;
+
+/// A tool an embedder serves next to the built-in tools.
+#[derive(Clone)]
+pub struct ExtraTool {
+    tool: Tool,
+    handler: ExtraToolHandler,
+}
+
+impl ExtraTool {
+    /// The tool definition, as listed to clients.
+    pub fn tool(&self) -> &Tool {
+        &self.tool
+    }
+
+    /// Whether the tool may change state. Only tools annotated with
+    /// `readOnlyHint: true` stay available on a read-only server.
+    pub fn is_mutating(&self) -> bool {
+        self.tool
+            .annotations
+            .as_ref()
+            .and_then(|annotations| annotations.read_only_hint)
+            != Some(true)
+    }
+
+    pub(crate) async fn call(
+        &self,
+        req: CallToolRequestParam,
+        lifecycle_manager: LifecycleManager,
+    ) -> Result<CallToolResult> {
+        (self.handler)(req, lifecycle_manager).await
+    }
+}
+
+/// Builds an [`McpServer`].
+pub struct McpServerBuilder {
+    lifecycle_manager: LifecycleManager,
+    disable_builtin_tools: bool,
+    read_only: bool,
+    call_timing: bool,
+    results: Option<ResultStore>,
+    extra_tools: Vec<ExtraTool>,
+    redaction: RedactionRules,
+    call_limit: Option<CallLimitConfig>,
+    details: ServerDetails,
+    component_log_level: LoggingLevel,
+}
+
+impl McpServerBuilder {
+    /// Start building a server for the components managed by `lifecycle_manager`.
+    pub fn new(lifecycle_manager: LifecycleManager) -> Self {
+        Self {
+            lifecycle_manager,
+            disable_builtin_tools: false,
+            read_only: false,
+            call_timing: false,
+            results: None,
+            extra_tools: Vec::new(),
+            redaction: RedactionRules::default(),
+            call_limit: None,
+            details: ServerDetails::default(),
+            component_log_level: LoggingLevel::Info,
+        }
+    }
+
+    /// Leave out the built-in tools, and any extra tools, so only component
+    /// tools are served.
+    pub fn with_disable_builtin_tools(mut self, disable_builtin_tools: bool) -> Self {
+        self.disable_builtin_tools = disable_builtin_tools;
+        self
+    }
+
+    /// Leave out the tools that load, unload, or install components or
+    /// change permissions, and extra tools not annotated as read-only.
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Report where the time of each component tool call went in the
+    /// result's `_meta.wassette.timing`: instantiation, execution, and total
+    /// milliseconds, and whether the component was cold-loaded for the call.
+    pub fn with_call_timing(mut self, call_timing: bool) -> Self {
+        self.call_timing = call_timing;
+        self
+    }
+
+    /// Serve tool results larger than the store's threshold as resources
+    /// held in `results`, which are deleted once their retention is over.
+    pub fn with_result_store(mut self, results: ResultStore) -> Self {
+        self.results = Some(results);
+        self
+    }
+
+    /// Redact the tool arguments `redaction` selects from the log lines and
+    /// error messages of tool calls, instead of the default rules.
+    pub fn with_redaction_rules(mut self, redaction: RedactionRules) -> Self {
+        self.redaction = redaction;
+        self
+    }
+
+    /// Run at most `call_limit.max_concurrent_calls` tool calls at once.
+    /// Further calls wait for a free slot and are refused with a
+    /// [`SERVER_AT_CAPACITY`](crate::call_limit::SERVER_AT_CAPACITY) error
+    /// once `call_limit.queue_timeout` is over. Unlimited by default.
+    pub fn with_call_limit(mut self, call_limit: CallLimitConfig) -> Self {
+        self.call_limit = Some(call_limit);
+        self
+    }
+
+    /// Report `details` from the `get-server-info` tool instead of this
+    /// crate's version and no transports.
+    pub fn with_server_details(mut self, details: ServerDetails) -> Self {
+        self.details = details;
+        self
+    }
+
+    /// Send what components write to stderr during a tool call to the
+    /// calling client as log messages at `level`, instead of `info`. Clients
+    /// that set a more severe minimum with `logging/setLevel` get none.
+    pub fn with_component_log_level(mut self, level: LoggingLevel) -> Self {
+        self.component_log_level = level;
+        self
+    }
+
+    /// Serve `tool` next to the built-in tools, calling `handler` for it.
+    /// The handler gets the call and the server's lifecycle manager.
+    pub fn with_extra_tool<F, Fut>(mut self, tool: Tool, handler: F) -> Self
+    where
+        F: Fn(CallToolRequestParam, LifecycleManager) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<CallToolResult>> + Send + 'static,
+    {
+        self.extra_tools.push(ExtraTool {
+            tool,
+            handler: Arc::new(move |req, lifecycle_manager| {
+                Box::pin(handler(req, lifecycle_manager))
+            }),
+        });
+        self
+    }
+
+    /// Build the server. Fails if an extra tool's name is taken by a built-in
+    /// tool or another extra tool, or if the redaction rules are invalid.
+    ///
+    /// Tool list change notifications are driven by the lifecycle manager's
+    /// event bus, so they are sent for every load and unload regardless of
+    /// whether it came from a tool call or from background loading. Must be
+    /// called from within a Tokio runtime.
+    pub fn build(self) -> Result<McpServer> {
+        let mut names = HashSet::new();
+        for extra in &self.extra_tools {
+            let name = extra.tool.name.as_ref();
+            if is_builtin_tool(name) || !names.insert(name) {
+                bail!("Tool name '{}' is already in use", name);
+            }
+        }
+
+        let redactor = self
+            .redaction
+            .compile()
+            .context("Invalid argument redaction rules")?;
+
+        if let Some(results) = &self.results {
+            results.spawn_cleanup();
+        }
+        let server = McpServer {
+            lifecycle_manager: self.lifecycle_manager,
+            peers: Peers::default(),
+            disable_builtin_tools: self.disable_builtin_tools,
+            read_only: self.read_only,
+            call_timing: self.call_timing,
+            results: self.results,
+            extra_tools: Arc::new(self.extra_tools),
+            tool_cache: Arc::default(),
+            redactor: Arc::new(redactor),
+            call_limiter: self
+                .call_limit
+                .map(|config| Arc::new(CallLimiter::new(config))),
+            details: Arc::new(self.details),
+            logging: ComponentLogging::new(self.component_log_level),
+        };
+
+        let peers = server.peers.clone();
+        tokio::spawn(forward_tool_list_changes(
+            server.lifecycle_manager.subscribe(),
+            move || peers.live(),
+        ));
+
+        Ok(server)
+    }
+}
+
+/// A security-oriented runtime that runs WebAssembly Components via MCP.
+#[derive(Clone)]
+pub struct McpServer {
+    lifecycle_manager: LifecycleManager,
+    peers: Peers,
+    disable_builtin_tools: bool,
+    read_only: bool,
+    call_timing: bool,
+    results: Option<ResultStore>,
+    extra_tools: Arc<Vec<ExtraTool>>,
+    tool_cache: Arc<ToolCache>,
+    redactor: Arc<Redactor>,
+    call_limiter: Option<Arc<CallLimiter>>,
+    details: Arc<ServerDetails>,
+    logging: ComponentLogging,
+}
+
+impl McpServer {
+    /// Start building a server; see [`McpServerBuilder`].
+    pub fn builder(lifecycle_manager: LifecycleManager) -> McpServerBuilder {
+        McpServerBuilder::new(lifecycle_manager)
+    }
+
+    /// The lifecycle manager the server serves components from.
+    pub fn lifecycle_manager(&self) -> &LifecycleManager {
+        &self.lifecycle_manager
+    }
+
+    /// Tool calls running and waiting for a slot, if the server limits
+    /// concurrent calls.
+    pub fn call_load(&self) -> Option<CallLoad> {
+        self.call_limiter.as_ref().map(|limiter| limiter.load())
+    }
+
+    /// Take a call slot for a call to `tool_name`, waiting for one if they
+    /// are all taken. `None` when calls are unlimited or the tool is exempt.
+    async fn call_slot(&self, tool_name: &str) -> Result<Option<CallSlot>, ErrorData> {
+        let Some(limiter) = &self.call_limiter else {
+            return Ok(None);
+        };
+        // A batch takes no slot itself: each of its calls takes one
+        if tool_name == "invoke-batch" {
+            return Ok(None);
+        }
+        let exempt = limiter.exempts_builtin_tools()
+            && (is_slot_exempt_builtin_tool(tool_name)
+                || self
+                    .extra_tools
+                    .iter()
+                    .any(|extra| extra.tool.name == tool_name && !extra.is_mutating()));
+        if exempt {
+            return Ok(None);
+        }
+        limiter.acquire(tool_name).await.map(Some)
+    }
+
+    /// The clients tool list change notifications are sent to.
+    pub fn peers(&self) -> &Peers {
+        &self.peers
+    }
+}
+
+#[allow(refining_impl_trait_reachable)]
+impl ServerHandler for McpServer {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities {
+                tools: Some(ToolsCapability {
+                    list_changed: Some(true),
+                }),
+                resources: self.results.as_ref().map(|_| ResourcesCapability::default()),
+                logging: Some(JsonObject::default()),
+                ..Default::default()
+            },
+            instructions: Some(
+                r#"This server runs tools in sandboxed WebAssembly environments with no default access to host resources.
+
+Key points:
+- Tools must be loaded before use: "Load component from oci://registry/tool:version" or "file:///path/to/tool.wasm"
+- When the server starts, it will load all tools present in the component directory.
+- You can list loaded tools with 'list-components' tool.
+- Each tool only accesses resources explicitly granted by a policy file (filesystem paths, network domains, etc.)
+- You MUST never modify the policy file directly, use tools to grant permissions instead.
+- Tools needs permission for that resource
+- If access is denied, suggest alternatives within allowed permissions or propose to grant permission"#.to_string(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    fn call_tool<'a>(
+        &'a self,
+        params: CallToolRequestParam,
+        ctx: RequestContext<RoleServer>,
+    ) -> Pin<Box<dyn Future<Output = Result<CallToolResult, ErrorData>> + Send + 'a>> {
+        self.peers.insert(&ctx.peer);
+
+        Box::pin(async move {
+            let tool_name = params.name.to_string();
+            let _slot = self.call_slot(&tool_name).await?;
+            let result = call_tool(
+                params,
+                CallToolContext {
+                    lifecycle_manager: &self.lifecycle_manager,
+                    extra_tools: &self.extra_tools,
+                    disable_builtin_tools: self.disable_builtin_tools,
+                    read_only: self.read_only,
+                    call_timing: self.call_timing,
+                    redactor: &self.redactor,
+                    details: &self.details,
+                    cancellation: &ctx.ct,
+                    stderr_log: Some(StderrLog {
+                        logging: &self.logging,
+                        peer: &ctx.peer,
+                    }),
+                    progress: ctx.meta.get_progress_token().map(|token| ProgressTarget {
+                        peer: &ctx.peer,
+                        token,
+                    }),
+                    call_limiter: self.call_limiter.as_deref(),
+                },
+            )
+            .await;
+            let result: CallToolResult = match result {
+                Ok(value) => serde_json::from_value(value).map_err(|e| {
+                    ErrorData::parse_error(format!("Failed to parse result: {e}"), None)
+                })?,
+                Err(err) => match err.downcast_ref::<ParameterLimitExceeded>() {
+                    Some(exceeded) => {
+                        return Err(ErrorData::invalid_params(
+                            err.to_string(),
+                            serde_json::to_value(exceeded).ok(),
+                        ))
+                    }
+                    None => return Err(ErrorData::parse_error(err.to_string(), None)),
+                },
+            };
+            match &self.results {
+                Some(results) => Ok(results.spill(&tool_name, result).await),
+                None => Ok(result),
+            }
+        })
+    }
+
+    fn set_level(
+        &self,
+        request: SetLevelRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> impl Future<Output = Result<(), ErrorData>> + Send + '_ {
+        self.peers.insert(&context.peer);
+        self.logging.set_minimum(request.level);
+        std::future::ready(Ok(()))
+    }
+
+    fn on_initialized(
+        &self,
+        context: NotificationContext<RoleServer>,
+    ) -> impl Future<Output = ()> + Send + '_ {
+        self.peers.insert(&context.peer);
+        std::future::ready(())
+    }
+
+    fn list_tools<'a>(
+        &'a self,
+        _params: Option<PaginatedRequestParam>,
+        ctx: RequestContext<RoleServer>,
+    ) -> Pin<Box<dyn Future<Output = Result<ListToolsResult, ErrorData>> + Send + 'a>> {
+        self.peers.insert(&ctx.peer);
+
+        Box::pin(async move {
+            list_tools(
+                &self.lifecycle_manager,
+                &self.tool_cache,
+                &self.extra_tools,
+                self.disable_builtin_tools,
+                self.read_only,
+            )
+            .await
+            .map_err(|err| ErrorData::parse_error(err.to_string(), None))
+        })
+    }
+
+    fn list_prompts<'a>(
+        &'a self,
+        _params: Option<PaginatedRequestParam>,
+        ctx: RequestContext<RoleServer>,
+    ) -> Pin<Box<dyn Future<Output = Result<ListPromptsResult, ErrorData>> + Send + 'a>> {
+        self.peers.insert(&ctx.peer);
+
+        Box::pin(async move {
+            let result = handle_prompts_list(serde_json::Value::Null).await;
+            match result {
+                Ok(value) => serde_json::from_value(value).map_err(|e| {
+                    ErrorData::parse_error(format!("Failed to parse result: {e}"), None)
+                }),
+                Err(err) => Err(ErrorData::parse_error(err.to_string(), None)),
+            }
+        })
+    }
+
+    fn list_resources<'a>(
+        &'a self,
+        _params: Option<PaginatedRequestParam>,
+        ctx: RequestContext<RoleServer>,
+    ) -> Pin<Box<dyn Future<Output = Result<ListResourcesResult, ErrorData>> + Send + 'a>> {
+        self.peers.insert(&ctx.peer);
+
+        Box::pin(async move {
+            let result =
+                handle_resources_list(serde_json::Value::Null, self.results.as_ref()).await;
+            match result {
+                Ok(value) => serde_json::from_value(value).map_err(|e| {
+                    ErrorData::parse_error(format!("Failed to parse result: {e}"), None)
+                }),
+                Err(err) => Err(ErrorData::parse_error(err.to_string(), None)),
+            }
+        })
+    }
+
+    fn read_resource<'a>(
+        &'a self,
+        params: ReadResourceRequestParam,
+        ctx: RequestContext<RoleServer>,
+    ) -> Pin<Box<dyn Future<Output = Result<ReadResourceResult, ErrorData>> + Send + 'a>> {
+        self.peers.insert(&ctx.peer);
+
+        Box::pin(async move {
+            let uri = params.uri.clone();
+            let result = handle_resources_read(params, self.results.as_ref()).await;
+            match result {
+                Ok(value) => serde_json::from_value(value).map_err(|e| {
+                    ErrorData::parse_error(format!("Failed to parse result: {e}"), None)
+                }),
+                Err(err) => Err(ErrorData::resource_not_found(
+                    err.to_string(),
+                    Some(serde_json::json!({ "uri": uri })),
+                )),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::time::Duration;
+
+    use rmcp::model::{Content, ToolAnnotations};
+    use serde_json::json;
+    use tokio_util::sync::CancellationToken;
+
+    use super::*;
+    use crate::call_limit::SERVER_AT_CAPACITY;
+
+    fn extra_tool(name: &'static str, read_only: bool) -> Tool {
+        Tool {
+            name: Cow::Borrowed(name),
+            description: Some(Cow::Borrowed("An embedder tool")),
+            input_schema: Arc::new(serde_json::Map::new()),
+            output_schema: None,
+            annotations: Some(ToolAnnotations {
+                read_only_hint: Some(read_only),
+                ..Default::default()
+            }),
+            title: None,
+            icons: None,
+            meta: None,
+        }
+    }
+
+    fn tool_names(list: &ListToolsResult) -> Vec<&str> {
+        list.tools.iter().map(|tool| tool.name.as_ref()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_only_reading_and_unloading_tools_skip_the_call_limit() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = LifecycleManager::new(&tempdir).await?;
+        let server = McpServer::builder(lifecycle_manager)
+            .with_call_limit(CallLimitConfig {
+                max_concurrent_calls: 1,
+                queue_timeout: Duration::from_millis(100),
+                exempt_builtin_tools: true,
+            })
+            .with_extra_tool(extra_tool("embedder-status", true), |_, _| async {
+                Ok(CallToolResult::success(vec![Content::text("ok")]))
+            })
+            .with_extra_tool(extra_tool("embedder-reset", false), |_, _| async {
+                Ok(CallToolResult::success(vec![Content::text("reset")]))
+            })
+            .build()?;
+
+        let slot = server.call_slot("fetch").await?;
+        assert!(slot.is_some());
+        for tool in [
+            "list-components",
+            "get-policy",
+            "unload-component",
+            "deactivate-component",
+            "invoke-batch",
+            "embedder-status",
+        ] {
+            assert!(server.call_slot(tool).await?.is_none(), "{tool}");
+        }
+        for tool in [
+            "grant-network-permission",
+            "install-component",
+            "embedder-reset",
+        ] {
+            let error = server.call_slot(tool).await.err().unwrap();
+            assert_eq!(error.code, SERVER_AT_CAPACITY, "{tool}");
+        }
+
+        // load-component waits for the slot and runs once it is free
+        let (loading, ()) = tokio::join!(server.call_slot("load-component"), async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            assert_eq!(server.call_load().unwrap().queued, 1);
+            drop(slot);
+        });
+        let loading = loading?;
+        assert!(loading.is_some());
+        assert_eq!(server.call_load().unwrap().in_flight, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_no_tool_skips_the_call_limit_by_default() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = LifecycleManager::new(&tempdir).await?;
+        let server = McpServer::builder(lifecycle_manager)
+            .with_call_limit(CallLimitConfig {
+                queue_timeout: Duration::from_millis(50),
+                ..CallLimitConfig::new(1)
+            })
+            .build()?;
+
+        let _slot = server.call_slot("fetch").await?;
+        let error = server.call_slot("list-components").await.err().unwrap();
+        assert_eq!(error.code, SERVER_AT_CAPACITY);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_extra_tools_are_listed_and_dispatched() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = LifecycleManager::new(&tempdir).await?;
+        let server = McpServer::builder(lifecycle_manager.clone())
+            .with_read_only(true)
+            .with_extra_tool(extra_tool("embedder-status", true), |_, _| async {
+                Ok(CallToolResult::success(vec![Content::text("ok")]))
+            })
+            .with_extra_tool(extra_tool("embedder-reset", false), |_, _| async {
+                Ok(CallToolResult::success(vec![Content::text("reset")]))
+            })
+            .build()?;
+
+        let list = list_tools(
+            &lifecycle_manager,
+            &server.tool_cache,
+            &server.extra_tools,
+            false,
+            true,
+        )
+        .await?;
+        let names = tool_names(&list);
+        assert!(names.contains(&"embedder-status"));
+        assert!(!names.contains(&"embedder-reset"));
+        assert!(!names.contains(&"load-component"));
+
+        let call = |name: &'static str| CallToolRequestParam {
+            name: Cow::Borrowed(name),
+            arguments: None,
+        };
+        let details = ServerDetails::default();
+        let cancellation = CancellationToken::new();
+        let result = call_tool(
+            call("embedder-status"),
+            CallToolContext {
+                extra_tools: &server.extra_tools,
+                disable_builtin_tools: false,
+                read_only: true,
+                ..CallToolContext::new(&lifecycle_manager, &details, &cancellation)
+            },
+        )
+        .await?;
+        assert_eq!(result["content"][0]["text"], json!("ok"));
+        let result = call_tool(
+            call("embedder-reset"),
+            CallToolContext {
+                extra_tools: &server.extra_tools,
+                disable_builtin_tools: false,
+                read_only: true,
+                ..CallToolContext::new(&lifecycle_manager, &details, &cancellation)
+            },
+        )
+        .await?;
+        assert_eq!(result["isError"], json!(true));
+        let result = call_tool(
+            call("embedder-status"),
+            CallToolContext {
+                extra_tools: &server.extra_tools,
+                disable_builtin_tools: true,
+                read_only: false,
+                ..CallToolContext::new(&lifecycle_manager, &details, &cancellation)
+            },
+        )
+        .await?;
+        assert_eq!(result["isError"], json!(true));
+
+        let list = list_tools(
+            &lifecycle_manager,
+            &server.tool_cache,
+            &server.extra_tools,
+            true,
+            false,
+        )
+        .await?;
+        assert!(tool_names(&list).is_empty());
+        Ok(())
+    }
+
+    /// A client that forwards the tool list change notifications it gets.
+    #[derive(Clone)]
+    struct NotifiedClient(tokio::sync::mpsc::UnboundedSender<()>);
+
+    impl rmcp::ClientHandler for NotifiedClient {
+        fn on_tool_list_changed(
+            &self,
+            _context: NotificationContext<rmcp::RoleClient>,
+        ) -> impl Future<Output = ()> + Send + '_ {
+            let _ = self.0.send(());
+            std::future::ready(())
+        }
+    }
+
+    async fn connect(
+        server: &McpServer,
+    ) -> Result<(
+        rmcp::service::RunningService<rmcp::RoleClient, NotifiedClient>,
+        tokio::sync::mpsc::UnboundedReceiver<()>,
+    )> {
+        use rmcp::ServiceExt;
+
+        let (server_transport, client_transport) = tokio::io::duplex(4096);
+        let server = server.clone();
+        tokio::spawn(async move {
+            if let Ok(running) = server.serve(server_transport).await {
+                let _ = running.waiting().await;
+            }
+        });
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let client = NotifiedClient(sender).serve(client_transport).await?;
+        // The server registers the client once it has sent a request
+        client.list_tools(None).await?;
+        Ok((client, receiver))
+    }
+
+    async fn notified(receiver: &mut tokio::sync::mpsc::UnboundedReceiver<()>) -> bool {
+        tokio::time::timeout(std::time::Duration::from_secs(10), receiver.recv())
+            .await
+            .is_ok_and(|notification| notification.is_some())
+    }
+
+    #[tokio::test]
+    async fn test_every_connected_client_is_notified() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = LifecycleManager::new(&tempdir).await?;
+        let server = McpServer::builder(lifecycle_manager.clone()).build()?;
+        let component = std::env::current_dir()?.join("../component2json/testdata/fetch-rs.wasm");
+        let uri = format!("file://{}", component.display());
+
+        let (first, mut first_notifications) = connect(&server).await?;
+        let (second, mut second_notifications) = connect(&server).await?;
+        assert_eq!(server.peers().live().len(), 2);
+
+        // A load in the background, not made through either client
+        let load = tokio::spawn({
+            let lifecycle_manager = lifecycle_manager.clone();
+            let uri = uri.clone();
+            async move { lifecycle_manager.load_component(&uri).await }
+        });
+        let component_id = load.await??.component_id;
+        assert!(notified(&mut first_notifications).await);
+        assert!(notified(&mut second_notifications).await);
+
+        // A client that disconnected is dropped, and the others still notified
+        first.cancel().await?;
+        lifecycle_manager.unload_component(&component_id).await?;
+        assert!(notified(&mut second_notifications).await);
+        assert_eq!(server.peers().live().len(), 1);
+
+        // A client that connects later is notified too
+        let (_third, mut third_notifications) = connect(&server).await?;
+        lifecycle_manager.load_component(&uri).await?;
+        assert!(notified(&mut second_notifications).await);
+        assert!(notified(&mut third_notifications).await);
+        drop(second);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_clients_set_the_component_log_level() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = LifecycleManager::new(&tempdir).await?;
+        let server = McpServer::builder(lifecycle_manager)
+            .with_component_log_level(LoggingLevel::Notice)
+            .build()?;
+        let (client, _notifications) = connect(&server).await?;
+        let capabilities = &client.peer_info().unwrap().capabilities;
+        assert!(capabilities.logging.is_some());
+        assert!(server.logging.enabled());
+
+        client
+            .set_level(SetLevelRequestParam {
+                level: LoggingLevel::Error,
+            })
+            .await?;
+        assert!(!server.logging.enabled());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_extra_tool_names_must_be_unique() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = LifecycleManager::new(&tempdir).await?;
+        let handler = |_, _| async { Ok(CallToolResult::success(vec![])) };
+
+        let result = McpServer::builder(lifecycle_manager.clone())
+            .with_extra_tool(extra_tool("load-component", true), handler)
+            .build();
+        assert!(result.is_err());
+
+        let result = McpServer::builder(lifecycle_manager)
+            .with_extra_tool(extra_tool("embedder-status", true), handler)
+            .with_extra_tool(extra_tool("embedder-status", true), handler)
+            .build();
+        assert!(result.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! The `get-server-info` built-in tool, reporting what a running server is
+//! and how far it has got, from state it already holds.
+
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use rmcp::model::{CallToolResult, Content};
+use serde_json::{json, Value};
+use tracing::instrument;
+use wassette::LifecycleManager;
+
+/// What the server knows about itself rather than about its components: the
+/// build it runs and how it is reached. The `wassette` binary fills this in;
+/// embedders default to this crate's version and no transports.
+#[derive(Debug, Clone)]
+pub struct ServerDetails {
+    version: String,
+    build_info: Option<String>,
+    transports: Vec<String>,
+    started_at: Instant,
+}
+
+impl Default for ServerDetails {
+    fn default() -> Self {
+        Self::new(env!("CARGO_PKG_VERSION"))
+    }
+}
+
+impl ServerDetails {
+    /// Details of a server running `version`, started now.
+    pub fn new(version: impl Into<String>) -> Self {
+        Self {
+            version: version.into(),
+            build_info: None,
+            transports: Vec::new(),
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Report `build_info`, such as the output of `wassette --version`.
+    pub fn with_build_info(mut self, build_info: impl Into<String>) -> Self {
+        self.build_info = Some(build_info.into());
+        self
+    }
+
+    /// Report the transports the server is served over.
+    pub fn with_transports<I, S>(mut self, transports: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.transports = transports.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Time since the details were created.
+    pub fn uptime(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+}
+
+/// The report `get-server-info` and `wassette status` return. Only reads
+/// state held in memory, so it never touches the component directory.
+pub async fn server_info_report(
+    lifecycle_manager: &LifecycleManager,
+    details: &ServerDetails,
+) -> Value {
+    let counts = lifecycle_manager.component_counts().await;
+    json!({
+        "version": details.version,
+        "build_info": details.build_info,
+        "uptime_secs": details.uptime().as_secs(),
+        "components": {
+            "loaded": counts.loaded,
+            "known": counts.known,
+        },
+        "tools": counts.tools,
+        "background_loading": lifecycle_manager.background_load_status(),
+        "transports": details.transports,
+    })
+}
+
+/// Output schema of `get-server-info`.
+pub(crate) fn server_info_output_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "version": {"type": "string"},
+            "build_info": {"type": ["string", "null"]},
+            "uptime_secs": {"type": "integer", "minimum": 0},
+            "components": {
+                "type": "object",
+                "properties": {
+                    "loaded": {"type": "integer", "minimum": 0},
+                    "known": {"type": "integer", "minimum": 0}
+                },
+                "required": ["loaded", "known"]
+            },
+            "tools": {"type": "integer", "minimum": 0},
+            "background_loading": {
+                "type": "object",
+                "properties": {
+                    "started": {"type": "boolean"},
+                    "completed": {"type": "boolean"},
+                    "queued": {"type": "integer", "minimum": 0},
+                    "processed": {"type": "integer", "minimum": 0}
+                },
+                "required": ["started", "completed", "queued", "processed"]
+            },
+            "transports": {"type": "array", "items": {"type": "string"}}
+        },
+        "required": [
+            "version",
+            "uptime_secs",
+            "components",
+            "tools",
+            "background_loading",
+            "transports"
+        ]
+    })
+}
+
+#[instrument(skip_all)]
+pub async fn handle_get_server_info(
+    lifecycle_manager: &LifecycleManager,
+    details: &ServerDetails,
+) -> Result<CallToolResult> {
+    let result = server_info_report(lifecycle_manager, details).await;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(serde_json::to_string(&result)?)],
+        structured_content: Some(result),
+        is_error: None,
+        meta: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_server_info_report() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = LifecycleManager::new(&tempdir).await?;
+        let details = ServerDetails::new("1.2.3")
+            .with_build_info("wassette 1.2.3")
+            .with_transports(["stdio"]);
+
+        let report = server_info_report(&lifecycle_manager, &details).await;
+        assert_eq!(report["version"], json!("1.2.3"));
+        assert_eq!(report["build_info"], json!("wassette 1.2.3"));
+        assert_eq!(report["components"], json!({"loaded": 0, "known": 0}));
+        assert_eq!(report["tools"], json!(0));
+        assert_eq!(report["background_loading"]["started"], json!(false));
+        assert_eq!(report["transports"], json!(["stdio"]));
+
+        lifecycle_manager
+            .load_existing_components_async(None)
+            .await?;
+        let report = server_info_report(&lifecycle_manager, &details).await;
+        assert_eq!(report["background_loading"]["completed"], json!(true));
+
+        let result = handle_get_server_info(&lifecycle_manager, &details).await?;
+        let structured = result.structured_content.unwrap();
+        assert_eq!(
+            structured["background_loading"],
+            report["background_loading"]
+        );
+        Ok(())
+    }
+}
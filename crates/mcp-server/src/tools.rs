@@ -6,63 +6,154 @@ use std::sync::Arc;
 use std::time::Instant;
 
 use anyhow::Result;
-use rmcp::model::{CallToolRequestParam, CallToolResult, Content, Tool};
-use rmcp::{Peer, RoleServer};
+use rmcp::model::{CallToolRequestParam, CallToolResult, Content, ListToolsResult, Tool};
 use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, instrument, warn};
-use wassette::LifecycleManager;
+use wassette::{validate_component_id, LifecycleManager, ParameterLimitExceeded, ProgressReporter};
 
+use crate::call_limit::CallLimiter;
 use crate::components::{
-    extract_args_from_request, get_component_tools, handle_component_call, handle_list_components,
-    handle_load_component, handle_unload_component,
+    call_component, extract_args_from_request, get_component_tools, handle_deactivate_component,
+    handle_get_changes, handle_get_component_docs, handle_get_load_status,
+    handle_get_permission_denials, handle_install_component, handle_list_component_stats,
+    handle_list_components, handle_load_component, handle_set_component_priority,
+    handle_set_component_visibility, handle_unload_component, invoke_batch, ToolCache,
 };
-
-/// The list of components that Wassette knows about
-const COMPONENT_LIST: &str = include_str!("../../../component-registry.json");
+use crate::logging::StderrLog;
+use crate::progress::{self, ProgressTarget};
+use crate::redaction::Redactor;
+use crate::server::ExtraTool;
+use crate::server_info::{handle_get_server_info, server_info_output_schema, ServerDetails};
 
 /// Handles a request to list available tools.
+///
+/// When `read_only` is set, built-in tools that change the server's state are
+/// left out of the list.
 #[instrument(skip(lifecycle_manager))]
 pub async fn handle_tools_list(
     lifecycle_manager: &LifecycleManager,
     disable_builtin_tools: bool,
+    read_only: bool,
 ) -> Result<Value> {
+    let response = list_tools(
+        lifecycle_manager,
+        &ToolCache::default(),
+        &[],
+        disable_builtin_tools,
+        read_only,
+    )
+    .await?;
+    Ok(serde_json::to_value(response)?)
+}
+
+/// [`handle_tools_list`], with an embedder's extra tools listed next to the
+/// built-in tools and subject to the same settings. Component tools are
+/// taken from `tool_cache` when their schemas have not changed.
+pub(crate) async fn list_tools(
+    lifecycle_manager: &LifecycleManager,
+    tool_cache: &ToolCache,
+    extra_tools: &[ExtraTool],
+    disable_builtin_tools: bool,
+    read_only: bool,
+) -> Result<ListToolsResult> {
     debug!("Handling tools list request");
 
-    let mut tools = get_component_tools(lifecycle_manager).await?;
+    let mut tools = get_component_tools(lifecycle_manager, tool_cache).await?;
     if !disable_builtin_tools {
-        tools.extend(get_builtin_tools());
+        tools.extend(
+            get_builtin_tools()
+                .into_iter()
+                .filter(|tool| !read_only || !is_mutating_builtin_tool(&tool.name)),
+        );
+        tools.extend(
+            extra_tools
+                .iter()
+                .filter(|extra| !read_only || !extra.is_mutating())
+                .map(|extra| extra.tool().clone()),
+        );
     }
     debug!(num_tools = %tools.len(), "Retrieved tools");
 
-    let response = rmcp::model::ListToolsResult {
+    Ok(ListToolsResult {
         tools,
         next_cursor: None,
-    };
-
-    Ok(serde_json::to_value(response)?)
+    })
 }
 
 /// Check if a tool name is a builtin tool
-fn is_builtin_tool(name: &str) -> bool {
+pub(crate) fn is_builtin_tool(name: &str) -> bool {
     matches!(
         name,
         "load-component"
             | "unload-component"
+            | "deactivate-component"
             | "list-components"
+            | "get-component-docs"
+            | "get-load-status"
+            | "get-permission-denials"
+            | "list-component-stats"
+            | "get-changes"
+            | "set-component-priority"
+            | "set-component-visibility"
             | "get-policy"
+            | "list-secret-keys"
+            | "check-network-access"
+            | "invoke-batch"
             | "grant-storage-permission"
             | "grant-network-permission"
             | "grant-environment-variable-permission"
+            | "grant-cpu-permission"
             | "revoke-storage-permission"
             | "revoke-network-permission"
             | "revoke-environment-variable-permission"
+            | "grant-config-value"
+            | "revoke-config-value"
             | "search-components"
+            | "install-component"
             | "reset-permission"
+            | "get-server-info"
     )
 }
 
-/// Sanitize tool arguments for logging by limiting string length and removing sensitive data
-fn sanitize_args_for_logging(args: &Option<serde_json::Map<String, Value>>) -> String {
+/// Check if a builtin tool loads or unloads components or changes permissions
+fn is_mutating_builtin_tool(name: &str) -> bool {
+    matches!(
+        name,
+        "load-component"
+            | "unload-component"
+            | "deactivate-component"
+            | "install-component"
+            | "set-component-priority"
+            | "set-component-visibility"
+            | "grant-storage-permission"
+            | "grant-network-permission"
+            | "grant-environment-variable-permission"
+            | "grant-cpu-permission"
+            | "revoke-storage-permission"
+            | "revoke-network-permission"
+            | "revoke-environment-variable-permission"
+            | "grant-config-value"
+            | "revoke-config-value"
+            | "reset-permission"
+    )
+}
+
+/// Check if a builtin tool may run without a call slot: the tools that only
+/// read, and unloading or deactivating a component, so a runaway component
+/// can still be stopped while component calls fill every slot
+pub(crate) fn is_slot_exempt_builtin_tool(name: &str) -> bool {
+    is_builtin_tool(name)
+        && (!is_mutating_builtin_tool(name)
+            || matches!(name, "unload-component" | "deactivate-component"))
+}
+
+/// Sanitize tool arguments for logging by limiting string length and removing
+/// the values `redactor` selects
+fn sanitize_args_for_logging(
+    args: &Option<serde_json::Map<String, Value>>,
+    redactor: &Redactor,
+) -> String {
     const MAX_ARG_LENGTH: usize = 200;
     const MAX_TOTAL_LENGTH: usize = 1000;
 
@@ -72,23 +163,13 @@ fn sanitize_args_for_logging(args: &Option<serde_json::Map<String, Value>>) -> S
             let mut sanitized = serde_json::Map::new();
             let mut total_length = 0;
 
-            for (key, value) in map {
-                // Skip potentially sensitive keys
-                if key.to_lowercase().contains("password")
-                    || key.to_lowercase().contains("secret")
-                    || key.to_lowercase().contains("token")
-                    || key.to_lowercase().contains("key")
-                {
-                    sanitized.insert(key.clone(), json!("<redacted>"));
-                    continue;
-                }
-
+            for (key, value) in redactor.redact_arguments(map) {
                 // Truncate long string values
                 let sanitized_value = match value {
                     Value::String(s) if s.len() > MAX_ARG_LENGTH => {
                         json!(format!("{}... ({} chars)", &s[..MAX_ARG_LENGTH], s.len()))
                     }
-                    _ => value.clone(),
+                    _ => value,
                 };
 
                 // Check if adding this key-value pair would exceed the total length before insertion
@@ -98,8 +179,8 @@ fn sanitize_args_for_logging(args: &Option<serde_json::Map<String, Value>>) -> S
                     break;
                 }
 
-                sanitized.insert(key.clone(), sanitized_value);
                 total_length += key.len() + 20;
+                sanitized.insert(key, sanitized_value);
             }
 
             serde_json::to_string(&sanitized).unwrap_or_else(|_| "{}".to_string())
@@ -108,16 +189,111 @@ fn sanitize_args_for_logging(args: &Option<serde_json::Map<String, Value>>) -> S
 }
 
 /// Handles a tool call request.
-#[instrument(skip_all, fields(method_name = %req.name))]
+///
+/// When `read_only` is set, calls to built-in tools that change the server's
+/// state are rejected even though they are not listed. A failed call is
+/// returned as a result with `isError` set, except for arguments that exceed
+/// the manager's [`ParameterLimits`](wassette::ParameterLimits), which fail
+/// with [`ParameterLimitExceeded`].
 pub async fn handle_tools_call(
     req: CallToolRequestParam,
     lifecycle_manager: &LifecycleManager,
-    server_peer: Peer<RoleServer>,
     disable_builtin_tools: bool,
+    read_only: bool,
+) -> Result<Value> {
+    let details = ServerDetails::default();
+    let cancellation = CancellationToken::new();
+    call_tool(
+        req,
+        CallToolContext {
+            disable_builtin_tools,
+            read_only,
+            ..CallToolContext::new(lifecycle_manager, &details, &cancellation)
+        },
+    )
+    .await
+}
+
+/// What [`call_tool`] needs besides the request: the server's settings and
+/// the ways back to the calling client.
+pub(crate) struct CallToolContext<'a> {
+    pub(crate) lifecycle_manager: &'a LifecycleManager,
+    /// An embedder's extra tools. They are treated like built-in tools: they
+    /// take precedence over component tools of the same name and are
+    /// rejected when built-in tools are disabled or, unless marked
+    /// read-only, when the server is read-only.
+    pub(crate) extra_tools: &'a [ExtraTool],
+    pub(crate) disable_builtin_tools: bool,
+    /// Reject calls to built-in tools that change the server's state.
+    pub(crate) read_only: bool,
+    /// Add the call's timing to the `_meta` of component tool results.
+    pub(crate) call_timing: bool,
+    /// Selects the arguments left out of the call's log lines and error
+    /// message.
+    pub(crate) redactor: &'a Redactor,
+    /// What `get-server-info` reports about the server.
+    pub(crate) details: &'a ServerDetails,
+    /// Triggered when the client cancels the request.
+    pub(crate) cancellation: &'a CancellationToken,
+    /// Where what components write to stderr during the call is sent as log
+    /// messages.
+    pub(crate) stderr_log: Option<StderrLog<'a>>,
+    /// Where the progress of loading a component is sent.
+    pub(crate) progress: Option<ProgressTarget<'a>>,
+    /// Slots each call of an `invoke-batch` takes while it runs.
+    pub(crate) call_limiter: Option<&'a CallLimiter>,
+}
+
+impl<'a> CallToolContext<'a> {
+    /// A context with built-in tools enabled and nothing else set, using the
+    /// default redaction rules.
+    pub(crate) fn new(
+        lifecycle_manager: &'a LifecycleManager,
+        details: &'a ServerDetails,
+        cancellation: &'a CancellationToken,
+    ) -> Self {
+        Self {
+            lifecycle_manager,
+            extra_tools: &[],
+            disable_builtin_tools: false,
+            read_only: false,
+            call_timing: false,
+            redactor: Redactor::default_rules(),
+            details,
+            cancellation,
+            stderr_log: None,
+            progress: None,
+            call_limiter: None,
+        }
+    }
+}
+
+/// [`handle_tools_call`], with the settings and client of `ctx`.
+#[instrument(skip_all, fields(method_name = %req.name))]
+pub(crate) async fn call_tool(
+    req: CallToolRequestParam,
+    ctx: CallToolContext<'_>,
 ) -> Result<Value> {
+    let CallToolContext {
+        lifecycle_manager,
+        extra_tools,
+        disable_builtin_tools,
+        read_only,
+        call_timing,
+        redactor,
+        details,
+        cancellation,
+        stderr_log,
+        progress,
+        call_limiter,
+    } = ctx;
     let start_time = Instant::now();
     let tool_name = req.name.to_string();
-    let sanitized_args = sanitize_args_for_logging(&req.arguments);
+    let sanitized_args = sanitize_args_for_logging(&req.arguments, redactor);
+    let arguments = req.arguments.clone();
+    let extra_tool = extra_tools
+        .iter()
+        .find(|extra| extra.tool().name == req.name);
 
     debug!(
         tool_name = %tool_name,
@@ -125,28 +301,84 @@ pub async fn handle_tools_call(
         "Tool invocation started"
     );
 
-    let result = if disable_builtin_tools && is_builtin_tool(req.name.as_ref()) {
+    // Only loading a component reports progress
+    let load_progress = progress
+        .filter(|_| matches!(req.name.as_ref(), "load-component" | "install-component"))
+        .map(|target| target.forward());
+    let progress_reporter = load_progress
+        .as_ref()
+        .map(|(reporter, _)| reporter as &dyn ProgressReporter);
+
+    let is_builtin = is_builtin_tool(req.name.as_ref()) || extra_tool.is_some();
+    let is_mutating = is_mutating_builtin_tool(req.name.as_ref())
+        || extra_tool.is_some_and(ExtraTool::is_mutating);
+    let result = if disable_builtin_tools && is_builtin {
         // When builtin tools are disabled, reject calls to builtin tools
         warn!(
             tool_name = %tool_name,
             "Tool invocation rejected: built-in tools are disabled"
         );
         Err(anyhow::anyhow!("Built-in tools are disabled"))
+    } else if read_only && is_mutating {
+        warn!(
+            tool_name = %tool_name,
+            "Tool invocation rejected: server is read-only"
+        );
+        Err(anyhow::anyhow!(
+            "Server is read-only: {} is not available",
+            tool_name
+        ))
+    } else if let Some(extra_tool) = extra_tool {
+        extra_tool.call(req, lifecycle_manager.clone()).await
     } else {
         // Handle builtin tools (if enabled) or component calls
         match req.name.as_ref() {
             "load-component" if !disable_builtin_tools => {
-                handle_load_component(&req, lifecycle_manager, server_peer).await
+                handle_load_component(&req, lifecycle_manager, cancellation, progress_reporter)
+                    .await
             }
             "unload-component" if !disable_builtin_tools => {
-                handle_unload_component(&req, lifecycle_manager, server_peer).await
+                handle_unload_component(&req, lifecycle_manager).await
+            }
+            "deactivate-component" if !disable_builtin_tools => {
+                handle_deactivate_component(&req, lifecycle_manager).await
             }
             "list-components" if !disable_builtin_tools => {
-                handle_list_components(lifecycle_manager).await
+                handle_list_components(&req, lifecycle_manager).await
+            }
+            "get-component-docs" if !disable_builtin_tools => {
+                handle_get_component_docs(&req, lifecycle_manager).await
+            }
+            "get-load-status" if !disable_builtin_tools => {
+                handle_get_load_status(&req, lifecycle_manager).await
+            }
+            "get-permission-denials" if !disable_builtin_tools => {
+                handle_get_permission_denials(&req, lifecycle_manager).await
+            }
+            "list-component-stats" if !disable_builtin_tools => {
+                handle_list_component_stats(&req, lifecycle_manager).await
+            }
+            "get-changes" if !disable_builtin_tools => {
+                handle_get_changes(&req, lifecycle_manager).await
+            }
+            "set-component-priority" if !disable_builtin_tools => {
+                handle_set_component_priority(&req, lifecycle_manager).await
+            }
+            "set-component-visibility" if !disable_builtin_tools => {
+                handle_set_component_visibility(&req, lifecycle_manager).await
             }
             "get-policy" if !disable_builtin_tools => {
                 handle_get_policy(&req, lifecycle_manager).await
             }
+            "list-secret-keys" if !disable_builtin_tools => {
+                handle_list_secret_keys(&req, lifecycle_manager).await
+            }
+            "check-network-access" if !disable_builtin_tools => {
+                handle_check_network_access(&req, lifecycle_manager).await
+            }
+            "invoke-batch" if !disable_builtin_tools => {
+                invoke_batch(&req, lifecycle_manager, cancellation, call_limiter).await
+            }
             "grant-storage-permission" if !disable_builtin_tools => {
                 handle_grant_storage_permission(&req, lifecycle_manager).await
             }
@@ -156,6 +388,9 @@ pub async fn handle_tools_call(
             "grant-environment-variable-permission" if !disable_builtin_tools => {
                 handle_grant_environment_variable_permission(&req, lifecycle_manager).await
             }
+            "grant-cpu-permission" if !disable_builtin_tools => {
+                handle_grant_cpu_permission(&req, lifecycle_manager).await
+            }
             "revoke-storage-permission" if !disable_builtin_tools => {
                 handle_revoke_storage_permission(&req, lifecycle_manager).await
             }
@@ -165,15 +400,31 @@ pub async fn handle_tools_call(
             "revoke-environment-variable-permission" if !disable_builtin_tools => {
                 handle_revoke_environment_variable_permission(&req, lifecycle_manager).await
             }
+            "grant-config-value" if !disable_builtin_tools => {
+                handle_grant_config_value(&req, lifecycle_manager).await
+            }
+            "revoke-config-value" if !disable_builtin_tools => {
+                handle_revoke_config_value(&req, lifecycle_manager).await
+            }
             "search-components" if !disable_builtin_tools => {
                 handle_search_component(&req, lifecycle_manager).await
             }
+            "install-component" if !disable_builtin_tools => {
+                handle_install_component(&req, lifecycle_manager, cancellation, progress_reporter)
+                    .await
+            }
             "reset-permission" if !disable_builtin_tools => {
                 handle_reset_permission(&req, lifecycle_manager).await
             }
-            _ => handle_component_call(&req, lifecycle_manager).await,
+            "get-server-info" if !disable_builtin_tools => {
+                handle_get_server_info(lifecycle_manager, details).await
+            }
+            _ => call_component(&req, lifecycle_manager, call_timing, stderr_log).await,
         }
     };
+    if let Some((reporter, task)) = load_progress {
+        progress::finish(reporter, task).await;
+    }
 
     let duration = start_time.elapsed();
 
@@ -191,7 +442,7 @@ pub async fn handle_tools_call(
                 tool_name = %tool_name,
                 duration_ms = %duration.as_millis(),
                 outcome = "error",
-                error = %e,
+                error = %redactor.redact_text(arguments.as_ref(), &e.to_string()),
                 "Tool invocation failed"
             );
         }
@@ -199,8 +450,10 @@ pub async fn handle_tools_call(
 
     match result {
         Ok(result) => Ok(serde_json::to_value(result)?),
+        // Rejected arguments are a protocol error rather than a failed call
+        Err(e) if e.is::<ParameterLimitExceeded>() => Err(e),
         Err(e) => {
-            let error_text = format!("Error: {e}");
+            let error_text = redactor.redact_text(arguments.as_ref(), &format!("Error: {e}"));
             let contents = vec![Content::text(error_text)];
 
             let error_result = CallToolResult {
@@ -241,13 +494,17 @@ fn get_builtin_tools() -> Vec<Tool> {
         Tool {
             name: Cow::Borrowed("unload-component"),
             description: Some(Cow::Borrowed(
-                "Unloads a tool or component.",
+                "Unloads a tool or component. The component's on-unload hook, if it exports one, runs first; a failing hook is reported as a warning and does not stop the unload.",
             )),
             input_schema: Arc::new(
                 serde_json::from_value(json!({
                     "type": "object",
                     "properties": {
-                        "id": {"type": "string"}
+                        "id": {"type": "string"},
+                        "skip_hooks": {
+                            "type": "boolean",
+                            "description": "Unload without running the component's on-unload hook, e.g. when the hook itself misbehaves"
+                        }
                     },
                     "required": ["id"]
                 }))
@@ -259,6 +516,30 @@ fn get_builtin_tools() -> Vec<Tool> {
             icons: None,
             meta: None,
         },
+        Tool {
+            name: Cow::Borrowed("deactivate-component"),
+            description: Some(Cow::Borrowed(
+                "Evicts a component from memory to free it, keeping its files, policy, and tools. The component is compiled again when one of its tools is called.",
+            )),
+            input_schema: Arc::new(
+                serde_json::from_value(json!({
+                    "type": "object",
+                    "properties": {
+                        "component_id": {
+                            "type": "string",
+                            "description": "ID of the component to evict"
+                        }
+                    },
+                    "required": ["component_id"]
+                }))
+                .unwrap_or_default(),
+            ),
+            output_schema: None,
+            annotations: None,
+            title: None,
+            icons: None,
+            meta: None,
+        },
         Tool {
             name: Cow::Borrowed("list-components"),
             description: Some(Cow::Borrowed(
@@ -267,7 +548,13 @@ fn get_builtin_tools() -> Vec<Tool> {
             input_schema: Arc::new(
                 serde_json::from_value(json!({
                     "type": "object",
-                    "properties": {},
+                    "properties": {
+                        "sort": {
+                            "type": "string",
+                            "enum": ["id", "last-used"],
+                            "description": "Order components by ID or by most recent tool call"
+                        }
+                    },
                     "required": []
                 }))
                 .unwrap_or_default(),
@@ -278,10 +565,189 @@ fn get_builtin_tools() -> Vec<Tool> {
             icons: None,
             meta: None,
         },
+        Tool {
+            name: Cow::Borrowed("get-component-docs"),
+            description: Some(Cow::Borrowed(
+                "Gets a component's own usage documentation as Markdown, taken from the docs in its WIT package",
+            )),
+            input_schema: Arc::new(
+                serde_json::from_value(json!({
+                    "type": "object",
+                    "properties": {
+                        "component_id": {
+                            "type": "string",
+                            "description": "ID of the component to get docs for"
+                        }
+                    },
+                    "required": ["component_id"]
+                }))
+                .unwrap_or_default(),
+            ),
+            output_schema: None,
+            annotations: None,
+            title: None,
+            icons: None,
+            meta: None,
+        },
+        Tool {
+            name: Cow::Borrowed("get-load-status"),
+            description: Some(Cow::Borrowed(
+                "Gets whether components are loaded, and why a component that is known but not loaded failed to load",
+            )),
+            input_schema: Arc::new(
+                serde_json::from_value(json!({
+                    "type": "object",
+                    "properties": {
+                        "component_id": {
+                            "type": "string",
+                            "description": "ID of a single component to get the load status of; all known components if omitted"
+                        }
+                    }
+                }))
+                .unwrap_or_default(),
+            ),
+            output_schema: None,
+            annotations: None,
+            title: None,
+            icons: None,
+            meta: None,
+        },
+        Tool {
+            name: Cow::Borrowed("get-permission-denials"),
+            description: Some(Cow::Borrowed(
+                "Gets how often a component was denied a permission since it was loaded, by permission type, and what its most recent denials were. Useful to find out which permission a failing component is missing.",
+            )),
+            input_schema: Arc::new(
+                serde_json::from_value(json!({
+                    "type": "object",
+                    "properties": {
+                        "component_id": {
+                            "type": "string",
+                            "description": "ID of the component to get the permission denials of"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Maximum number of recent denials to return, newest first; defaults to all that are kept"
+                        }
+                    },
+                    "required": ["component_id"]
+                }))
+                .unwrap_or_default(),
+            ),
+            output_schema: None,
+            annotations: None,
+            title: None,
+            icons: None,
+            meta: None,
+        },
+        Tool {
+            name: Cow::Borrowed("list-component-stats"),
+            description: Some(Cow::Borrowed(
+                "Lists how often the tools of each component were called, how many of the calls failed, their median and 95th percentile latency, and how many permissions the component was denied. Useful to find slow or failing tools.",
+            )),
+            input_schema: Arc::new(
+                serde_json::from_value(json!({
+                    "type": "object",
+                    "properties": {
+                        "component_id": {
+                            "type": "string",
+                            "description": "ID of a single component to get the stats of; defaults to every component that has been called"
+                        }
+                    }
+                }))
+                .unwrap_or_default(),
+            ),
+            output_schema: None,
+            annotations: None,
+            title: None,
+            icons: None,
+            meta: None,
+        },
+        Tool {
+            name: Cow::Borrowed("get-changes"),
+            description: Some(Cow::Borrowed(
+                "Gets the changes to the component registry numbered after a sequence number, oldest first: components loaded, replaced, unloaded, disabled, hidden, or shown, and policy and permission changes. Pass the last_seq of the previous response to catch up.",
+            )),
+            input_schema: Arc::new(
+                serde_json::from_value(json!({
+                    "type": "object",
+                    "properties": {
+                        "since": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Return changes with a higher sequence number than this; defaults to 0, all changes that are kept"
+                        }
+                    }
+                }))
+                .unwrap_or_default(),
+            ),
+            output_schema: None,
+            annotations: None,
+            title: None,
+            icons: None,
+            meta: None,
+        },
+        Tool {
+            name: Cow::Borrowed("set-component-priority"),
+            description: Some(Cow::Borrowed(
+                "Sets the priority with which a component is compiled when the server loads its components in the background. Higher priorities are compiled first.",
+            )),
+            input_schema: Arc::new(
+                serde_json::from_value(json!({
+                    "type": "object",
+                    "properties": {
+                        "component_id": {
+                            "type": "string",
+                            "description": "ID of the component to set the priority of"
+                        },
+                        "priority": {
+                            "type": "integer",
+                            "description": "Loading priority; omit to clear the priority"
+                        }
+                    },
+                    "required": ["component_id"]
+                }))
+                .unwrap_or_default(),
+            ),
+            output_schema: None,
+            annotations: None,
+            title: None,
+            icons: None,
+            meta: None,
+        },
+        Tool {
+            name: Cow::Borrowed("set-component-visibility"),
+            description: Some(Cow::Borrowed(
+                "Hides a component's tools from the tool list, or shows them again. Hidden tools can still be called by name.",
+            )),
+            input_schema: Arc::new(
+                serde_json::from_value(json!({
+                    "type": "object",
+                    "properties": {
+                        "component_id": {
+                            "type": "string",
+                            "description": "ID of the component to hide or show"
+                        },
+                        "hidden": {
+                            "type": "boolean",
+                            "description": "Whether to hide the component's tools from the tool list"
+                        }
+                    },
+                    "required": ["component_id", "hidden"]
+                }))
+                .unwrap_or_default(),
+            ),
+            output_schema: None,
+            annotations: None,
+            title: None,
+            icons: None,
+            meta: None,
+        },
         Tool {
             name: Cow::Borrowed("get-policy"),
             description: Some(Cow::Borrowed(
-                "Gets the policy information for a specific component",
+                "Gets the policy information for a specific component, including the scratch directory it is given when its policy enables one",
             )),
             input_schema: Arc::new(
                 serde_json::from_value(json!({
@@ -302,10 +768,112 @@ fn get_builtin_tools() -> Vec<Tool> {
             icons: None,
             meta: None,
         },
+        Tool {
+            name: Cow::Borrowed("list-secret-keys"),
+            description: Some(Cow::Borrowed(
+                "Lists the keys of the secrets set for a component, without their values, so missing configuration can be spotted before calling its tools",
+            )),
+            input_schema: Arc::new(
+                serde_json::from_value(json!({
+                    "type": "object",
+                    "properties": {
+                        "component_id": {
+                            "type": "string",
+                            "description": "ID of the component to list secret keys for"
+                        }
+                    },
+                    "required": ["component_id"]
+                }))
+                .unwrap_or_default(),
+            ),
+            output_schema: None,
+            annotations: None,
+            title: None,
+            icons: None,
+            meta: None,
+        },
+        Tool {
+            name: Cow::Borrowed("check-network-access"),
+            description: Some(Cow::Borrowed(
+                "Checks whether a component's network policy would allow an HTTP request to a URL, without making the request. Returns the policy rule that matched, the denied network the URL's IP address is in, or the command that would grant access."
+            )),
+            input_schema: Arc::new(
+                serde_json::from_value(json!({
+                    "type": "object",
+                    "properties": {
+                        "component_id": {
+                            "type": "string",
+                            "description": "ID of the component to check"
+                        },
+                        "url": {
+                            "type": "string",
+                            "description": "URL the component would request, e.g. https://api.example.com/v1"
+                        },
+                        "method": {
+                            "type": "string",
+                            "description": "HTTP method of the request, e.g. HEAD or OPTIONS for a preflight. Defaults to GET"
+                        }
+                    },
+                    "required": ["component_id", "url"]
+                }))
+                .unwrap_or_default(),
+            ),
+            output_schema: None,
+            annotations: None,
+            title: None,
+            icons: None,
+            meta: None,
+        },
+        Tool {
+            name: Cow::Borrowed("invoke-batch"),
+            description: Some(Cow::Borrowed(
+                "Calls one component tool once for each of a list of argument objects, running a few calls at a time, and returns each call's result or error in input order. A failing call does not stop the others."
+            )),
+            input_schema: Arc::new(
+                serde_json::from_value(json!({
+                    "type": "object",
+                    "properties": {
+                        "component_id": {
+                            "type": "string",
+                            "description": "ID of the component the tool belongs to. Optional; when given, the call fails if the tool belongs to another component"
+                        },
+                        "tool": {
+                            "type": "string",
+                            "description": "Name of the component tool to call"
+                        },
+                        "arguments": {
+                            "type": "array",
+                            "items": { "type": "object" },
+                            "minItems": 1,
+                            "maxItems": 100,
+                            "description": "One argument object per call"
+                        },
+                        "concurrency": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "maximum": 16,
+                            "description": "Number of calls to run at once. Defaults to 4"
+                        },
+                        "timeout_ms": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Time limit for the whole batch in milliseconds. Calls still running then are reported as failed"
+                        }
+                    },
+                    "required": ["tool", "arguments"]
+                }))
+                .unwrap_or_default(),
+            ),
+            output_schema: None,
+            annotations: None,
+            title: None,
+            icons: None,
+            meta: None,
+        },
         Tool {
             name: Cow::Borrowed("grant-storage-permission"),
             description: Some(Cow::Borrowed(
-                "Grants storage access permission to a component, allowing it to read from and/or write to specific storage locations."
+                "Grants storage access permission to a component, allowing it to read from and/or write to specific storage locations. The granted path must exist unless 'createIfMissing' is set, in which case it is created as a directory only the server's user can access."
             )),
             input_schema: Arc::new(
                 serde_json::from_value(json!({
@@ -329,6 +897,10 @@ fn get_builtin_tools() -> Vec<Tool> {
                               "enum": ["read", "write"]
                             },
                             "description": "Access type for the storage resource, this must be an array of strings with values 'read' or 'write'"
+                          },
+                          "createIfMissing": {
+                            "type": "boolean",
+                            "description": "Create the directory if the path does not exist, instead of failing; requires write access"
                           }
                         },
                         "required": ["uri", "access"],
@@ -348,7 +920,7 @@ fn get_builtin_tools() -> Vec<Tool> {
         Tool {
             name: Cow::Borrowed("grant-network-permission"),
             description: Some(Cow::Borrowed(
-                "Grants network access permission to a component, allowing it to make network requests to specific hosts."
+                "Grants network access permission to a component, allowing it to make network requests to specific hosts. Set 'port' and 'scheme' to only allow requests to that port or over that scheme. Pass 'hosts' instead of 'host' to grant several hosts in one policy update; the result lists each host as granted or already_granted."
             )),
             input_schema: Arc::new(
                 serde_json::from_value(json!({
@@ -363,10 +935,26 @@ fn get_builtin_tools() -> Vec<Tool> {
                         "properties": {
                           "host": { 
                             "type": "string",
-                            "description": "Host to grant network access to"
+                            "description": "Host to grant network access to, or a wildcard such as *.example.com for its subdomains"
+                          },
+                          "port": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "maximum": 65535,
+                            "description": "Only allow requests to this port; any port when omitted"
+                          },
+                          "scheme": {
+                            "type": "string",
+                            "enum": ["http", "https"],
+                            "description": "Only allow requests over this scheme; any scheme when omitted"
+                          },
+                          "hosts": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Hosts to grant network access to, applied together; use instead of 'host'"
                           }
                         },
-                        "required": ["host"],
+                        "minProperties": 1,
                         "additionalProperties": false
                       }
                     },
@@ -383,7 +971,7 @@ fn get_builtin_tools() -> Vec<Tool> {
         Tool {
             name: Cow::Borrowed("grant-environment-variable-permission"),
             description: Some(Cow::Borrowed(
-                "Grants environment variable access permission to a component, allowing it to access specific environment variables."
+                "Grants environment variable access permission to a component, allowing it to access specific environment variables. Setting inherit_all passes every configured environment variable through and is rejected unless the server was started with --allow-env-inherit."
             )),
             input_schema: Arc::new(
                 serde_json::from_value(json!({
@@ -399,9 +987,48 @@ fn get_builtin_tools() -> Vec<Tool> {
                           "key": { 
                             "type": "string",
                             "description": "Environment variable key to grant access to"
+                          },
+                          "inherit_all": {
+                            "type": "boolean",
+                            "description": "Grant access to every configured environment variable instead of a single key"
                           }
                         },
-                        "required": ["key"],
+                        "anyOf": [{ "required": ["key"] }, { "required": ["inherit_all"] }],
+                        "additionalProperties": false
+                      }
+                    },
+                    "required": ["component_id", "details"]
+                  }))
+                .unwrap_or_default(),
+            ),
+            output_schema: None,
+            annotations: None,
+            title: None,
+            icons: None,
+            meta: None,
+        },
+        Tool {
+            name: Cow::Borrowed("grant-cpu-permission"),
+            description: Some(Cow::Borrowed(
+                "Grants a CPU limit to a component. Each call may consume fuel in proportion to the limit, and a call that runs out of fuel is stopped."
+            )),
+            input_schema: Arc::new(
+                serde_json::from_value(json!({
+                    "type": "object",
+                    "properties": {
+                      "component_id": {
+                        "type": "string",
+                        "description": "ID of the component to grant the CPU limit to"
+                      },
+                      "details": {
+                        "type": "object",
+                        "properties": {
+                          "cpu": {
+                            "type": "string",
+                            "description": "CPU limit in cores or millicores (e.g., 1, 500m)"
+                          }
+                        },
+                        "required": ["cpu"],
                         "additionalProperties": false
                       }
                     },
@@ -453,7 +1080,7 @@ fn get_builtin_tools() -> Vec<Tool> {
         Tool {
             name: Cow::Borrowed("revoke-network-permission"),
             description: Some(Cow::Borrowed(
-                "Revokes network access permission from a component, removing its ability to make network requests to specific hosts."
+                "Revokes network access permission from a component, removing its ability to make network requests to specific hosts. Only the rule with the same host, port, and scheme is removed."
             )),
             input_schema: Arc::new(
                 serde_json::from_value(json!({
@@ -469,6 +1096,17 @@ fn get_builtin_tools() -> Vec<Tool> {
                           "host": { 
                             "type": "string",
                             "description": "Host to revoke network access from"
+                          },
+                          "port": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "maximum": 65535,
+                            "description": "Port of the rule to revoke; omit to revoke the rule granted without a port"
+                          },
+                          "scheme": {
+                            "type": "string",
+                            "enum": ["http", "https"],
+                            "description": "Scheme of the rule to revoke; omit to revoke the rule granted without a scheme"
                           }
                         },
                         "required": ["host"],
@@ -504,14 +1142,140 @@ fn get_builtin_tools() -> Vec<Tool> {
                           "key": { 
                             "type": "string",
                             "description": "Environment variable key to revoke access from"
+                          },
+                          "inherit_all": {
+                            "type": "boolean",
+                            "description": "Stop passing every configured environment variable through"
+                          }
+                        },
+                        "anyOf": [{ "required": ["key"] }, { "required": ["inherit_all"] }],
+                        "additionalProperties": false
+                      }
+                    },
+                    "required": ["component_id", "details"]
+                  }))
+                .unwrap_or_default(),
+            ),
+            output_schema: None,
+            annotations: None,
+            title: None,
+            icons: None,
+            meta: None,
+        },
+        Tool {
+            name: Cow::Borrowed("grant-config-value"),
+            description: Some(Cow::Borrowed(
+                "Sets a configuration value, such as a feature flag, that is passed to a component through wasi-config. The value is stored in the component's policy and takes precedence over an environment variable of the same name."
+            )),
+            input_schema: Arc::new(
+                serde_json::from_value(json!({
+                    "type": "object",
+                    "properties": {
+                      "component_id": {
+                        "type": "string",
+                        "description": "ID of the component to set the configuration value for"
+                      },
+                      "details": {
+                        "type": "object",
+                        "properties": {
+                          "key": {
+                            "type": "string",
+                            "description": "Configuration key, e.g. ENABLE_BETA_PARSER"
+                          },
+                          "value": {
+                            "type": "string",
+                            "description": "Value passed to the component"
+                          }
+                        },
+                        "required": ["key", "value"],
+                        "additionalProperties": false
+                      }
+                    },
+                    "required": ["component_id", "details"]
+                  }))
+                .unwrap_or_default(),
+            ),
+            output_schema: None,
+            annotations: None,
+            title: None,
+            icons: None,
+            meta: None,
+        },
+        Tool {
+            name: Cow::Borrowed("revoke-config-value"),
+            description: Some(Cow::Borrowed(
+                "Removes a configuration value from a component's policy, so it is no longer passed to the component through wasi-config."
+            )),
+            input_schema: Arc::new(
+                serde_json::from_value(json!({
+                    "type": "object",
+                    "properties": {
+                      "component_id": {
+                        "type": "string",
+                        "description": "ID of the component to remove the configuration value from"
+                      },
+                      "details": {
+                        "type": "object",
+                        "properties": {
+                          "key": {
+                            "type": "string",
+                            "description": "Configuration key to remove"
                           }
                         },
                         "required": ["key"],
                         "additionalProperties": false
                       }
                     },
-                    "required": ["component_id", "details"]
-                  }))
+                    "required": ["component_id", "details"]
+                  }))
+                .unwrap_or_default(),
+            ),
+            output_schema: None,
+            annotations: None,
+            title: None,
+            icons: None,
+            meta: None,
+        },
+        Tool {
+            name: Cow::Borrowed("reset-permission"),
+            description: Some(Cow::Borrowed(
+                "Resets all permissions for a component, removing all granted permissions and returning it to the default state."
+            )),
+            input_schema: Arc::new(
+                serde_json::from_value(json!({
+                    "type": "object",
+                    "properties": {
+                      "component_id": {
+                        "type": "string",
+                        "description": "ID of the component to reset permissions for"
+                      }
+                    },
+                    "required": ["component_id"]
+                  }))
+                .unwrap_or_default(),
+            ),
+            output_schema: None,
+            annotations: None,
+            title: None,
+            icons: None,
+            meta: None,
+        },
+        Tool {
+            name: Cow::Borrowed("search-components"),
+            description: Some(Cow::Borrowed(
+                "Lists all known components that can be fetched and loaded. Optionally filter by a search query.",
+            )),
+            input_schema: Arc::new(
+                serde_json::from_value(json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {
+                            "type": "string",
+                            "description": "Optional search query to filter components by name, description, or URI"
+                        }
+                    },
+                    "required": []
+                }))
                 .unwrap_or_default(),
             ),
             output_schema: None,
@@ -521,21 +1285,21 @@ fn get_builtin_tools() -> Vec<Tool> {
             meta: None,
         },
         Tool {
-            name: Cow::Borrowed("reset-permission"),
+            name: Cow::Borrowed("install-component"),
             description: Some(Cow::Borrowed(
-                "Resets all permissions for a component, removing all granted permissions and returning it to the default state."
+                "Loads a component by its registry name (as listed by search-components). The name is matched loosely; if several registry entries match, the candidates are returned instead of loading one.",
             )),
             input_schema: Arc::new(
                 serde_json::from_value(json!({
                     "type": "object",
                     "properties": {
-                      "component_id": {
-                        "type": "string",
-                        "description": "ID of the component to reset permissions for"
-                      }
+                        "name": {
+                            "type": "string",
+                            "description": "Name of the registry entry to install, e.g. 'Time Server' or 'fetch'"
+                        }
                     },
-                    "required": ["component_id"]
-                  }))
+                    "required": ["name"]
+                }))
                 .unwrap_or_default(),
             ),
             output_schema: None,
@@ -545,24 +1309,21 @@ fn get_builtin_tools() -> Vec<Tool> {
             meta: None,
         },
         Tool {
-            name: Cow::Borrowed("search-components"),
+            name: Cow::Borrowed("get-server-info"),
             description: Some(Cow::Borrowed(
-                "Lists all known components that can be fetched and loaded. Optionally filter by a search query.",
+                "Reports the server's version, uptime, how many components are loaded out of those known, the number of registered tools, whether background loading has completed, and the transports the server is served over",
             )),
             input_schema: Arc::new(
                 serde_json::from_value(json!({
                     "type": "object",
-                    "properties": {
-                        "query": {
-                            "type": "string",
-                            "description": "Optional search query to filter components by name, description, or URI"
-                        }
-                    },
+                    "properties": {},
                     "required": []
                 }))
                 .unwrap_or_default(),
             ),
-            output_schema: None,
+            output_schema: Some(Arc::new(
+                serde_json::from_value(server_info_output_schema()).unwrap_or_default(),
+            )),
             annotations: None,
             title: None,
             icons: None,
@@ -571,93 +1332,20 @@ fn get_builtin_tools() -> Vec<Tool> {
     ]
 }
 
-/// Calculate a relevance score for a component based on query terms
-/// Higher scores indicate better matches
-fn calculate_relevance_score(component: &Value, query_terms: &[String]) -> u32 {
-    let name = component["name"].as_str().unwrap_or("").to_lowercase();
-    let description = component["description"]
-        .as_str()
-        .unwrap_or("")
-        .to_lowercase();
-    let uri = component["uri"].as_str().unwrap_or("").to_lowercase();
-
-    let mut score = 0u32;
-
-    for term in query_terms {
-        // Exact name match gets highest score
-        if name == term.as_str() {
-            score += 100;
-        } else if name.starts_with(term) {
-            score += 50;
-        } else if name.contains(term) {
-            score += 20;
-        }
-
-        // Description matches get medium score
-        if description.starts_with(term) {
-            score += 15;
-        } else if description.contains(term) {
-            score += 10;
-        }
-
-        // URI matches get lower score
-        if uri.contains(term) {
-            score += 5;
-        }
-    }
-
-    score
-}
-
-#[instrument(skip(_lifecycle_manager))]
+#[instrument(skip(lifecycle_manager))]
 pub(crate) async fn handle_search_component(
     req: &CallToolRequestParam,
-    _lifecycle_manager: &LifecycleManager,
+    lifecycle_manager: &LifecycleManager,
 ) -> Result<CallToolResult> {
     let args = extract_args_from_request(req)?;
 
     // Extract the optional query parameter
-    let query = args.get("query").and_then(|v| v.as_str());
-
-    // Parse the component list
-    let components_value: Value = serde_json::from_str(COMPONENT_LIST)?;
-    let all_components = components_value
-        .as_array()
-        .ok_or_else(|| anyhow::anyhow!("Component registry is not an array"))?;
-
-    // Filter and rank components based on query
-    let filtered_components: Vec<Value> = if let Some(q) = query {
-        // Split query into words for multi-term matching
-        let query_terms: Vec<String> = q
-            .split_whitespace()
-            .map(|term| term.to_lowercase())
-            .collect();
+    let query = args.get("query").and_then(|v| v.as_str()).unwrap_or("");
 
-        if query_terms.is_empty() {
-            all_components.to_vec()
-        } else {
-            // Calculate relevance scores and filter out non-matches
-            let mut scored_components: Vec<(u32, &Value)> = all_components
-                .iter()
-                .map(|component| {
-                    let score = calculate_relevance_score(component, &query_terms);
-                    (score, component)
-                })
-                .filter(|(score, _)| *score > 0)
-                .collect();
-
-            // Sort by relevance score (descending)
-            scored_components.sort_by(|a, b| b.0.cmp(&a.0));
-
-            // Extract components in ranked order
-            scored_components
-                .into_iter()
-                .map(|(_, component)| (*component).clone())
-                .collect()
-        }
-    } else {
-        all_components.to_vec()
-    };
+    // Leave out components the server is not allowed to load
+    let mut filtered_components = wassette::registry::search(query);
+    filtered_components
+        .retain(|entry| lifecycle_manager.check_component_source(&entry.uri).is_ok());
 
     let status_text = serde_json::to_string(&json!({
         "status": "Component list found",
@@ -685,6 +1373,7 @@ pub async fn handle_get_policy(
         .get("component_id")
         .and_then(|v| v.as_str())
         .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'component_id'"))?;
+    validate_component_id(component_id)?;
 
     info!("Getting policy for component {}", component_id);
 
@@ -697,17 +1386,47 @@ pub async fn handle_get_policy(
     let policy_info = lifecycle_manager.get_policy_info(component_id).await;
 
     let status_text = if let Some(info) = policy_info {
-        serde_json::to_string(&json!({
+        let mut result = json!({
             "status": "policy found",
             "component_id": component_id,
             "policy_info": {
                 "policy_id": info.policy_id,
                 "source_uri": info.source_uri,
+                "origin": info.origin,
                 "local_path": info.local_path,
                 "created_at": info.created_at.duration_since(std::time::UNIX_EPOCH)
                     .unwrap_or_default().as_secs()
-            }
-        }))?
+            },
+            "permissions": info.permissions,
+            "permission_sources": info.permission_sources,
+        });
+        if !info.config.is_empty() {
+            result["config"] = json!(info.config);
+        }
+        if let Some(scratch) = &info.scratch {
+            result["scratch"] = json!(scratch);
+        }
+        if let Some(clock) = info
+            .permissions
+            .system
+            .as_ref()
+            .and_then(|system| system.clock.as_ref())
+        {
+            result["clock"] = json!({
+                "timezone": clock.timezone,
+                "fixed_time": clock.fixed_time,
+                "wall_clock": if clock.fixed_time.is_some() { "fixed" } else { "host" },
+            });
+        }
+        if info.inherit_all_environment {
+            // Make it obvious that the allow list does not bound this component
+            result["environment_access"] = if lifecycle_manager.allows_env_inherit() {
+                json!("inherits all configured environment variables")
+            } else {
+                json!("inheritAll is set but ignored; the server was not started with --allow-env-inherit")
+            };
+        }
+        serde_json::to_string(&result)?
     } else {
         serde_json::to_string(&json!({
             "status": "no policy found",
@@ -725,6 +1444,90 @@ pub async fn handle_get_policy(
     })
 }
 
+#[instrument(skip(lifecycle_manager))]
+pub async fn handle_list_secret_keys(
+    req: &CallToolRequestParam,
+    lifecycle_manager: &LifecycleManager,
+) -> Result<CallToolResult> {
+    let args = extract_args_from_request(req)?;
+
+    let component_id = args
+        .get("component_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'component_id'"))?;
+    validate_component_id(component_id)?;
+
+    info!("Listing secret keys for component {}", component_id);
+
+    if !lifecycle_manager
+        .list_components_known()
+        .await
+        .iter()
+        .any(|id| id == component_id)
+    {
+        return Err(anyhow::anyhow!("Component not found: {}", component_id));
+    }
+
+    // Never ask for the values; only the keys leave the server
+    let mut keys: Vec<String> = lifecycle_manager
+        .list_component_secrets(component_id, false)
+        .await?
+        .into_keys()
+        .collect();
+    keys.sort();
+
+    let result = json!({
+        "component_id": component_id,
+        "keys": keys
+            .iter()
+            .map(|key| json!({"key": key, "present": true}))
+            .collect::<Vec<_>>()
+    });
+
+    Ok(CallToolResult {
+        content: vec![Content::text(serde_json::to_string(&result)?)],
+        structured_content: None,
+        is_error: None,
+        meta: None,
+    })
+}
+
+#[instrument(skip(lifecycle_manager))]
+pub async fn handle_check_network_access(
+    req: &CallToolRequestParam,
+    lifecycle_manager: &LifecycleManager,
+) -> Result<CallToolResult> {
+    let args = extract_args_from_request(req)?;
+
+    let component_id = args
+        .get("component_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'component_id'"))?;
+    validate_component_id(component_id)?;
+
+    let url = args
+        .get("url")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'url'"))?;
+    let method = args.get("method").and_then(|v| v.as_str()).unwrap_or("GET");
+
+    lifecycle_manager
+        .ensure_component_loaded(component_id)
+        .await
+        .map_err(|e| anyhow::anyhow!("Component not found: {} ({})", component_id, e))?;
+
+    let check = lifecycle_manager
+        .check_network_access(component_id, method, url)
+        .await?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(serde_json::to_string(&check)?)],
+        structured_content: None,
+        is_error: None,
+        meta: None,
+    })
+}
+
 /// Generic helper for handling grant permission requests
 async fn handle_grant_permission_generic(
     req: &CallToolRequestParam,
@@ -738,6 +1541,7 @@ async fn handle_grant_permission_generic(
         .get("component_id")
         .and_then(|v| v.as_str())
         .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'component_id'"))?;
+    validate_component_id(component_id)?;
 
     let details = args
         .get("details")
@@ -804,7 +1608,56 @@ pub async fn handle_grant_network_permission(
     req: &CallToolRequestParam,
     lifecycle_manager: &LifecycleManager,
 ) -> Result<CallToolResult> {
-    handle_grant_permission_generic(req, lifecycle_manager, "network", "network").await
+    let args = extract_args_from_request(req)?;
+    let Some(hosts) = args.get("details").and_then(|details| details.get("hosts")) else {
+        return handle_grant_permission_generic(req, lifecycle_manager, "network", "network").await;
+    };
+
+    let component_id = args
+        .get("component_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'component_id'"))?;
+    validate_component_id(component_id)?;
+    let hosts: Vec<String> = serde_json::from_value(hosts.clone())
+        .map_err(|e| anyhow::anyhow!("'hosts' must be an array of strings: {}", e))?;
+
+    info!(
+        "Granting network permission for {} hosts to component {}",
+        hosts.len(),
+        component_id
+    );
+
+    // Ensure component is loaded (lazy compile)
+    lifecycle_manager
+        .ensure_component_loaded(component_id)
+        .await
+        .map_err(|e| anyhow::anyhow!("Component not found: {} ({})", component_id, e))?;
+
+    let results = lifecycle_manager
+        .grant_network_hosts(component_id, &hosts)
+        .await
+        .map_err(|e| {
+            error!("Failed to grant network permissions: {}", e);
+            anyhow::anyhow!(
+                "Failed to grant network permission to component {}: {}",
+                component_id,
+                e
+            )
+        })?;
+
+    let status_text = serde_json::to_string(&json!({
+        "status": "permission granted successfully",
+        "component_id": component_id,
+        "permission_type": "network",
+        "results": results,
+    }))?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(status_text)],
+        structured_content: None,
+        is_error: None,
+        meta: None,
+    })
 }
 
 #[instrument(skip(lifecycle_manager))]
@@ -826,7 +1679,60 @@ pub async fn handle_grant_memory_permission(
     req: &CallToolRequestParam,
     lifecycle_manager: &LifecycleManager,
 ) -> Result<CallToolResult> {
-    handle_grant_permission_generic(req, lifecycle_manager, "resource", "memory").await
+    let args = extract_args_from_request(req)?;
+
+    let component_id = args
+        .get("component_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'component_id'"))?;
+    validate_component_id(component_id)?;
+
+    let details = args
+        .get("details")
+        .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'details'"))?;
+
+    info!("Granting memory permission to component {}", component_id);
+
+    lifecycle_manager
+        .ensure_component_loaded(component_id)
+        .await
+        .map_err(|e| anyhow::anyhow!("Component not found: {} ({})", component_id, e))?;
+
+    let bytes = lifecycle_manager
+        .grant_memory_permission(component_id, details)
+        .await
+        .map_err(|e| {
+            error!("Failed to grant memory permission: {}", e);
+            anyhow::anyhow!(
+                "Failed to grant memory permission to component {}: {}",
+                component_id,
+                e
+            )
+        })?;
+
+    // Echo the limit in bytes so callers can see how the quantity was read
+    let status_text = serde_json::to_string(&json!({
+        "status": "permission granted successfully",
+        "component_id": component_id,
+        "permission_type": "memory",
+        "details": details,
+        "memory_bytes": bytes
+    }))?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(status_text)],
+        structured_content: None,
+        is_error: None,
+        meta: None,
+    })
+}
+
+#[instrument(skip(lifecycle_manager))]
+pub async fn handle_grant_cpu_permission(
+    req: &CallToolRequestParam,
+    lifecycle_manager: &LifecycleManager,
+) -> Result<CallToolResult> {
+    handle_grant_permission_generic(req, lifecycle_manager, "resource", "cpu").await
 }
 
 /// Generic helper for handling revoke permission requests
@@ -842,6 +1748,7 @@ async fn handle_revoke_permission_generic(
         .get("component_id")
         .and_then(|v| v.as_str())
         .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'component_id'"))?;
+    validate_component_id(component_id)?;
 
     let details = args
         .get("details")
@@ -905,6 +1812,7 @@ pub async fn handle_revoke_storage_permission(
         .get("component_id")
         .and_then(|v| v.as_str())
         .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'component_id'"))?;
+    validate_component_id(component_id)?;
 
     let details = args
         .get("details")
@@ -980,6 +1888,22 @@ pub async fn handle_revoke_environment_variable_permission(
     .await
 }
 
+#[instrument(skip(lifecycle_manager))]
+pub async fn handle_grant_config_value(
+    req: &CallToolRequestParam,
+    lifecycle_manager: &LifecycleManager,
+) -> Result<CallToolResult> {
+    handle_grant_permission_generic(req, lifecycle_manager, "config", "config value").await
+}
+
+#[instrument(skip(lifecycle_manager))]
+pub async fn handle_revoke_config_value(
+    req: &CallToolRequestParam,
+    lifecycle_manager: &LifecycleManager,
+) -> Result<CallToolResult> {
+    handle_revoke_permission_generic(req, lifecycle_manager, "config", "config value").await
+}
+
 #[instrument(skip(lifecycle_manager))]
 pub async fn handle_reset_permission(
     req: &CallToolRequestParam,
@@ -991,6 +1915,7 @@ pub async fn handle_reset_permission(
         .get("component_id")
         .and_then(|v| v.as_str())
         .ok_or_else(|| anyhow::anyhow!("Missing required argument: 'component_id'"))?;
+    validate_component_id(component_id)?;
 
     info!("Resetting all permissions for component {}", component_id);
 
@@ -1035,23 +1960,165 @@ mod tests {
     #[test]
     fn test_get_builtin_tools() {
         let tools = get_builtin_tools();
-        assert_eq!(tools.len(), 12);
+        assert_eq!(tools.len(), 28);
         assert!(tools.iter().any(|t| t.name == "load-component"));
         assert!(tools.iter().any(|t| t.name == "unload-component"));
+        assert!(tools.iter().any(|t| t.name == "deactivate-component"));
         assert!(tools.iter().any(|t| t.name == "list-components"));
+        assert!(tools.iter().any(|t| t.name == "get-component-docs"));
+        assert!(tools.iter().any(|t| t.name == "get-load-status"));
+        assert!(tools.iter().any(|t| t.name == "list-component-stats"));
+        assert!(tools.iter().any(|t| t.name == "get-changes"));
+        assert!(tools.iter().any(|t| t.name == "set-component-priority"));
+        assert!(tools.iter().any(|t| t.name == "set-component-visibility"));
         assert!(tools.iter().any(|t| t.name == "get-policy"));
+        assert!(tools.iter().any(|t| t.name == "list-secret-keys"));
+        assert!(tools.iter().any(|t| t.name == "check-network-access"));
+        assert!(tools.iter().any(|t| t.name == "invoke-batch"));
         assert!(tools.iter().any(|t| t.name == "grant-storage-permission"));
         assert!(tools.iter().any(|t| t.name == "grant-network-permission"));
         assert!(tools
             .iter()
             .any(|t| t.name == "grant-environment-variable-permission"));
+        assert!(tools.iter().any(|t| t.name == "grant-cpu-permission"));
         assert!(tools.iter().any(|t| t.name == "revoke-storage-permission"));
         assert!(tools.iter().any(|t| t.name == "revoke-network-permission"));
         assert!(tools
             .iter()
             .any(|t| t.name == "revoke-environment-variable-permission"));
+        assert!(tools.iter().any(|t| t.name == "grant-config-value"));
+        assert!(tools.iter().any(|t| t.name == "revoke-config-value"));
         assert!(tools.iter().any(|t| t.name == "reset-permission"));
         assert!(tools.iter().any(|t| t.name == "search-components"));
+        assert!(tools.iter().any(|t| t.name == "install-component"));
+        assert!(tools
+            .iter()
+            .any(|t| t.name == "get-server-info" && t.output_schema.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_read_only_hides_and_rejects_mutating_tools() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = wassette::LifecycleManager::new(&tempdir).await?;
+
+        let list = handle_tools_list(&lifecycle_manager, false, true).await?;
+        let names: Vec<&str> = list["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert!(names.contains(&"list-components"));
+        assert!(names.contains(&"get-policy"));
+        assert!(names.contains(&"list-secret-keys"));
+        assert!(!names.contains(&"load-component"));
+        assert!(!names.contains(&"grant-network-permission"));
+        assert!(!names.contains(&"reset-permission"));
+        assert!(!names.contains(&"set-component-priority"));
+        assert!(!names.contains(&"set-component-visibility"));
+        assert!(!names.contains(&"deactivate-component"));
+
+        let req = CallToolRequestParam {
+            name: "unload-component".into(),
+            arguments: Some(serde_json::Map::from_iter([(
+                "id".to_string(),
+                json!("fetch_rs"),
+            )])),
+        };
+        let result = handle_tools_call(req, &lifecycle_manager, false, true).await?;
+        assert_eq!(result["isError"], json!(true));
+        assert!(result["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("Server is read-only"));
+
+        let req = CallToolRequestParam {
+            name: "list-components".into(),
+            arguments: None,
+        };
+        let result = handle_tools_call(req, &lifecycle_manager, false, true).await?;
+        assert!(result.get("isError").is_none_or(|v| v.is_null()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_oversized_arguments_are_rejected_before_the_call() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = wassette::LifecycleManager::builder(tempdir.path())
+            .with_parameter_limits(wassette::ParameterLimits {
+                max_depth: 4,
+                ..Default::default()
+            })
+            .build()
+            .await?;
+
+        let req = CallToolRequestParam {
+            name: "fetch".into(),
+            arguments: Some(serde_json::Map::from_iter([(
+                "nested".to_string(),
+                json!([[[[1]]]]),
+            )])),
+        };
+        let error = handle_tools_call(req, &lifecycle_manager, false, false)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ParameterLimitExceeded>(),
+            Some(&ParameterLimitExceeded::Depth { max: 4 })
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_secret_keys_never_returns_values() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = wassette::LifecycleManager::builder(tempdir.path())
+            .with_secrets_dir(tempdir.path().join("secrets"))
+            .build()
+            .await?;
+        std::fs::write(tempdir.path().join("helper.wasm"), b"")?;
+        lifecycle_manager
+            .set_component_secrets(
+                "helper",
+                &[
+                    ("TOKEN".to_string(), "hunter2".to_string()),
+                    ("API_URL".to_string(), "https://example.com".to_string()),
+                ],
+            )
+            .await?;
+
+        let req = CallToolRequestParam {
+            name: "list-secret-keys".into(),
+            arguments: Some(serde_json::Map::from_iter([(
+                "component_id".to_string(),
+                json!("helper"),
+            )])),
+        };
+        let result = handle_tools_call(req, &lifecycle_manager, false, true).await?;
+        let text = result["content"][0]["text"].as_str().unwrap();
+        assert!(!text.contains("hunter2"));
+        let response: Value = serde_json::from_str(text)?;
+        assert_eq!(
+            response["keys"],
+            json!([
+                {"key": "API_URL", "present": true},
+                {"key": "TOKEN", "present": true}
+            ])
+        );
+
+        let req = CallToolRequestParam {
+            name: "list-secret-keys".into(),
+            arguments: Some(serde_json::Map::from_iter([(
+                "component_id".to_string(),
+                json!("missing"),
+            )])),
+        };
+        let result = handle_tools_call(req, &lifecycle_manager, false, false).await?;
+        assert_eq!(result["isError"], json!(true));
+
+        Ok(())
     }
 
     #[tokio::test]
@@ -1369,7 +2436,7 @@ mod tests {
         args.insert("password".to_string(), json!("my-password"));
         args.insert("token".to_string(), json!("bearer-token"));
 
-        let sanitized = sanitize_args_for_logging(&Some(args));
+        let sanitized = sanitize_args_for_logging(&Some(args), Redactor::default_rules());
 
         assert!(sanitized.contains("\"url\""));
         assert!(sanitized.contains("https://example.com"));
@@ -1385,7 +2452,7 @@ mod tests {
         let long_string = "a".repeat(300);
         args.insert("data".to_string(), json!(long_string));
 
-        let sanitized = sanitize_args_for_logging(&Some(args));
+        let sanitized = sanitize_args_for_logging(&Some(args), Redactor::default_rules());
 
         assert!(sanitized.contains("300 chars"));
         assert!(!sanitized.contains(&"a".repeat(300)));
@@ -1393,11 +2460,11 @@ mod tests {
 
     #[test]
     fn test_sanitize_args_for_logging_handles_empty() {
-        let sanitized = sanitize_args_for_logging(&None);
+        let sanitized = sanitize_args_for_logging(&None, Redactor::default_rules());
         assert_eq!(sanitized, "{}");
 
         let empty_args = serde_json::Map::new();
-        let sanitized = sanitize_args_for_logging(&Some(empty_args));
+        let sanitized = sanitize_args_for_logging(&Some(empty_args), Redactor::default_rules());
         assert_eq!(sanitized, "{}");
     }
 
@@ -1408,7 +2475,7 @@ mod tests {
         args.insert("count".to_string(), json!(42));
         args.insert("enabled".to_string(), json!(true));
 
-        let sanitized = sanitize_args_for_logging(&Some(args));
+        let sanitized = sanitize_args_for_logging(&Some(args), Redactor::default_rules());
 
         assert!(sanitized.contains("\"name\""));
         assert!(sanitized.contains("test"));
@@ -1646,6 +2713,46 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_search_and_install_respect_allowed_sources() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let lifecycle_manager = wassette::LifecycleManager::builder(tempdir.path())
+            .with_allowed_sources([wassette::AllowedSource::new(
+                "oci",
+                "ghcr.io/microsoft/fetch-*",
+            )])
+            .with_eager_loading(false)
+            .build()
+            .await?;
+
+        let req = CallToolRequestParam {
+            name: "search-components".into(),
+            arguments: Some(serde_json::Map::new()),
+        };
+        let result = handle_search_component(&req, &lifecycle_manager).await?;
+        let content = serde_json::to_value(&result.content)?;
+        let response: Value = serde_json::from_str(content[0]["text"].as_str().unwrap())?;
+        let components = response["components"].as_array().unwrap();
+        assert_eq!(components.len(), 1);
+        assert_eq!(components[0]["name"], json!("Fetch"));
+
+        let req = CallToolRequestParam {
+            name: "install-component".into(),
+            arguments: Some(serde_json::Map::from_iter([(
+                "name".to_string(),
+                json!("Time Server"),
+            )])),
+        };
+        let result = handle_tools_call(req, &lifecycle_manager, false, false).await?;
+        assert_eq!(result["isError"], json!(true));
+        assert!(result["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("oci://ghcr.io/microsoft/fetch-*"));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_search_component_integration_end_to_end() -> Result<()> {
         let tempdir = tempfile::tempdir()?;
@@ -1707,4 +2814,36 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_tools_list_is_deterministic() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let fixture = std::env::current_dir()?.join("../component2json/testdata/fetch-rs.wasm");
+        let lifecycle_manager = wassette::LifecycleManager::new(tempdir.path()).await?;
+        for name in ["zeta", "alpha"] {
+            let path = tempdir.path().join(format!("source-{name}.wasm"));
+            std::fs::copy(&fixture, &path)?;
+            lifecycle_manager
+                .load_component(&format!("file://{}", path.display()))
+                .await?;
+        }
+
+        let first =
+            serde_json::to_string(&handle_tools_list(&lifecycle_manager, false, false).await?)?;
+        let second =
+            serde_json::to_string(&handle_tools_list(&lifecycle_manager, false, false).await?)?;
+        assert_eq!(first, second);
+
+        let list: Value = serde_json::from_str(&first)?;
+        let tool_ids: Vec<&str> = list["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|tool| tool["_meta"][crate::components::TOOL_ID_META_KEY].as_str())
+            .collect();
+        assert_eq!(tool_ids.len(), 2);
+        assert!(tool_ids[0].starts_with("source-alpha/"));
+        assert!(tool_ids[1].starts_with("source-zeta/"));
+        Ok(())
+    }
 }
@@ -6,13 +6,17 @@
 //! Parser for MCP server policy files. Supports storage, network, environment
 //! and runtime permissions.
 
+use std::collections::BTreeMap;
+
 use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
 
+pub mod migration;
 pub mod parser;
 pub mod types;
 
-pub use parser::PolicyParser;
+pub use migration::{MigrationReport, CURRENT_POLICY_VERSION};
+pub use parser::{PolicyFormat, PolicyParser};
 pub use types::*;
 
 /// Policy document structure
@@ -26,18 +30,24 @@ pub struct PolicyDocument {
 
     /// Permission definitions
     pub permissions: Permissions,
+
+    /// Configuration values passed to the component through wasi-config,
+    /// e.g. feature flags. They take precedence over environment variables
+    /// of the same name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub config: BTreeMap<String, String>,
 }
 
 impl PolicyDocument {
     /// Validate the policy document
     pub fn validate(&self) -> Result<()> {
-        // Only supporting v1.x for now - will add v2 when we know what it looks like
-        if !self.version.starts_with("1.") {
-            bail!("Unsupported version: {}", self.version);
-        }
+        migration::check_current_version(&self.version)?;
         self.permissions
             .validate()
             .context("Permission validation failed")?;
+        if self.config.keys().any(|key| key.is_empty()) {
+            bail!("Config keys cannot be empty");
+        }
         Ok(())
     }
 
@@ -63,6 +73,7 @@ mod tests {
             version: "1.0".to_string(),
             description: Some("Test policy".to_string()),
             permissions: Permissions::default(),
+            config: BTreeMap::new(),
         };
 
         assert!(policy.validate().is_ok());
@@ -86,6 +97,7 @@ mod tests {
             version: "2.0".to_string(),
             description: None,
             permissions: Permissions::default(),
+            config: BTreeMap::new(),
         };
 
         let result = policy.validate();
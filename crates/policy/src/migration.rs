@@ -0,0 +1,186 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Schema version handling for policy documents
+//!
+//! Policy versions are `MAJOR.MINOR` strings. Minor versions of the current
+//! major version share one schema and are loaded as-is. Documents written for
+//! an older major version are upgraded in memory by the steps in
+//! [`MIGRATIONS`] before they are deserialized, and documents for a newer
+//! major version are rejected.
+//!
+//! Bumping the schema means raising [`CURRENT_POLICY_VERSION`] and adding one
+//! [`Migration`] that rewrites the raw YAML of the previous version.
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+
+/// Version written into new policy documents
+pub const CURRENT_POLICY_VERSION: &str = "1.0";
+
+/// A single upgrade step between two major policy versions
+pub(crate) struct Migration {
+    /// Major version this step upgrades from
+    pub from_major: u64,
+    /// Version the document has after this step
+    pub to_version: &'static str,
+    /// Rewrites the document in place and describes each change it made
+    pub apply: fn(&mut Mapping, &mut Vec<String>),
+}
+
+/// Upgrade steps, one per older major version.
+///
+/// There is only one policy schema so far, so the table is empty.
+pub(crate) static MIGRATIONS: &[Migration] = &[];
+
+/// Summary of an in-memory policy migration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationReport {
+    /// Version found in the document
+    pub from_version: String,
+    /// Version the document was migrated to
+    pub to_version: String,
+    /// Human-readable description of each change
+    pub changes: Vec<String>,
+}
+
+fn major_version(version: &str) -> Option<u64> {
+    version.split('.').next()?.parse().ok()
+}
+
+fn current_major() -> u64 {
+    major_version(CURRENT_POLICY_VERSION).expect("current policy version is valid")
+}
+
+/// Check that `version` can be loaded without migration.
+pub(crate) fn check_current_version(version: &str) -> Result<()> {
+    match major_version(version) {
+        Some(major) if major == current_major() => Ok(()),
+        Some(major) if major > current_major() => bail!(
+            "Unsupported version: {version} (this wassette supports policy version {}.x; upgrade wassette to load this policy)",
+            current_major()
+        ),
+        _ => bail!("Unsupported version: {version}"),
+    }
+}
+
+/// Upgrade a raw policy document to the current major version, returning a
+/// report when anything changed.
+pub(crate) fn migrate(document: &mut Value) -> Result<Option<MigrationReport>> {
+    migrate_with(document, MIGRATIONS)
+}
+
+fn migrate_with(document: &mut Value, migrations: &[Migration]) -> Result<Option<MigrationReport>> {
+    let Some(mapping) = document.as_mapping_mut() else {
+        return Ok(None);
+    };
+    // Leave missing or malformed versions to deserialization and validation
+    let from_version = match mapping.get("version") {
+        Some(Value::String(version)) => version.clone(),
+        Some(Value::Number(version)) => version.to_string(),
+        _ => return Ok(None),
+    };
+    let Some(mut major) = major_version(&from_version) else {
+        bail!("Unsupported version: {from_version}");
+    };
+    if major >= current_major() {
+        check_current_version(&from_version)?;
+        return Ok(None);
+    }
+
+    let mut version = from_version.clone();
+    let mut changes = Vec::new();
+    while major < current_major() {
+        let Some(step) = migrations.iter().find(|step| step.from_major == major) else {
+            bail!(
+                "Unsupported version: {version} (no migration to policy version {CURRENT_POLICY_VERSION} is known)"
+            );
+        };
+        (step.apply)(mapping, &mut changes);
+        version = step.to_version.to_string();
+        major = major_version(&version).expect("migration target version is valid");
+    }
+    mapping.insert("version".into(), Value::String(version.clone()));
+
+    Ok(Some(MigrationReport {
+        from_version,
+        to_version: version,
+        changes,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Hypothetical 0.x schema that kept a memory limit in MB at the top level
+    fn move_memory_limit(document: &mut Mapping, changes: &mut Vec<String>) {
+        if let Some(memory) = document.remove("memory_mb") {
+            let mut limits = Mapping::new();
+            limits.insert(
+                "memory".into(),
+                format!("{}Mi", memory.as_u64().unwrap()).into(),
+            );
+            let mut resources = Mapping::new();
+            resources.insert("limits".into(), limits.into());
+            let permissions = document
+                .entry("permissions".into())
+                .or_insert_with(|| Mapping::new().into());
+            permissions
+                .as_mapping_mut()
+                .unwrap()
+                .insert("resources".into(), resources.into());
+            changes.push("moved memory_mb to permissions.resources.limits.memory".to_string());
+        }
+    }
+
+    const TEST_MIGRATIONS: &[Migration] = &[Migration {
+        from_major: 0,
+        to_version: "1.0",
+        apply: move_memory_limit,
+    }];
+
+    #[test]
+    fn test_current_version_is_untouched() {
+        let mut document: Value =
+            serde_yaml::from_str("version: \"1.1\"\npermissions: {}").unwrap();
+        let original = document.clone();
+        assert_eq!(migrate_with(&mut document, TEST_MIGRATIONS).unwrap(), None);
+        assert_eq!(document, original);
+    }
+
+    #[test]
+    fn test_old_version_is_migrated() {
+        let mut document: Value =
+            serde_yaml::from_str("version: \"0.9\"\nmemory_mb: 256\n").unwrap();
+        let report = migrate_with(&mut document, TEST_MIGRATIONS)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(report.from_version, "0.9");
+        assert_eq!(report.to_version, "1.0");
+        assert_eq!(report.changes.len(), 1);
+        assert_eq!(document["version"], "1.0");
+        assert_eq!(
+            document["permissions"]["resources"]["limits"]["memory"],
+            "256Mi"
+        );
+    }
+
+    #[test]
+    fn test_future_and_unknown_versions_are_rejected() {
+        let mut document: Value = serde_yaml::from_str("version: \"2.0\"").unwrap();
+        let error = migrate_with(&mut document, TEST_MIGRATIONS)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("Unsupported version: 2.0"));
+        assert!(error.contains("upgrade wassette"));
+
+        let mut document: Value = serde_yaml::from_str("version: \"0.9\"").unwrap();
+        assert!(migrate_with(&mut document, &[]).is_err());
+
+        let mut document: Value = serde_yaml::from_str("version: \"latest\"").unwrap();
+        assert!(migrate_with(&mut document, TEST_MIGRATIONS).is_err());
+    }
+}
@@ -6,12 +6,52 @@ use std::path::Path;
 
 use anyhow::Context;
 
-use crate::{PolicyDocument, PolicyResult};
+use crate::{migration, MigrationReport, PolicyDocument, PolicyResult};
 
 pub struct PolicyParser;
 
+/// Format a policy document is written in. Policies are YAML unless they are
+/// written as a JSON object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PolicyFormat {
+    /// YAML, the format policies are written in by default
+    #[default]
+    Yaml,
+    /// JSON
+    Json,
+}
+
+impl PolicyFormat {
+    /// The format of `content`: JSON if it starts with `{`, YAML otherwise.
+    pub fn detect(content: &str) -> Self {
+        if content.trim_start().starts_with('{') {
+            Self::Json
+        } else {
+            Self::Yaml
+        }
+    }
+
+    /// The format a file's extension names: `.json` for JSON, `.yaml` or
+    /// `.yml` for YAML.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        match path.as_ref().extension()?.to_str()? {
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+
+    /// File extension for policies in this format, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Yaml => "yaml",
+            Self::Json => "json",
+        }
+    }
+}
+
 impl PolicyParser {
-    /// Parse a policy document from a YAML string
+    /// Parse a policy document from a YAML or JSON string
     ///
     /// # Example
     ///
@@ -32,12 +72,63 @@ impl PolicyParser {
     /// assert_eq!(policy.version, "1.0");
     /// ```
     pub fn parse_str(content: impl AsRef<str>) -> PolicyResult<PolicyDocument> {
-        let document: PolicyDocument = serde_yaml::from_str(content.as_ref())?;
+        Self::parse_str_with_migration(content).map(|(document, _)| document)
+    }
+
+    /// Parse a policy document from a YAML or JSON string, migrating
+    /// documents written for an older policy version in memory. The report
+    /// describes what was changed and is `None` when the document was already
+    /// current. The format is detected with [`PolicyFormat::detect`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use policy::PolicyParser;
+    ///
+    /// let (policy, report) =
+    ///     PolicyParser::parse_str_with_migration("version: '1.0'\npermissions: {}").unwrap();
+    /// assert_eq!(policy.version, "1.0");
+    /// assert!(report.is_none());
+    /// ```
+    pub fn parse_str_with_migration(
+        content: impl AsRef<str>,
+    ) -> PolicyResult<(PolicyDocument, Option<MigrationReport>)> {
+        let content = content.as_ref();
+        Self::parse_str_as(content, PolicyFormat::detect(content))
+    }
+
+    /// [`Self::parse_str_with_migration`] for a document known to be in
+    /// `format`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use policy::{PolicyFormat, PolicyParser};
+    ///
+    /// let (policy, _) = PolicyParser::parse_str_as(
+    ///     r#"{"version": "1.0", "permissions": {}}"#,
+    ///     PolicyFormat::Json,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(policy.version, "1.0");
+    /// ```
+    pub fn parse_str_as(
+        content: &str,
+        format: PolicyFormat,
+    ) -> PolicyResult<(PolicyDocument, Option<MigrationReport>)> {
+        // Migrations work on YAML values, which JSON documents convert to
+        let mut value: serde_yaml::Value = match format {
+            PolicyFormat::Yaml => serde_yaml::from_str(content)?,
+            PolicyFormat::Json => serde_json::from_str(content)?,
+        };
+        let report = migration::migrate(&mut value)?;
+        let document: PolicyDocument = serde_yaml::from_value(value)?;
         document.validate()?;
-        Ok(document)
+        Ok((document, report))
     }
 
-    /// Parse a policy document from a file path
+    /// Parse a policy document from a file path. The format is taken from
+    /// the file's extension, or detected from its content.
     ///
     /// # Example
     ///
@@ -48,8 +139,10 @@ impl PolicyParser {
     /// println!("Loaded policy: {}", policy.description.unwrap_or_default());
     /// ```
     pub fn parse_file<P: AsRef<Path>>(path: P) -> PolicyResult<PolicyDocument> {
+        let format = PolicyFormat::from_path(&path);
         let content = fs::read_to_string(path)?;
-        Self::parse_str(&content)
+        let format = format.unwrap_or_else(|| PolicyFormat::detect(&content));
+        Self::parse_str_as(&content, format).map(|(document, _)| document)
     }
 
     /// Parse a policy document from bytes
@@ -78,6 +171,7 @@ impl PolicyParser {
     ///     version: "1.0".to_string(),
     ///     description: Some("Test policy".to_string()),
     ///     permissions: Permissions::default(),
+    ///     config: Default::default(),
     /// };
     ///
     /// let yaml = PolicyParser::to_yaml(&policy).unwrap();
@@ -89,7 +183,39 @@ impl PolicyParser {
         Ok(yaml)
     }
 
-    /// Write a policy document to a file
+    /// Serialize a policy document to a pretty-printed JSON string
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use policy::{PolicyParser, PolicyDocument, Permissions};
+    ///
+    /// let policy = PolicyDocument {
+    ///     version: "1.0".to_string(),
+    ///     description: None,
+    ///     permissions: Permissions::default(),
+    ///     config: Default::default(),
+    /// };
+    ///
+    /// let json = PolicyParser::to_json(&policy).unwrap();
+    /// assert!(json.contains(r#""version": "1.0""#));
+    /// ```
+    pub fn to_json(document: &PolicyDocument) -> PolicyResult<String> {
+        document.validate()?;
+        let json = serde_json::to_string_pretty(document)?;
+        Ok(json)
+    }
+
+    /// Serialize a policy document in `format`
+    pub fn to_string_as(document: &PolicyDocument, format: PolicyFormat) -> PolicyResult<String> {
+        match format {
+            PolicyFormat::Yaml => Self::to_yaml(document),
+            PolicyFormat::Json => Self::to_json(document),
+        }
+    }
+
+    /// Write a policy document to a file, as JSON if the file's extension is
+    /// `.json` and as YAML otherwise
     ///
     /// # Example
     ///
@@ -100,8 +226,8 @@ impl PolicyParser {
     /// PolicyParser::write_file(&policy, "./testdata/docker.yaml").unwrap();
     /// ```
     pub fn write_file<P: AsRef<Path>>(document: &PolicyDocument, path: P) -> PolicyResult<()> {
-        let yaml = Self::to_yaml(document)?;
-        fs::write(path, yaml)?;
+        let format = PolicyFormat::from_path(&path).unwrap_or_default();
+        fs::write(path, Self::to_string_as(document, format)?)?;
         Ok(())
     }
 }
@@ -114,8 +240,8 @@ mod tests {
 
     use super::*;
     use crate::{
-        AccessType, CapabilityAction, CpuLimit, MemoryLimit, PermissionList, Permissions,
-        StoragePermission,
+        AccessType, CapabilityAction, CpuLimit, MemoryLimit, NetworkHostPermission,
+        NetworkPermission, Permissions, StoragePermission, StoragePermissions, TimeoutLimit,
     };
 
     #[test]
@@ -157,6 +283,81 @@ permissions: {}
         result.unwrap_err();
     }
 
+    #[test]
+    fn test_parse_network_timeout() {
+        let yaml_content = r#"
+version: "1.0"
+permissions:
+  network:
+    allow:
+    - host: "api.example.com"
+      timeout: "45s"
+"#;
+
+        let policy = PolicyParser::parse_str(yaml_content).unwrap();
+        let allow = policy.permissions.network.unwrap().allow.unwrap();
+        assert_eq!(
+            allow[0],
+            NetworkPermission::Host(NetworkHostPermission {
+                host: "api.example.com".to_string(),
+                timeout: Some(TimeoutLimit::String("45s".to_string())),
+                ..Default::default()
+            })
+        );
+
+        let error = PolicyParser::parse_str(yaml_content.replace("45s", "soon")).unwrap_err();
+        assert!(format!("{error:#}").contains("Invalid timeout for host api.example.com"));
+    }
+
+    #[test]
+    fn test_parse_network_port_and_scheme() {
+        let yaml_content = r#"
+version: "1.0"
+permissions:
+  network:
+    allow:
+    - host: "api.example.com"
+      port: 443
+      scheme: "https"
+"#;
+
+        let policy = PolicyParser::parse_str(yaml_content).unwrap();
+        let allow = policy.permissions.network.unwrap().allow.unwrap();
+        assert_eq!(
+            allow[0],
+            NetworkPermission::Host(NetworkHostPermission {
+                host: "api.example.com".to_string(),
+                port: Some(443),
+                scheme: Some("https".to_string()),
+                ..Default::default()
+            })
+        );
+
+        let error = PolicyParser::parse_str(yaml_content.replace("https", "ftp")).unwrap_err();
+        assert!(format!("{error:#}").contains("Scheme must be http or https"));
+        assert!(PolicyParser::parse_str(yaml_content.replace("443", "0")).is_err());
+    }
+
+    #[test]
+    fn test_parse_execution_timeout() {
+        let yaml_content = r#"
+version: "1.0"
+permissions:
+  resources:
+    limits:
+      timeout: "250ms"
+"#;
+
+        let policy = PolicyParser::parse_str(yaml_content).unwrap();
+        let limits = policy.permissions.resources.unwrap().limits.unwrap();
+        assert_eq!(
+            limits.timeout.unwrap().to_duration().unwrap(),
+            std::time::Duration::from_millis(250)
+        );
+
+        assert!(PolicyParser::parse_str(yaml_content.replace("250ms", "0s")).is_err());
+    }
+
     #[test]
     fn test_parse_str_invalid_yaml() {
         let yaml_content = r#"
@@ -172,12 +373,13 @@ invalid: yaml: content
     #[test]
     fn test_round_trip_serialization() {
         let permissions = Permissions {
-            storage: Some(PermissionList {
+            storage: Some(StoragePermissions {
                 allow: Some(vec![StoragePermission {
                     uri: "fs://work/agent/**".to_string(),
                     access: vec![AccessType::Read, AccessType::Write],
                 }]),
                 deny: None,
+                scratch: None,
             }),
             ..Default::default()
         };
@@ -185,6 +387,7 @@ invalid: yaml: content
             version: "1.0".to_string(),
             description: Some("Test policy".to_string()),
             permissions,
+            config: Default::default(),
         };
 
         let yaml = PolicyParser::to_yaml(&original).unwrap();
@@ -227,6 +430,7 @@ permissions:
             version: "1.0".to_string(),
             description: Some("Write test policy".to_string()),
             permissions,
+            config: Default::default(),
         };
 
         let temp_file = NamedTempFile::new().unwrap();
@@ -563,4 +767,39 @@ permissions: {}
             });
         }
     }
+
+    #[test]
+    fn test_json_policy_matches_yaml() {
+        let json_content = r#"{
+  "version": "1.0",
+  "permissions": {
+    "storage": {"allow": [{"uri": "fs://work/agent/**", "access": ["read", "write"]}]}
+  }
+}"#;
+        assert_eq!(PolicyFormat::detect(json_content), PolicyFormat::Json);
+        let policy = PolicyParser::parse_str(json_content).unwrap();
+        let yaml_policy = PolicyParser::parse_str(
+            r#"
+version: "1.0"
+permissions:
+  storage:
+    allow:
+    - uri: "fs://work/agent/**"
+      access: ["read", "write"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(policy, yaml_policy);
+
+        let err = PolicyParser::parse_str(r#"{"version": "1.0", "permissions": }"#).unwrap_err();
+        assert!(err.to_string().contains("line 1"), "{err}");
+
+        let mut file = tempfile::Builder::new().suffix(".json").tempfile().unwrap();
+        PolicyParser::write_file(&policy, file.path()).unwrap();
+        let mut written = String::new();
+        std::io::Read::read_to_string(file.as_file_mut(), &mut written).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&written).unwrap();
+        assert_eq!(value["version"], "1.0");
+        assert_eq!(PolicyParser::parse_file(file.path()).unwrap(), policy);
+    }
 }
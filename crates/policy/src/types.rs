@@ -6,8 +6,9 @@
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::sync::OnceLock;
+use std::time::Duration;
 
-use anyhow::bail;
+use anyhow::{bail, Context};
 use serde::{Deserialize, Serialize};
 
 use crate::PolicyResult;
@@ -34,10 +35,22 @@ pub struct StoragePermission {
 /// Network host permission
 ///
 /// host: Hostname or pattern (supports wildcards like *.domain.com)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// port: Optional port requests must go to (443)
+/// scheme: Optional scheme requests must use ("http" or "https")
+/// timeout: Optional time limit for requests to this host ("30s", "2m")
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct NetworkHostPermission {
     /// Hostname or pattern (supports wildcards like *.domain.com)
     pub host: String,
+    /// Port requests must go to; any port when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// Scheme requests must use; any scheme when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<String>,
+    /// Time limit for requests to this host, overriding the server default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<TimeoutLimit>,
 }
 
 /// Network CIDR permission
@@ -134,7 +147,8 @@ pub enum CpuLimit {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum MemoryLimit {
-    /// String format supporting Ki, Mi, Gi suffixes ("512Mi", "1Gi")
+    /// String format supporting the binary Ki, Mi, Gi, Ti suffixes ("512Mi",
+    /// "1Gi") and the decimal k, M, G, T suffixes ("2G")
     String(String),
     /// Numeric format for backward compatibility (assumed to be in MB)
     Number(u64),
@@ -147,6 +161,9 @@ pub struct ResourceLimitValues {
     pub cpu: Option<CpuLimit>,
     /// Memory limit in k8s format ("512Mi", "1Gi", "256Ki")
     pub memory: Option<MemoryLimit>,
+    /// Maximum time a called function may run before it is stopped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<TimeoutLimit>,
     /// Cached parsed CPU value in cores (not serialized)
     #[serde(skip)]
     cpu_cores_cache: OnceLock<f64>,
@@ -167,6 +184,61 @@ pub struct ResourceLimits {
     pub memory: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub io: Option<u64>,
+    /// Time limits for component operations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeouts: Option<TimeoutLimits>,
+}
+
+/// Duration limit that supports "500ms", "10s", "2m" style values
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TimeoutLimit {
+    /// String format with an `ms`, `s`, or `m` suffix
+    String(String),
+    /// Numeric format in seconds
+    Number(u64),
+}
+
+/// Time limits under the timeouts section
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct TimeoutLimits {
+    /// Maximum time a component may take to instantiate, separate from the
+    /// time its functions may run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instantiation: Option<TimeoutLimit>,
+}
+
+/// A component and tools another component may call through the host
+///
+/// component: ID of the component that may be called
+/// tools: Tools of that component that may be called; all of them when empty
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallPermission {
+    pub component: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<String>,
+}
+
+impl CallPermission {
+    /// Whether the rule covers `tool` of `component`.
+    pub fn matches(&self, component: &str, tool: &str) -> bool {
+        self.component == component
+            && (self.tools.is_empty() || self.tools.iter().any(|t| t == tool))
+    }
+}
+
+impl PermissionList<CallPermission> {
+    /// Whether an allow rule covers `tool` of `component` and no deny rule
+    /// does.
+    pub fn allows_call(&self, component: &str, tool: &str) -> bool {
+        let matches = |rules: &Option<Vec<CallPermission>>| {
+            rules
+                .iter()
+                .flatten()
+                .any(|rule| rule.matches(component, tool))
+        };
+        matches(&self.allow) && !matches(&self.deny)
+    }
 }
 
 /// IPC permission configuration (future/TODO)
@@ -200,21 +272,169 @@ impl<T> Default for PermissionList<T> {
     }
 }
 
+/// Storage permissions with allow/deny rules and the host-managed scratch
+/// directory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct StoragePermissions {
+    pub allow: Option<Vec<StoragePermission>>,
+    pub deny: Option<Vec<StoragePermission>>,
+    /// A scratch directory the host provisions for the component
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scratch: Option<ScratchStorage>,
+}
+
+/// Default guest path of the scratch directory
+pub const DEFAULT_SCRATCH_PATH: &str = "/tmp";
+
+/// Scratch directory settings
+///
+/// enabled: Whether the scratch directory is provided (default true)
+/// path: Guest path it is mounted at (default /tmp)
+/// maxBytes: Largest total size of the files it may hold
+/// persist: Keep its contents between calls until the component is unloaded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScratchStorage {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Guest path the directory is mounted at, `/tmp` when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Largest total size of the files in the directory, in bytes
+    #[serde(rename = "maxBytes", default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<u64>,
+    /// Keep the contents between calls; they are removed when the component
+    /// is unloaded
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub persist: bool,
+}
+
+impl ScratchStorage {
+    /// The guest path the directory is mounted at.
+    pub fn guest_path(&self) -> &str {
+        self.path.as_deref().unwrap_or(DEFAULT_SCRATCH_PATH)
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// System settings the component observes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct SystemPermissions {
+    /// The clock the component sees
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clock: Option<ClockSettings>,
+    /// Where the component's standard output goes, decided by the host when
+    /// unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdout: Option<StdioMode>,
+    /// Where the component's standard error goes, decided by the host when
+    /// unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr: Option<StdioMode>,
+}
+
+/// What happens to what a component writes to stdout or stderr
+///
+/// capture: kept in memory and returned with the tool result
+/// inherit: written to the host's own stream
+/// deny: discarded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StdioMode {
+    Capture,
+    Inherit,
+    Deny,
+}
+
+/// Guest clock settings
+///
+/// timezone: IANA timezone passed to the component as `TZ`, e.g. Europe/Berlin
+/// fixedTime: RFC 3339 time the wall clock is frozen at, e.g. 2024-01-01T00:00:00Z
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct ClockSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// The wall clock always reads this time; the monotonic clock still
+    /// advances
+    #[serde(rename = "fixedTime", default, skip_serializing_if = "Option::is_none")]
+    pub fixed_time: Option<String>,
+}
+
+impl ClockSettings {
+    /// The time the wall clock is frozen at, as the time since the Unix epoch.
+    pub fn fixed_time_since_epoch(&self) -> PolicyResult<Option<Duration>> {
+        let Some(fixed_time) = &self.fixed_time else {
+            return Ok(None);
+        };
+        let time = chrono::DateTime::parse_from_rfc3339(fixed_time).with_context(|| {
+            format!("Invalid fixedTime, expected an RFC 3339 time: {fixed_time}")
+        })?;
+        let Ok(seconds) = u64::try_from(time.timestamp()) else {
+            bail!("fixedTime must not be before 1970: {}", fixed_time);
+        };
+        Ok(Some(
+            Duration::from_secs(seconds)
+                + Duration::from_nanos(time.timestamp_subsec_nanos().into()),
+        ))
+    }
+
+    /// Validate the timezone name and fixed time
+    pub fn validate(&self) -> PolicyResult<()> {
+        if let Some(timezone) = &self.timezone {
+            if timezone.is_empty() {
+                bail!("Clock timezone can't be empty");
+            }
+            if !timezone
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "/_+-:".contains(c))
+            {
+                bail!(
+                    "Clock timezone doesn't look like a timezone name: {}",
+                    timezone
+                );
+            }
+        }
+        self.fixed_time_since_epoch()?;
+        Ok(())
+    }
+}
+
 /// Environment permissions (allow-only for security)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct EnvironmentPermissions {
     pub allow: Option<Vec<EnvironmentPermission>>,
+    /// Pass every configured environment variable through instead of only
+    /// the allowed keys. Only honored when the host opts in.
+    #[serde(rename = "inheritAll", default, skip_serializing_if = "is_false")]
+    pub inherit_all: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
 }
 
 /// Complete permissions structure
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct Permissions {
-    pub storage: Option<PermissionList<StoragePermission>>,
+    pub storage: Option<StoragePermissions>,
     pub network: Option<PermissionList<NetworkPermission>>,
     pub environment: Option<EnvironmentPermissions>,
     pub runtime: Option<Runtime>,
     pub resources: Option<ResourceLimits>,
     pub ipc: Option<PermissionList<IpcPermission>>,
+    /// Components and tools the component may call through the
+    /// `wassette:broker/call` host interface.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub call: Option<PermissionList<CallPermission>>,
+    /// Let the component read its own limits through the
+    /// `wassette:limits/get` host interface.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub introspection: bool,
+    /// System settings such as the clock the component sees
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<SystemPermissions>,
 }
 
 impl CpuLimit {
@@ -278,6 +498,14 @@ impl MemoryLimit {
                     (&s[..s.len() - 2], 1024u64 * 1024 * 1024)
                 } else if s.ends_with("Ti") {
                     (&s[..s.len() - 2], 1024u64 * 1024 * 1024 * 1024)
+                } else if s.ends_with('k') || s.ends_with('K') {
+                    (&s[..s.len() - 1], 1000u64)
+                } else if s.ends_with('M') {
+                    (&s[..s.len() - 1], 1000u64 * 1000)
+                } else if s.ends_with('G') {
+                    (&s[..s.len() - 1], 1000u64 * 1000 * 1000)
+                } else if s.ends_with('T') {
+                    (&s[..s.len() - 1], 1000u64 * 1000 * 1000 * 1000)
                 } else {
                     // No suffix, assume bytes
                     (s.as_str(), 1u64)
@@ -309,12 +537,56 @@ impl MemoryLimit {
     }
 }
 
+impl TimeoutLimit {
+    /// Validate and convert the timeout to a duration
+    pub fn to_duration(&self) -> PolicyResult<Duration> {
+        let duration = match self {
+            TimeoutLimit::String(s) => {
+                let (value_str, unit_ms) = if let Some(value) = s.strip_suffix("ms") {
+                    (value, 1u64)
+                } else if let Some(value) = s.strip_suffix('s') {
+                    (value, 1000)
+                } else if let Some(value) = s.strip_suffix('m') {
+                    (value, 60 * 1000)
+                } else {
+                    bail!("Timeout needs an ms, s, or m suffix: {}", s);
+                };
+
+                let value: u64 = value_str
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid timeout value: {}", s))?;
+                let millis = value
+                    .checked_mul(unit_ms)
+                    .ok_or_else(|| anyhow::anyhow!("Timeout value too large: {}", s))?;
+                Duration::from_millis(millis)
+            }
+            TimeoutLimit::Number(n) => Duration::from_secs(*n),
+        };
+
+        if duration.is_zero() {
+            bail!("Timeout cannot be zero");
+        }
+        Ok(duration)
+    }
+}
+
+impl TimeoutLimits {
+    /// Validate timeout values
+    pub fn validate(&self) -> PolicyResult<()> {
+        if let Some(instantiation) = &self.instantiation {
+            instantiation.to_duration()?;
+        }
+        Ok(())
+    }
+}
+
 impl ResourceLimitValues {
     /// Create a new ResourceLimitValues instance
     pub fn new(cpu: Option<CpuLimit>, memory: Option<MemoryLimit>) -> Self {
         Self {
             cpu,
             memory,
+            timeout: None,
             cpu_cores_cache: OnceLock::new(),
             memory_bytes_cache: OnceLock::new(),
         }
@@ -359,6 +631,9 @@ impl ResourceLimitValues {
         // Validation now uses the cached getters, which will parse and cache the values
         self.cpu_cores()?;
         self.memory_bytes()?;
+        if let Some(timeout) = &self.timeout {
+            timeout.to_duration()?;
+        }
         Ok(())
     }
 }
@@ -370,6 +645,10 @@ impl ResourceLimits {
             limits.validate()?;
         }
 
+        if let Some(timeouts) = &self.timeouts {
+            timeouts.validate()?;
+        }
+
         // Validate legacy fields
         if let Some(cpu) = self.cpu {
             if cpu < 0.0 {
@@ -440,6 +719,22 @@ impl Permissions {
         Ok(())
     }
 
+    fn validate_network_target(host_perm: &NetworkHostPermission) -> PolicyResult<()> {
+        if host_perm.port == Some(0) {
+            bail!("Port can't be 0 for host {}", host_perm.host);
+        }
+        if let Some(scheme) = &host_perm.scheme {
+            if scheme != "http" && scheme != "https" {
+                bail!(
+                    "Scheme must be http or https for host {}: {}",
+                    host_perm.host,
+                    scheme
+                );
+            }
+        }
+        Ok(())
+    }
+
     fn validate_environment_key(key: &str) -> PolicyResult<()> {
         if key.is_empty() {
             bail!("Environment key can't be empty");
@@ -472,6 +767,15 @@ impl Permissions {
                     }
                 }
             }
+            if let Some(scratch) = &storage.scratch {
+                let path = scratch.guest_path();
+                if !path.starts_with('/') || path == "/" {
+                    bail!("Scratch path must be an absolute path below '/': {}", path);
+                }
+                if scratch.max_bytes == Some(0) {
+                    bail!("Scratch maxBytes must be greater than zero");
+                }
+            }
         }
 
         if let Some(network) = &self.network {
@@ -480,6 +784,12 @@ impl Permissions {
                     match perm {
                         NetworkPermission::Host(host_perm) => {
                             Self::validate_network_host(&host_perm.host)?;
+                            Self::validate_network_target(host_perm)?;
+                            if let Some(timeout) = &host_perm.timeout {
+                                timeout.to_duration().with_context(|| {
+                                    format!("Invalid timeout for host {}", host_perm.host)
+                                })?;
+                            }
                         }
                         NetworkPermission::Cidr(cidr_perm) => {
                             if cidr_perm.cidr.is_empty() {
@@ -523,6 +833,28 @@ impl Permissions {
             resources.validate()?;
         }
 
+        if let Some(clock) = self
+            .system
+            .as_ref()
+            .and_then(|system| system.clock.as_ref())
+        {
+            clock.validate()?;
+        }
+
+        if let Some(call) = &self.call {
+            for perm in call.allow.iter().chain(call.deny.iter()).flatten() {
+                if perm.component.is_empty() {
+                    bail!("Call component can't be empty");
+                }
+                if perm.tools.iter().any(|tool| tool.is_empty()) {
+                    bail!(
+                        "Call tool names can't be empty for component {}",
+                        perm.component
+                    );
+                }
+            }
+        }
+
         Ok(())
     }
 }
@@ -534,12 +866,13 @@ mod tests {
     #[test]
     fn test_storage_permission_validation() {
         let permissions = Permissions {
-            storage: Some(PermissionList {
+            storage: Some(StoragePermissions {
                 allow: Some(vec![StoragePermission {
                     uri: "".to_string(),
                     access: vec![AccessType::Read],
                 }]),
                 deny: None,
+                scratch: None,
             }),
             ..Default::default()
         };
@@ -565,12 +898,13 @@ mod tests {
     #[test]
     fn test_valid_permissions() {
         let permissions = Permissions {
-            storage: Some(PermissionList {
+            storage: Some(StoragePermissions {
                 allow: Some(vec![StoragePermission {
                     uri: "fs://work/agent/**".to_string(),
                     access: vec![AccessType::Read, AccessType::Write],
                 }]),
                 deny: None,
+                scratch: None,
             }),
             ..Default::default()
         };
@@ -592,6 +926,110 @@ mod tests {
         assert!(Permissions::validate_storage_uri("fs://work/agent/**/**.txt").is_err());
     }
 
+    #[test]
+    fn test_scratch_storage_parsing_and_validation() {
+        let storage: StoragePermissions =
+            serde_yaml::from_str("scratch:\n  maxBytes: 1048576\n").unwrap();
+        let scratch = storage.scratch.clone().unwrap();
+        assert!(scratch.enabled);
+        assert!(!scratch.persist);
+        assert_eq!(scratch.guest_path(), DEFAULT_SCRATCH_PATH);
+        assert_eq!(scratch.max_bytes, Some(1024 * 1024));
+        let mut permissions = Permissions {
+            storage: Some(storage),
+            ..Default::default()
+        };
+        assert!(permissions.validate().is_ok());
+
+        for path in ["scratch", "/"] {
+            permissions.storage.as_mut().unwrap().scratch = Some(ScratchStorage {
+                path: Some(path.to_string()),
+                ..scratch.clone()
+            });
+            assert!(permissions.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn test_clock_settings_parsing_and_validation() {
+        let system: SystemPermissions = serde_yaml::from_str(
+            "clock:\n  timezone: Europe/Berlin\n  fixedTime: \"2024-01-01T01:00:00.5+01:00\"\n",
+        )
+        .unwrap();
+        let clock = system.clock.clone().unwrap();
+        assert_eq!(clock.timezone.as_deref(), Some("Europe/Berlin"));
+        assert_eq!(
+            clock.fixed_time_since_epoch().unwrap(),
+            Some(Duration::from_millis(1_704_067_200_500))
+        );
+        let mut permissions = Permissions {
+            system: Some(system),
+            ..Default::default()
+        };
+        assert!(permissions.validate().is_ok());
+
+        for (timezone, fixed_time) in [
+            ("", None),
+            ("Europe/Berlin; rm -rf", None),
+            ("UTC", Some("2024-01-01")),
+            ("UTC", Some("1969-12-31T23:59:59Z")),
+        ] {
+            permissions.system.as_mut().unwrap().clock = Some(ClockSettings {
+                timezone: Some(timezone.to_string()),
+                fixed_time: fixed_time.map(str::to_string),
+            });
+            assert!(
+                permissions.validate().is_err(),
+                "{timezone:?} {fixed_time:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_stdio_mode_parsing() {
+        let system: SystemPermissions =
+            serde_yaml::from_str("stdout: capture\nstderr: deny\n").unwrap();
+        assert_eq!(system.stdout, Some(StdioMode::Capture));
+        assert_eq!(system.stderr, Some(StdioMode::Deny));
+        assert_eq!(
+            serde_yaml::to_string(&system).unwrap(),
+            "stdout: capture\nstderr: deny\n"
+        );
+        assert!(serde_yaml::from_str::<SystemPermissions>("stdout: allow\n").is_err());
+    }
+
+    #[test]
+    fn test_call_permission_parsing_and_matching() {
+        let call: PermissionList<CallPermission> = serde_yaml::from_str(
+            r#"
+allow:
+  - component: parser
+    tools: [parse]
+  - component: formatter
+deny:
+  - component: formatter
+    tools: [reset]
+"#,
+        )
+        .unwrap();
+        assert!(call.allows_call("parser", "parse"));
+        assert!(!call.allows_call("parser", "validate"));
+        assert!(call.allows_call("formatter", "format"));
+        assert!(!call.allows_call("formatter", "reset"));
+        assert!(!call.allows_call("fetcher", "fetch"));
+
+        let mut permissions = Permissions {
+            call: Some(call),
+            ..Default::default()
+        };
+        assert!(permissions.validate().is_ok());
+        permissions.call.as_mut().unwrap().allow = Some(vec![CallPermission {
+            component: String::new(),
+            tools: vec![],
+        }]);
+        assert!(permissions.validate().is_err());
+    }
+
     #[test]
     fn test_network_host_wildcard_validation() {
         assert!(Permissions::validate_network_host("example.com").is_ok());
@@ -624,10 +1062,21 @@ mod tests {
         assert!(Permissions::validate_environment_key("PATH**").is_err());
     }
 
+    #[test]
+    fn test_environment_inherit_all() {
+        let env: EnvironmentPermissions = serde_yaml::from_str("inheritAll: true").unwrap();
+        assert!(env.inherit_all);
+        assert!(env.allow.is_none());
+
+        let env: EnvironmentPermissions = serde_yaml::from_str("allow:\n  - key: PATH\n").unwrap();
+        assert!(!env.inherit_all);
+        assert!(!serde_yaml::to_string(&env).unwrap().contains("inheritAll"));
+    }
+
     #[test]
     fn test_comprehensive_wildcard_validation() {
         let permissions = Permissions {
-            storage: Some(PermissionList {
+            storage: Some(StoragePermissions {
                 allow: Some(vec![
                     StoragePermission {
                         uri: "fs://work/agent/**".to_string(),
@@ -642,18 +1091,22 @@ mod tests {
                     uri: "fs://work/agent/secret/*".to_string(),
                     access: vec![AccessType::Write],
                 }]),
+                scratch: None,
             }),
             network: Some(PermissionList {
                 allow: Some(vec![
                     NetworkPermission::Host(NetworkHostPermission {
                         host: "*.example.com".to_string(),
+                        ..Default::default()
                     }),
                     NetworkPermission::Host(NetworkHostPermission {
                         host: "api.service.com".to_string(),
+                        ..Default::default()
                     }),
                 ]),
                 deny: Some(vec![NetworkPermission::Host(NetworkHostPermission {
                     host: "*.malicious.com".to_string(),
+                    ..Default::default()
                 })]),
             }),
             // Test environment with valid keys (no wildcards allowed)
@@ -669,6 +1122,7 @@ mod tests {
                         key: "MY_DEBUG_VAR".to_string(),
                     },
                 ]),
+                ..Default::default()
             }),
             ..Default::default()
         };
@@ -734,6 +1188,18 @@ mod tests {
             32u64 * 1024 * 1024 * 1024
         );
 
+        // Test decimal suffixes
+        let memory_decimal = MemoryLimit::String("2G".to_string());
+        assert_eq!(memory_decimal.to_bytes().unwrap(), 2_000_000_000);
+        let memory_decimal = MemoryLimit::String("500M".to_string());
+        assert_eq!(memory_decimal.to_bytes().unwrap(), 500_000_000);
+        let memory_decimal = MemoryLimit::String("64k".to_string());
+        assert_eq!(memory_decimal.to_bytes().unwrap(), 64_000);
+
+        // Test values that overflow
+        let memory_huge = MemoryLimit::String("99999999999T".to_string());
+        assert!(memory_huge.to_bytes().is_err());
+
         // Test plain bytes (above minimum)
         let memory_bytes = MemoryLimit::String("131072".to_string()); // 128KB
         assert_eq!(memory_bytes.to_bytes().unwrap(), 131072);
@@ -808,6 +1274,42 @@ mod tests {
         assert_eq!(empty_limits.memory_bytes().unwrap(), None);
     }
 
+    #[test]
+    fn test_timeout_limit_to_duration() {
+        assert_eq!(
+            TimeoutLimit::String("500ms".to_string())
+                .to_duration()
+                .unwrap(),
+            Duration::from_millis(500)
+        );
+        assert_eq!(
+            TimeoutLimit::String("30s".to_string())
+                .to_duration()
+                .unwrap(),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            TimeoutLimit::String("2m".to_string())
+                .to_duration()
+                .unwrap(),
+            Duration::from_secs(120)
+        );
+        assert_eq!(
+            TimeoutLimit::Number(10).to_duration().unwrap(),
+            Duration::from_secs(10)
+        );
+
+        assert!(TimeoutLimit::String("10".to_string())
+            .to_duration()
+            .is_err());
+        assert!(TimeoutLimit::String("0s".to_string())
+            .to_duration()
+            .is_err());
+        assert!(TimeoutLimit::String("fast".to_string())
+            .to_duration()
+            .is_err());
+    }
+
     #[test]
     fn test_resource_limits_validation() {
         // Valid new format
@@ -819,6 +1321,7 @@ mod tests {
             cpu: None,
             memory: None,
             io: None,
+            timeouts: None,
         };
         assert!(valid_new.validate().is_ok());
 
@@ -828,6 +1331,7 @@ mod tests {
             cpu: Some(1.5),
             memory: Some(512),
             io: Some(1000),
+            timeouts: None,
         };
         assert!(valid_legacy.validate().is_ok());
 
@@ -840,6 +1344,7 @@ mod tests {
             cpu: None,
             memory: None,
             io: None,
+            timeouts: None,
         };
         assert!(invalid_new.validate().is_err());
 
@@ -849,6 +1354,7 @@ mod tests {
             cpu: Some(-1.0),
             memory: None,
             io: None,
+            timeouts: None,
         };
         assert!(invalid_legacy.validate().is_err());
     }
@@ -856,12 +1362,13 @@ mod tests {
     #[test]
     fn test_k8s_style_permissions_validation() {
         let permissions = Permissions {
-            storage: Some(PermissionList {
+            storage: Some(StoragePermissions {
                 allow: Some(vec![StoragePermission {
                     uri: "fs://workspace/**".to_string(),
                     access: vec![AccessType::Read, AccessType::Write],
                 }]),
                 deny: None,
+                scratch: None,
             }),
             network: None,
             environment: None,
@@ -874,8 +1381,12 @@ mod tests {
                 cpu: None,
                 memory: None,
                 io: None,
+                timeouts: None,
             }),
             ipc: None,
+            call: None,
+            introspection: false,
+            system: None,
         };
 
         assert!(permissions.validate().is_ok());
@@ -884,12 +1395,13 @@ mod tests {
     #[test]
     fn test_invalid_wildcard_combinations() {
         let mut permissions = Permissions {
-            storage: Some(PermissionList {
+            storage: Some(StoragePermissions {
                 allow: Some(vec![StoragePermission {
                     uri: "fs://work/agent/**file".to_string(),
                     access: vec![AccessType::Read],
                 }]),
                 deny: None,
+                scratch: None,
             }),
             ..Default::default()
         };
@@ -900,6 +1412,7 @@ mod tests {
         permissions.network = Some(PermissionList {
             allow: Some(vec![NetworkPermission::Host(NetworkHostPermission {
                 host: "example*.com".to_string(), // Invalid: * in middle
+                ..Default::default()
             })]),
             deny: None,
         });
@@ -910,6 +1423,7 @@ mod tests {
             allow: Some(vec![EnvironmentPermission {
                 key: "PATH_WITH_WILDCARD_*".to_string(),
             }]),
+            ..Default::default()
         });
         assert!(permissions.validate().is_err());
     }
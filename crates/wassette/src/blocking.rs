@@ -0,0 +1,294 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! A synchronous facade over [`LifecycleManager`] for applications that do
+//! not run an async runtime, enabled with the `blocking` feature.
+//!
+//! A [`BlockingLifecycleManager`] owns a multi-threaded Tokio runtime and
+//! runs each call to completion on it, with the same errors as the async
+//! API. Dropping it drops the manager and shuts the runtime down.
+//!
+//! ```no_run
+//! use wassette::blocking::BlockingLifecycleManager;
+//!
+//! fn main() -> anyhow::Result<()> {
+//!     let manager = BlockingLifecycleManager::new("/var/lib/wassette/components")?;
+//!     let outcome = manager.load_component("oci://ghcr.io/microsoft/fetch-rs:latest")?;
+//!     manager.grant_permission(
+//!         &outcome.component_id,
+//!         "network",
+//!         &serde_json::json!({"host": "example.com"}),
+//!     )?;
+//!     let result = manager.execute_component_call(
+//!         &outcome.component_id,
+//!         "fetch",
+//!         r#"{"url": "https://example.com/"}"#,
+//!     )?;
+//!     println!("{result}");
+//!     Ok(())
+//! }
+//! ```
+//!
+//! The facade must not be used from within an async runtime: blocking on
+//! one of its threads panics. Async applications use [`LifecycleManager`]
+//! directly.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use tokio::runtime::Runtime;
+
+use crate::{
+    ComponentLoadOutcome, ComponentUnloadOutcome, HostGrant, LifecycleBuilder, LifecycleManager,
+    UnloadOptions,
+};
+
+/// How long dropping a [`BlockingLifecycleManager`] waits for tasks still
+/// running on its runtime
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A [`LifecycleManager`] with synchronous methods, running on a runtime of
+/// its own.
+pub struct BlockingLifecycleManager {
+    // Both are only taken when dropped
+    manager: Option<LifecycleManager>,
+    runtime: Option<Runtime>,
+}
+
+impl BlockingLifecycleManager {
+    /// Creates a manager for `component_dir` with default configuration and
+    /// eager loading, like [`LifecycleManager::new`].
+    pub fn new(component_dir: impl AsRef<Path>) -> Result<Self> {
+        Self::from_builder(LifecycleManager::builder(component_dir))
+    }
+
+    /// Builds the manager configured by `builder` on a new runtime.
+    pub fn from_builder(builder: LifecycleBuilder) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .thread_name("wassette-blocking")
+            .build()
+            .context("Failed to start the async runtime")?;
+        let manager = runtime.block_on(builder.build())?;
+        Ok(Self {
+            manager: Some(manager),
+            runtime: Some(runtime),
+        })
+    }
+
+    /// The wrapped manager, for methods that are not mirrored here; run
+    /// its async methods with [`Self::block_on`].
+    pub fn manager(&self) -> &LifecycleManager {
+        self.manager
+            .as_ref()
+            .expect("manager is present until dropped")
+    }
+
+    /// Runs `future` to completion on the manager's runtime.
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime
+            .as_ref()
+            .expect("runtime is present until dropped")
+            .block_on(future)
+    }
+
+    /// See [`LifecycleManager::load_component`].
+    pub fn load_component(&self, uri: &str) -> Result<ComponentLoadOutcome> {
+        self.block_on(self.manager().load_component(uri))
+    }
+
+    /// See [`LifecycleManager::unload_component`].
+    pub fn unload_component(&self, id: &str) -> Result<()> {
+        self.block_on(self.manager().unload_component(id))
+    }
+
+    /// See [`LifecycleManager::unload_component_with_options`].
+    pub fn unload_component_with_options(
+        &self,
+        id: &str,
+        options: UnloadOptions,
+    ) -> Result<ComponentUnloadOutcome> {
+        self.block_on(self.manager().unload_component_with_options(id, options))
+    }
+
+    /// See [`LifecycleManager::list_components`].
+    pub fn list_components(&self) -> Vec<String> {
+        self.block_on(self.manager().list_components())
+    }
+
+    /// See [`LifecycleManager::list_tools`].
+    pub fn list_tools(&self) -> Vec<Arc<Value>> {
+        self.block_on(self.manager().list_tools())
+    }
+
+    /// See [`LifecycleManager::execute_component_call`].
+    pub fn execute_component_call(
+        &self,
+        component_id: &str,
+        function_name: &str,
+        parameters: &str,
+    ) -> Result<String> {
+        self.block_on(self.manager().execute_component_call(
+            component_id,
+            function_name,
+            parameters,
+        ))
+    }
+
+    /// See [`LifecycleManager::attach_policy`].
+    pub fn attach_policy(&self, component_id: &str, policy_uri: &str) -> Result<()> {
+        self.block_on(self.manager().attach_policy(component_id, policy_uri))
+    }
+
+    /// See [`LifecycleManager::detach_policy`].
+    pub fn detach_policy(&self, component_id: &str) -> Result<()> {
+        self.block_on(self.manager().detach_policy(component_id))
+    }
+
+    /// See [`LifecycleManager::grant_permission`].
+    pub fn grant_permission(
+        &self,
+        component_id: &str,
+        permission_type: &str,
+        details: &Value,
+    ) -> Result<()> {
+        self.block_on(
+            self.manager()
+                .grant_permission(component_id, permission_type, details),
+        )
+    }
+
+    /// See [`LifecycleManager::grant_network_hosts`].
+    pub fn grant_network_hosts(
+        &self,
+        component_id: &str,
+        hosts: &[String],
+    ) -> Result<Vec<HostGrant>> {
+        self.block_on(self.manager().grant_network_hosts(component_id, hosts))
+    }
+
+    /// See [`LifecycleManager::revoke_permission`].
+    pub fn revoke_permission(
+        &self,
+        component_id: &str,
+        permission_type: &str,
+        details: &Value,
+    ) -> Result<()> {
+        self.block_on(
+            self.manager()
+                .revoke_permission(component_id, permission_type, details),
+        )
+    }
+
+    /// See [`LifecycleManager::reset_permission`].
+    pub fn reset_permission(&self, component_id: &str) -> Result<()> {
+        self.block_on(self.manager().reset_permission(component_id))
+    }
+
+    /// See [`LifecycleManager::list_component_secrets`].
+    pub fn list_component_secrets(
+        &self,
+        component_id: &str,
+        show_values: bool,
+    ) -> Result<HashMap<String, Option<String>>> {
+        self.block_on(
+            self.manager()
+                .list_component_secrets(component_id, show_values),
+        )
+    }
+
+    /// See [`LifecycleManager::set_component_secrets`].
+    pub fn set_component_secrets(
+        &self,
+        component_id: &str,
+        secrets: &[(String, String)],
+    ) -> Result<()> {
+        self.block_on(self.manager().set_component_secrets(component_id, secrets))
+    }
+
+    /// See [`LifecycleManager::delete_component_secrets`].
+    pub fn delete_component_secrets(&self, component_id: &str, keys: &[String]) -> Result<()> {
+        self.block_on(self.manager().delete_component_secrets(component_id, keys))
+    }
+}
+
+impl Drop for BlockingLifecycleManager {
+    fn drop(&mut self) {
+        let Some(runtime) = self.runtime.take() else {
+            return;
+        };
+        // Drop the manager inside the runtime, in case its teardown spawns
+        // tasks, then give running tasks a moment to finish
+        {
+            let _guard = runtime.enter();
+            self.manager.take();
+        }
+        runtime.shutdown_timeout(SHUTDOWN_TIMEOUT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TEST_COMPONENT_ID;
+
+    #[test]
+    fn test_blocking_manager_from_plain_main() -> Result<()> {
+        let wasm = crate::testing::build_rust_component(
+            std::env::current_dir()?.join("../../examples/fetch-rs"),
+            "fetch_rs",
+        )?;
+        let component_dir = tempfile::tempdir()?;
+        let secrets_dir = tempfile::tempdir()?;
+        let manager = BlockingLifecycleManager::from_builder(
+            LifecycleManager::builder(component_dir.path()).with_secrets_dir(secrets_dir.path()),
+        )?;
+        assert!(manager.list_components().is_empty());
+
+        let outcome = manager.load_component(&format!("file://{}", wasm.display()))?;
+        assert_eq!(outcome.component_id, TEST_COMPONENT_ID);
+        assert_eq!(manager.list_components(), vec![TEST_COMPONENT_ID]);
+        assert!(!manager.list_tools().is_empty());
+
+        let result = manager.execute_component_call(
+            TEST_COMPONENT_ID,
+            "fetch",
+            r#"{"url": "https://denied.test/"}"#,
+        )?;
+        assert!(result.contains("HttpRequestDenied"));
+
+        manager.grant_permission(
+            TEST_COMPONENT_ID,
+            "network",
+            &serde_json::json!({"host": "example.com"}),
+        )?;
+        manager.revoke_permission(
+            TEST_COMPONENT_ID,
+            "network",
+            &serde_json::json!({"host": "example.com"}),
+        )?;
+
+        manager.set_component_secrets(
+            TEST_COMPONENT_ID,
+            &[("TOKEN".to_string(), "secret".to_string())],
+        )?;
+        let secrets = manager.list_component_secrets(TEST_COMPONENT_ID, true)?;
+        assert_eq!(secrets["TOKEN"].as_deref(), Some("secret"));
+        manager.delete_component_secrets(TEST_COMPONENT_ID, &["TOKEN".to_string()])?;
+
+        // Errors are those of the async API
+        assert!(manager
+            .grant_permission("missing", "network", &serde_json::json!({"host": "a.test"}))
+            .is_err());
+
+        manager.unload_component(TEST_COMPONENT_ID)?;
+        assert!(manager.list_components().is_empty());
+        drop(manager);
+        Ok(())
+    }
+}
@@ -0,0 +1,198 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! The `wassette:broker/call` host interface, through which a component
+//! calls a tool of another loaded component:
+//!
+//! ```wit
+//! package wassette:broker;
+//!
+//! interface call {
+//!     /// Call `tool` of `component` with JSON `arguments`, returning the
+//!     /// JSON result.
+//!     call: func(component: string, tool: string, arguments: string) -> result<string, string>;
+//! }
+//! ```
+//!
+//! Only calls allowed by the caller's `permissions.call` rules are made. The
+//! call runs through [`LifecycleManager::execute_component_call`] like a
+//! call from a client, so the target runs under its own policy. Calls may
+//! nest up to [`MAX_BROKERED_CALL_DEPTH`] deep, and each one is announced
+//! with [`LifecycleEvent::BrokeredCall`].
+
+use anyhow::Result;
+use policy::{CallPermission, PermissionList};
+use tracing::{info, warn};
+use wasmtime::component::Linker;
+use wasmtime::StoreContextMut;
+
+use crate::{LifecycleEvent, LifecycleManager, PermissionError, WasiState, WassetteWasiState};
+
+/// Name of the host interface components import to call other components.
+pub const BROKER_INTERFACE: &str = "wassette:broker/call";
+
+/// Number of brokered calls that may be nested in each other.
+pub const MAX_BROKERED_CALL_DEPTH: usize = 4;
+
+tokio::task_local! {
+    /// Number of brokered calls the running call is nested in.
+    static CALL_DEPTH: usize;
+}
+
+/// Number of brokered calls the running call is nested in; zero for a call
+/// made by a client.
+pub(crate) fn current_depth() -> usize {
+    CALL_DEPTH.try_with(|depth| *depth).unwrap_or(0)
+}
+
+/// Makes the brokered calls of one component instance.
+#[derive(Clone)]
+pub(crate) struct Broker {
+    manager: LifecycleManager,
+    caller: String,
+    depth: usize,
+    grants: PermissionList<CallPermission>,
+}
+
+impl Broker {
+    pub(crate) fn new(
+        manager: LifecycleManager,
+        caller: &str,
+        grants: PermissionList<CallPermission>,
+    ) -> Self {
+        Self {
+            manager,
+            caller: caller.to_string(),
+            depth: current_depth(),
+            grants,
+        }
+    }
+
+    /// Call `tool` of `component` for the caller, if its policy allows it.
+    pub(crate) async fn call(
+        &self,
+        component: &str,
+        tool: &str,
+        arguments: &str,
+    ) -> std::result::Result<String, String> {
+        if !self.grants.allows_call(component, tool) {
+            let error = PermissionError::CallDenied {
+                component: component.to_string(),
+                tool: tool.to_string(),
+            };
+            warn!(caller = %self.caller, %component, %tool, "Brokered call denied");
+            self.manager.emit_permission_denied(&self.caller, &error);
+            return Err(error.to_user_message(&self.caller));
+        }
+        let depth = self.depth + 1;
+        if depth > MAX_BROKERED_CALL_DEPTH {
+            return Err(format!(
+                "Component '{}' cannot call tool '{tool}' of component '{component}': brokered calls may only be nested {MAX_BROKERED_CALL_DEPTH} deep",
+                self.caller
+            ));
+        }
+
+        info!(caller = %self.caller, %component, %tool, depth, "Brokered call");
+        self.manager.events.emit(LifecycleEvent::BrokeredCall {
+            component_id: self.caller.clone(),
+            target_component_id: component.to_string(),
+            tool: tool.to_string(),
+            depth,
+        });
+        CALL_DEPTH
+            .scope(
+                depth,
+                self.manager
+                    .execute_component_call(component, tool, arguments),
+            )
+            .await
+            .map_err(|error| format!("{error:#}"))
+    }
+}
+
+/// Define [`BROKER_INTERFACE`] in `linker`.
+pub(crate) fn add_to_linker(linker: &mut Linker<WassetteWasiState<WasiState>>) -> Result<()> {
+    linker.instance(BROKER_INTERFACE)?.func_wrap_async(
+        "call",
+        |store: StoreContextMut<'_, WassetteWasiState<WasiState>>,
+         (component, tool, arguments): (String, String, String)| {
+            let broker = store.data().inner.broker.clone();
+            Box::new(async move {
+                let result = match broker {
+                    Some(broker) => broker.call(&component, &tool, &arguments).await,
+                    None => Err("Calling other components requires `permissions.call` in the component's policy".to_string()),
+                };
+                Ok((result,))
+            })
+        },
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{create_test_manager, TEST_COMPONENT_ID};
+
+    fn grants(yaml: &str) -> PermissionList<CallPermission> {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_brokered_calls_are_checked_and_announced() -> Result<()> {
+        let manager = create_test_manager().await?;
+        manager.load_test_component().await?;
+        let mut events = manager.subscribe();
+        let broker = Broker::new(
+            manager.manager().clone(),
+            "caller",
+            grants("allow:\n  - component: fetch_rs\n    tools: [fetch]\n"),
+        );
+
+        let error = broker
+            .call(TEST_COMPONENT_ID, "other", "{}")
+            .await
+            .unwrap_err();
+        assert!(error.contains("component: \"fetch_rs\"\n        tools: [\"other\"]"));
+        assert_eq!(
+            events.try_recv()?,
+            LifecycleEvent::PermissionDenied {
+                component_id: "caller".to_string(),
+                permission_type: "call".to_string(),
+                details: serde_json::json!({ "component": "fetch_rs", "tool": "other" }),
+            }
+        );
+
+        // The target runs under its own policy, which grants no network access
+        let result = broker
+            .call(
+                TEST_COMPONENT_ID,
+                "fetch",
+                r#"{"url": "https://denied.test/"}"#,
+            )
+            .await
+            .unwrap();
+        assert!(result.contains("HttpRequestDenied"));
+        assert_eq!(
+            events.try_recv()?,
+            LifecycleEvent::BrokeredCall {
+                component_id: "caller".to_string(),
+                target_component_id: TEST_COMPONENT_ID.to_string(),
+                tool: "fetch".to_string(),
+                depth: 1,
+            }
+        );
+
+        let nested = CALL_DEPTH
+            .scope(MAX_BROKERED_CALL_DEPTH, async {
+                Broker::new(manager.manager().clone(), "caller", broker.grants.clone())
+            })
+            .await;
+        let error = nested
+            .call(TEST_COMPONENT_ID, "fetch", "{}")
+            .await
+            .unwrap_err();
+        assert!(error.contains("nested 4 deep"));
+        Ok(())
+    }
+}
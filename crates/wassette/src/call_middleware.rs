@@ -0,0 +1,60 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Embedder middleware run around every component tool call.
+//!
+//! A [`CallMiddleware`] sees a call's arguments before the component runs and
+//! its result afterwards, and can change either. Register middleware with
+//! [`LifecycleBuilder::with_call_middleware`](crate::LifecycleBuilder::with_call_middleware);
+//! it wraps [`LifecycleManager::execute_component_call`](crate::LifecycleManager::execute_component_call)
+//! and therefore also the tool calls made through the MCP server.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::CallTiming;
+
+/// Future returned by [`CallMiddleware`] hooks.
+pub type MiddlewareFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Hooks run before and after each component tool call.
+///
+/// Middleware runs in the order it was registered, for `before` as well as
+/// `after`. Both hooks do nothing by default, so a middleware only
+/// implements the one it needs.
+pub trait CallMiddleware: Send + Sync + 'static {
+    /// Called before the component runs, with the arguments it will be
+    /// called with. Returning an error stops the call: the component and
+    /// the remaining `before` hooks are not run, and the call fails with
+    /// that error.
+    fn before<'a>(
+        &'a self,
+        component_id: &'a str,
+        tool: &'a str,
+        arguments: &'a mut Value,
+    ) -> MiddlewareFuture<'a, Result<()>> {
+        let _ = (component_id, tool, arguments);
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Called with the result of the call, which it may replace. Runs for
+    /// every middleware whose `before` succeeded, also when the component
+    /// failed or a later middleware stopped the call.
+    fn after<'a>(
+        &'a self,
+        component_id: &'a str,
+        tool: &'a str,
+        result: &'a mut Result<Value>,
+        timing: &'a CallTiming,
+    ) -> MiddlewareFuture<'a, ()> {
+        let _ = (component_id, tool, result, timing);
+        Box::pin(async {})
+    }
+}
+
+/// Middleware registered with a lifecycle manager, in registration order.
+pub(crate) type CallMiddlewares = Arc<[Arc<dyn CallMiddleware>]>;
@@ -0,0 +1,236 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! A numbered log of the changes to the component registry, so tools that
+//! mirror wassette's state elsewhere can catch up instead of diffing
+//! component listings.
+//!
+//! Every [`LifecycleEvent`] that changes which components and tools are
+//! served, or under what policy, and every lifecycle hook that runs, is
+//! numbered and kept in memory. With a
+//! changelog file configured, each change is also appended to it as a JSON
+//! line, and the last sequence number is stored next to it in
+//! `<file>.seq`, so numbering continues across restarts even when the log
+//! is rotated.
+
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::LifecycleEvent;
+
+/// Number of recent changes kept in memory for
+/// [`LifecycleManager::changes_since`](crate::LifecycleManager::changes_since).
+pub const CHANGES_RETAINED: usize = 1000;
+
+/// A change to the component registry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangeEvent {
+    /// Sequence number, one higher than that of the previous change
+    pub seq: u64,
+    /// When the change happened, as a Unix timestamp in seconds
+    pub recorded_at: u64,
+    /// What changed
+    #[serde(flatten)]
+    pub event: LifecycleEvent,
+}
+
+struct ChangeLogState {
+    last_seq: u64,
+    recent: VecDeque<ChangeEvent>,
+}
+
+/// Recent registry changes, optionally backed by a changelog file.
+#[derive(Clone)]
+pub(crate) struct ChangeLog {
+    path: Option<PathBuf>,
+    state: Arc<Mutex<ChangeLogState>>,
+}
+
+impl Default for ChangeLog {
+    fn default() -> Self {
+        Self {
+            path: None,
+            state: Arc::new(Mutex::new(ChangeLogState {
+                last_seq: 0,
+                recent: VecDeque::new(),
+            })),
+        }
+    }
+}
+
+fn seq_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".seq");
+    path.with_file_name(file_name)
+}
+
+async fn read_seq_file(path: &Path) -> Result<u64> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(content) => content
+            .trim()
+            .parse()
+            .with_context(|| format!("Failed to parse sequence number in {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// The last [`CHANGES_RETAINED`] changes in the changelog at `path`; lines
+/// that cannot be parsed are skipped.
+async fn read_changelog(path: &Path) -> Result<VecDeque<ChangeEvent>> {
+    let content = match tokio::fs::read_to_string(path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(VecDeque::new()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read changelog {}", path.display()))
+        }
+    };
+    let mut recent = VecDeque::new();
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        match serde_json::from_str::<ChangeEvent>(line) {
+            Ok(change) => {
+                recent.push_back(change);
+                if recent.len() > CHANGES_RETAINED {
+                    recent.pop_front();
+                }
+            }
+            Err(error) => {
+                warn!(%error, path = %path.display(), "Skipping unreadable changelog line")
+            }
+        }
+    }
+    Ok(recent)
+}
+
+impl ChangeLog {
+    /// Open the changelog at `path`, picking up numbering where the last
+    /// run left off; without a path, changes are only kept in memory.
+    pub(crate) async fn open(path: Option<PathBuf>) -> Result<Self> {
+        let Some(path) = path else {
+            return Ok(Self::default());
+        };
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let recent = read_changelog(&path).await?;
+        let last_seq = read_seq_file(&seq_path(&path))
+            .await?
+            .max(recent.back().map_or(0, |change| change.seq));
+        Ok(Self {
+            path: Some(path),
+            state: Arc::new(Mutex::new(ChangeLogState { last_seq, recent })),
+        })
+    }
+
+    /// Number and record `event` if it changes the registry. Failing to
+    /// write the changelog is logged; the change is still kept in memory.
+    pub(crate) fn record(&self, event: &LifecycleEvent) {
+        if !event.is_recorded() {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.last_seq += 1;
+        let change = ChangeEvent {
+            seq: state.last_seq,
+            recorded_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            event: event.clone(),
+        };
+        if let Some(path) = &self.path {
+            if let Err(error) = append(path, &change) {
+                warn!(%error, path = %path.display(), "Failed to write changelog");
+            }
+        }
+        state.recent.push_back(change);
+        if state.recent.len() > CHANGES_RETAINED {
+            state.recent.pop_front();
+        }
+    }
+
+    /// Changes with a sequence number above `seq`, oldest first.
+    pub(crate) fn since(&self, seq: u64) -> Vec<ChangeEvent> {
+        self.state
+            .lock()
+            .unwrap()
+            .recent
+            .iter()
+            .filter(|change| change.seq > seq)
+            .cloned()
+            .collect()
+    }
+
+    /// Sequence number of the most recent change, 0 if there was none.
+    pub(crate) fn last_seq(&self) -> u64 {
+        self.state.lock().unwrap().last_seq
+    }
+}
+
+fn append(path: &Path, change: &ChangeEvent) -> Result<()> {
+    let mut line = serde_json::to_string(change)?;
+    line.push('\n');
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())?;
+    std::fs::write(seq_path(path), change.seq.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sequence_survives_reopen_and_rotation() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("changes.jsonl");
+        let changes = ChangeLog::open(Some(path.clone())).await?;
+        changes.record(&LifecycleEvent::ComponentLoaded {
+            component_id: "fetch".to_string(),
+            tools: vec!["fetch".to_string()],
+        });
+        changes.record(&LifecycleEvent::SecretChanged {
+            component_id: "fetch".to_string(),
+            keys: vec!["TOKEN".to_string()],
+        });
+        changes.record(&LifecycleEvent::PolicyDetached {
+            component_id: "fetch".to_string(),
+        });
+        assert_eq!(changes.last_seq(), 2);
+        assert_eq!(std::fs::read_to_string(&path)?.lines().count(), 2);
+
+        let reopened = ChangeLog::open(Some(path.clone())).await?;
+        let since = reopened.since(1);
+        assert_eq!(since.len(), 1);
+        assert_eq!(
+            since[0].event,
+            LifecycleEvent::PolicyDetached {
+                component_id: "fetch".to_string()
+            }
+        );
+
+        std::fs::remove_file(&path)?;
+        let rotated = ChangeLog::open(Some(path.clone())).await?;
+        assert!(rotated.since(0).is_empty());
+        rotated.record(&LifecycleEvent::ComponentUnloaded {
+            component_id: "fetch".to_string(),
+        });
+        assert_eq!(rotated.since(0)[0].seq, 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! The toolchain that compiled a precompiled (`.cwasm`) component.
+//!
+//! Every precompiled artifact is recorded in the component metadata together
+//! with the wasmtime version, target triple, and engine flags that produced
+//! it. The same record decides whether the artifact can be reused: a cached
+//! `.cwasm` whose [`CompileInfo::fingerprint`] differs from the running
+//! engine's is recompiled instead of deserialized.
+
+use anyhow::{Context, Result};
+use object::{Object, ObjectSection};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use wasmtime::Engine;
+
+/// Section of a precompiled artifact in which wasmtime records its version.
+const ENGINE_SECTION: &str = ".wasmtime.engine";
+
+/// The smallest valid component: the preamble of an empty component binary.
+const EMPTY_COMPONENT: &[u8] = b"\0asm\x0d\x00\x01\x00";
+
+/// Toolchain and settings a precompiled component was compiled with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompileInfo {
+    /// Version of wasmtime (and the Cranelift it bundles)
+    pub wasmtime_version: String,
+    /// Target triple the code was generated for
+    pub target: String,
+    /// Engine configuration flags, as `name=value`
+    pub engine_flags: Vec<String>,
+}
+
+impl CompileInfo {
+    /// Describe what `engine` compiles with, given the flags it was
+    /// configured with. The wasmtime version is read back from a compiled
+    /// empty component, so it is the one actually linked in.
+    pub(crate) fn for_engine(engine: &Engine, engine_flags: Vec<String>) -> Result<Self> {
+        let artifact = engine
+            .precompile_component(EMPTY_COMPONENT)
+            .context("Failed to precompile probe component")?;
+        let wasmtime_version = artifact_wasmtime_version(&artifact)
+            .context("Precompiled artifact does not record a wasmtime version")?;
+        Ok(Self {
+            wasmtime_version,
+            target: target_lexicon::HOST.to_string(),
+            engine_flags,
+        })
+    }
+
+    /// A digest of everything that makes precompiled code reusable; two
+    /// artifacts with the same fingerprint are interchangeable.
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.wasmtime_version.as_bytes());
+        hasher.update([0]);
+        hasher.update(self.target.as_bytes());
+        for flag in &self.engine_flags {
+            hasher.update([0]);
+            hasher.update(flag.as_bytes());
+        }
+        format!("sha256:{}", hex::encode(hasher.finalize()))
+    }
+}
+
+/// The version string wasmtime writes into the engine section of an
+/// artifact: a format byte, a length byte, then the version.
+fn artifact_wasmtime_version(artifact: &[u8]) -> Option<String> {
+    let file = object::File::parse(artifact).ok()?;
+    let data = file.section_by_name(ENGINE_SECTION)?.data().ok()?;
+    let (&len, rest) = data.get(1..)?.split_first()?;
+    let version = std::str::from_utf8(rest.get(..usize::from(len))?).ok()?;
+    (!version.is_empty()).then(|| version.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_info_reads_linked_wasmtime_version() -> Result<()> {
+        let mut config = wasmtime::Config::new();
+        config.wasm_component_model(true);
+        let engine = Engine::new(&config)?;
+
+        let info = CompileInfo::for_engine(&engine, vec!["wasm_component_model=true".into()])?;
+        assert!(info.wasmtime_version.starts_with("36."));
+        assert_eq!(info.target, target_lexicon::HOST.to_string());
+
+        let mut other_flags = info.clone();
+        other_flags.engine_flags.push("async_support=true".into());
+        assert_eq!(info.fingerprint(), info.clone().fingerprint());
+        assert_ne!(info.fingerprint(), other_flags.fingerprint());
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Which components in the component directory a lifecycle manager serves.
+//!
+//! Several servers can share one component directory while each serves only
+//! some of its components. A [`ComponentFilter`] lists the component IDs a
+//! manager serves and the ones it ignores; components it does not serve are
+//! neither compiled nor listed, and loading them fails. Entries are matched
+//! against the whole ID, and `*` matches any run of characters, so
+//! `fetch-*` covers `fetch-rs` and `fetch-js`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::source_policy::glob_match;
+
+/// Allow and deny lists of component IDs or ID patterns. The default filter
+/// serves every component.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComponentFilter {
+    /// Components to serve; every component unless set
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+    /// Components to ignore, even if they are allowed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
+}
+
+impl ComponentFilter {
+    /// Serve only the components matching `patterns`.
+    pub fn allow(patterns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            allow: patterns.into_iter().map(Into::into).collect(),
+            deny: Vec::new(),
+        }
+    }
+
+    /// Serve every component except the ones matching `patterns`.
+    pub fn deny(patterns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            allow: Vec::new(),
+            deny: patterns.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Also ignore the components matching `patterns`.
+    pub fn and_deny(mut self, patterns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.deny.extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Whether the component with this ID is served.
+    pub fn allows(&self, component_id: &str) -> bool {
+        let matches = |pattern: &String| {
+            // IDs have no path segments, so `*` may match anything
+            glob_match(
+                pattern.replace('*', "**").as_bytes(),
+                component_id.as_bytes(),
+            )
+        };
+        (self.allow.is_empty() || self.allow.iter().any(matches)) && !self.deny.iter().any(matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_matches_ids_and_patterns() {
+        assert!(ComponentFilter::default().allows("anything"));
+
+        let filter = ComponentFilter::allow(["fetch-*", "time-server"]).and_deny(["*-debug"]);
+        assert!(filter.allows("fetch-rs"));
+        assert!(filter.allows("time-server"));
+        assert!(!filter.allows("time-server-2"));
+        assert!(!filter.allows("fetch-debug"));
+        assert!(!filter.allows("filesystem"));
+
+        let filter = ComponentFilter::deny(["scratch"]);
+        assert!(filter.allows("fetch-rs"));
+        assert!(!filter.allows("scratch"));
+    }
+}
@@ -7,21 +7,71 @@
 use std::io::BufReader;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 use anyhow::{anyhow, Context, Result};
+use policy::PolicyFormat;
+use serde::Serialize;
 use sha2::{Digest, Sha256};
 use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tokio::task::spawn_blocking;
 
+use crate::download_cache::DownloadCache;
 use crate::loader::DownloadedResource;
 use crate::{ComponentMetadata, ValidationStamp};
 
+/// Default age after which [`ComponentStorage::garbage_collect`] removes
+/// temporary files left in the downloads directory.
+pub const DEFAULT_GC_TEMP_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Suffixes of the temporary files written in the downloads directory: blobs
+/// being copied into the cache and index updates not yet moved into place.
+const DOWNLOAD_TEMP_SUFFIXES: [&str; 2] = [".part", ".tmp"];
+
+/// What [`ComponentStorage::garbage_collect`] removes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GcOptions {
+    /// Temporary files in the downloads directory are removed once they are
+    /// older than this; younger ones may belong to a download in progress
+    pub temp_max_age: Duration,
+    /// Only report what would be removed, without removing anything
+    pub dry_run: bool,
+}
+
+impl Default for GcOptions {
+    fn default() -> Self {
+        Self {
+            temp_max_age: DEFAULT_GC_TEMP_MAX_AGE,
+            dry_run: false,
+        }
+    }
+}
+
+/// Summary of a [`ComponentStorage::garbage_collect`] run. File names are
+/// relative to the component directory.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GcReport {
+    /// Whether nothing was removed, and the report lists what would have been
+    pub dry_run: bool,
+    /// Precompiled (`.cwasm`) files of components with no `.wasm` file
+    pub precompiled: Vec<String>,
+    /// Metadata files of components with no `.wasm` file
+    pub metadata: Vec<String>,
+    /// Temporary files in the downloads directory older than the maximum age
+    pub temp_files: Vec<String>,
+    /// Number of files removed, or that would be in a dry run
+    pub files_removed: usize,
+    /// Total size of those files in bytes
+    pub bytes_freed: u64,
+}
+
 /// Handles filesystem layout and metadata persistence for components.
 #[derive(Clone)]
 pub struct ComponentStorage {
     root: PathBuf,
     downloads_dir: PathBuf,
     downloads_semaphore: Arc<Semaphore>,
+    download_cache: DownloadCache,
 }
 
 impl ComponentStorage {
@@ -45,6 +95,7 @@ impl ComponentStorage {
 
         Ok(Self {
             root,
+            download_cache: DownloadCache::new(downloads_dir.clone()),
             downloads_dir,
             downloads_semaphore: Arc::new(Semaphore::new(max_concurrent_downloads.max(1))),
         })
@@ -61,6 +112,11 @@ impl ComponentStorage {
         &self.downloads_dir
     }
 
+    /// Hash-indexed cache of downloaded artifacts kept in the downloads directory.
+    pub(crate) fn download_cache(&self) -> &DownloadCache {
+        &self.download_cache
+    }
+
     async fn acquire_download_permit(&self) -> OwnedSemaphorePermit {
         self.downloads_semaphore
             .clone()
@@ -74,9 +130,22 @@ impl ComponentStorage {
         self.root.join(format!("{component_id}.wasm"))
     }
 
-    /// Absolute path to the policy file associated with a component.
+    /// Absolute path to the policy file associated with a component:
+    /// `<id>.policy.json` if the component has a JSON policy, and
+    /// `<id>.policy.yaml` otherwise.
     pub fn policy_path(&self, component_id: &str) -> PathBuf {
-        self.root.join(format!("{component_id}.policy.yaml"))
+        let json_path = self.policy_path_as(component_id, PolicyFormat::Json);
+        if json_path.is_file() {
+            json_path
+        } else {
+            self.policy_path_as(component_id, PolicyFormat::Yaml)
+        }
+    }
+
+    /// Absolute path to a component's policy file written in `format`.
+    pub fn policy_path_as(&self, component_id: &str, format: PolicyFormat) -> PathBuf {
+        self.root
+            .join(format!("{component_id}.policy.{}", format.extension()))
     }
 
     /// Absolute path to the metadata JSON for a component.
@@ -106,12 +175,15 @@ impl ComponentStorage {
 
         self.remove_component_artifacts(component_id).await?;
 
-        resource.copy_to(self.root()).await.with_context(|| {
-            format!(
-                "Failed to copy component to destination: {}",
-                self.root.display()
-            )
-        })?;
+        resource
+            .copy_to(self.root(), component_id)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to copy component to destination: {}",
+                    self.root.display()
+                )
+            })?;
 
         Ok(self.component_path(component_id))
     }
@@ -182,12 +254,21 @@ impl ComponentStorage {
     }
 
     /// Remove a file if it exists, translating IO errors into `anyhow`.
+    ///
+    /// A symlink is removed, not its target. A directory in the file's place
+    /// was not created by wassette and is left alone with a warning.
     pub async fn remove_if_exists(
         &self,
         path: &Path,
         description: &str,
         component_id: &str,
     ) -> Result<()> {
+        if let Ok(metadata) = tokio::fs::symlink_metadata(path).await {
+            if metadata.is_dir() {
+                tracing::warn!(component_id = %component_id, path = %path.display(), "Leaving directory in place of the {}", description);
+                return Ok(());
+            }
+        }
         match tokio::fs::remove_file(path).await {
             Ok(()) => {
                 tracing::debug!(component_id = %component_id, path = %path.display(), "Removed {}", description);
@@ -244,22 +325,34 @@ impl ComponentStorage {
         })
     }
 
-    /// Check if the validation stamp matches the current file on disk.
-    pub async fn validate_stamp(path: &Path, stamp: &ValidationStamp) -> bool {
+    /// Check whether the file at `path` is still the one `stamp` was
+    /// created for.
+    ///
+    /// A stamp with a content hash is checked against the SHA-256 hash of
+    /// the file, so a file replaced with one of the same size and
+    /// modification time is caught, and a file only touched is not taken
+    /// for a new one. With `fast`, or for a stamp without a hash, only the
+    /// size and modification time are compared.
+    pub async fn validate_stamp(
+        path: &Path,
+        stamp: &ValidationStamp,
+        fast: bool,
+    ) -> StampValidation {
         let metadata = match tokio::fs::metadata(path).await {
             Ok(metadata) => metadata,
-            Err(_) => return false,
+            Err(_) => return StampValidation::Changed,
         };
 
         if metadata.len() != stamp.file_size {
-            return false;
+            return StampValidation::Changed;
         }
 
-        if let Some(expected_hash) = &stamp.content_hash {
-            match compute_file_hash(path).await {
-                Ok(actual_hash) => return actual_hash == *expected_hash,
-                Err(_) => return false,
-            }
+        if let (false, Some(expected_hash)) = (fast, &stamp.content_hash) {
+            return match compute_file_hash(path).await {
+                Ok(actual_hash) if actual_hash == *expected_hash => StampValidation::Valid,
+                Ok(_) => StampValidation::HashMismatch,
+                Err(_) => StampValidation::Changed,
+            };
         }
 
         let mtime = match metadata
@@ -272,15 +365,182 @@ impl ComponentStorage {
             .map(|d| d.as_secs())
         {
             Ok(mtime) => mtime,
-            Err(_) => return false,
+            Err(_) => return StampValidation::Changed,
         };
 
         if mtime != stamp.mtime {
-            return false;
+            return StampValidation::Changed;
         }
 
-        true
+        StampValidation::Valid
     }
+
+    /// Remove what components that are gone left behind: precompiled and
+    /// metadata files with no `.wasm` file next to them, and temporary
+    /// files in the downloads directory older than
+    /// [`GcOptions::temp_max_age`]. Policies are kept, since one may be
+    /// attached before its component is loaded again. With
+    /// [`GcOptions::dry_run`] nothing is removed.
+    pub async fn garbage_collect(&self, options: GcOptions) -> Result<GcReport> {
+        let mut report = GcReport {
+            dry_run: options.dry_run,
+            ..Default::default()
+        };
+
+        let mut entries = tokio::fs::read_dir(&self.root).await.with_context(|| {
+            format!("Failed to read component directory {}", self.root.display())
+        })?;
+        let mut orphans = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let Some(file_name) = path.file_name().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let precompiled = file_name.strip_suffix(&format!(".{}", crate::PRECOMPILED_EXT));
+            let metadata = file_name.strip_suffix(&format!(".{}", crate::METADATA_EXT));
+            let Some(component_id) = precompiled.or(metadata) else {
+                continue;
+            };
+            // A `.wasm` symlink counts only if it points at a file
+            let has_component = tokio::fs::metadata(self.component_path(component_id))
+                .await
+                .is_ok_and(|metadata| metadata.is_file());
+            if !has_component && entry.file_type().await?.is_file() {
+                orphans.push((path.clone(), precompiled.is_some()));
+            }
+        }
+        orphans.sort();
+        for (path, precompiled) in orphans {
+            let name = self
+                .remove_garbage(&path, options.dry_run, &mut report)
+                .await?;
+            if precompiled {
+                report.precompiled.push(name);
+            } else {
+                report.metadata.push(name);
+            }
+        }
+
+        // Held until the sweep is done, so no blob copy starts meanwhile and
+        // the ones in progress are known
+        let _downloads = self.download_cache.lock_index().await;
+        let now = SystemTime::now();
+        let mut temp_files = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.downloads_dir)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to read downloads directory {}",
+                    self.downloads_dir.display()
+                )
+            })?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let is_temp = path
+                .file_name()
+                .and_then(|s| s.to_str())
+                .is_some_and(|name| {
+                    DOWNLOAD_TEMP_SUFFIXES
+                        .iter()
+                        .any(|suffix| name.ends_with(suffix))
+                        && !self.download_cache.is_writing(name)
+                });
+            let metadata = entry.metadata().await?;
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .unwrap_or_default();
+            if is_temp && metadata.is_file() && age > options.temp_max_age {
+                temp_files.push(path);
+            }
+        }
+        temp_files.sort();
+        for path in temp_files {
+            let name = self
+                .remove_garbage(&path, options.dry_run, &mut report)
+                .await?;
+            report.temp_files.push(name);
+        }
+
+        Ok(report)
+    }
+
+    /// Remove `path`, unless `dry_run`, and count it in `report`. Returns
+    /// its name relative to the component directory.
+    async fn remove_garbage(
+        &self,
+        path: &Path,
+        dry_run: bool,
+        report: &mut GcReport,
+    ) -> Result<String> {
+        let size = tokio::fs::metadata(path)
+            .await
+            .map(|metadata| metadata.len())
+            .unwrap_or(0);
+        if !dry_run {
+            tokio::fs::remove_file(path)
+                .await
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+            tracing::debug!(path = %path.display(), "Removed orphaned file");
+        }
+        report.files_removed += 1;
+        report.bytes_freed += size;
+        let name = path.strip_prefix(&self.root).unwrap_or(path);
+        Ok(name.to_string_lossy().into_owned())
+    }
+}
+
+/// Outcome of checking a file against its [`ValidationStamp`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StampValidation {
+    /// The file is the one the stamp was created for
+    Valid,
+    /// The file is gone, or its size or modification time changed
+    Changed,
+    /// The file has the recorded size, but not the recorded content hash
+    HashMismatch,
+}
+
+impl StampValidation {
+    /// Whether the file is the one the stamp was created for.
+    pub fn is_valid(self) -> bool {
+        self == Self::Valid
+    }
+}
+
+/// ID of the component held by the component directory entry at `path`:
+/// the file stem of a `.wasm` regular file or of a symlink to one. Anything
+/// else named `*.wasm`, such as a directory or a dangling symlink, is not a
+/// component and is skipped with a debug log, so every scan of the component
+/// directory agrees on which components it holds.
+pub(crate) fn component_id_from_path(path: &Path) -> Option<String> {
+    if path.extension().and_then(|ext| ext.to_str()) != Some("wasm") {
+        return None;
+    }
+    let component_id = path.file_stem()?.to_str()?;
+    match std::fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() => Some(component_id.to_string()),
+        Ok(_) => {
+            tracing::debug!(path = %path.display(), "Skipping component directory entry that is not a file");
+            None
+        }
+        Err(error) => {
+            tracing::debug!(path = %path.display(), %error, "Skipping unreadable component directory entry");
+            None
+        }
+    }
+}
+
+/// SHA-256 digest of a file in `sha256:<hex>` form, as recorded in the
+/// component manifest.
+pub(crate) async fn file_digest(path: &Path) -> Result<String> {
+    Ok(format!("sha256:{}", compute_file_hash(path).await?))
+}
+
+/// SHA-256 hash of `bytes`, in the form validation stamps record it.
+pub(crate) fn content_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
 }
 
 async fn compute_file_hash(path: &Path) -> Result<String> {
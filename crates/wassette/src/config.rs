@@ -5,14 +5,35 @@
 //! [`LifecycleManager`](crate::LifecycleManager).
 
 use std::collections::HashMap;
+use std::future::Future;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
+use crate::call_middleware::CallMiddleware;
+use crate::component_filter::ComponentFilter;
+use crate::download_cache::DownloadsRetention;
+use crate::embedded::{EmbeddedComponent, StaticComponentSource};
+use crate::health::HealthCheckConfig;
+use crate::http::OutboundHttpConfig;
+use crate::manifest::MANIFEST_FILE_NAME;
+use crate::parameter_limits::ParameterLimits;
+use crate::permission_hook::{PermissionDecision, PermissionHook};
+use crate::policy_history::DEFAULT_POLICY_HISTORY_LIMIT;
+use crate::runtime_context::RuntimeOptions;
+use crate::scratch::SCRATCH_DIR;
+use crate::signature::SignaturePolicy;
+use crate::source_policy::AllowedSource;
+use crate::stdio::StdioMode;
+use crate::tool_collision::ToolCollisionStrategy;
+use crate::warmup::WarmupConfig;
+use crate::wasistate::{WasiStateTemplate, DEFAULT_MAX_MEMORY_GRANT_FRACTION};
 use crate::{
-    get_default_secrets_dir, LifecycleManager, DEFAULT_HTTP_TIMEOUT_SECS, DEFAULT_OCI_TIMEOUT_SECS,
+    get_default_component_dir, get_default_secrets_dir, LifecycleManager,
+    DEFAULT_HTTP_TIMEOUT_SECS, DEFAULT_INSTANTIATION_TIMEOUT_SECS, DEFAULT_OCI_TIMEOUT_SECS,
 };
 
 /// Credentials for authenticating with a container registry.
@@ -34,26 +55,75 @@ pub struct RegistryCredential {
 
 impl From<&RegistryCredential> for oci_client::secrets::RegistryAuth {
     fn from(cred: &RegistryCredential) -> Self {
-        oci_client::secrets::RegistryAuth::Basic(
-            cred.username.clone(),
-            cred.password.clone(),
-        )
+        oci_client::secrets::RegistryAuth::Basic(cred.username.clone(), cred.password.clone())
     }
 }
 
 /// Fully-specified configuration for constructing a [`LifecycleManager`].
-#[derive(Clone)]
+///
+/// The config serializes to the same TOML as the `wassette` CLI config file,
+/// so a stored config can be read with [`LifecycleConfig::from_file`]. The
+/// HTTP and OCI clients, the permission hook, call middleware, embedded
+/// components, and the default stdio mode are not serialized; a deserialized
+/// config gets default clients, inherited stdio, and none of the others.
+/// Durations are stored in whole seconds.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(into = "ConfigFile", try_from = "ConfigFile")]
 pub struct LifecycleConfig {
-    component_dir: PathBuf,
-    secrets_dir: PathBuf,
-    environment_vars: HashMap<String, String>,
-    http_client: reqwest::Client,
-    oci_client: oci_client::Client,
-    registry_credentials: HashMap<String, RegistryCredential>,
-    eager_load: bool,
+    pub(crate) component_dir: PathBuf,
+    pub(crate) secrets_dir: PathBuf,
+    pub(crate) environment_vars: HashMap<String, String>,
+    pub(crate) http_client: reqwest::Client,
+    pub(crate) oci_client: oci_client::Client,
+    pub(crate) registry_credentials: HashMap<String, RegistryCredential>,
+    pub(crate) manifest_path: PathBuf,
+    pub(crate) health_checks: HealthCheckConfig,
+    pub(crate) warmup: HashMap<String, WarmupConfig>,
+    pub(crate) instantiation_timeout: Duration,
+    pub(crate) execution_timeout: Option<Duration>,
+    pub(crate) parameter_limits: ParameterLimits,
+    pub(crate) outbound_http: OutboundHttpConfig,
+    pub(crate) load_priority: Vec<String>,
+    pub(crate) permission_hook: Option<PermissionHook>,
+    pub(crate) allow_env_inherit: bool,
+    pub(crate) allow_any_host: bool,
+    pub(crate) allow_absolute_storage_paths: bool,
+    pub(crate) allowed_sources: Vec<AllowedSource>,
+    pub(crate) allowed_policy_sources: Option<Vec<AllowedSource>>,
+    pub(crate) signature_policy: SignaturePolicy,
+    pub(crate) policy_history_limit: usize,
+    pub(crate) max_memory_grant_fraction: f64,
+    pub(crate) auto_reload_on_drift: bool,
+    pub(crate) dir_watch: bool,
+    pub(crate) strict_validation: bool,
+    pub(crate) startup_gc: bool,
+    pub(crate) startup_downloads_prune: bool,
+    pub(crate) instance_pool: usize,
+    pub(crate) runtime_options: RuntimeOptions,
+    pub(crate) component_filter: ComponentFilter,
+    pub(crate) tool_collisions: ToolCollisionStrategy,
+    pub(crate) call_middleware: Vec<Arc<dyn CallMiddleware>>,
+    pub(crate) downloads_retention: DownloadsRetention,
+    pub(crate) scratch_dir: PathBuf,
+    pub(crate) changelog_path: Option<PathBuf>,
+    pub(crate) embedded_components: StaticComponentSource,
+    pub(crate) default_stdio: StdioMode,
+    pub(crate) eager_load: bool,
 }
 
 impl LifecycleConfig {
+    /// Read a config from a TOML file in the format of the `wassette` CLI
+    /// config file. Settings that only apply to the server, such as
+    /// `bind_address`, are ignored, and a missing `component_dir` or
+    /// `secrets_dir` falls back to the platform default.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))
+    }
+
     /// Location where components live.
     pub fn component_dir(&self) -> &Path {
         &self.component_dir
@@ -89,26 +159,161 @@ impl LifecycleConfig {
         &self.registry_credentials
     }
 
-    pub(crate) fn into_parts(
-        self,
-    ) -> (
-        PathBuf,
-        PathBuf,
-        HashMap<String, String>,
-        reqwest::Client,
-        oci_client::Client,
-        HashMap<String, RegistryCredential>,
-        bool,
-    ) {
-        (
-            self.component_dir,
-            self.secrets_dir,
-            self.environment_vars,
-            self.http_client,
-            self.oci_client,
-            self.registry_credentials,
-            self.eager_load,
-        )
+    /// Location of the `components.lock.json` state manifest.
+    pub fn manifest_path(&self) -> &Path {
+        &self.manifest_path
+    }
+
+    /// How component health probes are run.
+    pub fn health_checks(&self) -> &HealthCheckConfig {
+        &self.health_checks
+    }
+
+    /// Warm-up settings keyed by component ID.
+    pub fn warmup(&self) -> &HashMap<String, WarmupConfig> {
+        &self.warmup
+    }
+
+    /// Default time a component may take to instantiate.
+    pub fn instantiation_timeout(&self) -> Duration {
+        self.instantiation_timeout
+    }
+
+    /// Default time a called function may run, if limited.
+    pub fn execution_timeout(&self) -> Option<Duration> {
+        self.execution_timeout
+    }
+
+    /// Limits the arguments of a call are checked against.
+    pub fn parameter_limits(&self) -> ParameterLimits {
+        self.parameter_limits
+    }
+
+    /// Timeouts and connection limits for HTTP requests made by components.
+    pub fn outbound_http(&self) -> &OutboundHttpConfig {
+        &self.outbound_http
+    }
+
+    /// Component IDs compiled first by background loading, highest priority first.
+    pub fn load_priority(&self) -> &[String] {
+        &self.load_priority
+    }
+
+    /// Whether policies may pass every configured environment variable through.
+    pub fn allow_env_inherit(&self) -> bool {
+        self.allow_env_inherit
+    }
+
+    /// Whether policies may allow every network host with `*`.
+    pub fn allow_any_host(&self) -> bool {
+        self.allow_any_host
+    }
+
+    /// Whether storage permissions may grant absolute host paths.
+    pub fn allow_absolute_storage_paths(&self) -> bool {
+        self.allow_absolute_storage_paths
+    }
+
+    /// Sources components may be loaded from; empty allows every source.
+    pub fn allowed_sources(&self) -> &[AllowedSource] {
+        &self.allowed_sources
+    }
+
+    /// Sources policies may be attached from; the component sources unless
+    /// configured separately.
+    pub fn allowed_policy_sources(&self) -> &[AllowedSource] {
+        self.allowed_policy_sources
+            .as_deref()
+            .unwrap_or(&self.allowed_sources)
+    }
+
+    /// Whether component signatures are checked, and whose are trusted.
+    pub fn signature_policy(&self) -> &SignaturePolicy {
+        &self.signature_policy
+    }
+
+    /// Number of policy revisions kept for each component.
+    pub fn policy_history_limit(&self) -> usize {
+        self.policy_history_limit
+    }
+
+    /// Share of the host's RAM a memory grant may ask for.
+    pub fn max_memory_grant_fraction(&self) -> f64 {
+        self.max_memory_grant_fraction
+    }
+
+    /// Whether components whose wasm file changed on disk are reloaded.
+    pub fn auto_reload_on_drift(&self) -> bool {
+        self.auto_reload_on_drift
+    }
+
+    /// Whether the component directory is watched for wasm and policy files
+    /// added, replaced, or removed behind the manager's back.
+    pub fn dir_watch(&self) -> bool {
+        self.dir_watch
+    }
+
+    /// Whether cached metadata is checked against the SHA-256 hash of the
+    /// component's wasm file, not only its size and modification time.
+    pub fn strict_validation(&self) -> bool {
+        self.strict_validation
+    }
+
+    /// Whether orphaned files are removed from the component directory when
+    /// the manager is built.
+    pub fn startup_gc(&self) -> bool {
+        self.startup_gc
+    }
+
+    /// Whether the downloads directory is pruned when the manager is built.
+    pub fn startup_downloads_prune(&self) -> bool {
+        self.startup_downloads_prune
+    }
+
+    /// Idle instances kept per component for reuse by later calls; zero
+    /// when instances are not pooled.
+    pub fn instance_pool(&self) -> usize {
+        self.instance_pool
+    }
+
+    /// How the wasmtime engine is tuned.
+    pub fn runtime_options(&self) -> &RuntimeOptions {
+        &self.runtime_options
+    }
+
+    /// Which components in the component directory are served.
+    pub fn component_filter(&self) -> &ComponentFilter {
+        &self.component_filter
+    }
+
+    /// What happens to tools that share a name with another component's tools.
+    pub fn tool_collision_strategy(&self) -> ToolCollisionStrategy {
+        self.tool_collisions
+    }
+
+    /// How long downloads are kept and how large the downloads directory may grow.
+    pub fn downloads_retention(&self) -> DownloadsRetention {
+        self.downloads_retention
+    }
+
+    /// Directory components' scratch directories are created in.
+    pub fn scratch_dir(&self) -> &Path {
+        &self.scratch_dir
+    }
+
+    /// File registry changes are appended to, if any.
+    pub fn changelog_path(&self) -> Option<&Path> {
+        self.changelog_path.as_deref()
+    }
+
+    /// Components built into the host binary.
+    pub fn embedded_components(&self) -> &StaticComponentSource {
+        &self.embedded_components
+    }
+
+    /// Where components' stdout and stderr go unless their policy decides.
+    pub fn default_stdio(&self) -> StdioMode {
+        self.default_stdio
     }
 }
 
@@ -121,6 +326,38 @@ pub struct LifecycleBuilder {
     http_client: Option<reqwest::Client>,
     oci_client: Option<oci_client::Client>,
     registry_credentials: HashMap<String, RegistryCredential>,
+    manifest_path: Option<PathBuf>,
+    health_checks: HealthCheckConfig,
+    warmup: HashMap<String, WarmupConfig>,
+    instantiation_timeout: Duration,
+    execution_timeout: Option<Duration>,
+    parameter_limits: ParameterLimits,
+    outbound_http: OutboundHttpConfig,
+    load_priority: Vec<String>,
+    permission_hook: Option<PermissionHook>,
+    allow_env_inherit: bool,
+    allow_any_host: bool,
+    allow_absolute_storage_paths: bool,
+    allowed_sources: Vec<AllowedSource>,
+    allowed_policy_sources: Option<Vec<AllowedSource>>,
+    signature_policy: SignaturePolicy,
+    policy_history_limit: usize,
+    max_memory_grant_fraction: f64,
+    auto_reload_on_drift: bool,
+    dir_watch: bool,
+    strict_validation: bool,
+    startup_gc: bool,
+    startup_downloads_prune: bool,
+    instance_pool: usize,
+    runtime_options: RuntimeOptions,
+    component_filter: ComponentFilter,
+    tool_collisions: ToolCollisionStrategy,
+    call_middleware: Vec<Arc<dyn CallMiddleware>>,
+    downloads_retention: DownloadsRetention,
+    scratch_dir: Option<PathBuf>,
+    changelog_path: Option<PathBuf>,
+    embedded_components: Vec<EmbeddedComponent>,
+    default_stdio: StdioMode,
     eager_load: bool,
 }
 
@@ -135,6 +372,38 @@ impl LifecycleBuilder {
             http_client: None,
             oci_client: None,
             registry_credentials: HashMap::new(),
+            manifest_path: None,
+            health_checks: HealthCheckConfig::default(),
+            warmup: HashMap::new(),
+            instantiation_timeout: Duration::from_secs(DEFAULT_INSTANTIATION_TIMEOUT_SECS),
+            execution_timeout: None,
+            parameter_limits: ParameterLimits::default(),
+            outbound_http: OutboundHttpConfig::default(),
+            load_priority: Vec::new(),
+            permission_hook: None,
+            allow_env_inherit: false,
+            allow_any_host: false,
+            allow_absolute_storage_paths: false,
+            allowed_sources: Vec::new(),
+            allowed_policy_sources: None,
+            signature_policy: SignaturePolicy::default(),
+            policy_history_limit: DEFAULT_POLICY_HISTORY_LIMIT,
+            max_memory_grant_fraction: DEFAULT_MAX_MEMORY_GRANT_FRACTION,
+            auto_reload_on_drift: false,
+            dir_watch: false,
+            strict_validation: true,
+            startup_gc: false,
+            startup_downloads_prune: true,
+            instance_pool: 0,
+            runtime_options: RuntimeOptions::default(),
+            component_filter: ComponentFilter::default(),
+            tool_collisions: ToolCollisionStrategy::default(),
+            call_middleware: Vec::new(),
+            downloads_retention: DownloadsRetention::default(),
+            scratch_dir: None,
+            changelog_path: None,
+            embedded_components: Vec::new(),
+            default_stdio: StdioMode::Inherit,
             eager_load: true,
         }
     }
@@ -188,7 +457,369 @@ impl LifecycleBuilder {
         registry: impl Into<String>,
         credential: RegistryCredential,
     ) -> Self {
-        self.registry_credentials.insert(registry.into(), credential);
+        self.registry_credentials
+            .insert(registry.into(), credential);
+        self
+    }
+
+    /// Override where the `components.lock.json` state manifest is kept.
+    ///
+    /// Defaults to a file inside the component directory. Point this outside
+    /// the component directory when that directory is ephemeral.
+    pub fn with_manifest_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.manifest_path = Some(path.into());
+        self
+    }
+
+    /// Configure the health probes run after a component is compiled.
+    ///
+    /// By default a failing probe is recorded but the component's tools are
+    /// still registered.
+    pub fn with_health_checks(mut self, health_checks: HealthCheckConfig) -> Self {
+        self.health_checks = health_checks;
+        self
+    }
+
+    /// Set the full map of warm-up settings (keyed by component ID).
+    pub fn with_warmup(mut self, warmup: HashMap<String, WarmupConfig>) -> Self {
+        self.warmup = warmup;
+        self
+    }
+
+    /// Warm up a single component in the background after it is loaded.
+    pub fn with_component_warmup(
+        mut self,
+        component_id: impl Into<String>,
+        warmup: WarmupConfig,
+    ) -> Self {
+        self.warmup.insert(component_id.into(), warmup);
+        self
+    }
+
+    /// Override how long a component may take to instantiate before a call
+    /// fails with [`ComponentError::InstantiationTimeout`](crate::ComponentError::InstantiationTimeout).
+    ///
+    /// A component's policy can override this with
+    /// `resources.timeouts.instantiation`.
+    pub fn with_instantiation_timeout(mut self, timeout: Duration) -> Self {
+        self.instantiation_timeout = timeout;
+        self
+    }
+
+    /// Stop called functions that run longer than `timeout`, failing the
+    /// call with [`ComponentError::ExecutionTimeout`](crate::ComponentError::ExecutionTimeout).
+    /// Calls have no deadline unless this is set.
+    ///
+    /// A component's policy can override this with
+    /// `resources.limits.timeout`.
+    pub fn with_execution_timeout(mut self, timeout: Duration) -> Self {
+        self.execution_timeout = Some(timeout);
+        self
+    }
+
+    /// Override the limits on the size, nesting depth, and array lengths of
+    /// the arguments of a call. Calls that exceed them fail with
+    /// [`ParameterLimitExceeded`](crate::ParameterLimitExceeded) before the
+    /// arguments are parsed.
+    pub fn with_parameter_limits(mut self, parameter_limits: ParameterLimits) -> Self {
+        self.parameter_limits = parameter_limits;
+        self
+    }
+
+    /// Override the timeouts and per-host connection limit applied to HTTP
+    /// requests made by components.
+    ///
+    /// A network rule in a component's policy can override the request
+    /// timeout for its host with `timeout`.
+    pub fn with_outbound_http(mut self, outbound_http: OutboundHttpConfig) -> Self {
+        self.outbound_http = outbound_http;
+        self
+    }
+
+    /// Compile the listed components first when loading the component
+    /// directory in the background, in list order.
+    ///
+    /// A priority set with
+    /// [`LifecycleManager::set_component_priority`](crate::LifecycleManager::set_component_priority)
+    /// or stored in a component's metadata takes precedence over the list.
+    pub fn with_load_priority(
+        mut self,
+        component_ids: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.load_priority = component_ids.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Only load components from the given sources. Loading from any other
+    /// URI fails with [`SourceNotAllowed`](crate::SourceNotAllowed) before
+    /// anything is fetched. An empty list, the default, allows every source.
+    pub fn with_allowed_sources(
+        mut self,
+        allowed_sources: impl IntoIterator<Item = AllowedSource>,
+    ) -> Self {
+        self.allowed_sources = allowed_sources.into_iter().collect();
+        self
+    }
+
+    /// Only attach policies from the given sources. Attaching from any other
+    /// URI fails with [`SourceNotAllowed`](crate::SourceNotAllowed) before
+    /// the policy is fetched. Unless this is set, policies are held to the
+    /// same sources as components (see [`Self::with_allowed_sources`]); an
+    /// empty list allows every source. Policy files restored from the
+    /// component directory are not checked.
+    pub fn with_allowed_policy_sources(
+        mut self,
+        allowed_sources: impl IntoIterator<Item = AllowedSource>,
+    ) -> Self {
+        self.allowed_policy_sources = Some(allowed_sources.into_iter().collect());
+        self
+    }
+
+    /// Check the signature of every component loaded from a URI against
+    /// the keys and keyless identities of `policy` before it is installed.
+    /// Under [`SignatureMode::Enforce`](crate::SignatureMode::Enforce) a
+    /// component whose signature does not verify fails with
+    /// [`SignatureRejected`](crate::SignatureRejected) and nothing is
+    /// installed; under [`SignatureMode::WarnOnly`](crate::SignatureMode::WarnOnly)
+    /// it loads with a warning. Signatures are not checked by default.
+    pub fn with_signature_policy(mut self, policy: SignaturePolicy) -> Self {
+        self.signature_policy = policy;
+        self
+    }
+
+    /// Keep the last `limit` revisions of each component's policy, so it can
+    /// be rolled back with
+    /// [`LifecycleManager::restore_policy_revision`](crate::LifecycleManager::restore_policy_revision).
+    /// Defaults to [`DEFAULT_POLICY_HISTORY_LIMIT`]; zero keeps no history.
+    pub fn with_policy_history_limit(mut self, limit: usize) -> Self {
+        self.policy_history_limit = limit;
+        self
+    }
+
+    /// Refuse to grant a component a memory limit above `fraction` of the
+    /// host's RAM. Defaults to [`DEFAULT_MAX_MEMORY_GRANT_FRACTION`]; must be
+    /// positive. Where the host's RAM cannot be read, memory grants are only
+    /// checked for a valid quantity.
+    pub fn with_max_memory_grant_fraction(mut self, fraction: f64) -> Self {
+        self.max_memory_grant_fraction = fraction;
+        self
+    }
+
+    /// Give `hook` the final say on the sandbox of every component.
+    ///
+    /// The hook runs each time a component is instantiated, after the
+    /// [`WasiStateTemplate`] has been built from the component's policy. It
+    /// can allow the template, replace it, or deny the instantiation; a
+    /// denied tool call fails with
+    /// [`PermissionError::Denied`](crate::PermissionError::Denied) carrying the
+    /// hook's reason. Use [`Self::with_async_permission_hook`] for hooks that
+    /// need to await.
+    pub fn with_permission_hook(
+        self,
+        hook: impl Fn(&str, &WasiStateTemplate) -> PermissionDecision + Send + Sync + 'static,
+    ) -> Self {
+        self.with_async_permission_hook(move |component_id, template| {
+            std::future::ready(hook(component_id, template))
+        })
+    }
+
+    /// Like [`Self::with_permission_hook`], for a hook that returns a future,
+    /// e.g. because it asks a remote authorization service. The future must
+    /// not borrow the arguments; clone what it needs.
+    pub fn with_async_permission_hook<F>(
+        mut self,
+        hook: impl Fn(&str, &WasiStateTemplate) -> F + Send + Sync + 'static,
+    ) -> Self
+    where
+        F: Future<Output = PermissionDecision> + Send + 'static,
+    {
+        self.permission_hook = Some(Arc::new(move |component_id, template| {
+            Box::pin(hook(component_id, template))
+        }));
+        self
+    }
+
+    /// Allow component policies to set `environment.inheritAll`, which passes
+    /// every configured environment variable to the component instead of only
+    /// the allowed keys. Off by default; the process environment is never
+    /// passed through unless it was configured explicitly.
+    pub fn with_env_inherit(mut self, allow: bool) -> Self {
+        self.allow_env_inherit = allow;
+        self
+    }
+
+    /// Allow component policies to list `*` as a network host, which lets
+    /// the component reach every host. Off by default: without it, `*` is
+    /// ignored with a warning and granting it fails. Wildcards such as
+    /// `*.example.com` are always allowed.
+    pub fn with_any_host(mut self, allow: bool) -> Self {
+        self.allow_any_host = allow;
+        self
+    }
+
+    /// Allow storage permissions with an absolute host path, such as
+    /// `fs:///data`, which mount that path wherever it is on the host. Off by
+    /// default: without it, such rules are ignored with a warning and
+    /// granting them fails, so components only get storage inside the
+    /// component directory.
+    pub fn with_absolute_storage_paths(mut self, allow: bool) -> Self {
+        self.allow_absolute_storage_paths = allow;
+        self
+    }
+
+    /// Reload a component when the drift check finds that its wasm file was
+    /// replaced on disk, instead of only refusing its calls. Off by default;
+    /// see [`LifecycleManager::check_drift`](crate::LifecycleManager::check_drift).
+    pub fn with_auto_reload_on_drift(mut self, auto_reload: bool) -> Self {
+        self.auto_reload_on_drift = auto_reload;
+        self
+    }
+
+    /// Watch the component directory while the manager runs. Wasm files
+    /// copied into it are loaded, replaced ones reloaded, and removed ones
+    /// unregistered, keeping their policy files. Changed policy files are
+    /// applied to their component. Off by default. Changes are picked up
+    /// once a file has been left alone for a moment, so files still being
+    /// written or moved into place are not loaded half-way.
+    pub fn with_dir_watch(mut self, watch: bool) -> Self {
+        self.dir_watch = watch;
+        self
+    }
+
+    /// Check a component's cached metadata against the SHA-256 hash of its
+    /// wasm file before serving its tools from it. On by default; with
+    /// `false` only the file's size and modification time are compared,
+    /// which is faster on large component directories but misses files
+    /// replaced with one of the same size within the same second.
+    pub fn with_strict_validation(mut self, strict: bool) -> Self {
+        self.strict_validation = strict;
+        self
+    }
+
+    /// Run [`LifecycleManager::garbage_collect`](crate::LifecycleManager::garbage_collect)
+    /// with the default [`GcOptions`](crate::GcOptions) when the manager is
+    /// built, removing what components that are gone left in the component
+    /// directory. Off by default.
+    pub fn with_startup_gc(mut self, enabled: bool) -> Self {
+        self.startup_gc = enabled;
+        self
+    }
+
+    /// Prune the downloads directory as
+    /// [`LifecycleManager::prune_downloads`](crate::LifecycleManager::prune_downloads)
+    /// does when the manager is built. On by default; turn it off for
+    /// short-lived managers that should leave the cache alone.
+    pub fn with_startup_downloads_prune(mut self, enabled: bool) -> Self {
+        self.startup_downloads_prune = enabled;
+        self
+    }
+
+    /// Keep up to `size` idle instances of each component after its calls
+    /// and hand them to later calls, which then skip instantiation. Off
+    /// (zero) by default.
+    ///
+    /// A pooled instance is reused as is: memory and globals the guest set
+    /// in one call are visible to the next, so only enable this for
+    /// components that keep no per-call state. Instances are built from the
+    /// component's policy and dropped when it changes or the component is
+    /// reloaded. Calls that need per-call sandbox state never use the pool:
+    /// calls streaming stderr, calls of components that capture stdout or
+    /// stderr, use a scratch directory, or may call other components, and
+    /// every call while a [permission hook](Self::with_permission_hook) is set.
+    /// A call that fails drops its instance.
+    pub fn with_instance_pool(mut self, size: usize) -> Self {
+        self.instance_pool = size;
+        self
+    }
+
+    /// Tune the wasmtime engine: how instances are allocated, how much
+    /// compiled code is optimized, and where compiled code is cached. The
+    /// options are checked when the config is built, so a pool that cannot
+    /// be reserved fails then rather than at the first call.
+    pub fn with_runtime_options(mut self, options: RuntimeOptions) -> Self {
+        self.runtime_options = options;
+        self
+    }
+
+    /// Serve only the components in the component directory that `filter`
+    /// allows. Other components are neither compiled nor listed, and loading
+    /// them fails with [`ComponentError::Filtered`](crate::ComponentError::Filtered).
+    pub fn with_component_filter(mut self, filter: ComponentFilter) -> Self {
+        self.component_filter = filter;
+        self
+    }
+
+    /// Decide what happens when a component being loaded exports a tool
+    /// whose name another loaded component already uses. Defaults to
+    /// [`ToolCollisionStrategy::Allow`], which registers the tool anyway so
+    /// that calls to the shared name fail until one component is unloaded.
+    pub fn with_tool_collision_strategy(mut self, strategy: ToolCollisionStrategy) -> Self {
+        self.tool_collisions = strategy;
+        self
+    }
+
+    /// Run `middleware` around every component tool call, after the
+    /// middleware registered before it. See [`CallMiddleware`].
+    pub fn with_call_middleware(mut self, middleware: impl CallMiddleware) -> Self {
+        self.call_middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Bound the downloads directory: files older than `retention.max_age`
+    /// are removed, and the oldest files are evicted once the directory grows
+    /// past `retention.max_total_bytes`. The directory is pruned when the
+    /// manager is built, unless turned off with
+    /// [`Self::with_startup_downloads_prune`], and by
+    /// [`LifecycleManager::prune_downloads`].
+    pub fn with_downloads_retention(mut self, retention: DownloadsRetention) -> Self {
+        self.downloads_retention = retention;
+        self
+    }
+
+    /// Create the scratch directories that policies ask for with
+    /// `storage.scratch` under `dir`, instead of the `scratch` directory
+    /// inside the component directory.
+    pub fn with_scratch_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.scratch_dir = Some(dir.into());
+        self
+    }
+
+    /// Append every registry change to the file at `path` as a JSON line,
+    /// numbered so that numbering continues across restarts. Without a
+    /// changelog, changes are only kept in memory for
+    /// [`LifecycleManager::changes_since`](crate::LifecycleManager::changes_since).
+    pub fn with_changelog(mut self, path: impl Into<PathBuf>) -> Self {
+        self.changelog_path = Some(path.into());
+        self
+    }
+
+    /// Keep registry changes only in memory, even when the settings this
+    /// builder started from name a changelog file.
+    pub fn without_changelog(mut self) -> Self {
+        self.changelog_path = None;
+        self
+    }
+
+    /// Serve `components` from memory, next to the components in the
+    /// component directory. They are compiled when the manager is built,
+    /// which fails if one of them or its policy is invalid, and nothing of
+    /// them is written to the component directory. Their IDs are reserved:
+    /// loading another component under one, unloading them, and changing
+    /// their policy or permissions fail with
+    /// [`ComponentError::ReadOnly`](crate::ComponentError::ReadOnly).
+    pub fn with_embedded_components(mut self, components: &[EmbeddedComponent]) -> Self {
+        self.embedded_components.extend_from_slice(components);
+        self
+    }
+
+    /// Send the stdout and stderr of components whose policy does not say
+    /// otherwise where `mode` says. Defaults to [`StdioMode::Inherit`]; a
+    /// server speaking MCP over its own stdio should capture instead, so
+    /// component output cannot corrupt the transport. Captured output is
+    /// returned by
+    /// [`LifecycleManager::execute_component_call_with_output`](crate::LifecycleManager::execute_component_call_with_output).
+    pub fn with_default_stdio(mut self, mode: StdioMode) -> Self {
+        self.default_stdio = mode;
         self
     }
 
@@ -200,6 +831,12 @@ impl LifecycleBuilder {
 
     /// Produce a validated [`LifecycleConfig`] without constructing a manager.
     pub fn build_config(self) -> Result<LifecycleConfig> {
+        anyhow::ensure!(
+            self.max_memory_grant_fraction.is_finite() && self.max_memory_grant_fraction > 0.0,
+            "max_memory_grant_fraction must be a positive number, got {}",
+            self.max_memory_grant_fraction
+        );
+        self.runtime_options.validate()?;
         let component_dir = match self.component_dir.canonicalize() {
             Ok(path) => path,
             Err(_) => self.component_dir.clone(),
@@ -207,6 +844,14 @@ impl LifecycleBuilder {
 
         let secrets_dir = self.secrets_dir.unwrap_or_else(get_default_secrets_dir);
 
+        let manifest_path = self
+            .manifest_path
+            .unwrap_or_else(|| component_dir.join(MANIFEST_FILE_NAME));
+
+        let scratch_dir = self
+            .scratch_dir
+            .unwrap_or_else(|| component_dir.join(SCRATCH_DIR));
+
         let http_client = match self.http_client {
             Some(client) => client,
             None => default_http_client()?,
@@ -224,10 +869,89 @@ impl LifecycleBuilder {
             http_client,
             oci_client,
             registry_credentials: self.registry_credentials,
+            manifest_path,
+            health_checks: self.health_checks,
+            warmup: self.warmup,
+            instantiation_timeout: self.instantiation_timeout,
+            execution_timeout: self.execution_timeout,
+            parameter_limits: self.parameter_limits,
+            outbound_http: self.outbound_http,
+            load_priority: self.load_priority,
+            permission_hook: self.permission_hook,
+            allow_env_inherit: self.allow_env_inherit,
+            allow_any_host: self.allow_any_host,
+            allow_absolute_storage_paths: self.allow_absolute_storage_paths,
+            allowed_sources: self.allowed_sources,
+            allowed_policy_sources: self.allowed_policy_sources,
+            signature_policy: self.signature_policy,
+            policy_history_limit: self.policy_history_limit,
+            max_memory_grant_fraction: self.max_memory_grant_fraction,
+            auto_reload_on_drift: self.auto_reload_on_drift,
+            dir_watch: self.dir_watch,
+            strict_validation: self.strict_validation,
+            startup_gc: self.startup_gc,
+            startup_downloads_prune: self.startup_downloads_prune,
+            instance_pool: self.instance_pool,
+            runtime_options: self.runtime_options,
+            component_filter: self.component_filter,
+            tool_collisions: self.tool_collisions,
+            call_middleware: self.call_middleware,
+            downloads_retention: self.downloads_retention,
+            scratch_dir,
+            changelog_path: self.changelog_path,
+            embedded_components: StaticComponentSource::new(self.embedded_components),
+            default_stdio: self.default_stdio,
             eager_load: self.eager_load,
         })
     }
 
+    /// Start from the settings of `config`, for example one read with
+    /// [`LifecycleConfig::from_file`], so they can be overridden before the
+    /// manager is built.
+    pub fn from_config(config: LifecycleConfig) -> Self {
+        Self {
+            component_dir: config.component_dir,
+            secrets_dir: Some(config.secrets_dir),
+            environment_vars: config.environment_vars,
+            http_client: Some(config.http_client),
+            oci_client: Some(config.oci_client),
+            registry_credentials: config.registry_credentials,
+            manifest_path: Some(config.manifest_path),
+            health_checks: config.health_checks,
+            warmup: config.warmup,
+            instantiation_timeout: config.instantiation_timeout,
+            execution_timeout: config.execution_timeout,
+            parameter_limits: config.parameter_limits,
+            outbound_http: config.outbound_http,
+            load_priority: config.load_priority,
+            permission_hook: config.permission_hook,
+            allow_env_inherit: config.allow_env_inherit,
+            allow_any_host: config.allow_any_host,
+            allow_absolute_storage_paths: config.allow_absolute_storage_paths,
+            allowed_sources: config.allowed_sources,
+            allowed_policy_sources: config.allowed_policy_sources,
+            signature_policy: config.signature_policy,
+            policy_history_limit: config.policy_history_limit,
+            max_memory_grant_fraction: config.max_memory_grant_fraction,
+            auto_reload_on_drift: config.auto_reload_on_drift,
+            dir_watch: config.dir_watch,
+            strict_validation: config.strict_validation,
+            startup_gc: config.startup_gc,
+            startup_downloads_prune: config.startup_downloads_prune,
+            instance_pool: config.instance_pool,
+            runtime_options: config.runtime_options,
+            component_filter: config.component_filter,
+            tool_collisions: config.tool_collisions,
+            call_middleware: config.call_middleware,
+            downloads_retention: config.downloads_retention,
+            scratch_dir: Some(config.scratch_dir),
+            changelog_path: config.changelog_path,
+            embedded_components: config.embedded_components.components().copied().collect(),
+            default_stdio: config.default_stdio,
+            eager_load: config.eager_load,
+        }
+    }
+
     /// Construct a [`LifecycleManager`] using the current builder settings.
     ///
     /// If eager loading is enabled the component directory is scanned
@@ -245,6 +969,284 @@ impl LifecycleBuilder {
     }
 }
 
+/// Serialized form of a [`LifecycleConfig`], laid out like the CLI config
+/// file. Keys only the CLI knows are ignored when reading.
+#[derive(Serialize, Deserialize)]
+struct ConfigFile {
+    #[serde(default = "get_default_component_dir")]
+    component_dir: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secrets_dir: Option<PathBuf>,
+    #[serde(default)]
+    environment_vars: HashMap<String, String>,
+    #[serde(default)]
+    registry_credentials: HashMap<String, RegistryCredential>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    manifest_path: Option<PathBuf>,
+    #[serde(default)]
+    require_healthy_components: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    health_check_timeout_secs: Option<u64>,
+    #[serde(default)]
+    warmup: HashMap<String, WarmupConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    instantiation_timeout_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    execution_timeout_secs: Option<u64>,
+    #[serde(default)]
+    parameter_limits: ParameterLimitsFile,
+    #[serde(default)]
+    outbound_http: OutboundHttpFile,
+    #[serde(default)]
+    load_priority: Vec<String>,
+    #[serde(default)]
+    allow_env_inherit: bool,
+    #[serde(default)]
+    allow_any_host: bool,
+    #[serde(default)]
+    allow_absolute_storage_paths: bool,
+    #[serde(default)]
+    allowed_sources: Vec<AllowedSource>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    allowed_policy_sources: Option<Vec<AllowedSource>>,
+    #[serde(default)]
+    signature_policy: SignaturePolicy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    policy_history_limit: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_memory_grant_fraction: Option<f64>,
+    #[serde(default)]
+    auto_reload_on_drift: bool,
+    #[serde(default)]
+    dir_watch: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    strict_validation: Option<bool>,
+    #[serde(default)]
+    startup_gc: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    startup_downloads_prune: Option<bool>,
+    #[serde(default)]
+    instance_pool: usize,
+    #[serde(default)]
+    runtime: RuntimeOptions,
+    #[serde(default)]
+    component_filter: ComponentFilter,
+    #[serde(default)]
+    tool_collisions: ToolCollisionStrategy,
+    #[serde(default)]
+    downloads: DownloadsFile,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scratch_dir: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    changelog_file: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    eager_load: Option<bool>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct OutboundHttpFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    connect_timeout_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    request_timeout_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_connections_per_host: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_response_bytes: Option<u64>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct ParameterLimitsFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_bytes: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_depth: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_array_length: Option<usize>,
+}
+
+/// Downloads directory limits; zero turns a limit off.
+#[derive(Default, Serialize, Deserialize)]
+struct DownloadsFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_age_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_total_bytes: Option<u64>,
+}
+
+impl From<LifecycleConfig> for ConfigFile {
+    fn from(config: LifecycleConfig) -> Self {
+        Self {
+            component_dir: config.component_dir,
+            secrets_dir: Some(config.secrets_dir),
+            environment_vars: config.environment_vars,
+            registry_credentials: config.registry_credentials,
+            manifest_path: Some(config.manifest_path),
+            require_healthy_components: config.health_checks.require_healthy,
+            health_check_timeout_secs: Some(config.health_checks.timeout.as_secs()),
+            warmup: config.warmup,
+            instantiation_timeout_secs: Some(config.instantiation_timeout.as_secs()),
+            execution_timeout_secs: config.execution_timeout.map(|timeout| timeout.as_secs()),
+            parameter_limits: ParameterLimitsFile {
+                max_bytes: Some(config.parameter_limits.max_bytes),
+                max_depth: Some(config.parameter_limits.max_depth),
+                max_array_length: Some(config.parameter_limits.max_array_length),
+            },
+            outbound_http: OutboundHttpFile {
+                connect_timeout_secs: Some(config.outbound_http.connect_timeout.as_secs()),
+                request_timeout_secs: Some(config.outbound_http.request_timeout.as_secs()),
+                max_connections_per_host: Some(config.outbound_http.max_connections_per_host),
+                max_response_bytes: config.outbound_http.max_response_bytes,
+            },
+            load_priority: config.load_priority,
+            allow_env_inherit: config.allow_env_inherit,
+            allow_any_host: config.allow_any_host,
+            allow_absolute_storage_paths: config.allow_absolute_storage_paths,
+            allowed_sources: config.allowed_sources,
+            allowed_policy_sources: config.allowed_policy_sources,
+            signature_policy: config.signature_policy,
+            policy_history_limit: Some(config.policy_history_limit),
+            max_memory_grant_fraction: Some(config.max_memory_grant_fraction),
+            auto_reload_on_drift: config.auto_reload_on_drift,
+            dir_watch: config.dir_watch,
+            strict_validation: Some(config.strict_validation),
+            startup_gc: config.startup_gc,
+            startup_downloads_prune: Some(config.startup_downloads_prune),
+            instance_pool: config.instance_pool,
+            runtime: config.runtime_options,
+            component_filter: config.component_filter,
+            tool_collisions: config.tool_collisions,
+            downloads: DownloadsFile {
+                max_age_secs: Some(
+                    config
+                        .downloads_retention
+                        .max_age
+                        .map_or(0, |max_age| max_age.as_secs()),
+                ),
+                max_total_bytes: Some(config.downloads_retention.max_total_bytes.unwrap_or(0)),
+            },
+            scratch_dir: Some(config.scratch_dir),
+            changelog_file: config.changelog_path,
+            eager_load: Some(config.eager_load),
+        }
+    }
+}
+
+impl TryFrom<ConfigFile> for LifecycleConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(file: ConfigFile) -> Result<Self> {
+        let mut builder = LifecycleBuilder::new(file.component_dir)
+            .with_environment_vars(file.environment_vars)
+            .with_registry_credentials(file.registry_credentials)
+            .with_warmup(file.warmup)
+            .with_load_priority(file.load_priority)
+            .with_env_inherit(file.allow_env_inherit)
+            .with_any_host(file.allow_any_host)
+            .with_absolute_storage_paths(file.allow_absolute_storage_paths)
+            .with_allowed_sources(file.allowed_sources)
+            .with_signature_policy(file.signature_policy)
+            .with_auto_reload_on_drift(file.auto_reload_on_drift)
+            .with_dir_watch(file.dir_watch)
+            .with_startup_gc(file.startup_gc)
+            .with_instance_pool(file.instance_pool)
+            .with_runtime_options(file.runtime)
+            .with_component_filter(file.component_filter)
+            .with_tool_collision_strategy(file.tool_collisions);
+        if let Some(secrets_dir) = file.secrets_dir {
+            builder = builder.with_secrets_dir(secrets_dir);
+        }
+        if let Some(manifest_path) = file.manifest_path {
+            builder = builder.with_manifest_path(manifest_path);
+        }
+        let health_defaults = HealthCheckConfig::default();
+        builder = builder.with_health_checks(HealthCheckConfig {
+            timeout: file
+                .health_check_timeout_secs
+                .map_or(health_defaults.timeout, Duration::from_secs),
+            require_healthy: file.require_healthy_components,
+        });
+        if let Some(secs) = file.instantiation_timeout_secs {
+            builder = builder.with_instantiation_timeout(Duration::from_secs(secs));
+        }
+        if let Some(secs) = file.execution_timeout_secs {
+            builder = builder.with_execution_timeout(Duration::from_secs(secs));
+        }
+        let parameter_defaults = ParameterLimits::default();
+        builder = builder.with_parameter_limits(ParameterLimits {
+            max_bytes: file
+                .parameter_limits
+                .max_bytes
+                .unwrap_or(parameter_defaults.max_bytes),
+            max_depth: file
+                .parameter_limits
+                .max_depth
+                .unwrap_or(parameter_defaults.max_depth),
+            max_array_length: file
+                .parameter_limits
+                .max_array_length
+                .unwrap_or(parameter_defaults.max_array_length),
+        });
+        let http_defaults = OutboundHttpConfig::default();
+        builder = builder.with_outbound_http(OutboundHttpConfig {
+            connect_timeout: file
+                .outbound_http
+                .connect_timeout_secs
+                .map_or(http_defaults.connect_timeout, Duration::from_secs),
+            request_timeout: file
+                .outbound_http
+                .request_timeout_secs
+                .map_or(http_defaults.request_timeout, Duration::from_secs),
+            max_connections_per_host: file
+                .outbound_http
+                .max_connections_per_host
+                .unwrap_or(http_defaults.max_connections_per_host),
+            max_response_bytes: file
+                .outbound_http
+                .max_response_bytes
+                .or(http_defaults.max_response_bytes),
+        });
+        if let Some(sources) = file.allowed_policy_sources {
+            builder = builder.with_allowed_policy_sources(sources);
+        }
+        if let Some(limit) = file.policy_history_limit {
+            builder = builder.with_policy_history_limit(limit);
+        }
+        if let Some(fraction) = file.max_memory_grant_fraction {
+            builder = builder.with_max_memory_grant_fraction(fraction);
+        }
+        let downloads_defaults = DownloadsRetention::default();
+        builder = builder.with_downloads_retention(DownloadsRetention {
+            max_age: match file.downloads.max_age_secs {
+                Some(0) => None,
+                Some(secs) => Some(Duration::from_secs(secs)),
+                None => downloads_defaults.max_age,
+            },
+            max_total_bytes: match file.downloads.max_total_bytes {
+                Some(0) => None,
+                Some(bytes) => Some(bytes),
+                None => downloads_defaults.max_total_bytes,
+            },
+        });
+        if let Some(scratch_dir) = file.scratch_dir {
+            builder = builder.with_scratch_dir(scratch_dir);
+        }
+        if let Some(changelog_file) = file.changelog_file {
+            builder = builder.with_changelog(changelog_file);
+        }
+        if let Some(strict) = file.strict_validation {
+            builder = builder.with_strict_validation(strict);
+        }
+        if let Some(prune) = file.startup_downloads_prune {
+            builder = builder.with_startup_downloads_prune(prune);
+        }
+        if let Some(eager) = file.eager_load {
+            builder = builder.with_eager_loading(eager);
+        }
+        builder.build_config()
+    }
+}
+
 /// Create the default HTTP client used when none is supplied.
 fn default_http_client() -> Result<reqwest::Client> {
     let http_timeout = std::env::var("HTTP_TIMEOUT_SECS")
@@ -270,3 +1272,212 @@ fn default_oci_client() -> Result<oci_client::Client> {
         ..Default::default()
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime_context::{InstanceAllocation, OptimizationLevel};
+
+    fn full_config(dir: &Path) -> Result<LifecycleConfig> {
+        LifecycleBuilder::new(dir.join("components"))
+            .with_secrets_dir(dir.join("secrets"))
+            .with_environment_vars(HashMap::from([("API_URL".into(), "https://x".into())]))
+            .with_registry_credentials(HashMap::from([(
+                "ghcr.io".to_string(),
+                RegistryCredential {
+                    username: "user".into(),
+                    password: "pass".into(),
+                },
+            )]))
+            .with_manifest_path(dir.join("state.lock.json"))
+            .with_health_checks(HealthCheckConfig {
+                timeout: Duration::from_secs(2),
+                require_healthy: true,
+            })
+            .with_warmup(HashMap::from([(
+                "fetch_rs".to_string(),
+                WarmupConfig {
+                    instances: 3,
+                    tool: Some("ping".into()),
+                },
+            )]))
+            .with_instantiation_timeout(Duration::from_secs(4))
+            .with_execution_timeout(Duration::from_secs(8))
+            .with_parameter_limits(ParameterLimits {
+                max_bytes: 9000,
+                max_depth: 10,
+                max_array_length: 11,
+            })
+            .with_outbound_http(OutboundHttpConfig {
+                connect_timeout: Duration::from_secs(5),
+                request_timeout: Duration::from_secs(6),
+                max_connections_per_host: 7,
+                max_response_bytes: Some(1 << 30),
+            })
+            .with_load_priority(vec!["time".to_string()])
+            .with_env_inherit(true)
+            .with_any_host(true)
+            .with_absolute_storage_paths(true)
+            .with_allowed_sources(vec![AllowedSource {
+                scheme: "oci".into(),
+                host_pattern: Some("ghcr.io/**".into()),
+            }])
+            .with_allowed_policy_sources(Vec::new())
+            .with_policy_history_limit(3)
+            .with_max_memory_grant_fraction(0.5)
+            .with_auto_reload_on_drift(true)
+            .with_dir_watch(true)
+            .with_strict_validation(false)
+            .with_startup_gc(true)
+            .with_startup_downloads_prune(false)
+            .with_instance_pool(2)
+            .with_runtime_options(RuntimeOptions {
+                allocation: InstanceAllocation::Pooling,
+                max_instances: Some(50),
+                max_memory_bytes: Some(256 << 20),
+                opt_level: OptimizationLevel::SpeedAndSize,
+                parallel_compilation: false,
+                cache_dir: Some(dir.join("cache")),
+                cpu_limits: true,
+            })
+            .with_component_filter(ComponentFilter {
+                allow: vec!["fetch-*".into()],
+                deny: vec!["*-debug".into()],
+            })
+            .with_tool_collision_strategy(ToolCollisionStrategy::Prefix)
+            .with_downloads_retention(DownloadsRetention {
+                max_age: None,
+                max_total_bytes: Some(1024),
+            })
+            .with_scratch_dir(dir.join("scratch"))
+            .with_changelog(dir.join("changes.jsonl"))
+            .with_eager_loading(false)
+            .build_config()
+    }
+
+    #[test]
+    fn test_config_round_trips_through_toml() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let config = full_config(dir.path())?;
+        let toml = toml::to_string(&config)?;
+        let restored: LifecycleConfig = toml::from_str(&toml)?;
+
+        assert_eq!(toml::to_string(&restored)?, toml);
+        assert_eq!(restored.component_dir(), config.component_dir());
+        assert_eq!(restored.secrets_dir(), dir.path().join("secrets"));
+        assert_eq!(restored.environment_vars()["API_URL"], "https://x");
+        assert_eq!(restored.registry_credentials()["ghcr.io"].password, "pass");
+        assert_eq!(restored.manifest_path(), dir.path().join("state.lock.json"));
+        assert_eq!(restored.health_checks(), config.health_checks());
+        assert_eq!(restored.warmup(), config.warmup());
+        assert_eq!(restored.instantiation_timeout(), Duration::from_secs(4));
+        assert_eq!(restored.execution_timeout(), Some(Duration::from_secs(8)));
+        assert_eq!(restored.parameter_limits(), config.parameter_limits());
+        assert_eq!(restored.outbound_http(), config.outbound_http());
+        assert_eq!(restored.load_priority(), ["time"]);
+        assert!(restored.allow_env_inherit());
+        assert!(restored.allow_any_host());
+        assert!(restored.allow_absolute_storage_paths());
+        assert_eq!(restored.allowed_sources(), config.allowed_sources());
+        assert!(restored.allowed_policy_sources().is_empty());
+        assert_eq!(restored.policy_history_limit(), 3);
+        assert_eq!(restored.max_memory_grant_fraction(), 0.5);
+        assert!(restored.auto_reload_on_drift());
+        assert!(restored.dir_watch());
+        assert!(!restored.strict_validation());
+        assert!(restored.startup_gc());
+        assert!(!restored.startup_downloads_prune());
+        assert_eq!(restored.instance_pool(), 2);
+        assert_eq!(restored.runtime_options(), config.runtime_options());
+        assert_eq!(restored.component_filter(), config.component_filter());
+        assert_eq!(
+            restored.tool_collision_strategy(),
+            ToolCollisionStrategy::Prefix
+        );
+        assert_eq!(restored.downloads_retention(), config.downloads_retention());
+        assert_eq!(restored.scratch_dir(), dir.path().join("scratch"));
+        assert_eq!(
+            restored.changelog_path(),
+            Some(dir.path().join("changes.jsonl").as_path())
+        );
+        assert!(!restored.eager_load());
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_file_reads_cli_config_and_builder_overrides() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            format!(
+                r#"
+component_dir = {:?}
+bind_address = "127.0.0.1:9001"
+read_only = true
+instantiation_timeout_secs = 30
+tool_collisions = "reject"
+
+[environment_vars]
+LOG_LEVEL = "debug"
+
+[outbound_http]
+max_connections_per_host = 2
+"#,
+                dir.path().join("components")
+            ),
+        )?;
+
+        let config = LifecycleConfig::from_file(&path)?;
+        assert_eq!(config.environment_vars()["LOG_LEVEL"], "debug");
+        assert_eq!(config.instantiation_timeout(), Duration::from_secs(30));
+        assert_eq!(config.outbound_http().max_connections_per_host, 2);
+        assert_eq!(
+            config.outbound_http().connect_timeout,
+            OutboundHttpConfig::default().connect_timeout
+        );
+        assert_eq!(config.secrets_dir(), get_default_secrets_dir());
+        assert!(config.eager_load());
+
+        let config = LifecycleBuilder::from_config(config)
+            .with_tool_collision_strategy(ToolCollisionStrategy::Allow)
+            .with_eager_loading(false)
+            .build_config()?;
+        assert_eq!(
+            config.tool_collision_strategy(),
+            ToolCollisionStrategy::Allow
+        );
+        assert_eq!(config.instantiation_timeout(), Duration::from_secs(30));
+        assert!(!config.eager_load());
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_runtime_options_fail_the_build() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "[runtime]\nallocation = \"pooling\"\nmax_memory_bytes = 1099511627776\n",
+        )?;
+        let Err(error) = LifecycleConfig::from_file(&path) else {
+            panic!("a pool that does not fit in the address space should be refused");
+        };
+        assert!(format!("{error:#}").contains("Pooling allocation of 1000 instances"));
+
+        let Err(error) = LifecycleBuilder::new(dir.path().join("components"))
+            .with_runtime_options(RuntimeOptions {
+                max_instances: Some(10),
+                ..Default::default()
+            })
+            .build_config()
+        else {
+            panic!("pool limits without pooling allocation should be refused");
+        };
+        assert_eq!(
+            format!("{error:#}"),
+            "max_instances and max_memory_bytes only apply to pooling allocation"
+        );
+        Ok(())
+    }
+}
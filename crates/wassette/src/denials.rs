@@ -0,0 +1,119 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Counts of the permission denials of each component, to spot components
+//! that keep running into their sandbox.
+//!
+//! Denials are counted as they are announced with
+//! [`LifecycleEvent::PermissionDenied`](crate::LifecycleEvent::PermissionDenied).
+//! The counts are kept in memory; they survive reloads of a component and
+//! are dropped when it is unloaded.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::usage::unix_now;
+
+/// Number of recent denials kept for each component.
+pub const RECENT_DENIALS_LIMIT: usize = 20;
+
+/// A single permission denial.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PermissionDenial {
+    /// Type of the permission, e.g. `network`, `storage`, or `call`
+    pub permission_type: String,
+    /// What was denied, e.g. the host or path
+    pub details: Value,
+    /// When the denial happened, as a Unix timestamp in seconds
+    pub denied_at: u64,
+}
+
+/// The permission denials of a component since it was loaded.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PermissionDenials {
+    /// Number of denials of any type
+    pub total: u64,
+    /// Number of denials, keyed by permission type
+    pub by_type: BTreeMap<String, u64>,
+    /// The most recent denials, newest first, at most
+    /// [`RECENT_DENIALS_LIMIT`]
+    pub recent: VecDeque<PermissionDenial>,
+}
+
+/// Denial counts keyed by component ID.
+#[derive(Clone, Default)]
+pub(crate) struct DenialTracker {
+    components: Arc<Mutex<HashMap<String, PermissionDenials>>>,
+}
+
+impl DenialTracker {
+    pub(crate) fn record(&self, component_id: &str, permission_type: &str, details: Value) {
+        let denied_at = unix_now();
+        let mut components = self.components.lock().unwrap();
+        let denials = components.entry(component_id.to_string()).or_default();
+        denials.total += 1;
+        *denials
+            .by_type
+            .entry(permission_type.to_string())
+            .or_default() += 1;
+        denials.recent.push_front(PermissionDenial {
+            permission_type: permission_type.to_string(),
+            details,
+            denied_at,
+        });
+        denials.recent.truncate(RECENT_DENIALS_LIMIT);
+    }
+
+    pub(crate) fn get(&self, component_id: &str) -> PermissionDenials {
+        self.components
+            .lock()
+            .unwrap()
+            .get(component_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Denial counts of every component that has been denied something.
+    pub(crate) fn snapshot(&self) -> BTreeMap<String, PermissionDenials> {
+        self.components
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, denials)| (id.clone(), denials.clone()))
+            .collect()
+    }
+
+    pub(crate) fn remove(&self, component_id: &str) {
+        self.components.lock().unwrap().remove(component_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_denials_are_counted_by_type() {
+        let tracker = DenialTracker::default();
+        for i in 0..RECENT_DENIALS_LIMIT + 5 {
+            tracker.record("fetch", "network", json!({ "host": format!("h{i}.test") }));
+        }
+        tracker.record("fetch", "storage", json!({ "path": "/etc" }));
+
+        let denials = tracker.get("fetch");
+        assert_eq!(denials.total, RECENT_DENIALS_LIMIT as u64 + 6);
+        assert_eq!(denials.by_type["network"], RECENT_DENIALS_LIMIT as u64 + 5);
+        assert_eq!(denials.by_type["storage"], 1);
+        assert_eq!(denials.recent.len(), RECENT_DENIALS_LIMIT);
+        assert_eq!(denials.recent[0].permission_type, "storage");
+
+        tracker.remove("fetch");
+        assert_eq!(tracker.get("fetch"), PermissionDenials::default());
+        assert!(tracker.snapshot().is_empty());
+    }
+}
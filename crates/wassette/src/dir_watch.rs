@@ -0,0 +1,212 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Watching the component directory for wasm and policy files that are
+//! added, replaced, or removed behind the manager's back, enabled with
+//! [`LifecycleBuilder::with_dir_watch`](crate::LifecycleBuilder::with_dir_watch).
+//!
+//! Changes are debounced per file: a file is only looked at once no change
+//! to it was seen for [`DEBOUNCE`], so a file being copied in is not loaded
+//! half-way. Whatever ends up at the path counts, which covers files moved
+//! into place by an atomic rename as well as files written in place. A
+//! component that fails to load is tried again a few times, in case its
+//! file was still being written.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+use crate::events::LifecycleEvent;
+use crate::path_guard;
+use crate::policy_fragments::{self, POLICY_FRAGMENTS_DIR};
+use crate::LifecycleManager;
+
+/// How long a file must be left alone before a change to it is applied.
+pub(crate) const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// How often a change is applied before giving up on it.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// A file in the component directory the watcher acts on.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum WatchedFile {
+    /// `<id>.wasm`
+    Component(String),
+    /// `<id>.policy.yaml`, `<id>.policy.json`, or a policy fragment
+    /// `policy-fragments/<id>/<category>.policy.yaml`
+    Policy(String),
+}
+
+impl WatchedFile {
+    /// The component file or policy file at `path` in the component
+    /// directory `root`, if it is one.
+    pub(crate) fn from_path(root: &Path, path: &Path) -> Option<Self> {
+        let file = if let Some((component_id, _)) = policy_fragments::fragment_from_path(root, path)
+        {
+            Self::Policy(component_id)
+        } else {
+            if path.parent()? != root {
+                return None;
+            }
+            let name = path.file_name()?.to_str()?;
+            if let Some(component_id) = name.strip_suffix(".wasm") {
+                Self::Component(component_id.to_string())
+            } else {
+                let component_id = name
+                    .strip_suffix(".policy.yaml")
+                    .or_else(|| name.strip_suffix(".policy.json"))?;
+                Self::Policy(component_id.to_string())
+            }
+        };
+        path_guard::validate_component_id(file.component_id()).ok()?;
+        Some(file)
+    }
+
+    pub(crate) fn component_id(&self) -> &str {
+        match self {
+            Self::Component(component_id) | Self::Policy(component_id) => component_id,
+        }
+    }
+}
+
+/// A change waiting for its file to settle.
+struct PendingChange {
+    due: Instant,
+    attempts: u32,
+}
+
+/// Watch the component directory of `manager` and apply the changes to its
+/// files until the runtime shuts down.
+pub(crate) fn spawn(manager: LifecycleManager) -> Result<tokio::task::JoinHandle<()>> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) => {
+                for path in event.paths {
+                    let _ = sender.send(path);
+                }
+            }
+            Err(error) => warn!(%error, "Component directory watch failed"),
+        })
+        .context("Failed to create the component directory watcher")?;
+    let root = manager.component_root().to_path_buf();
+    watcher
+        .watch(&root, RecursiveMode::NonRecursive)
+        .with_context(|| format!("Failed to watch component directory {}", root.display()))?;
+    let fragments_dir = root.join(POLICY_FRAGMENTS_DIR);
+    std::fs::create_dir_all(&fragments_dir)
+        .and_then(|()| {
+            watcher
+                .watch(&fragments_dir, RecursiveMode::Recursive)
+                .map_err(std::io::Error::other)
+        })
+        .with_context(|| {
+            format!(
+                "Failed to watch policy fragments directory {}",
+                fragments_dir.display()
+            )
+        })?;
+    info!(component_dir = %root.display(), "Watching the component directory for changes");
+    Ok(tokio::spawn(run(manager, watcher, receiver)))
+}
+
+async fn run(
+    manager: LifecycleManager,
+    // Dropping the watcher stops the notifications
+    _watcher: RecommendedWatcher,
+    mut receiver: mpsc::UnboundedReceiver<PathBuf>,
+) {
+    let mut pending: HashMap<WatchedFile, PendingChange> = HashMap::new();
+    loop {
+        let next_due = pending.values().map(|change| change.due).min();
+        tokio::select! {
+            path = receiver.recv() => {
+                let Some(path) = path else { break };
+                if let Some(file) = WatchedFile::from_path(manager.component_root(), &path) {
+                    // A file that changes again starts settling afresh
+                    pending.insert(file, PendingChange { due: Instant::now() + DEBOUNCE, attempts: 0 });
+                }
+            }
+            _ = tokio::time::sleep_until(next_due.unwrap_or_else(Instant::now)), if next_due.is_some() => {
+                let now = Instant::now();
+                let due: Vec<WatchedFile> = pending
+                    .iter()
+                    .filter(|(_, change)| change.due <= now)
+                    .map(|(file, _)| file.clone())
+                    .collect();
+                for file in due {
+                    let attempts = pending.remove(&file).map_or(0, |change| change.attempts) + 1;
+                    let Err(error) = apply(&manager, &file).await else {
+                        continue;
+                    };
+                    if attempts < MAX_ATTEMPTS {
+                        debug!(?file, %error, attempts, "Retrying a change in the component directory");
+                        pending
+                            .entry(file)
+                            .or_insert(PendingChange { due: Instant::now() + DEBOUNCE, attempts });
+                    } else {
+                        warn!(?file, error = %format!("{error:#}"), "Failed to apply a change in the component directory");
+                        if let WatchedFile::Component(component_id) = file {
+                            manager.events.emit(LifecycleEvent::ComponentDisabled {
+                                component_id,
+                                reason: format!("{error:#}"),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn apply(manager: &LifecycleManager, file: &WatchedFile) -> Result<()> {
+    match file {
+        WatchedFile::Component(component_id) => manager.sync_component_file(component_id).await,
+        WatchedFile::Policy(component_id) => manager.sync_policy_files(component_id).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watched_files_are_recognized_by_name() {
+        let root = Path::new("/components");
+        let file = |name: &str| WatchedFile::from_path(root, root.join(name).as_path());
+        assert_eq!(
+            file("fetch.wasm"),
+            Some(WatchedFile::Component("fetch".to_string()))
+        );
+        assert_eq!(
+            file("fetch.policy.yaml"),
+            Some(WatchedFile::Policy("fetch".to_string()))
+        );
+        assert_eq!(
+            file("policy-fragments/fetch/network.policy.yaml"),
+            Some(WatchedFile::Policy("fetch".to_string()))
+        );
+        // A dotted ID is a component of its own, not a fragment
+        assert_eq!(
+            file("fetch.network.policy.yaml"),
+            Some(WatchedFile::Policy("fetch.network".to_string()))
+        );
+        assert_eq!(
+            file("fetch.v2.policy.json"),
+            Some(WatchedFile::Policy("fetch.v2".to_string()))
+        );
+        assert_eq!(file("fetch.policy.meta.json"), None);
+        assert_eq!(file("fetch.metadata.json"), None);
+        assert_eq!(file("fetch.cwasm"), None);
+        assert_eq!(file(".wasm"), None);
+        assert_eq!(file("policy-fragments/fetch/other.policy.yaml"), None);
+        assert_eq!(file("policy-history/fetch/1.policy.yaml"), None);
+        assert_eq!(file("downloads/fetch.wasm"), None);
+    }
+}
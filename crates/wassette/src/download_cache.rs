@@ -0,0 +1,793 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Content-addressed cache of downloaded component artifacts.
+//!
+//! Blobs live in the `downloads` directory as `<sha256>.wasm` and are indexed
+//! by source URI in `index.json`. Every lookup re-hashes the blob before it is
+//! handed out, so a modified cache entry is discarded instead of loaded.
+//!
+//! The directory is kept bounded by [`DownloadsRetention`]: files older than
+//! its maximum age are removed, and when the directory grows past its size
+//! budget the oldest files are evicted first. Blobs a load is using are
+//! pinned and never removed.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, MutexGuard};
+use tracing::{debug, warn};
+
+use crate::component_storage::file_digest;
+use crate::loader::DownloadedResource;
+
+const INDEX_FILE_NAME: &str = "index.json";
+const BLOB_EXT: &str = "wasm";
+
+/// Default age after which a file in the downloads directory is removed.
+pub const DEFAULT_DOWNLOADS_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Default size budget of the downloads directory.
+pub const DEFAULT_DOWNLOADS_MAX_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Default time between prunes of the downloads directory while serving.
+pub const DEFAULT_DOWNLOADS_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How long files are kept in the downloads directory and how large it may
+/// grow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadsRetention {
+    /// Files older than this are removed; `None` keeps them regardless of age
+    pub max_age: Option<Duration>,
+    /// Total size the directory is pruned down to, oldest files first;
+    /// `None` leaves it unbounded
+    pub max_total_bytes: Option<u64>,
+}
+
+impl Default for DownloadsRetention {
+    fn default() -> Self {
+        Self {
+            max_age: Some(DEFAULT_DOWNLOADS_MAX_AGE),
+            max_total_bytes: Some(DEFAULT_DOWNLOADS_MAX_BYTES),
+        }
+    }
+}
+
+/// HTTP validators used to revalidate a cached download.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct CacheValidators {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+}
+
+impl CacheValidators {
+    pub(crate) fn from_headers(headers: &reqwest::header::HeaderMap) -> Self {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value: &reqwest::header::HeaderValue| value.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            etag: header(reqwest::header::ETAG),
+            last_modified: header(reqwest::header::LAST_MODIFIED),
+        }
+    }
+}
+
+/// Index entry describing the blob last downloaded from a source URI.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct CachedBlob {
+    /// Digest of the blob (`sha256:<hex>`)
+    pub digest: String,
+    /// File name the artifact was downloaded as; its stem is the component ID
+    pub file_name: String,
+    #[serde(flatten)]
+    pub validators: CacheValidators,
+    /// Seconds since the Unix epoch when the blob was stored for this URI
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stored_at: Option<u64>,
+}
+
+/// A blob returned by [`DownloadCache::lookup`]. The blob stays pinned, and is
+/// left alone by pruning, until this is dropped.
+pub(crate) struct CacheHit {
+    blob: CachedBlob,
+    _pin: BlobPin,
+}
+
+impl Deref for CacheHit {
+    type Target = CachedBlob;
+
+    fn deref(&self) -> &CachedBlob {
+        &self.blob
+    }
+}
+
+type Pins = Arc<std::sync::Mutex<HashMap<String, usize>>>;
+
+/// Keeps a blob from being pruned while a load uses it.
+struct BlobPin {
+    pins: Pins,
+    digest: String,
+}
+
+impl BlobPin {
+    fn new(pins: &Pins, digest: &str) -> Self {
+        *pins.lock().unwrap().entry(digest.to_string()).or_default() += 1;
+        Self {
+            pins: Arc::clone(pins),
+            digest: digest.to_string(),
+        }
+    }
+}
+
+impl Drop for BlobPin {
+    fn drop(&mut self) {
+        let mut pins = self.pins.lock().unwrap();
+        if let Some(count) = pins.get_mut(&self.digest) {
+            *count -= 1;
+            if *count == 0 {
+                pins.remove(&self.digest);
+            }
+        }
+    }
+}
+
+type Writes = Arc<std::sync::Mutex<HashSet<String>>>;
+
+/// Marks a temporary file in the downloads directory as still being
+/// written, so pruning and garbage collection leave it alone until this is
+/// dropped.
+struct PartialWrite {
+    writes: Writes,
+    file_name: String,
+}
+
+impl PartialWrite {
+    fn new(writes: &Writes, path: &Path) -> Self {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        writes.lock().unwrap().insert(file_name.clone());
+        Self {
+            writes: Arc::clone(writes),
+            file_name,
+        }
+    }
+}
+
+impl Drop for PartialWrite {
+    fn drop(&mut self) {
+        self.writes.lock().unwrap().remove(&self.file_name);
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheIndex {
+    #[serde(default)]
+    entries: BTreeMap<String, CachedBlob>,
+}
+
+/// Summary of a `gc` or prune run over the downloads directory.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DownloadsGcReport {
+    /// Digests of the blobs that were removed because nothing references them
+    pub removed: Vec<String>,
+    /// Blob digests, or names of other files, removed for being older than
+    /// the maximum age
+    pub expired: Vec<String>,
+    /// Blob digests, or names of other files, evicted to bring the directory
+    /// under its size budget
+    pub evicted: Vec<String>,
+    /// Number of blobs kept
+    pub retained: usize,
+    /// Total size of the removed files in bytes
+    pub reclaimed_bytes: u64,
+}
+
+/// Downloads directory cache shared by all clones of a lifecycle manager.
+#[derive(Clone)]
+pub(crate) struct DownloadCache {
+    dir: PathBuf,
+    index_lock: Arc<Mutex<()>>,
+    /// Digests of the blobs in use by loads, with the number of users. Only
+    /// changed while `index_lock` is held or by a pin being dropped.
+    pins: Pins,
+    /// Names of the temporary files blobs are being copied into. Only added
+    /// to while `index_lock` is held.
+    writes: Writes,
+}
+
+impl DownloadCache {
+    pub(crate) fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            index_lock: Arc::new(Mutex::new(())),
+            pins: Pins::default(),
+            writes: Writes::default(),
+        }
+    }
+
+    /// Hold off index writes and new blob copies until the returned guard is
+    /// dropped, so temporary files in the directory can be swept; those a
+    /// copy already in progress is writing are reported by
+    /// [`Self::is_writing`].
+    pub(crate) async fn lock_index(&self) -> MutexGuard<'_, ()> {
+        self.index_lock.lock().await
+    }
+
+    /// Whether `file_name` in the downloads directory is a temporary file a
+    /// blob is still being copied into.
+    pub(crate) fn is_writing(&self, file_name: &str) -> bool {
+        self.writes.lock().unwrap().contains(file_name)
+    }
+
+    fn is_pinned(&self, digest: &str) -> bool {
+        self.pins.lock().unwrap().contains_key(digest)
+    }
+
+    fn blob_path(&self, digest: &str) -> PathBuf {
+        let hex = digest.strip_prefix("sha256:").unwrap_or(digest);
+        self.dir.join(format!("{hex}.{BLOB_EXT}"))
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.dir.join(INDEX_FILE_NAME)
+    }
+
+    async fn read_index(&self) -> Result<CacheIndex> {
+        let path = self.index_path();
+        match tokio::fs::read_to_string(&path).await {
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse download index {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(CacheIndex::default()),
+            Err(e) => {
+                Err(e).with_context(|| format!("Failed to read download index {}", path.display()))
+            }
+        }
+    }
+
+    async fn write_index(&self, index: &CacheIndex) -> Result<()> {
+        let path = self.index_path();
+        let tmp_path = path.with_extension("json.tmp");
+        let json = serde_json::to_string_pretty(index).context("Failed to serialize index")?;
+        tokio::fs::write(&tmp_path, json)
+            .await
+            .with_context(|| format!("Failed to write download index {}", tmp_path.display()))?;
+        tokio::fs::rename(&tmp_path, &path).await.with_context(|| {
+            format!(
+                "Failed to move download index into place {}",
+                path.display()
+            )
+        })
+    }
+
+    /// Returns the cached blob for `uri` if it exists and still matches its
+    /// digest, pinned until the returned hit is dropped.
+    ///
+    /// Entries whose blob is missing or fails verification are dropped from the
+    /// index so the next load downloads a fresh copy.
+    pub(crate) async fn lookup(&self, uri: &str) -> Option<CacheHit> {
+        let _guard = self.index_lock.lock().await;
+        let mut index = match self.read_index().await {
+            Ok(index) => index,
+            Err(error) => {
+                warn!(%error, "Ignoring unreadable download index");
+                return None;
+            }
+        };
+        let blob = index.entries.get(uri)?.clone();
+
+        match file_digest(&self.blob_path(&blob.digest)).await {
+            Ok(actual) if actual == blob.digest => {
+                let pin = BlobPin::new(&self.pins, &blob.digest);
+                return Some(CacheHit { blob, _pin: pin });
+            }
+            Ok(actual) => {
+                warn!(%uri, expected = %blob.digest, %actual, "Cached download failed verification, discarding");
+                let _ = tokio::fs::remove_file(self.blob_path(&blob.digest)).await;
+            }
+            Err(error) => {
+                debug!(%uri, %error, "Cached download is missing");
+            }
+        }
+
+        index.entries.remove(uri);
+        if let Err(error) = self.write_index(&index).await {
+            warn!(%error, "Failed to update download index");
+        }
+        None
+    }
+
+    /// Copy a verified blob into a fresh temporary resource named after the
+    /// original download, ready to be staged into the component directory.
+    pub(crate) async fn materialize(&self, blob: &CachedBlob) -> Result<DownloadedResource> {
+        let name = Path::new(&blob.file_name)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .context("Cached download has no file name")?;
+        let (resource, file) = DownloadedResource::new_temp_file(name, BLOB_EXT).await?;
+        drop(file);
+        tokio::fs::copy(self.blob_path(&blob.digest), resource.as_ref())
+            .await
+            .context("Failed to copy cached download")?;
+        Ok(resource)
+    }
+
+    /// Record the artifact at `path` as the latest download of `uri`.
+    pub(crate) async fn store(
+        &self,
+        uri: &str,
+        path: &Path,
+        validators: CacheValidators,
+    ) -> Result<()> {
+        let file_name = path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .context("Downloaded artifact has no file name")?
+            .to_string();
+        let digest = file_digest(path).await?;
+        let _pin = {
+            let _guard = self.index_lock.lock().await;
+            BlobPin::new(&self.pins, &digest)
+        };
+        let blob_path = self.blob_path(&digest);
+        if !blob_path.exists() {
+            // Copied under a temporary name that is removed when dropped, so
+            // a store interrupted by a cancelled load leaves no partial blob.
+            // It is created and marked as written under the index lock, which
+            // sweeps of temporary files hold, so none removes it mid-copy
+            let (partial, _writing) = {
+                let _guard = self.index_lock.lock().await;
+                let partial = tempfile::Builder::new()
+                    .suffix(".part")
+                    .tempfile_in(&self.dir)
+                    .context("Failed to create a temporary file in the downloads directory")?;
+                let writing = PartialWrite::new(&self.writes, partial.path());
+                (partial, writing)
+            };
+            tokio::fs::copy(path, partial.path())
+                .await
+                .with_context(|| format!("Failed to cache download at {}", blob_path.display()))?;
+            partial
+                .persist(&blob_path)
+                .with_context(|| format!("Failed to cache download at {}", blob_path.display()))?;
+        }
+
+        let _guard = self.index_lock.lock().await;
+        let mut index = self.read_index().await.unwrap_or_default();
+        index.entries.insert(
+            uri.to_string(),
+            CachedBlob {
+                digest,
+                file_name,
+                validators,
+                stored_at: Some(unix_secs(SystemTime::now())),
+            },
+        );
+        self.write_index(&index).await
+    }
+
+    /// Remove files older than `retention.max_age`, then evict the oldest
+    /// files until the directory fits `retention.max_total_bytes`. Pinned
+    /// blobs and the index are never removed.
+    ///
+    /// A blob's age counts from when it was last stored, as recorded in the
+    /// index; other files, such as leftovers of an interrupted index write,
+    /// age from their modification time. With `dry_run`, only reports what
+    /// would be removed.
+    pub(crate) async fn prune(
+        &self,
+        retention: DownloadsRetention,
+        dry_run: bool,
+    ) -> Result<DownloadsGcReport> {
+        let _guard = self.index_lock.lock().await;
+        let mut report = DownloadsGcReport::default();
+        let mut index = self.read_index().await.unwrap_or_default();
+        let mut stored_at: HashMap<&str, u64> = HashMap::new();
+        for blob in index.entries.values() {
+            if let Some(at) = blob.stored_at {
+                let latest = stored_at.entry(blob.digest.as_str()).or_default();
+                *latest = (*latest).max(at);
+            }
+        }
+
+        struct Candidate {
+            path: PathBuf,
+            name: String,
+            digest: Option<String>,
+            size: u64,
+            created: u64,
+        }
+        let mut candidates = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.dir).await.with_context(|| {
+            format!("Failed to read downloads directory {}", self.dir.display())
+        })?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let metadata = entry.metadata().await?;
+            let Some(file_name) = path.file_name().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            if !metadata.is_file() || file_name == INDEX_FILE_NAME || self.is_writing(file_name) {
+                continue;
+            }
+            let digest = (path.extension().and_then(|ext| ext.to_str()) == Some(BLOB_EXT))
+                .then(|| path.file_stem().and_then(|s| s.to_str()))
+                .flatten()
+                .map(|hex| format!("sha256:{hex}"));
+            let modified = metadata.modified().map(unix_secs).unwrap_or_default();
+            let created = digest
+                .as_deref()
+                .and_then(|digest| stored_at.get(digest).copied())
+                .unwrap_or(modified);
+            candidates.push(Candidate {
+                name: digest.clone().unwrap_or_else(|| file_name.to_string()),
+                path,
+                digest,
+                size: metadata.len(),
+                created,
+            });
+        }
+        // Oldest first, so eviction below removes the oldest files
+        candidates.sort_by(|a, b| a.created.cmp(&b.created).then(a.name.cmp(&b.name)));
+
+        let now = unix_secs(SystemTime::now());
+        let pinned = |candidate: &Candidate| {
+            candidate
+                .digest
+                .as_deref()
+                .is_some_and(|digest| self.is_pinned(digest))
+        };
+        let mut total: u64 = candidates.iter().map(|candidate| candidate.size).sum();
+        let mut removed = HashSet::new();
+        for candidate in &candidates {
+            let expired = retention
+                .max_age
+                .is_some_and(|max_age| now.saturating_sub(candidate.created) > max_age.as_secs());
+            let over_budget = retention.max_total_bytes.is_some_and(|max| total > max);
+            if !(expired || over_budget) || pinned(candidate) {
+                continue;
+            }
+            if !dry_run {
+                tokio::fs::remove_file(&candidate.path)
+                    .await
+                    .with_context(|| format!("Failed to remove {}", candidate.path.display()))?;
+                debug!(file = %candidate.name, expired, "Pruned download");
+            }
+            total -= candidate.size;
+            report.reclaimed_bytes += candidate.size;
+            if expired {
+                report.expired.push(candidate.name.clone());
+            } else {
+                report.evicted.push(candidate.name.clone());
+            }
+            if let Some(digest) = &candidate.digest {
+                removed.insert(digest.clone());
+            }
+        }
+        report.retained = candidates
+            .iter()
+            .filter(|candidate| {
+                candidate
+                    .digest
+                    .as_ref()
+                    .is_some_and(|digest| !removed.contains(digest))
+            })
+            .count();
+
+        if !removed.is_empty() && !dry_run {
+            index
+                .entries
+                .retain(|_, blob| !removed.contains(&blob.digest));
+            self.write_index(&index).await?;
+        }
+        Ok(report)
+    }
+
+    /// Remove every blob whose digest is not in `referenced`, along with the
+    /// index entries pointing at it. Blobs in `pruned` were removed by a
+    /// prune pass of the same run, or would have been in a dry run, and are
+    /// left out. With `dry_run`, only reports what would be removed.
+    pub(crate) async fn gc(
+        &self,
+        referenced: &HashSet<String>,
+        pruned: &HashSet<String>,
+        dry_run: bool,
+    ) -> Result<DownloadsGcReport> {
+        let _guard = self.index_lock.lock().await;
+        let mut report = DownloadsGcReport::default();
+        let mut remaining = HashSet::new();
+
+        let mut entries = tokio::fs::read_dir(&self.dir).await.with_context(|| {
+            format!("Failed to read downloads directory {}", self.dir.display())
+        })?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(BLOB_EXT) {
+                continue;
+            }
+            let Some(hex) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let digest = format!("sha256:{hex}");
+            if pruned.contains(&digest) {
+                continue;
+            }
+            if referenced.contains(&digest) || self.is_pinned(&digest) {
+                report.retained += 1;
+                remaining.insert(digest);
+                continue;
+            }
+
+            let size = entry.metadata().await.map(|m| m.len()).unwrap_or(0);
+            if !dry_run {
+                tokio::fs::remove_file(&path).await.with_context(|| {
+                    format!("Failed to remove cached download {}", path.display())
+                })?;
+                debug!(%digest, "Removed unreferenced download");
+            }
+            report.reclaimed_bytes += size;
+            report.removed.push(digest);
+        }
+
+        if !dry_run {
+            let mut index = self.read_index().await.unwrap_or_default();
+            index
+                .entries
+                .retain(|_, blob| remaining.contains(&blob.digest));
+            self.write_index(&index).await?;
+        }
+
+        report.removed.sort();
+        Ok(report)
+    }
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn write_artifact(dir: &Path, name: &str, bytes: &[u8]) -> Result<PathBuf> {
+        let path = dir.join(name);
+        tokio::fs::write(&path, bytes).await?;
+        Ok(path)
+    }
+
+    #[tokio::test]
+    async fn test_store_and_lookup_round_trip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = DownloadCache::new(dir.path());
+        let artifact = write_artifact(dir.path(), "time_server.download", b"component").await?;
+        let validators = CacheValidators {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+        };
+
+        cache
+            .store(
+                "https://example.com/time_server.wasm",
+                &artifact,
+                validators.clone(),
+            )
+            .await?;
+
+        let blob = cache
+            .lookup("https://example.com/time_server.wasm")
+            .await
+            .expect("stored download should be found");
+        assert_eq!(blob.validators, validators);
+
+        let resource = cache.materialize(&blob).await?;
+        assert_eq!(resource.id()?, "time_server");
+        assert_eq!(tokio::fs::read(resource.as_ref()).await?, b"component");
+
+        assert!(cache
+            .lookup("https://example.com/other.wasm")
+            .await
+            .is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lookup_rejects_tampered_blob() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = DownloadCache::new(dir.path());
+        let artifact = write_artifact(dir.path(), "fetch.download", b"original").await?;
+        cache
+            .store(
+                "https://example.com/fetch.wasm",
+                &artifact,
+                CacheValidators::default(),
+            )
+            .await?;
+
+        let blob = cache
+            .lookup("https://example.com/fetch.wasm")
+            .await
+            .unwrap();
+        tokio::fs::write(cache.blob_path(&blob.digest), b"tampered").await?;
+
+        assert!(cache
+            .lookup("https://example.com/fetch.wasm")
+            .await
+            .is_none());
+        assert!(!cache.blob_path(&blob.digest).exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_gc_removes_unreferenced_blobs() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = DownloadCache::new(dir.path());
+        let kept = write_artifact(dir.path(), "kept.download", b"kept").await?;
+        let dropped = write_artifact(dir.path(), "dropped.download", b"dropped").await?;
+        cache
+            .store(
+                "https://example.com/kept.wasm",
+                &kept,
+                CacheValidators::default(),
+            )
+            .await?;
+        cache
+            .store(
+                "https://example.com/dropped.wasm",
+                &dropped,
+                CacheValidators::default(),
+            )
+            .await?;
+
+        let kept_digest = file_digest(&kept).await?;
+        let referenced = HashSet::from([kept_digest.clone()]);
+        let preview = cache.gc(&referenced, &HashSet::new(), true).await?;
+        assert_eq!(preview.removed, vec![file_digest(&dropped).await?]);
+        assert!(cache
+            .lookup("https://example.com/dropped.wasm")
+            .await
+            .is_some());
+
+        let report = cache.gc(&referenced, &HashSet::new(), false).await?;
+
+        assert_eq!(report.removed, vec![file_digest(&dropped).await?]);
+        assert_eq!(report.retained, 1);
+        assert_eq!(report.reclaimed_bytes, b"dropped".len() as u64);
+        assert!(cache
+            .lookup("https://example.com/kept.wasm")
+            .await
+            .is_some());
+        assert!(cache
+            .lookup("https://example.com/dropped.wasm")
+            .await
+            .is_none());
+        Ok(())
+    }
+    /// Move the time `uri` was stored back by `age`.
+    async fn age_entry(cache: &DownloadCache, uri: &str, age: Duration) -> Result<()> {
+        let mut index = cache.read_index().await?;
+        let blob = index.entries.get_mut(uri).unwrap();
+        blob.stored_at = Some(blob.stored_at.unwrap() - age.as_secs());
+        cache.write_index(&index).await
+    }
+
+    #[tokio::test]
+    async fn test_prune_removes_expired_and_pinned_blobs_survive() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = DownloadCache::new(dir.path());
+        let old = write_artifact(dir.path(), "old.download", b"old").await?;
+        let held = write_artifact(dir.path(), "held.download", b"held").await?;
+        let fresh = write_artifact(dir.path(), "fresh.download", b"fresh").await?;
+        for (uri, path) in [("old", &old), ("held", &held), ("fresh", &fresh)] {
+            cache.store(uri, path, CacheValidators::default()).await?;
+            tokio::fs::remove_file(path).await?;
+        }
+        let day = Duration::from_secs(24 * 60 * 60);
+        age_entry(&cache, "old", 10 * day).await?;
+        age_entry(&cache, "held", 10 * day).await?;
+        let stray = dir.path().join("interrupted.part");
+        tokio::fs::write(&stray, b"partial").await?;
+
+        let hit = cache.lookup("held").await.unwrap();
+        let retention = DownloadsRetention {
+            max_age: Some(7 * day),
+            max_total_bytes: None,
+        };
+        let report = cache.prune(retention, false).await?;
+        assert_eq!(report.expired, vec![file_digest_of(b"old")]);
+        assert!(report.evicted.is_empty());
+        assert_eq!(report.retained, 2);
+        assert!(stray.exists());
+        assert!(cache.lookup("old").await.is_none());
+        assert!(cache.blob_path(&hit.digest).exists());
+
+        drop(hit);
+        let report = cache.prune(retention, false).await?;
+        assert_eq!(report.expired, vec![file_digest_of(b"held")]);
+        assert!(cache.lookup("fresh").await.is_some());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_blobs_being_copied_are_not_swept() -> Result<()> {
+        use crate::component_storage::{ComponentStorage, GcOptions};
+
+        let dir = tempfile::tempdir()?;
+        let storage = ComponentStorage::new(dir.path(), 1).await?;
+        let cache = storage.download_cache();
+        let partial = cache.dir.join("copying.part");
+        tokio::fs::write(&partial, b"partial").await?;
+        std::fs::File::options()
+            .write(true)
+            .open(&partial)?
+            .set_modified(SystemTime::now() - Duration::from_secs(2 * 60 * 60))?;
+        let writing = PartialWrite::new(&cache.writes, &partial);
+
+        let retention = DownloadsRetention {
+            max_age: Some(Duration::ZERO),
+            max_total_bytes: Some(0),
+        };
+        let report = cache.prune(retention, false).await?;
+        assert!(report.expired.is_empty() && report.evicted.is_empty());
+        let sweep = GcOptions {
+            temp_max_age: Duration::ZERO,
+            dry_run: false,
+        };
+        assert!(storage.garbage_collect(sweep).await?.temp_files.is_empty());
+        assert!(partial.exists());
+
+        drop(writing);
+        let report = storage.garbage_collect(sweep).await?;
+        assert_eq!(report.temp_files, ["downloads/copying.part"]);
+        assert!(!partial.exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_prune_evicts_oldest_over_budget() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = DownloadCache::new(dir.path());
+        let hour = Duration::from_secs(60 * 60);
+        for (uri, bytes, age) in [("a", b"aaaa", 3), ("b", b"bbbb", 2), ("c", b"cccc", 1)] {
+            let path = write_artifact(dir.path(), "x.download", bytes).await?;
+            cache.store(uri, &path, CacheValidators::default()).await?;
+            tokio::fs::remove_file(&path).await?;
+            age_entry(&cache, uri, age * hour).await?;
+        }
+
+        let retention = DownloadsRetention {
+            max_age: None,
+            max_total_bytes: Some(8),
+        };
+        let preview = cache.prune(retention, true).await?;
+        assert_eq!(preview.evicted, vec![file_digest_of(b"aaaa")]);
+        assert!(cache.lookup("a").await.is_some());
+
+        let report = cache.prune(retention, false).await?;
+        assert!(report.expired.is_empty());
+        assert_eq!(report.evicted, vec![file_digest_of(b"aaaa")]);
+        assert_eq!(report.reclaimed_bytes, 4);
+        assert!(cache.lookup("a").await.is_none());
+        assert!(cache.lookup("b").await.is_some());
+        assert!(cache.lookup("c").await.is_some());
+        Ok(())
+    }
+
+    fn file_digest_of(bytes: &[u8]) -> String {
+        use sha2::{Digest, Sha256};
+        format!("sha256:{:x}", Sha256::digest(bytes))
+    }
+}
@@ -0,0 +1,108 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Components built into the host binary, for sealed deployments that
+//! should not depend on what is in the component directory.
+//!
+//! Embedded components are compiled from memory when the manager is built
+//! and run under the policy embedded with them. They are read-only: loading
+//! another component under their ID, unloading them, and changing their
+//! policy or permissions fail with
+//! [`ComponentError::ReadOnly`](crate::ComponentError::ReadOnly).
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::ComponentError;
+
+/// A component built into the host binary, typically with
+/// `include_bytes!` and `include_str!`.
+///
+/// ```
+/// use wassette::EmbeddedComponent;
+///
+/// const COMPONENTS: &[EmbeddedComponent] = &[
+///     EmbeddedComponent::new("empty", b"(component)")
+///         .with_policy("version: \"1.0\"\npermissions: {}\n"),
+/// ];
+/// # assert_eq!(COMPONENTS[0].id, "empty");
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedComponent {
+    /// ID the component is served under
+    pub id: &'static str,
+    /// The component's wasm (or WAT) bytes
+    pub wasm: &'static [u8],
+    /// The component's policy as YAML; without one the component runs with
+    /// no permissions
+    pub policy: Option<&'static str>,
+}
+
+impl EmbeddedComponent {
+    /// An embedded component without a policy.
+    pub const fn new(id: &'static str, wasm: &'static [u8]) -> Self {
+        Self {
+            id,
+            wasm,
+            policy: None,
+        }
+    }
+
+    /// Run the component under the policy in `yaml`.
+    pub const fn with_policy(mut self, yaml: &'static str) -> Self {
+        self.policy = Some(yaml);
+        self
+    }
+}
+
+/// The embedded components of a manager, keyed by ID. See
+/// [`LifecycleBuilder::with_embedded_components`](crate::LifecycleBuilder::with_embedded_components).
+#[derive(Debug, Clone, Default)]
+pub struct StaticComponentSource {
+    components: Arc<BTreeMap<String, EmbeddedComponent>>,
+}
+
+impl StaticComponentSource {
+    /// A source serving `components`; a later component replaces an
+    /// earlier one with the same ID.
+    pub fn new(components: impl IntoIterator<Item = EmbeddedComponent>) -> Self {
+        Self {
+            components: Arc::new(
+                components
+                    .into_iter()
+                    .map(|component| (component.id.to_string(), component))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// The embedded component with ID `component_id`.
+    pub fn get(&self, component_id: &str) -> Option<&EmbeddedComponent> {
+        self.components.get(component_id)
+    }
+
+    /// Whether `component_id` is embedded.
+    pub fn contains(&self, component_id: &str) -> bool {
+        self.components.contains_key(component_id)
+    }
+
+    /// The embedded components, ordered by ID.
+    pub fn components(&self) -> impl Iterator<Item = &EmbeddedComponent> {
+        self.components.values()
+    }
+
+    /// Whether no component is embedded.
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    /// Fail with [`ComponentError::ReadOnly`] if `component_id` is embedded.
+    pub(crate) fn check_writable(&self, component_id: &str) -> Result<(), ComponentError> {
+        if self.contains(component_id) {
+            return Err(ComponentError::ReadOnly {
+                component_id: component_id.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,281 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Lifecycle events published by [`LifecycleManager`](crate::LifecycleManager).
+//!
+//! Embedders call [`LifecycleManager::subscribe`](crate::LifecycleManager::subscribe)
+//! to receive events instead of polling for changes. Events are emitted only
+//! after the corresponding operation has succeeded. Events that change the
+//! registry are also numbered and recorded, see
+//! [`LifecycleManager::changes_since`](crate::LifecycleManager::changes_since).
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+use crate::changes::ChangeLog;
+use crate::hooks::LifecycleHookReport;
+use crate::warmup::WarmupReport;
+
+/// Number of events buffered per subscriber before the oldest are dropped.
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// A change to the set of components, their policies, or their secrets.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LifecycleEvent {
+    /// A component was loaded that was not loaded before
+    ComponentLoaded {
+        /// Component identifier
+        component_id: String,
+        /// Normalized names of the tools the component exposes
+        tools: Vec<String>,
+    },
+    /// A loaded component was replaced by a new version
+    ComponentReplaced {
+        /// Component identifier
+        component_id: String,
+        /// Normalized names of the tools the new version exposes
+        tools: Vec<String>,
+        /// Tools the previous version did not expose
+        added_tools: Vec<String>,
+        /// Tools the previous version exposed that the new version does not
+        removed_tools: Vec<String>,
+        /// Tools whose schema differs between the versions
+        changed_tools: Vec<String>,
+    },
+    /// Tools were registered for a component that is fetched on first use,
+    /// see [`LifecycleManager::register_virtual_component`](crate::LifecycleManager::register_virtual_component)
+    VirtualComponentRegistered {
+        /// Component identifier
+        component_id: String,
+        /// URI the component is loaded from on first use
+        source_uri: String,
+        /// Normalized names of the registered tools
+        tools: Vec<String>,
+    },
+    /// A component was unloaded and its files removed
+    ComponentUnloaded {
+        /// Component identifier
+        component_id: String,
+    },
+    /// A component's wasm file was removed from the component directory and
+    /// its tools unregistered; its policy files were kept, see
+    /// [`LifecycleBuilder::with_dir_watch`](crate::LifecycleBuilder::with_dir_watch)
+    ComponentRemoved {
+        /// Component identifier
+        component_id: String,
+    },
+    /// A component in the component directory could not be loaded and its
+    /// tools are unavailable
+    ComponentDisabled {
+        /// Component identifier
+        component_id: String,
+        /// Why the component could not be loaded
+        reason: String,
+    },
+    /// A component's wasm file changed on disk after the component was
+    /// registered; its tools are refused until it is loaded again
+    ComponentDrifted {
+        /// Component identifier
+        component_id: String,
+        /// How the file on disk differs from the registered one
+        reason: String,
+    },
+    /// A component's tools were hidden from or shown again in tool listings
+    ComponentVisibilityChanged {
+        /// Component identifier
+        component_id: String,
+        /// Whether the component is now hidden
+        hidden: bool,
+    },
+    /// The background warm-up of a component finished
+    ComponentWarmedUp {
+        /// Component identifier
+        component_id: String,
+        /// Timing of the warm-up
+        report: WarmupReport,
+    },
+    /// A policy was attached to a component
+    PolicyAttached {
+        /// Component identifier
+        component_id: String,
+        /// URI the policy was loaded from
+        source_uri: String,
+    },
+    /// A component's policy was detached or reset
+    PolicyDetached {
+        /// Component identifier
+        component_id: String,
+    },
+    /// A component's policy was rolled back to a stored revision
+    PolicyRolledBack {
+        /// Component identifier
+        component_id: String,
+        /// Revision the policy was restored from
+        revision: u64,
+    },
+    /// A permission was granted to a component
+    PermissionGranted {
+        /// Component identifier
+        component_id: String,
+        /// Permission type (`network`, `storage`, `environment`, ...)
+        permission_type: String,
+        /// Permission details as passed to the grant
+        details: Value,
+    },
+    /// A permission was revoked from a component
+    PermissionRevoked {
+        /// Component identifier
+        component_id: String,
+        /// Permission type (`network`, `storage`, `environment`, ...)
+        permission_type: String,
+        /// Permission details as passed to the revoke
+        details: Value,
+    },
+    /// The sandbox refused a request of a component, or the permission hook
+    /// refused to instantiate it
+    PermissionDenied {
+        /// Component identifier
+        component_id: String,
+        /// Permission type (`network`, `storage`, or `hook`)
+        permission_type: String,
+        /// What was refused, e.g. the host and URI of a network request
+        details: Value,
+    },
+    /// A component called a tool of another component through the
+    /// `wassette:broker/call` host interface, and its policy allowed it
+    BrokeredCall {
+        /// The calling component
+        component_id: String,
+        /// The component that was called
+        target_component_id: String,
+        /// The tool that was called
+        tool: String,
+        /// Number of brokered calls the call is nested in, 1 for a call
+        /// made by a component called by a client
+        depth: usize,
+    },
+    /// Secrets of a component were set or deleted
+    SecretChanged {
+        /// Component identifier
+        component_id: String,
+        /// Keys of the secrets that changed
+        keys: Vec<String>,
+    },
+    /// A component's `on-load` or `on-unload` hook ran
+    LifecycleHookRan {
+        /// Component identifier
+        component_id: String,
+        /// Which hook ran and how it went
+        report: LifecycleHookReport,
+    },
+}
+
+impl LifecycleEvent {
+    /// The component this event refers to.
+    pub fn component_id(&self) -> &str {
+        match self {
+            Self::ComponentLoaded { component_id, .. }
+            | Self::ComponentReplaced { component_id, .. }
+            | Self::VirtualComponentRegistered { component_id, .. }
+            | Self::ComponentUnloaded { component_id }
+            | Self::ComponentRemoved { component_id }
+            | Self::ComponentDisabled { component_id, .. }
+            | Self::ComponentDrifted { component_id, .. }
+            | Self::ComponentVisibilityChanged { component_id, .. }
+            | Self::ComponentWarmedUp { component_id, .. }
+            | Self::PolicyAttached { component_id, .. }
+            | Self::PolicyDetached { component_id }
+            | Self::PolicyRolledBack { component_id, .. }
+            | Self::PermissionGranted { component_id, .. }
+            | Self::PermissionRevoked { component_id, .. }
+            | Self::PermissionDenied { component_id, .. }
+            | Self::BrokeredCall { component_id, .. }
+            | Self::SecretChanged { component_id, .. }
+            | Self::LifecycleHookRan { component_id, .. } => component_id,
+        }
+    }
+
+    /// Whether the event may change the list of tools exposed to clients.
+    ///
+    /// Replacing a component with a version that exposes identical tools does
+    /// not count as a change.
+    pub fn changes_tool_list(&self) -> bool {
+        match self {
+            Self::ComponentReplaced {
+                added_tools,
+                removed_tools,
+                changed_tools,
+                ..
+            } => !(added_tools.is_empty() && removed_tools.is_empty() && changed_tools.is_empty()),
+            _ => matches!(
+                self,
+                Self::ComponentLoaded { .. }
+                    | Self::VirtualComponentRegistered { .. }
+                    | Self::ComponentUnloaded { .. }
+                    | Self::ComponentRemoved { .. }
+                    | Self::ComponentDisabled { .. }
+                    | Self::ComponentVisibilityChanged { .. }
+            ),
+        }
+    }
+
+    /// Whether the event changes which components and tools are served, or
+    /// under which policy: loads, replacements, unloads, removals, components being
+    /// disabled, hidden, or shown, and policy and permission changes.
+    pub fn is_registry_change(&self) -> bool {
+        matches!(
+            self,
+            Self::ComponentLoaded { .. }
+                | Self::ComponentReplaced { .. }
+                | Self::VirtualComponentRegistered { .. }
+                | Self::ComponentUnloaded { .. }
+                | Self::ComponentRemoved { .. }
+                | Self::ComponentDisabled { .. }
+                | Self::ComponentVisibilityChanged { .. }
+                | Self::PolicyAttached { .. }
+                | Self::PolicyDetached { .. }
+                | Self::PolicyRolledBack { .. }
+                | Self::PermissionGranted { .. }
+                | Self::PermissionRevoked { .. }
+        )
+    }
+
+    /// Whether the event is numbered and kept in the changelog: registry
+    /// changes, and the lifecycle hooks run as components are loaded and
+    /// unloaded, so the changelog shows what ran on their behalf.
+    pub fn is_recorded(&self) -> bool {
+        self.is_registry_change() || matches!(self, Self::LifecycleHookRan { .. })
+    }
+}
+
+/// Fan-out channel shared by all clones of a lifecycle manager.
+#[derive(Clone)]
+pub(crate) struct EventBus {
+    sender: broadcast::Sender<LifecycleEvent>,
+    changes: ChangeLog,
+}
+
+impl EventBus {
+    /// A bus recording registry changes in `changes`.
+    pub(crate) fn new(changes: ChangeLog) -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self { sender, changes }
+    }
+
+    pub(crate) fn changes(&self) -> &ChangeLog {
+        &self.changes
+    }
+
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<LifecycleEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Publish an event. Having no subscribers is not an error.
+    pub(crate) fn emit(&self, event: LifecycleEvent) {
+        tracing::debug!(?event, "Lifecycle event");
+        self.changes.record(&event);
+        let _ = self.sender.send(event);
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Optional health probes invoked right after a component is compiled.
+//!
+//! A component opts in by exporting a function from the
+//! `wassette:health/check` interface, or a function named `health-check`.
+//! The probe is called without arguments; it fails when it traps, times out,
+//! returns a WIT `result` error, or returns `false`.
+
+use std::time::Duration;
+
+use component2json::ToolMetadata;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Interface a component exports to provide a health probe.
+pub const HEALTH_CHECK_INTERFACE: &str = "wassette:health/check";
+
+/// Function name recognized as a health probe outside the health interface.
+pub const HEALTH_CHECK_FUNCTION: &str = "health-check";
+
+/// Default time a health probe may take before it is considered failed.
+const DEFAULT_HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How health probes are run after a component is loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthCheckConfig {
+    /// Maximum time a probe may take
+    pub timeout: Duration,
+    /// Refuse to register the tools of components whose probe fails
+    pub require_healthy: bool,
+}
+
+impl Default for HealthCheckConfig {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_HEALTH_CHECK_TIMEOUT,
+            require_healthy: false,
+        }
+    }
+}
+
+/// Result of running a component's health probe.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HealthReport {
+    /// Whether the probe succeeded
+    pub healthy: bool,
+    /// Normalized tool name of the probe that was invoked
+    pub probe: String,
+    /// Why the probe failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Time the probe took, including instantiation
+    pub duration_ms: u64,
+}
+
+/// Find the health probe among a component's exported tools, preferring the
+/// `wassette:health/check` interface over a bare `health-check` function.
+pub(crate) fn find_health_probe(tools: &[ToolMetadata]) -> Option<&ToolMetadata> {
+    let in_health_interface = |tool: &&ToolMetadata| {
+        tool.identifier
+            .interface_name
+            .as_deref()
+            .map(|name| name.split('@').next().unwrap_or(name) == HEALTH_CHECK_INTERFACE)
+            .unwrap_or(false)
+    };
+
+    tools.iter().find(in_health_interface).or_else(|| {
+        tools
+            .iter()
+            .find(|tool| tool.identifier.function_name == HEALTH_CHECK_FUNCTION)
+    })
+}
+
+/// Interpret the JSON-converted return value of a probe that did not trap.
+pub(crate) fn probe_result_error(result: &Value) -> Option<String> {
+    match result.get("result") {
+        Some(Value::Bool(false)) => Some("health check returned false".to_string()),
+        Some(Value::Object(obj)) if obj.contains_key("err") => Some(match &obj["err"] {
+            Value::Null => "health check returned an error".to_string(),
+            Value::String(message) => message.clone(),
+            other => other.to_string(),
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use component2json::FunctionIdentifier;
+    use serde_json::json;
+
+    use super::*;
+
+    fn tool(interface_name: Option<&str>, function_name: &str) -> ToolMetadata {
+        ToolMetadata {
+            identifier: FunctionIdentifier {
+                package_name: None,
+                interface_name: interface_name.map(str::to_string),
+                function_name: function_name.to_string(),
+            },
+            normalized_name: function_name.to_string(),
+            schema: json!({}),
+        }
+    }
+
+    #[test]
+    fn test_find_health_probe() {
+        let tools = vec![
+            tool(None, "fetch"),
+            tool(None, "health-check"),
+            tool(Some("wassette:health/check@0.1.0"), "check"),
+        ];
+        let probe = find_health_probe(&tools).unwrap();
+        assert_eq!(probe.identifier.function_name, "check");
+
+        let probe = find_health_probe(&tools[..2]).unwrap();
+        assert_eq!(probe.identifier.function_name, "health-check");
+
+        assert!(find_health_probe(&tools[..1]).is_none());
+        assert!(find_health_probe(&[tool(Some("wassette:healthy/check"), "check")]).is_none());
+    }
+
+    #[test]
+    fn test_probe_result_error() {
+        assert_eq!(probe_result_error(&Value::Null), None);
+        assert_eq!(probe_result_error(&json!({"result": true})), None);
+        assert_eq!(probe_result_error(&json!({"result": {"ok": null}})), None);
+        assert_eq!(
+            probe_result_error(&json!({"result": false})).as_deref(),
+            Some("health check returned false")
+        );
+        assert_eq!(
+            probe_result_error(&json!({"result": {"err": "database unreachable"}})).as_deref(),
+            Some("database unreachable")
+        );
+        assert_eq!(
+            probe_result_error(&json!({"result": {"err": null}})).as_deref(),
+            Some("health check returned an error")
+        );
+    }
+}
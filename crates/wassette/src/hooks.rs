@@ -0,0 +1,170 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Optional lifecycle hooks invoked after a component is loaded and before
+//! it is unloaded.
+//!
+//! A component opts in by exporting `on-load` or `on-unload` from the
+//! `wassette:lifecycle/hooks` interface, or functions with those names.
+//! Hooks are called without arguments, under the component's own policy,
+//! and are not exposed as tools. A hook fails when it traps, times out, or
+//! returns a WIT `result` error; a failure is reported as a warning and
+//! never stops the load or unload.
+
+use std::time::Duration;
+
+use component2json::ToolMetadata;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Interface a component exports its lifecycle hooks from.
+pub const LIFECYCLE_HOOKS_INTERFACE: &str = "wassette:lifecycle/hooks";
+
+/// Time a lifecycle hook may take before it is considered failed.
+pub(crate) const LIFECYCLE_HOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A point in a component's lifecycle at which it may run code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LifecycleHook {
+    /// After the component is compiled and its tools are registered
+    OnLoad,
+    /// Before the component's files are removed
+    OnUnload,
+}
+
+impl LifecycleHook {
+    /// Name of the exported function that implements the hook.
+    pub fn function_name(self) -> &'static str {
+        match self {
+            Self::OnLoad => "on-load",
+            Self::OnUnload => "on-unload",
+        }
+    }
+}
+
+/// Result of running one of a component's lifecycle hooks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LifecycleHookReport {
+    /// Which hook ran
+    pub hook: LifecycleHook,
+    /// Whether the hook succeeded
+    pub succeeded: bool,
+    /// Why the hook failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Time the hook took, including instantiation
+    pub duration_ms: u64,
+}
+
+fn in_hooks_interface(tool: &ToolMetadata) -> bool {
+    tool.identifier
+        .interface_name
+        .as_deref()
+        .map(|name| name.split('@').next().unwrap_or(name) == LIFECYCLE_HOOKS_INTERFACE)
+        .unwrap_or(false)
+}
+
+fn is_hook(tool: &ToolMetadata, hook: LifecycleHook) -> bool {
+    tool.identifier.function_name == hook.function_name()
+        && (tool.identifier.interface_name.is_none() || in_hooks_interface(tool))
+}
+
+/// Whether an export is a lifecycle hook rather than a tool.
+pub(crate) fn is_lifecycle_hook(tool: &ToolMetadata) -> bool {
+    is_hook(tool, LifecycleHook::OnLoad) || is_hook(tool, LifecycleHook::OnUnload)
+}
+
+/// Find `hook` among a component's exports, preferring the
+/// `wassette:lifecycle/hooks` interface over a bare function.
+pub(crate) fn find_lifecycle_hook(
+    exports: &[ToolMetadata],
+    hook: LifecycleHook,
+) -> Option<&ToolMetadata> {
+    let matching = || exports.iter().filter(move |tool| is_hook(tool, hook));
+    matching()
+        .find(|tool| in_hooks_interface(tool))
+        .or_else(|| matching().next())
+}
+
+/// Interpret the JSON-converted return value of a hook that did not trap.
+pub(crate) fn hook_result_error(hook: LifecycleHook, result: &Value) -> Option<String> {
+    match result.get("result") {
+        Some(Value::Object(obj)) if obj.contains_key("err") => Some(match &obj["err"] {
+            Value::Null => format!("{} hook returned an error", hook.function_name()),
+            Value::String(message) => message.clone(),
+            other => other.to_string(),
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use component2json::FunctionIdentifier;
+    use serde_json::json;
+
+    use super::*;
+
+    fn tool(interface_name: Option<&str>, function_name: &str) -> ToolMetadata {
+        ToolMetadata {
+            identifier: FunctionIdentifier {
+                package_name: None,
+                interface_name: interface_name.map(str::to_string),
+                function_name: function_name.to_string(),
+            },
+            normalized_name: function_name.to_string(),
+            schema: json!({}),
+        }
+    }
+
+    #[test]
+    fn test_find_lifecycle_hook() {
+        let exports = vec![
+            tool(None, "fetch"),
+            tool(None, "on-load"),
+            tool(Some("wassette:lifecycle/hooks@0.1.0"), "on-load"),
+            tool(Some("example:db/admin"), "on-unload"),
+        ];
+        let hook = find_lifecycle_hook(&exports, LifecycleHook::OnLoad).unwrap();
+        assert!(hook.identifier.interface_name.is_some());
+        let hook = find_lifecycle_hook(&exports[..2], LifecycleHook::OnLoad).unwrap();
+        assert!(hook.identifier.interface_name.is_none());
+
+        // Functions of other interfaces are tools, even with a hook's name
+        assert!(find_lifecycle_hook(&exports, LifecycleHook::OnUnload).is_none());
+        assert!(!is_lifecycle_hook(&exports[3]));
+        assert!(!is_lifecycle_hook(&exports[0]));
+        assert!(is_lifecycle_hook(&exports[1]));
+        assert!(is_lifecycle_hook(&tool(
+            Some("wassette:lifecycle/hooks"),
+            "on-unload"
+        )));
+    }
+
+    #[test]
+    fn test_hook_result_error() {
+        let hook = LifecycleHook::OnLoad;
+        assert_eq!(hook_result_error(hook, &Value::Null), None);
+        assert_eq!(
+            hook_result_error(hook, &json!({"result": {"ok": null}})),
+            None
+        );
+        assert_eq!(
+            hook_result_error(hook, &json!({"result": {"err": "schema exists"}})).as_deref(),
+            Some("schema exists")
+        );
+        assert_eq!(
+            hook_result_error(LifecycleHook::OnUnload, &json!({"result": {"err": null}}))
+                .as_deref(),
+            Some("on-unload hook returned an error")
+        );
+    }
+
+    #[test]
+    fn test_lifecycle_hook_serializes_as_function_name() {
+        for hook in [LifecycleHook::OnLoad, LifecycleHook::OnUnload] {
+            assert_eq!(json!(hook), json!(hook.function_name()));
+        }
+    }
+}
@@ -1,52 +1,458 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
+use bytes::Bytes;
+use http_body::{Body, Frame, SizeHint};
+use http_body_util::BodyExt;
+use ipnet::IpNet;
+use policy::NetworkHostPermission;
+use serde::Serialize;
+use tokio::sync::Semaphore;
 use tracing::{debug, warn};
 use url::Url;
 use wasmtime::component::{Resource, ResourceTable};
 use wasmtime_wasi::{WasiCtxView, WasiView};
 use wasmtime_wasi_http::bindings::http::types;
-use wasmtime_wasi_http::types::{HostFutureIncomingResponse, OutgoingRequestConfig};
+use wasmtime_wasi_http::body::HyperIncomingBody;
+use wasmtime_wasi_http::types::{
+    default_send_request_handler, HostFutureIncomingResponse, IncomingResponse,
+    OutgoingRequestConfig,
+};
 use wasmtime_wasi_http::{HttpResult, WasiHttpView};
 
 use crate::wasistate::PermissionError;
 
+/// Allowed host entry that matches every host. Only honored when the manager
+/// allows it, see
+/// [`LifecycleBuilder::with_any_host`](crate::LifecycleBuilder::with_any_host).
+pub(crate) const ANY_HOST: &str = "*";
+
+/// Which request hosts an allowed host entry covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum HostPattern {
+    /// Only the host itself
+    Exact,
+    /// Subdomains of the host at any depth, but not the host itself
+    /// (`*.example.com`)
+    Subdomains,
+    /// Every host (`*`)
+    Any,
+    /// IP addresses in a network (`10.0.0.0/8`), for requests to an IP
+    /// address rather than a host name
+    Network(IpNet),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct AllowedHost {
     scheme: Option<String>,
     host: String,
+    port: Option<u16>,
+    pattern: HostPattern,
+    /// The policy entry this was parsed from
+    rule: String,
 }
 
 impl AllowedHost {
     fn from_str(host_str: &str) -> Result<Self> {
-        if let Ok(url) = Url::parse(host_str) {
-            Ok(AllowedHost {
-                scheme: Some(url.scheme().to_string()),
-                host: url.host_str().unwrap_or("").to_string(),
-            })
-        } else if let Ok(url) = Url::parse(&format!("http://{host_str}")) {
-            Ok(AllowedHost {
+        if let Ok(network) = host_str.parse::<IpNet>() {
+            return Ok(AllowedHost {
                 scheme: None,
-                host: url.host_str().unwrap_or("").to_string(),
-            })
-        } else {
-            Err(anyhow::anyhow!("Invalid host format: {}", host_str))
+                host: network.to_string(),
+                port: None,
+                pattern: HostPattern::Network(network),
+                rule: host_str.to_string(),
+            });
         }
+        // Without `://`, `example.com:8080` would parse as a URL with the
+        // scheme `example.com`
+        let (scheme, url) = if let Some(url) = host_str
+            .contains("://")
+            .then(|| Url::parse(host_str).ok())
+            .flatten()
+        {
+            (Some(url.scheme().to_string()), url)
+        } else if let Ok(url) = Url::parse(&format!("http://{host_str}")) {
+            (None, url)
+        } else {
+            return Err(anyhow::anyhow!("Invalid host format: {}", host_str));
+        };
+        // The URL parser drops a port that is the scheme's default, so tell
+        // `example.com:80` from `example.com` by looking at the string
+        let authority = host_str
+            .split_once("://")
+            .map_or(host_str, |(_, rest)| rest);
+        let authority = authority.split('/').next().unwrap_or_default();
+        let explicit_port = authority
+            .rsplit_once(':')
+            .is_some_and(|(_, port)| !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()));
+        let port = url
+            .port()
+            .or_else(|| explicit_port.then(|| url.port_or_known_default()).flatten());
+        let host = url.host_str().unwrap_or("");
+        let (pattern, host) = if host == ANY_HOST {
+            (HostPattern::Any, "")
+        } else if let Some(domain) = host.strip_prefix("*.") {
+            (HostPattern::Subdomains, domain)
+        } else {
+            (HostPattern::Exact, host)
+        };
+        Ok(AllowedHost {
+            scheme,
+            host: host.to_string(),
+            port,
+            pattern,
+            rule: host_str.to_string(),
+        })
     }
 
-    fn matches(&self, request_host: &str, request_scheme: Option<&str>) -> bool {
-        if self.host != request_host {
-            return false;
+    fn matches(&self, request: &RequestTarget) -> bool {
+        self.matches_host(&request.host)
+            && self.matches_scheme(request.scheme.as_deref())
+            && self.matches_port(request.port)
+    }
+
+    fn matches_host(&self, request_host: &str) -> bool {
+        match self.pattern {
+            HostPattern::Exact => self.host == request_host,
+            HostPattern::Subdomains => request_host
+                .strip_suffix(self.host.as_str())
+                .is_some_and(|prefix| prefix.len() > 1 && prefix.ends_with('.')),
+            HostPattern::Any => true,
+            HostPattern::Network(network) => {
+                request_ip(request_host).is_some_and(|ip| network.contains(&ip))
+            }
         }
+    }
 
+    fn matches_scheme(&self, request_scheme: Option<&str>) -> bool {
         match (&self.scheme, request_scheme) {
             (Some(allowed_scheme), Some(req_scheme)) => allowed_scheme == req_scheme,
             _ => true,
         }
     }
+
+    fn matches_port(&self, request_port: Option<u16>) -> bool {
+        self.port.is_none() || self.port == request_port
+    }
+
+    /// How narrowly the entry matches, so the most specific of several
+    /// matching entries can be picked.
+    fn specificity(&self) -> (usize, bool, bool) {
+        let host = match self.pattern {
+            HostPattern::Exact => usize::MAX,
+            HostPattern::Subdomains => self.host.len(),
+            HostPattern::Network(network) => network.prefix_len().into(),
+            HostPattern::Any => 0,
+        };
+        (host, self.port.is_some(), self.scheme.is_some())
+    }
+}
+
+/// The host, scheme, and port a request goes to; the port defaults to the
+/// scheme's.
+struct RequestTarget {
+    host: String,
+    scheme: Option<String>,
+    port: Option<u16>,
+}
+
+impl RequestTarget {
+    fn from_uri(uri: &hyper::Uri) -> Option<Self> {
+        let scheme = uri.scheme_str().map(str::to_ascii_lowercase);
+        let default_port = match scheme.as_deref() {
+            Some("http") => Some(80),
+            Some("https") => Some(443),
+            _ => None,
+        };
+        Some(Self {
+            host: uri.host()?.to_ascii_lowercase(),
+            port: uri.port_u16().or(default_port),
+            scheme,
+        })
+    }
+}
+
+/// The IP address a request host names, if it is an IP literal rather than a
+/// host name. IPv6 literals keep their brackets in a URI's host.
+fn request_ip(host: &str) -> Option<std::net::IpAddr> {
+    host.trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .ok()
+}
+
+/// The network among `denied_networks` that contains the IP address `uri`
+/// is sent to. Host names are never denied this way, as the address they
+/// resolve to is not known before the request is made.
+fn find_denied_network<'a>(denied_networks: &'a [IpNet], uri: &hyper::Uri) -> Option<&'a IpNet> {
+    let ip = request_ip(uri.host()?)?;
+    denied_networks.iter().find(|network| network.contains(&ip))
+}
+
+/// The networks of the CIDR entries in `rules`, skipping entries that are
+/// not valid CIDR notation.
+fn parse_networks<'a>(rules: impl IntoIterator<Item = &'a String>) -> Vec<IpNet> {
+    rules
+        .into_iter()
+        .filter_map(|rule| match rule.parse() {
+            Ok(network) => Some(network),
+            Err(error) => {
+                warn!(rule, %error, "Ignoring invalid CIDR network rule");
+                None
+            }
+        })
+        .collect()
+}
+
+/// The policy entry for a network host rule, as matched by
+/// [`WassetteWasiState`]: `https://api.example.com:443` for a rule with a
+/// scheme and port, just the host for a rule without.
+pub(crate) fn allowed_host_rule(rule: &NetworkHostPermission) -> String {
+    let mut entry = String::new();
+    if let Some(scheme) = &rule.scheme {
+        entry.push_str(scheme);
+        entry.push_str("://");
+    }
+    entry.push_str(&rule.host);
+    if let Some(port) = rule.port {
+        entry.push_str(&format!(":{port}"));
+    }
+    entry
+}
+
+/// Whether the allowed host entry `rule` matches every host.
+pub(crate) fn is_any_host(rule: &str) -> bool {
+    AllowedHost::from_str(rule).is_ok_and(|allowed| allowed.pattern == HostPattern::Any)
+}
+
+/// Find the allowed host that permits a request to `uri`.
+///
+/// Hosts are compared case-insensitively. An entry matches its host exactly,
+/// `*.example.com` matches subdomains of `example.com` at any depth but not
+/// `example.com` itself, and `*` matches every host. When several entries
+/// match, the most specific one wins: an exact host before a wildcard, and a
+/// longer wildcard before a shorter one. An entry with a scheme
+/// (`https://api.example.com`) only matches that scheme, and an entry with a
+/// port (`api.example.com:8443`) only that port, where a request without a
+/// port goes to its scheme's default; an entry without either matches any
+/// scheme and port. A CIDR entry (`10.0.0.0/8`) matches requests to an IP
+/// address in the network, and a longer prefix wins over a shorter one.
+/// The request method is not considered.
+fn find_allowed_host<'a>(
+    allowed_hosts: impl IntoIterator<Item = &'a AllowedHost>,
+    uri: &hyper::Uri,
+) -> Option<&'a AllowedHost> {
+    let request = RequestTarget::from_uri(uri)?;
+    allowed_hosts
+        .into_iter()
+        .filter(|allowed_host| allowed_host.matches(&request))
+        .max_by_key(|allowed_host| allowed_host.specificity())
+}
+
+/// For a request to `uri` that no entry allows, the scheme and port that
+/// kept entries for its host from matching. Both are `None` when no entry
+/// covers the host at all.
+fn denied_scheme_and_port<'a>(
+    allowed_hosts: impl IntoIterator<Item = &'a AllowedHost>,
+    uri: &hyper::Uri,
+) -> (Option<String>, Option<u16>) {
+    let Some(request) = RequestTarget::from_uri(uri) else {
+        return (None, None);
+    };
+    let for_host: Vec<&AllowedHost> = allowed_hosts
+        .into_iter()
+        .filter(|allowed_host| allowed_host.matches_host(&request.host))
+        .collect();
+    if for_host.is_empty() {
+        return (None, None);
+    }
+    let scheme_allowed = for_host
+        .iter()
+        .any(|allowed_host| allowed_host.matches_scheme(request.scheme.as_deref()));
+    let port_allowed = for_host
+        .iter()
+        .any(|allowed_host| allowed_host.matches_port(request.port));
+    // Each is allowed by some entry, but no entry allows both
+    let both = scheme_allowed && port_allowed;
+    (
+        (both || !scheme_allowed)
+            .then_some(request.scheme)
+            .flatten(),
+        (both || !port_allowed).then_some(request.port).flatten(),
+    )
+}
+
+/// The narrowest wildcard entry that would allow `host`: `*.api.example.com`
+/// for `eu.api.example.com`. `None` for IP addresses and hosts with fewer
+/// than three labels, where the wildcard would cover a whole top-level
+/// domain.
+pub(crate) fn narrowest_wildcard(host: &str) -> Option<String> {
+    if host.parse::<std::net::IpAddr>().is_ok() || host.starts_with('[') {
+        return None;
+    }
+    let (_, parent) = host.split_once('.')?;
+    parent
+        .contains('.')
+        .then(|| format!("*.{}", parent.to_ascii_lowercase()))
+}
+
+/// Result of checking whether a component may make an HTTP request, without
+/// making it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NetworkAccessCheck {
+    /// Whether the request would be allowed
+    pub allowed: bool,
+    /// URL that was checked
+    pub url: String,
+    /// HTTP method that was checked
+    pub method: String,
+    /// Host the request would be sent to
+    pub host: String,
+    /// The network policy entry that allows the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matched_rule: Option<String>,
+    /// The denied network the request's IP address is in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub denied_by: Option<String>,
+    /// CLI command that would allow the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grant_command: Option<String>,
+}
+
+/// Check a `method` request to `url` against a component's allowed hosts
+/// and denied networks using the same matching as [`WassetteWasiState`]
+/// applies to outgoing requests. Network rules apply to every method, so a
+/// `HEAD` or `OPTIONS` preflight is allowed exactly when a `GET` would be.
+pub(crate) fn check_network_access(
+    component_id: &str,
+    allowed_hosts: &HashSet<String>,
+    denied_networks: &HashSet<String>,
+    method: &str,
+    url: &str,
+) -> Result<NetworkAccessCheck> {
+    let method = hyper::Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+        .map_err(|_| anyhow::anyhow!("Invalid HTTP method '{method}'"))?;
+    let uri: hyper::Uri = url
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Invalid URL '{}': {}", url, e))?;
+    let Some(host) = uri.host().map(str::to_string) else {
+        bail!("URL must include a scheme and host, e.g. https://api.example.com/");
+    };
+
+    let parsed_hosts: Vec<AllowedHost> = allowed_hosts
+        .iter()
+        .filter_map(|host_str| AllowedHost::from_str(host_str).ok())
+        .collect();
+    let denied_by =
+        find_denied_network(&parse_networks(denied_networks), &uri).map(ToString::to_string);
+    let matched_rule = denied_by
+        .is_none()
+        .then(|| find_allowed_host(&parsed_hosts, &uri).map(|h| h.rule.clone()))
+        .flatten();
+    // A grant does not lift a denied network, so there is none to suggest
+    let grant_command = (matched_rule.is_none() && denied_by.is_none()).then(|| {
+        let mut command = format!("wassette permission grant network {component_id} {host}");
+        let (scheme, port) = denied_scheme_and_port(&parsed_hosts, &uri);
+        if let Some(port) = port {
+            command.push_str(&format!(" --port {port}"));
+        }
+        if let Some(scheme) = scheme {
+            command.push_str(&format!(" --scheme {scheme}"));
+        }
+        command
+    });
+
+    Ok(NetworkAccessCheck {
+        allowed: matched_rule.is_some(),
+        url: url.to_string(),
+        method: method.to_string(),
+        grant_command,
+        host,
+        matched_rule,
+        denied_by,
+    })
+}
+
+/// Default time allowed to establish a connection to a remote host
+pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 30;
+/// Default time allowed for a remote host to start responding
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 120;
+/// Default number of concurrent requests a component instance set may have
+/// open to one host
+pub const DEFAULT_MAX_CONNECTIONS_PER_HOST: usize = 16;
+
+/// Limits applied to HTTP requests made by components.
+///
+/// A network rule in a component's policy can raise or lower the request
+/// timeout for its host with `timeout`. Response bodies are streamed to the
+/// component as they arrive, so only `max_response_bytes` bounds how much a
+/// component may download.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboundHttpConfig {
+    /// Maximum time to establish a connection
+    pub connect_timeout: Duration,
+    /// Maximum time from sending a request until the response headers arrive,
+    /// including any time spent waiting for a free connection slot
+    pub request_timeout: Duration,
+    /// Maximum concurrent requests to a single host across all components
+    pub max_connections_per_host: usize,
+    /// Maximum size of a response body, or `None` for no limit. Checked
+    /// against `Content-Length` before the body is read and again as each
+    /// chunk arrives
+    pub max_response_bytes: Option<u64>,
+}
+
+impl Default for OutboundHttpConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECS),
+            request_timeout: Duration::from_secs(DEFAULT_REQUEST_TIMEOUT_SECS),
+            max_connections_per_host: DEFAULT_MAX_CONNECTIONS_PER_HOST,
+            max_response_bytes: None,
+        }
+    }
+}
+
+/// Shared per-host connection limits for outgoing requests.
+pub(crate) struct OutboundHttpLimiter {
+    config: OutboundHttpConfig,
+    hosts: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl OutboundHttpLimiter {
+    pub(crate) fn new(config: OutboundHttpConfig) -> Self {
+        Self {
+            config,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Connection slots for `host`, created on first use.
+    fn host_slots(&self, host: &str) -> Arc<Semaphore> {
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        hosts
+            .entry(host.to_ascii_lowercase())
+            .or_insert_with(|| {
+                Arc::new(Semaphore::new(self.config.max_connections_per_host.max(1)))
+            })
+            .clone()
+    }
+
+    /// Connection slots to `host` that are free right now.
+    fn available_slots(&self, host: &str) -> usize {
+        let max = self.config.max_connections_per_host.max(1);
+        let hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        hosts
+            .get(&host.to_ascii_lowercase())
+            .map_or(max, |slots| slots.available_permits())
+    }
 }
 
 /// WassetteWasiState is a wrapper around a WASI state that enforces network policies by filtering
@@ -58,8 +464,18 @@ pub struct WassetteWasiState<T> {
     /// Set of allowed hosts for network requests (extracted from policy document)
     allowed_hosts: HashSet<AllowedHost>,
 
-    /// Last permission error (for tracking network denials)
-    last_network_denial: std::sync::Arc<std::sync::Mutex<Option<(String, String)>>>,
+    /// Networks requests to an IP address may not go to, even when an
+    /// allowed host matches
+    denied_networks: Vec<IpNet>,
+
+    /// Last network denial or timeout, recorded for error reporting
+    last_network_error: Arc<Mutex<Option<PermissionError>>>,
+
+    /// Timeouts and connection limits, when enforced
+    outbound: Option<Arc<OutboundHttpLimiter>>,
+
+    /// Request timeouts set on individual network rules
+    rule_timeouts: HashMap<String, Duration>,
 }
 
 impl<T> WassetteWasiState<T> {
@@ -82,28 +498,73 @@ impl<T> WassetteWasiState<T> {
         Ok(Self {
             inner,
             allowed_hosts: parsed_hosts,
-            last_network_denial: std::sync::Arc::new(std::sync::Mutex::new(None)),
+            denied_networks: Vec::new(),
+            last_network_error: Arc::new(Mutex::new(None)),
+            outbound: None,
+            rule_timeouts: HashMap::new(),
         })
     }
 
+    /// Deny requests to IP addresses in `denied_networks`, given in CIDR
+    /// notation, whatever the allowed hosts say.
+    pub fn with_denied_networks(mut self, denied_networks: &HashSet<String>) -> Self {
+        self.denied_networks = parse_networks(denied_networks);
+        self
+    }
+
+    /// Enforce `limiter`'s timeouts and connection limits on outgoing
+    /// requests, with `rule_timeouts` overriding the request timeout for the
+    /// matching network rule.
+    pub(crate) fn with_outbound_limits(
+        mut self,
+        limiter: Arc<OutboundHttpLimiter>,
+        rule_timeouts: HashMap<String, Duration>,
+    ) -> Self {
+        self.outbound = Some(limiter);
+        self.rule_timeouts = rule_timeouts;
+        self
+    }
+
     /// Check if a host is allowed by the policy
     fn is_host_allowed(&self, uri: &hyper::Uri) -> bool {
-        let request_host = if let Some(host) = uri.host() {
-            host.to_string()
-        } else {
-            return false;
-        };
+        find_denied_network(&self.denied_networks, uri).is_none()
+            && find_allowed_host(&self.allowed_hosts, uri).is_some()
+    }
 
-        let request_scheme = uri.scheme().map(|s| s.as_str());
+    /// Request timeout for `uri`: the matching rule's timeout, else the default.
+    fn request_timeout(&self, uri: &hyper::Uri, default: Duration) -> Duration {
+        find_allowed_host(&self.allowed_hosts, uri)
+            .and_then(|allowed| self.rule_timeouts.get(&allowed.rule))
+            .copied()
+            .unwrap_or(default)
+    }
 
-        let req = request_host.to_ascii_lowercase();
-        for allowed_host in &self.allowed_hosts {
-            if allowed_host.matches(&req, request_scheme) {
-                return true;
-            }
-        }
+    /// Free and total connection slots for each host the policy allows,
+    /// sorted by host. Empty when connection limits are not enforced.
+    pub(crate) fn host_connection_slots(&self) -> Vec<(String, usize, usize)> {
+        let Some(limiter) = &self.outbound else {
+            return Vec::new();
+        };
+        let max = limiter.config.max_connections_per_host.max(1);
+        // Slots are kept per host, so wildcard entries have none of their own
+        let mut hosts: Vec<&str> = self
+            .allowed_hosts
+            .iter()
+            .filter(|allowed| allowed.pattern == HostPattern::Exact)
+            .map(|allowed| allowed.host.as_str())
+            .collect();
+        hosts.sort_unstable();
+        hosts.dedup();
+        hosts
+            .into_iter()
+            .map(|host| (host.to_string(), limiter.available_slots(host), max))
+            .collect()
+    }
 
-        false
+    fn record_network_error(&self, error: PermissionError) {
+        if let Ok(mut last) = self.last_network_error.lock() {
+            *last = Some(error);
+        }
     }
 }
 
@@ -111,13 +572,10 @@ impl<T> WassetteWasiState<T> {
 impl WassetteWasiState<crate::wasistate::WasiState> {
     /// Get the last permission error if any occurred (checks both sources)
     pub fn get_last_permission_error(&self) -> Option<PermissionError> {
-        // First check if there was a network denial recorded
-        if let Ok(denial) = self.last_network_denial.lock() {
-            if let Some((host, uri)) = denial.as_ref() {
-                return Some(PermissionError::NetworkDenied {
-                    host: host.clone(),
-                    uri: uri.clone(),
-                });
+        // First check if there was a network denial or timeout recorded
+        if let Ok(last) = self.last_network_error.lock() {
+            if let Some(error) = last.as_ref() {
+                return Some(error.clone());
             }
         }
 
@@ -128,6 +586,27 @@ impl WassetteWasiState<crate::wasistate::WasiState> {
             .ok()
             .and_then(|guard| guard.clone())
     }
+
+    /// Get the last outgoing request timeout, if one occurred
+    pub fn last_network_timeout(&self) -> Option<PermissionError> {
+        self.last_network_error
+            .lock()
+            .ok()
+            .and_then(|last| last.clone())
+            .filter(|error| matches!(error, PermissionError::NetworkTimeout { .. }))
+    }
+
+    /// Forget the errors and deadline of the last call, before the instance
+    /// runs another one.
+    pub(crate) fn reset_for_call(&mut self) {
+        if let Ok(mut last) = self.last_network_error.lock() {
+            *last = None;
+        }
+        if let Ok(mut last) = self.inner.last_permission_error.lock() {
+            *last = None;
+        }
+        self.inner.execution_deadline = None;
+    }
 }
 
 impl<T: WasiView> WasiView for WassetteWasiState<T> {
@@ -177,22 +656,183 @@ impl<T: WasiHttpView> WasiHttpView for WassetteWasiState<T> {
             );
 
             // Record the network denial for later retrieval
-            if let Ok(mut denial) = self.last_network_denial.lock() {
-                *denial = Some((host, uri_str));
-            }
+            let (scheme, port) = denied_scheme_and_port(&self.allowed_hosts, uri);
+            self.record_network_error(PermissionError::NetworkDenied {
+                host,
+                uri: uri_str,
+                scheme,
+                port,
+            });
 
             return Err(types::ErrorCode::HttpRequestDenied.into());
         }
 
         debug!(uri = %uri, "HTTP request allowed by network policy");
 
-        self.inner.send_request(request, config)
+        let Some(limiter) = self.outbound.clone() else {
+            return self.inner.send_request(request, config);
+        };
+
+        let host = uri.host().unwrap_or("").to_string();
+        let uri_str = uri.to_string();
+        let connect_timeout = limiter.config.connect_timeout;
+        let max_response_bytes = limiter.config.max_response_bytes;
+        let request_timeout = self.request_timeout(uri, limiter.config.request_timeout);
+        let config = OutgoingRequestConfig {
+            connect_timeout: config.connect_timeout.min(connect_timeout),
+            first_byte_timeout: config.first_byte_timeout.min(request_timeout),
+            between_bytes_timeout: config.between_bytes_timeout.min(request_timeout),
+            ..config
+        };
+        let slots = limiter.host_slots(&host);
+        let last_network_error = Arc::clone(&self.last_network_error);
+
+        let handle = wasmtime_wasi::runtime::spawn(async move {
+            let send = async {
+                let permit = slots
+                    .acquire_owned()
+                    .await
+                    .expect("semaphore is never closed");
+                let mut response = default_send_request_handler(request, config).await?;
+                if let Some(limit) = max_response_bytes {
+                    response = limit_response_body(response, limit, &uri_str)?;
+                }
+                Ok(hold_until_body_done(response, permit))
+            };
+            let (result, timeout) = match tokio::time::timeout(request_timeout, send).await {
+                Ok(Err(types::ErrorCode::ConnectionTimeout)) => {
+                    (Err(types::ErrorCode::ConnectionTimeout), connect_timeout)
+                }
+                Ok(Err(types::ErrorCode::ConnectionReadTimeout)) | Err(_) => (
+                    Err(types::ErrorCode::ConnectionReadTimeout),
+                    request_timeout,
+                ),
+                Ok(result) => return Ok(result),
+            };
+
+            warn!(
+                uri = %uri_str,
+                timeout_ms = %timeout.as_millis(),
+                "HTTP request timed out"
+            );
+            if let Ok(mut last) = last_network_error.lock() {
+                *last = Some(PermissionError::NetworkTimeout {
+                    host,
+                    uri: uri_str,
+                    timeout,
+                });
+            }
+            Ok(result)
+        });
+
+        Ok(HostFutureIncomingResponse::pending(handle))
+    }
+}
+
+/// Keep a host connection slot taken until `response`'s body has been read.
+fn hold_until_body_done(
+    mut response: IncomingResponse,
+    permit: tokio::sync::OwnedSemaphorePermit,
+) -> IncomingResponse {
+    let worker = response.worker.take();
+    response.worker = Some(wasmtime_wasi::runtime::spawn(async move {
+        if let Some(worker) = worker {
+            worker.await;
+        }
+        drop(permit);
+    }));
+    response
+}
+
+/// Fail `response` if it declares a body larger than `limit`, and otherwise
+/// wrap its body so reading past `limit` bytes fails.
+fn limit_response_body(
+    mut response: IncomingResponse,
+    limit: u64,
+    uri: &str,
+) -> Result<IncomingResponse, types::ErrorCode> {
+    let declared = response
+        .resp
+        .headers()
+        .get(hyper::header::CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse::<u64>().ok());
+    if let Some(length) = declared.filter(|length| *length > limit) {
+        warn!(uri, length, limit, "HTTP response exceeds the size limit");
+        return Err(types::ErrorCode::HttpResponseBodySize(Some(length)));
+    }
+    let uri = uri.to_string();
+    response.resp = response
+        .resp
+        .map(|body| LimitedBody::new(body, limit, uri).boxed());
+    Ok(response)
+}
+
+/// A response body that passes frames through as they arrive and fails once
+/// more than `limit` bytes have been read.
+struct LimitedBody {
+    inner: HyperIncomingBody,
+    limit: u64,
+    received: u64,
+    uri: String,
+}
+
+impl LimitedBody {
+    fn new(inner: HyperIncomingBody, limit: u64, uri: String) -> Self {
+        Self {
+            inner,
+            limit,
+            received: 0,
+            uri,
+        }
+    }
+}
+
+impl Body for LimitedBody {
+    type Data = Bytes;
+    type Error = types::ErrorCode;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, types::ErrorCode>>> {
+        let this = &mut *self;
+        let frame = ready!(Pin::new(&mut this.inner).poll_frame(cx));
+        if let Some(data) = frame
+            .as_ref()
+            .and_then(|frame| frame.as_ref().ok())
+            .and_then(Frame::data_ref)
+        {
+            this.received += data.len() as u64;
+            if this.received > this.limit {
+                warn!(
+                    uri = %this.uri,
+                    limit = this.limit,
+                    "HTTP response exceeded the size limit while streaming"
+                );
+                return Poll::Ready(Some(Err(types::ErrorCode::HttpResponseBodySize(Some(
+                    this.received,
+                )))));
+            }
+        }
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use std::collections::HashSet;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::StreamExt;
 
     use super::*;
 
@@ -278,6 +918,52 @@ mod tests {
         assert!(state.is_host_allowed(&uri2));
     }
 
+    #[test]
+    fn test_port_and_scheme_restrictions() {
+        let rule = |host: &str, port, scheme: Option<&str>| {
+            allowed_host_rule(&NetworkHostPermission {
+                host: host.to_string(),
+                port,
+                scheme: scheme.map(str::to_string),
+                ..Default::default()
+            })
+        };
+        let allowed_hosts = HashSet::from([
+            rule("api.example.com", Some(443), Some("https")),
+            rule("internal.corp", Some(80), None),
+            rule("*.cdn.example.com", Some(8443), None),
+        ]);
+        assert!(allowed_hosts.contains("https://api.example.com:443"));
+        let state = WassetteWasiState::new(create_mock_wasi_state(), allowed_hosts).unwrap();
+        let allowed = |url: &str| state.is_host_allowed(&url.parse().unwrap());
+
+        // A request without a port goes to its scheme's default
+        assert!(allowed("https://api.example.com/v1"));
+        assert!(allowed("https://api.example.com:443/v1"));
+        assert!(!allowed("https://api.example.com:8443/v1"));
+        assert!(!allowed("http://api.example.com:443/v1"));
+        assert!(allowed("http://internal.corp/"));
+        assert!(!allowed("http://internal.corp:8080/admin"));
+        assert!(allowed("https://eu.cdn.example.com:8443/"));
+        assert!(!allowed("https://eu.cdn.example.com/"));
+
+        let denied =
+            |url: &str| denied_scheme_and_port(&state.allowed_hosts, &url.parse().unwrap());
+        assert_eq!(
+            denied("http://internal.corp:8080/admin"),
+            (None, Some(8080))
+        );
+        assert_eq!(
+            denied("http://api.example.com/"),
+            (Some("http".to_string()), Some(80))
+        );
+        assert_eq!(
+            denied("http://api.example.com:443/"),
+            (Some("http".to_string()), None)
+        );
+        assert_eq!(denied("https://other.example.com/"), (None, None));
+    }
+
     #[test]
     fn test_scheme_specific_matching() {
         let mut allowed_hosts = HashSet::new();
@@ -313,6 +999,141 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_check_network_access() {
+        let allowed_hosts = HashSet::from([
+            "https://secure.api.com".to_string(),
+            "api.example.com".to_string(),
+        ]);
+
+        let check = check_network_access(
+            "fetch",
+            &allowed_hosts,
+            &HashSet::new(),
+            "GET",
+            "https://API.example.com:8443/v1",
+        )
+        .unwrap();
+        assert!(check.allowed);
+        assert_eq!(check.matched_rule.as_deref(), Some("api.example.com"));
+        assert_eq!(check.grant_command, None);
+
+        let check = check_network_access(
+            "fetch",
+            &allowed_hosts,
+            &HashSet::new(),
+            "GET",
+            "https://secure.api.com/",
+        )
+        .unwrap();
+        assert_eq!(
+            check.matched_rule.as_deref(),
+            Some("https://secure.api.com")
+        );
+
+        let check = check_network_access(
+            "fetch",
+            &allowed_hosts,
+            &HashSet::new(),
+            "GET",
+            "http://secure.api.com/",
+        )
+        .unwrap();
+        assert!(!check.allowed);
+        assert_eq!(
+            check.grant_command.as_deref(),
+            Some("wassette permission grant network fetch secure.api.com --scheme http")
+        );
+
+        let check = check_network_access(
+            "fetch",
+            &allowed_hosts,
+            &HashSet::new(),
+            "GET",
+            "https://other.api.com/",
+        )
+        .unwrap();
+        assert_eq!(
+            check.grant_command.as_deref(),
+            Some("wassette permission grant network fetch other.api.com")
+        );
+
+        assert!(
+            check_network_access("fetch", &allowed_hosts, &HashSet::new(), "GET", "/relative")
+                .is_err()
+        );
+
+        // Every method is allowed by the same rules
+        let check = check_network_access(
+            "fetch",
+            &allowed_hosts,
+            &HashSet::new(),
+            "options",
+            "https://api.example.com/",
+        )
+        .unwrap();
+        assert!(check.allowed);
+        assert_eq!(check.method, "OPTIONS");
+        let error = check_network_access(
+            "fetch",
+            &allowed_hosts,
+            &HashSet::new(),
+            "GE T",
+            "https://api.example.com/",
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "Invalid HTTP method 'GE T'");
+    }
+
+    #[test]
+    fn test_cidr_rules_match_ip_addresses() {
+        let allowed_hosts = HashSet::from([
+            "10.0.0.0/8".to_string(),
+            "10.1.0.0/16".to_string(),
+            "fd00::/8".to_string(),
+            "api.example.com".to_string(),
+        ]);
+        let denied_networks = HashSet::from(["10.1.2.0/24".to_string()]);
+        let check = |url: &str| {
+            check_network_access("fetch", &allowed_hosts, &denied_networks, "GET", url).unwrap()
+        };
+
+        let allowed = check("http://10.1.9.9:8080/");
+        assert!(allowed.allowed);
+        assert_eq!(allowed.matched_rule.as_deref(), Some("10.1.0.0/16"));
+        assert_eq!(
+            check("http://[fd00::1]/").matched_rule.as_deref(),
+            Some("fd00::/8")
+        );
+
+        let denied = check("http://10.1.2.3/");
+        assert!(!denied.allowed);
+        assert_eq!(denied.denied_by.as_deref(), Some("10.1.2.0/24"));
+        assert_eq!(denied.grant_command, None);
+
+        let outside = check("http://192.168.1.1/");
+        assert!(!outside.allowed);
+        assert_eq!(
+            outside.grant_command.as_deref(),
+            Some("wassette permission grant network fetch 192.168.1.1")
+        );
+        // Host names are matched by host rules only
+        assert!(!check("http://intranet.example.com/").allowed);
+
+        let state = WassetteWasiState::new(create_mock_wasi_state(), allowed_hosts.clone())
+            .unwrap()
+            .with_denied_networks(&denied_networks);
+        for (url, allowed) in [
+            ("http://10.200.0.1/", true),
+            ("http://10.1.2.200/", false),
+            ("http://192.168.1.1/", false),
+            ("https://api.example.com/", true),
+        ] {
+            let uri: hyper::Uri = url.parse().unwrap();
+            assert_eq!(state.is_host_allowed(&uri), allowed, "{url}");
+        }
+    }
+
     #[test]
     fn test_host_matching_is_case_insensitive() {
         let mut allowed_hosts = HashSet::new();
@@ -326,4 +1147,217 @@ mod tests {
         assert!(state.is_host_allowed(&uri1));
         assert!(state.is_host_allowed(&uri2));
     }
+
+    #[test]
+    fn test_wildcard_hosts_match_subdomains() {
+        let allowed_hosts = HashSet::from(["*.example.com".to_string()]);
+        let state = WassetteWasiState::new(create_mock_wasi_state(), allowed_hosts).unwrap();
+
+        for allowed in [
+            "https://api.example.com/",
+            "https://eu.api.example.com/",
+            "http://A.B.C.EXAMPLE.COM:8080/",
+        ] {
+            let uri: hyper::Uri = allowed.parse().unwrap();
+            assert!(state.is_host_allowed(&uri), "{allowed}");
+        }
+        for denied in [
+            "https://example.com/",
+            "https://badexample.com/",
+            "https://example.com.evil.net/",
+        ] {
+            let uri: hyper::Uri = denied.parse().unwrap();
+            assert!(!state.is_host_allowed(&uri), "{denied}");
+        }
+
+        let allowed_hosts = HashSet::from([ANY_HOST.to_string()]);
+        let state = WassetteWasiState::new(create_mock_wasi_state(), allowed_hosts).unwrap();
+        let uri: hyper::Uri = "https://anything.test/".parse().unwrap();
+        assert!(state.is_host_allowed(&uri));
+    }
+
+    #[test]
+    fn test_most_specific_host_rule_wins() {
+        let allowed_hosts = HashSet::from([
+            "*".to_string(),
+            "*.example.com".to_string(),
+            "*.api.example.com".to_string(),
+            "eu.api.example.com".to_string(),
+        ]);
+        let matched_rule = |url: &str| {
+            check_network_access("fetch", &allowed_hosts, &HashSet::new(), "GET", url)
+                .unwrap()
+                .matched_rule
+        };
+
+        assert_eq!(
+            matched_rule("https://eu.api.example.com/").as_deref(),
+            Some("eu.api.example.com")
+        );
+        assert_eq!(
+            matched_rule("https://us.api.example.com/").as_deref(),
+            Some("*.api.example.com")
+        );
+        assert_eq!(
+            matched_rule("https://api.example.com/").as_deref(),
+            Some("*.example.com")
+        );
+        assert_eq!(matched_rule("https://example.com/").as_deref(), Some("*"));
+    }
+
+    #[test]
+    fn test_narrowest_wildcard() {
+        assert_eq!(
+            narrowest_wildcard("eu.api.example.com").as_deref(),
+            Some("*.api.example.com")
+        );
+        assert_eq!(
+            narrowest_wildcard("api.example.com").as_deref(),
+            Some("*.example.com")
+        );
+        assert_eq!(narrowest_wildcard("example.com"), None);
+        assert_eq!(narrowest_wildcard("localhost"), None);
+        assert_eq!(narrowest_wildcard("10.0.0.1"), None);
+        assert_eq!(narrowest_wildcard("[::1]"), None);
+    }
+
+    #[test]
+    fn test_request_timeout_uses_matching_rule() {
+        let allowed_hosts = HashSet::from([
+            "api.example.com".to_string(),
+            "https://slow.example.com".to_string(),
+        ]);
+        let state = WassetteWasiState::new(create_mock_wasi_state(), allowed_hosts)
+            .unwrap()
+            .with_outbound_limits(
+                Arc::new(OutboundHttpLimiter::new(OutboundHttpConfig::default())),
+                HashMap::from([(
+                    "https://slow.example.com".to_string(),
+                    Duration::from_secs(300),
+                )]),
+            );
+        let default = Duration::from_secs(10);
+
+        let uri: hyper::Uri = "https://slow.example.com/report".parse().unwrap();
+        assert_eq!(
+            state.request_timeout(&uri, default),
+            Duration::from_secs(300)
+        );
+
+        let uri: hyper::Uri = "https://api.example.com/".parse().unwrap();
+        assert_eq!(state.request_timeout(&uri, default), default);
+    }
+
+    #[tokio::test]
+    async fn test_connection_slots_are_shared_per_host() {
+        let limiter = OutboundHttpLimiter::new(OutboundHttpConfig {
+            max_connections_per_host: 1,
+            ..Default::default()
+        });
+
+        let permit = limiter
+            .host_slots("api.example.com")
+            .try_acquire_owned()
+            .unwrap();
+        assert!(limiter.host_slots("API.example.com").try_acquire().is_err());
+        assert!(limiter
+            .host_slots("other.example.com")
+            .try_acquire()
+            .is_ok());
+
+        drop(permit);
+        assert!(limiter.host_slots("api.example.com").try_acquire().is_ok());
+    }
+
+    static CHUNK: [u8; 64 * 1024] = [0; 64 * 1024];
+
+    /// A body of `chunks` frames, produced only when read, counting how many
+    /// have been produced.
+    fn lazy_body(chunks: usize, produced: Arc<AtomicUsize>) -> HyperIncomingBody {
+        let frames = futures::stream::iter(0..chunks).map(move |_| {
+            produced.fetch_add(1, Ordering::SeqCst);
+            Ok(Frame::data(Bytes::from_static(&CHUNK)))
+        });
+        BodyExt::boxed(http_body_util::StreamBody::new(frames))
+    }
+
+    fn incoming_response(body: HyperIncomingBody, content_length: Option<u64>) -> IncomingResponse {
+        let mut resp = hyper::Response::builder();
+        if let Some(length) = content_length {
+            resp = resp.header(hyper::header::CONTENT_LENGTH, length);
+        }
+        IncomingResponse {
+            resp: resp.body(body).unwrap(),
+            worker: None,
+            between_bytes_timeout: Duration::from_secs(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_limited_body_streams_without_buffering() {
+        // 1 GiB, far more than the test may hold in memory at once
+        let chunks = 16 * 1024;
+        let produced = Arc::new(AtomicUsize::new(0));
+        let mut body = LimitedBody::new(
+            lazy_body(chunks, Arc::clone(&produced)),
+            2 << 30,
+            "https://example.com/large".to_string(),
+        );
+
+        let mut read = 0;
+        let mut bytes = 0;
+        while let Some(frame) = body.frame().await {
+            read += 1;
+            bytes += frame.unwrap().into_data().unwrap().len();
+            // Each chunk reaches the reader before the next one is produced
+            assert_eq!(produced.load(Ordering::SeqCst), read);
+        }
+        assert_eq!(read, chunks);
+        assert_eq!(bytes, 1 << 30);
+    }
+
+    #[tokio::test]
+    async fn test_limited_body_fails_once_past_the_limit() {
+        let produced = Arc::new(AtomicUsize::new(0));
+        let limit = 3 * CHUNK.len() as u64 + 1;
+        let mut body = LimitedBody::new(
+            lazy_body(100, Arc::clone(&produced)),
+            limit,
+            "https://example.com/large".to_string(),
+        );
+
+        for _ in 0..3 {
+            assert!(body.frame().await.unwrap().is_ok());
+        }
+        let error = body.frame().await.unwrap().unwrap_err();
+        assert!(matches!(
+            error,
+            types::ErrorCode::HttpResponseBodySize(Some(size)) if size == 4 * CHUNK.len() as u64
+        ));
+        assert_eq!(produced.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_limit_response_body_checks_content_length() {
+        let uri = "https://example.com/large";
+        let produced = Arc::new(AtomicUsize::new(0));
+        let declared = 10 * CHUNK.len() as u64;
+        let response = incoming_response(lazy_body(10, Arc::clone(&produced)), Some(declared));
+        let error = limit_response_body(response, declared - 1, uri).unwrap_err();
+        assert!(matches!(
+            error,
+            types::ErrorCode::HttpResponseBodySize(Some(size)) if size == declared
+        ));
+        assert_eq!(produced.load(Ordering::SeqCst), 0);
+
+        let response = incoming_response(lazy_body(10, Arc::clone(&produced)), Some(declared));
+        let response = limit_response_body(response, declared, uri).unwrap();
+        let body = response.resp.into_body().collect().await.unwrap();
+        assert_eq!(body.to_bytes().len() as u64, declared);
+
+        // Without a declared length the limit applies while reading
+        let response = incoming_response(lazy_body(10, Arc::clone(&produced)), None);
+        let response = limit_response_body(response, declared - 1, uri).unwrap();
+        assert!(response.resp.into_body().collect().await.is_err());
+    }
 }
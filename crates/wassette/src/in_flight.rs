@@ -0,0 +1,80 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Deduplication of overlapping operations on the same key, such as two
+//! clients loading the same component URI at once.
+//!
+//! An operation holds the lock of its key while it runs. One started for
+//! the same key meanwhile waits for it, then finds what the first stored in
+//! the lock and can return that instead of doing the work again. A key is
+//! forgotten once nothing holds or waits for its lock, so the next
+//! operation on it starts afresh.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+
+use tokio::sync::OwnedMutexGuard;
+
+type Slot<T> = tokio::sync::Mutex<Option<T>>;
+
+/// Per-key locks for operations in progress, each holding the result the
+/// last holder stored.
+pub(crate) struct InFlight<T> {
+    slots: Mutex<HashMap<String, Weak<Slot<T>>>>,
+}
+
+impl<T> Default for InFlight<T> {
+    fn default() -> Self {
+        Self {
+            slots: Mutex::default(),
+        }
+    }
+}
+
+impl<T> InFlight<T> {
+    /// Lock `key`, waiting for the operation holding it to finish. The guard
+    /// holds the result a previous holder stored, if this caller overlapped
+    /// with it; storing a result shares it with the callers still waiting.
+    pub(crate) async fn lock(&self, key: &str) -> OwnedMutexGuard<Option<T>> {
+        let slot = {
+            let mut slots = self.slots.lock().unwrap();
+            slots.retain(|_, slot| slot.strong_count() > 0);
+            match slots.get(key).and_then(Weak::upgrade) {
+                Some(slot) => slot,
+                None => {
+                    let slot = Arc::new(Slot::default());
+                    slots.insert(key.to_string(), Arc::downgrade(&slot));
+                    slot
+                }
+            }
+        };
+        slot.lock_owned().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_waiting_caller_sees_stored_result() {
+        let in_flight = Arc::new(InFlight::<u32>::default());
+        let mut first = in_flight.lock("oci://example/a").await;
+        assert!(first.is_none());
+
+        let waiting = tokio::spawn({
+            let in_flight = in_flight.clone();
+            async move { *in_flight.lock("oci://example/a").await }
+        });
+        tokio::task::yield_now().await;
+        // Other keys are not held up
+        assert!(in_flight.lock("oci://example/b").await.is_none());
+
+        *first = Some(7);
+        drop(first);
+        assert_eq!(waiting.await.unwrap(), Some(7));
+
+        // Nobody overlapped with this caller, so it starts afresh
+        assert!(in_flight.lock("oci://example/a").await.is_none());
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Idle component instances kept after a call, so later calls of the same
+//! component skip instantiation.
+//!
+//! Pooling is opt-in with
+//! [`LifecycleBuilder::with_instance_pool`](crate::LifecycleBuilder::with_instance_pool)
+//! and reuses instances at the component level: what the guest keeps in its
+//! memory between calls stays there. Each instance remembers the compiled
+//! component and the policy version it was built for, and is only handed
+//! out while both are still current. Policy changes and reloads also drop
+//! the component's instances right away, so they do not linger.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use wasmtime::component::{Instance, InstancePre};
+use wasmtime::Store;
+
+use crate::{WasiState, WassetteWasiState};
+
+type ComponentPre = Arc<InstancePre<WassetteWasiState<WasiState>>>;
+
+/// What a pooled instance was built from: the compiled component and the
+/// version of the policy template its sandbox came from.
+#[derive(Clone)]
+pub(crate) struct PoolKey {
+    pub(crate) instance_pre: ComponentPre,
+    pub(crate) policy_version: u64,
+}
+
+impl PoolKey {
+    fn matches(&self, other: &PoolKey) -> bool {
+        Arc::ptr_eq(&self.instance_pre, &other.instance_pre)
+            && self.policy_version == other.policy_version
+    }
+}
+
+/// An instantiated component and its store, idle between calls.
+pub(crate) struct PooledInstance {
+    key: PoolKey,
+    pub(crate) store: Store<WassetteWasiState<WasiState>>,
+    pub(crate) instance: Instance,
+}
+
+/// Whether an instance whose sandbox holds `state` can be pooled. Sandboxes
+/// that capture the guest's output or broker its calls keep state of the
+/// call they were built for.
+pub(crate) fn can_pool(state: &WasiState) -> bool {
+    state.stdout_capture.is_none() && state.stderr_capture.is_none() && state.broker.is_none()
+}
+
+/// Up to `size` idle instances per component.
+pub(crate) struct InstancePool {
+    size: usize,
+    idle: Mutex<HashMap<String, Vec<PooledInstance>>>,
+}
+
+impl InstancePool {
+    pub(crate) fn new(size: usize) -> Self {
+        Self {
+            size,
+            idle: Mutex::default(),
+        }
+    }
+
+    /// Whether instances are pooled at all.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.size > 0
+    }
+
+    /// An idle instance of `component_id` built for `key`. Instances built
+    /// for anything else are dropped.
+    pub(crate) fn take(&self, component_id: &str, key: &PoolKey) -> Option<PooledInstance> {
+        let mut idle = self.idle.lock().unwrap();
+        let instances = idle.get_mut(component_id)?;
+        instances.retain(|pooled| pooled.key.matches(key));
+        let pooled = instances.pop();
+        if instances.is_empty() {
+            idle.remove(component_id);
+        }
+        pooled
+    }
+
+    /// Keep an instance of `component_id` built for `key` for a later call,
+    /// unless the component already has as many idle instances as the pool
+    /// holds.
+    pub(crate) fn put(
+        &self,
+        component_id: &str,
+        key: PoolKey,
+        store: Store<WassetteWasiState<WasiState>>,
+        instance: Instance,
+    ) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut idle = self.idle.lock().unwrap();
+        let instances = idle.entry(component_id.to_string()).or_default();
+        if instances.len() < self.size {
+            instances.push(PooledInstance {
+                key,
+                store,
+                instance,
+            });
+        }
+    }
+
+    /// Drop the idle instances of `component_id`.
+    pub(crate) fn invalidate(&self, component_id: &str) {
+        self.idle.lock().unwrap().remove(component_id);
+    }
+
+    /// Number of idle instances of `component_id`.
+    #[cfg(test)]
+    pub(crate) fn idle(&self, component_id: &str) -> usize {
+        self.idle
+            .lock()
+            .unwrap()
+            .get(component_id)
+            .map_or(0, Vec::len)
+    }
+}
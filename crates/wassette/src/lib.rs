@@ -3,64 +3,206 @@
 
 //! A security-oriented runtime that runs WebAssembly Components via MCP
 
-#![warn(missing_docs)]
 #![warn(missing_docs)]
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
 use component2json::{
     component_exports_to_json_schema, component_exports_to_json_schema_with_docs,
-    component_exports_to_tools, component_exports_to_tools_with_docs, create_placeholder_results,
-    extract_package_docs, json_to_vals, vals_to_json, FunctionIdentifier, ToolMetadata,
+    component_exports_to_tools, component_exports_to_tools_with_docs, component_skipped_exports,
+    create_placeholder_results, extract_package_docs, json_to_vals, package_docs_to_markdown,
+    vals_to_json, FunctionIdentifier, SkippedExport,
 };
 use etcetera::BaseStrategy;
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use base64::Engine;
 use tokio::fs::DirEntry;
-use tokio::sync::{RwLock, Semaphore};
+use tokio::sync::{broadcast, RwLock};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, instrument, warn};
 use wasmtime::component::{Component, InstancePre};
 use wasmtime::Store;
 
+#[cfg(any(test, feature = "blocking"))]
+pub mod blocking;
+mod broker;
+mod call_middleware;
+mod changes;
+mod compile_info;
+mod component_filter;
 mod component_storage;
 mod config;
+mod denials;
+mod dir_watch;
+mod download_cache;
+mod embedded;
+mod events;
+mod health;
+mod hooks;
 mod http;
+mod in_flight;
+mod instance_pool;
+mod limits;
+mod load_state;
 mod loader;
+mod manifest;
+mod metrics;
 pub mod oci_multi_layer;
+mod parameter_limits;
+mod path_guard;
+mod permission_hook;
+mod policy_fragments;
+mod policy_history;
 mod policy_internal;
+mod priority;
+mod progress;
+mod prometheus;
+pub mod registry;
 mod runtime_context;
 pub mod schema;
+mod scratch;
 mod secrets;
+mod signature;
+mod source_policy;
+mod state_archive;
+mod stdio;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod tool_collision;
+mod usage;
+mod visibility;
+mod warmup;
 mod wasistate;
 
-use component_storage::ComponentStorage;
+use broker::Broker;
+pub use broker::{BROKER_INTERFACE, MAX_BROKERED_CALL_DEPTH};
+use call_middleware::CallMiddlewares;
+pub use call_middleware::{CallMiddleware, MiddlewareFuture};
+use changes::ChangeLog;
+pub use changes::{ChangeEvent, CHANGES_RETAINED};
+pub use compile_info::CompileInfo;
+pub use component2json::ToolMetadata;
+pub use component_filter::ComponentFilter;
+use component_storage::{
+    component_id_from_path, content_hash, file_digest, ComponentStorage, StampValidation,
+};
+pub use component_storage::{GcOptions, GcReport, DEFAULT_GC_TEMP_MAX_AGE};
 pub use config::{LifecycleBuilder, LifecycleConfig, RegistryCredential};
-pub use http::WassetteWasiState;
-use loader::{ComponentResource, DownloadedResource};
+use denials::DenialTracker;
+pub use denials::{PermissionDenial, PermissionDenials, RECENT_DENIALS_LIMIT};
+pub use download_cache::{
+    DownloadsGcReport, DownloadsRetention, DEFAULT_DOWNLOADS_MAX_AGE, DEFAULT_DOWNLOADS_MAX_BYTES,
+    DEFAULT_DOWNLOADS_PRUNE_INTERVAL,
+};
+pub use embedded::{EmbeddedComponent, StaticComponentSource};
+use events::EventBus;
+pub use events::LifecycleEvent;
+use health::{find_health_probe, probe_result_error};
+pub use health::{HealthCheckConfig, HealthReport, HEALTH_CHECK_FUNCTION, HEALTH_CHECK_INTERFACE};
+use hooks::{find_lifecycle_hook, hook_result_error, is_lifecycle_hook, LIFECYCLE_HOOK_TIMEOUT};
+pub use hooks::{LifecycleHook, LifecycleHookReport, LIFECYCLE_HOOKS_INTERFACE};
+use http::OutboundHttpLimiter;
+pub use http::{NetworkAccessCheck, OutboundHttpConfig, WassetteWasiState};
+use in_flight::InFlight;
+use instance_pool::{can_pool, InstancePool, PoolKey};
+pub use limits::LIMITS_INTERFACE;
+use load_state::{schema_hash, LoadStateEntry};
+pub use load_state::{
+    BackgroundLoadStatus, ComponentLoadState, LoadStatus, StaleSchema, DEFAULT_DRIFT_CHECK_INTERVAL,
+};
+pub use loader::DownloadCancelled;
+use loader::DownloadedResource;
+use manifest::ManifestStore;
+pub use manifest::{
+    ComponentManifest, ManifestEntry, RestoreFailure, RestoreReport, MANIFEST_FILE_NAME,
+};
+use metrics::MetricsRegistry;
+pub use metrics::{ComponentStats, ToolStats};
+pub use parameter_limits::{
+    ParameterLimitExceeded, ParameterLimits, DEFAULT_MAX_PARAMETER_ARRAY_LENGTH,
+    DEFAULT_MAX_PARAMETER_BYTES, DEFAULT_MAX_PARAMETER_DEPTH,
+};
+pub use path_guard::validate_component_id;
+use permission_hook::PermissionHook;
+pub use permission_hook::{PermissionDecision, PermissionHookFuture};
+pub use policy_fragments::{PolicyCategory, POLICY_FRAGMENTS_DIR};
+pub use policy_history::{PolicyRevision, DEFAULT_POLICY_HISTORY_LIMIT, POLICY_HISTORY_DIR};
 use policy_internal::PolicyManager;
-pub use policy_internal::{PermissionGrantRequest, PermissionRule, PolicyInfo};
+pub use policy_internal::{
+    HostGrant, HostGrantStatus, PermissionGrantRequest, PermissionRule, PolicyInfo, PolicyOrigin,
+};
+use priority::LoadPriorities;
+use progress::CountingProgress;
+pub use progress::{LoadProgress, ProgressReporter, TerminalProgress};
 use runtime_context::RuntimeContext;
+pub use runtime_context::{
+    ComponentImport, InstanceAllocation, OptimizationLevel, RuntimeOptions,
+    DEFAULT_POOL_MAX_INSTANCES,
+};
+use scratch::ScratchDirs;
+pub use scratch::{ScratchDir, SCRATCH_DIR};
 pub use secrets::SecretsManager;
+pub use signature::{
+    KeylessTrustRoot, SignatureMode, SignaturePolicy, SignatureRejected, TrustedIdentity,
+    TrustedKey, SIGNATURE_SUFFIX,
+};
+use source_policy::SourcePolicy;
+pub use source_policy::{AllowedSource, SourceNotAllowed};
+pub use state_archive::{
+    StateExportOptions, StateExportReport, StateImportIssue, StateImportOptions, StateImportReport,
+};
+use state_archive::{StateHeader, COMPONENTS_DIR, SECRETS_DIR};
+pub use stdio::{
+    CapturedOutput, StderrLines, StdioMode, MAX_CAPTURED_BYTES, MAX_STDERR_LINE_BYTES,
+};
+use tool_collision::prefixed_tool_name;
+pub use tool_collision::{ToolCollision, ToolCollisionStrategy, ToolCollisions};
+use usage::UsageTracker;
+pub use usage::{ComponentUsage, LATENCY_SAMPLES, USAGE_FILE_NAME};
+use visibility::HiddenComponents;
+pub use visibility::VISIBILITY_FILE_NAME;
+use warmup::WarmupScheduler;
+pub use warmup::{WarmupConfig, WarmupReport};
 use wasistate::WasiState;
 pub use wasistate::{
     create_wasi_state_template_from_policy, CustomResourceLimiter, PermissionError,
-    WasiStateTemplate,
+    WasiStateTemplate, DEFAULT_MAX_MEMORY_GRANT_FRACTION, FUEL_PER_CPU_CORE,
 };
 
 const DOWNLOADS_DIR: &str = "downloads";
 const PRECOMPILED_EXT: &str = "cwasm";
 const METADATA_EXT: &str = "metadata.json";
 
+/// How often a component call that failed with
+/// [`ComponentError::Unavailable`] is retried, and the delay before the
+/// first retry, which doubles for each one after it.
+const TRANSIENT_CALL_RETRIES: u32 = 2;
+const TRANSIENT_CALL_BACKOFF: Duration = Duration::from_millis(50);
+
 // Default timeout configurations
 pub(crate) const DEFAULT_OCI_TIMEOUT_SECS: u64 = 30;
 pub(crate) const DEFAULT_HTTP_TIMEOUT_SECS: u64 = 30;
+pub(crate) const DEFAULT_INSTANTIATION_TIMEOUT_SECS: u64 = 10;
 pub(crate) const DEFAULT_DOWNLOAD_CONCURRENCY: usize = 8;
+/// Get the default component directory path based on the OS
+pub(crate) fn get_default_component_dir() -> PathBuf {
+    let dir_strategy = etcetera::choose_base_strategy();
+    match dir_strategy {
+        Ok(strategy) => strategy.data_dir().join("wassette").join("components"),
+        Err(_) => {
+            eprintln!("WARN: Unable to determine default component directory, using `components` directory in the current working directory");
+            PathBuf::from("./components")
+        }
+    }
+}
+
 /// Get the default secrets directory path based on the OS
 pub(crate) fn get_default_secrets_dir() -> PathBuf {
     let dir_strategy = etcetera::choose_base_strategy();
@@ -77,7 +219,8 @@ pub(crate) fn get_default_secrets_dir() -> PathBuf {
 struct ToolInfo {
     component_id: String,
     identifier: FunctionIdentifier,
-    schema: Value,
+    /// Shared so listing tools does not deep-copy the (large) schemas
+    schema: Arc<Value>,
 }
 
 /// Component metadata for fast startup without compilation
@@ -85,7 +228,8 @@ struct ToolInfo {
 pub struct ComponentMetadata {
     /// Component identifier
     pub component_id: String,
-    /// Tool schemas for this component
+    /// Tool schemas for this component, with the examples from its docs
+    /// under `_meta.examples`
     pub tool_schemas: Vec<Value>,
     /// Function identifiers
     pub function_identifiers: Vec<FunctionIdentifier>,
@@ -95,6 +239,19 @@ pub struct ComponentMetadata {
     pub validation_stamp: ValidationStamp,
     /// Metadata creation timestamp
     pub created_at: u64,
+    /// Package documentation rendered as Markdown, if the component has any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docs: Option<String>,
+    /// Background loading priority; higher priorities are compiled first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+    /// Toolchain that compiled the component's precompiled artifact
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compiled_with: Option<CompileInfo>,
+    /// URI the component was last loaded from; an `https://` URL loaded
+    /// again gets the same component ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_uri: Option<String>,
 }
 
 /// Validation stamp to check if component has changed
@@ -118,6 +275,7 @@ struct ComponentRegistryState {
     components: HashMap<String, ComponentInstance>,
     tool_map: HashMap<String, Vec<ToolInfo>>,
     component_map: HashMap<String, Vec<String>>,
+    load_states: HashMap<String, LoadStateEntry>,
 }
 
 impl std::fmt::Debug for ComponentRegistryState {
@@ -126,6 +284,7 @@ impl std::fmt::Debug for ComponentRegistryState {
             .field("components_len", &self.components.len())
             .field("tool_map", &self.tool_map)
             .field("component_map", &self.component_map)
+            .field("load_states", &self.load_states)
             .finish()
     }
 }
@@ -139,6 +298,148 @@ pub enum LoadResult {
     New,
 }
 
+/// Errors raised while preparing a component to handle a call.
+///
+/// Returned inside [`anyhow::Error`]; use `downcast_ref` to match on it.
+#[derive(Debug, thiserror::Error)]
+pub enum ComponentError {
+    /// The component ID cannot name files in the component directory, e.g.
+    /// because it contains `..` or a path separator
+    #[error("Invalid component ID '{component_id}': component IDs must not contain path separators or be '.' or '..'")]
+    InvalidId {
+        /// The rejected component identifier
+        component_id: String,
+    },
+    /// The component did not finish instantiating in time, e.g. because its
+    /// constructor blocks on network I/O
+    #[error(
+        "Component '{component_id}' did not finish instantiating within {}ms",
+        timeout.as_millis()
+    )]
+    InstantiationTimeout {
+        /// Component identifier
+        component_id: String,
+        /// The timeout that was exceeded
+        timeout: Duration,
+    },
+    /// The called function ran longer than the component is allowed to and
+    /// was stopped, e.g. because it loops forever
+    #[error(
+        "Component '{component_id}' exceeded its allotted execution time of {}ms",
+        timeout.as_millis()
+    )]
+    ExecutionTimeout {
+        /// Component identifier
+        component_id: String,
+        /// The timeout that was exceeded
+        timeout: Duration,
+    },
+    /// The called function used up the fuel its policy's CPU limit allows
+    #[error(
+        "Component '{component_id}' ran out of fuel after {fuel} units, the limit set by its CPU \
+         permission. Grant a higher limit with the grant-cpu-permission tool or \
+         `wassette permission grant cpu {component_id} <limit>`"
+    )]
+    FuelExhausted {
+        /// Component identifier
+        component_id: String,
+        /// The fuel the call was given
+        fuel: u64,
+    },
+    /// The component's policy sets a CPU limit, which cannot be enforced
+    /// because the engine does not meter fuel; see
+    /// [`RuntimeOptions::cpu_limits`]
+    #[error(
+        "Component '{component_id}' has a CPU limit, but fuel metering is off. Set \
+         `cpu_limits = true` in the [runtime] table of the configuration to enforce it"
+    )]
+    CpuLimitsDisabled {
+        /// Component identifier
+        component_id: String,
+    },
+    /// The component failed to compile or instantiate and its file has not
+    /// changed since
+    #[error("Component '{component_id}' failed to load: {reason}")]
+    LoadFailed {
+        /// Component identifier
+        component_id: String,
+        /// Why the last load attempt failed
+        reason: String,
+    },
+    /// The component is excluded by the filter configured with
+    /// [`LifecycleBuilder::with_component_filter`]
+    #[error("Component '{component_id}' is filtered by configuration")]
+    Filtered {
+        /// Component identifier
+        component_id: String,
+    },
+    /// The component's wasm file was replaced on disk after the component
+    /// was registered, so the registered tools may not match it
+    #[error(
+        "Component '{component_id}' changed on disk after it was loaded ({reason}); load it again to use the new file"
+    )]
+    Drifted {
+        /// Component identifier
+        component_id: String,
+        /// How the file on disk differs from the registered one
+        reason: String,
+    },
+    /// The component was briefly unavailable while it was being loaded or
+    /// replaced. The call is retried a few times before this is returned.
+    #[error("Component '{component_id}' is temporarily unavailable: {reason}")]
+    Unavailable {
+        /// Component identifier
+        component_id: String,
+        /// What the component was in the middle of
+        reason: String,
+    },
+    /// The component exports tools that other loaded components already
+    /// export and tool collisions are configured to be rejected with
+    /// [`LifecycleBuilder::with_tool_collision_strategy`]
+    #[error(
+        "Component '{component_id}' was not loaded because its tools collide with loaded components: {}",
+        tool_collision::describe_collisions(collisions)
+    )]
+    ToolCollision {
+        /// Component identifier
+        component_id: String,
+        /// The colliding tools
+        collisions: Vec<ToolCollision>,
+    },
+    /// A component registered with
+    /// [`LifecycleManager::register_virtual_component`] could not be fetched
+    /// or loaded on its first call, and its tools were unregistered
+    #[error("Component '{component_id}' could not be loaded from '{source_uri}': {reason}")]
+    VirtualLoadFailed {
+        /// Component identifier
+        component_id: String,
+        /// URI the component was to be loaded from
+        source_uri: String,
+        /// Why loading failed
+        reason: String,
+    },
+    /// The component's policy grants an absolute host path and
+    /// [`LifecycleBuilder::with_absolute_storage_paths`] does not allow it.
+    /// The component is not loaded rather than loaded without the mount
+    #[error(
+        "Component '{component_id}' has a policy granting the absolute host path '{uri}', which requires allow_absolute_storage_paths to be set in the server configuration"
+    )]
+    AbsoluteStoragePath {
+        /// Component identifier
+        component_id: String,
+        /// The storage URI naming the absolute path
+        uri: String,
+    },
+    /// The component is embedded with
+    /// [`LifecycleBuilder::with_embedded_components`] and cannot be
+    /// replaced, unloaded, or have its policy changed
+    #[error("Component '{component_id}' is part of a read-only deployment and cannot be changed")]
+    ReadOnly {
+        /// Component identifier
+        component_id: String,
+    },
+}
+
 /// Detailed outcome for a component load operation.
 #[derive(Debug, Clone)]
 pub struct ComponentLoadOutcome {
@@ -148,6 +449,135 @@ pub struct ComponentLoadOutcome {
     pub status: LoadResult,
     /// Normalized tool names exposed by the component after registration.
     pub tool_names: Vec<String>,
+    /// Result of the component's health probe, if it exports one.
+    pub health: Option<HealthReport>,
+    /// Result of the component's `on-load` hook, if it exports one. A
+    /// failed hook is also listed in `warnings`.
+    pub on_load: Option<LifecycleHookReport>,
+    /// Tools that were not registered for the component before this load.
+    pub added_tools: Vec<String>,
+    /// Tools the previous version exposed that the new version no longer does.
+    pub removed_tools: Vec<String>,
+    /// Tools present in both versions whose schema changed.
+    pub changed_tools: Vec<String>,
+    /// Tools that share a name with another component's tools, and how
+    /// they were registered.
+    pub tool_collisions: Option<ToolCollisions>,
+    /// Problems with the component that did not stop it from loading, such
+    /// as exposing no tools.
+    pub warnings: Vec<String>,
+    /// The exports that are not tools, with the reason each was skipped.
+    /// Only listed when the component exposes no tools.
+    pub skipped_exports: Vec<SkippedExport>,
+    /// How the component ID was chosen for a component loaded from an
+    /// `https://` URL, which does not name the ID the way a file or OCI
+    /// reference does. `None` for other sources.
+    pub id_assignment: Option<IdAssignment>,
+}
+
+/// Options for [`LifecycleManager::unload_component_with_options`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UnloadOptions {
+    /// Remove the component without running its `on-unload` hook, e.g. when
+    /// the hook itself misbehaves
+    pub skip_hooks: bool,
+}
+
+/// Detailed outcome for a component unload operation.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ComponentUnloadOutcome {
+    /// Identifier of the component that was unloaded.
+    pub component_id: String,
+    /// Result of the component's `on-unload` hook, if it exports one and
+    /// hooks were not skipped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_unload: Option<LifecycleHookReport>,
+    /// Problems that did not stop the unload, such as a failed hook.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// How the ID of a component loaded from an `https://` URL was chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdAssignment {
+    /// The last path segment of the URL, with characters that are not safe
+    /// in file names replaced
+    PathSegment,
+    /// The last path segment plus a short hash of the component's content,
+    /// because a different component was installed under the path segment
+    HashSuffixed,
+    /// The ID the same URL was loaded under before
+    Reused,
+}
+
+/// Where the time of a component call went, as measured by
+/// [`LifecycleManager::execute_component_call_timed`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CallTiming {
+    /// Time spent instantiating the component, in milliseconds
+    pub instantiation_ms: u64,
+    /// Time the called function ran, in milliseconds
+    pub execution_ms: u64,
+    /// Time the whole call took, including compiling the component if it
+    /// was cold-loaded, in milliseconds
+    pub total_ms: u64,
+    /// Whether the component was only registered from metadata and had to
+    /// be compiled for this call
+    pub cold_load: bool,
+    /// How often the call was retried because the component was briefly
+    /// unavailable
+    pub retries: u32,
+    /// Whether the call ran on an idle instance from the
+    /// [instance pool](LifecycleBuilder::with_instance_pool) instead of
+    /// instantiating the component
+    pub pooled: bool,
+}
+
+/// Result of a call made with
+/// [`LifecycleManager::execute_component_call_with_output`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ComponentCallOutput {
+    /// The function's results, wrapped in a `result` object
+    pub result: Value,
+    /// Where the time of the call went
+    pub timing: CallTiming,
+    /// What the component wrote to the streams that are captured for it
+    pub output: CapturedOutput,
+}
+
+/// Number of components and tools the lifecycle manager knows of, as
+/// returned by [`LifecycleManager::component_counts`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ComponentCounts {
+    /// Components that are compiled and callable
+    pub loaded: usize,
+    /// Components known in any load state, including loaded ones
+    pub known: usize,
+    /// Tools registered across all components
+    pub tools: usize,
+}
+
+/// Difference between the tools registered for a component before and after
+/// an upsert. Each list is sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ToolChanges {
+    added: Vec<String>,
+    removed: Vec<String>,
+    changed: Vec<String>,
+    /// Names the tools were registered under, in the order they were given
+    tool_names: Vec<String>,
+    collisions: Option<ToolCollisions>,
+    /// Set when the tools replace schemas served from cached metadata that
+    /// do not match the compiled component
+    stale_schema: Option<StaleSchema>,
+}
+
+/// Files of an installed component, see
+/// [`LifecycleManager::read_installed_files`].
+struct InstalledFiles {
+    wasm: Vec<u8>,
+    metadata: Option<Vec<u8>>,
 }
 
 impl ComponentRegistry {
@@ -160,9 +590,11 @@ impl ComponentRegistry {
         component_id: String,
         instance: ComponentInstance,
         tools: Vec<ToolMetadata>,
-    ) -> Result<LoadResult> {
+        stamp: Option<ValidationStamp>,
+        collision_strategy: ToolCollisionStrategy,
+    ) -> Result<(LoadResult, ToolChanges)> {
         let mut state = self.state.write().await;
-        state.upsert_component(component_id, instance, tools)
+        state.upsert_component(component_id, instance, tools, stamp, collision_strategy)
     }
 
     async fn remove_component(&self, component_id: &str) -> Option<ComponentInstance> {
@@ -170,6 +602,29 @@ impl ComponentRegistry {
         state.unregister_component(component_id)
     }
 
+    /// Drop the compiled instance of a loaded component but keep its tools
+    /// registered, as [`Self::register_metadata_if_absent`] would register
+    /// them, so they stay listed and the component is compiled on first use.
+    /// Returns whether the component was loaded.
+    async fn deactivate_component(&self, component_id: &str) -> bool {
+        let mut state = self.state.write().await;
+        if state.components.remove(component_id).is_none() {
+            return false;
+        }
+        // Tools are left in place rather than registered again, so they do
+        // not disappear from listings in between
+        if let Some(entry) = state.load_states.get_mut(component_id) {
+            entry.state = ComponentLoadState::MetadataRegistered;
+            entry.failed_stamp = None;
+        } else {
+            state.load_states.insert(
+                component_id.to_string(),
+                ComponentLoadState::MetadataRegistered.into(),
+            );
+        }
+        true
+    }
+
     async fn get_component(&self, component_id: &str) -> Option<ComponentInstance> {
         let state = self.state.read().await;
         state.components.get(component_id).cloned()
@@ -203,19 +658,93 @@ impl ComponentRegistry {
         state.tool_map.get(tool_name).cloned()
     }
 
-    async fn list_tools(&self) -> Vec<Value> {
+    /// Tool schemas ordered by component ID, then tool name.
+    async fn list_tools(&self) -> Vec<Arc<Value>> {
+        self.component_tools()
+            .await
+            .into_iter()
+            .map(|(_, schema)| schema)
+            .collect()
+    }
+
+    /// Tool schemas with the ID of the component exporting them, ordered by
+    /// component ID, then tool name.
+    async fn component_tools(&self) -> Vec<(String, Arc<Value>)> {
         let state = self.state.read().await;
-        state
+        let mut tools: Vec<(&str, &str, &Arc<Value>)> = state
             .tool_map
-            .values()
-            .flat_map(|tools| tools.iter().map(|t| t.schema.clone()))
+            .iter()
+            .flat_map(|(name, tools)| {
+                tools
+                    .iter()
+                    .map(move |t| (t.component_id.as_str(), name.as_str(), &t.schema))
+            })
+            .collect();
+        tools.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+        tools
+            .into_iter()
+            .map(|(component_id, _, schema)| (component_id.to_string(), Arc::clone(schema)))
+            .collect()
+    }
+
+    async fn counts(&self) -> ComponentCounts {
+        let state = self.state.read().await;
+        let known: HashSet<&String> = state
+            .components
+            .keys()
+            .chain(state.load_states.keys())
+            .collect();
+        ComponentCounts {
+            loaded: state.components.len(),
+            known: known.len(),
+            tools: state.tool_map.values().map(Vec::len).sum(),
+        }
+    }
+
+    /// Why each component that failed to load did so.
+    async fn load_failures(&self) -> BTreeMap<String, String> {
+        let state = self.state.read().await;
+        state
+            .load_states
+            .iter()
+            .filter_map(|(id, entry)| match &entry.state {
+                ComponentLoadState::Failed { reason } => Some((id.clone(), reason.clone())),
+                _ => None,
+            })
             .collect()
     }
 
+    async fn list_tool_names(&self) -> Vec<String> {
+        let state = self.state.read().await;
+        let mut names: Vec<String> = state.tool_map.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Fail with [`ComponentError::ToolCollision`] if registering `tools`
+    /// for `component_id` would be rejected under `collision_strategy`,
+    /// without registering anything.
+    async fn check_tool_collisions(
+        &self,
+        component_id: &str,
+        tools: &[ToolMetadata],
+        collision_strategy: ToolCollisionStrategy,
+    ) -> Result<()> {
+        if collision_strategy != ToolCollisionStrategy::Reject {
+            return Ok(());
+        }
+        let state = self.state.read().await;
+        state
+            .resolve_tool_collisions(component_id, &mut tools.to_vec(), collision_strategy)
+            .map(|_| ())
+    }
+
     async fn register_metadata_if_absent(
         &self,
         component_id: &str,
-        tools: Vec<ToolMetadata>,
+        mut tools: Vec<ToolMetadata>,
+        stamp: ValidationStamp,
+        collision_strategy: ToolCollisionStrategy,
     ) -> Result<bool> {
         let mut state = self.state.write().await;
 
@@ -225,9 +754,220 @@ impl ComponentRegistry {
             return Ok(false);
         }
 
+        let schema_hash = schema_hash(&tools);
+        state.resolve_tool_collisions(component_id, &mut tools, collision_strategy)?;
         state.register_tools_only(component_id, tools);
+        state.load_states.insert(
+            component_id.to_string(),
+            LoadStateEntry::registered(ComponentLoadState::MetadataRegistered, Some(stamp))
+                .with_schema_hash(Some(schema_hash)),
+        );
         Ok(true)
     }
+
+    /// Register the tools of a component that is fetched from `source_uri`
+    /// on first use. Fails if the component is already known.
+    async fn register_virtual(
+        &self,
+        component_id: &str,
+        source_uri: &str,
+        mut tools: Vec<ToolMetadata>,
+        collision_strategy: ToolCollisionStrategy,
+    ) -> Result<Vec<String>> {
+        let mut state = self.state.write().await;
+
+        if state.components.contains_key(component_id)
+            || state.component_map.contains_key(component_id)
+            || state.load_states.contains_key(component_id)
+        {
+            bail!("Component '{component_id}' is already registered");
+        }
+
+        state.resolve_tool_collisions(component_id, &mut tools, collision_strategy)?;
+        let tool_names = tools
+            .iter()
+            .map(|tool| tool.normalized_name.clone())
+            .collect();
+        state.register_tools_only(component_id, tools);
+        state.load_states.insert(
+            component_id.to_string(),
+            ComponentLoadState::Virtual {
+                source_uri: source_uri.to_string(),
+            }
+            .into(),
+        );
+        Ok(tool_names)
+    }
+
+    /// Forget a component that is registered but was never loaded, such as
+    /// a virtual component that could not be fetched. Returns whether it
+    /// was removed.
+    async fn discard_unloaded(&self, component_id: &str) -> bool {
+        let mut state = self.state.write().await;
+        if state.components.contains_key(component_id)
+            || !state.load_states.contains_key(component_id)
+        {
+            return false;
+        }
+        state.unregister_component(component_id);
+        true
+    }
+
+    /// Schemas of the tools registered for a component.
+    async fn component_tool_schemas(&self, component_id: &str) -> Vec<Arc<Value>> {
+        let state = self.state.read().await;
+        state
+            .component_map
+            .get(component_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|tool_name| state.tool_map.get(tool_name))
+            .flatten()
+            .filter(|info| info.component_id == component_id)
+            .map(|info| Arc::clone(&info.schema))
+            .collect()
+    }
+
+    async fn stale_schema(&self, component_id: &str) -> Option<StaleSchema> {
+        let state = self.state.read().await;
+        state
+            .load_states
+            .get(component_id)
+            .and_then(|entry| entry.stale_schema.clone())
+    }
+
+    async fn load_state(&self, component_id: &str) -> Option<ComponentLoadState> {
+        let state = self.state.read().await;
+        state
+            .load_states
+            .get(component_id)
+            .map(|entry| entry.state.clone())
+    }
+
+    /// Record a component found in the component directory, unless its
+    /// state is already known.
+    async fn discover(&self, component_id: &str) {
+        let mut state = self.state.write().await;
+        state
+            .load_states
+            .entry(component_id.to_string())
+            .or_insert_with(|| ComponentLoadState::Discovered.into());
+    }
+
+    /// Mark a component as loading. A loaded component keeps serving calls
+    /// while it is replaced, so it stays [`ComponentLoadState::Loaded`].
+    async fn begin_loading(&self, component_id: &str) {
+        let mut state = self.state.write().await;
+        if !state.components.contains_key(component_id) {
+            // Keep the hash of tools already served, to check them against
+            // the compiled ones, and any stale schemas found before
+            let previous = state.load_states.remove(component_id);
+            let mut entry = LoadStateEntry::from(ComponentLoadState::Loading);
+            if let Some(previous) = previous {
+                entry.schema_hash = previous.schema_hash;
+                entry.stale_schema = previous.stale_schema;
+            }
+            state.load_states.insert(component_id.to_string(), entry);
+        }
+    }
+
+    /// Record why loading a component failed. Does nothing unless the
+    /// component is still loading, so a failure is recorded only once and
+    /// never hides a previously loaded version.
+    async fn fail_loading(
+        &self,
+        component_id: &str,
+        failure: ComponentLoadState,
+        stamp: Option<ValidationStamp>,
+    ) {
+        let mut state = self.state.write().await;
+        if let Some(entry) = state.load_states.get_mut(component_id) {
+            if entry.state == ComponentLoadState::Loading {
+                entry.state = failure;
+                entry.failed_stamp = stamp;
+            }
+        }
+    }
+
+    /// The reason the last load of a component failed, along with the stamp
+    /// of the wasm file that failed.
+    async fn recorded_failure(&self, component_id: &str) -> Option<(String, ValidationStamp)> {
+        let state = self.state.read().await;
+        let entry = state.load_states.get(component_id)?;
+        match (&entry.state, &entry.failed_stamp) {
+            (ComponentLoadState::Failed { reason }, Some(stamp)) => {
+                Some((reason.clone(), stamp.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Components whose tools are registered and not yet known to have
+    /// drifted, with the stamp of the wasm file their tools came from.
+    async fn registered_stamps(&self) -> Vec<(String, ValidationStamp)> {
+        let state = self.state.read().await;
+        let mut stamps: Vec<(String, ValidationStamp)> = state
+            .load_states
+            .iter()
+            .filter(|(_, entry)| {
+                matches!(
+                    entry.state,
+                    ComponentLoadState::Loaded | ComponentLoadState::MetadataRegistered
+                )
+            })
+            .filter_map(|(id, entry)| Some((id.clone(), entry.registered_stamp.clone()?)))
+            .collect();
+        stamps.sort_by(|a, b| a.0.cmp(&b.0));
+        stamps
+    }
+
+    /// Mark a component as drifted unless it was reloaded or unloaded since
+    /// `stamp` was read. Returns whether the state changed.
+    async fn mark_drifted(
+        &self,
+        component_id: &str,
+        stamp: &ValidationStamp,
+        reason: String,
+    ) -> bool {
+        let mut state = self.state.write().await;
+        let Some(entry) = state.load_states.get_mut(component_id) else {
+            return false;
+        };
+        let unchanged = entry.registered_stamp.as_ref().is_some_and(|registered| {
+            registered.file_size == stamp.file_size && registered.mtime == stamp.mtime
+        });
+        if !unchanged || matches!(entry.state, ComponentLoadState::Drifted { .. }) {
+            return false;
+        }
+        entry.state = ComponentLoadState::Drifted { reason };
+        true
+    }
+
+    /// Components registered with
+    /// [`LifecycleManager::register_virtual_component`] that were not
+    /// fetched yet.
+    async fn virtual_components(&self) -> Vec<String> {
+        let state = self.state.read().await;
+        state
+            .load_states
+            .iter()
+            .filter(|(_, entry)| matches!(entry.state, ComponentLoadState::Virtual { .. }))
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    /// Components currently marked as drifted.
+    async fn drifted_components(&self) -> Vec<String> {
+        let state = self.state.read().await;
+        let mut ids: Vec<String> = state
+            .load_states
+            .iter()
+            .filter(|(_, entry)| matches!(entry.state, ComponentLoadState::Drifted { .. }))
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.sort();
+        ids
+    }
 }
 
 impl ComponentRegistryState {
@@ -235,22 +975,168 @@ impl ComponentRegistryState {
         &mut self,
         component_id: String,
         instance: ComponentInstance,
-        tools: Vec<ToolMetadata>,
-    ) -> Result<LoadResult> {
+        mut tools: Vec<ToolMetadata>,
+        stamp: Option<ValidationStamp>,
+        collision_strategy: ToolCollisionStrategy,
+    ) -> Result<(LoadResult, ToolChanges)> {
         let replaced = self.components.contains_key(&component_id);
+        let collisions =
+            self.resolve_tool_collisions(&component_id, &mut tools, collision_strategy)?;
+        let mut changes = self.tool_changes(&component_id, &tools);
+        changes.tool_names = tools
+            .iter()
+            .map(|tool| tool.normalized_name.clone())
+            .collect();
+        changes.collisions = collisions;
+
+        // Tools served before the component was compiled are checked against
+        // the ones it was compiled with
+        let previous = self.load_states.get(&component_id);
+        let served_hash = previous
+            .filter(|_| !replaced)
+            .and_then(|entry| entry.schema_hash.clone());
+        changes.stale_schema = match (served_hash, &instance.schema_hash) {
+            (Some(served_hash), Some(compiled_hash)) if served_hash != *compiled_hash => {
+                Some(StaleSchema {
+                    served_hash,
+                    compiled_hash: compiled_hash.clone(),
+                    added_tools: changes.added.clone(),
+                    removed_tools: changes.removed.clone(),
+                    changed_tools: changes.changed.clone(),
+                })
+            }
+            _ => None,
+        };
+        let mut entry = LoadStateEntry::registered(ComponentLoadState::Loaded, stamp)
+            .with_schema_hash(instance.schema_hash.clone());
+        entry.stale_schema = changes
+            .stale_schema
+            .clone()
+            .or_else(|| previous.and_then(|entry| entry.stale_schema.clone()));
+
         self.unregister_tools(&component_id);
         self.register_tools_only(&component_id, tools);
+        self.load_states.insert(component_id.clone(), entry);
         self.components.insert(component_id, instance);
 
-        Ok(if replaced {
+        let status = if replaced {
             LoadResult::Replaced
         } else {
             LoadResult::New
-        })
+        };
+        Ok((status, changes))
+    }
+
+    /// Find the tools in `tools` that share a name with another component's
+    /// registered tools and apply `strategy` to them. With
+    /// [`ToolCollisionStrategy::Prefix`] the colliding tools are renamed in
+    /// place; with [`ToolCollisionStrategy::Reject`] any collision fails
+    /// with [`ComponentError::ToolCollision`].
+    fn resolve_tool_collisions(
+        &self,
+        component_id: &str,
+        tools: &mut [ToolMetadata],
+        strategy: ToolCollisionStrategy,
+    ) -> Result<Option<ToolCollisions>> {
+        let mut collisions = Vec::new();
+        for tool in tools.iter_mut() {
+            let mut existing_components: Vec<String> = self
+                .tool_map
+                .get(&tool.normalized_name)
+                .into_iter()
+                .flatten()
+                .filter(|info| info.component_id != component_id)
+                .map(|info| info.component_id.clone())
+                .collect();
+            if existing_components.is_empty() {
+                continue;
+            }
+            existing_components.sort();
+            existing_components.dedup();
+
+            let registered_as = match strategy {
+                ToolCollisionStrategy::Allow => Some(tool.normalized_name.clone()),
+                ToolCollisionStrategy::Reject => None,
+                ToolCollisionStrategy::Prefix => {
+                    let name = prefixed_tool_name(component_id, &tool.normalized_name);
+                    if let Some(schema) = tool.schema.as_object_mut() {
+                        schema.insert("name".to_string(), Value::String(name.clone()));
+                    }
+                    Some(name)
+                }
+            };
+            let original = match &registered_as {
+                Some(name) => std::mem::replace(&mut tool.normalized_name, name.clone()),
+                None => tool.normalized_name.clone(),
+            };
+            collisions.push(ToolCollision {
+                tool: original,
+                existing_components,
+                registered_as,
+            });
+        }
+
+        if collisions.is_empty() {
+            return Ok(None);
+        }
+        collisions.sort_by(|a, b| a.tool.cmp(&b.tool));
+        if strategy == ToolCollisionStrategy::Reject {
+            return Err(ComponentError::ToolCollision {
+                component_id: component_id.to_string(),
+                collisions,
+            }
+            .into());
+        }
+        warn!(
+            %component_id,
+            %strategy,
+            tools = ?collisions.iter().map(|c| c.tool.as_str()).collect::<Vec<_>>(),
+            "Component tools collide with tools of other components"
+        );
+        Ok(Some(ToolCollisions {
+            strategy,
+            tools: collisions,
+        }))
+    }
+
+    /// Compare the tools currently registered for a component with `tools`.
+    fn tool_changes(&self, component_id: &str, tools: &[ToolMetadata]) -> ToolChanges {
+        let mut previous: HashMap<&str, &Value> = self
+            .component_map
+            .get(component_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|name| {
+                let info = self
+                    .tool_map
+                    .get(name)?
+                    .iter()
+                    .find(|info| info.component_id == component_id)?;
+                Some((name.as_str(), info.schema.as_ref()))
+            })
+            .collect();
+
+        let mut changes = ToolChanges::default();
+        for tool in tools {
+            match previous.remove(tool.normalized_name.as_str()) {
+                None => changes.added.push(tool.normalized_name.clone()),
+                Some(schema) if *schema != tool.schema => {
+                    changes.changed.push(tool.normalized_name.clone())
+                }
+                Some(_) => {}
+            }
+        }
+        changes.removed = previous.into_keys().map(str::to_string).collect();
+
+        changes.added.sort();
+        changes.removed.sort();
+        changes.changed.sort();
+        changes
     }
 
     fn unregister_component(&mut self, component_id: &str) -> Option<ComponentInstance> {
         self.unregister_tools(component_id);
+        self.load_states.remove(component_id);
         self.components.remove(component_id)
     }
 
@@ -280,7 +1166,7 @@ impl ComponentRegistryState {
             let tool_info = ToolInfo {
                 component_id: component_id.to_string(),
                 identifier,
-                schema,
+                schema: Arc::new(schema),
             };
 
             self.tool_map
@@ -306,6 +1192,53 @@ pub struct LifecycleManager {
     http_client: reqwest::Client,
     secrets_manager: Arc<SecretsManager>,
     registry_credentials: HashMap<String, oci_client::secrets::RegistryAuth>,
+    manifest: ManifestStore,
+    events: EventBus,
+    health_checks: HealthCheckConfig,
+    warmup: Arc<HashMap<String, WarmupConfig>>,
+    warmup_scheduler: WarmupScheduler,
+    warmup_reports: Arc<RwLock<HashMap<String, WarmupReport>>>,
+    instantiation_timeout: Duration,
+    execution_timeout: Option<Duration>,
+    parameter_limits: ParameterLimits,
+    outbound_http: Arc<OutboundHttpLimiter>,
+    usage: UsageTracker,
+    denials: DenialTracker,
+    metrics: MetricsRegistry,
+    hidden: HiddenComponents,
+    priorities: LoadPriorities,
+    permission_hook: Option<PermissionHook>,
+    source_policy: SourcePolicy,
+    signature_policy: SignaturePolicy,
+    auto_reload_on_drift: bool,
+    strict_validation: bool,
+    component_filter: Arc<ComponentFilter>,
+    tool_collisions: ToolCollisionStrategy,
+    call_middleware: CallMiddlewares,
+    downloads_retention: DownloadsRetention,
+    scratch: ScratchDirs,
+    embedded: StaticComponentSource,
+    default_stdio: StdioMode,
+    /// Held for reading while a component's file is replaced through the
+    /// manager, and for writing while the drift check compares files, so a
+    /// load in progress is not taken for drift
+    staging_lock: Arc<RwLock<()>>,
+    /// Held for reading while a call picks up a component's instance and
+    /// policy template, and for writing while either is swapped
+    swap_lock: Arc<RwLock<()>>,
+    /// Held while a virtual component is fetched on first use, so
+    /// concurrent calls fetch it once
+    materialize_lock: Arc<tokio::sync::Mutex<()>>,
+    /// Loads in progress by URI, so a second load of the same URI waits for
+    /// the first and shares its outcome
+    loads_in_flight: Arc<InFlight<ComponentLoadOutcome>>,
+    /// Compilations of installed components in progress by ID, so calls
+    /// that find the same component unloaded compile it once
+    compiles_in_flight: Arc<InFlight<()>>,
+    /// Idle instances kept for later calls, if enabled with
+    /// [`LifecycleBuilder::with_instance_pool`]
+    instance_pool: Arc<InstancePool>,
+    background_load: Arc<std::sync::Mutex<BackgroundLoadStatus>>,
 }
 
 /// A representation of a loaded component instance. It contains both the base component info and a
@@ -315,6 +1248,41 @@ pub struct ComponentInstance {
     component: Arc<Component>,
     instance_pre: Arc<InstancePre<WassetteWasiState<WasiState>>>,
     package_docs: Option<Value>,
+    health: Option<HealthReport>,
+    /// Hash of the tools the instance was registered with
+    schema_hash: Option<String>,
+}
+
+/// A loaded component and the policy template it runs under, taken together
+/// when a call starts.
+struct ComponentSnapshot {
+    component: ComponentInstance,
+    policy_template: Arc<WasiStateTemplate>,
+    policy_version: u64,
+}
+
+/// What a call of a component function runs in, besides its arguments.
+struct ComponentCall<'a> {
+    component: &'a ComponentInstance,
+    /// The sandbox the call runs in, including its CPU and time limits
+    policy_template: Arc<WasiStateTemplate>,
+    /// Where lines of stderr are sent while the call runs
+    stderr_lines: Option<StderrLines>,
+    /// Which idle instances the call may use, if instances are pooled
+    pool_key: Option<PoolKey>,
+}
+
+impl<'a> ComponentCall<'a> {
+    /// A call in a fresh instance of `component` under `policy_template`,
+    /// with stderr not streamed anywhere.
+    fn new(component: &'a ComponentInstance, policy_template: Arc<WasiStateTemplate>) -> Self {
+        Self {
+            component,
+            policy_template,
+            stderr_lines: None,
+            pool_key: None,
+        }
+    }
 }
 
 impl LifecycleManager {
@@ -342,13 +1310,77 @@ impl LifecycleManager {
     /// Construct a lifecycle manager from an explicit configuration without loading components.
     #[instrument(skip_all, fields(component_dir = %config.component_dir().display()))]
     pub async fn from_config(config: LifecycleConfig) -> Result<Self> {
-        let (component_dir, secrets_dir, environment_vars, http_client, oci_client, raw_credentials, _) =
-            config.into_parts();
+        let LifecycleConfig {
+            component_dir,
+            secrets_dir,
+            environment_vars,
+            http_client,
+            oci_client,
+            registry_credentials: raw_credentials,
+            manifest_path,
+            health_checks,
+            warmup,
+            instantiation_timeout,
+            execution_timeout,
+            parameter_limits,
+            outbound_http,
+            load_priority,
+            permission_hook,
+            allow_env_inherit,
+            allow_any_host,
+            allow_absolute_storage_paths,
+            allowed_sources,
+            allowed_policy_sources,
+            signature_policy,
+            policy_history_limit,
+            max_memory_grant_fraction,
+            auto_reload_on_drift,
+            dir_watch,
+            strict_validation,
+            startup_gc,
+            startup_downloads_prune,
+            instance_pool,
+            runtime_options,
+            component_filter,
+            tool_collisions,
+            call_middleware,
+            downloads_retention,
+            scratch_dir,
+            changelog_path,
+            embedded_components: embedded,
+            default_stdio,
+            eager_load: _,
+        } = config;
 
         let storage =
             ComponentStorage::new(component_dir.clone(), DEFAULT_DOWNLOAD_CONCURRENCY).await?;
+        if startup_downloads_prune {
+            if let Err(error) = storage
+                .download_cache()
+                .prune(downloads_retention, false)
+                .await
+            {
+                warn!(%error, "Failed to prune the downloads directory");
+            }
+        }
+        if startup_gc {
+            match storage.garbage_collect(GcOptions::default()).await {
+                Ok(report) if report.files_removed > 0 => info!(
+                    files_removed = report.files_removed,
+                    bytes_freed = report.bytes_freed,
+                    "Removed orphaned files from the component directory"
+                ),
+                Ok(_) => {}
+                Err(error) => warn!(%error, "Failed to garbage collect the component directory"),
+            }
+        }
+        let usage = UsageTracker::load(component_dir.join(USAGE_FILE_NAME)).await;
+        let denials = DenialTracker::default();
+        let metrics = MetricsRegistry::new(usage.clone(), denials.clone());
+        let hidden = HiddenComponents::load(component_dir.join(VISIBILITY_FILE_NAME)).await;
+        let changes = ChangeLog::open(changelog_path).await?;
 
-        let runtime = Arc::new(RuntimeContext::initialize()?);
+        let runtime = Arc::new(RuntimeContext::initialize(&runtime_options)?);
 
         let secrets_manager = Arc::new(SecretsManager::new(secrets_dir.clone()));
         secrets_manager.ensure_secrets_dir().await?;
@@ -368,9 +1400,18 @@ impl LifecycleManager {
             Arc::clone(&environment_vars),
             Arc::clone(&oci_client),
             http_client.clone(),
-        );
+            allow_env_inherit,
+            SourcePolicy::for_policies(
+                allowed_policy_sources.unwrap_or_else(|| allowed_sources.clone()),
+            ),
+        )
+        .with_history_limit(policy_history_limit)
+        .with_scratch_root(scratch_dir.clone())
+        .with_max_memory_grant_fraction(max_memory_grant_fraction)
+        .with_any_host(allow_any_host)
+        .with_absolute_storage_paths(allow_absolute_storage_paths);
 
-        Ok(Self {
+        let manager = Self {
             runtime,
             registry: ComponentRegistry::new(),
             storage,
@@ -379,48 +1420,158 @@ impl LifecycleManager {
             http_client,
             secrets_manager,
             registry_credentials,
-        })
+            manifest: ManifestStore::new(manifest_path),
+            events: EventBus::new(changes),
+            health_checks,
+            warmup: Arc::new(warmup),
+            warmup_scheduler: WarmupScheduler::new(),
+            warmup_reports: Arc::new(RwLock::new(HashMap::new())),
+            instantiation_timeout,
+            execution_timeout,
+            parameter_limits,
+            outbound_http: Arc::new(OutboundHttpLimiter::new(outbound_http)),
+            usage,
+            denials,
+            metrics,
+            hidden,
+            priorities: LoadPriorities::new(load_priority),
+            permission_hook,
+            source_policy: SourcePolicy::new(allowed_sources),
+            signature_policy,
+            auto_reload_on_drift,
+            strict_validation,
+            component_filter: Arc::new(component_filter),
+            tool_collisions,
+            call_middleware: call_middleware.into(),
+            downloads_retention,
+            scratch: ScratchDirs::new(scratch_dir),
+            embedded,
+            default_stdio,
+            staging_lock: Arc::default(),
+            swap_lock: Arc::default(),
+            materialize_lock: Arc::default(),
+            loads_in_flight: Arc::default(),
+            compiles_in_flight: Arc::default(),
+            instance_pool: Arc::new(InstancePool::new(instance_pool)),
+            background_load: Arc::default(),
+        };
+        // Embedded components have no metadata to be listed from, so they
+        // are compiled right away
+        manager.register_embedded_components().await?;
+        if dir_watch {
+            dir_watch::spawn(manager.clone())?;
+        }
+        Ok(manager)
+    }
+
+    /// Compile the components embedded with
+    /// [`LifecycleBuilder::with_embedded_components`] from memory and
+    /// register their tools. Nothing is written to the component directory.
+    async fn register_embedded_components(&self) -> Result<()> {
+        for embedded in self.embedded.components() {
+            let component_id = embedded.id;
+            path_guard::validate_component_id(component_id)?;
+            let component =
+                Component::new(self.runtime.as_ref(), embedded.wasm).with_context(|| {
+                    format!("Failed to compile embedded component '{component_id}'")
+                })?;
+            let component_instance = self
+                .prepare_component(component, embedded.wasm)
+                .with_context(|| format!("Failed to load embedded component '{component_id}'"))?;
+            let tool_metadata = self.component_tool_metadata(&component_instance);
+
+            self.registry.begin_loading(component_id).await;
+            let (status, changes, _) = self
+                .check_health_and_register(component_id, component_instance, tool_metadata)
+                .await
+                .with_context(|| format!("Failed to load embedded component '{component_id}'"))?;
+            self.emit_load_event(component_id, &status, &changes);
+            self.schedule_warmup(component_id);
+            info!(%component_id, tools = ?changes.tool_names, "Loaded embedded component");
+        }
+        Ok(())
+    }
+
+    /// Subscribe to [`LifecycleEvent`]s emitted after components are loaded,
+    /// replaced, unloaded, or disabled, and after policies, permissions, or
+    /// secrets change.
+    ///
+    /// Each receiver buffers a bounded number of events; a receiver that falls
+    /// too far behind observes [`broadcast::error::RecvError::Lagged`].
+    pub fn subscribe(&self) -> broadcast::Receiver<LifecycleEvent> {
+        self.events.subscribe()
+    }
+
+    fn emit_load_event(&self, component_id: &str, status: &LoadResult, changes: &ToolChanges) {
+        self.instance_pool.invalidate(component_id);
+        let component_id = component_id.to_string();
+        let tools = changes.tool_names.clone();
+        self.events.emit(match status {
+            LoadResult::New => LifecycleEvent::ComponentLoaded {
+                component_id,
+                tools,
+            },
+            LoadResult::Replaced => LifecycleEvent::ComponentReplaced {
+                component_id,
+                tools,
+                added_tools: changes.added.clone(),
+                removed_tools: changes.removed.clone(),
+                changed_tools: changes.changed.clone(),
+            },
+        });
+    }
+
+    /// Count and announce a permission error recorded for a component, if it
+    /// is a denial.
+    fn emit_permission_denied(&self, component_id: &str, error: &PermissionError) {
+        if let Some((permission_type, details)) = error.denial_details() {
+            self.denials
+                .record(component_id, permission_type, details.clone());
+            self.events.emit(LifecycleEvent::PermissionDenied {
+                component_id: component_id.to_string(),
+                permission_type: permission_type.to_string(),
+                details,
+            });
+        }
     }
 
     /// Load every component present in the component directory, updating the registry and cache.
     #[instrument(skip(self))]
     pub async fn load_all_components(&self) -> Result<()> {
-        let loaded_components =
-            load_components_parallel(self.storage.root(), Arc::clone(&self.runtime)).await?;
-
-        let mut registered_ids = Vec::new();
+        let loaded_components = load_components_parallel(
+            self.storage.root(),
+            Arc::clone(&self.runtime),
+            &self.component_filter,
+        )
+        .await?;
 
         for (component_instance, name) in loaded_components {
-            let tool_metadata = if let Some(ref package_docs) = component_instance.package_docs {
-                component_exports_to_tools_with_docs(
-                    &component_instance.component,
-                    self.runtime.as_ref(),
-                    true,
-                    package_docs,
-                )
-            } else {
-                component_exports_to_tools(
-                    &component_instance.component,
-                    self.runtime.as_ref(),
-                    true,
-                )
-            };
-
-            if let Err(error) = self
-                .registry
-                .upsert_component(name.clone(), component_instance, tool_metadata)
-                .await
-            {
-                warn!(%name, %error, "Failed to register component in registry");
+            if self.embedded.contains(&name) {
+                warn!(%name, "Ignoring the file of an embedded component in the component directory");
                 continue;
             }
+            let tool_metadata = self.component_tool_metadata(&component_instance);
 
-            registered_ids.push(name);
-        }
-
-        for component_id in registered_ids {
-            if let Err(error) = self.restore_policy_attachment(&component_id).await {
-                warn!(%component_id, %error, "Failed to restore policy attachment");
+            self.registry.begin_loading(&name).await;
+            match self
+                .check_health_and_register(&name, component_instance, tool_metadata)
+                .await
+            {
+                Ok((status, changes, _)) => {
+                    self.emit_load_event(&name, &status, &changes);
+                    self.schedule_warmup(&name);
+                }
+                Err(error) => {
+                    warn!(%name, %error, "Failed to register component in registry");
+                    let failure = ComponentLoadState::Failed {
+                        reason: error.to_string(),
+                    };
+                    self.registry.fail_loading(&name, failure, None).await;
+                    self.events.emit(LifecycleEvent::ComponentDisabled {
+                        component_id: name,
+                        reason: error.to_string(),
+                    });
+                }
             }
         }
 
@@ -428,25 +1579,119 @@ impl LifecycleManager {
         Ok(())
     }
 
-    async fn restore_policy_attachment(&self, component_id: &str) -> Result<()> {
-        self.policy_manager.restore_from_disk(component_id).await
-    }
-
-    async fn resolve_component_resource(&self, uri: &str) -> Result<(String, DownloadedResource)> {
-        // Show progress when running in CLI mode (stderr is a TTY)
-        let show_progress = std::io::stderr().is_terminal();
+    async fn resolve_component_resource(
+        &self,
+        uri: &str,
+        cancellation: &CancellationToken,
+        progress: Option<&dyn ProgressReporter>,
+    ) -> Result<(String, DownloadedResource, Option<String>)> {
+        self.source_policy.check(uri)?;
 
         let auth = self.auth_for_uri(uri);
-        let resource = loader::load_resource_with_progress::<ComponentResource>(
+        let uri = uri.trim();
+        let oci_reference = uri
+            .strip_prefix("oci://")
+            .and_then(|reference| reference.parse::<oci_client::Reference>().ok());
+        let mut load_uri = uri.to_string();
+        let mut signature_warning = None;
+        if let (true, Some(reference)) = (self.signature_policy.is_enabled(), &oci_reference) {
+            let verification = loader::cancellable(
+                uri,
+                cancellation,
+                self.signature_policy
+                    .verify_oci(&self.oci_client, reference, &auth),
+            )
+            .await;
+            match verification {
+                Ok((digest, identity)) => {
+                    info!(uri, %digest, identity, "Component signature verified");
+                    // Pull exactly what was signed, even if the tag moves
+                    load_uri = format!("oci://{}", reference.clone_with_digest(digest));
+                }
+                Err(error) if error.is::<DownloadCancelled>() => return Err(error),
+                Err(error) => signature_warning = Some(self.signature_failed(uri, error)?),
+            }
+        }
+
+        // Count what is pulled from registries, which cache hits are not
+        let counted = CountingProgress::new(progress);
+        let progress = match oci_reference {
+            Some(_) => Some(&counted as &dyn ProgressReporter),
+            None => progress,
+        };
+        let resource = loader::cancellable(
             uri,
-            &self.oci_client,
-            &self.http_client,
-            show_progress,
-            &auth,
+            cancellation,
+            loader::load_component_with_cache(
+                &load_uri,
+                self.storage.download_cache(),
+                &self.oci_client,
+                &self.http_client,
+                progress,
+                &auth,
+            ),
         )
-        .await?;
+        .await;
+        self.metrics.record_oci_download(counted.downloaded());
+        let resource = resource?;
+
+        if self.signature_policy.is_enabled() && oci_reference.is_none() {
+            match self
+                .signature_policy
+                .verify_detached(&self.http_client, uri, resource.as_ref())
+                .await
+            {
+                Ok(identity) => info!(uri, identity, "Component signature verified"),
+                Err(error) => signature_warning = Some(self.signature_failed(uri, error)?),
+            }
+        }
         let id = resource.id()?;
-        Ok((id, resource))
+        Ok((id, resource, signature_warning))
+    }
+
+    /// Handle a component from `uri` whose signature did not verify: fail
+    /// with [`SignatureRejected`] when signatures are enforced, otherwise
+    /// return the warning to load it with.
+    fn signature_failed(&self, uri: &str, error: anyhow::Error) -> Result<String> {
+        let reason = format!("{error:#}");
+        if self.signature_policy.mode == SignatureMode::Enforce {
+            return Err(SignatureRejected {
+                uri: uri.to_string(),
+                expected: self.signature_policy.identities(),
+                reason,
+            }
+            .into());
+        }
+        warn!(uri, %reason, "Loading component whose signature was not verified");
+        Ok(format!(
+            "The signature of '{uri}' was not verified: {reason}"
+        ))
+    }
+
+    /// Fail with [`ComponentError::Filtered`] if the component filter
+    /// excludes `component_id`.
+    fn check_component_filter(&self, component_id: &str) -> Result<()> {
+        if self.component_filter.allows(component_id) {
+            return Ok(());
+        }
+        Err(ComponentError::Filtered {
+            component_id: component_id.to_string(),
+        }
+        .into())
+    }
+
+    /// Fail with [`ComponentError::ReadOnly`] if `component_id` is an
+    /// embedded component.
+    fn check_writable(&self, component_id: &str) -> Result<()> {
+        path_guard::validate_component_id(component_id)?;
+        Ok(self.embedded.check_writable(component_id)?)
+    }
+
+    /// Check whether components may be loaded from `uri` under the sources
+    /// configured with [`LifecycleBuilder::with_allowed_sources`]. Nothing
+    /// is fetched.
+    pub fn check_component_source(&self, uri: &str) -> std::result::Result<(), SourceNotAllowed> {
+        self.source_policy.check(uri)
     }
 
     /// Resolve the [`oci_client::secrets::RegistryAuth`] to use for the given URI.
@@ -531,7 +1776,8 @@ impl LifecycleManager {
             // Exact key
             if let Some(entry) = map.get(registry) {
                 if let Some(auth_str) = entry.get("auth").and_then(|v| v.as_str()) {
-                    if let Ok(decoded) = base64::engine::general_purpose::STANDARD.decode(auth_str) {
+                    if let Ok(decoded) = base64::engine::general_purpose::STANDARD.decode(auth_str)
+                    {
                         if let Ok(s) = String::from_utf8(decoded) {
                             if let Some((user, pass)) = s.split_once(':') {
                                 return Some(oci_client::secrets::RegistryAuth::Basic(
@@ -548,7 +1794,9 @@ impl LifecycleManager {
             for (k, v) in map.iter() {
                 if k.ends_with(registry) {
                     if let Some(auth_str) = v.get("auth").and_then(|vv| vv.as_str()) {
-                        if let Ok(decoded) = base64::engine::general_purpose::STANDARD.decode(auth_str) {
+                        if let Ok(decoded) =
+                            base64::engine::general_purpose::STANDARD.decode(auth_str)
+                        {
                             if let Ok(s) = String::from_utf8(decoded) {
                                 if let Some((user, pass)) = s.split_once(':') {
                                     return Some(oci_client::secrets::RegistryAuth::Basic(
@@ -569,8 +1817,11 @@ impl LifecycleManager {
     /// Run the docker credential helper (docker-credential-<name>) with the `get`
     /// operation, passing the registry on stdin. Returns `Some(RegistryAuth)` on
     /// success.
-    fn run_credential_helper(helper_name: &str, registry: &str) -> Option<oci_client::secrets::RegistryAuth> {
-        use std::io::{Read, Write};
+    fn run_credential_helper(
+        helper_name: &str,
+        registry: &str,
+    ) -> Option<oci_client::secrets::RegistryAuth> {
+        use std::io::Write;
         use std::process::{Command, Stdio};
 
         // Helper binary is usually named `docker-credential-<helper_name>`
@@ -604,7 +1855,12 @@ impl LifecycleManager {
 
         let v: serde_json::Value = serde_json::from_str(&out).ok()?;
         let username = v.get("Username").or_else(|| v.get("username"))?.as_str()?;
-        let secret = v.get("Secret").or_else(|| v.get("secret")).or_else(|| v.get("Password")).or_else(|| v.get("password"))?.as_str()?;
+        let secret = v
+            .get("Secret")
+            .or_else(|| v.get("secret"))
+            .or_else(|| v.get("Password"))
+            .or_else(|| v.get("password"))?
+            .as_str()?;
 
         Some(oci_client::secrets::RegistryAuth::Basic(
             username.to_string(),
@@ -628,1368 +1884,7387 @@ impl LifecycleManager {
         }
     }
 
+    /// Compile a component and register its tools, recording a failure in
+    /// the component's load state.
+    ///
+    /// Only a file that does not compile or link is recorded with its stamp,
+    /// so [`Self::ensure_component_loaded`] refuses it until it changes.
+    /// Everything else, such as I/O errors, failed health checks, or tool
+    /// collisions, may not happen again and is retried on the next call.
     async fn compile_and_register_component(
         &self,
         component_id: &str,
         wasm_path: &Path,
     ) -> Result<ComponentLoadOutcome> {
-        let (component, wasm_bytes) = self
-            .load_component_optimized(wasm_path, component_id)
-            .await?;
-
-        let instance_pre = self
-            .runtime
-            .instantiate_pre(&component)
-            .context("failed to instantiate component")?;
-
-        // Extract package docs from wasm bytes
-        let package_docs = extract_package_docs(&wasm_bytes);
-
-        let component_instance = ComponentInstance {
-            component: Arc::new(component),
-            instance_pre: Arc::new(instance_pre),
-            package_docs: package_docs.clone(),
+        // A file under an embedded component's ID never replaces it
+        self.check_writable(component_id)?;
+        self.registry.begin_loading(component_id).await;
+        let start = Instant::now();
+        let (result, deterministic) = match self.compile_component(component_id, wasm_path).await {
+            Ok(compiled) => (
+                self.try_register_compiled_component(component_id, wasm_path, compiled)
+                    .await,
+                false,
+            ),
+            Err(error) => {
+                let deterministic = error
+                    .root_cause()
+                    .downcast_ref::<std::io::Error>()
+                    .is_none();
+                (Err(error), deterministic)
+            }
         };
+        self.metrics
+            .record_load(start.elapsed().as_millis() as u64, result.is_ok());
+        if let Err(error) = &result {
+            let stamp = if deterministic {
+                self.storage
+                    .create_validation_stamp(wasm_path, self.strict_validation)
+                    .await
+                    .ok()
+            } else {
+                None
+            };
+            let failure = ComponentLoadState::Failed {
+                reason: format!("{error:#}"),
+            };
+            self.registry
+                .fail_loading(component_id, failure, stamp)
+                .await;
+        }
+        result
+    }
 
-        // Use package docs if available
-        let tool_metadata = if let Some(ref docs) = package_docs {
-            component_exports_to_tools_with_docs(
-                &component_instance.component,
-                self.runtime.as_ref(),
-                true,
-                docs,
-            )
-        } else {
-            component_exports_to_tools(&component_instance.component, self.runtime.as_ref(), true)
-        };
+    async fn try_register_compiled_component(
+        &self,
+        component_id: &str,
+        wasm_path: &Path,
+        (component_instance, compiled_with): (ComponentInstance, Option<CompileInfo>),
+    ) -> Result<ComponentLoadOutcome> {
+        let package_docs = component_instance.package_docs.clone();
+        let tool_metadata = self.component_tool_metadata(&component_instance);
+
+        let mut warnings = Vec::new();
+        let mut skipped_exports = Vec::new();
+        if tool_metadata.is_empty() {
+            skipped_exports =
+                component_skipped_exports(&component_instance.component, self.runtime.as_ref());
+            let found = if skipped_exports.is_empty() {
+                "none".to_string()
+            } else {
+                skipped_exports
+                    .iter()
+                    .map(|export| format!("{} ({})", export.name, export.reason))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            warn!(%component_id, exports = %found, "Component exposes no tools");
+            warnings.push(format!(
+                "Component '{component_id}' loaded, but exposes no tools; exports found: {found}"
+            ));
+        }
 
-        let tool_names: Vec<String> = tool_metadata
-            .iter()
-            .map(|tool| tool.normalized_name.clone())
-            .collect();
+        // Checked before the metadata is written, so a rejected component
+        // leaves nothing behind that would register it on the next start
+        self.registry
+            .check_tool_collisions(component_id, &tool_metadata, self.tool_collisions)
+            .await?;
 
-        if let Ok(validation_stamp) = self.storage.create_validation_stamp(wasm_path, false).await {
+        if let Ok(validation_stamp) = self.storage.create_validation_stamp(wasm_path, true).await {
             if let Err(e) = self
-                .save_component_metadata(component_id, &tool_metadata, validation_stamp)
+                .save_component_metadata(
+                    component_id,
+                    &tool_metadata,
+                    package_docs.as_ref().and_then(package_docs_to_markdown),
+                    validation_stamp,
+                    compiled_with,
+                )
                 .await
             {
                 warn!(%component_id, error = %e, "Failed to save component metadata");
             }
         }
 
-        let load_result = self
-            .registry
-            .upsert_component(component_id.to_string(), component_instance, tool_metadata)
+        let on_load = self.lifecycle_hook(&component_instance, LifecycleHook::OnLoad);
+        let (load_result, changes, health) = self
+            .check_health_and_register(component_id, component_instance, tool_metadata)
             .await?;
 
-        if let Err(error) = self.policy_manager.restore_from_disk(component_id).await {
-            warn!(%component_id, %error, "Failed to restore policy attachment");
+        if let Some(stale) = &changes.stale_schema {
+            warn!(
+                %component_id,
+                served_hash = %stale.served_hash,
+                compiled_hash = %stale.compiled_hash,
+                "Tool schemas served from cached metadata did not match the compiled component"
+            );
+            warnings.push(format!(
+                "Tool schemas of component '{component_id}' served from cached metadata did not match the compiled component and were replaced"
+            ));
+        }
+        self.emit_load_event(component_id, &load_result, &changes);
+        let on_load = match on_load {
+            Some(function) => {
+                self.run_registered_lifecycle_hook(component_id, LifecycleHook::OnLoad, &function)
+                    .await
+            }
+            None => None,
+        };
+        if let Some(error) = on_load.as_ref().and_then(|report| report.error.as_ref()) {
+            warnings.push(format!(
+                "on-load hook of component '{component_id}' failed: {error}"
+            ));
         }
+        self.schedule_warmup(component_id);
 
         Ok(ComponentLoadOutcome {
             component_id: component_id.to_string(),
             status: load_result,
-            tool_names,
+            tool_names: changes.tool_names,
+            health,
+            on_load,
+            added_tools: changes.added,
+            removed_tools: changes.removed,
+            changed_tools: changes.changed,
+            tool_collisions: changes.collisions,
+            warnings,
+            skipped_exports,
+            id_assignment: None,
         })
     }
 
-    /// Loads a new component from the given URI. This URI can be a file path, an OCI reference, or a URL.
-    ///
-    /// If a component with the given id already exists, it will be updated with the new component.
-    /// Returns rich [`ComponentLoadOutcome`] information describing the loaded
-    /// component and whether it replaced an existing instance.
-    #[instrument(skip(self))]
-    pub async fn load_component(&self, uri: &str) -> Result<ComponentLoadOutcome> {
-        debug!(uri, "Loading component");
-        let (component_id, resource) = self.resolve_component_resource(uri).await?;
-        let staged_path = self
-            .stage_component_artifact(&component_id, resource)
+    /// Compile a component, from its precompiled artifact if this engine
+    /// built it, and pre-instantiate it against the server's linker.
+    async fn compile_component(
+        &self,
+        component_id: &str,
+        wasm_path: &Path,
+    ) -> Result<(ComponentInstance, Option<CompileInfo>)> {
+        let (component, wasm_bytes, compiled_with) = self
+            .load_component_optimized(wasm_path, component_id)
             .await?;
-        let outcome = self
-            .compile_and_register_component(&component_id, &staged_path)
-            .await
-            .with_context(|| {
-                format!(
-                    "Failed to compile component from path: {}. Please ensure the file is a valid WebAssembly component.",
-                    staged_path.display()
-                )
-            })?;
-
-        info!(
-            component_id = %outcome.component_id,
-            status = ?outcome.status,
-            tools = ?outcome.tool_names,
-            "Successfully loaded component"
-        );
-        Ok(outcome)
+        let component_instance = self.prepare_component(component, &wasm_bytes)?;
+        Ok((component_instance, compiled_with))
     }
 
-    /// Unloads the component with the specified id. This removes the component from the runtime
-    /// and removes all associated files from disk, making it the reverse operation of load_component.
-    /// This function fails if any files cannot be removed (except when they don't exist).
-    #[instrument(skip(self))]
-    pub async fn unload_component(&self, id: &str) -> Result<()> {
-        debug!("Unloading component and removing files from disk");
-
-        // Remove files first, then clean up memory on success
-        self.storage.remove_component_artifacts(id).await?;
-
-        let policy_path = self.get_component_policy_path(id);
-        self.storage
-            .remove_if_exists(&policy_path, "policy file", id)
-            .await?;
+    /// Pre-instantiate a compiled component against the server's linker.
+    fn prepare_component(
+        &self,
+        component: Component,
+        wasm_bytes: &[u8],
+    ) -> Result<ComponentInstance> {
+        let instance_pre = self
+            .runtime
+            .instantiate_pre(&component)
+            .context("failed to instantiate component")?;
 
-        let metadata_path = self.get_component_metadata_path(id);
-        self.storage
-            .remove_if_exists(&metadata_path, "policy metadata file", id)
-            .await?;
+        Ok(ComponentInstance {
+            component: Arc::new(component),
+            instance_pre: Arc::new(instance_pre),
+            package_docs: extract_package_docs(wasm_bytes),
+            health: None,
+            schema_hash: None,
+        })
+    }
 
-        // Only cleanup memory after all files are successfully removed
-        self.registry.remove_component(id).await;
-        self.policy_manager.cleanup(id).await;
+    /// The tools a component exposes, described with its package docs if it
+    /// has any. Lifecycle hooks are not tools.
+    fn component_tool_metadata(&self, component_instance: &ComponentInstance) -> Vec<ToolMetadata> {
+        let mut tools = match &component_instance.package_docs {
+            Some(docs) => component_exports_to_tools_with_docs(
+                &component_instance.component,
+                self.runtime.as_ref(),
+                true,
+                docs,
+            ),
+            None => component_exports_to_tools(
+                &component_instance.component,
+                self.runtime.as_ref(),
+                true,
+            ),
+        };
+        tools.retain(|tool| !is_lifecycle_hook(tool));
+        tools
+    }
 
-        info!(component_id = %id, "Component unloaded successfully");
-        Ok(())
+    /// The function implementing `hook`, if the component exports one.
+    fn lifecycle_hook(
+        &self,
+        component_instance: &ComponentInstance,
+        hook: LifecycleHook,
+    ) -> Option<FunctionIdentifier> {
+        let exports =
+            component_exports_to_tools(&component_instance.component, self.runtime.as_ref(), true);
+        find_lifecycle_hook(&exports, hook).map(|tool| tool.identifier.clone())
     }
 
-    /// Returns the component ID for a given tool name.
-    /// If there are multiple components with the same tool name, returns an error.
+    /// Compiles a component in the component directory the way loading it
+    /// does and returns the schema of the tools it would register, without
+    /// registering them, in the form [`Self::get_component_schema`] returns
+    /// for components that are not loaded. Unlike that, this never falls
+    /// back to the tools saved in the component's metadata, and it
+    /// fails where loading would, e.g. on imports the server does not
+    /// provide. Reuses the precompiled artifact if this engine built it.
     #[instrument(skip(self))]
-    pub async fn get_component_id_for_tool(&self, tool_name: &str) -> Result<String> {
-        let tool_infos = self
-            .registry
-            .tool_infos(tool_name)
-            .await
-            .context("Tool not found")?;
-
-        if tool_infos.len() > 1 {
-            bail!(
-                "Multiple components found for tool '{}': {}",
-                tool_name,
-                tool_infos
-                    .iter()
-                    .map(|info| info.component_id.as_str())
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            );
+    pub async fn inspect_component(&self, component_id: &str) -> Result<Value> {
+        path_guard::validate_component_id(component_id)?;
+        let wasm_path = self.component_path(component_id);
+        if !wasm_path.is_file() {
+            return Err(anyhow!("Component not found: {}", component_id));
         }
-
-        Ok(tool_infos[0].component_id.clone())
+        let (component_instance, _) = self.compile_component(component_id, &wasm_path).await?;
+        let tools: Vec<Value> = self
+            .component_tool_metadata(&component_instance)
+            .iter()
+            .map(|tool| schema::canonicalize_output_schema(&tool.schema))
+            .collect();
+        Ok(serde_json::json!({ "tools": tools }))
     }
 
-    /// Lists all available tools across all components
+    /// Lists the interfaces and functions a component in the component
+    /// directory imports, each marked with whether this runtime provides it.
+    /// Components with imports that are not provided fail to load.
     #[instrument(skip(self))]
-    pub async fn list_tools(&self) -> Vec<Value> {
-        self.registry.list_tools().await
+    pub async fn component_imports(&self, component_id: &str) -> Result<Vec<ComponentImport>> {
+        path_guard::validate_component_id(component_id)?;
+        let wasm_path = self.component_path(component_id);
+        if !wasm_path.is_file() {
+            return Err(anyhow!("Component not found: {}", component_id));
+        }
+        let (component, _, _) = self
+            .load_component_optimized(&wasm_path, component_id)
+            .await?;
+        Ok(self.runtime.component_imports(&component))
     }
 
-    /// Returns the schema for a specific tool owned by a component, if available
-    #[instrument(skip(self))]
-    pub async fn get_tool_schema_for_component(
+    /// Restore the component's policy, run its health probe if it exports one,
+    /// and register its tools.
+    ///
+    /// Fails without registering anything when the probe fails and healthy
+    /// components are required.
+    async fn check_health_and_register(
         &self,
         component_id: &str,
-        tool_name: &str,
-    ) -> Option<Value> {
-        let tool_infos = self.registry.tool_infos(tool_name).await?;
-        tool_infos
-            .iter()
-            .find(|info| info.component_id == component_id)
-            .map(|info| info.schema.clone())
-    }
+        mut component_instance: ComponentInstance,
+        tool_metadata: Vec<ToolMetadata>,
+    ) -> Result<(LoadResult, ToolChanges, Option<HealthReport>)> {
+        // The probe runs under the policy the new instance will be
+        // registered with; it is only installed together with the instance
+        let restored_template = match self.embedded.get(component_id) {
+            Some(EmbeddedComponent {
+                policy: Some(yaml), ..
+            }) => Some(
+                self.policy_manager
+                    .template_from_yaml(component_id, yaml)
+                    .await?,
+            ),
+            // Policy files on disk never apply to embedded components
+            Some(_) => None,
+            None => self.policy_manager.template_from_disk(component_id).await?,
+        };
+        let health = match find_health_probe(&tool_metadata) {
+            Some(probe) => {
+                let policy_template = match &restored_template {
+                    Some(template) => Arc::clone(template),
+                    None => {
+                        self.policy_manager
+                            .template_for_component(component_id)
+                            .await
+                    }
+                };
+                Some(
+                    self.run_health_check(
+                        component_id,
+                        &component_instance,
+                        policy_template,
+                        probe,
+                    )
+                    .await,
+                )
+            }
+            None => None,
+        };
 
-    /// Returns the requested component. Returns `None` if the component is not found.
-    #[instrument(skip(self))]
-    pub async fn get_component(&self, component_id: &str) -> Option<ComponentInstance> {
-        self.registry.get_component(component_id).await
-    }
+        if let Some(report) = health.as_ref().filter(|report| !report.healthy) {
+            let error = report.error.as_deref().unwrap_or("unknown error");
+            if self.health_checks.require_healthy {
+                let failure = ComponentLoadState::Disabled {
+                    reason: error.to_string(),
+                };
+                self.registry
+                    .fail_loading(component_id, failure, None)
+                    .await;
+                bail!("Health check failed for component {component_id}: {error}");
+            }
+            warn!(%component_id, %error, "Component health check failed");
+        }
 
-    /// Lists all loaded components by their IDs
-    #[instrument(skip(self))]
-    pub async fn list_components(&self) -> Vec<String> {
-        self.registry.list_components().await
+        component_instance.health = health.clone();
+        component_instance.schema_hash = Some(schema_hash(&tool_metadata));
+        // Remembered so that a file replaced behind the manager's back is
+        // noticed by the drift check, also when `cp -p` kept its size and
+        // modification time
+        let stamp = self
+            .storage
+            .create_validation_stamp(&self.component_path(component_id), true)
+            .await
+            .ok();
+        let _swap = self.swap_lock.write().await;
+        if let Some(template) = restored_template {
+            self.policy_manager
+                .store_template(component_id, template)
+                .await;
+        }
+        let (load_result, changes) = self
+            .registry
+            .upsert_component(
+                component_id.to_string(),
+                component_instance,
+                tool_metadata,
+                stamp,
+                self.tool_collisions,
+            )
+            .await?;
+        Ok((load_result, changes, health))
     }
 
-    /// Lists all known components by ID (union of loaded components and any
-    /// `*.wasm` files present in the component directory). Does not compile components.
-    #[instrument(skip(self))]
-    pub async fn list_components_known(&self) -> Vec<String> {
-        use std::collections::HashSet;
-        let loaded = self.registry.list_components().await;
-        let mut set: HashSet<String> = loaded.into_iter().collect();
-
-        if let Ok(entries) = std::fs::read_dir(self.storage.root()) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-
-                // 1) Detect regular .wasm files
-                let is_wasm = path
-                    .extension()
-                    .and_then(|ext| ext.to_str())
-                    .map(|ext| ext.eq_ignore_ascii_case("wasm"))
-                    .unwrap_or(false);
-                if is_wasm {
-                    if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
-                        set.insert(stem.to_string());
-                        continue;
-                    }
-                }
+    /// Start the configured warm-up for a component in the background.
+    ///
+    /// Warm-ups run one at a time so they only use spare capacity; the
+    /// result is announced with [`LifecycleEvent::ComponentWarmedUp`].
+    fn schedule_warmup(&self, component_id: &str) {
+        let Some(config) = self.warmup.get(component_id).cloned() else {
+            return;
+        };
+        let manager = self.clone();
+        let component_id = component_id.to_string();
+        let permits = self.warmup_scheduler.permits();
+        tokio::spawn(async move {
+            let start = Instant::now();
+            let Ok(_permit) = permits.acquire_owned().await else {
+                return;
+            };
+            let mut report = manager.warm_up_component(&component_id, &config).await;
+            report.total_ms = start.elapsed().as_millis() as u64;
 
-                // 2) Detect metadata files ("<id>.metadata.json")
-                if let Some(fname) = path.file_name().and_then(|s| s.to_str()) {
-                    if fname.ends_with(&format!(".{METADATA_EXT}")) {
-                        if let Ok(content) = std::fs::read_to_string(&path) {
-                            if let Ok(meta) = serde_json::from_str::<ComponentMetadata>(&content) {
-                                set.insert(meta.component_id);
-                            }
-                        }
-                    }
-                }
+            match &report.error {
+                Some(error) => warn!(%component_id, %error, ?report, "Component warm-up failed"),
+                None => info!(%component_id, ?report, "Component warm-up finished"),
             }
-        }
-
-        let mut v: Vec<String> = set.into_iter().collect();
-        v.sort();
-        v
+            manager
+                .warmup_reports
+                .write()
+                .await
+                .insert(component_id.clone(), report.clone());
+            manager.events.emit(LifecycleEvent::ComponentWarmedUp {
+                component_id,
+                report,
+            });
+        });
     }
 
-    /// Gets the schema for a specific component
-    #[instrument(skip(self))]
-    pub async fn get_component_schema(&self, component_id: &str) -> Option<Value> {
-        // Prefer live component schema if loaded
-        if let Some(component_instance) = self.get_component(component_id).await {
-            return Some(
-                if let Some(ref package_docs) = component_instance.package_docs {
-                    component_exports_to_json_schema_with_docs(
-                        &component_instance.component,
-                        self.runtime.as_ref(),
-                        true,
-                        package_docs,
-                    )
-                } else {
-                    component_exports_to_json_schema(
-                        &component_instance.component,
-                        self.runtime.as_ref(),
-                        true,
-                    )
-                },
-            );
-        }
+    async fn warm_up_component(&self, component_id: &str, config: &WarmupConfig) -> WarmupReport {
+        let mut report = WarmupReport {
+            instances: 0,
+            instantiation_ms: 0,
+            tool: config.tool.clone(),
+            tool_ms: None,
+            total_ms: 0,
+            error: None,
+        };
+        let Some(mut snapshot) = self.snapshot_component(component_id).await else {
+            report.error = Some("component is no longer loaded".to_string());
+            return report;
+        };
 
-        // Fallback to metadata-based schema without compiling the component
-        match self.load_component_metadata(component_id).await {
-            Ok(Some(metadata)) => {
-                let tools: Vec<Value> = metadata
-                    .tool_schemas
-                    .into_iter()
-                    .map(|schema| schema::canonicalize_output_schema(&schema))
-                    .collect();
-                Some(serde_json::json!({
-                    "tools": tools
-                }))
+        for _ in 0..config.instances {
+            // Let pending requests run between instantiations
+            tokio::task::yield_now().await;
+            // A permission change in the meantime applies to the next instance
+            let Some(current) = self.refresh_snapshot(component_id, snapshot).await else {
+                report.error = Some("component is no longer loaded".to_string());
+                return report;
+            };
+            snapshot = current;
+            let start = Instant::now();
+            let (store, instance, _, _) = match self
+                .instantiate_component(
+                    component_id,
+                    &snapshot.component,
+                    Arc::clone(&snapshot.policy_template),
+                    None,
+                )
+                .await
+            {
+                Ok(instantiated) => instantiated,
+                Err(error) => {
+                    report.error = Some(error.to_string());
+                    return report;
+                }
+            };
+            report.instances += 1;
+            report.instantiation_ms += start.elapsed().as_millis() as u64;
+            // Keep the instance for the first calls instead of dropping it
+            if let Some(key) = self.pool_key(&snapshot) {
+                if can_pool(&store.data().inner) {
+                    self.instance_pool.put(component_id, key, store, instance);
+                }
             }
-            _ => None,
         }
-    }
 
-    fn component_path(&self, component_id: &str) -> PathBuf {
-        self.storage.component_path(component_id)
-    }
+        let Some(tool) = &config.tool else {
+            return report;
+        };
+        let identifier = self
+            .registry
+            .tool_infos(tool)
+            .await
+            .and_then(|infos| {
+                infos
+                    .into_iter()
+                    .find(|info| info.component_id == component_id)
+            })
+            .map(|info| info.identifier);
+        let Some(identifier) = identifier else {
+            report.error = Some(format!("component does not export tool {tool}"));
+            return report;
+        };
 
-    /// Get the path to precompiled component file
-    fn component_precompiled_path(&self, component_id: &str) -> PathBuf {
-        self.storage.precompiled_path(component_id)
+        let Some(snapshot) = self.refresh_snapshot(component_id, snapshot).await else {
+            report.error = Some("component is no longer loaded".to_string());
+            return report;
+        };
+        let start = Instant::now();
+        let no_arguments = Value::Object(Default::default());
+        let pool_key = self.pool_key(&snapshot);
+        match self
+            .call_component_function(
+                component_id,
+                ComponentCall {
+                    pool_key,
+                    ..ComponentCall::new(&snapshot.component, snapshot.policy_template)
+                },
+                &identifier,
+                &no_arguments,
+                &mut CallTiming::default(),
+                &mut CapturedOutput::default(),
+            )
+            .await
+        {
+            Ok(_) => report.tool_ms = Some(start.elapsed().as_millis() as u64),
+            Err(error) => report.error = Some(format!("warm-up call to {tool} failed: {error}")),
+        }
+        report
     }
 
-    pub(crate) fn get_component_policy_path(&self, component_id: &str) -> PathBuf {
-        self.policy_manager.policy_path(component_id)
+    /// Invoke a component's health probe, bounded by the configured timeout.
+    async fn run_health_check(
+        &self,
+        component_id: &str,
+        component_instance: &ComponentInstance,
+        policy_template: Arc<WasiStateTemplate>,
+        probe: &ToolMetadata,
+    ) -> HealthReport {
+        let start = Instant::now();
+        let no_arguments = Value::Object(Default::default());
+        let mut timing = CallTiming::default();
+        let mut output = CapturedOutput::default();
+        let call = self.call_component_function(
+            component_id,
+            ComponentCall::new(component_instance, policy_template),
+            &probe.identifier,
+            &no_arguments,
+            &mut timing,
+            &mut output,
+        );
+        let error = match tokio::time::timeout(self.health_checks.timeout, call).await {
+            Ok(Ok(result)) => probe_result_error(&result),
+            Ok(Err(error)) => Some(error.to_string()),
+            Err(_) => Some(format!(
+                "health check timed out after {}ms",
+                self.health_checks.timeout.as_millis()
+            )),
+        };
+        let report = HealthReport {
+            healthy: error.is_none(),
+            probe: probe.normalized_name.clone(),
+            error,
+            duration_ms: start.elapsed().as_millis() as u64,
+        };
+        debug!(%component_id, ?report, "Ran component health check");
+        report
     }
 
-    pub(crate) fn get_component_metadata_path(&self, component_id: &str) -> PathBuf {
-        self.policy_manager.metadata_path(component_id)
+    /// Run a lifecycle hook of a registered component under its current
+    /// policy. `None` if the component is no longer registered.
+    async fn run_registered_lifecycle_hook(
+        &self,
+        component_id: &str,
+        hook: LifecycleHook,
+        function: &FunctionIdentifier,
+    ) -> Option<LifecycleHookReport> {
+        let snapshot = self.snapshot_component(component_id).await?;
+        Some(
+            self.run_lifecycle_hook(
+                component_id,
+                &snapshot.component,
+                snapshot.policy_template,
+                hook,
+                function,
+            )
+            .await,
+        )
     }
 
-    /// Attach a policy to a component by URI.
-    pub async fn attach_policy(&self, component_id: &str, policy_uri: &str) -> Result<()> {
-        if !self.registry.contains_component(component_id).await {
-            return Err(anyhow!("Component not found: {}", component_id));
+    /// Invoke a component's lifecycle hook, bounded by
+    /// [`LIFECYCLE_HOOK_TIMEOUT`], and record that it ran.
+    async fn run_lifecycle_hook(
+        &self,
+        component_id: &str,
+        component_instance: &ComponentInstance,
+        policy_template: Arc<WasiStateTemplate>,
+        hook: LifecycleHook,
+        function: &FunctionIdentifier,
+    ) -> LifecycleHookReport {
+        let start = Instant::now();
+        let no_arguments = Value::Object(Default::default());
+        let mut timing = CallTiming::default();
+        let mut output = CapturedOutput::default();
+        let call = self.call_component_function(
+            component_id,
+            ComponentCall::new(component_instance, policy_template),
+            function,
+            &no_arguments,
+            &mut timing,
+            &mut output,
+        );
+        let error = match tokio::time::timeout(LIFECYCLE_HOOK_TIMEOUT, call).await {
+            Ok(Ok(result)) => hook_result_error(hook, &result),
+            Ok(Err(error)) => Some(error.to_string()),
+            Err(_) => Some(format!(
+                "{} hook timed out after {}ms",
+                hook.function_name(),
+                LIFECYCLE_HOOK_TIMEOUT.as_millis()
+            )),
+        };
+        let report = LifecycleHookReport {
+            hook,
+            succeeded: error.is_none(),
+            error,
+            duration_ms: start.elapsed().as_millis() as u64,
+        };
+        match &report.error {
+            Some(error) => warn!(%component_id, %error, ?report, "Component lifecycle hook failed"),
+            None => info!(%component_id, ?report, "Ran component lifecycle hook"),
         }
-        self.policy_manager
-            .attach_policy(component_id, policy_uri)
-            .await
+        self.events.emit(LifecycleEvent::LifecycleHookRan {
+            component_id: component_id.to_string(),
+            report: report.clone(),
+        });
+        report
     }
 
-    /// Detach any policy associated with the given component.
-    pub async fn detach_policy(&self, component_id: &str) -> Result<()> {
-        self.policy_manager.detach_policy(component_id).await
+    /// Run the `on-unload` hook of a component about to be unloaded,
+    /// compiling it first if it is not registered.
+    async fn run_unload_hook(&self, component_id: &str) -> Option<LifecycleHookReport> {
+        let (component_instance, policy_template) =
+            match self.snapshot_component(component_id).await {
+                Some(snapshot) => (snapshot.component, snapshot.policy_template),
+                None => {
+                    let wasm_path = self.component_path(component_id);
+                    if !wasm_path.is_file() {
+                        return None;
+                    }
+                    match self.compile_component(component_id, &wasm_path).await {
+                        Ok((component_instance, _)) => (
+                            component_instance,
+                            self.policy_manager
+                                .template_for_component(component_id)
+                                .await,
+                        ),
+                        Err(error) => {
+                            warn!(
+                                %component_id,
+                                %error,
+                                "Failed to compile component for its on-unload hook"
+                            );
+                            return None;
+                        }
+                    }
+                }
+            };
+        let function = self.lifecycle_hook(&component_instance, LifecycleHook::OnUnload)?;
+        Some(
+            self.run_lifecycle_hook(
+                component_id,
+                &component_instance,
+                policy_template,
+                LifecycleHook::OnUnload,
+                &function,
+            )
+            .await,
+        )
     }
 
-    /// Retrieve policy metadata for a component if one is attached.
-    pub async fn get_policy_info(&self, component_id: &str) -> Option<PolicyInfo> {
-        self.policy_manager.get_policy_info(component_id).await
+    /// Loads a new component from the given URI. This URI can be a file path, an OCI reference, or a URL.
+    ///
+    /// If a component with the given id already exists, it will be updated with the new component.
+    /// A load of a URI that is already being loaded waits for that load and
+    /// returns its outcome instead of downloading and compiling the component
+    /// again; if that load fails, this one is tried on its own.
+    /// Returns rich [`ComponentLoadOutcome`] information describing the loaded
+    /// component and whether it replaced an existing instance.
+    pub async fn load_component(&self, uri: &str) -> Result<ComponentLoadOutcome> {
+        self.load_component_with_cancellation(uri, &CancellationToken::new())
+            .await
     }
 
-    /// Grant a specific permission rule to a component.
-    #[instrument(skip(self))]
-    pub async fn grant_permission(
+    /// [`Self::load_component`], abandoned when `cancellation` is triggered
+    /// before the component is downloaded. A cancelled load fails with
+    /// [`DownloadCancelled`], aborts the download, and leaves the component
+    /// directory untouched. Once the download has finished the component is
+    /// installed and compiled regardless, so it is never left half-written.
+    ///
+    /// When stderr is a terminal, the progress of the load is printed to it.
+    #[instrument(skip(self, cancellation))]
+    pub async fn load_component_with_cancellation(
         &self,
-        component_id: &str,
-        permission_type: &str,
-        details: &serde_json::Value,
-    ) -> Result<()> {
-        if !self.registry.contains_component(component_id).await {
-            return Err(anyhow!("Component not found: {}", component_id));
-        }
-        self.policy_manager
-            .grant_permission(component_id, permission_type, details)
+        uri: &str,
+        cancellation: &CancellationToken,
+    ) -> Result<ComponentLoadOutcome> {
+        let terminal = std::io::stderr().is_terminal().then(TerminalProgress::new);
+        let progress = terminal
+            .as_ref()
+            .map(|terminal| terminal as &dyn ProgressReporter);
+        self.load_component_reporting(uri, cancellation, progress)
             .await
     }
 
-    /// Revoke a specific permission rule from a component.
-    #[instrument(skip(self))]
-    pub async fn revoke_permission(
+    /// [`Self::load_component_with_cancellation`], reporting to `progress`
+    /// how much of an `oci://` or `https://` component has been downloaded,
+    /// and when it starts compiling, instead of printing to a terminal.
+    #[instrument(skip(self, cancellation, progress))]
+    pub async fn load_component_with_progress(
         &self,
-        component_id: &str,
-        permission_type: &str,
-        details: &serde_json::Value,
-    ) -> Result<()> {
-        if !self.registry.contains_component(component_id).await {
-            return Err(anyhow!("Component not found: {}", component_id));
-        }
-        self.policy_manager
-            .revoke_permission(component_id, permission_type, details)
+        uri: &str,
+        cancellation: &CancellationToken,
+        progress: &dyn ProgressReporter,
+    ) -> Result<ComponentLoadOutcome> {
+        self.load_component_reporting(uri, cancellation, Some(progress))
             .await
     }
 
-    /// Reset all permissions for a component to defaults.
-    #[instrument(skip(self))]
-    pub async fn reset_permission(&self, component_id: &str) -> Result<()> {
-        if !self.registry.contains_component(component_id).await {
-            return Err(anyhow!("Component not found: {}", component_id));
+    async fn load_component_reporting(
+        &self,
+        uri: &str,
+        cancellation: &CancellationToken,
+        progress: Option<&dyn ProgressReporter>,
+    ) -> Result<ComponentLoadOutcome> {
+        let mut in_flight = tokio::select! {
+            in_flight = self.loads_in_flight.lock(uri.trim()) => in_flight,
+            _ = cancellation.cancelled() => {
+                return Err(DownloadCancelled {
+                    uri: uri.trim().to_string(),
+                }
+                .into())
+            }
+        };
+        if let Some(outcome) = in_flight.as_ref() {
+            debug!(uri, "Sharing the outcome of a concurrent load");
+            return Ok(outcome.clone());
         }
-        self.policy_manager.reset_permission(component_id).await
+        let outcome = self
+            .load_component_uncontended(uri, cancellation, progress)
+            .await?;
+        *in_flight = Some(outcome.clone());
+        Ok(outcome)
     }
 
-    /// Revoke storage permission for a specific URI.
-    #[instrument(skip(self))]
-    pub async fn revoke_storage_permission_by_uri(
+    async fn load_component_uncontended(
         &self,
-        component_id: &str,
         uri: &str,
-    ) -> Result<()> {
-        if !self.registry.contains_component(component_id).await {
-            return Err(anyhow!("Component not found: {}", component_id));
+        cancellation: &CancellationToken,
+        progress: Option<&dyn ProgressReporter>,
+    ) -> Result<ComponentLoadOutcome> {
+        debug!(uri, "Loading component");
+        let (component_id, resource, signature_warning) = self
+            .resolve_component_resource(uri, cancellation, progress)
+            .await?;
+        if cancellation.is_cancelled() {
+            return Err(DownloadCancelled {
+                uri: uri.trim().to_string(),
+            }
+            .into());
+        }
+        if let Some(progress) = progress {
+            progress.report(LoadProgress::Compiling);
+        }
+        if !uri.trim().starts_with("https://") {
+            let mut outcome = self.install_component(uri, &component_id, resource).await?;
+            outcome.warnings.extend(signature_warning);
+            return Ok(outcome);
         }
-        self.policy_manager
-            .revoke_storage_permission_by_uri(component_id, uri)
-            .await
-    }
 
-    /// Returns the component directory root on disk.
-    pub fn component_root(&self) -> &Path {
-        self.storage.root()
+        let name = component_id;
+        let (component_id, id_assignment) = self
+            .assign_url_component_id(uri.trim(), &name, &resource)
+            .await?;
+        let mut outcome = self.install_component(uri, &component_id, resource).await?;
+        outcome.warnings.extend(signature_warning);
+        if id_assignment == IdAssignment::HashSuffixed {
+            outcome.warnings.push(format!(
+                "A different component is installed as '{name}'; this one was loaded as '{component_id}'"
+            ));
+        }
+        outcome.id_assignment = Some(id_assignment);
+        Ok(outcome)
     }
 
-    /// Ensure a specific component is loaded (compiled and instantiated) by its ID.
-    /// If it's already loaded, this is a no-op. If the wasm file is not present in
-    /// the component directory, an error is returned.
-    #[instrument(skip(self))]
-    pub async fn ensure_component_loaded(&self, component_id: &str) -> Result<()> {
-        if self.registry.contains_component(component_id).await {
-            return Ok(());
+    /// Choose the ID of a component downloaded from the `https://` URL
+    /// `uri`, whose last path segment gave `name`. A URL loaded before keeps
+    /// its ID. Otherwise `name` is used, unless a component with different
+    /// content is installed under it, in which case the first eight hex
+    /// digits of the download's SHA-256 digest are appended.
+    async fn assign_url_component_id(
+        &self,
+        uri: &str,
+        name: &str,
+        resource: &DownloadedResource,
+    ) -> Result<(String, IdAssignment)> {
+        if let Some(component_id) = self.component_id_for_source(uri).await {
+            debug!(%uri, %component_id, "Reusing the component ID of a URL loaded before");
+            return Ok((component_id, IdAssignment::Reused));
         }
 
-        let entry_path = self.component_path(component_id);
-        if !entry_path.exists() {
-            bail!("Component not found: {}", component_id);
+        let installed = self.component_path(name);
+        if !installed.exists() {
+            return Ok((name.to_string(), IdAssignment::PathSegment));
         }
+        let digest = file_digest(resource.as_ref()).await?;
+        if file_digest(&installed).await? == digest {
+            return Ok((name.to_string(), IdAssignment::PathSegment));
+        }
+        let hash = digest.trim_start_matches("sha256:");
+        Ok((format!("{name}-{}", &hash[..8]), IdAssignment::HashSuffixed))
+    }
 
-        self.compile_and_register_component(component_id, &entry_path)
-            .await
-            .with_context(|| {
-                format!(
-                    "Failed to compile component from path: {}",
-                    entry_path.display()
-                )
-            })?;
+    /// ID of the installed component whose metadata records `uri` as its
+    /// source.
+    async fn component_id_for_source(&self, uri: &str) -> Option<String> {
+        let mut component_ids = self.list_components_known().await;
+        component_ids.sort();
+        for component_id in component_ids {
+            if let Ok(Some(metadata)) = self.load_component_metadata(&component_id).await {
+                if metadata.source_uri.as_deref() == Some(uri) {
+                    return Some(component_id);
+                }
+            }
+        }
+        None
+    }
 
+    /// Record `uri` as the source of `component_id` in its metadata.
+    async fn record_component_source(&self, component_id: &str, uri: &str) -> Result<()> {
+        let Some(mut metadata) = self.load_component_metadata(component_id).await? else {
+            return Ok(());
+        };
+        if metadata.source_uri.as_deref() != Some(uri) {
+            metadata.source_uri = Some(uri.to_string());
+            self.storage.write_metadata(&metadata).await?;
+        }
         Ok(())
     }
 
-    /// Save component metadata to disk
-    async fn save_component_metadata(
+    /// Stage a fetched component under `component_id`, compile it, and
+    /// register its tools.
+    async fn install_component(
         &self,
+        uri: &str,
         component_id: &str,
-        tool_metadata: &[ToolMetadata],
-        validation_stamp: ValidationStamp,
-    ) -> Result<()> {
-        let metadata = ComponentMetadata {
-            component_id: component_id.to_string(),
-            tool_schemas: tool_metadata.iter().map(|t| t.schema.clone()).collect(),
-            function_identifiers: tool_metadata.iter().map(|t| t.identifier.clone()).collect(),
-            tool_names: tool_metadata
-                .iter()
-                .map(|t| t.normalized_name.clone())
-                .collect(),
-            validation_stamp,
-            created_at: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs(),
+        resource: DownloadedResource,
+    ) -> Result<ComponentLoadOutcome> {
+        path_guard::validate_component_id(component_id)?;
+        self.check_component_filter(component_id)?;
+        self.check_writable(component_id)?;
+        let _staging = self.staging_lock.read().await;
+        let copied = resource.as_ref() != self.component_path(component_id);
+        let previous = if copied {
+            self.read_installed_files(component_id).await
+        } else {
+            None
+        };
+        let known = self.registry.load_state(component_id).await.is_some();
+        let staged_path = self
+            .stage_component_artifact(component_id, resource)
+            .await?;
+        let outcome = match self
+            .compile_and_register_component(component_id, &staged_path)
+            .await
+        {
+            Ok(outcome) => outcome,
+            Err(error)
+                if matches!(
+                    error.downcast_ref::<ComponentError>(),
+                    Some(ComponentError::ToolCollision { .. })
+                ) =>
+            {
+                self.discard_rejected_install(component_id, copied, previous, known)
+                    .await;
+                return Err(error);
+            }
+            Err(error) => {
+                return Err(error.context(format!(
+                    "Failed to compile component from path: {}. Please ensure the file is a valid WebAssembly component.",
+                    staged_path.display()
+                )))
+            }
         };
 
-        self.storage.write_metadata(&metadata).await?;
+        if let Err(error) = self
+            .record_manifest_entry(&outcome.component_id, uri.trim(), &staged_path)
+            .await
+        {
+            warn!(component_id = %outcome.component_id, %error, "Failed to update component manifest");
+        }
+        if let Err(error) = self
+            .record_component_source(&outcome.component_id, uri.trim())
+            .await
+        {
+            warn!(component_id = %outcome.component_id, %error, "Failed to record component source");
+        }
 
-        info!(component_id = %component_id, "Saved component metadata");
-        Ok(())
+        info!(
+            component_id = %outcome.component_id,
+            status = ?outcome.status,
+            tools = ?outcome.tool_names,
+            "Successfully loaded component"
+        );
+        Ok(outcome)
     }
 
-    /// Load component metadata from disk
-    async fn load_component_metadata(
-        &self,
-        component_id: &str,
-    ) -> Result<Option<ComponentMetadata>> {
-        self.storage.read_metadata(component_id).await
+    /// The wasm file and metadata of an installed component, kept so they
+    /// can be put back if the install replacing them is rejected.
+    async fn read_installed_files(&self, component_id: &str) -> Option<InstalledFiles> {
+        let wasm = tokio::fs::read(self.component_path(component_id))
+            .await
+            .ok()?;
+        let metadata = tokio::fs::read(self.storage.metadata_path(component_id))
+            .await
+            .ok();
+        Some(InstalledFiles { wasm, metadata })
     }
 
-    /// Save precompiled component to disk
-    async fn save_precompiled_component(
+    /// Undo the staging of a component whose tools were rejected as
+    /// colliding: remove the files that were written for it and put back
+    /// the ones of the version it was meant to replace. A file loaded from
+    /// its place in the component directory is left where it is.
+    async fn discard_rejected_install(
         &self,
         component_id: &str,
-        wasm_bytes: &[u8],
-    ) -> Result<()> {
-        let precompiled_data = self
-            .runtime
-            .precompile_component(wasm_bytes)
-            .context("Failed to precompile component")?;
+        copied: bool,
+        previous: Option<InstalledFiles>,
+        known: bool,
+    ) {
+        let removed = if copied {
+            self.storage.remove_component_artifacts(component_id).await
+        } else {
+            self.storage
+                .remove_if_exists(
+                    &self.storage.metadata_path(component_id),
+                    "metadata file",
+                    component_id,
+                )
+                .await
+        };
+        if let Err(error) = removed {
+            warn!(%component_id, %error, "Failed to remove the files of a rejected component");
+        }
+        if let Some(previous) = previous {
+            let restored = async {
+                tokio::fs::write(self.component_path(component_id), &previous.wasm).await?;
+                if let Some(metadata) = &previous.metadata {
+                    tokio::fs::write(self.storage.metadata_path(component_id), metadata).await?;
+                }
+                anyhow::Ok(())
+            }
+            .await;
+            if let Err(error) = restored {
+                warn!(%component_id, %error, "Failed to restore the component a rejected install replaced");
+            }
+        } else if !known {
+            self.registry.discard_unloaded(component_id).await;
+        }
+    }
 
-        self.storage
-            .write_precompiled(component_id, &precompiled_data)
+    /// Register the tools of a component that is only fetched from
+    /// `source_uri` when one of them is first called, so a catalog of
+    /// components can be advertised without downloading any of them.
+    ///
+    /// The component is listed as [`ComponentLoadState::Virtual`] until
+    /// then. The first call loads it from `source_uri` under `component_id`
+    /// as [`Self::load_component`] would, replacing the registered tools with
+    /// the ones the component exports, and then proceeds. If the component
+    /// cannot be fetched or loaded, its tools are unregistered and the call
+    /// fails with [`ComponentError::VirtualLoadFailed`].
+    ///
+    /// `source_uri` must be allowed by
+    /// [`LifecycleBuilder::with_allowed_sources`]. Fails if `component_id`
+    /// is already known.
+    #[instrument(skip(self, tools))]
+    pub async fn register_virtual_component(
+        &self,
+        component_id: &str,
+        source_uri: &str,
+        tools: Vec<ToolMetadata>,
+    ) -> Result<()> {
+        let source_uri = source_uri.trim();
+        path_guard::validate_component_id(component_id)?;
+        self.source_policy.check(source_uri)?;
+        self.check_component_filter(component_id)?;
+        let tools = self
+            .registry
+            .register_virtual(component_id, source_uri, tools, self.tool_collisions)
             .await?;
-
-        info!(component_id = %component_id, "Saved precompiled component");
+        info!(%component_id, source_uri, ?tools, "Registered virtual component");
+        self.events
+            .emit(LifecycleEvent::VirtualComponentRegistered {
+                component_id: component_id.to_string(),
+                source_uri: source_uri.to_string(),
+                tools,
+            });
         Ok(())
     }
 
-    /// Load component from precompiled cache or compile fresh
-    async fn load_component_optimized(
+    /// Fetch and load a virtual component on its first call. A component
+    /// that cannot be loaded is forgotten along with its tools.
+    async fn materialize_virtual_component(
         &self,
-        wasm_path: &Path,
         component_id: &str,
-    ) -> Result<(Component, Vec<u8>)> {
-        let precompiled_path = self.component_precompiled_path(component_id);
-
-        // Try to load from precompiled cache first
-        if precompiled_path.exists() {
-            match unsafe { Component::deserialize_file(self.runtime.as_ref(), &precompiled_path) } {
-                Ok(component) => {
-                    debug!(component_id = %component_id, "Loaded component from precompiled cache");
-                    // Still need the wasm bytes for metadata/validation
-                    let wasm_bytes = tokio::fs::read(wasm_path)
-                        .await
-                        .context("Failed to read wasm file")?;
-                    return Ok((component, wasm_bytes));
-                }
-                Err(e) => {
-                    warn!(%component_id, error = %e, "Failed to load precompiled component, falling back to compilation");
-                }
-            }
+        source_uri: &str,
+    ) -> Result<()> {
+        let _materializing = self.materialize_lock.lock().await;
+        // A concurrent call may have loaded it already
+        if !matches!(
+            self.registry.load_state(component_id).await,
+            Some(ComponentLoadState::Virtual { .. })
+        ) {
+            return Ok(());
         }
 
-        // Fall back to compilation
-        let wasm_bytes = tokio::fs::read(wasm_path)
-            .await
-            .context("Failed to read wasm file")?;
-
-        let component = Component::new(self.runtime.as_ref(), &wasm_bytes)
-            .context("Failed to compile component")?;
-
-        // Save precompiled version for next time (async, don't block on this)
-        if let Err(e) = self
-            .save_precompiled_component(component_id, &wasm_bytes)
-            .await
-        {
-            warn!(%component_id, error = %e, "Failed to save precompiled component");
+        info!(%component_id, source_uri, "Fetching virtual component");
+        let result = async {
+            let (_, resource, _) = self
+                .resolve_component_resource(source_uri, &CancellationToken::new(), None)
+                .await?;
+            self.install_component(source_uri, component_id, resource)
+                .await
         }
+        .await;
+        let Err(error) = result else {
+            return Ok(());
+        };
 
-        debug!(component_id = %component_id, "Compiled component and saved to cache");
-        Ok((component, wasm_bytes))
+        let reason = format!("{error:#}");
+        if self.registry.discard_unloaded(component_id).await {
+            let _ = tokio::fs::remove_file(self.component_path(component_id)).await;
+            warn!(%component_id, source_uri, %reason, "Failed to fetch virtual component");
+            self.events.emit(LifecycleEvent::ComponentDisabled {
+                component_id: component_id.to_string(),
+                reason: reason.clone(),
+            });
+        }
+        Err(ComponentError::VirtualLoadFailed {
+            component_id: component_id.to_string(),
+            source_uri: source_uri.to_string(),
+            reason,
+        }
+        .into())
     }
 
-    async fn get_wasi_state_for_component(
+    async fn record_manifest_entry(
         &self,
         component_id: &str,
-    ) -> Result<(WassetteWasiState<WasiState>, Option<CustomResourceLimiter>)> {
-        let policy_template = self
-            .policy_manager
-            .template_for_component(component_id)
-            .await;
-
-        let wasi_state = policy_template.build()?;
-        let allowed_hosts = policy_template.allowed_hosts.clone();
-        let resource_limiter = wasi_state.resource_limiter.clone();
+        source_uri: &str,
+        wasm_path: &Path,
+    ) -> Result<()> {
+        let entry = ManifestEntry {
+            source_uri: source_uri.to_string(),
+            digest: file_digest(wasm_path).await?,
+            policy_source: self
+                .policy_manager
+                .get_policy_info(component_id)
+                .await
+                .map(|info| info.source_uri),
+            load_order: None,
+        };
+        self.manifest
+            .update(|manifest| manifest.record(component_id, entry))
+            .await
+    }
 
-        let wassette_wasi_state = WassetteWasiState::new(wasi_state, allowed_hosts)?;
-        Ok((wassette_wasi_state, resource_limiter))
+    async fn update_manifest_policy(&self, component_id: &str, policy_source: Option<&str>) {
+        let result = self
+            .manifest
+            .update(|manifest| {
+                if let Some(entry) = manifest.components.get_mut(component_id) {
+                    entry.policy_source = policy_source.map(str::to_string);
+                }
+            })
+            .await;
+        if let Err(error) = result {
+            warn!(%component_id, %error, "Failed to update component manifest");
+        }
     }
 
-    /// Executes a function call on a WebAssembly component
+    /// Remove cached downloads that are referenced neither by a component in
+    /// the component directory nor by the state manifest, after pruning the
+    /// downloads directory as [`Self::prune_downloads`] does. With `dry_run`,
+    /// only reports what would be removed.
     #[instrument(skip(self))]
-    pub async fn execute_component_call(
-        &self,
-        component_id: &str,
-        function_name: &str,
-        parameters: &str,
-    ) -> Result<String> {
-        let start_time = Instant::now();
-
-        debug!(
-            component_id = %component_id,
-            function_name = %function_name,
-            "Starting WebAssembly component execution"
-        );
-
-        let component = self
-            .get_component(component_id)
-            .await
-            .ok_or_else(|| anyhow!("Component not found: {}", component_id))?;
-
-        let (state, resource_limiter) = self.get_wasi_state_for_component(component_id).await?;
+    pub async fn gc_downloads(&self, dry_run: bool) -> Result<DownloadsGcReport> {
+        let pruned = if dry_run {
+            self.storage
+                .download_cache()
+                .prune(self.downloads_retention, true)
+                .await?
+        } else {
+            self.prune_downloads().await?
+        };
 
-        let mut store = Store::new(self.runtime.as_ref(), state);
+        let mut referenced = HashSet::new();
 
-        // Apply memory limits if configured in the policy by setting up a limiter closure
-        // that extracts the resource limiter from the WasiState
-        if resource_limiter.is_some() {
-            store.limiter(|state: &mut WassetteWasiState<WasiState>| {
-                // Extract the resource limiter from the inner state
-                state
-                    .inner
-                    .resource_limiter
-                    .as_mut()
-                    .expect("Resource limiter should be present - checked above")
-            });
+        let mut entries = tokio::fs::read_dir(self.storage.root()).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if component_id_from_path(&path).is_some() {
+                referenced.insert(file_digest(&path).await?);
+            }
         }
 
-        let instantiation_start = Instant::now();
-        let instance = component.instance_pre.instantiate_async(&mut store).await?;
-        let instantiation_duration = instantiation_start.elapsed();
-
-        debug!(
-            component_id = %component_id,
-            instantiation_ms = %instantiation_duration.as_millis(),
-            "Component instance created"
-        );
-
-        // Use the new function identifier lookup instead of dot-splitting
-        let function_id = self
-            .registry
-            .tool_identifier(function_name)
-            .await
-            .ok_or_else(|| anyhow!("Unknown tool name: {}", function_name))?;
+        let manifest = ComponentManifest::load(self.manifest.path()).await?;
+        referenced.extend(manifest.components.into_values().map(|entry| entry.digest));
 
-        let (interface_name, func_name) = (
-            function_id.interface_name.as_deref().unwrap_or(""),
-            &function_id.function_name,
+        let pruned_names: HashSet<String> = pruned
+            .expired
+            .iter()
+            .chain(&pruned.evicted)
+            .cloned()
+            .collect();
+        let mut report = self
+            .storage
+            .download_cache()
+            .gc(&referenced, &pruned_names, dry_run)
+            .await?;
+        report.expired = pruned.expired;
+        report.evicted = pruned.evicted;
+        report.reclaimed_bytes += pruned.reclaimed_bytes;
+        if dry_run {
+            return Ok(report);
+        }
+        info!(
+            removed = report.removed.len(),
+            expired = report.expired.len(),
+            evicted = report.evicted.len(),
+            reclaimed_bytes = report.reclaimed_bytes,
+            "Pruned unreferenced downloads"
         );
+        Ok(report)
+    }
 
-        let func = if !interface_name.is_empty() {
-            let interface_index = instance
-                .get_export_index(&mut store, None, interface_name)
-                .ok_or_else(|| anyhow!("Interface not found: {}", interface_name))?;
-
-            let function_index = instance
-                .get_export_index(&mut store, Some(&interface_index), func_name)
-                .ok_or_else(|| {
-                    anyhow!(
-                        "Function not found in interface: {}.{}",
-                        interface_name,
-                        func_name
-                    )
-                })?;
-
-            instance
-                .get_func(&mut store, function_index)
-                .ok_or_else(|| {
-                    anyhow!(
-                        "Function not found in interface: {}.{}",
-                        interface_name,
-                        func_name
-                    )
-                })?
-        } else {
-            let func_index = instance
-                .get_export_index(&mut store, None, func_name)
-                .ok_or_else(|| anyhow!("Function not found: {}", func_name))?;
-            instance
-                .get_func(&mut store, func_index)
-                .ok_or_else(|| anyhow!("Function not found: {}", func_name))?
-        };
-
-        let params: serde_json::Value = serde_json::from_str(parameters)?;
-        let argument_vals = json_to_vals(&params, &func.params(&store))?;
-
-        let mut results = create_placeholder_results(&func.results(&store));
+    /// Remove the precompiled and metadata files of components whose wasm
+    /// file is gone, and stale temporary files in the downloads directory;
+    /// see [`GcOptions`]. Loads that replace a component's files wait until
+    /// it is done.
+    #[instrument(skip(self))]
+    pub async fn garbage_collect(&self, options: GcOptions) -> Result<GcReport> {
+        let _staging = self.staging_lock.write().await;
+        let report = self.storage.garbage_collect(options).await?;
+        if !options.dry_run && report.files_removed > 0 {
+            info!(
+                files_removed = report.files_removed,
+                bytes_freed = report.bytes_freed,
+                "Removed orphaned files from the component directory"
+            );
+        }
+        Ok(report)
+    }
 
-        let execution_start = Instant::now();
+    /// Remove downloads older than the configured maximum age and evict the
+    /// oldest ones while the downloads directory is over its size budget.
+    /// Downloads an in-progress load is using are left alone. See
+    /// [`LifecycleBuilder::with_downloads_retention`].
+    #[instrument(skip(self))]
+    pub async fn prune_downloads(&self) -> Result<DownloadsGcReport> {
+        let report = self
+            .storage
+            .download_cache()
+            .prune(self.downloads_retention, false)
+            .await?;
+        if !report.expired.is_empty() || !report.evicted.is_empty() {
+            info!(
+                expired = report.expired.len(),
+                evicted = report.evicted.len(),
+                reclaimed_bytes = report.reclaimed_bytes,
+                "Pruned downloads directory"
+            );
+        }
+        Ok(report)
+    }
 
-        // Execute the WASM function and capture any errors
-        let call_result = func
-            .call_async(&mut store, &argument_vals, &mut results)
-            .await;
+    /// Run [`Self::prune_downloads`] every `period` in the background.
+    pub fn spawn_downloads_prune(&self, period: Duration) -> tokio::task::JoinHandle<()> {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            // The first tick completes immediately; the directory was already
+            // pruned when the manager was built.
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(error) = manager.prune_downloads().await {
+                    warn!(%error, "Failed to prune the downloads directory");
+                }
+            }
+        })
+    }
 
-        let execution_duration = execution_start.elapsed();
+    /// Returns the path of the `components.lock.json` state manifest.
+    pub fn manifest_path(&self) -> &Path {
+        self.manifest.path()
+    }
 
-        // If the call failed, check if it was due to a permission denial
-        if let Err(e) = call_result {
-            // Check if there was a permission error recorded during execution
-            if let Some(perm_error) = store.data().get_last_permission_error() {
-                // Return a more informative error with instructions
-                return Err(anyhow!(perm_error.to_user_message(component_id)));
+    /// Re-fetch every component recorded in the manifest at `path` that is
+    /// missing from the component directory or whose artifact no longer
+    /// matches the recorded digest.
+    ///
+    /// Components that are already present are left untouched. Failures are
+    /// collected in the returned [`RestoreReport`] rather than aborting the
+    /// whole restore.
+    #[instrument(skip(self, path), fields(path = %path.as_ref().display()))]
+    pub async fn restore_from_manifest(&self, path: impl AsRef<Path>) -> Result<RestoreReport> {
+        let path = path.as_ref();
+        let manifest = ComponentManifest::load(path).await?;
+        let mut report = RestoreReport::default();
+        let mut unchanged = Vec::new();
+
+        for (component_id, entry) in manifest.components {
+            match self.restore_manifest_entry(&component_id, &entry).await {
+                Ok(true) => report.restored.push(component_id),
+                Ok(false) => {
+                    report.unchanged.push(component_id.clone());
+                    unchanged.push((component_id, entry));
+                }
+                Err(error) => {
+                    warn!(%component_id, %error, "Failed to restore component from manifest");
+                    report.failed.push(RestoreFailure {
+                        component_id,
+                        error: format!("{error:#}"),
+                    });
+                }
             }
-            // Otherwise, return the original WASM execution error
-            return Err(e);
         }
 
-        let result_json = vals_to_json(&results);
-
-        let total_duration = start_time.elapsed();
+        // Carry over entries that did not need a reload when restoring from a
+        // manifest other than the one this manager keeps up to date.
+        if path != self.manifest.path() && !unchanged.is_empty() {
+            self.manifest
+                .update(|manifest| manifest.components.extend(unchanged))
+                .await?;
+        }
 
-        debug!(
-            component_id = %component_id,
-            function_name = %function_name,
-            total_duration_ms = %total_duration.as_millis(),
-            instantiation_ms = %instantiation_duration.as_millis(),
-            execution_ms = %execution_duration.as_millis(),
-            "WebAssembly component execution completed"
+        info!(
+            restored = report.restored.len(),
+            unchanged = report.unchanged.len(),
+            failed = report.failed.len(),
+            "Finished restoring components from manifest"
         );
-
-        if let Some(result_str) = result_json.as_str() {
-            Ok(result_str.to_string())
-        } else {
-            Ok(serde_json::to_string(&result_json)?)
-        }
+        Ok(report)
     }
 
-    /// Load existing components from component directory in the background with bounded parallelism
-    /// Default concurrency is min(num_cpus, 4) if not specified
-    #[instrument(skip(self, notify_fn))]
-    pub async fn load_existing_components_async<F>(
+    /// Returns `true` if the component had to be re-fetched.
+    async fn restore_manifest_entry(
         &self,
-        concurrency: Option<usize>,
-        notify_fn: Option<F>,
-    ) -> Result<()>
-    where
-        F: Fn() + Send + Sync + 'static,
-    {
-        // First phase: Quick metadata-based registry population
-        self.populate_registry_from_metadata().await?;
-
-        let concurrency = concurrency.unwrap_or_else(|| std::cmp::min(num_cpus::get(), 4));
+        component_id: &str,
+        entry: &ManifestEntry,
+    ) -> Result<bool> {
+        path_guard::validate_component_id(component_id)?;
+        let wasm_path = self.component_path(component_id);
+        if wasm_path.is_file() && file_digest(&wasm_path).await? == entry.digest {
+            debug!(%component_id, "Component already present, skipping restore");
+            return Ok(false);
+        }
 
-        info!(
-            "Starting background component loading with concurrency: {}",
-            concurrency
-        );
+        let outcome = self.load_component(&entry.source_uri).await?;
+        if outcome.component_id != component_id {
+            warn!(
+                expected = %component_id,
+                actual = %outcome.component_id,
+                "Restored component resolved to a different ID"
+            );
+        }
 
-        let semaphore = Arc::new(Semaphore::new(concurrency));
-        let mut entries = tokio::fs::read_dir(self.storage.root()).await?;
-        let mut load_futures = Vec::new();
+        let digest = file_digest(&self.component_path(&outcome.component_id)).await?;
+        if digest != entry.digest {
+            warn!(
+                component_id = %outcome.component_id,
+                expected = %entry.digest,
+                actual = %digest,
+                "Restored component digest differs from manifest"
+            );
+        }
 
-        while let Some(entry) = entries.next_entry().await? {
-            let self_clone = self.clone();
-            let semaphore = semaphore.clone();
-            let notify_fn = notify_fn.as_ref().map(std::sync::Arc::new);
-
-            let future = async move {
-                let _permit = semaphore.acquire().await.unwrap();
-
-                match self_clone.load_component_from_entry_optimized(entry).await {
-                    Ok(true) => {
-                        // Component was loaded, notify if callback provided
-                        if let Some(notify) = notify_fn {
-                            notify();
-                        }
-                    }
-                    Ok(false) => {} // No component to load (not a .wasm file)
-                    Err(e) => warn!("Failed to load component: {}", e),
-                }
-            };
-            load_futures.push(future);
+        // The policy file next to the component is used wherever the policy
+        // was attached from; its source is only fetched again without one
+        if let Some(policy_source) = &entry.policy_source {
+            if self.get_policy_info(&outcome.component_id).await.is_some() {
+                debug!(component_id = %outcome.component_id, %policy_source, "Restored policy from its local copy");
+            } else {
+                self.attach_policy(&outcome.component_id, policy_source)
+                    .await
+                    .with_context(|| {
+                        format!("Failed to re-fetch policy from {policy_source}, and no local copy exists")
+                    })?;
+                self.policy_manager
+                    .set_origin(&outcome.component_id, PolicyOrigin::Refetched)
+                    .await;
+            }
         }
 
-        // Wait for all components to load
-        futures::future::join_all(load_futures).await;
-        info!("Background component loading completed");
-        Ok(())
+        Ok(true)
     }
 
-    /// Populate tool registry from cached metadata without compiling components
-    async fn populate_registry_from_metadata(&self) -> Result<()> {
+    /// Write the component directory's state to a zstd-compressed tar archive
+    /// at `path`: every installed component with its metadata and policy,
+    /// and the state manifest. Secrets are only included when requested in
+    /// `options`. Precompiled artifacts are left out; the importing host
+    /// compiles the components itself.
+    #[instrument(skip(self, path), fields(path = %path.as_ref().display()))]
+    pub async fn export_state(
+        &self,
+        path: impl AsRef<Path>,
+        options: StateExportOptions,
+    ) -> Result<StateExportReport> {
+        let mut component_ids = Vec::new();
         let mut entries = tokio::fs::read_dir(self.storage.root()).await?;
-        let mut loaded_count = 0;
-
         while let Some(entry) = entries.next_entry().await? {
-            let entry_path = entry.path();
-            let is_wasm = entry_path
-                .extension()
-                .map(|ext| ext == "wasm")
-                .unwrap_or(false);
-
-            if !is_wasm {
-                continue;
+            component_ids.extend(component_id_from_path(&entry.path()));
+        }
+        component_ids.sort();
+
+        let mut report = StateExportReport::default();
+        let mut digests = std::collections::BTreeMap::new();
+        let mut files = Vec::new();
+        for id in component_ids {
+            let wasm_path = self.component_path(&id);
+            digests.insert(id.clone(), file_digest(&wasm_path).await?);
+
+            let mut component_files = vec![
+                wasm_path,
+                self.storage.metadata_path(&id),
+                self.storage.policy_path(&id),
+                self.storage.policy_metadata_path(&id),
+            ];
+            component_files.extend(
+                PolicyCategory::ALL
+                    .into_iter()
+                    .map(|category| self.policy_manager.fragment_path(&id, category)),
+            );
+            for file in component_files.into_iter().filter(|file| file.exists()) {
+                let Ok(relative) = file.strip_prefix(self.storage.root()) else {
+                    continue;
+                };
+                let name: Vec<_> = relative.iter().filter_map(|part| part.to_str()).collect();
+                files.push((format!("{COMPONENTS_DIR}/{}", name.join("/")), file.clone()));
             }
 
-            let Some(component_id) = entry_path.file_stem().and_then(|s| s.to_str()) else {
-                continue;
+            if options.include_secrets {
+                let secrets_path = self.secrets_manager.get_component_secrets_path(&id);
+                if secrets_path.exists() {
+                    files.push((format!("{SECRETS_DIR}/{id}.yaml"), secrets_path));
+                    report.secrets.push(id.clone());
+                }
+            }
+            report.components.push(id);
+        }
+
+        if self.manifest.path().exists() {
+            files.push((
+                MANIFEST_FILE_NAME.to_string(),
+                self.manifest.path().to_path_buf(),
+            ));
+            report.manifest = true;
+        }
+
+        state_archive::write_archive(path.as_ref(), &StateHeader::new(digests), files).await?;
+        info!(
+            components = report.components.len(),
+            secrets = report.secrets.len(),
+            "Exported state archive"
+        );
+        Ok(report)
+    }
+
+    /// Install the components from a state archive written by
+    /// [`export_state`](Self::export_state) and load them.
+    ///
+    /// Components already installed with the same digest are skipped, and
+    /// components installed with a different digest are reported as conflicts
+    /// and left untouched. State manifest entries are added for the imported
+    /// and unchanged components that the local manifest does not record yet.
+    ///
+    /// The archive must be an allowed source as a `file://` URI. Each
+    /// component is checked as if it were loaded again from the source the
+    /// archive records for it: the source must be allowed, and signatures are
+    /// verified against it. Components that fail, are filtered out, or are
+    /// embedded are reported in `failed`. Metadata and precompiled artifacts
+    /// are never taken from the archive.
+    #[instrument(skip(self, path), fields(path = %path.as_ref().display()))]
+    pub async fn import_state(
+        &self,
+        path: impl AsRef<Path>,
+        options: StateImportOptions,
+    ) -> Result<StateImportReport> {
+        // The components come from the archive, so it must be an allowed
+        // source as much as a component file loaded from the same place
+        let archive_uri = format!("file://{}", std::path::absolute(path.as_ref())?.display());
+        self.source_policy.check(&archive_uri)?;
+
+        let staging = tempfile::tempdir()?;
+        state_archive::unpack_archive(path.as_ref(), staging.path()).await?;
+        let header = StateHeader::load(staging.path()).await?;
+        let staged_components = staging.path().join(COMPONENTS_DIR);
+        let archived_manifest =
+            ComponentManifest::load(staging.path().join(MANIFEST_FILE_NAME)).await?;
+
+        let mut report = StateImportReport::default();
+        let mut manifest_entries = Vec::new();
+        for (id, digest) in header.components {
+            let issue = |reason: &str| StateImportIssue {
+                component_id: id.clone(),
+                reason: reason.to_string(),
             };
 
-            // Try to load cached metadata
-            if let Ok(Some(metadata)) = self.load_component_metadata(component_id).await {
-                // Validate that the component file hasn't changed
-                if ComponentStorage::validate_stamp(&entry_path, &metadata.validation_stamp).await {
-                    let tool_metadata: Vec<ToolMetadata> = metadata
-                        .function_identifiers
-                        .into_iter()
-                        .zip(metadata.tool_schemas)
-                        .zip(metadata.tool_names)
-                        .map(|((identifier, schema), normalized_name)| {
-                            let canonical = schema::canonicalize_output_schema(&schema);
-                            ToolMetadata {
-                                identifier,
-                                schema: canonical,
-                                normalized_name,
-                            }
-                        })
-                        .collect();
+            if path_guard::validate_component_id(&id).is_err() {
+                report.failed.push(issue("invalid component ID"));
+                continue;
+            }
+            if let Err(error) = self
+                .check_component_filter(&id)
+                .and_then(|()| self.check_writable(&id))
+            {
+                report.failed.push(issue(&format!("{error:#}")));
+                continue;
+            }
+            let staged_wasm = staged_components.join(format!("{id}.wasm"));
+            if !staged_wasm.exists() || file_digest(&staged_wasm).await? != digest {
+                report
+                    .failed
+                    .push(issue("component in the archive is missing or corrupt"));
+                continue;
+            }
 
-                    match self
-                        .registry
-                        .register_metadata_if_absent(component_id, tool_metadata)
-                        .await
-                    {
-                        Ok(true) => {
-                            loaded_count += 1;
-                            debug!(component_id = %component_id, "Registered tools from cached metadata");
-                            continue;
-                        }
-                        Ok(false) => {
-                            debug!(component_id = %component_id, "Skipping cached metadata; component already registered");
-                            continue;
-                        }
-                        Err(e) => {
-                            warn!(%component_id, error = %e, "Failed to register tools from metadata");
-                            continue;
+            let target = self.component_path(&id);
+            if target.exists() {
+                if file_digest(&target).await? != digest {
+                    report
+                        .conflicts
+                        .push(issue("a different version is already installed"));
+                    continue;
+                }
+                report.unchanged.push(id.clone());
+            } else {
+                let source_uri = archived_manifest
+                    .components
+                    .get(&id)
+                    .map(|entry| entry.source_uri.as_str());
+                if let Err(error) = self
+                    .check_imported_component(source_uri, &staged_wasm)
+                    .await
+                {
+                    warn!(component_id = %id, %error, "Refused to import component");
+                    report.failed.push(issue(&format!("{error:#}")));
+                    continue;
+                }
+
+                // Metadata and precompiled artifacts are rebuilt here rather
+                // than trusted from the archive: the precompiled artifact the
+                // metadata vouches for is loaded as native code
+                for stale in [
+                    self.storage.metadata_path(&id),
+                    self.component_precompiled_path(&id),
+                ] {
+                    self.storage
+                        .remove_if_exists(&stale, "stale component file", &id)
+                        .await?;
+                }
+                for extension in ["wasm", "policy.yaml", "policy.json", "policy.meta.json"] {
+                    let file_name = format!("{id}.{extension}");
+                    let staged = staged_components.join(&file_name);
+                    if staged.exists() {
+                        tokio::fs::copy(&staged, self.storage.root().join(&file_name))
+                            .await
+                            .with_context(|| format!("Failed to install {file_name}"))?;
+                    }
+                }
+                for category in PolicyCategory::ALL {
+                    let staged = policy_fragments::fragment_path(&staged_components, &id, category);
+                    if staged.exists() {
+                        let target = self.policy_manager.fragment_path(&id, category);
+                        if let Some(parent) = target.parent() {
+                            tokio::fs::create_dir_all(parent).await?;
                         }
+                        tokio::fs::copy(&staged, &target).await.with_context(|| {
+                            format!("Failed to install {category} policy fragment of {id}")
+                        })?;
+                    }
+                }
+
+                if let Err(error) = self.ensure_component_loaded(&id).await {
+                    warn!(component_id = %id, %error, "Failed to load imported component");
+                    report.failed.push(issue(&format!("{error:#}")));
+                    continue;
+                }
+                if let Some(source_uri) = source_uri {
+                    if let Err(error) = self.record_component_source(&id, source_uri).await {
+                        warn!(component_id = %id, %error, "Failed to record component source");
                     }
                 }
+                report.imported.push(id.clone());
             }
 
-            debug!(component_id = %component_id, "No valid cached metadata found, will load component later");
+            if let Some(entry) = archived_manifest.components.get(&id) {
+                manifest_entries.push((id.clone(), entry.clone()));
+            }
+
+            let staged_secrets = staging.path().join(SECRETS_DIR).join(format!("{id}.yaml"));
+            if options.include_secrets && staged_secrets.exists() {
+                let secrets_path = self.secrets_manager.get_component_secrets_path(&id);
+                if secrets_path.exists() {
+                    if tokio::fs::read(&secrets_path).await?
+                        != tokio::fs::read(&staged_secrets).await?
+                    {
+                        report
+                            .conflicts
+                            .push(issue("different secrets are already set"));
+                    }
+                } else {
+                    let content = tokio::fs::read_to_string(&staged_secrets).await?;
+                    let secrets: HashMap<String, String> = serde_yaml::from_str(&content)
+                        .with_context(|| format!("Failed to parse archived secrets for {id}"))?;
+                    let secrets: Vec<_> = secrets.into_iter().collect();
+                    self.secrets_manager
+                        .set_component_secrets(&id, &secrets)
+                        .await?;
+                    report.secrets.push(id.clone());
+                }
+            }
         }
 
-        if loaded_count > 0 {
-            info!(
-                "Registered {} components from cached metadata",
-                loaded_count
-            );
+        if !manifest_entries.is_empty() {
+            self.manifest
+                .update(|manifest| {
+                    for (id, entry) in manifest_entries {
+                        manifest.components.entry(id).or_insert(entry);
+                    }
+                })
+                .await?;
         }
 
-        Ok(())
+        info!(
+            imported = report.imported.len(),
+            unchanged = report.unchanged.len(),
+            conflicts = report.conflicts.len(),
+            failed = report.failed.len(),
+            "Imported state archive"
+        );
+        Ok(report)
     }
 
-    /// Load a component from directory entry with optimization
-    async fn load_component_from_entry_optimized(&self, entry: DirEntry) -> Result<bool> {
-        let entry_path = entry.path();
-        let is_file = entry
-            .metadata()
-            .await
-            .map(|m| m.is_file())
-            .context("unable to read file metadata")?;
-        let is_wasm = entry_path
-            .extension()
-            .map(|ext| ext == "wasm")
-            .unwrap_or(false);
-        if !(is_file && is_wasm) {
-            return Ok(false);
+    /// Apply the checks [`Self::load_component`] applies to a download to
+    /// a component staged from a state archive, which the exporting host
+    /// loaded from `source_uri`: the source must be allowed, and when
+    /// signatures are checked, the staged file must verify against the
+    /// signature published at the source. For `oci://` sources that means
+    /// the signed artifact must contain the staged file.
+    async fn check_imported_component(&self, source_uri: Option<&str>, wasm: &Path) -> Result<()> {
+        if let Some(source_uri) = source_uri {
+            self.source_policy.check(source_uri)?;
+        }
+        if !self.signature_policy.is_enabled() {
+            return Ok(());
         }
 
-        let component_id = entry_path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .map(String::from)
-            .context("wasm file didn't have a valid file name")?;
+        let Some(source_uri) = source_uri else {
+            self.signature_failed(
+                "state archive",
+                anyhow!("the archive records no source to verify the signature against"),
+            )?;
+            return Ok(());
+        };
+        let verification = match source_uri
+            .strip_prefix("oci://")
+            .map(str::parse::<oci_client::Reference>)
+        {
+            Some(Ok(reference)) => {
+                self.signature_policy
+                    .verify_oci_layer(
+                        &self.oci_client,
+                        &reference,
+                        &self.auth_for_uri(source_uri),
+                        &file_digest(wasm).await?,
+                    )
+                    .await
+            }
+            Some(Err(error)) => Err(error.into()),
+            None => {
+                self.signature_policy
+                    .verify_detached(&self.http_client, source_uri, wasm)
+                    .await
+            }
+        };
+        match verification {
+            Ok(identity) => info!(source_uri, identity, "Component signature verified"),
+            Err(error) => {
+                self.signature_failed(source_uri, error)?;
+            }
+        }
+        Ok(())
+    }
 
-        if self.registry.contains_component(&component_id).await {
-            debug!(component_id = %component_id, "Component already loaded in memory");
-            return Ok(false);
+    /// Recompiles a component from its wasm file in the component directory
+    /// and registers its tools again, with the policy and secrets attached
+    /// to it on disk. Unlike unloading and loading it again, this keeps the
+    /// policy, secrets, and metadata files and does not need the URI the
+    /// component was loaded from. The precompiled artifact is reused unless
+    /// the wasm file changed since it was built.
+    ///
+    /// If recompiling fails, the version registered before stays registered
+    /// and the error is returned.
+    #[instrument(skip(self))]
+    pub async fn reload_component(&self, component_id: &str) -> Result<ComponentLoadOutcome> {
+        path_guard::validate_component_id(component_id)?;
+        self.check_component_filter(component_id)?;
+        self.check_writable(component_id)?;
+        let wasm_path = self.component_path(component_id);
+        if !wasm_path.is_file() {
+            return Err(anyhow!("Component not found: {}", component_id));
         }
 
-        let start_time = Instant::now();
-        self.compile_and_register_component(&component_id, &entry_path)
+        let _staging = self.staging_lock.read().await;
+        self.remove_stale_precompiled(component_id, &wasm_path)
+            .await;
+        let outcome = self
+            .compile_and_register_component(component_id, &wasm_path)
             .await
-            .with_context(|| {
-                format!(
-                    "Failed to compile component from path: {}",
-                    entry_path.display()
-                )
-            })?;
+            .with_context(|| format!("Failed to reload component '{component_id}'"))?;
 
-        info!(component_id = %component_id, elapsed = ?start_time.elapsed(), "component loaded");
-        Ok(true)
+        info!(
+            %component_id,
+            status = ?outcome.status,
+            tools = ?outcome.tool_names,
+            "Reloaded component"
+        );
+        Ok(outcome)
     }
 
-    // Granular permission system methods
-}
-
-#[cfg(test)]
-mod docker_cred_tests {
-    use super::*;
-    use std::fs::{self, File};
-    use std::io::Write;
-    use std::path::PathBuf;
+    /// Check the file at `path` against `stamp`: by content hash, unless
+    /// strict validation is off.
+    async fn check_stamp(&self, path: &Path, stamp: &ValidationStamp) -> StampValidation {
+        ComponentStorage::validate_stamp(path, stamp, !self.strict_validation).await
+    }
 
-    fn write_config(dir: &PathBuf, content: &str) {
-        let mut cfg = dir.clone();
-        cfg.push("config.json");
-        let mut f = File::create(&cfg).expect("create config");
-        f.write_all(content.as_bytes()).expect("write config");
+    /// Remove the precompiled artifact of a component if its wasm file
+    /// changed since the artifact was built.
+    async fn remove_stale_precompiled(&self, component_id: &str, wasm_path: &Path) {
+        let precompiled_path = self.component_precompiled_path(component_id);
+        if !precompiled_path.exists() {
+            return;
+        }
+        let fresh = match self.load_component_metadata(component_id).await {
+            Ok(Some(metadata)) => self
+                .check_stamp(wasm_path, &metadata.validation_stamp)
+                .await
+                .is_valid(),
+            _ => false,
+        };
+        if fresh {
+            return;
+        }
+        debug!(%component_id, "Removing precompiled component built from an older file");
+        if let Err(error) = tokio::fs::remove_file(&precompiled_path).await {
+            warn!(%component_id, %error, "Failed to remove stale precompiled component");
+        }
     }
 
-    #[test]
-    fn cred_helper_is_used_when_present() {
-        let tmp = std::env::temp_dir().join(format!("wassette-test-cred-{}-{}", std::process::id(), std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_millis()));
-        let _ = fs::create_dir_all(&tmp);
+    /// Evicts a loaded component from memory, keeping its files on disk.
+    /// Unlike [`Self::unload_component`], its wasm file, precompiled
+    /// artifact, policy, and metadata are kept, and its tools stay listed.
+    /// The component is compiled again, from its precompiled artifact if
+    /// this engine built it, when one of its tools is called or
+    /// [`Self::ensure_component_loaded`] is called.
+    ///
+    /// Returns `false` if the component was not loaded. Fails for
+    /// components without a wasm file in the component directory, which
+    /// could not be loaded again.
+    #[instrument(skip(self))]
+    pub async fn deactivate_component(&self, id: &str) -> Result<bool> {
+        path_guard::validate_component_id(id)?;
+        self.check_writable(id)?;
+        if !self.component_path(id).is_file() {
+            bail!("Component not found: {}", id);
+        }
 
-        // config points to a helper named `myhelper`
-        let config = r#"{ "credsStore": "myhelper" }"#;
-        write_config(&tmp, config);
+        let deactivated = {
+            let _swap = self.swap_lock.write().await;
+            self.registry.deactivate_component(id).await
+        };
+        if deactivated {
+            info!(component_id = %id, "Component deactivated");
+        }
+        Ok(deactivated)
+    }
 
-        // create a fake docker-credential-myhelper.cmd that prints JSON
-        let helper_path = tmp.join("docker-credential-myhelper.cmd");
-        let mut hf = File::create(&helper_path).expect("create helper");
-        // Windows-friendly command script: prints JSON to stdout
-        hf.write_all(b"@echo off\r\necho {\"Username\":\"u_test\",\"Secret\":\"s_test\"}\r\n").expect("write helper");
+    /// Unloads the component with the specified id. This removes the component from the runtime
+    /// and removes all associated files from disk, making it the reverse operation of load_component.
+    /// This function fails if any files cannot be removed (except when they don't exist).
+    ///
+    /// The component's `on-unload` hook runs first, see
+    /// [`Self::unload_component_with_options`].
+    pub async fn unload_component(&self, id: &str) -> Result<()> {
+        self.unload_component_with_options(id, UnloadOptions::default())
+            .await
+            .map(|_| ())
+    }
 
-        // Ensure helper is found by PATH and DOCKER_CONFIG points to tmp
-        let old_path = std::env::var_os("PATH");
-        let mut path_val = tmp.clone().into_os_string();
-        if let Some(p) = old_path.clone() {
-            path_val.push(";");
-            path_val.push(p);
+    /// [`Self::unload_component`], reporting the result of the component's
+    /// `on-unload` hook. The hook runs before any file is removed, under the
+    /// component's policy; a failing hook is recorded as a warning and does
+    /// not stop the unload. With [`UnloadOptions::skip_hooks`] the hook is
+    /// not run.
+    #[instrument(skip(self))]
+    pub async fn unload_component_with_options(
+        &self,
+        id: &str,
+        options: UnloadOptions,
+    ) -> Result<ComponentUnloadOutcome> {
+        debug!("Unloading component and removing files from disk");
+        path_guard::validate_component_id(id)?;
+        self.check_writable(id)?;
+
+        let mut outcome = ComponentUnloadOutcome {
+            component_id: id.to_string(),
+            ..Default::default()
+        };
+        if options.skip_hooks {
+            info!(component_id = %id, "Skipping the on-unload hook");
+        } else {
+            outcome.on_unload = self.run_unload_hook(id).await;
+            if let Some(error) = outcome
+                .on_unload
+                .as_ref()
+                .and_then(|report| report.error.as_ref())
+            {
+                outcome.warnings.push(format!(
+                    "on-unload hook of component '{id}' failed: {error}"
+                ));
+            }
         }
-        std::env::set_var("PATH", &path_val);
-        std::env::set_var("DOCKER_CONFIG", &tmp);
 
-        let auth = LifecycleManager::try_read_docker_config_auth("example.com");
+        // Remove files first, then clean up memory on success
+        self.storage.remove_component_artifacts(id).await?;
 
-        // cleanup
-        let _ = fs::remove_file(&helper_path);
-        let _ = fs::remove_file(tmp.join("config.json"));
-        let _ = fs::remove_dir(&tmp);
-        if let Some(p) = old_path {
-            std::env::set_var("PATH", p);
+        self.policy_manager.remove_policy_files(id).await?;
+        self.policy_manager.remove_history(id).await?;
+
+        // Only cleanup memory after all files are successfully removed
+        {
+            let _swap = self.swap_lock.write().await;
+            self.registry.remove_component(id).await;
+            self.policy_manager.cleanup(id).await;
+        }
+        self.scratch.remove(id).await;
+        self.warmup_reports.write().await.remove(id);
+        if let Err(error) = self.usage.remove(id).await {
+            warn!(component_id = %id, %error, "Failed to update usage file");
+        }
+        self.denials.remove(id);
+        if let Err(error) = self.hidden.set(id, false).await {
+            warn!(component_id = %id, %error, "Failed to update visibility file");
+        }
+        self.instance_pool.invalidate(id);
+        self.events.emit(LifecycleEvent::ComponentUnloaded {
+            component_id: id.to_string(),
+        });
+
+        if let Err(error) = self
+            .manifest
+            .update(|manifest| {
+                manifest.components.remove(id);
+            })
+            .await
+        {
+            warn!(component_id = %id, %error, "Failed to update component manifest");
         }
 
-        match auth {
-            Some(oci_client::secrets::RegistryAuth::Basic(u, p)) => {
-                assert_eq!(u, "u_test");
-                assert_eq!(p, "s_test");
-            }
-            other => panic!("expected Basic auth, got: {:?}", other),
+        info!(component_id = %id, "Component unloaded successfully");
+        Ok(outcome)
+    }
+
+    /// Returns the component ID for a given tool name.
+    /// If there are multiple components with the same tool name, returns an error.
+    #[instrument(skip(self))]
+    pub async fn get_component_id_for_tool(&self, tool_name: &str) -> Result<String> {
+        let tool_infos = self
+            .registry
+            .tool_infos(tool_name)
+            .await
+            .context("Tool not found")?;
+
+        if tool_infos.len() > 1 {
+            bail!(
+                "Multiple components found for tool '{}': {}",
+                tool_name,
+                tool_infos
+                    .iter()
+                    .map(|info| info.component_id.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
         }
+
+        Ok(tool_infos[0].component_id.clone())
     }
 
-    #[test]
-    fn auths_base64_is_used_when_allowed() {
-        let tmp = std::env::temp_dir().join(format!("wassette-test-auths-{}-{}", std::process::id(), std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_millis()));
-        let _ = fs::create_dir_all(&tmp);
+    /// Lists all available tools across all components, ordered by component
+    /// ID and then tool name.
+    ///
+    /// Schemas are shared with the registry; serialize them directly instead
+    /// of cloning the underlying [`Value`]s.
+    #[instrument(skip(self))]
+    pub async fn list_tools(&self) -> Vec<Arc<Value>> {
+        self.registry.list_tools().await
+    }
 
-        let encoded = base64::engine::general_purpose::STANDARD.encode("user_x:pass_x");
-        let config = format!(r#"{{ "auths": {{ "example.com": {{ "auth": "{}" }} }} }}"#, encoded);
-        write_config(&tmp, &config);
+    /// Like [`Self::list_tools`], with each schema paired with the ID of the
+    /// component exporting the tool. The same tool keeps handing out the same
+    /// [`Arc`] until its component is reloaded or unloaded, so callers can
+    /// cache what they derive from a schema by its pointer.
+    #[instrument(skip(self))]
+    pub async fn list_component_tools(&self) -> Vec<(String, Arc<Value>)> {
+        self.registry.component_tools().await
+    }
 
-        let old = std::env::var_os("WASSETTE_ALLOW_INSECURE_DOCKER_AUTH");
-        std::env::set_var("WASSETTE_ALLOW_INSECURE_DOCKER_AUTH", "1");
-        std::env::set_var("DOCKER_CONFIG", &tmp);
+    /// Counts the loaded and known components and the registered tools,
+    /// without reading the component directory.
+    pub async fn component_counts(&self) -> ComponentCounts {
+        self.registry.counts().await
+    }
 
-        let auth = LifecycleManager::try_read_docker_config_auth("example.com");
+    /// How far [`Self::load_existing_components_async`] has got.
+    pub fn background_load_status(&self) -> BackgroundLoadStatus {
+        self.background_load.lock().unwrap().clone()
+    }
 
-        // cleanup
-        let _ = fs::remove_file(tmp.join("config.json"));
-        let _ = fs::remove_dir(&tmp);
-        if let Some(v) = old {
-            std::env::set_var("WASSETTE_ALLOW_INSECURE_DOCKER_AUTH", v);
-        } else {
-            std::env::remove_var("WASSETTE_ALLOW_INSECURE_DOCKER_AUTH");
+    /// Whether the first pass of [`Self::load_existing_components_async`] has
+    /// finished, with the components loaded so far and those that failed.
+    pub async fn loading_status(&self) -> LoadStatus {
+        let counts = self.registry.counts().await;
+        LoadStatus {
+            ready: self.background_load_status().completed,
+            loaded: counts.loaded,
+            known: counts.known,
+            failures: self.registry.load_failures().await,
+            error: None,
         }
+    }
+
+    /// Lists the names of all available tools, sorted, without their schemas
+    #[instrument(skip(self))]
+    pub async fn list_tool_names(&self) -> Vec<String> {
+        self.registry.list_tool_names().await
+    }
+
+    /// Returns the schema for a specific tool owned by a component, if available
+    #[instrument(skip(self))]
+    pub async fn get_tool_schema_for_component(
+        &self,
+        component_id: &str,
+        tool_name: &str,
+    ) -> Option<Arc<Value>> {
+        let tool_infos = self.registry.tool_infos(tool_name).await?;
+        tool_infos
+            .into_iter()
+            .find(|info| info.component_id == component_id)
+            .map(|info| info.schema)
+    }
+
+    /// Returns the result of the health probe run when the component was
+    /// last compiled. Returns `None` for components without a probe and for
+    /// components that are not loaded.
+    pub async fn get_component_health(&self, component_id: &str) -> Option<HealthReport> {
+        self.registry.get_component(component_id).await?.health
+    }
+
+    /// Returns the timing of the last warm-up of a component. Returns `None`
+    /// if the component has no warm-up configured or it has not finished yet.
+    pub async fn get_warmup_report(&self, component_id: &str) -> Option<WarmupReport> {
+        self.warmup_reports.read().await.get(component_id).cloned()
+    }
+
+    /// Returns when the component and each of its tools were last called.
+    /// Returns `None` if none of its tools have been called since it was loaded.
+    pub fn get_component_usage(&self, component_id: &str) -> Option<ComponentUsage> {
+        self.usage.get(component_id)
+    }
+
+    /// Returns the usage of every component that has been called, keyed by component ID.
+    pub fn component_usage(&self) -> std::collections::BTreeMap<String, ComponentUsage> {
+        self.usage.snapshot()
+    }
+
+    /// Returns how often the component was denied a permission since it was
+    /// loaded, by permission type, and its most recent denials. Reloading
+    /// the component keeps the counts; unloading it resets them.
+    pub fn permission_denials(&self, component_id: &str) -> PermissionDenials {
+        self.denials.get(component_id)
+    }
+
+    /// Returns the permission denials of every component that has been
+    /// denied something, keyed by component ID.
+    pub fn all_permission_denials(&self) -> std::collections::BTreeMap<String, PermissionDenials> {
+        self.denials.snapshot()
+    }
+
+    /// Returns how often the component's tools were called, how many of the
+    /// calls failed, and how long they took. Returns `None` if none of its
+    /// tools have been called since it was loaded.
+    pub fn get_component_stats(&self, component_id: &str) -> Option<ComponentStats> {
+        self.metrics.get(component_id)
+    }
+
+    /// Returns the execution metrics of every component that has been
+    /// called, keyed by component ID.
+    pub fn get_all_stats(&self) -> std::collections::BTreeMap<String, ComponentStats> {
+        self.metrics.snapshot()
+    }
+
+    /// Returns the execution metrics in the Prometheus text exposition
+    /// format: tool calls by outcome and their durations, permission
+    /// denials, component load durations, the number of loaded components,
+    /// and the bytes downloaded from OCI registries.
+    pub async fn prometheus_metrics(&self) -> String {
+        let mut out = String::new();
+        self.metrics.encode_prometheus(&mut out);
+        let name = "wassette_active_components";
+        prometheus::write_header(
+            &mut out,
+            name,
+            "gauge",
+            "Components compiled and ready to be called.",
+        );
+        let active = self.list_components().await.len();
+        prometheus::write_sample(&mut out, name, &[], active as u64);
+        out
+    }
+
+    /// Returns the registry changes numbered above `seq`, oldest first:
+    /// loads, replacements, unloads, components being disabled, hidden, or
+    /// shown, and policy and permission changes. Only the last
+    /// [`CHANGES_RETAINED`] changes are kept, so a caller whose `seq` is
+    /// older than the first change returned has missed some.
+    pub fn changes_since(&self, seq: u64) -> Vec<ChangeEvent> {
+        self.events.changes().since(seq)
+    }
+
+    /// Returns the sequence number of the most recent registry change, 0 if
+    /// there was none.
+    pub fn last_change_seq(&self) -> u64 {
+        self.events.changes().last_seq()
+    }
+
+    /// Returns the limits the arguments of a call are checked against.
+    pub fn parameter_limits(&self) -> ParameterLimits {
+        self.parameter_limits
+    }
+
+    /// Writes pending usage updates and execution metrics to the usage
+    /// file. Updates are otherwise written at most every 30 seconds, so call
+    /// this before shutting down.
+    pub async fn flush_usage(&self) -> Result<()> {
+        self.usage.flush().await
+    }
+
+    /// Returns the requested component. Returns `None` if the component is not found.
+    #[instrument(skip(self))]
+    pub async fn get_component(&self, component_id: &str) -> Option<ComponentInstance> {
+        self.registry.get_component(component_id).await
+    }
+
+    /// Lists all loaded components by their IDs
+    #[instrument(skip(self))]
+    pub async fn list_components(&self) -> Vec<String> {
+        self.registry.list_components().await
+    }
+
+    /// Lists all known components by ID (union of loaded components and any
+    /// `*.wasm` files present in the component directory). Does not compile components.
+    #[instrument(skip(self))]
+    pub async fn list_components_known(&self) -> Vec<String> {
+        let loaded = self.registry.list_components().await;
+        let mut set: HashSet<String> = loaded.into_iter().collect();
+        set.extend(self.registry.virtual_components().await);
+
+        if let Ok(entries) = std::fs::read_dir(self.storage.root()) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+
+                // 1) Detect .wasm files
+                if let Some(component_id) = component_id_from_path(&path) {
+                    set.insert(component_id);
+                    continue;
+                }
+
+                // 2) Detect metadata files ("<id>.metadata.json")
+                if let Some(fname) = path.file_name().and_then(|s| s.to_str()) {
+                    if fname.ends_with(&format!(".{METADATA_EXT}")) {
+                        if let Ok(content) = std::fs::read_to_string(&path) {
+                            if let Ok(meta) = serde_json::from_str::<ComponentMetadata>(&content) {
+                                set.insert(meta.component_id);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut v: Vec<String> = set
+            .into_iter()
+            .filter(|id| self.component_filter.allows(id))
+            .collect();
+        v.sort();
+        v
+    }
+
+    /// Returns where a component is in the loading process, and why it is not
+    /// loaded if its last load failed. A component that is only present as a
+    /// file in the component directory is [`ComponentLoadState::Discovered`].
+    /// Returns `None` for unknown components.
+    #[instrument(skip(self))]
+    pub async fn get_component_load_state(&self, component_id: &str) -> Option<ComponentLoadState> {
+        if let Some(state) = self.registry.load_state(component_id).await {
+            return Some(state);
+        }
+        self.component_path(component_id)
+            .is_file()
+            .then_some(ComponentLoadState::Discovered)
+    }
+
+    /// Returns how the tool schemas served for a component from cached
+    /// metadata differed from the compiled component, if they did the last
+    /// time it was compiled after being registered from metadata. Kept until
+    /// the component is unloaded.
+    pub async fn get_component_stale_schema(&self, component_id: &str) -> Option<StaleSchema> {
+        self.registry.stale_schema(component_id).await
+    }
+
+    /// Gets the schema for a specific component
+    #[instrument(skip(self))]
+    pub async fn get_component_schema(&self, component_id: &str) -> Option<Value> {
+        // Prefer live component schema if loaded
+        if let Some(component_instance) = self.get_component(component_id).await {
+            return Some(
+                if let Some(ref package_docs) = component_instance.package_docs {
+                    component_exports_to_json_schema_with_docs(
+                        &component_instance.component,
+                        self.runtime.as_ref(),
+                        true,
+                        package_docs,
+                    )
+                } else {
+                    component_exports_to_json_schema(
+                        &component_instance.component,
+                        self.runtime.as_ref(),
+                        true,
+                    )
+                },
+            );
+        }
+
+        // A virtual component is described by the tools it was registered with
+        if let Some(ComponentLoadState::Virtual { .. }) =
+            self.registry.load_state(component_id).await
+        {
+            let tools = self.registry.component_tool_schemas(component_id).await;
+            return Some(serde_json::json!({
+                "tools": tools
+                    .iter()
+                    .map(|schema| schema::canonicalize_output_schema(schema))
+                    .collect::<Vec<_>>()
+            }));
+        }
+
+        // Fallback to metadata-based schema without compiling the component
+        match self.load_component_metadata(component_id).await {
+            Ok(Some(metadata)) => {
+                let tools: Vec<Value> = metadata
+                    .tool_schemas
+                    .into_iter()
+                    .map(|schema| schema::canonicalize_output_schema(&schema))
+                    .collect();
+                Some(serde_json::json!({
+                    "tools": tools
+                }))
+            }
+            _ => None,
+        }
+    }
+
+    /// Gets the package documentation of a component rendered as Markdown
+    #[instrument(skip(self))]
+    pub async fn get_component_docs(&self, component_id: &str) -> Option<String> {
+        // Prefer live component docs if loaded
+        if let Some(component_instance) = self.get_component(component_id).await {
+            return component_instance
+                .package_docs
+                .as_ref()
+                .and_then(package_docs_to_markdown);
+        }
+
+        // Fallback to the docs saved in the component metadata
+        match self.load_component_metadata(component_id).await {
+            Ok(Some(metadata)) => metadata.docs,
+            _ => None,
+        }
+    }
+
+    fn component_path(&self, component_id: &str) -> PathBuf {
+        self.storage.component_path(component_id)
+    }
+
+    /// Get the path to precompiled component file
+    fn component_precompiled_path(&self, component_id: &str) -> PathBuf {
+        self.storage.precompiled_path(component_id)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn get_component_policy_path(&self, component_id: &str) -> PathBuf {
+        self.policy_manager.policy_path(component_id)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn get_component_metadata_path(&self, component_id: &str) -> PathBuf {
+        self.policy_manager.metadata_path(component_id)
+    }
+
+    /// Attach a policy to a component by URI.
+    pub async fn attach_policy(&self, component_id: &str, policy_uri: &str) -> Result<()> {
+        self.attach_policy_with_cancellation(component_id, policy_uri, &CancellationToken::new())
+            .await
+    }
+
+    /// [`Self::attach_policy`], abandoned when `cancellation` is triggered
+    /// while the policy is downloaded. A cancelled attach fails with
+    /// [`DownloadCancelled`] and leaves the current policy in place.
+    pub async fn attach_policy_with_cancellation(
+        &self,
+        component_id: &str,
+        policy_uri: &str,
+        cancellation: &CancellationToken,
+    ) -> Result<()> {
+        self.check_writable(component_id)?;
+        if !self.registry.contains_component(component_id).await {
+            return Err(anyhow!("Component not found: {}", component_id));
+        }
+        self.policy_manager
+            .attach_policy(component_id, policy_uri, cancellation)
+            .await?;
+        self.update_manifest_policy(component_id, Some(policy_uri))
+            .await;
+        self.instance_pool.invalidate(component_id);
+        self.events.emit(LifecycleEvent::PolicyAttached {
+            component_id: component_id.to_string(),
+            source_uri: policy_uri.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Detach any policy associated with the given component.
+    pub async fn detach_policy(&self, component_id: &str) -> Result<()> {
+        self.check_writable(component_id)?;
+        self.policy_manager.detach_policy(component_id).await?;
+        self.update_manifest_policy(component_id, None).await;
+        self.instance_pool.invalidate(component_id);
+        self.events.emit(LifecycleEvent::PolicyDetached {
+            component_id: component_id.to_string(),
+        });
+        Ok(())
+    }
+
+    /// The stored revisions of a component's policy, oldest first. See
+    /// [`LifecycleBuilder::with_policy_history_limit`].
+    pub async fn policy_history(&self, component_id: &str) -> Result<Vec<PolicyRevision>> {
+        path_guard::validate_component_id(component_id)?;
+        self.policy_manager.policy_history(component_id).await
+    }
+
+    /// Roll a component's policy back to a stored revision. The policy file
+    /// is replaced atomically and the restored policy applies to the next
+    /// call; the rollback is itself recorded as a new revision.
+    #[instrument(skip(self))]
+    pub async fn restore_policy_revision(&self, component_id: &str, revision: u64) -> Result<()> {
+        self.check_writable(component_id)?;
+        if !self.registry.contains_component(component_id).await {
+            return Err(anyhow!("Component not found: {}", component_id));
+        }
+        self.policy_manager
+            .restore_policy_revision(component_id, revision)
+            .await?;
+        self.instance_pool.invalidate(component_id);
+        self.events.emit(LifecycleEvent::PolicyRolledBack {
+            component_id: component_id.to_string(),
+            revision,
+        });
+        Ok(())
+    }
+
+    /// Retrieve policy metadata for a component if one is attached.
+    pub async fn get_policy_info(&self, component_id: &str) -> Option<PolicyInfo> {
+        self.policy_manager.get_policy_info(component_id).await
+    }
+
+    /// Check whether the component's network policy would allow a `method`
+    /// HTTP request to `url`, without making the request.
+    pub async fn check_network_access(
+        &self,
+        component_id: &str,
+        method: &str,
+        url: &str,
+    ) -> Result<NetworkAccessCheck> {
+        if !self.registry.contains_component(component_id).await {
+            return Err(anyhow!("Component not found: {}", component_id));
+        }
+        let template = self
+            .policy_manager
+            .template_for_component(component_id)
+            .await;
+        http::check_network_access(
+            component_id,
+            &template.allowed_hosts,
+            &template.denied_networks,
+            method,
+            url,
+        )
+    }
+
+    /// Whether policies may set `environment.inheritAll`. See
+    /// [`LifecycleBuilder::with_env_inherit`].
+    pub fn allows_env_inherit(&self) -> bool {
+        self.policy_manager.allows_env_inherit()
+    }
+
+    /// Grant a specific permission rule to a component.
+    #[instrument(skip(self))]
+    pub async fn grant_permission(
+        &self,
+        component_id: &str,
+        permission_type: &str,
+        details: &serde_json::Value,
+    ) -> Result<()> {
+        self.check_writable(component_id)?;
+        if !self.registry.contains_component(component_id).await {
+            return Err(anyhow!("Component not found: {}", component_id));
+        }
+        self.policy_manager
+            .grant_permission(component_id, permission_type, details)
+            .await?;
+        self.instance_pool.invalidate(component_id);
+        self.events.emit(LifecycleEvent::PermissionGranted {
+            component_id: component_id.to_string(),
+            permission_type: permission_type.to_string(),
+            details: details.clone(),
+        });
+        Ok(())
+    }
+
+    /// Grant a component a memory limit, given in `details` as `memory` or
+    /// `resources.limits.memory`: a quantity such as `512Mi` or `2G`, or a
+    /// whole number of megabytes. Limits that do not parse, or that exceed
+    /// the share of the host's RAM set with
+    /// [`LifecycleBuilder::with_max_memory_grant_fraction`], are refused.
+    /// Returns the limit in bytes.
+    #[instrument(skip(self))]
+    pub async fn grant_memory_permission(
+        &self,
+        component_id: &str,
+        details: &serde_json::Value,
+    ) -> Result<u64> {
+        let memory = policy_internal::resource_limit(details, "memory")
+            .and_then(policy_internal::memory_limit)
+            .ok_or_else(|| anyhow!("Missing 'memory' field for memory permission"))?;
+        self.grant_permission(component_id, "resource", details)
+            .await?;
+        memory.to_bytes()
+    }
+
+    /// Grant network access to several hosts with a single policy update.
+    /// See [`HostGrantStatus`] for how hosts that are already allowed are
+    /// reported.
+    #[instrument(skip(self))]
+    pub async fn grant_network_hosts(
+        &self,
+        component_id: &str,
+        hosts: &[String],
+    ) -> Result<Vec<HostGrant>> {
+        self.check_writable(component_id)?;
+        if !self.registry.contains_component(component_id).await {
+            return Err(anyhow!("Component not found: {}", component_id));
+        }
+        let results = self
+            .policy_manager
+            .grant_network_hosts(component_id, hosts)
+            .await?;
+        self.instance_pool.invalidate(component_id);
+        for result in &results {
+            if result.status == HostGrantStatus::Granted {
+                self.events.emit(LifecycleEvent::PermissionGranted {
+                    component_id: component_id.to_string(),
+                    permission_type: "network".to_string(),
+                    details: serde_json::json!({ "host": result.host }),
+                });
+            }
+        }
+        Ok(results)
+    }
+
+    /// Revoke a specific permission rule from a component.
+    #[instrument(skip(self))]
+    pub async fn revoke_permission(
+        &self,
+        component_id: &str,
+        permission_type: &str,
+        details: &serde_json::Value,
+    ) -> Result<()> {
+        self.check_writable(component_id)?;
+        if !self.registry.contains_component(component_id).await {
+            return Err(anyhow!("Component not found: {}", component_id));
+        }
+        self.policy_manager
+            .revoke_permission(component_id, permission_type, details)
+            .await?;
+        self.instance_pool.invalidate(component_id);
+        self.events.emit(LifecycleEvent::PermissionRevoked {
+            component_id: component_id.to_string(),
+            permission_type: permission_type.to_string(),
+            details: details.clone(),
+        });
+        Ok(())
+    }
+
+    /// Reset all permissions for a component to defaults.
+    #[instrument(skip(self))]
+    pub async fn reset_permission(&self, component_id: &str) -> Result<()> {
+        self.check_writable(component_id)?;
+        if !self.registry.contains_component(component_id).await {
+            return Err(anyhow!("Component not found: {}", component_id));
+        }
+        self.policy_manager.reset_permission(component_id).await?;
+        self.update_manifest_policy(component_id, None).await;
+        self.instance_pool.invalidate(component_id);
+        self.events.emit(LifecycleEvent::PolicyDetached {
+            component_id: component_id.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Revoke storage permission for a specific URI.
+    #[instrument(skip(self))]
+    pub async fn revoke_storage_permission_by_uri(
+        &self,
+        component_id: &str,
+        uri: &str,
+    ) -> Result<()> {
+        self.check_writable(component_id)?;
+        if !self.registry.contains_component(component_id).await {
+            return Err(anyhow!("Component not found: {}", component_id));
+        }
+        self.policy_manager
+            .revoke_storage_permission_by_uri(component_id, uri)
+            .await?;
+        self.instance_pool.invalidate(component_id);
+        self.events.emit(LifecycleEvent::PermissionRevoked {
+            component_id: component_id.to_string(),
+            permission_type: "storage".to_string(),
+            details: serde_json::json!({ "uri": uri }),
+        });
+        Ok(())
+    }
+
+    /// Returns the component directory root on disk.
+    pub fn component_root(&self) -> &Path {
+        self.storage.root()
+    }
+
+    /// Ensure a specific component is loaded (compiled and instantiated) by its ID.
+    /// If it's already loaded, this is a no-op. If the wasm file is not present in
+    /// the component directory, an error is returned.
+    #[instrument(skip(self))]
+    pub async fn ensure_component_loaded(&self, component_id: &str) -> Result<()> {
+        path_guard::validate_component_id(component_id)?;
+        self.check_component_filter(component_id)?;
+        if self.registry.contains_component(component_id).await {
+            return Ok(());
+        }
+        let _compiling = self.compiles_in_flight.lock(component_id).await;
+        if self.registry.contains_component(component_id).await {
+            return Ok(());
+        }
+
+        let entry_path = self.component_path(component_id);
+        if !entry_path.is_file() {
+            bail!("Component not found: {}", component_id);
+        }
+
+        // Don't recompile a file that was just seen to fail; a new file clears the failure
+        if let Some((reason, stamp)) = self.registry.recorded_failure(component_id).await {
+            if self.check_stamp(&entry_path, &stamp).await.is_valid() {
+                return Err(ComponentError::LoadFailed {
+                    component_id: component_id.to_string(),
+                    reason,
+                }
+                .into());
+            }
+        }
+
+        self.compile_and_register_component(component_id, &entry_path)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to compile component from path: {}",
+                    entry_path.display()
+                )
+            })?;
+
+        Ok(())
+    }
+
+    /// Compare the wasm file of every registered component with the file its
+    /// tools were registered from. A component whose file was replaced
+    /// behind the manager's back is marked [`ComponentLoadState::Drifted`]
+    /// and announced with [`LifecycleEvent::ComponentDrifted`]; its calls
+    /// are refused until it is loaded again. With
+    /// [`LifecycleBuilder::with_auto_reload_on_drift`] it is reloaded from
+    /// the new file right away. Removed files are left to unloading.
+    ///
+    /// Returns the components that are drifted after the check.
+    #[instrument(skip(self))]
+    pub async fn check_drift(&self) -> Vec<String> {
+        // Held through the reloads, so a load of the same component cannot
+        // slip in between the check and the reload
+        let _staging = self.staging_lock.write().await;
+        let drifted = self.mark_drifted_components().await;
+        if self.auto_reload_on_drift {
+            for component_id in drifted {
+                let path = self.component_path(&component_id);
+                match self
+                    .compile_and_register_component(&component_id, &path)
+                    .await
+                {
+                    Ok(_) => info!(%component_id, "Reloaded component after its file changed"),
+                    Err(error) => {
+                        warn!(%component_id, error = %format!("{error:#}"), "Failed to reload drifted component")
+                    }
+                }
+            }
+        }
+        self.registry.drifted_components().await
+    }
+
+    /// Mark and announce the components whose file changed since they were
+    /// registered. Returns the components newly marked.
+    async fn mark_drifted_components(&self) -> Vec<String> {
+        let mut drifted = Vec::new();
+        for (component_id, stamp) in self.registry.registered_stamps().await {
+            let Some(reason) = self.file_drift(&component_id, &stamp).await else {
+                continue;
+            };
+            if !self
+                .registry
+                .mark_drifted(&component_id, &stamp, reason.clone())
+                .await
+            {
+                continue;
+            }
+
+            warn!(%component_id, %reason, "Component file changed on disk");
+            self.events.emit(LifecycleEvent::ComponentDrifted {
+                component_id: component_id.clone(),
+                reason,
+            });
+            drifted.push(component_id);
+        }
+        drifted
+    }
+
+    /// How the wasm file of a component differs from the one `stamp` was
+    /// created for, or `None` if it does not or is gone. A file of the same
+    /// size and modification time is hashed when `stamp` has a hash.
+    async fn file_drift(&self, component_id: &str, stamp: &ValidationStamp) -> Option<String> {
+        let path = self.component_path(component_id);
+        let current = self
+            .storage
+            .create_validation_stamp(&path, false)
+            .await
+            .ok()?;
+        if current.file_size != stamp.file_size {
+            Some(format!(
+                "file size changed from {} to {} bytes",
+                stamp.file_size, current.file_size
+            ))
+        } else if current.mtime != stamp.mtime {
+            Some("file modification time changed".to_string())
+        } else {
+            let expected = stamp.content_hash.as_ref()?;
+            let hashed = self
+                .storage
+                .create_validation_stamp(&path, true)
+                .await
+                .ok()?;
+            (hashed.content_hash.as_ref() != Some(expected))
+                .then(|| "file contents changed".to_string())
+        }
+    }
+
+    /// Compare the wasm file of every component in the component directory
+    /// with the stamp saved in its metadata when its tools were last
+    /// registered. A difference means the file was replaced without going
+    /// through a lifecycle manager, and a server running on this directory
+    /// may still serve the tools of the old file. Unlike
+    /// [`Self::check_drift`], no load state is changed.
+    ///
+    /// Returns each drifted component with how its file differs, by ID.
+    pub async fn check_metadata_drift(&self) -> Vec<(String, String)> {
+        let mut drifted = Vec::new();
+        for component_id in self.list_components_known().await {
+            let Ok(Some(metadata)) = self.load_component_metadata(&component_id).await else {
+                continue;
+            };
+            if let Some(reason) = self
+                .file_drift(&component_id, &metadata.validation_stamp)
+                .await
+            {
+                drifted.push((component_id, reason));
+            }
+        }
+        drifted.sort();
+        drifted
+    }
+
+    /// Load, reload, or unregister a component whose wasm file was added,
+    /// replaced, or removed behind the manager's back, as noticed by the
+    /// watcher enabled with [`LifecycleBuilder::with_dir_watch`]. Files the
+    /// manager wrote or removed itself are left alone.
+    pub(crate) async fn sync_component_file(&self, component_id: &str) -> Result<()> {
+        if self.embedded.contains(component_id) || !self.component_filter.allows(component_id) {
+            return Ok(());
+        }
+        let path = self.component_path(component_id);
+        // Loads through the manager hold the staging lock until the
+        // component is registered, so their files are not taken for changes
+        let exists = {
+            let _staging = self.staging_lock.write().await;
+            if path.is_file() {
+                let registered = self
+                    .registry
+                    .registered_stamps()
+                    .await
+                    .into_iter()
+                    .find(|(id, _)| id == component_id);
+                if let Some((_, stamp)) = registered {
+                    if self.file_drift(component_id, &stamp).await.is_none() {
+                        return Ok(());
+                    }
+                }
+                true
+            } else if self.registry.load_state(component_id).await.is_some()
+                || self.registry.contains_component(component_id).await
+            {
+                false
+            } else {
+                return Ok(());
+            }
+        };
+
+        if exists {
+            let _staging = self.staging_lock.read().await;
+            self.remove_stale_precompiled(component_id, &path).await;
+            let outcome = self
+                .compile_and_register_component(component_id, &path)
+                .await?;
+            info!(%component_id, tools = ?outcome.tool_names, "Loaded component from the component directory");
+        } else {
+            self.remove_component_from_registry(component_id).await;
+        }
+        Ok(())
+    }
+
+    /// Unregister a component whose wasm file is gone, keeping its policy
+    /// files so they apply again if the file comes back.
+    async fn remove_component_from_registry(&self, component_id: &str) {
+        {
+            let _swap = self.swap_lock.write().await;
+            self.registry.remove_component(component_id).await;
+        }
+        self.instance_pool.invalidate(component_id);
+        // Derived from the removed wasm file, so no longer of use
+        for path in [
+            self.storage.metadata_path(component_id),
+            self.storage.precompiled_path(component_id),
+        ] {
+            if let Err(error) = tokio::fs::remove_file(&path).await {
+                if error.kind() != std::io::ErrorKind::NotFound {
+                    warn!(%component_id, path = %path.display(), %error, "Failed to remove a file of a removed component");
+                }
+            }
+        }
+        self.events.emit(LifecycleEvent::ComponentRemoved {
+            component_id: component_id.to_string(),
+        });
+        info!(%component_id, "Unregistered component whose file was removed");
+    }
+
+    /// Apply the policy files of a loaded component after they were
+    /// changed behind the manager's back, as noticed by the watcher enabled
+    /// with [`LifecycleBuilder::with_dir_watch`]. Policy files the manager
+    /// wrote itself are left alone. Removing every policy file of a
+    /// component puts it back under the default policy.
+    pub(crate) async fn sync_policy_files(&self, component_id: &str) -> Result<()> {
+        if self.embedded.contains(component_id)
+            || !self.registry.contains_component(component_id).await
+            || self
+                .policy_manager
+                .is_disk_policy_current(component_id)
+                .await
+        {
+            return Ok(());
+        }
+        let has_policy = {
+            let _swap = self.swap_lock.write().await;
+            self.policy_manager.reload_from_disk(component_id).await?
+        };
+        self.instance_pool.invalidate(component_id);
+        if has_policy {
+            let policy_path = self.storage.policy_path(component_id);
+            info!(%component_id, path = %policy_path.display(), "Applied policy files changed on disk");
+            self.events.emit(LifecycleEvent::PolicyAttached {
+                component_id: component_id.to_string(),
+                source_uri: format!("file://{}", policy_path.display()),
+            });
+        } else {
+            info!(%component_id, "Policy files removed from disk, using the default policy");
+            self.events.emit(LifecycleEvent::PolicyDetached {
+                component_id: component_id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Run [`Self::check_drift`] every `period` in the background.
+    pub fn spawn_drift_check(&self, period: Duration) -> tokio::task::JoinHandle<()> {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                interval.tick().await;
+                manager.check_drift().await;
+            }
+        })
+    }
+
+    /// Save component metadata to disk
+    async fn save_component_metadata(
+        &self,
+        component_id: &str,
+        tool_metadata: &[ToolMetadata],
+        docs: Option<String>,
+        validation_stamp: ValidationStamp,
+        compiled_with: Option<CompileInfo>,
+    ) -> Result<()> {
+        let metadata = ComponentMetadata {
+            component_id: component_id.to_string(),
+            tool_schemas: tool_metadata.iter().map(|t| t.schema.clone()).collect(),
+            function_identifiers: tool_metadata.iter().map(|t| t.identifier.clone()).collect(),
+            tool_names: tool_metadata
+                .iter()
+                .map(|t| t.normalized_name.clone())
+                .collect(),
+            validation_stamp,
+            created_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            docs,
+            priority: self.priorities.assigned(component_id),
+            compiled_with,
+            // Keep the source across recompiles of the same file
+            source_uri: self
+                .load_component_metadata(component_id)
+                .await
+                .ok()
+                .flatten()
+                .and_then(|metadata| metadata.source_uri),
+        };
+
+        self.storage.write_metadata(&metadata).await?;
+
+        info!(component_id = %component_id, "Saved component metadata");
+        Ok(())
+    }
+
+    /// Load component metadata from disk
+    async fn load_component_metadata(
+        &self,
+        component_id: &str,
+    ) -> Result<Option<ComponentMetadata>> {
+        self.storage.read_metadata(component_id).await
+    }
+
+    /// Save precompiled component to disk. Returns the toolchain it was
+    /// compiled with, to be recorded in the component metadata.
+    async fn save_precompiled_component(
+        &self,
+        component_id: &str,
+        wasm_bytes: &[u8],
+    ) -> Result<CompileInfo> {
+        let precompiled_data = self
+            .runtime
+            .precompile_component(wasm_bytes)
+            .context("Failed to precompile component")?;
+
+        self.storage
+            .write_precompiled(component_id, &precompiled_data)
+            .await?;
+
+        info!(component_id = %component_id, "Saved precompiled component");
+        Ok(self.runtime.compile_info().clone())
+    }
+
+    /// Load component from precompiled cache or compile fresh. The cache is
+    /// only used when the metadata records that it was compiled with the
+    /// same toolchain and flags as the running engine.
+    async fn load_component_optimized(
+        &self,
+        wasm_path: &Path,
+        component_id: &str,
+    ) -> Result<(Component, Vec<u8>, Option<CompileInfo>)> {
+        let precompiled_path = self.component_precompiled_path(component_id);
+        let current = self.runtime.compile_info();
+        // Needed for metadata either way, and to tell whether the
+        // precompiled artifact was built from this file
+        let wasm_bytes = tokio::fs::read(wasm_path)
+            .await
+            .context("Failed to read wasm file")?;
+
+        // Try to load from precompiled cache first
+        if precompiled_path.exists() {
+            let metadata = self
+                .load_component_metadata(component_id)
+                .await
+                .ok()
+                .flatten();
+            // The artifact was compiled from the file the metadata was stamped with
+            let built_from = metadata
+                .as_ref()
+                .and_then(|metadata| metadata.validation_stamp.content_hash.clone());
+            let compiled_with = metadata.and_then(|metadata| metadata.compiled_with);
+            match compiled_with {
+                Some(_) if built_from.is_some_and(|hash| hash != content_hash(&wasm_bytes)) => {
+                    info!(%component_id, "Precompiled component was built from a different wasm file, recompiling");
+                }
+                Some(info) if info.fingerprint() == current.fingerprint() => {
+                    match unsafe {
+                        Component::deserialize_file(self.runtime.as_ref(), &precompiled_path)
+                    } {
+                        Ok(component) => {
+                            debug!(component_id = %component_id, "Loaded component from precompiled cache");
+                            return Ok((component, wasm_bytes, Some(info)));
+                        }
+                        Err(e) => {
+                            warn!(%component_id, error = %e, "Failed to load precompiled component, falling back to compilation");
+                        }
+                    }
+                }
+                Some(info) => {
+                    info!(
+                        %component_id,
+                        cached_wasmtime = %info.wasmtime_version,
+                        current_wasmtime = %current.wasmtime_version,
+                        "Precompiled component was built by a different toolchain, recompiling"
+                    );
+                }
+                None => {
+                    debug!(%component_id, "Precompiled component has no recorded toolchain, recompiling");
+                }
+            }
+        }
+
+        // Fall back to compilation
+        let component = Component::new(self.runtime.as_ref(), &wasm_bytes)
+            .context("Failed to compile component")?;
+
+        // Save precompiled version for next time
+        let compiled_with = match self
+            .save_precompiled_component(component_id, &wasm_bytes)
+            .await
+        {
+            Ok(info) => Some(info),
+            Err(e) => {
+                warn!(%component_id, error = %e, "Failed to save precompiled component");
+                None
+            }
+        };
+
+        debug!(component_id = %component_id, "Compiled component and saved to cache");
+        Ok((component, wasm_bytes, compiled_with))
+    }
+
+    /// The toolchain that compiled a component's precompiled artifact, as
+    /// recorded in its metadata. `None` if the component has not been
+    /// precompiled or was precompiled before this was recorded.
+    pub async fn component_compile_info(&self, component_id: &str) -> Option<CompileInfo> {
+        if !self.component_precompiled_path(component_id).exists() {
+            return None;
+        }
+        self.load_component_metadata(component_id)
+            .await
+            .ok()
+            .flatten()
+            .and_then(|metadata| metadata.compiled_with)
+    }
+
+    /// The toolchain and flags this lifecycle manager compiles components with.
+    pub fn runtime_compile_info(&self) -> &CompileInfo {
+        self.runtime.compile_info()
+    }
+
+    /// The sandbox template for a component: the one built from its policy,
+    /// as allowed, replaced, or denied by the permission hook.
+    #[cfg(test)]
+    async fn sandbox_template_for_component(
+        &self,
+        component_id: &str,
+    ) -> Result<Arc<WasiStateTemplate>> {
+        let policy_template = self
+            .policy_manager
+            .template_for_component(component_id)
+            .await;
+        self.apply_permission_hook(component_id, policy_template)
+            .await
+    }
+
+    /// Let the permission hook, if any, allow, replace, or deny the sandbox
+    /// built from a component's policy.
+    async fn apply_permission_hook(
+        &self,
+        component_id: &str,
+        policy_template: Arc<WasiStateTemplate>,
+    ) -> Result<Arc<WasiStateTemplate>> {
+        let Some(hook) = &self.permission_hook else {
+            return Ok(policy_template);
+        };
+
+        match hook(component_id, &policy_template).await {
+            PermissionDecision::Allow => Ok(policy_template),
+            PermissionDecision::Modify(template) => {
+                debug!(%component_id, "Permission hook replaced the sandbox template");
+                Ok(Arc::new(*template))
+            }
+            PermissionDecision::Deny(reason) => {
+                warn!(%component_id, %reason, "Permission hook denied instantiation");
+                let error = PermissionError::Denied { reason };
+                self.emit_permission_denied(component_id, &error);
+                let message = error.to_user_message(component_id);
+                Err(anyhow::Error::new(error).context(message))
+            }
+        }
+    }
+
+    #[cfg(test)]
+    async fn get_wasi_state_for_component(
+        &self,
+        component_id: &str,
+    ) -> Result<(WassetteWasiState<WasiState>, Option<CustomResourceLimiter>)> {
+        let policy_template = self.sandbox_template_for_component(component_id).await?;
+        self.wasi_state_from_template(component_id, &policy_template, None)
+    }
+
+    fn wasi_state_from_template(
+        &self,
+        component_id: &str,
+        policy_template: &WasiStateTemplate,
+        stderr_lines: Option<StderrLines>,
+    ) -> Result<(WassetteWasiState<WasiState>, Option<CustomResourceLimiter>)> {
+        let mut wasi_state = policy_template
+            .build_for_call(self.default_stdio, stderr_lines)
+            .map_err(|error| {
+                let message = match error.downcast_ref::<PermissionError>() {
+                    Some(permission_error) => permission_error.to_user_message(component_id),
+                    None => {
+                        format!(
+                            "Failed to set up the sandbox of component '{component_id}': {error:#}"
+                        )
+                    }
+                };
+                error.context(message)
+            })?;
+        let allowed_hosts = policy_template.allowed_hosts.clone();
+        let resource_limiter = wasi_state.resource_limiter.clone();
+        wasi_state.broker = policy_template
+            .calls
+            .clone()
+            .map(|grants| Broker::new(self.clone(), component_id, grants));
+
+        let wassette_wasi_state = WassetteWasiState::new(wasi_state, allowed_hosts)?
+            .with_denied_networks(&policy_template.denied_networks)
+            .with_outbound_limits(
+                Arc::clone(&self.outbound_http),
+                policy_template.network_timeouts.clone(),
+            );
+        Ok((wassette_wasi_state, resource_limiter))
+    }
+
+    /// Executes a function call on a WebAssembly component, returning the
+    /// result as text: a JSON string as is, any other value serialized as
+    /// JSON. Use [`Self::execute_component_call_timed`] to tell a string
+    /// that holds JSON from a structured result.
+    #[instrument(skip(self))]
+    pub async fn execute_component_call(
+        &self,
+        component_id: &str,
+        function_name: &str,
+        parameters: &str,
+    ) -> Result<String> {
+        let (result, _) = self
+            .execute_component_call_timed(component_id, function_name, parameters)
+            .await?;
+        Ok(match result {
+            Value::String(text) => text,
+            other => serde_json::to_string(&other)?,
+        })
+    }
+
+    /// Executes a function call on a WebAssembly component, returning the
+    /// JSON result together with where the time of the call went. The
+    /// function's results are wrapped in a `result` object. A component
+    /// that is only registered from metadata is compiled first, and the call
+    /// is reported as a cold load.
+    ///
+    /// A call that finds the component in the middle of being loaded or
+    /// replaced is retried up to twice with a short backoff. Other failures,
+    /// such as guest traps and denied permissions, are returned right away.
+    ///
+    /// `parameters` are checked against the manager's [`ParameterLimits`]
+    /// before they are parsed, failing with [`ParameterLimitExceeded`].
+    ///
+    /// The call runs through the [`CallMiddleware`] registered with
+    /// [`LifecycleBuilder::with_call_middleware`].
+    #[instrument(skip(self))]
+    pub async fn execute_component_call_timed(
+        &self,
+        component_id: &str,
+        function_name: &str,
+        parameters: &str,
+    ) -> Result<(Value, CallTiming)> {
+        let call = self
+            .execute_component_call_with_output(component_id, function_name, parameters)
+            .await?;
+        Ok((call.result, call.timing))
+    }
+
+    /// Same as [`Self::execute_component_call_timed`], also returning what
+    /// the component wrote to stdout and stderr, for the streams that are
+    /// captured under its policy or [`LifecycleBuilder::with_default_stdio`].
+    /// Each stream keeps up to [`MAX_CAPTURED_BYTES`].
+    #[instrument(skip(self))]
+    pub async fn execute_component_call_with_output(
+        &self,
+        component_id: &str,
+        function_name: &str,
+        parameters: &str,
+    ) -> Result<ComponentCallOutput> {
+        self.execute_call(component_id, function_name, parameters, None)
+            .await
+    }
+
+    /// Same as [`Self::execute_component_call_with_output`], also sending
+    /// each line the component writes to stderr to `stderr_lines` while the
+    /// call runs. Stderr the component's policy leaves to the host is sent
+    /// there instead of to the host's stderr; captured stderr is sent there
+    /// as well as captured, and denied stderr is not sent. Lines are dropped
+    /// while the channel is full, and lines longer than
+    /// [`MAX_STDERR_LINE_BYTES`] are split. The channel closes once the call
+    /// is over, whether it succeeded or not.
+    #[instrument(skip(self, stderr_lines))]
+    pub async fn execute_component_call_with_stderr(
+        &self,
+        component_id: &str,
+        function_name: &str,
+        parameters: &str,
+        stderr_lines: StderrLines,
+    ) -> Result<ComponentCallOutput> {
+        self.execute_call(component_id, function_name, parameters, Some(stderr_lines))
+            .await
+    }
+
+    async fn execute_call(
+        &self,
+        component_id: &str,
+        function_name: &str,
+        parameters: &str,
+        stderr_lines: Option<StderrLines>,
+    ) -> Result<ComponentCallOutput> {
+        let start_time = Instant::now();
+        let mut timing = CallTiming::default();
+        let mut output = CapturedOutput::default();
+
+        debug!(
+            component_id = %component_id,
+            function_name = %function_name,
+            "Starting WebAssembly component execution"
+        );
+
+        self.parameter_limits.check_str(parameters)?;
+        let mut arguments: Value = serde_json::from_str(parameters)?;
+        let mut before_ran = 0;
+        let mut stopped = None;
+        for middleware in self.call_middleware.iter() {
+            if let Err(error) = middleware
+                .before(component_id, function_name, &mut arguments)
+                .await
+            {
+                debug!(%component_id, %function_name, %error, "Call middleware stopped the call");
+                stopped = Some(error);
+                break;
+            }
+            before_ran += 1;
+        }
+
+        let mut result = match stopped {
+            Some(error) => Err(error),
+            None => {
+                self.execute_with_retries(
+                    component_id,
+                    function_name,
+                    &arguments,
+                    &mut timing,
+                    &mut output,
+                    stderr_lines.as_ref(),
+                )
+                .await
+            }
+        };
+        timing.total_ms = start_time.elapsed().as_millis() as u64;
+        self.usage
+            .record_finished(component_id, function_name, &timing, result.is_ok());
+        for middleware in &self.call_middleware[..before_ran] {
+            middleware
+                .after(component_id, function_name, &mut result, &timing)
+                .await;
+        }
+        let result = result?;
+
+        debug!(
+            component_id = %component_id,
+            function_name = %function_name,
+            total_duration_ms = %timing.total_ms,
+            retries = timing.retries,
+            "WebAssembly component execution completed"
+        );
+        Ok(ComponentCallOutput {
+            result,
+            timing,
+            output,
+        })
+    }
+
+    /// Run a component call, retrying it while the component is briefly
+    /// unavailable.
+    async fn execute_with_retries(
+        &self,
+        component_id: &str,
+        function_name: &str,
+        arguments: &Value,
+        timing: &mut CallTiming,
+        output: &mut CapturedOutput,
+        stderr_lines: Option<&StderrLines>,
+    ) -> Result<Value> {
+        let result = loop {
+            let result = self
+                .try_execute_component_call(
+                    component_id,
+                    function_name,
+                    arguments,
+                    timing,
+                    output,
+                    stderr_lines,
+                )
+                .await;
+            match result {
+                Err(error)
+                    if timing.retries < TRANSIENT_CALL_RETRIES
+                        && matches!(
+                            error.downcast_ref::<ComponentError>(),
+                            Some(ComponentError::Unavailable { .. })
+                        ) =>
+                {
+                    timing.retries += 1;
+                    debug!(
+                        %component_id,
+                        %function_name,
+                        retry = timing.retries,
+                        error = %error,
+                        "Retrying component call after a transient error"
+                    );
+                    tokio::time::sleep(TRANSIENT_CALL_BACKOFF * 2u32.pow(timing.retries - 1)).await;
+                }
+                result => break result,
+            }
+        };
+        if timing.retries > 0 {
+            self.usage.record_retries(component_id, timing.retries);
+        }
+        result
+    }
+
+    /// One attempt at a component call. Failures caused by the component
+    /// being loaded or replaced at the same time are reported as
+    /// [`ComponentError::Unavailable`].
+    async fn try_execute_component_call(
+        &self,
+        component_id: &str,
+        function_name: &str,
+        arguments: &Value,
+        timing: &mut CallTiming,
+        output: &mut CapturedOutput,
+        stderr_lines: Option<&StderrLines>,
+    ) -> Result<serde_json::Value> {
+        let mut load_state = self.registry.load_state(component_id).await;
+        if let Some(ComponentLoadState::Virtual { source_uri }) = load_state.clone() {
+            self.materialize_virtual_component(component_id, &source_uri)
+                .await?;
+            timing.cold_load = true;
+            load_state = self.registry.load_state(component_id).await;
+        }
+        match &load_state {
+            Some(ComponentLoadState::Drifted { reason }) => {
+                return Err(ComponentError::Drifted {
+                    component_id: component_id.to_string(),
+                    reason: reason.clone(),
+                }
+                .into());
+            }
+            // A component that another call is compiling is compiled here
+            // too, rather than found missing
+            Some(ComponentLoadState::MetadataRegistered | ComponentLoadState::Loading)
+                if !self.registry.contains_component(component_id).await =>
+            {
+                if let Err(error) = self.ensure_component_loaded(component_id).await {
+                    if !self.component_path(component_id).is_file() {
+                        return Err(ComponentError::Unavailable {
+                            component_id: component_id.to_string(),
+                            reason: "its wasm file is being replaced".to_string(),
+                        }
+                        .into());
+                    }
+                    return Err(error);
+                }
+                timing.cold_load = true;
+            }
+            _ => {}
+        }
+
+        let snapshot = match self.snapshot_component(component_id).await {
+            Some(snapshot) => snapshot,
+            None if matches!(
+                load_state,
+                Some(
+                    ComponentLoadState::MetadataRegistered
+                        | ComponentLoadState::Loading
+                        | ComponentLoadState::Loaded
+                )
+            ) =>
+            {
+                return Err(ComponentError::Unavailable {
+                    component_id: component_id.to_string(),
+                    reason: "it is being loaded".to_string(),
+                }
+                .into());
+            }
+            None => bail!("Component not found: {}", component_id),
+        };
+
+        // Use the new function identifier lookup instead of dot-splitting
+        let function_id = self
+            .registry
+            .tool_identifier(function_name)
+            .await
+            .ok_or_else(|| anyhow!("Unknown tool name: {}", function_name))?;
+        self.usage.record(component_id, function_name);
+
+        let scratch = snapshot.policy_template.scratch.clone();
+        let scratch_use = scratch.as_ref().map(|_| self.scratch.enter(component_id));
+        // Calls streaming their stderr get an instance of their own
+        let pool_key = stderr_lines
+            .is_none()
+            .then(|| self.pool_key(&snapshot))
+            .flatten();
+        let result = self
+            .call_component_function(
+                component_id,
+                ComponentCall {
+                    stderr_lines: stderr_lines.cloned(),
+                    pool_key,
+                    ..ComponentCall::new(&snapshot.component, snapshot.policy_template)
+                },
+                &function_id,
+                arguments,
+                timing,
+                output,
+            )
+            .await;
+        match (scratch, scratch_use) {
+            (Some(scratch), Some(scratch_use)) => {
+                self.scratch.finish(&scratch, scratch_use, result).await
+            }
+            _ => result,
+        }
+    }
+
+    /// The key instances of `snapshot` are pooled under, or `None` if they
+    /// are not pooled. Components whose sandbox has state of its own, a
+    /// scratch directory or a permission hook's say, get an instance per call.
+    fn pool_key(&self, snapshot: &ComponentSnapshot) -> Option<PoolKey> {
+        (self.instance_pool.is_enabled()
+            && snapshot.policy_template.scratch.is_none()
+            && self.permission_hook.is_none())
+        .then(|| PoolKey {
+            instance_pre: Arc::clone(&snapshot.component.instance_pre),
+            policy_version: snapshot.policy_version,
+        })
+    }
+
+    /// A loaded component together with the policy template it currently
+    /// runs under, taken so that a concurrent reload cannot pair the old
+    /// instance with the new policy or the other way round.
+    async fn snapshot_component(&self, component_id: &str) -> Option<ComponentSnapshot> {
+        let _swap = self.swap_lock.read().await;
+        let component = self.registry.get_component(component_id).await?;
+        let (policy_template, policy_version) = self
+            .policy_manager
+            .versioned_template_for_component(component_id)
+            .await;
+        Some(ComponentSnapshot {
+            component,
+            policy_template,
+            policy_version,
+        })
+    }
+
+    /// Keep `snapshot` if the component's policy has not changed since it
+    /// was taken, otherwise take a new one, so nothing built from a replaced
+    /// template (such as its memory limits) outlives the change.
+    async fn refresh_snapshot(
+        &self,
+        component_id: &str,
+        snapshot: ComponentSnapshot,
+    ) -> Option<ComponentSnapshot> {
+        if self.policy_manager.template_version(component_id).await == snapshot.policy_version {
+            return Some(snapshot);
+        }
+        debug!(%component_id, "Policy changed since the call started, refreshing");
+        self.snapshot_component(component_id).await
+    }
+
+    /// Create a store with WASI state built from `policy_template`, as
+    /// decided by the permission hook, and instantiate the component in it.
+    ///
+    /// Instantiation is bounded by the component's policy timeout, or the
+    /// manager default, and fails with [`ComponentError::InstantiationTimeout`].
+    /// The time instantiation took and the template the hook decided on are
+    /// returned with the instance. The lines the instance writes to stderr
+    /// are sent to `stderr_lines`, if given.
+    async fn instantiate_component(
+        &self,
+        component_id: &str,
+        component: &ComponentInstance,
+        policy_template: Arc<WasiStateTemplate>,
+        stderr_lines: Option<StderrLines>,
+    ) -> Result<(
+        Store<WassetteWasiState<WasiState>>,
+        wasmtime::component::Instance,
+        Duration,
+        Arc<WasiStateTemplate>,
+    )> {
+        let policy_template = self
+            .apply_permission_hook(component_id, policy_template)
+            .await?;
+        let (state, resource_limiter) =
+            self.wasi_state_from_template(component_id, &policy_template, stderr_lines)?;
+
+        let mut store = Store::new(self.runtime.as_ref(), state);
+        // Yield to the executor on every epoch tick, so the timeouts raced
+        // against the guest also stop code that never awaits
+        store.epoch_deadline_async_yield_and_update(1);
+        // Only the call is metered, under the policy's CPU limit
+        if self.runtime.meters_fuel() {
+            store.set_fuel(u64::MAX)?;
+        }
+
+        // Apply memory limits if configured in the policy by setting up a limiter closure
+        // that extracts the resource limiter from the WasiState
+        if resource_limiter.is_some() {
+            store.limiter(|state: &mut WassetteWasiState<WasiState>| {
+                // Extract the resource limiter from the inner state
+                state
+                    .inner
+                    .resource_limiter
+                    .as_mut()
+                    .expect("Resource limiter should be present - checked above")
+            });
+        }
+
+        let timeout = policy_template
+            .instantiation_timeout
+            .unwrap_or(self.instantiation_timeout);
+
+        let instantiation_start = Instant::now();
+        let instance = tokio::time::timeout(
+            timeout,
+            component.instance_pre.instantiate_async(&mut store),
+        )
+        .await
+        .map_err(|_| ComponentError::InstantiationTimeout {
+            component_id: component_id.to_string(),
+            timeout,
+        })??;
+        let instantiation_duration = instantiation_start.elapsed();
+
+        debug!(
+            component_id = %component_id,
+            instantiation_ms = %instantiation_duration.as_millis(),
+            "Component instance created"
+        );
+
+        Ok((store, instance, instantiation_duration, policy_template))
+    }
+
+    /// Instantiate the component of `call` under its policy template and call
+    /// the function identified by `function_id`, returning the JSON-converted
+    /// results. The instantiation and execution times are recorded in
+    /// `timing`, and the captured stdout and stderr in `output`, also when
+    /// the call fails.
+    ///
+    /// With a pool key, an idle instance built for it is used if the pool
+    /// has one, and the instance is returned to the pool after a successful
+    /// call, unless its sandbox captures output or brokers calls.
+    async fn call_component_function(
+        &self,
+        component_id: &str,
+        call: ComponentCall<'_>,
+        function_id: &FunctionIdentifier,
+        params: &Value,
+        timing: &mut CallTiming,
+        output: &mut CapturedOutput,
+    ) -> Result<Value> {
+        let ComponentCall {
+            component,
+            policy_template,
+            stderr_lines,
+            pool_key,
+        } = call;
+        let pooled = pool_key
+            .as_ref()
+            .and_then(|key| self.instance_pool.take(component_id, key));
+        // The template the permission hook settled on, which the sandbox,
+        // the limits below, and any permission error message follow
+        let (mut store, instance, instantiation_duration, policy_template) = match pooled {
+            Some(mut pooled) => {
+                debug!(%component_id, "Reusing a pooled component instance");
+                pooled.store.data_mut().reset_for_call();
+                pooled.store.epoch_deadline_async_yield_and_update(1);
+                if self.runtime.meters_fuel() {
+                    pooled.store.set_fuel(u64::MAX)?;
+                }
+                timing.pooled = true;
+                (
+                    pooled.store,
+                    pooled.instance,
+                    Duration::ZERO,
+                    policy_template,
+                )
+            }
+            None => {
+                self.instantiate_component(component_id, component, policy_template, stderr_lines)
+                    .await?
+            }
+        };
+
+        let (interface_name, func_name) = (
+            function_id.interface_name.as_deref().unwrap_or(""),
+            &function_id.function_name,
+        );
+
+        let func = if !interface_name.is_empty() {
+            let interface_index = instance
+                .get_export_index(&mut store, None, interface_name)
+                .ok_or_else(|| anyhow!("Interface not found: {}", interface_name))?;
+
+            let function_index = instance
+                .get_export_index(&mut store, Some(&interface_index), func_name)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Function not found in interface: {}.{}",
+                        interface_name,
+                        func_name
+                    )
+                })?;
+
+            instance
+                .get_func(&mut store, function_index)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Function not found in interface: {}.{}",
+                        interface_name,
+                        func_name
+                    )
+                })?
+        } else {
+            let func_index = instance
+                .get_export_index(&mut store, None, func_name)
+                .ok_or_else(|| anyhow!("Function not found: {}", func_name))?;
+            instance
+                .get_func(&mut store, func_index)
+                .ok_or_else(|| anyhow!("Function not found: {}", func_name))?
+        };
+
+        let argument_vals = json_to_vals(params, &func.params(&store))?;
+
+        let mut results = create_placeholder_results(&func.results(&store));
+
+        // The guest traps once the epoch passes the deadline
+        let execution_timeout = policy_template.execution_timeout.or(self.execution_timeout);
+        if let Some(timeout) = execution_timeout {
+            store.set_epoch_deadline(runtime_context::epoch_ticks(timeout));
+            store.epoch_deadline_trap();
+            store.data_mut().inner.execution_deadline = Some(Instant::now() + timeout);
+        }
+        if let Some(fuel) = policy_template.fuel_limit {
+            if !self.runtime.meters_fuel() {
+                return Err(ComponentError::CpuLimitsDisabled {
+                    component_id: component_id.to_string(),
+                }
+                .into());
+            }
+            store.set_fuel(fuel)?;
+        }
+
+        let execution_start = Instant::now();
+
+        // Execute the WASM function and capture any errors
+        let call_result = func
+            .call_async(&mut store, &argument_vals, &mut results)
+            .await;
+
+        let execution_duration = execution_start.elapsed();
+        timing.instantiation_ms = instantiation_duration.as_millis() as u64;
+        timing.execution_ms = execution_duration.as_millis() as u64;
+        *output = store.data().inner.captured_output();
+        let permission_error = store.data().get_last_permission_error();
+        if let Some(error) = &permission_error {
+            self.emit_permission_denied(component_id, error);
+        }
+
+        // If the call failed, check if it was due to a permission denial
+        if let Err(e) = call_result {
+            // Check if there was a permission error recorded during execution
+            if let Some(perm_error) = permission_error {
+                // Return a more informative error with instructions and the
+                // grants the call ran with
+                return Err(anyhow!(
+                    perm_error.to_user_message_with_grants(component_id, &policy_template)
+                ));
+            }
+            if let (Some(timeout), Some(wasmtime::Trap::Interrupt)) =
+                (execution_timeout, e.downcast_ref::<wasmtime::Trap>())
+            {
+                return Err(ComponentError::ExecutionTimeout {
+                    component_id: component_id.to_string(),
+                    timeout,
+                }
+                .into());
+            }
+            if let (Some(fuel), Some(wasmtime::Trap::OutOfFuel)) = (
+                policy_template.fuel_limit,
+                e.downcast_ref::<wasmtime::Trap>(),
+            ) {
+                return Err(ComponentError::FuelExhausted {
+                    component_id: component_id.to_string(),
+                    fuel,
+                }
+                .into());
+            }
+            // Otherwise, return the original WASM execution error
+            return Err(e);
+        }
+
+        debug!(
+            component_id = %component_id,
+            execution_ms = %execution_duration.as_millis(),
+            "Component function returned"
+        );
+
+        let result_json = vals_to_json(&results);
+
+        // A guest that turns a timed-out request into an error result gets
+        // the host and timeout that caused it reported instead
+        if let Some(timeout) = store.data().last_network_timeout() {
+            if result_json.pointer("/result/err").is_some() {
+                return Err(anyhow!(timeout.to_user_message(component_id)));
+            }
+        }
+
+        if let Some(key) = pool_key {
+            if can_pool(&store.data().inner) {
+                match func.post_return_async(&mut store).await {
+                    Ok(()) => self.instance_pool.put(component_id, key, store, instance),
+                    Err(error) => {
+                        debug!(%component_id, %error, "Not pooling an instance whose post-return failed");
+                    }
+                }
+            }
+        }
+
+        Ok(result_json)
+    }
+
+    /// Load existing components from component directory in the background with bounded parallelism
+    /// Default concurrency is min(num_cpus, 4) if not specified
+    ///
+    /// Components are compiled in priority order (see [`Self::set_component_priority`]),
+    /// most recently used first among equal priorities.
+    ///
+    /// Each loaded component is announced with [`LifecycleEvent::ComponentLoaded`]; components
+    /// that fail to load are announced with [`LifecycleEvent::ComponentDisabled`].
+    #[instrument(skip(self))]
+    pub async fn load_existing_components_async(&self, concurrency: Option<usize>) -> Result<()> {
+        self.background_load.lock().unwrap().started = true;
+
+        // First phase: Quick metadata-based registry population
+        self.populate_registry_from_metadata().await?;
+
+        let concurrency = concurrency.unwrap_or_else(|| std::cmp::min(num_cpus::get(), 4));
+
+        info!(
+            "Starting background component loading with concurrency: {}",
+            concurrency
+        );
+
+        let mut entries = tokio::fs::read_dir(self.storage.root()).await?;
+        let mut wasm_entries = HashMap::new();
+        while let Some(entry) = entries.next_entry().await? {
+            if let Some(component_id) = component_id_from_path(&entry.path()) {
+                if self.component_filter.allows(&component_id)
+                    && !self.embedded.contains(&component_id)
+                {
+                    wasm_entries.insert(component_id, entry);
+                }
+            }
+        }
+
+        let mut order: Vec<String> = wasm_entries.keys().cloned().collect();
+        self.priorities.sort(&mut order, |id| {
+            self.usage.get(id).map(|usage| usage.last_used)
+        });
+        debug!(?order, "Background component loading order");
+        let queue: Vec<(String, DirEntry)> = order
+            .into_iter()
+            .filter_map(|id| wasm_entries.remove(&id).map(|entry| (id, entry)))
+            .collect();
+        self.background_load.lock().unwrap().queued = queue.len();
+
+        // Components start loading in queue order, at most `concurrency` at a time
+        futures::stream::iter(queue)
+            .for_each_concurrent(concurrency, |(component_id, entry)| async move {
+                if let Err(e) = self.load_component_from_entry_optimized(entry).await {
+                    warn!("Failed to load component: {}", e);
+                    self.events.emit(LifecycleEvent::ComponentDisabled {
+                        component_id,
+                        reason: format!("{e:#}"),
+                    });
+                }
+                self.background_load.lock().unwrap().processed += 1;
+            })
+            .await;
+
+        self.background_load.lock().unwrap().completed = true;
+        info!("Background component loading completed");
+        Ok(())
+    }
+
+    /// Set the priority with which background loading compiles a component;
+    /// higher priorities are compiled first. `None` clears the priority, so
+    /// the component falls back to its position in the list configured with
+    /// [`LifecycleBuilder::with_load_priority`], or priority 0.
+    ///
+    /// The priority is saved in the component's metadata, if it has been
+    /// compiled, so it applies to later restarts as well.
+    #[instrument(skip(self))]
+    pub async fn set_component_priority(
+        &self,
+        component_id: &str,
+        priority: Option<i32>,
+    ) -> Result<()> {
+        path_guard::validate_component_id(component_id)?;
+        if !self.component_path(component_id).is_file() {
+            bail!("Component not found: {}", component_id);
+        }
+        self.priorities.assign(component_id, priority);
+
+        if let Some(mut metadata) = self.load_component_metadata(component_id).await? {
+            metadata.priority = priority;
+            self.storage.write_metadata(&metadata).await?;
+        }
+        Ok(())
+    }
+
+    /// Returns the priority with which background loading compiles a component.
+    pub fn get_component_priority(&self, component_id: &str) -> i32 {
+        self.priorities.get(component_id)
+    }
+
+    /// Hide a component's tools from tool listings, or show them again.
+    ///
+    /// A hidden component stays loaded and its tools can still be called by
+    /// name; only listings such as the MCP server's `tools/list` leave them
+    /// out. The setting is saved in the component directory and survives
+    /// restarts until the component is unloaded.
+    #[instrument(skip(self))]
+    pub async fn set_component_hidden(&self, component_id: &str, hidden: bool) -> Result<()> {
+        path_guard::validate_component_id(component_id)?;
+        if !self.component_path(component_id).is_file() {
+            bail!("Component not found: {}", component_id);
+        }
+        if self.hidden.set(component_id, hidden).await? {
+            self.events
+                .emit(LifecycleEvent::ComponentVisibilityChanged {
+                    component_id: component_id.to_string(),
+                    hidden,
+                });
+        }
+        Ok(())
+    }
+
+    /// Returns whether a component's tools are hidden from tool listings.
+    pub fn is_component_hidden(&self, component_id: &str) -> bool {
+        self.hidden.is_hidden(component_id)
+    }
+
+    /// Returns the version of a component's policy. It changes whenever a
+    /// grant, revoke, attach, detach, reset, or reload changes the sandbox
+    /// the component runs in, so embedders caching anything derived from the
+    /// policy can tell when to rebuild it.
+    pub async fn get_policy_version(&self, component_id: &str) -> u64 {
+        self.policy_manager.template_version(component_id).await
+    }
+
+    /// Register the tools of every component in the component directory
+    /// from its cached metadata, without compiling it. Components without
+    /// valid metadata are recorded as [`ComponentLoadState::Discovered`].
+    ///
+    /// Components recorded in the state manifest are registered first, in
+    /// the order they were loaded, then the others by ID, so the component
+    /// whose tools were rejected or prefixed for colliding is the same one
+    /// on every start.
+    pub async fn populate_registry_from_metadata(&self) -> Result<()> {
+        let mut entries = tokio::fs::read_dir(self.storage.root()).await?;
+        let mut component_paths = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let entry_path = entry.path();
+            if let Some(id) = component_id_from_path(&entry_path) {
+                component_paths.push((id, entry_path));
+            }
+        }
+        let manifest = ComponentManifest::load(self.manifest.path())
+            .await
+            .unwrap_or_else(|error| {
+                warn!(%error, "Failed to read the state manifest, registering components by ID");
+                ComponentManifest::default()
+            });
+        component_paths.sort_by_key(|(id, _)| {
+            let entry = manifest.components.get(id);
+            (
+                entry.is_none(),
+                entry.and_then(|entry| entry.load_order),
+                id.clone(),
+            )
+        });
+        let mut loaded_count = 0;
+
+        for (id, entry_path) in component_paths {
+            let component_id = id.as_str();
+            if !self.component_filter.allows(component_id) {
+                debug!(%component_id, "Skipping component excluded by the component filter");
+                continue;
+            }
+
+            // Try to load cached metadata
+            if let Ok(Some(metadata)) = self.load_component_metadata(component_id).await {
+                if metadata.priority.is_some() && self.priorities.assigned(component_id).is_none() {
+                    self.priorities.assign(component_id, metadata.priority);
+                }
+
+                // Validate that the component file hasn't changed
+                let validation = self
+                    .check_stamp(&entry_path, &metadata.validation_stamp)
+                    .await;
+                if validation == StampValidation::HashMismatch {
+                    warn!(%component_id, "Component file does not match the hash in its metadata, it will be recompiled");
+                }
+                if validation.is_valid() {
+                    let tool_metadata: Vec<ToolMetadata> = metadata
+                        .function_identifiers
+                        .into_iter()
+                        .zip(metadata.tool_schemas)
+                        .zip(metadata.tool_names)
+                        .map(|((identifier, mut schema), normalized_name)| {
+                            if let Some(output_schema) = schema.get_mut("outputSchema") {
+                                *output_schema = schema::canonicalize_output_schema(output_schema);
+                            }
+                            ToolMetadata {
+                                identifier,
+                                schema,
+                                normalized_name,
+                            }
+                        })
+                        .collect();
+
+                    match self
+                        .registry
+                        .register_metadata_if_absent(
+                            component_id,
+                            tool_metadata,
+                            metadata.validation_stamp,
+                            self.tool_collisions,
+                        )
+                        .await
+                    {
+                        Ok(true) => {
+                            loaded_count += 1;
+                            debug!(component_id = %component_id, "Registered tools from cached metadata");
+                            continue;
+                        }
+                        Ok(false) => {
+                            debug!(component_id = %component_id, "Skipping cached metadata; component already registered");
+                            continue;
+                        }
+                        Err(e) => {
+                            warn!(%component_id, error = %e, "Failed to register tools from metadata");
+                            continue;
+                        }
+                    }
+                }
+            }
+
+            self.registry.discover(component_id).await;
+            debug!(component_id = %component_id, "No valid cached metadata found, will load component later");
+        }
+
+        if loaded_count > 0 {
+            info!(
+                "Registered {} components from cached metadata",
+                loaded_count
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Load a component from directory entry with optimization
+    async fn load_component_from_entry_optimized(&self, entry: DirEntry) -> Result<bool> {
+        let entry_path = entry.path();
+        let Some(component_id) = component_id_from_path(&entry_path) else {
+            return Ok(false);
+        };
+
+        if self.registry.contains_component(&component_id).await {
+            debug!(component_id = %component_id, "Component already loaded in memory");
+            return Ok(false);
+        }
+
+        let start_time = Instant::now();
+        self.compile_and_register_component(&component_id, &entry_path)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to compile component from path: {}",
+                    entry_path.display()
+                )
+            })?;
+
+        info!(component_id = %component_id, elapsed = ?start_time.elapsed(), "component loaded");
+        Ok(true)
+    }
+
+    // Granular permission system methods
+}
+
+#[cfg(test)]
+mod docker_cred_tests {
+    use super::*;
+    use std::fs::{self, File};
+    use std::io::Write;
+
+    fn write_config(dir: &Path, content: &str) {
+        let mut cfg = dir.to_path_buf();
+        cfg.push("config.json");
+        let mut f = File::create(&cfg).expect("create config");
+        f.write_all(content.as_bytes()).expect("write config");
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn cred_helper_is_used_when_present() {
+        let tmp = std::env::temp_dir().join(format!(
+            "wassette-test-cred-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_millis()
+        ));
+        let _ = fs::create_dir_all(&tmp);
+
+        // config points to a helper named `myhelper`
+        let config = r#"{ "credsStore": "myhelper" }"#;
+        write_config(&tmp, config);
+
+        // create a fake docker-credential-myhelper.cmd that prints JSON
+        let helper_path = tmp.join("docker-credential-myhelper.cmd");
+        let mut hf = File::create(&helper_path).expect("create helper");
+        // Windows-friendly command script: prints JSON to stdout
+        hf.write_all(b"@echo off\r\necho {\"Username\":\"u_test\",\"Secret\":\"s_test\"}\r\n")
+            .expect("write helper");
+
+        // Ensure helper is found by PATH and DOCKER_CONFIG points to tmp
+        let old_path = std::env::var_os("PATH");
+        let mut path_val = tmp.clone().into_os_string();
+        if let Some(p) = old_path.clone() {
+            path_val.push(";");
+            path_val.push(p);
+        }
+        std::env::set_var("PATH", &path_val);
+        std::env::set_var("DOCKER_CONFIG", &tmp);
+
+        let auth = LifecycleManager::try_read_docker_config_auth("example.com");
+
+        // cleanup
+        let _ = fs::remove_file(&helper_path);
+        let _ = fs::remove_file(tmp.join("config.json"));
+        let _ = fs::remove_dir(&tmp);
+        if let Some(p) = old_path {
+            std::env::set_var("PATH", p);
+        }
+
+        match auth {
+            Some(oci_client::secrets::RegistryAuth::Basic(u, p)) => {
+                assert_eq!(u, "u_test");
+                assert_eq!(p, "s_test");
+            }
+            other => panic!("expected Basic auth, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn auths_base64_is_used_when_allowed() {
+        let tmp = std::env::temp_dir().join(format!(
+            "wassette-test-auths-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_millis()
+        ));
+        let _ = fs::create_dir_all(&tmp);
+
+        let encoded = base64::engine::general_purpose::STANDARD.encode("user_x:pass_x");
+        let config = format!(
+            r#"{{ "auths": {{ "example.com": {{ "auth": "{}" }} }} }}"#,
+            encoded
+        );
+        write_config(&tmp, &config);
+
+        let old = std::env::var_os("WASSETTE_ALLOW_INSECURE_DOCKER_AUTH");
+        std::env::set_var("WASSETTE_ALLOW_INSECURE_DOCKER_AUTH", "1");
+        std::env::set_var("DOCKER_CONFIG", &tmp);
+
+        let auth = LifecycleManager::try_read_docker_config_auth("example.com");
+
+        // cleanup
+        let _ = fs::remove_file(tmp.join("config.json"));
+        let _ = fs::remove_dir(&tmp);
+        if let Some(v) = old {
+            std::env::set_var("WASSETTE_ALLOW_INSECURE_DOCKER_AUTH", v);
+        } else {
+            std::env::remove_var("WASSETTE_ALLOW_INSECURE_DOCKER_AUTH");
+        }
+
+        match auth {
+            Some(oci_client::secrets::RegistryAuth::Basic(u, p)) => {
+                assert_eq!(u, "user_x");
+                assert_eq!(p, "pass_x");
+            }
+            other => panic!("expected Basic auth, got: {:?}", other),
+        }
+    }
+}
+// Load components in parallel for improved startup performance
+async fn load_components_parallel(
+    component_dir: &Path,
+    runtime: Arc<RuntimeContext>,
+    filter: &ComponentFilter,
+) -> Result<Vec<(ComponentInstance, String)>> {
+    let mut entries = tokio::fs::read_dir(component_dir).await?;
+    let mut load_futures = Vec::new();
+
+    while let Some(entry) = entries.next_entry().await? {
+        let entry_path = entry.path();
+        let Some(component_id) = component_id_from_path(&entry_path) else {
+            continue;
+        };
+        if !filter.allows(&component_id) {
+            continue;
+        }
+        let runtime_clone = Arc::clone(&runtime);
+        load_futures.push(load_component_from_path(
+            runtime_clone,
+            component_id,
+            entry_path,
+        ));
+    }
+
+    let results = futures::future::join_all(load_futures).await;
+    let mut components = Vec::new();
+
+    for result in results {
+        match result {
+            Ok(component) => components.push(component),
+            Err(e) => warn!("Failed to load component: {}", e),
+        }
+    }
+
+    Ok(components)
+}
+
+impl LifecycleManager {
+    /// Get the secrets manager
+    pub fn secrets_manager(&self) -> &SecretsManager {
+        &self.secrets_manager
+    }
+
+    /// List secrets for a component
+    pub async fn list_component_secrets(
+        &self,
+        component_id: &str,
+        show_values: bool,
+    ) -> Result<std::collections::HashMap<String, Option<String>>> {
+        self.secrets_manager
+            .list_component_secrets(component_id, show_values)
+            .await
+    }
+
+    /// Set secrets for a component
+    pub async fn set_component_secrets(
+        &self,
+        component_id: &str,
+        secrets: &[(String, String)],
+    ) -> Result<()> {
+        path_guard::validate_component_id(component_id)?;
+        // Check if component exists in the component directory
+        let component_path = self.component_path(component_id);
+        if !component_path.is_file() {
+            bail!("Component not found: {}", component_id);
+        }
+
+        self.secrets_manager
+            .set_component_secrets(component_id, secrets)
+            .await?;
+        self.instance_pool.invalidate(component_id);
+        self.events.emit(LifecycleEvent::SecretChanged {
+            component_id: component_id.to_string(),
+            keys: secrets.iter().map(|(key, _)| key.clone()).collect(),
+        });
+        Ok(())
+    }
+
+    /// Delete secrets for a component
+    pub async fn delete_component_secrets(
+        &self,
+        component_id: &str,
+        keys: &[String],
+    ) -> Result<()> {
+        self.secrets_manager
+            .delete_component_secrets(component_id, keys)
+            .await?;
+        self.instance_pool.invalidate(component_id);
+        self.events.emit(LifecycleEvent::SecretChanged {
+            component_id: component_id.to_string(),
+            keys: keys.to_vec(),
+        });
+        Ok(())
+    }
+
+    /// Load secrets for a component as environment variables
+    pub async fn load_component_secrets(
+        &self,
+        component_id: &str,
+    ) -> Result<std::collections::HashMap<String, String>> {
+        self.secrets_manager
+            .load_component_secrets(component_id)
+            .await
+    }
+}
+
+async fn load_component_from_path(
+    runtime: Arc<RuntimeContext>,
+    name: String,
+    entry_path: PathBuf,
+) -> Result<(ComponentInstance, String)> {
+    let start_time = Instant::now();
+
+    // Read wasm bytes to extract package docs
+    let wasm_bytes = tokio::fs::read(&entry_path)
+        .await
+        .context("Failed to read wasm file")?;
+
+    // Extract package docs before spawning blocking task
+    let package_docs = extract_package_docs(&wasm_bytes);
+
+    let runtime_for_component = Arc::clone(&runtime);
+    let component = tokio::task::spawn_blocking(move || {
+        Component::from_file(runtime_for_component.as_ref(), entry_path)
+    })
+    .await??;
+    info!(component_id = %name, elapsed = ?start_time.elapsed(), "component loaded");
+    let instance_pre = runtime.instantiate_pre(&component)?;
+    Ok((
+        ComponentInstance {
+            component: Arc::new(component),
+            instance_pre: Arc::new(instance_pre),
+            package_docs,
+            health: None,
+            schema_hash: None,
+        },
+        name,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    use policy::PolicyParser;
+    use test_log::test;
+
+    use super::*;
+    use crate::testing::{self, TestHarness};
+
+    pub(crate) const TEST_COMPONENT_ID: &str = "fetch_rs";
+
+    impl TestHarness {
+        pub(crate) async fn load_test_component(&self) -> Result<()> {
+            self.load_path(build_example_component().await?).await?;
+            Ok(())
+        }
+    }
+
+    pub(crate) async fn create_test_manager() -> Result<TestHarness> {
+        TestHarness::new().await
+    }
+
+    /// Like [`create_test_manager`], with extra builder settings applied.
+    pub(crate) async fn create_test_manager_with(
+        configure: impl FnOnce(LifecycleBuilder) -> LifecycleBuilder,
+    ) -> Result<TestHarness> {
+        TestHarness::with_builder(configure).await
+    }
+
+    pub(crate) async fn build_example_component() -> Result<PathBuf> {
+        testing::build_rust_component(
+            std::env::current_dir()?.join("../../examples/fetch-rs"),
+            "fetch_rs",
+        )
+    }
+
+    #[test(tokio::test)]
+    async fn test_lifecycle_manager_tool_registry() -> Result<()> {
+        let manager = create_test_manager().await?;
+
+        let temp_dir = tempfile::tempdir()?;
+        let component_path = temp_dir.path().join("mock_component.wasm");
+        std::fs::write(&component_path, b"mock wasm bytes")?;
+
+        let load_result = manager
+            .load_component(component_path.to_str().unwrap())
+            .await;
+        assert!(load_result.is_err()); // Expected since we're using invalid WASM
+
+        let lookup_result = manager.get_component_id_for_tool("non-existent").await;
+        assert!(lookup_result.is_err());
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_list_tools_shares_schemas_with_registry() -> Result<()> {
+        let registry = ComponentRegistry::new();
+        for component in 0..5 {
+            let tools = (0..100)
+                .map(|tool| {
+                    let name = format!("component-{component}-tool-{tool}");
+                    ToolMetadata {
+                        identifier: FunctionIdentifier {
+                            package_name: None,
+                            interface_name: None,
+                            function_name: name.clone(),
+                        },
+                        normalized_name: name.clone(),
+                        schema: serde_json::json!({
+                            "name": name,
+                            "inputSchema": {"type": "object", "properties": {"input": {"type": "string"}}},
+                            "outputSchema": {"type": "object", "properties": {"result": {"type": "string"}}},
+                        }),
+                    }
+                })
+                .collect();
+            registry
+                .register_metadata_if_absent(
+                    &format!("component-{component}"),
+                    tools,
+                    ValidationStamp {
+                        file_size: 0,
+                        mtime: 0,
+                        content_hash: None,
+                    },
+                    ToolCollisionStrategy::Allow,
+                )
+                .await?;
+        }
+
+        let first = registry.list_tools().await;
+        let second = registry.list_tools().await;
+        assert_eq!(first.len(), 500);
+        // Listing hands out references to the stored schemas instead of deep copies
+        for (a, b) in first.iter().zip(second.iter()) {
+            assert!(Arc::ptr_eq(a, b));
+        }
+        assert!(first.iter().all(|schema| Arc::strong_count(schema) == 3));
+
+        let names = registry.list_tool_names().await;
+        assert_eq!(names.len(), 500);
+        assert!(names.windows(2).all(|pair| pair[0] < pair[1]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tool_changes_between_versions() {
+        let tool = |name: &str, description: &str| ToolMetadata {
+            identifier: FunctionIdentifier {
+                package_name: None,
+                interface_name: None,
+                function_name: name.to_string(),
+            },
+            normalized_name: name.to_string(),
+            schema: serde_json::json!({"name": name, "description": description}),
+        };
+        let mut state = ComponentRegistryState::default();
+        state.register_tools_only(
+            "weather",
+            vec![
+                tool("forecast", "v1"),
+                tool("alerts", "v1"),
+                tool("radar", "v1"),
+            ],
+        );
+
+        let changes = state.tool_changes(
+            "weather",
+            &[
+                tool("forecast", "v1"),
+                tool("alerts", "v2"),
+                tool("history", "v1"),
+            ],
+        );
+        assert_eq!(
+            changes,
+            ToolChanges {
+                added: vec!["history".to_string()],
+                removed: vec!["radar".to_string()],
+                changed: vec!["alerts".to_string()],
+                ..Default::default()
+            }
+        );
+
+        let changes = state.tool_changes("other", &[tool("forecast", "v1")]);
+        assert_eq!(changes.added, vec!["forecast".to_string()]);
+        assert!(changes.removed.is_empty() && changes.changed.is_empty());
+    }
+
+    #[test(tokio::test)]
+    async fn test_manifest_tracks_loads_and_restores() -> Result<()> {
+        let original = create_test_manager().await?;
+        original.load_test_component().await?;
+
+        let manifest = ComponentManifest::load(original.manifest_path()).await?;
+        let entry = manifest
+            .components
+            .get(TEST_COMPONENT_ID)
+            .expect("loaded component should be recorded");
+        assert!(entry.source_uri.starts_with("file://"));
+        assert!(entry.digest.starts_with("sha256:"));
+
+        // A fresh component directory simulates a wiped ephemeral container.
+        let restored = create_test_manager().await?;
+        let report = restored
+            .restore_from_manifest(original.manifest_path())
+            .await?;
+        assert_eq!(report.restored, vec![TEST_COMPONENT_ID.to_string()]);
+        assert!(report.failed.is_empty());
+        assert_eq!(restored.list_components().await, vec![TEST_COMPONENT_ID]);
+
+        let report = restored
+            .restore_from_manifest(restored.manifest_path())
+            .await?;
+        assert!(report.restored.is_empty());
+        assert_eq!(report.unchanged, vec![TEST_COMPONENT_ID.to_string()]);
+
+        restored.unload_component(TEST_COMPONENT_ID).await?;
+        let manifest = ComponentManifest::load(restored.manifest_path()).await?;
+        assert!(manifest.components.is_empty());
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_restore_prefers_local_policy_copy() -> Result<()> {
+        let original = create_test_manager().await?;
+        original.load_test_component().await?;
+        let policy_dir = tempfile::tempdir()?;
+        let policy_path = policy_dir.path().join("policy.yaml");
+        std::fs::write(
+            &policy_path,
+            "version: \"1.0\"\npermissions:\n  network:\n    allow:\n      - host: \"example.com\"\n",
+        )?;
+        original
+            .attach_policy(
+                TEST_COMPONENT_ID,
+                &format!("file://{}", policy_path.display()),
+            )
+            .await?;
+        let info = original.get_policy_info(TEST_COMPONENT_ID).await.unwrap();
+        assert_eq!(info.origin, PolicyOrigin::Attached);
+
+        // Without a local copy the policy is fetched from its source again
+        let refetched = create_test_manager().await?;
+        let report = refetched
+            .restore_from_manifest(original.manifest_path())
+            .await?;
+        assert!(report.failed.is_empty());
+        let info = refetched.get_policy_info(TEST_COMPONENT_ID).await.unwrap();
+        assert_eq!(info.origin, PolicyOrigin::Refetched);
+
+        // The policy was attached from a temp file that is gone now
+        drop(policy_dir);
+        let missing = create_test_manager().await?;
+        let report = missing
+            .restore_from_manifest(original.manifest_path())
+            .await?;
+        assert_eq!(report.failed.len(), 1);
+        assert!(report.failed[0].error.contains("no local copy exists"));
+
+        // The local copy next to the component is used regardless
+        std::fs::remove_file(original.component_path(TEST_COMPONENT_ID))?;
+        let restarted = LifecycleManager::builder(original.component_root())
+            .with_eager_loading(false)
+            .build()
+            .await?;
+        let report = restarted
+            .restore_from_manifest(restarted.manifest_path())
+            .await?;
+        assert_eq!(report.restored, vec![TEST_COMPONENT_ID.to_string()]);
+        assert!(report.failed.is_empty());
+        let info = restarted.get_policy_info(TEST_COMPONENT_ID).await.unwrap();
+        assert_eq!(info.origin, PolicyOrigin::LocalCopy);
+        let template = restarted
+            .policy_manager
+            .template_for_component(TEST_COMPONENT_ID)
+            .await;
+        assert!(template.allowed_hosts.contains("example.com"));
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_component_ids_with_traversal_are_rejected() -> Result<()> {
+        let manager = create_test_manager().await?;
+        let outside = manager.component_root().join("../escape.wasm");
+        std::fs::write(&outside, b"not a component")?;
+
+        let error = manager.unload_component("../escape").await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ComponentError>(),
+            Some(ComponentError::InvalidId { .. })
+        ));
+        assert!(outside.exists());
+
+        let error = manager
+            .set_component_priority("%2e%2e%2fescape", Some(1))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ComponentError>(),
+            Some(ComponentError::InvalidId { .. })
+        ));
+
+        let id = "../escape";
+        let details = serde_json::json!({"host": "example.com"});
+        let secrets = [("KEY".to_string(), "value".to_string())];
+        let errors = [
+            manager
+                .grant_permission(id, "network", &details)
+                .await
+                .unwrap_err(),
+            manager.detach_policy(id).await.unwrap_err(),
+            manager.policy_history(id).await.unwrap_err(),
+            manager.ensure_component_loaded(id).await.unwrap_err(),
+            manager
+                .set_component_secrets(id, &secrets)
+                .await
+                .unwrap_err(),
+            manager.list_component_secrets(id, false).await.unwrap_err(),
+            manager
+                .delete_component_secrets(id, &["KEY".to_string()])
+                .await
+                .unwrap_err(),
+        ];
+        for error in errors {
+            assert!(
+                matches!(
+                    error.downcast_ref::<ComponentError>(),
+                    Some(ComponentError::InvalidId { .. })
+                ),
+                "unexpected error: {error:#}"
+            );
+        }
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_url_component_ids_are_deterministic() -> Result<()> {
+        let manager = create_test_manager().await?;
+        manager.load_test_component().await?;
+        let uri = "https://example.com/downloads/fetch_rs.wasm?version=3";
+
+        // The same content as the installed component keeps its ID
+        let same = DownloadedResource::Local(manager.component_path(TEST_COMPONENT_ID));
+        let (id, assignment) = manager
+            .assign_url_component_id(uri, TEST_COMPONENT_ID, &same)
+            .await?;
+        assert_eq!(id, TEST_COMPONENT_ID);
+        assert_eq!(assignment, IdAssignment::PathSegment);
+
+        // Different content under the same name gets a content hash suffix
+        let tempdir = tempfile::tempdir()?;
+        let other_path = tempdir.path().join("fetch_rs.wasm");
+        std::fs::write(&other_path, b"other artifact")?;
+        let other = DownloadedResource::Local(other_path.clone());
+        let (id, assignment) = manager
+            .assign_url_component_id(uri, TEST_COMPONENT_ID, &other)
+            .await?;
+        let digest = file_digest(&other_path).await?;
+        assert_eq!(id, format!("fetch_rs-{}", &digest["sha256:".len()..][..8]));
+        assert_eq!(assignment, IdAssignment::HashSuffixed);
+
+        // A URL recorded in a component's metadata keeps that component's ID
+        manager
+            .record_component_source(TEST_COMPONENT_ID, uri)
+            .await?;
+        let (id, assignment) = manager
+            .assign_url_component_id(uri, TEST_COMPONENT_ID, &other)
+            .await?;
+        assert_eq!(id, TEST_COMPONENT_ID);
+        assert_eq!(assignment, IdAssignment::Reused);
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_export_and_import_state() -> Result<()> {
+        let source_secrets = tempfile::tempdir()?;
+        let target_secrets = tempfile::tempdir()?;
+        let source =
+            create_test_manager_with(|builder| builder.with_secrets_dir(source_secrets.path()))
+                .await?;
+        source.load_test_component().await?;
+        source
+            .secrets_manager()
+            .set_component_secrets(TEST_COMPONENT_ID, &[("TOKEN".into(), "s3cret".into())])
+            .await?;
+
+        let archive_dir = tempfile::tempdir()?;
+        let archive = archive_dir.path().join("state.tar.zst");
+        let report = source
+            .export_state(&archive, StateExportOptions::default())
+            .await?;
+        assert_eq!(report.components, vec![TEST_COMPONENT_ID]);
+        assert!(report.secrets.is_empty());
+        assert!(report.manifest);
+
+        let target =
+            create_test_manager_with(|builder| builder.with_secrets_dir(target_secrets.path()))
+                .await?;
+        let options = StateImportOptions {
+            include_secrets: true,
+        };
+        let report = target.import_state(&archive, options.clone()).await?;
+        assert_eq!(report.imported, vec![TEST_COMPONENT_ID]);
+        assert!(report.failed.is_empty());
+        // Secrets were not exported, so there is nothing to import.
+        assert!(report.secrets.is_empty());
+        assert_eq!(target.list_components().await, vec![TEST_COMPONENT_ID]);
+        let manifest = ComponentManifest::load(target.manifest_path()).await?;
+        assert!(manifest.components.contains_key(TEST_COMPONENT_ID));
+
+        source
+            .export_state(
+                &archive,
+                StateExportOptions {
+                    include_secrets: true,
+                },
+            )
+            .await?;
+        let report = target.import_state(&archive, options).await?;
+        assert!(report.imported.is_empty());
+        assert_eq!(report.unchanged, vec![TEST_COMPONENT_ID]);
+        assert_eq!(report.secrets, vec![TEST_COMPONENT_ID]);
+        let secrets = target
+            .secrets_manager()
+            .load_component_secrets(TEST_COMPONENT_ID)
+            .await?;
+        assert_eq!(secrets.get("TOKEN").map(String::as_str), Some("s3cret"));
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_import_state_checks_components_like_a_load() -> Result<()> {
+        let (signing, trusted) = signature::tests::test_key("release@example.com");
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("no-tools.wasm");
+        std::fs::write(&path, "(component)")?;
+        let uri = format!("file://{}", path.display());
+        let source = create_test_manager().await?;
+        source.load_component(&uri).await?;
+        let archive = dir.path().join("state.tar.zst");
+        source
+            .export_state(&archive, StateExportOptions::default())
+            .await?;
+
+        // The archive is a file source like any other
+        let oci_only = create_test_manager_with(|builder| {
+            builder.with_allowed_sources(vec![AllowedSource::any("oci")])
+        })
+        .await?;
+        let error = oci_only
+            .import_state(&archive, StateImportOptions::default())
+            .await
+            .unwrap_err();
+        assert!(error.downcast_ref::<SourceNotAllowed>().is_some());
+
+        // Unsigned at its source: refused under an enforced policy
+        let enforced = create_test_manager_with(|builder| {
+            builder.with_signature_policy(SignaturePolicy::enforce([trusted.clone()]))
+        })
+        .await?;
+        let report = enforced
+            .import_state(&archive, StateImportOptions::default())
+            .await?;
+        assert!(report.imported.is_empty());
+        assert_eq!(report.failed[0].component_id, "no-tools");
+        assert!(report.failed[0].reason.contains("release@example.com"));
+        assert!(!enforced.component_path("no-tools").exists());
+
+        // Signed at its source: imported
+        std::fs::write(
+            dir.path().join("no-tools.wasm.sig"),
+            signature::tests::sign(&signing, b"(component)"),
+        )?;
+        let report = enforced
+            .import_state(&archive, StateImportOptions::default())
+            .await?;
+        assert_eq!(report.imported, vec!["no-tools"]);
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_subscribe_receives_load_and_unload_events() -> Result<()> {
+        let manager = create_test_manager().await?;
+        let mut events = manager.subscribe();
+
+        manager.load_test_component().await?;
+        match events.recv().await? {
+            LifecycleEvent::ComponentLoaded {
+                component_id,
+                tools,
+            } => {
+                assert_eq!(component_id, TEST_COMPONENT_ID);
+                assert!(!tools.is_empty());
+            }
+            other => panic!("expected ComponentLoaded, got {other:?}"),
+        }
+
+        manager.load_test_component().await?;
+        let replaced = events.recv().await?;
+        match &replaced {
+            LifecycleEvent::ComponentReplaced {
+                added_tools,
+                removed_tools,
+                changed_tools,
+                ..
+            } => {
+                assert!(added_tools.is_empty());
+                assert!(removed_tools.is_empty());
+                assert!(changed_tools.is_empty());
+            }
+            other => panic!("expected ComponentReplaced, got {other:?}"),
+        }
+        // Reloading an identical component does not change the tool list
+        assert!(!replaced.changes_tool_list());
+
+        manager.unload_component(TEST_COMPONENT_ID).await?;
+        assert_eq!(
+            events.recv().await?,
+            LifecycleEvent::ComponentUnloaded {
+                component_id: TEST_COMPONENT_ID.to_string()
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_background_loading_follows_priority() -> Result<()> {
+        let manager =
+            create_test_manager_with(|builder| builder.with_load_priority(["b", "c"])).await?;
+        for id in ["a", "b", "c", "d"] {
+            std::fs::write(
+                manager.component_root().join(format!("{id}.wasm")),
+                b"not a component",
+            )?;
+        }
+        manager.set_component_priority("d", Some(10)).await?;
+        assert_eq!(manager.get_component_priority("d"), 10);
+        assert_eq!(manager.get_component_priority("b"), 2);
+        assert!(manager
+            .set_component_priority("missing", Some(1))
+            .await
+            .is_err());
+
+        assert_eq!(
+            manager.background_load_status(),
+            BackgroundLoadStatus::default()
+        );
+        assert!(!manager.loading_status().await.ready);
+        let mut events = manager.subscribe();
+        manager.load_existing_components_async(Some(1)).await?;
+        assert_eq!(
+            manager.background_load_status(),
+            BackgroundLoadStatus {
+                started: true,
+                completed: true,
+                queued: 4,
+                processed: 4,
+            }
+        );
+        let status = manager.loading_status().await;
+        assert!(status.ready);
+        assert_eq!((status.loaded, status.known), (0, 4));
+        assert_eq!(
+            status.failures.keys().collect::<Vec<_>>(),
+            ["a", "b", "c", "d"]
+        );
+        assert_eq!(
+            manager.component_counts().await,
+            ComponentCounts {
+                loaded: 0,
+                known: 4,
+                tools: 0,
+            }
+        );
+
+        let mut order = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let LifecycleEvent::ComponentDisabled { component_id, .. } = event {
+                order.push(component_id);
+            }
+        }
+        assert_eq!(order, ["d", "b", "c", "a"]);
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_warmup_runs_in_background_after_load() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let manager = LifecycleManager::builder(tempdir.path())
+            .with_component_warmup(
+                TEST_COMPONENT_ID,
+                WarmupConfig {
+                    instances: 2,
+                    tool: Some("missing-tool".to_string()),
+                },
+            )
+            .build()
+            .await?;
+        let mut events = manager.subscribe();
+
+        let component_path = build_example_component().await?;
+        manager
+            .load_component(&format!("file://{}", component_path.display()))
+            .await?;
+
+        let report = loop {
+            match events.recv().await? {
+                LifecycleEvent::ComponentWarmedUp {
+                    component_id,
+                    report,
+                } => {
+                    assert_eq!(component_id, TEST_COMPONENT_ID);
+                    break report;
+                }
+                _ => continue,
+            }
+        };
+        assert_eq!(report.instances, 2);
+        assert_eq!(report.tool_ms, None);
+        assert!(report
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("does not export tool missing-tool"));
+        assert_eq!(
+            manager.get_warmup_report(TEST_COMPONENT_ID).await,
+            Some(report)
+        );
+
+        manager.unload_component(TEST_COMPONENT_ID).await?;
+        assert!(manager.get_warmup_report(TEST_COMPONENT_ID).await.is_none());
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_warmup_fills_instance_pool() -> Result<()> {
+        let manager = create_test_manager_with(|builder| {
+            builder.with_instance_pool(2).with_component_warmup(
+                TEST_COMPONENT_ID,
+                WarmupConfig {
+                    instances: 2,
+                    tool: None,
+                },
+            )
+        })
+        .await?;
+        let mut events = manager.subscribe();
+        manager.load_test_component().await?;
+
+        let report = loop {
+            if let LifecycleEvent::ComponentWarmedUp { report, .. } = events.recv().await? {
+                break report;
+            }
+        };
+        assert_eq!(report.instances, 2);
+        assert_eq!(manager.instance_pool.idle(TEST_COMPONENT_ID), 2);
+
+        // The first call already runs on a warmed instance
+        let (_, timing) = manager
+            .execute_component_call_timed(
+                TEST_COMPONENT_ID,
+                "fetch",
+                r#"{"url": "https://denied.test/"}"#,
+            )
+            .await?;
+        assert!(timing.pooled);
+        assert_eq!(timing.instantiation_ms, 0);
+        assert_eq!(manager.instance_pool.idle(TEST_COMPONENT_ID), 2);
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_new_manager() -> Result<()> {
+        let _manager = create_test_manager().await?;
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_disallowed_source_is_rejected_before_fetching() -> Result<()> {
+        let manager = create_test_manager_with(|builder| {
+            builder.with_allowed_sources([AllowedSource::new("oci", "registry.internal/**")])
+        })
+        .await?;
+
+        let fixture = std::env::current_dir()?
+            .join("../component2json/testdata/fetch-rs.wasm")
+            .canonicalize()?;
+        let error = manager
+            .load_component(&format!("file://{}", fixture.display()))
+            .await
+            .expect_err("file:// source should be rejected");
+        let rejected = error
+            .downcast_ref::<SourceNotAllowed>()
+            .expect("error should be SourceNotAllowed");
+        assert_eq!(rejected.allowed, ["oci://registry.internal/**"]);
+        assert!(manager.list_components_known().await.is_empty());
+        assert!(manager
+            .check_component_source("oci://registry.internal/team/tool:1")
+            .is_ok());
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_policy_sources_are_checked_before_attaching() -> Result<()> {
+        let component_dir = tempfile::tempdir()?;
+        let policy_dir = tempfile::tempdir()?;
+        let allowed_dir = policy_dir.path().join("allowed");
+        let other_dir = policy_dir.path().join("other");
+        std::fs::create_dir_all(&allowed_dir)?;
+        std::fs::create_dir_all(&other_dir)?;
+        let policy_content = r#"
+version: "1.0"
+permissions:
+  network:
+    allow:
+      - host: "example.com"
+"#;
+        std::fs::write(allowed_dir.join("policy.yaml"), policy_content)?;
+        std::fs::write(other_dir.join("policy.yaml"), policy_content)?;
+
+        let manager = LifecycleManager::builder(component_dir.path())
+            .with_allowed_sources([AllowedSource::any("file")])
+            .with_allowed_policy_sources([AllowedSource::new(
+                "file",
+                format!("{}/**", allowed_dir.display()),
+            )])
+            .with_eager_loading(false)
+            .build()
+            .await?;
+        let fixture = std::env::current_dir()?
+            .join("../component2json/testdata/fetch-rs.wasm")
+            .canonicalize()?;
+        let component_id = manager
+            .load_component(&format!("file://{}", fixture.display()))
+            .await?
+            .component_id;
+
+        let error = manager
+            .attach_policy(
+                &component_id,
+                &format!("file://{}", other_dir.join("policy.yaml").display()),
+            )
+            .await
+            .expect_err("policy outside the allowed directory should be rejected");
+        let rejected = error
+            .downcast_ref::<SourceNotAllowed>()
+            .expect("error should be SourceNotAllowed");
+        assert_eq!(rejected.resource, "policies");
+        assert!(manager.get_policy_info(&component_id).await.is_none());
+
+        manager
+            .attach_policy(
+                &component_id,
+                &format!("file://{}", allowed_dir.join("policy.yaml").display()),
+            )
+            .await?;
+        assert!(manager.get_policy_info(&component_id).await.is_some());
+        drop(manager);
+
+        // The policy file now next to the component is restored even though
+        // no policy source is allowed any more.
+        let restarted = LifecycleManager::builder(component_dir.path())
+            .with_allowed_policy_sources([AllowedSource::any("oci")])
+            .build()
+            .await?;
+        let template = restarted
+            .policy_manager
+            .template_for_component(&component_id)
+            .await;
+        assert!(template.allowed_hosts.contains("example.com"));
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_policy_rollback_restores_revision() -> Result<()> {
+        let manager = create_test_manager().await?;
+        let fixture = std::env::current_dir()?
+            .join("../component2json/testdata/fetch-rs.wasm")
+            .canonicalize()?;
+        let component_id = manager
+            .load_component(&format!("file://{}", fixture.display()))
+            .await?
+            .component_id;
+        let mut events = manager.subscribe();
+
+        manager
+            .grant_permission(
+                &component_id,
+                "network",
+                &serde_json::json!({"host": "api.example.com"}),
+            )
+            .await?;
+        manager
+            .grant_permission(
+                &component_id,
+                "network",
+                &serde_json::json!({"host": "evil.example"}),
+            )
+            .await?;
+        let history = manager.policy_history(&component_id).await?;
+        let actions: Vec<&str> = history.iter().map(|r| r.action.as_str()).collect();
+        assert_eq!(actions, ["grant network", "grant network"]);
+
+        manager
+            .restore_policy_revision(&component_id, history[0].revision)
+            .await?;
+        let template = manager
+            .policy_manager
+            .template_for_component(&component_id)
+            .await;
+        assert!(template.allowed_hosts.contains("api.example.com"));
+        assert!(!template.allowed_hosts.contains("evil.example"));
+        let on_disk = std::fs::read_to_string(manager.get_component_policy_path(&component_id))?;
+        assert!(!on_disk.contains("evil.example"));
+
+        let history = manager.policy_history(&component_id).await?;
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[2].action, "rollback to 1");
+        assert!(manager
+            .restore_policy_revision(&component_id, 42)
+            .await
+            .is_err());
+
+        let mut rolled_back = None;
+        while let Ok(event) = events.try_recv() {
+            if let LifecycleEvent::PolicyRolledBack { revision, .. } = event {
+                rolled_back = Some(revision);
+            }
+        }
+        assert_eq!(rolled_back, Some(1));
+
+        manager.unload_component(&component_id).await?;
+        assert!(manager.policy_history(&component_id).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_policy_fragments_are_merged_and_updated() -> Result<()> {
+        let manager = create_test_manager().await?;
+        let fixture = std::env::current_dir()?
+            .join("../component2json/testdata/fetch-rs.wasm")
+            .canonicalize()?;
+        let component_id = manager
+            .load_component(&format!("file://{}", fixture.display()))
+            .await?
+            .component_id;
+        let network_path = manager
+            .policy_manager
+            .fragment_path(&component_id, PolicyCategory::Network);
+        std::fs::create_dir_all(network_path.parent().unwrap())?;
+        std::fs::write(
+            &network_path,
+            "version: \"1.0\"\npermissions:\n  network:\n    allow:\n      - host: \"api.example.com\"\n",
+        )?;
+
+        manager
+            .grant_permission(
+                &component_id,
+                "network",
+                &serde_json::json!({"host": "cdn.example.com"}),
+            )
+            .await?;
+        manager
+            .grant_permission(
+                &component_id,
+                "environment-variable",
+                &serde_json::json!({"key": "API_KEY"}),
+            )
+            .await?;
+
+        let fragment = std::fs::read_to_string(&network_path)?;
+        assert!(fragment.contains("api.example.com"));
+        assert!(fragment.contains("cdn.example.com"));
+        let main = std::fs::read_to_string(manager.get_component_policy_path(&component_id))?;
+        assert!(main.contains("API_KEY"));
+        assert!(!main.contains("example.com"));
+
+        let restored = manager
+            .policy_manager
+            .template_from_disk(&component_id)
+            .await?
+            .expect("policy restored from disk");
+        assert!(restored.allowed_hosts.contains("api.example.com"));
+        assert!(restored.allowed_hosts.contains("cdn.example.com"));
+
+        let info = manager.get_policy_info(&component_id).await.unwrap();
+        assert_eq!(
+            info.permission_sources[&PolicyCategory::Network],
+            network_path
+        );
+        assert_eq!(
+            info.permission_sources[&PolicyCategory::Environment],
+            manager.get_component_policy_path(&component_id)
+        );
+
+        let history = manager.policy_history(&component_id).await?;
+        assert_eq!(history[0].fragment, Some(PolicyCategory::Network));
+        assert_eq!(history[1].fragment, None);
+
+        manager.unload_component(&component_id).await?;
+        assert!(!network_path.exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_policy_fragments_do_not_collide_with_dotted_ids() -> Result<()> {
+        let manager = create_test_manager().await?;
+        let fixture = std::env::current_dir()?
+            .join("../component2json/testdata/fetch-rs.wasm")
+            .canonicalize()?;
+        let sources = tempfile::tempdir()?;
+        for id in ["x", "x.network"] {
+            let path = sources.path().join(format!("{id}.wasm"));
+            std::fs::copy(&fixture, &path)?;
+            manager
+                .load_component(&format!("file://{}", path.display()))
+                .await?;
+        }
+
+        // The network fragment of `x` is not the policy file of `x.network`
+        let fragment_path = manager
+            .policy_manager
+            .fragment_path("x", PolicyCategory::Network);
+        std::fs::create_dir_all(fragment_path.parent().unwrap())?;
+        std::fs::write(
+            &fragment_path,
+            "version: \"1.0\"\npermissions:\n  network:\n    allow:\n      - host: \"x.example.com\"\n",
+        )?;
+        assert_ne!(
+            fragment_path,
+            manager.get_component_policy_path("x.network")
+        );
+
+        manager
+            .grant_permission(
+                "x.network",
+                "network",
+                &serde_json::json!({"host": "dotted.example.com"}),
+            )
+            .await?;
+        manager
+            .grant_permission(
+                "x",
+                "network",
+                &serde_json::json!({"host": "cdn.example.com"}),
+            )
+            .await?;
+
+        let x = manager
+            .policy_manager
+            .template_from_disk("x")
+            .await?
+            .expect("policy of x restored from disk");
+        assert!(x.allowed_hosts.contains("x.example.com"));
+        assert!(x.allowed_hosts.contains("cdn.example.com"));
+        assert!(!x.allowed_hosts.contains("dotted.example.com"));
+        let dotted = manager
+            .policy_manager
+            .template_from_disk("x.network")
+            .await?
+            .expect("policy of x.network restored from disk");
+        assert!(dotted.allowed_hosts.contains("dotted.example.com"));
+        assert!(!dotted.allowed_hosts.contains("x.example.com"));
+        assert!(!dotted.allowed_hosts.contains("cdn.example.com"));
+
+        manager.unload_component("x.network").await?;
+        assert!(fragment_path.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_sources_default_to_component_sources() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let sources = [AllowedSource::new("oci", "registry.internal/**")];
+        let inherited = LifecycleManager::builder(&tempdir)
+            .with_allowed_sources(sources.clone())
+            .build_config()?;
+        assert_eq!(inherited.allowed_policy_sources(), sources);
+
+        let separate = LifecycleManager::builder(&tempdir)
+            .with_allowed_sources(sources)
+            .with_allowed_policy_sources([])
+            .build_config()?;
+        assert!(separate.allowed_policy_sources().is_empty());
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_load_and_unload_component() -> Result<()> {
+        let manager = create_test_manager().await?;
+
+        let load_result = manager.load_component("/path/to/nonexistent").await;
+        assert!(load_result.is_err());
+
+        manager.load_test_component().await?;
+
+        let loaded_components = manager.list_components().await;
+        assert_eq!(loaded_components.len(), 1);
+
+        manager.unload_component(TEST_COMPONENT_ID).await?;
+
+        let loaded_components = manager.list_components().await;
+        assert!(loaded_components.is_empty());
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_get_component() -> Result<()> {
+        let manager = create_test_manager().await?;
+        assert!(manager.get_component("non-existent").await.is_none());
+
+        manager.load_test_component().await?;
+
+        manager
+            .get_component(TEST_COMPONENT_ID)
+            .await
+            .expect("Should be able to get a component we just loaded");
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_get_component_docs() -> Result<()> {
+        let manager = create_test_manager().await?;
+        assert!(manager.get_component_docs("non-existent").await.is_none());
+
+        // The example build does not embed package docs
+        manager.load_test_component().await?;
+        assert!(manager
+            .get_component_docs(TEST_COMPONENT_ID)
+            .await
+            .is_none());
+
+        let documented = std::env::current_dir()?.join("../component2json/testdata/fetch-rs.wasm");
+        let outcome = manager
+            .load_component(&format!("file://{}", documented.display()))
+            .await?;
+        let docs = manager
+            .get_component_docs(&outcome.component_id)
+            .await
+            .expect("testdata component has package docs");
+        assert!(docs.contains("Fetch data from a URL"));
+
+        let metadata = manager
+            .load_component_metadata(&outcome.component_id)
+            .await?
+            .expect("metadata is saved on load");
+        assert_eq!(metadata.docs.as_deref(), Some(docs.as_str()));
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_component_filter_excludes_components() -> Result<()> {
+        let manager = create_test_manager_with(|builder| {
+            builder.with_component_filter(ComponentFilter::allow(["served-*"]))
+        })
+        .await?;
+        for id in ["served-a", "ignored"] {
+            std::fs::write(
+                manager.component_root().join(format!("{id}.wasm")),
+                b"not a component",
+            )?;
+        }
+        assert_eq!(manager.list_components_known().await, ["served-a"]);
+
+        let mut events = manager.subscribe();
+        manager.load_existing_components_async(Some(1)).await?;
+        let mut disabled = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let LifecycleEvent::ComponentDisabled { component_id, .. } = event {
+                disabled.push(component_id);
+            }
+        }
+        assert_eq!(disabled, ["served-a"]);
+
+        let error = manager
+            .ensure_component_loaded("ignored")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ComponentError>(),
+            Some(ComponentError::Filtered { component_id }) if component_id == "ignored"
+        ));
+        let outside = tempfile::tempdir()?;
+        let path = outside.path().join("ignored.wasm");
+        std::fs::write(&path, b"not a component")?;
+        let error = manager
+            .load_component(&format!("file://{}", path.display()))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("filtered by configuration"));
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_load_failure_that_may_pass_is_retried() -> Result<()> {
+        let manager = create_test_manager_with(|builder| {
+            builder.with_tool_collision_strategy(ToolCollisionStrategy::Reject)
+        })
+        .await?;
+        manager.load_test_component().await?;
+        std::fs::copy(
+            build_example_component().await?,
+            manager.component_root().join("fetch-copy.wasm"),
+        )?;
+
+        // The file compiles, but its tools collide with a loaded component
+        let error = manager
+            .ensure_component_loaded("fetch-copy")
+            .await
+            .unwrap_err();
+        assert!(!matches!(
+            error.downcast_ref::<ComponentError>(),
+            Some(ComponentError::LoadFailed { .. })
+        ));
+        assert!(matches!(
+            manager.get_component_load_state("fetch-copy").await,
+            Some(ComponentLoadState::Failed { .. })
+        ));
+
+        // Without the collision, the unchanged file loads
+        manager.unload_component(TEST_COMPONENT_ID).await?;
+        manager.ensure_component_loaded("fetch-copy").await?;
+        assert_eq!(
+            manager.get_component_id_for_tool("fetch").await?,
+            "fetch-copy"
+        );
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_load_state_records_failure_and_skips_recompile() -> Result<()> {
+        let manager = create_test_manager().await?;
+        let broken_path = manager.component_root().join("broken.wasm");
+        std::fs::write(&broken_path, b"not a component")?;
+        assert_eq!(
+            manager.get_component_load_state("broken").await,
+            Some(ComponentLoadState::Discovered)
+        );
+
+        let error = manager.ensure_component_loaded("broken").await.unwrap_err();
+        assert!(error.downcast_ref::<ComponentError>().is_none());
+        let state = manager.get_component_load_state("broken").await.unwrap();
+        assert!(matches!(state, ComponentLoadState::Failed { .. }));
+
+        // The unchanged file is not compiled again
+        let error = manager.ensure_component_loaded("broken").await.unwrap_err();
+        match error.downcast_ref::<ComponentError>() {
+            Some(ComponentError::LoadFailed { reason, .. }) => {
+                assert_eq!(Some(reason.as_str()), state.reason())
+            }
+            other => panic!("expected recorded load failure, got {other:?}"),
+        }
+
+        // A replaced file is attempted again
+        std::fs::write(&broken_path, b"still not a component")?;
+        let error = manager.ensure_component_loaded("broken").await.unwrap_err();
+        assert!(error.downcast_ref::<ComponentError>().is_none());
+
+        let component = std::env::current_dir()?.join("../component2json/testdata/fetch-rs.wasm");
+        let outcome = manager
+            .load_component(&format!("file://{}", component.display()))
+            .await?;
+        assert_eq!(
+            manager
+                .get_component_load_state(&outcome.component_id)
+                .await,
+            Some(ComponentLoadState::Loaded)
+        );
+        manager.unload_component(&outcome.component_id).await?;
+        assert_eq!(
+            manager
+                .get_component_load_state(&outcome.component_id)
+                .await,
+            None
+        );
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_precompiled_artifact_records_toolchain() -> Result<()> {
+        let manager = create_test_manager().await?;
+        let component = std::env::current_dir()?.join("../component2json/testdata/fetch-rs.wasm");
+        let outcome = manager
+            .load_component(&format!("file://{}", component.display()))
+            .await?;
+        let id = outcome.component_id;
+        let current = manager.runtime_compile_info().clone();
+        assert_eq!(
+            manager.component_compile_info(&id).await,
+            Some(current.clone())
+        );
+
+        // An artifact from another toolchain is recompiled, not deserialized
+        let mut metadata = manager.load_component_metadata(&id).await?.unwrap();
+        metadata.compiled_with = Some(CompileInfo {
+            wasmtime_version: "0.0.0".to_string(),
+            ..current.clone()
+        });
+        manager.storage.write_metadata(&metadata).await?;
+        let wasm_path = manager.component_path(&id);
+        let (_, _, compiled_with) = manager.load_component_optimized(&wasm_path, &id).await?;
+        assert_eq!(compiled_with, Some(current));
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_duplicate_component_id() -> Result<()> {
+        let manager = create_test_manager().await?;
+
+        manager.load_test_component().await?;
+
+        let components = manager.list_components().await;
+        assert_eq!(components.len(), 1);
+        assert_eq!(components[0], TEST_COMPONENT_ID);
+
+        // Load again and make sure we still only have one
+
+        manager.load_test_component().await?;
+        let components = manager.list_components().await;
+        assert_eq!(components.len(), 1);
+        assert_eq!(components[0], TEST_COMPONENT_ID);
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_component_reload() -> Result<()> {
+        let manager = create_test_manager().await?;
+        let component_path = build_example_component().await?;
+
+        manager
+            .load_component(&format!("file://{}", component_path.to_str().unwrap()))
+            .await?;
+
+        let component_id = manager.get_component_id_for_tool("fetch").await?;
+        assert_eq!(component_id, TEST_COMPONENT_ID);
+
+        manager
+            .load_component(&format!("file://{}", component_path.to_str().unwrap()))
+            .await?;
+
+        let component_id = manager.get_component_id_for_tool("fetch").await?;
+        assert_eq!(component_id, TEST_COMPONENT_ID);
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_component_without_tools_is_reported() -> Result<()> {
+        let manager = create_test_manager().await?;
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("no-tools.wasm");
+        std::fs::write(
+            &path,
+            r#"(component
+                (core module $m)
+                (export "m" (core module $m))
+            )"#,
+        )?;
+
+        let outcome = manager
+            .load_component(&format!("file://{}", path.display()))
+            .await?;
+        assert!(outcome.tool_names.is_empty());
+        assert_eq!(
+            outcome.warnings,
+            ["Component 'no-tools' loaded, but exposes no tools; exports found: m (core module)"]
+        );
+        assert_eq!(outcome.skipped_exports.len(), 1);
+
+        let outcome = manager
+            .load_component(&format!(
+                "file://{}",
+                build_example_component().await?.display()
+            ))
+            .await?;
+        assert!(outcome.warnings.is_empty() && outcome.skipped_exports.is_empty());
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_cancelled_load_leaves_nothing_behind() -> Result<()> {
+        let manager = create_test_manager().await?;
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("no-tools.wasm");
+        std::fs::write(&path, "(component)")?;
+        let uri = format!("file://{}", path.display());
+
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        let error = manager
+            .load_component_with_cancellation(&uri, &cancellation)
+            .await
+            .unwrap_err();
+        assert!(
+            error.downcast_ref::<DownloadCancelled>().is_some(),
+            "unexpected error: {error:#}"
+        );
+        assert!(!manager.component_path("no-tools").exists());
+        assert!(manager.list_components_known().await.is_empty());
+
+        manager
+            .load_component_with_cancellation(&uri, &CancellationToken::new())
+            .await?;
+        assert!(manager.component_path("no-tools").exists());
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_load_component_with_progress_reports_compiling() -> Result<()> {
+        let manager = create_test_manager().await?;
+        let uri = format!("file://{}", build_example_component().await?.display());
+        let recorded = progress::tests::RecordedProgress::default();
+        manager
+            .load_component_with_progress(&uri, &CancellationToken::new(), &recorded)
+            .await?;
+
+        // Local files are not downloaded, so only compilation is reported
+        assert_eq!(recorded.0.into_inner().unwrap(), [LoadProgress::Compiling]);
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_signature_policy_checks_detached_signatures() -> Result<()> {
+        let (signing, trusted) = signature::tests::test_key("release@example.com");
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("no-tools.wasm");
+        std::fs::write(&path, "(component)")?;
+        let uri = format!("file://{}", path.display());
+
+        // Unsigned: enforced policies refuse it and install nothing
+        let manager = create_test_manager_with(|builder| {
+            builder.with_signature_policy(SignaturePolicy::enforce([trusted.clone()]))
+        })
+        .await?;
+        let error = manager.load_component(&uri).await.unwrap_err();
+        let rejected = error.downcast_ref::<SignatureRejected>().unwrap();
+        assert_eq!(rejected.expected, ["release@example.com"]);
+        assert!(error.to_string().contains("release@example.com"));
+        assert!(!manager.component_path("no-tools").exists());
+
+        // Unsigned under a warn-only policy: loaded with a warning
+        let warn_only = create_test_manager_with(|builder| {
+            builder.with_signature_policy(SignaturePolicy::warn_only([trusted]))
+        })
+        .await?;
+        let outcome = warn_only.load_component(&uri).await?;
+        let expected = format!("The signature of '{uri}' was not verified: no signature found");
+        assert!(outcome
+            .warnings
+            .iter()
+            .any(|warning| warning.starts_with(&expected)));
+
+        // Signed with the trusted key: loaded
+        std::fs::write(
+            dir.path().join("no-tools.wasm.sig"),
+            signature::tests::sign(&signing, b"(component)"),
+        )?;
+        let outcome = manager.load_component(&uri).await?;
+        assert_eq!(outcome.component_id, "no-tools");
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_tool_collision_strategies() -> Result<()> {
+        let copy_dir = tempfile::tempdir()?;
+        let copy_path = copy_dir.path().join("fetch-copy.wasm");
+        std::fs::copy(build_example_component().await?, &copy_path)?;
+        let copy_uri = format!("file://{}", copy_path.display());
+
+        let manager = create_test_manager_with(|builder| {
+            builder.with_tool_collision_strategy(ToolCollisionStrategy::Reject)
+        })
+        .await?;
+        manager.load_test_component().await?;
+        let error = manager.load_component(&copy_uri).await.unwrap_err();
+        let Some(ComponentError::ToolCollision {
+            component_id,
+            collisions,
+        }) = error.downcast_ref::<ComponentError>()
+        else {
+            panic!("unexpected error: {error:#}");
+        };
+        assert_eq!(component_id, "fetch-copy");
+        assert_eq!(collisions[0].tool, "fetch");
+        assert_eq!(collisions[0].existing_components, [TEST_COMPONENT_ID]);
+        assert!(format!("{error:#}").contains("fetch (exported by fetch_rs)"));
+        assert_eq!(
+            manager.get_component_id_for_tool("fetch").await?,
+            TEST_COMPONENT_ID
+        );
+
+        let manager = create_test_manager_with(|builder| {
+            builder.with_tool_collision_strategy(ToolCollisionStrategy::Prefix)
+        })
+        .await?;
+        manager.load_test_component().await?;
+        for _ in 0..2 {
+            let outcome = manager.load_component(&copy_uri).await?;
+            let collisions = outcome.tool_collisions.unwrap();
+            assert_eq!(collisions.strategy, ToolCollisionStrategy::Prefix);
+            assert_eq!(
+                collisions.tools[0].registered_as.as_deref(),
+                Some("fetch-copy_fetch")
+            );
+            assert!(outcome.tool_names.contains(&"fetch-copy_fetch".to_string()));
+        }
+        assert_eq!(
+            manager.get_component_id_for_tool("fetch").await?,
+            TEST_COMPONENT_ID
+        );
+        assert_eq!(
+            manager
+                .get_component_id_for_tool("fetch-copy_fetch")
+                .await?,
+            "fetch-copy"
+        );
+
+        let manager = create_test_manager().await?;
+        manager.load_test_component().await?;
+        let outcome = manager.load_component(&copy_uri).await?;
+        let collisions = outcome.tool_collisions.unwrap();
+        assert_eq!(collisions.strategy, ToolCollisionStrategy::Allow);
+        assert_eq!(collisions.tools[0].registered_as.as_deref(), Some("fetch"));
+        assert!(manager.get_component_id_for_tool("fetch").await.is_err());
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_tool_collisions_survive_a_restart() -> Result<()> {
+        let copy_dir = tempfile::tempdir()?;
+        let copy_path = copy_dir.path().join("fetch-copy.wasm");
+        std::fs::copy(build_example_component().await?, &copy_path)?;
+        let copy_uri = format!("file://{}", copy_path.display());
+
+        // A rejected component leaves no files that would register it on
+        // the next start
+        let manager = create_test_manager_with(|builder| {
+            builder.with_tool_collision_strategy(ToolCollisionStrategy::Reject)
+        })
+        .await?;
+        manager.load_test_component().await?;
+        let error = manager.load_component(&copy_uri).await.unwrap_err();
+        assert!(!format!("{error:#}").contains("Failed to compile component"));
+        assert!(!manager.component_path("fetch-copy").exists());
+        assert!(!manager.storage.metadata_path("fetch-copy").exists());
+        assert_eq!(manager.registry.load_state("fetch-copy").await, None);
+
+        let restarted = LifecycleManager::builder(manager.component_root())
+            .with_tool_collision_strategy(ToolCollisionStrategy::Reject)
+            .with_eager_loading(false)
+            .build()
+            .await?;
+        restarted.populate_registry_from_metadata().await?;
+        assert_eq!(
+            restarted.get_component_id_for_tool("fetch").await?,
+            TEST_COMPONENT_ID
+        );
+        assert_eq!(restarted.registry.load_state("fetch-copy").await, None);
+
+        // Components are registered in load order, not by ID ("fetch-copy"
+        // sorts before "fetch_rs"), so the prefixed one stays prefixed
+        let manager = create_test_manager_with(|builder| {
+            builder.with_tool_collision_strategy(ToolCollisionStrategy::Prefix)
+        })
+        .await?;
+        manager.load_test_component().await?;
+        manager.load_component(&copy_uri).await?;
+        for _ in 0..2 {
+            let restarted = LifecycleManager::builder(manager.component_root())
+                .with_tool_collision_strategy(ToolCollisionStrategy::Prefix)
+                .with_eager_loading(false)
+                .build()
+                .await?;
+            restarted.populate_registry_from_metadata().await?;
+            assert_eq!(
+                restarted.get_component_id_for_tool("fetch").await?,
+                TEST_COMPONENT_ID
+            );
+            assert_eq!(
+                restarted
+                    .get_component_id_for_tool("fetch-copy_fetch")
+                    .await?,
+                "fetch-copy"
+            );
+        }
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_dir_watch_follows_files_changed_on_disk() -> Result<()> {
+        let manager = create_test_manager_with(|builder| builder.with_dir_watch(true)).await?;
+        let mut events = manager.subscribe();
+        async fn next_event(
+            events: &mut broadcast::Receiver<LifecycleEvent>,
+        ) -> Result<LifecycleEvent> {
+            Ok(tokio::time::timeout(Duration::from_secs(60), events.recv()).await??)
+        }
+
+        // Copied in two writes, as a slow copy would
+        let wasm = std::fs::read(build_example_component().await?)?;
+        let path = manager.component_root().join("watched.wasm");
+        let (head, tail) = wasm.split_at(wasm.len() / 2);
+        std::fs::write(&path, head)?;
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)?
+            .write_all(tail)?;
+        let event = next_event(&mut events).await?;
+        assert!(
+            matches!(&event, LifecycleEvent::ComponentLoaded { component_id, .. } if component_id == "watched"),
+            "{event:?}"
+        );
+
+        let policy_path = manager.component_root().join("watched.policy.yaml");
+        std::fs::write(
+            &policy_path,
+            "version: \"1.0\"\npermissions:\n  network:\n    allow:\n      - host: \"example.com\"\n",
+        )?;
+        let event = next_event(&mut events).await?;
+        assert!(
+            matches!(&event, LifecycleEvent::PolicyAttached { component_id, .. } if component_id == "watched"),
+            "{event:?}"
+        );
+        let template = manager
+            .policy_manager
+            .template_for_component("watched")
+            .await;
+        assert!(template.allowed_hosts.contains("example.com"));
+
+        std::fs::remove_file(&path)?;
+        assert_eq!(
+            next_event(&mut events).await?,
+            LifecycleEvent::ComponentRemoved {
+                component_id: "watched".to_string()
+            }
+        );
+        assert!(!manager
+            .list_components()
+            .await
+            .contains(&"watched".to_string()));
+        assert!(policy_path.exists());
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_replaced_wasm_file_is_marked_drifted() -> Result<()> {
+        let manager = create_test_manager().await?;
+        manager.load_test_component().await?;
+        assert!(manager.check_drift().await.is_empty());
+        assert!(manager.check_metadata_drift().await.is_empty());
+
+        let wasm_path = manager.component_path(TEST_COMPONENT_ID);
+        let original = tokio::fs::read(&wasm_path).await?;
+        tokio::fs::write(&wasm_path, b"not the registered component").await?;
+
+        // The saved metadata disagrees with the file as well, for managers
+        // that did not register the component, such as `wassette doctor`
+        let metadata_drift = manager.check_metadata_drift().await;
+        assert_eq!(metadata_drift.len(), 1);
+        assert_eq!(metadata_drift[0].0, TEST_COMPONENT_ID);
+        assert!(metadata_drift[0].1.starts_with("file size changed"));
+
+        let mut events = manager.subscribe();
+        assert_eq!(manager.check_drift().await, [TEST_COMPONENT_ID]);
+        assert!(matches!(
+            events.try_recv()?,
+            LifecycleEvent::ComponentDrifted { component_id, .. } if component_id == TEST_COMPONENT_ID
+        ));
+        assert!(matches!(
+            manager.get_component_load_state(TEST_COMPONENT_ID).await,
+            Some(ComponentLoadState::Drifted { .. })
+        ));
+        // Already marked components are not announced again
+        assert_eq!(manager.check_drift().await, [TEST_COMPONENT_ID]);
+        assert!(events.try_recv().is_err());
+
+        let error = manager
+            .execute_component_call(
+                TEST_COMPONENT_ID,
+                "fetch",
+                r#"{"url": "https://example.com"}"#,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ComponentError>(),
+            Some(ComponentError::Drifted { .. })
+        ));
+
+        // Loading the file again clears the drift
+        tokio::fs::write(&wasm_path, original).await?;
+        manager
+            .load_component(&format!("file://{}", wasm_path.display()))
+            .await?;
+        assert_eq!(
+            manager.get_component_load_state(TEST_COMPONENT_ID).await,
+            Some(ComponentLoadState::Loaded)
+        );
+        assert!(manager.check_drift().await.is_empty());
+        assert!(manager.check_metadata_drift().await.is_empty());
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_file_copied_with_its_times_is_marked_drifted() -> Result<()> {
+        let manager = create_test_manager().await?;
+        manager.load_test_component().await?;
+
+        // Like `cp -p`: other contents of the same size, with the
+        // modification time of the registered file
+        let wasm_path = manager.component_path(TEST_COMPONENT_ID);
+        let modified = std::fs::metadata(&wasm_path)?.modified()?;
+        let mut bytes = tokio::fs::read(&wasm_path).await?;
+        *bytes.last_mut().unwrap() ^= 0xff;
+        tokio::fs::write(&wasm_path, &bytes).await?;
+        std::fs::File::options()
+            .write(true)
+            .open(&wasm_path)?
+            .set_modified(modified)?;
+
+        assert_eq!(
+            manager.check_metadata_drift().await,
+            [(
+                TEST_COMPONENT_ID.to_string(),
+                "file contents changed".to_string()
+            )]
+        );
+        assert_eq!(manager.check_drift().await, [TEST_COMPONENT_ID]);
+        Ok(())
+    }
 
-        match auth {
-            Some(oci_client::secrets::RegistryAuth::Basic(u, p)) => {
-                assert_eq!(u, "user_x");
-                assert_eq!(p, "pass_x");
+    #[test(tokio::test)]
+    async fn test_component_path_update() -> Result<()> {
+        let manager = create_test_manager().await?;
+
+        let component_id = "test-component";
+        let expected_path = manager.component_root().join("test-component.wasm");
+        let actual_path = manager.component_path(component_id);
+
+        assert_eq!(actual_path, expected_path);
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_get_wasi_state_for_component_with_policy() -> Result<()> {
+        let manager = create_test_manager().await?;
+        manager.load_test_component().await?;
+
+        // Create and attach a policy
+        let policy_content = r#"
+version: "1.0"
+description: "Test policy"
+permissions:
+  network:
+    allow:
+      - host: "example.com"
+"#;
+        let policy_path = manager.component_root().join("test-policy.yaml");
+        tokio::fs::write(&policy_path, policy_content).await?;
+
+        let policy_uri = format!("file://{}", policy_path.display());
+        manager
+            .attach_policy(TEST_COMPONENT_ID, &policy_uri)
+            .await?;
+
+        // Test getting WASI state for component with attached policy
+        let _wasi_state = manager
+            .get_wasi_state_for_component(TEST_COMPONENT_ID)
+            .await?;
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_permission_hook_decides_sandbox() -> Result<()> {
+        let manager = create_test_manager_with(|builder| {
+            builder.with_permission_hook(|component_id, template| match component_id {
+                "blocked" => PermissionDecision::Deny("not on the allowlist".to_string()),
+                "modified" => PermissionDecision::Modify(Box::new(WasiStateTemplate {
+                    instantiation_timeout: Some(Duration::from_secs(7)),
+                    ..template.clone()
+                })),
+                _ => PermissionDecision::Allow,
+            })
+        })
+        .await?;
+
+        let template = manager.sandbox_template_for_component("allowed").await?;
+        assert_eq!(template.instantiation_timeout, None);
+        let template = manager.sandbox_template_for_component("modified").await?;
+        assert_eq!(template.instantiation_timeout, Some(Duration::from_secs(7)));
+
+        let error = manager
+            .get_wasi_state_for_component("blocked")
+            .await
+            .err()
+            .expect("hook should deny the component");
+        match error.downcast_ref::<PermissionError>() {
+            Some(PermissionError::Denied { reason }) => {
+                assert_eq!(reason, "not on the allowlist")
             }
-            other => panic!("expected Basic auth, got: {:?}", other),
+            other => panic!("expected permission denial, got {other:?}"),
         }
+        assert!(error.to_string().contains("blocked"));
+
+        Ok(())
     }
-}
-// Load components in parallel for improved startup performance
-async fn load_components_parallel(
-    component_dir: &Path,
-    runtime: Arc<RuntimeContext>,
-) -> Result<Vec<(ComponentInstance, String)>> {
-    let mut entries = tokio::fs::read_dir(component_dir).await?;
-    let mut load_futures = Vec::new();
 
-    while let Some(entry) = entries.next_entry().await? {
-        let runtime_clone = Arc::clone(&runtime);
-        let future = async move {
-            match load_component_from_entry(runtime_clone, entry).await {
-                Ok(Some(result)) => Some(Ok(result)),
-                Ok(None) => None,
-                Err(e) => Some(Err(e)),
+    #[test(tokio::test)]
+    async fn test_denial_lists_grants_narrowed_by_permission_hook() -> Result<()> {
+        let manager = create_test_manager_with(|builder| {
+            builder.with_permission_hook(|_, template| {
+                PermissionDecision::Modify(Box::new(WasiStateTemplate {
+                    allowed_hosts: HashSet::from(["example.com".to_string()]),
+                    ..template.clone()
+                }))
+            })
+        })
+        .await?;
+        manager.load_test_component().await?;
+        for host in ["example.com", "denied.test"] {
+            manager
+                .grant_permission(
+                    TEST_COMPONENT_ID,
+                    "network",
+                    &serde_json::json!({ "host": host }),
+                )
+                .await?;
+        }
+
+        // The policy allows denied.test, but the hook took it away, so a
+        // denial lists only what the hook kept
+        let component = manager
+            .get_component(TEST_COMPONENT_ID)
+            .await
+            .expect("component is loaded");
+        let policy_template = manager
+            .policy_manager
+            .template_for_component(TEST_COMPONENT_ID)
+            .await;
+        assert!(policy_template.allowed_hosts.contains("denied.test"));
+        let (_, _, _, sandbox_template) = manager
+            .instantiate_component(TEST_COMPONENT_ID, &component, policy_template, None)
+            .await?;
+        let message = PermissionError::NetworkDenied {
+            host: "denied.test".to_string(),
+            uri: "https://denied.test/".to_string(),
+            scheme: None,
+            port: None,
+        }
+        .to_user_message_with_grants(TEST_COMPONENT_ID, &sandbox_template);
+        assert!(
+            message.ends_with("Allowed hosts: example.com"),
+            "unexpected message: {message}"
+        );
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_async_permission_hook() -> Result<()> {
+        let manager = create_test_manager_with(|builder| {
+            builder.with_async_permission_hook(|component_id, _| {
+                let component_id = component_id.to_string();
+                async move {
+                    tokio::task::yield_now().await;
+                    PermissionDecision::Deny(format!("{component_id} is not approved"))
+                }
+            })
+        })
+        .await?;
+
+        let error = manager
+            .sandbox_template_for_component("pending")
+            .await
+            .err()
+            .expect("hook should deny the component");
+        assert!(matches!(
+            error.downcast_ref::<PermissionError>(),
+            Some(PermissionError::Denied { reason }) if reason == "pending is not approved"
+        ));
+
+        Ok(())
+    }
+
+    /// A component whose `probe` function grows its memory a page at a time
+    /// until the memory limit stops it, and returns `generation * 100000`
+    /// plus the pages it reached.
+    fn probe_component(generation: u32) -> Result<Vec<u8>> {
+        Ok(wat::parse_str(format!(
+            r#"(component
+                (core module $m
+                    (memory (export "memory") 1)
+                    (func (export "probe") (result i32)
+                        (block $full
+                            (loop $grow
+                                (br_if $full
+                                    (i32.eq (memory.grow (i32.const 1)) (i32.const -1)))
+                                (br $grow)))
+                        (i32.add (i32.const {}) (memory.size))))
+                (core instance $i (instantiate $m))
+                (func (export "probe") (result u32)
+                    (canon lift (core func $i "probe"))))"#,
+            generation * 100_000
+        ))?)
+    }
+
+    #[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+    async fn test_calls_during_reload_use_matching_instance_and_policy() -> Result<()> {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let manager = create_test_manager().await?;
+        let tempdir = tempfile::tempdir()?;
+        let component = tempdir.path().join("probe.wasm");
+        let uri = format!("file://{}", component.display());
+        std::fs::write(&component, probe_component(0)?)?;
+        let component_id = manager.load_component(&uri).await?.component_id;
+        let policy_path = manager.get_component_policy_path(&component_id);
+        let memory = |mebibytes: u32| serde_json::json!({"resources": {"limits": {"memory": format!("{mebibytes}Mi")}}});
+        manager
+            .grant_permission(&component_id, "resource", &memory(4))
+            .await?;
+
+        // Every (component generation, memory limit in pages) pair that was
+        // installed together; a call must observe exactly one of them
+        let mut installed = vec![(0, 4 * 16)];
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let callers: Vec<_> = (0..3)
+            .map(|_| {
+                let manager = LifecycleManager::clone(&manager);
+                let component_id = component_id.clone();
+                let stop = Arc::clone(&stop);
+                tokio::spawn(async move {
+                    let mut seen = Vec::new();
+                    while !stop.load(Ordering::Relaxed) {
+                        let result = manager
+                            .execute_component_call(&component_id, "probe", "{}")
+                            .await?;
+                        let value: Value = serde_json::from_str(&result)?;
+                        let probe = value["result"]
+                            .as_u64()
+                            .context("probe did not return a number")?;
+                        seen.push((probe / 100_000, probe % 100_000));
+                    }
+                    anyhow::Ok(seen)
+                })
+            })
+            .collect();
+
+        // Each round reloads a new generation with its own limit, then
+        // changes that limit without reloading
+        for generation in 0..3u32 {
+            if generation > 0 {
+                let limit = 16 * generation - 4;
+                let policy = format!(
+                    "version: \"1.0\"\npermissions:\n  resources:\n    limits:\n      memory: \"{limit}Mi\"\n"
+                );
+                tokio::fs::write(&policy_path, policy).await?;
+                tokio::fs::write(&component, probe_component(generation)?).await?;
+                manager.load_component(&uri).await?;
+                installed.push((generation as u64, limit as u64 * 16));
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+
+            let limit = 16 * generation + 8;
+            manager
+                .grant_permission(&component_id, "resource", &memory(limit))
+                .await?;
+            installed.push((generation as u64, limit as u64 * 16));
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        stop.store(true, Ordering::Relaxed);
+
+        let mut generations = HashSet::new();
+        for caller in callers {
+            for (generation, pages) in caller.await?? {
+                assert!(
+                    installed.contains(&(generation, pages)),
+                    "generation {generation} ran with a {pages}-page limit, which it was never installed with"
+                );
+                generations.insert(generation);
+            }
+        }
+        assert!(generations.len() > 1, "calls did not overlap a reload");
+
+        Ok(())
+    }
+
+    /// A component whose `grow` function grows its memory by `pages` pages
+    /// of 64Ki, trapping when it cannot, and returns its size in pages.
+    const GROW_MEMORY_COMPONENT: &str = r#"(component
+        (core module $m
+            (memory (export "memory") 1)
+            (func (export "grow") (param i32) (result i32)
+                (if (i32.eq (memory.grow (local.get 0)) (i32.const -1))
+                    (then unreachable))
+                (memory.size)))
+        (core instance $i (instantiate $m))
+        (func (export "grow") (param "pages" u32) (result u32)
+            (canon lift (core func $i "grow"))))"#;
+
+    /// Grow the memory of a component past 64Mi under a 512Mi limit, lower
+    /// the limit to 64Mi, and check the next calls are held to it.
+    async fn check_lowered_memory_limit(manager: &LifecycleManager, pooled: bool) -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let component = tempdir.path().join("grow.wasm");
+        std::fs::write(&component, wat::parse_str(GROW_MEMORY_COMPONENT)?)?;
+        let component_id = manager
+            .load_component(&format!("file://{}", component.display()))
+            .await?
+            .component_id;
+        let grow = |pages: u32| {
+            let component_id = component_id.clone();
+            async move {
+                manager
+                    .execute_component_call_timed(
+                        &component_id,
+                        "grow",
+                        &format!(r#"{{"pages": {pages}}}"#),
+                    )
+                    .await
+                    .map(|(value, timing)| (value["result"].clone(), timing))
             }
         };
-        load_futures.push(future);
+        let memory = |limit: &str| serde_json::json!({"resources": {"limits": {"memory": limit}}});
+
+        manager
+            .grant_permission(&component_id, "resource", &memory("512Mi"))
+            .await?;
+        // 100Mi, then a page more in the same instance when it is pooled
+        let (size, _) = grow(1600).await?;
+        assert_eq!(size, serde_json::json!(1601));
+        let (size, timing) = grow(1).await?;
+        assert_eq!(timing.pooled, pooled);
+        assert_eq!(size, serde_json::json!(if pooled { 1602 } else { 2 }));
+
+        let version = manager.get_policy_version(&component_id).await;
+        manager
+            .grant_permission(&component_id, "resource", &memory("64Mi"))
+            .await?;
+        assert!(manager.get_policy_version(&component_id).await > version);
+
+        // About 69Mi does not fit under the new limit
+        let error = grow(1100)
+            .await
+            .expect_err("growing past the lowered limit should fail");
+        assert!(
+            format!("{error:#}").contains("unreachable"),
+            "unexpected error: {error:#}"
+        );
+        // 32Mi does, in a store built for the new limit rather than the
+        // instance that grew to 100Mi
+        let (size, timing) = grow(512).await?;
+        assert!(!timing.pooled);
+        assert_eq!(size, serde_json::json!(513));
+        Ok(())
     }
 
-    let results = futures::future::join_all(load_futures).await;
-    let mut components = Vec::new();
+    #[test(tokio::test)]
+    async fn test_lowered_memory_limit_applies_to_next_call() -> Result<()> {
+        let manager = create_test_manager().await?;
+        check_lowered_memory_limit(&manager, false).await
+    }
+
+    #[test(tokio::test)]
+    async fn test_lowered_memory_limit_rebuilds_pooled_store() -> Result<()> {
+        let manager = create_test_manager_with(|builder| builder.with_instance_pool(2)).await?;
+        check_lowered_memory_limit(&manager, true).await
+    }
+
+    #[test(tokio::test)]
+    async fn test_policy_restoration_on_startup() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+
+        // Create a component file
+        let component_content = if let Ok(content) =
+            std::fs::read("examples/fetch-rs/target/wasm32-wasip2/debug/fetch_rs.wasm")
+        {
+            content
+        } else {
+            let path = build_example_component().await?;
+            std::fs::read(path)?
+        };
+        let component_path = tempdir.path().join("test-component.wasm");
+        std::fs::write(&component_path, component_content)?;
+
+        // Create a co-located policy file
+        let policy_content = r#"
+version: "1.0"
+description: "Test policy"
+permissions:
+  network:
+    allow:
+      - host: "example.com"
+"#;
+        let policy_path = tempdir.path().join("test-component.policy.yaml");
+        std::fs::write(&policy_path, policy_content)?;
+
+        // Create a new LifecycleManager to test policy restoration
+        let manager = LifecycleManager::new(&tempdir).await?;
+
+        // Check if policy was restored
+        let policy_info = manager.get_policy_info("test-component").await;
+        assert!(policy_info.is_some());
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_policy_file_not_found_error() -> Result<()> {
+        let manager = create_test_manager().await?;
+        manager.load_test_component().await?;
+
+        let non_existent_uri = "file:///non/existent/policy.yaml";
+
+        // Test attaching non-existent policy file
+        let result = manager
+            .attach_policy(TEST_COMPONENT_ID, non_existent_uri)
+            .await;
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_policy_invalid_uri_scheme() -> Result<()> {
+        let manager = create_test_manager().await?;
+        manager.load_test_component().await?;
+
+        let invalid_uri = "invalid-scheme://policy.yaml";
+
+        // Test attaching policy with invalid URI scheme
+        let result = manager.attach_policy(TEST_COMPONENT_ID, invalid_uri).await;
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Unsupported policy scheme"));
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_execute_component_call_with_per_component_policy() -> Result<()> {
+        let manager = create_test_manager().await?;
+        manager.load_test_component().await?;
+
+        // Test execution with default policy (no explicit policy attached)
+        // This tests that the execution works with the default policy
+        let result = manager
+            .execute_component_call(
+                TEST_COMPONENT_ID,
+                "fetch",
+                r#"{"url": "https://example.com"}"#,
+            )
+            .await;
+
+        // The call might fail due to network restrictions in test environment,
+        // but it should at least attempt to execute (not fail due to component not found)
+        // We just verify the call was made successfully in terms of component lookup
+        match result {
+            Ok(_) => {} // Success
+            Err(e) => {
+                // Should not be a component lookup error
+                assert!(!e.to_string().contains("Component not found"));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_execute_component_call_records_usage() -> Result<()> {
+        let manager = create_test_manager().await?;
+        manager.load_test_component().await?;
+        assert!(manager.get_component_usage(TEST_COMPONENT_ID).is_none());
+
+        // The host is not in the policy, so the call fails but still counts as a use.
+        let _ = manager
+            .execute_component_call(
+                TEST_COMPONENT_ID,
+                "fetch",
+                r#"{"url": "https://example.invalid"}"#,
+            )
+            .await;
+        let usage = manager
+            .get_component_usage(TEST_COMPONENT_ID)
+            .expect("call should be recorded");
+        assert_eq!(usage.tools.get("fetch"), Some(&usage.last_used));
+
+        manager.unload_component(TEST_COMPONENT_ID).await?;
+        assert!(manager.component_usage().is_empty());
+
+        Ok(())
+    }
+
+    /// Middleware that logs its hooks and, depending on its name, rewrites
+    /// the URL argument, stops the call, or replaces the result.
+    struct RecordingMiddleware {
+        name: &'static str,
+        log: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl CallMiddleware for RecordingMiddleware {
+        fn before<'a>(
+            &'a self,
+            _component_id: &'a str,
+            _tool: &'a str,
+            arguments: &'a mut Value,
+        ) -> MiddlewareFuture<'a, Result<()>> {
+            Box::pin(async move {
+                let url = arguments["url"].as_str().unwrap_or_default().to_string();
+                self.log
+                    .lock()
+                    .unwrap()
+                    .push(format!("{}:before:{url}", self.name));
+                match self.name {
+                    "rewrite" => arguments["url"] = "https://rewritten.invalid".into(),
+                    "stop" => bail!("quota exceeded"),
+                    _ => {}
+                }
+                Ok(())
+            })
+        }
 
-    for result in results.into_iter().flatten() {
-        match result {
-            Ok(component) => components.push(component),
-            Err(e) => warn!("Failed to load component: {}", e),
+        fn after<'a>(
+            &'a self,
+            _component_id: &'a str,
+            _tool: &'a str,
+            result: &'a mut Result<Value>,
+            _timing: &'a CallTiming,
+        ) -> MiddlewareFuture<'a, ()> {
+            Box::pin(async move {
+                let outcome = if result.is_ok() { "ok" } else { "err" };
+                self.log
+                    .lock()
+                    .unwrap()
+                    .push(format!("{}:after:{outcome}", self.name));
+                if self.name == "replace" {
+                    *result = Ok(Value::String("handled".to_string()));
+                }
+            })
         }
     }
 
-    Ok(components)
-}
+    #[test(tokio::test)]
+    async fn test_call_middleware_wraps_calls_in_order() -> Result<()> {
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let middleware = |name| RecordingMiddleware {
+            name,
+            log: Arc::clone(&log),
+        };
+        let manager = create_test_manager_with(|builder| {
+            builder
+                .with_call_middleware(middleware("rewrite"))
+                .with_call_middleware(middleware("replace"))
+        })
+        .await?;
+        manager.load_test_component().await?;
 
-impl LifecycleManager {
-    /// Get the secrets manager
-    pub fn secrets_manager(&self) -> &SecretsManager {
-        &self.secrets_manager
-    }
+        // The rewritten host is not in the policy either; the component
+        // returns the error as its result, which the last middleware replaces
+        let result = manager
+            .execute_component_call(
+                TEST_COMPONENT_ID,
+                "fetch",
+                r#"{"url": "https://example.invalid"}"#,
+            )
+            .await?;
+        assert_eq!(result, "handled");
+        assert_eq!(
+            log.lock().unwrap().drain(..).collect::<Vec<_>>(),
+            [
+                "rewrite:before:https://example.invalid",
+                "replace:before:https://rewritten.invalid",
+                "rewrite:after:ok",
+                "replace:after:ok",
+            ]
+        );
 
-    /// List secrets for a component
-    pub async fn list_component_secrets(
-        &self,
-        component_id: &str,
-        show_values: bool,
-    ) -> Result<std::collections::HashMap<String, Option<String>>> {
-        self.secrets_manager
-            .list_component_secrets(component_id, show_values)
+        let manager = create_test_manager_with(|builder| {
+            builder
+                .with_call_middleware(middleware("first"))
+                .with_call_middleware(middleware("stop"))
+                .with_call_middleware(middleware("never"))
+        })
+        .await?;
+        manager.load_test_component().await?;
+        let error = manager
+            .execute_component_call(
+                TEST_COMPONENT_ID,
+                "fetch",
+                r#"{"url": "https://example.invalid"}"#,
+            )
             .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "quota exceeded");
+        assert!(manager.get_component_usage(TEST_COMPONENT_ID).is_none());
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                "first:before:https://example.invalid",
+                "stop:before:https://example.invalid",
+                "first:after:err",
+            ]
+        );
+        Ok(())
     }
 
-    /// Set secrets for a component
-    pub async fn set_component_secrets(
-        &self,
-        component_id: &str,
-        secrets: &[(String, String)],
-    ) -> Result<()> {
-        // Check if component exists in the component directory
-        let component_path = self.component_path(component_id);
-        if !component_path.exists() {
-            bail!("Component not found: {}", component_id);
-        }
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn test_component_directory_entries_that_are_not_wasm_files() -> Result<()> {
+        let manager = create_test_manager().await?;
+        let root = manager.component_root().to_path_buf();
+        let wasm = build_example_component().await?;
+        std::fs::create_dir(root.join("folder.wasm"))?;
+        std::os::unix::fs::symlink(&wasm, root.join("linked.wasm"))?;
+        std::os::unix::fs::symlink(root.join("missing"), root.join("dangling.wasm"))?;
+        std::fs::write(root.join("empty.wasm"), b"")?;
+
+        assert_eq!(manager.list_components_known().await, ["empty", "linked"]);
+        manager.load_existing_components_async(None).await?;
+        assert_eq!(manager.list_components().await, ["linked"]);
+        assert!(matches!(
+            manager.get_component_load_state("empty").await,
+            Some(ComponentLoadState::Failed { .. })
+        ));
+        assert_eq!(manager.get_component_load_state("folder").await, None);
+        assert!(manager.ensure_component_loaded("folder").await.is_err());
+        manager.gc_downloads(false).await?;
+
+        // Loading the directory up front skips the same entries
+        let restarted = LifecycleManager::new(&root).await?;
+        assert_eq!(restarted.list_components().await, ["linked"]);
+        drop(restarted);
+
+        manager.unload_component("folder").await?;
+        assert!(root.join("folder.wasm").is_dir());
+        manager.unload_component("dangling").await?;
+        assert!(!root.join("dangling.wasm").is_symlink());
+        manager.unload_component("linked").await?;
+        assert!(!root.join("linked.wasm").is_symlink());
+        assert!(wasm.exists());
+        manager.unload_component("empty").await?;
+        assert!(manager.list_components_known().await.is_empty());
 
-        self.secrets_manager
-            .set_component_secrets(component_id, secrets)
-            .await
+        Ok(())
     }
 
-    /// Delete secrets for a component
-    pub async fn delete_component_secrets(
-        &self,
-        component_id: &str,
-        keys: &[String],
-    ) -> Result<()> {
-        self.secrets_manager
-            .delete_component_secrets(component_id, keys)
-            .await
-    }
+    #[test(tokio::test)]
+    async fn test_garbage_collect_removes_orphaned_files() -> Result<()> {
+        let manager = create_test_manager().await?;
+        let root = manager.component_root().to_path_buf();
+        for name in [
+            "gone.cwasm",
+            "gone.metadata.json",
+            "kept.wasm",
+            "kept.cwasm",
+        ] {
+            std::fs::write(root.join(name), b"data")?;
+        }
+        std::fs::write(root.join("kept.metadata.json"), b"{}")?;
+        std::fs::write(root.join("gone.policy.yaml"), b"version: \"1.0\"")?;
+        let downloads = root.join(DOWNLOADS_DIR);
+        std::fs::write(downloads.join("stale.part"), b"partial")?;
+        std::fs::write(downloads.join("fresh.part"), b"partial")?;
+        std::fs::File::options()
+            .write(true)
+            .open(downloads.join("stale.part"))?
+            .set_modified(std::time::SystemTime::now() - Duration::from_secs(2 * 60 * 60))?;
+
+        let report = manager
+            .garbage_collect(GcOptions {
+                dry_run: true,
+                ..Default::default()
+            })
+            .await?;
+        assert!(report.dry_run);
+        assert_eq!(report.precompiled, ["gone.cwasm"]);
+        assert_eq!(report.metadata, ["gone.metadata.json"]);
+        assert_eq!(report.temp_files, ["downloads/stale.part"]);
+        assert_eq!(report.files_removed, 3);
+        assert_eq!(report.bytes_freed, 15);
+        assert!(root.join("gone.cwasm").exists());
+        assert!(downloads.join("stale.part").exists());
+
+        let report = manager.garbage_collect(GcOptions::default()).await?;
+        assert!(!report.dry_run);
+        assert_eq!(report.files_removed, 3);
+        for name in ["gone.cwasm", "gone.metadata.json", "downloads/stale.part"] {
+            assert!(!root.join(name).exists(), "{name} was not removed");
+        }
+        for name in [
+            "kept.wasm",
+            "kept.cwasm",
+            "kept.metadata.json",
+            "gone.policy.yaml",
+            "downloads/fresh.part",
+        ] {
+            assert!(root.join(name).exists(), "{name} was removed");
+        }
+        let report = manager.garbage_collect(GcOptions::default()).await?;
+        assert_eq!(report.files_removed, 0);
 
-    /// Load secrets for a component as environment variables
-    pub async fn load_component_secrets(
-        &self,
-        component_id: &str,
-    ) -> Result<std::collections::HashMap<String, String>> {
-        self.secrets_manager
-            .load_component_secrets(component_id)
-            .await
+        Ok(())
     }
-}
 
-async fn load_component_from_entry(
-    runtime: Arc<RuntimeContext>,
-    entry: DirEntry,
-) -> Result<Option<(ComponentInstance, String)>> {
-    let start_time = Instant::now();
-    let is_file = entry
-        .metadata()
-        .await
-        .map(|m| m.is_file())
-        .context("unable to read file metadata")?;
-    let is_wasm = entry
-        .path()
-        .extension()
-        .map(|ext| ext == "wasm")
-        .unwrap_or(false);
-    if !(is_file && is_wasm) {
-        return Ok(None);
-    }
-    let entry_path = entry.path();
+    #[test(tokio::test)]
+    async fn test_startup_downloads_prune_can_be_turned_off() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let downloads = dir.path().join(DOWNLOADS_DIR);
+        std::fs::create_dir_all(&downloads)?;
+        let stray = downloads.join("stray.part");
+        std::fs::write(&stray, b"partial")?;
+        let retention = DownloadsRetention {
+            max_age: None,
+            max_total_bytes: Some(1),
+        };
 
-    // Read wasm bytes to extract package docs
-    let wasm_bytes = tokio::fs::read(&entry_path)
-        .await
-        .context("Failed to read wasm file")?;
+        let manager = LifecycleManager::builder(dir.path())
+            .with_downloads_retention(retention)
+            .with_startup_downloads_prune(false)
+            .with_eager_loading(false)
+            .build()
+            .await?;
+        assert!(stray.exists());
 
-    // Extract package docs before spawning blocking task
-    let package_docs = extract_package_docs(&wasm_bytes);
+        manager.prune_downloads().await?;
+        assert!(!stray.exists());
 
-    let runtime_for_component = Arc::clone(&runtime);
-    let component = tokio::task::spawn_blocking(move || {
-        Component::from_file(runtime_for_component.as_ref(), entry_path)
-    })
-    .await??;
-    let name = entry
-        .path()
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .map(String::from)
-        .context("wasm file didn't have a valid file name")?;
-    info!(component_id = %name, elapsed = ?start_time.elapsed(), "component loaded");
-    let instance_pre = runtime.instantiate_pre(&component)?;
-    Ok(Some((
-        ComponentInstance {
-            component: Arc::new(component),
-            instance_pre: Arc::new(instance_pre),
-            package_docs,
-        },
-        name,
-    )))
-}
+        std::fs::write(&stray, b"partial")?;
+        LifecycleManager::builder(dir.path())
+            .with_downloads_retention(retention)
+            .with_eager_loading(false)
+            .build()
+            .await?;
+        assert!(!stray.exists());
 
-#[cfg(test)]
-mod tests {
-    use std::ops::Deref;
-    use std::path::PathBuf;
-    use std::process::Command;
+        Ok(())
+    }
 
-    use policy::PolicyParser;
-    use test_log::test;
+    #[test(tokio::test)]
+    async fn test_execute_component_call_timed_reports_cold_load() -> Result<()> {
+        let manager = create_test_manager().await?;
+        manager.load_test_component().await?;
+        let arguments = r#"{"url": "https://denied.test/"}"#;
 
-    use super::*;
+        let (_, timing) = manager
+            .execute_component_call_timed(TEST_COMPONENT_ID, "fetch", arguments)
+            .await?;
+        assert!(!timing.cold_load);
+        assert!(timing.total_ms >= timing.instantiation_ms + timing.execution_ms);
 
-    pub(crate) const TEST_COMPONENT_ID: &str = "fetch_rs";
+        // A restarted manager only knows the component from its metadata
+        // until the call compiles it
+        let restarted = LifecycleManager::builder(manager.component_root())
+            .with_eager_loading(false)
+            .build()
+            .await?;
+        restarted.populate_registry_from_metadata().await?;
+        assert_eq!(
+            restarted.registry.load_state(TEST_COMPONENT_ID).await,
+            Some(ComponentLoadState::MetadataRegistered)
+        );
+        let (_, timing) = restarted
+            .execute_component_call_timed(TEST_COMPONENT_ID, "fetch", arguments)
+            .await?;
+        assert!(timing.cold_load);
+        let (_, timing) = restarted
+            .execute_component_call_timed(TEST_COMPONENT_ID, "fetch", arguments)
+            .await?;
+        assert!(!timing.cold_load);
 
-    /// Helper struct for keeping a reference to the temporary directory used for testing the
-    /// lifecycle manager
-    pub(crate) struct TestLifecycleManager {
-        pub manager: LifecycleManager,
-        _tempdir: tempfile::TempDir,
+        Ok(())
     }
 
-    impl TestLifecycleManager {
-        pub async fn load_test_component(&self) -> Result<()> {
-            let component_path = build_example_component().await?;
+    #[test(tokio::test)]
+    async fn test_instance_pool_skips_instantiation() -> Result<()> {
+        let arguments = r#"{"url": "https://denied.test/"}"#;
+        async fn five_calls(
+            manager: &LifecycleManager,
+            arguments: &str,
+        ) -> Result<Vec<CallTiming>> {
+            let mut timings = Vec::new();
+            for _ in 0..5 {
+                let (_, timing) = manager
+                    .execute_component_call_timed(TEST_COMPONENT_ID, "fetch", arguments)
+                    .await?;
+                timings.push(timing);
+            }
+            Ok(timings)
+        }
 
-            self.manager
-                .load_component(&format!("file://{}", component_path.to_str().unwrap()))
-                .await?;
+        let unpooled = create_test_manager().await?;
+        unpooled.load_test_component().await?;
+        let unpooled = five_calls(&unpooled, arguments).await?;
+        assert!(unpooled.iter().all(|timing| !timing.pooled));
 
-            Ok(())
-        }
-    }
+        let manager = create_test_manager_with(|builder| builder.with_instance_pool(2)).await?;
+        manager.load_test_component().await?;
+        let pooled = five_calls(&manager, arguments).await?;
+        // Only the first call instantiates; later ones reuse its instance
+        assert!(!pooled[0].pooled);
+        assert!(pooled[1..]
+            .iter()
+            .all(|timing| timing.pooled && timing.instantiation_ms == 0));
+        let total = |timings: &[CallTiming]| -> u64 {
+            timings.iter().map(|timing| timing.instantiation_ms).sum()
+        };
+        debug!(
+            unpooled_ms = total(&unpooled),
+            pooled_ms = total(&pooled),
+            "Instantiation time of five calls"
+        );
+        assert!(total(&pooled) <= total(&unpooled));
+        assert_eq!(manager.instance_pool.idle(TEST_COMPONENT_ID), 1);
 
-    impl Deref for TestLifecycleManager {
-        type Target = LifecycleManager;
+        // A policy change drops the idle instances
+        manager
+            .grant_permission(
+                TEST_COMPONENT_ID,
+                "environment-variable",
+                &serde_json::json!({"key": "API_KEY"}),
+            )
+            .await?;
+        assert_eq!(manager.instance_pool.idle(TEST_COMPONENT_ID), 0);
+        let (_, timing) = manager
+            .execute_component_call_timed(TEST_COMPONENT_ID, "fetch", arguments)
+            .await?;
+        assert!(!timing.pooled);
 
-        fn deref(&self) -> &Self::Target {
-            &self.manager
-        }
+        // So does a reload
+        manager.load_test_component().await?;
+        assert_eq!(manager.instance_pool.idle(TEST_COMPONENT_ID), 0);
+        Ok(())
     }
 
-    pub(crate) async fn create_test_manager() -> Result<TestLifecycleManager> {
-        let tempdir = tempfile::tempdir()?;
-        let manager = LifecycleManager::new(&tempdir).await?;
-        Ok(TestLifecycleManager {
-            manager,
-            _tempdir: tempdir,
+    #[test(tokio::test)]
+    async fn test_components_run_under_pooling_allocation() -> Result<()> {
+        let manager = create_test_manager_with(|builder| {
+            builder.with_runtime_options(RuntimeOptions {
+                allocation: InstanceAllocation::Pooling,
+                max_instances: Some(16),
+                max_memory_bytes: Some(512 << 20),
+                ..Default::default()
+            })
         })
+        .await?;
+        manager.load_test_component().await?;
+
+        let (result, _) = manager
+            .execute_component_call_timed(
+                TEST_COMPONENT_ID,
+                "fetch",
+                r#"{"url": "https://denied.test/"}"#,
+            )
+            .await?;
+        assert!(result.pointer("/result/err").is_some());
+        Ok(())
     }
 
-    pub(crate) async fn build_example_component() -> Result<PathBuf> {
-        let cwd = std::env::current_dir()?;
-        println!("CWD: {}", cwd.display());
-        let component_path =
-            cwd.join("../../examples/fetch-rs/target/wasm32-wasip2/release/fetch_rs.wasm");
+    #[test(tokio::test)]
+    async fn test_tampered_component_is_not_served_from_metadata() -> Result<()> {
+        let manager = create_test_manager().await?;
+        let wasm_path = manager.component_path("tampered");
+        tokio::fs::write(&wasm_path, b"original bytes").await?;
+        let stamp = manager
+            .storage
+            .create_validation_stamp(&wasm_path, true)
+            .await?;
+        assert!(stamp.content_hash.is_some());
+        manager
+            .save_component_metadata("tampered", &[], None, stamp, None)
+            .await?;
 
-        if !component_path.exists() {
-            let status = Command::new("cargo")
-                .current_dir(cwd.join("../../examples/fetch-rs"))
-                .args(["build", "--release", "--target", "wasm32-wasip2"])
-                .status()
-                .context("Failed to execute cargo component build")?;
+        // Same size and modification time, different content
+        let mtime = std::fs::metadata(&wasm_path)?.modified()?;
+        std::fs::write(&wasm_path, b"tampered bytes")?;
+        std::fs::File::options()
+            .write(true)
+            .open(&wasm_path)?
+            .set_modified(mtime)?;
+
+        let component_dir = manager.component_root();
+        let restart = |strict| async move {
+            let restarted = LifecycleManager::builder(component_dir)
+                .with_eager_loading(false)
+                .with_strict_validation(strict)
+                .build()
+                .await?;
+            restarted.populate_registry_from_metadata().await?;
+            anyhow::Ok(restarted)
+        };
+        let strict = restart(true).await?;
+        assert_eq!(
+            strict.registry.load_state("tampered").await,
+            Some(ComponentLoadState::Discovered)
+        );
+        let fast = restart(false).await?;
+        assert_eq!(
+            fast.registry.load_state("tampered").await,
+            Some(ComponentLoadState::MetadataRegistered)
+        );
 
-            if !status.success() {
-                anyhow::bail!("Failed to compile fetch-rs component");
-            }
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_concurrent_loads_of_a_uri_compile_once() -> Result<()> {
+        let manager = create_test_manager().await?;
+        let uri = format!("file://{}", build_example_component().await?.display());
+        let mut events = manager.subscribe();
+
+        let outcomes =
+            futures::future::join_all((0..4).map(|_| manager.load_component(&uri))).await;
+        for outcome in outcomes {
+            let outcome = outcome?;
+            assert_eq!(outcome.component_id, TEST_COMPONENT_ID);
+            assert_eq!(outcome.status, LoadResult::New);
         }
 
-        if !component_path.exists() {
-            anyhow::bail!(
-                "Component file not found after build: {}",
-                component_path.display()
-            );
+        let mut loads = 0;
+        while let Ok(event) = events.try_recv() {
+            if matches!(
+                event,
+                LifecycleEvent::ComponentLoaded { .. } | LifecycleEvent::ComponentReplaced { .. }
+            ) {
+                loads += 1;
+            }
         }
+        assert_eq!(loads, 1);
 
-        Ok(component_path)
+        // Once the loads are over, the URI is loaded again
+        let outcome = manager.load_component(&uri).await?;
+        assert_eq!(outcome.status, LoadResult::Replaced);
+        Ok(())
     }
 
     #[test(tokio::test)]
-    async fn test_lifecycle_manager_tool_registry() -> Result<()> {
+    async fn test_stale_cached_schema_is_replaced_and_reported() -> Result<()> {
         let manager = create_test_manager().await?;
+        manager.load_test_component().await?;
+        let compiled = manager.list_tools().await;
+        let arguments = r#"{"url": "https://denied.test/"}"#;
+        let restart = || async {
+            let restarted = LifecycleManager::builder(manager.component_root())
+                .with_eager_loading(false)
+                .build()
+                .await?;
+            restarted.populate_registry_from_metadata().await?;
+            anyhow::Ok(restarted)
+        };
 
-        let temp_dir = tempfile::tempdir()?;
-        let component_path = temp_dir.path().join("mock_component.wasm");
-        std::fs::write(&component_path, b"mock wasm bytes")?;
+        // Cached schemas of an unchanged file are served as compiled
+        let restarted = restart().await?;
+        assert_eq!(restarted.list_tools().await, compiled);
+        restarted
+            .execute_component_call(TEST_COMPONENT_ID, "fetch", arguments)
+            .await?;
+        assert_eq!(
+            restarted
+                .get_component_stale_schema(TEST_COMPONENT_ID)
+                .await,
+            None
+        );
 
-        let load_result = manager
-            .load_component(component_path.to_str().unwrap())
-            .await;
-        assert!(load_result.is_err()); // Expected since we're using invalid WASM
+        // Metadata that no longer describes the wasm, while the stamp still
+        // matches
+        let metadata_path = manager.storage.metadata_path(TEST_COMPONENT_ID);
+        let mut metadata: Value = serde_json::from_slice(&tokio::fs::read(&metadata_path).await?)?;
+        metadata["tool_schemas"][0]["description"] = serde_json::json!("Outdated description");
+        tokio::fs::write(&metadata_path, serde_json::to_vec(&metadata)?).await?;
+
+        let restarted = restart().await?;
+        assert_eq!(
+            restarted.list_tools().await[0]["description"],
+            "Outdated description"
+        );
+        let mut events = restarted.subscribe();
+        restarted
+            .execute_component_call(TEST_COMPONENT_ID, "fetch", arguments)
+            .await?;
+        assert_eq!(restarted.list_tools().await, compiled);
+        let stale = restarted
+            .get_component_stale_schema(TEST_COMPONENT_ID)
+            .await
+            .expect("stale schema is recorded");
+        assert_eq!(stale.changed_tools, vec!["fetch"]);
+        assert!(stale.added_tools.is_empty() && stale.removed_tools.is_empty());
+        assert_ne!(stale.served_hash, stale.compiled_hash);
+        let event = events.recv().await?;
+        assert!(matches!(event, LifecycleEvent::ComponentLoaded { .. }));
+        assert!(event.changes_tool_list());
 
-        let lookup_result = manager.get_component_id_for_tool("non-existent").await;
-        assert!(lookup_result.is_err());
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_virtual_component_is_loaded_on_first_call() -> Result<()> {
+        let manager = create_test_manager().await?;
+        let path = build_example_component().await?;
+        let component = Component::from_file(manager.runtime.as_ref(), &path)?;
+        let tools = component_exports_to_tools(&component, manager.runtime.as_ref(), true);
+        let uri = format!("file://{}", path.display());
+        let mut events = manager.subscribe();
+
+        manager
+            .register_virtual_component("catalog_fetch", &uri, tools.clone())
+            .await?;
+        assert_eq!(
+            manager.get_component_load_state("catalog_fetch").await,
+            Some(ComponentLoadState::Virtual {
+                source_uri: uri.clone()
+            })
+        );
+        assert_eq!(manager.list_components_known().await, vec!["catalog_fetch"]);
+        assert_eq!(
+            manager.get_component_id_for_tool("fetch").await?,
+            "catalog_fetch"
+        );
+        let schema = manager.get_component_schema("catalog_fetch").await.unwrap();
+        assert_eq!(schema["tools"].as_array().unwrap().len(), tools.len());
+        assert!(matches!(
+            events.try_recv()?,
+            LifecycleEvent::VirtualComponentRegistered { .. }
+        ));
+        assert!(manager
+            .register_virtual_component("catalog_fetch", &uri, tools.clone())
+            .await
+            .is_err());
+
+        let (result, timing) = manager
+            .execute_component_call_timed(
+                "catalog_fetch",
+                "fetch",
+                r#"{"url": "https://denied.test/"}"#,
+            )
+            .await?;
+        assert!(result.to_string().contains("HttpRequestDenied"));
+        assert!(timing.cold_load);
+        assert_eq!(
+            manager.get_component_load_state("catalog_fetch").await,
+            Some(ComponentLoadState::Loaded)
+        );
+        assert!(manager.component_path("catalog_fetch").is_file());
+
+        // A component that cannot be fetched takes its tools with it
+        manager
+            .register_virtual_component("missing", "file:///nonexistent/missing.wasm", tools)
+            .await?;
+        let error = manager
+            .execute_component_call("missing", "fetch", "{}")
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(
+                error.downcast_ref::<ComponentError>(),
+                Some(ComponentError::VirtualLoadFailed { .. })
+            ),
+            "unexpected error: {error:#}"
+        );
+        assert_eq!(manager.get_component_load_state("missing").await, None);
+        assert!(manager
+            .registry
+            .component_tool_schemas("missing")
+            .await
+            .is_empty());
+        Ok(())
+    }
+
+    /// A component whose `seconds` tool returns the seconds of
+    /// `wasi:clocks/wall-clock.now`.
+    const WALL_CLOCK_COMPONENT: &str = r#"(component
+        (import "wasi:clocks/wall-clock@0.2.0" (instance $clock
+            (type $datetime (record (field "seconds" u64) (field "nanoseconds" u32)))
+            (export "datetime" (type $dt (eq $datetime)))
+            (export "now" (func (result $dt)))))
+        (core module $memory (memory (export "memory") 1))
+        (core instance $memory (instantiate $memory))
+        (alias export $clock "now" (func $now))
+        (core func $now_lowered (canon lower (func $now) (memory $memory "memory")))
+        (core module $clock_reader
+            (import "host" "now" (func $now (param i32)))
+            (import "host" "memory" (memory 1))
+            (func (export "seconds") (result i64)
+                (call $now (i32.const 0))
+                (i64.load (i32.const 0))))
+        (core instance $reader (instantiate $clock_reader
+            (with "host" (instance
+                (export "now" (func $now_lowered))
+                (export "memory" (memory $memory "memory"))))))
+        (func (export "seconds") (result u64) (canon lift (core func $reader "seconds")))
+    )"#;
+
+    #[test(tokio::test)]
+    async fn test_policy_clock_freezes_wall_clock() -> Result<()> {
+        let manager = create_test_manager().await?;
+        let component_id = manager
+            .load_bytes("clock", WALL_CLOCK_COMPONENT.as_bytes())
+            .await?;
+        let now: Value = manager.call("seconds", serde_json::json!({})).await?;
+        assert!(now["result"].as_u64().unwrap() > 1_704_067_200);
+
+        manager
+            .attach_policy_yaml(
+                &component_id,
+                r#"
+version: "1.0"
+permissions:
+  system:
+    clock:
+      timezone: Europe/Berlin
+      fixedTime: "2024-01-01T00:00:00Z"
+"#,
+            )
+            .await?;
+        let now: Value = manager.call("seconds", serde_json::json!({})).await?;
+        assert_eq!(now["result"], 1_704_067_200);
+
+        let template = manager
+            .sandbox_template_for_component(&component_id)
+            .await?;
+        assert_eq!(
+            template.config_vars.get("TZ").map(String::as_str),
+            Some("Europe/Berlin")
+        );
+        Ok(())
+    }
+
+    /// A component whose `spin` tool never returns.
+    const SPIN_COMPONENT: &str = r#"(component
+        (core module $spin (func (export "spin") (loop $forever (br $forever))))
+        (core instance $spin (instantiate $spin))
+        (func (export "spin") (canon lift (core func $spin "spin")))
+    )"#;
+
+    #[test(tokio::test)]
+    async fn test_infinite_loop_traps_at_execution_timeout() -> Result<()> {
+        let manager = create_test_manager_with(|builder| {
+            builder.with_execution_timeout(Duration::from_millis(300))
+        })
+        .await?;
+        let component_id = manager
+            .load_bytes("spin", SPIN_COMPONENT.as_bytes())
+            .await?;
 
+        let timed_out = |error: anyhow::Error| match error.downcast_ref::<ComponentError>() {
+            Some(ComponentError::ExecutionTimeout { timeout, .. }) => {
+                assert!(error
+                    .to_string()
+                    .contains(&format!("{}ms", timeout.as_millis())));
+                *timeout
+            }
+            _ => panic!("expected an execution timeout, got {error:#}"),
+        };
+        let start = Instant::now();
+        let error = manager
+            .call::<Value>("spin", serde_json::json!({}))
+            .await
+            .unwrap_err();
+        assert_eq!(timed_out(error), Duration::from_millis(300));
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        // The policy overrides the default
+        manager
+            .attach_policy_yaml(
+                &component_id,
+                "version: \"1.0\"\npermissions:\n  resources:\n    limits:\n      timeout: \"50ms\"\n",
+            )
+            .await?;
+        let error = manager
+            .call::<Value>("spin", serde_json::json!({}))
+            .await
+            .unwrap_err();
+        assert_eq!(timed_out(error), Duration::from_millis(50));
         Ok(())
     }
 
     #[test(tokio::test)]
-    async fn test_new_manager() -> Result<()> {
-        let _manager = create_test_manager().await?;
+    async fn test_call_rejects_arguments_over_parameter_limits() -> Result<()> {
+        let manager = create_test_manager().await?;
+        let component_id = manager
+            .load_bytes("clock", WALL_CLOCK_COMPONENT.as_bytes())
+            .await?;
+
+        let deep = format!(
+            "{{\"value\": {}{}}}",
+            "[".repeat(10_000),
+            "]".repeat(10_000)
+        );
+        let error = manager
+            .execute_component_call(&component_id, "seconds", &deep)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ParameterLimitExceeded>(),
+            Some(&ParameterLimitExceeded::Depth {
+                max: DEFAULT_MAX_PARAMETER_DEPTH
+            })
+        );
         Ok(())
     }
 
     #[test(tokio::test)]
-    async fn test_load_and_unload_component() -> Result<()> {
-        let manager = create_test_manager().await?;
-
-        let load_result = manager.load_component("/path/to/nonexistent").await;
-        assert!(load_result.is_err());
-
-        manager.load_test_component().await?;
-
-        let loaded_components = manager.list_components().await;
-        assert_eq!(loaded_components.len(), 1);
-
-        manager.unload_component(TEST_COMPONENT_ID).await?;
-
-        let loaded_components = manager.list_components().await;
-        assert!(loaded_components.is_empty());
+    async fn test_cpu_limit_stops_call_when_fuel_runs_out() -> Result<()> {
+        let manager = create_test_manager_with(|builder| {
+            builder.with_runtime_options(RuntimeOptions {
+                cpu_limits: true,
+                ..Default::default()
+            })
+        })
+        .await?;
+        let component_id = manager
+            .load_bytes("spin", SPIN_COMPONENT.as_bytes())
+            .await?;
+        manager
+            .grant_permission(
+                &component_id,
+                "resource",
+                &serde_json::json!({"resources": {"limits": {"cpu": "1m"}}}),
+            )
+            .await?;
 
+        let error = manager
+            .call::<Value>("spin", serde_json::json!({}))
+            .await
+            .unwrap_err();
+        match error.downcast_ref::<ComponentError>() {
+            Some(ComponentError::FuelExhausted { fuel, .. }) => {
+                assert_eq!(*fuel, FUEL_PER_CPU_CORE / 1000);
+                assert!(error.to_string().contains("grant-cpu-permission"));
+            }
+            _ => panic!("expected the fuel to run out, got {error:#}"),
+        }
         Ok(())
     }
 
     #[test(tokio::test)]
-    async fn test_get_component() -> Result<()> {
+    async fn test_cpu_limit_needs_fuel_metering() -> Result<()> {
         let manager = create_test_manager().await?;
-        assert!(manager.get_component("non-existent").await.is_none());
-
-        manager.load_test_component().await?;
-
+        assert!(!manager.runtime.meters_fuel());
+        let component_id = manager
+            .load_bytes("spin", SPIN_COMPONENT.as_bytes())
+            .await?;
         manager
-            .get_component(TEST_COMPONENT_ID)
+            .grant_permission(
+                &component_id,
+                "resource",
+                &serde_json::json!({"resources": {"limits": {"cpu": "1m"}}}),
+            )
+            .await?;
+
+        let error = manager
+            .call::<Value>("spin", serde_json::json!({}))
             .await
-            .expect("Should be able to get a component we just loaded");
+            .unwrap_err();
+        assert!(
+            matches!(
+                error.downcast_ref::<ComponentError>(),
+                Some(ComponentError::CpuLimitsDisabled { component_id }) if component_id == "spin"
+            ),
+            "expected the CPU limit to be refused, got {error:#}"
+        );
         Ok(())
     }
 
+    /// A component with a `ping` tool, an `on-load` hook that succeeds, and
+    /// an `on-unload` hook that traps.
+    const HOOKS_COMPONENT: &str = r#"(component
+        (core module $hooks
+            (func (export "ok"))
+            (func (export "trap") unreachable)
+            (func (export "ping") (result i32) (i32.const 1)))
+        (core instance $hooks (instantiate $hooks))
+        (func (export "on-load") (canon lift (core func $hooks "ok")))
+        (func (export "on-unload") (canon lift (core func $hooks "trap")))
+        (func (export "ping") (result u32) (canon lift (core func $hooks "ping")))
+    )"#;
+
     #[test(tokio::test)]
-    async fn test_duplicate_component_id() -> Result<()> {
+    async fn test_lifecycle_hooks_run_on_load_and_unload() -> Result<()> {
         let manager = create_test_manager().await?;
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("hooks.wasm");
+        std::fs::write(&path, HOOKS_COMPONENT)?;
+        let uri = format!("file://{}", path.display());
+        let mut events = manager.subscribe();
+
+        let outcome = manager.load_component(&uri).await?;
+        assert_eq!(outcome.tool_names, ["ping"]);
+        let on_load = outcome.on_load.expect("on-load hook should run");
+        assert_eq!(on_load.hook, LifecycleHook::OnLoad);
+        assert!(on_load.succeeded);
+        assert!(outcome.warnings.is_empty());
+
+        // A failing on-unload hook does not stop the unload
+        let outcome = manager
+            .unload_component_with_options("hooks", UnloadOptions::default())
+            .await?;
+        let on_unload = outcome.on_unload.expect("on-unload hook should run");
+        assert_eq!(on_unload.hook, LifecycleHook::OnUnload);
+        assert!(!on_unload.succeeded && on_unload.error.is_some());
+        assert_eq!(outcome.warnings.len(), 1);
+        assert!(outcome.warnings[0].contains("on-unload hook"));
+        assert!(manager.list_components().await.is_empty());
+        assert!(!manager.component_path("hooks").exists());
+
+        let mut hooks_ran = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let LifecycleEvent::LifecycleHookRan { report, .. } = event {
+                hooks_ran.push(report.hook);
+            }
+        }
+        assert_eq!(hooks_ran, [LifecycleHook::OnLoad, LifecycleHook::OnUnload]);
+        let recorded = manager
+            .changes_since(0)
+            .into_iter()
+            .filter(|change| matches!(change.event, LifecycleEvent::LifecycleHookRan { .. }))
+            .count();
+        assert_eq!(recorded, 2);
+
+        // Hooks can be skipped for emergency unloads
+        manager.load_component(&uri).await?;
+        let outcome = manager
+            .unload_component_with_options("hooks", UnloadOptions { skip_hooks: true })
+            .await?;
+        assert!(outcome.on_unload.is_none());
+        assert!(outcome.warnings.is_empty());
+        assert!(manager.list_components().await.is_empty());
+        Ok(())
+    }
 
-        manager.load_test_component().await?;
-
-        let components = manager.list_components().await;
-        assert_eq!(components.len(), 1);
-        assert_eq!(components[0], TEST_COMPONENT_ID);
+    /// A component whose `greet` writes a line to stdout and one to stderr
+    /// and returns 42.
+    const STDIO_COMPONENT: &str = r#"(component $C
+        (import "wasi:io/error@0.2.0" (instance $io_error
+            (export "error" (type (sub resource)))))
+        (alias export $io_error "error" (type $error))
+        (import "wasi:io/streams@0.2.0" (instance $streams
+            (alias outer $C $error (type $error))
+            (export "error" (type $error_export (eq $error)))
+            (export "output-stream" (type $output_stream (sub resource)))
+            (type $stream_error (variant
+                (case "last-operation-failed" (own $error_export))
+                (case "closed")))
+            (export "stream-error" (type $stream_error_export (eq $stream_error)))
+            (export "[method]output-stream.blocking-write-and-flush" (func
+                (param "self" (borrow $output_stream))
+                (param "contents" (list u8))
+                (result (result (error $stream_error_export)))))))
+        (alias export $streams "output-stream" (type $output_stream))
+        (import "wasi:cli/stdout@0.2.0" (instance $stdout
+            (alias outer $C $output_stream (type $output_stream))
+            (export "output-stream" (type $output_stream_export (eq $output_stream)))
+            (export "get-stdout" (func (result (own $output_stream_export))))))
+        (import "wasi:cli/stderr@0.2.0" (instance $stderr
+            (alias outer $C $output_stream (type $output_stream))
+            (export "output-stream" (type $output_stream_export (eq $output_stream)))
+            (export "get-stderr" (func (result (own $output_stream_export))))))
+        (core module $memory
+            (memory (export "memory") 1)
+            (data (i32.const 16) "hello from stdout")
+            (data (i32.const 48) "warning on stderr"))
+        (core instance $memory (instantiate $memory))
+        (alias export $stdout "get-stdout" (func $get_stdout))
+        (alias export $stderr "get-stderr" (func $get_stderr))
+        (alias export $streams "[method]output-stream.blocking-write-and-flush" (func $write))
+        (core func $get_stdout_lowered (canon lower (func $get_stdout)))
+        (core func $get_stderr_lowered (canon lower (func $get_stderr)))
+        (core func $write_lowered (canon lower (func $write) (memory $memory "memory")))
+        (core module $writer
+            (import "host" "get-stdout" (func $get_stdout (result i32)))
+            (import "host" "get-stderr" (func $get_stderr (result i32)))
+            (import "host" "write" (func $write (param i32 i32 i32 i32)))
+            (func (export "greet") (result i32)
+                (call $write (call $get_stdout) (i32.const 16) (i32.const 17) (i32.const 96))
+                (call $write (call $get_stderr) (i32.const 48) (i32.const 17) (i32.const 96))
+                (i32.const 42)))
+        (core instance $writer (instantiate $writer
+            (with "host" (instance
+                (export "get-stdout" (func $get_stdout_lowered))
+                (export "get-stderr" (func $get_stderr_lowered))
+                (export "write" (func $write_lowered))))))
+        (func (export "greet") (result u32) (canon lift (core func $writer "greet")))
+    )"#;
 
-        // Load again and make sure we still only have one
+    #[test(tokio::test)]
+    async fn test_captured_stdio_is_returned_with_the_result() -> Result<()> {
+        let manager =
+            create_test_manager_with(|builder| builder.with_default_stdio(StdioMode::Capture))
+                .await?;
+        let component_id = manager
+            .load_bytes("stdio", STDIO_COMPONENT.as_bytes())
+            .await?;
 
-        manager.load_test_component().await?;
-        let components = manager.list_components().await;
-        assert_eq!(components.len(), 1);
-        assert_eq!(components[0], TEST_COMPONENT_ID);
+        let call = manager
+            .execute_component_call_with_output(&component_id, "greet", "{}")
+            .await?;
+        assert_eq!(call.result, serde_json::json!({"result": 42}));
+        assert_eq!(
+            call.output,
+            CapturedOutput {
+                stdout: "hello from stdout".to_string(),
+                stderr: "warning on stderr".to_string(),
+            }
+        );
 
+        // The policy decides over the manager's default
+        manager
+            .attach_policy_yaml(
+                &component_id,
+                "version: \"1.0\"\npermissions:\n  system:\n    stderr: deny\n",
+            )
+            .await?;
+        let call = manager
+            .execute_component_call_with_output(&component_id, "greet", "{}")
+            .await?;
+        assert_eq!(call.output.stdout, "hello from stdout");
+        assert_eq!(call.output.stderr, "");
         Ok(())
     }
 
     #[test(tokio::test)]
-    async fn test_component_reload() -> Result<()> {
+    async fn test_stderr_lines_are_sent_while_the_call_runs() -> Result<()> {
         let manager = create_test_manager().await?;
-        let component_path = build_example_component().await?;
-
+        let component_id = manager
+            .load_bytes("stdio", STDIO_COMPONENT.as_bytes())
+            .await?;
         manager
-            .load_component(&format!("file://{}", component_path.to_str().unwrap()))
+            .attach_policy_yaml(
+                &component_id,
+                "version: \"1.0\"\npermissions:\n  system:\n    stdout: deny\n",
+            )
             .await?;
 
-        let component_id = manager.get_component_id_for_tool("fetch").await?;
-        assert_eq!(component_id, TEST_COMPONENT_ID);
+        // Inherited stderr goes to the channel instead of the host
+        let (lines, mut received) = tokio::sync::mpsc::channel(8);
+        let call = manager
+            .execute_component_call_with_stderr(&component_id, "greet", "{}", lines)
+            .await?;
+        assert_eq!(call.result, serde_json::json!({"result": 42}));
+        assert!(call.output.is_empty());
+        assert_eq!(received.recv().await.as_deref(), Some("warning on stderr"));
+        assert_eq!(received.recv().await, None);
+        Ok(())
+    }
 
+    #[test(tokio::test)]
+    async fn test_policy_captures_stdio_of_inheriting_manager() -> Result<()> {
+        let manager = create_test_manager().await?;
+        let component_id = manager
+            .load_bytes("stdio", STDIO_COMPONENT.as_bytes())
+            .await?;
         manager
-            .load_component(&format!("file://{}", component_path.to_str().unwrap()))
+            .attach_policy_yaml(
+                &component_id,
+                "version: \"1.0\"\npermissions:\n  system:\n    stdout: capture\n    stderr: deny\n",
+            )
             .await?;
+        let call = manager
+            .execute_component_call_with_output(&component_id, "greet", "{}")
+            .await?;
+        assert_eq!(call.output.stdout, "hello from stdout");
+        assert_eq!(call.output.stderr, "");
+        Ok(())
+    }
 
-        let component_id = manager.get_component_id_for_tool("fetch").await?;
-        assert_eq!(component_id, TEST_COMPONENT_ID);
+    #[test(tokio::test)]
+    async fn test_reload_component_keeps_policy_and_previous_version() -> Result<()> {
+        let manager = create_test_manager().await?;
+        let component_id = manager
+            .load_bytes("clock", WALL_CLOCK_COMPONENT.as_bytes())
+            .await?;
+        manager
+            .attach_policy_yaml(
+                &component_id,
+                "version: \"1.0\"\npermissions:\n  system:\n    clock:\n      fixedTime: \"2024-01-01T00:00:00Z\"\n",
+            )
+            .await?;
+        let precompiled_path = manager.component_precompiled_path(&component_id);
+        assert!(precompiled_path.exists());
+
+        let outcome = manager.reload_component(&component_id).await?;
+        assert_eq!(outcome.status, LoadResult::Replaced);
+        assert_eq!(outcome.tool_names, ["seconds"]);
+        assert!(manager.get_component_policy_path(&component_id).exists());
+        assert!(precompiled_path.exists());
+        let now: Value = manager.call("seconds", serde_json::json!({})).await?;
+        assert_eq!(now["result"], 1_704_067_200);
+
+        // A broken file leaves the previous version registered, and its
+        // precompiled artifact is dropped
+        std::fs::write(manager.component_path(&component_id), "not a component")?;
+        assert!(manager.reload_component(&component_id).await.is_err());
+        assert!(!precompiled_path.exists());
+        assert_eq!(
+            manager.get_component_load_state(&component_id).await,
+            Some(ComponentLoadState::Loaded)
+        );
+        let now: Value = manager.call("seconds", serde_json::json!({})).await?;
+        assert_eq!(now["result"], 1_704_067_200);
 
+        assert!(manager.reload_component("missing").await.is_err());
         Ok(())
     }
 
     #[test(tokio::test)]
-    async fn test_component_path_update() -> Result<()> {
+    async fn test_deactivated_component_loads_again_on_call() -> Result<()> {
         let manager = create_test_manager().await?;
+        let component_id = manager
+            .load_bytes("clock", WALL_CLOCK_COMPONENT.as_bytes())
+            .await?;
+        manager
+            .attach_policy_yaml(
+                &component_id,
+                "version: \"1.0\"\npermissions:\n  system:\n    clock:\n      fixedTime: \"2024-01-01T00:00:00Z\"\n",
+            )
+            .await?;
 
-        let component_id = "test-component";
-        let expected_path = manager.component_root().join("test-component.wasm");
-        let actual_path = manager.component_path(component_id);
+        assert!(manager.deactivate_component(&component_id).await?);
+        assert!(manager.get_component(&component_id).await.is_none());
+        assert_eq!(
+            manager.get_component_load_state(&component_id).await,
+            Some(ComponentLoadState::MetadataRegistered)
+        );
+        assert_eq!(manager.list_tools().await.len(), 1);
+        assert!(manager.component_path(&component_id).exists());
+        assert!(manager.get_component_policy_path(&component_id).exists());
+        assert!(!manager.deactivate_component(&component_id).await?);
+
+        let now: Value = manager.call("seconds", serde_json::json!({})).await?;
+        assert_eq!(now["result"], 1_704_067_200);
+        assert_eq!(
+            manager.get_component_load_state(&component_id).await,
+            Some(ComponentLoadState::Loaded)
+        );
 
-        assert_eq!(actual_path, expected_path);
+        assert!(manager.deactivate_component("missing").await.is_err());
         Ok(())
     }
 
     #[test(tokio::test)]
-    async fn test_get_wasi_state_for_component_with_policy() -> Result<()> {
-        let manager = create_test_manager().await?;
-        manager.load_test_component().await?;
-
-        // Create and attach a policy
-        let policy_content = r#"
-version: "1.0"
-description: "Test policy"
-permissions:
-  network:
-    allow:
-      - host: "example.com"
-"#;
-        let policy_path = manager.component_root().join("test-policy.yaml");
-        tokio::fs::write(&policy_path, policy_content).await?;
+    async fn test_embedded_components_are_served_read_only() -> Result<()> {
+        const CLOCK: EmbeddedComponent =
+            EmbeddedComponent::new("clock", WALL_CLOCK_COMPONENT.as_bytes()).with_policy(
+                "version: \"1.0\"\npermissions:\n  system:\n    clock:\n      fixedTime: \"2024-01-01T00:00:00Z\"\n",
+            );
+        let manager =
+            create_test_manager_with(|builder| builder.with_embedded_components(&[CLOCK])).await?;
+
+        assert_eq!(manager.list_components().await, ["clock"]);
+        let now: Value = manager.call("seconds", serde_json::json!({})).await?;
+        assert_eq!(now["result"], 1_704_067_200);
+        assert!(!manager.component_path("clock").exists());
+        assert!(!manager.get_component_policy_path("clock").exists());
+
+        let read_only = |error: anyhow::Error| {
+            matches!(
+                error.downcast_ref::<ComponentError>(),
+                Some(ComponentError::ReadOnly { .. })
+            )
+        };
+        assert!(read_only(
+            manager
+                .load_bytes("clock", b"(component)")
+                .await
+                .unwrap_err()
+        ));
+        assert!(read_only(
+            manager.unload_component("clock").await.unwrap_err()
+        ));
+        assert!(read_only(
+            manager
+                .grant_permission("clock", "network", &serde_json::json!({"host": "x.test"}))
+                .await
+                .unwrap_err()
+        ));
+        assert!(read_only(manager.detach_policy("clock").await.unwrap_err()));
+        let now: Value = manager.call("seconds", serde_json::json!({})).await?;
+        assert_eq!(now["result"], 1_704_067_200);
+
+        let broken = EmbeddedComponent::new("broken", b"not a component");
+        assert!(
+            create_test_manager_with(|builder| builder.with_embedded_components(&[broken]))
+                .await
+                .is_err()
+        );
+        Ok(())
+    }
 
-        let policy_uri = format!("file://{}", policy_path.display());
+    #[test(tokio::test)]
+    async fn test_registry_changes_are_numbered_and_persisted() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let changelog = dir.path().join("changes.jsonl");
+        let manager =
+            create_test_manager_with(|builder| builder.with_changelog(changelog.clone())).await?;
+        let component_id = manager
+            .load_bytes("clock", WALL_CLOCK_COMPONENT.as_bytes())
+            .await?;
         manager
-            .attach_policy(TEST_COMPONENT_ID, &policy_uri)
+            .attach_policy_yaml(&component_id, "version: \"1.0\"\npermissions: {}\n")
             .await?;
+        manager.unload_component(&component_id).await?;
 
-        // Test getting WASI state for component with attached policy
-        let _wasi_state = manager
-            .get_wasi_state_for_component(TEST_COMPONENT_ID)
-            .await?;
+        let changes = manager.changes_since(0);
+        let types: Vec<_> = changes
+            .iter()
+            .map(|change| serde_json::to_value(change).unwrap()["type"].clone())
+            .collect();
+        assert_eq!(
+            types,
+            ["component_loaded", "policy_attached", "component_unloaded"]
+        );
+        assert_eq!(
+            changes.iter().map(|change| change.seq).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        assert_eq!(manager.changes_since(2).len(), 1);
+        assert_eq!(std::fs::read_to_string(&changelog)?.lines().count(), 3);
 
+        let restarted =
+            create_test_manager_with(|builder| builder.with_changelog(changelog.clone())).await?;
+        assert_eq!(restarted.last_change_seq(), 3);
+        assert_eq!(restarted.changes_since(0), changes);
         Ok(())
     }
 
     #[test(tokio::test)]
-    async fn test_policy_restoration_on_startup() -> Result<()> {
-        let tempdir = tempfile::tempdir()?;
-
-        // Create a component file
-        let component_content = if let Ok(content) =
-            std::fs::read("examples/fetch-rs/target/wasm32-wasip2/debug/fetch_rs.wasm")
-        {
-            content
-        } else {
-            let path = build_example_component().await?;
-            std::fs::read(path)?
-        };
-        let component_path = tempdir.path().join("test-component.wasm");
-        std::fs::write(&component_path, component_content)?;
-
-        // Create a co-located policy file
-        let policy_content = r#"
-version: "1.0"
-description: "Test policy"
-permissions:
-  network:
-    allow:
-      - host: "example.com"
-"#;
-        let policy_path = tempdir.path().join("test-component.policy.yaml");
-        std::fs::write(&policy_path, policy_content)?;
+    async fn test_inspect_component_matches_registered_tools() -> Result<()> {
+        let manager = create_test_manager().await?;
+        manager.load_test_component().await?;
 
-        // Create a new LifecycleManager to test policy restoration
-        let manager = LifecycleManager::new(&tempdir).await?;
+        // A manager that has not loaded anything compiles the component, and
+        // finds the tools saved when it was loaded
+        let cli = LifecycleManager::new_unloaded(manager.component_root()).await?;
+        let inspected = cli.inspect_component(TEST_COMPONENT_ID).await?;
+        assert_eq!(
+            Some(inspected),
+            cli.get_component_schema(TEST_COMPONENT_ID).await
+        );
+        assert!(cli.get_component(TEST_COMPONENT_ID).await.is_none());
 
-        // Check if policy was restored
-        let policy_info = manager.get_policy_info("test-component").await;
-        assert!(policy_info.is_some());
+        let imports = cli.component_imports(TEST_COMPONENT_ID).await?;
+        assert!(imports
+            .iter()
+            .any(|import| import.name.starts_with("wasi:http/outgoing-handler")));
+        assert!(imports.iter().all(|import| import.provided));
 
+        let error = cli.inspect_component("missing").await.unwrap_err();
+        assert!(error.to_string().contains("Component not found"));
+        assert!(cli.inspect_component("../etc").await.is_err());
         Ok(())
     }
 
     #[test(tokio::test)]
-    async fn test_policy_file_not_found_error() -> Result<()> {
+    async fn test_permission_denials_are_counted_until_unload() -> Result<()> {
         let manager = create_test_manager().await?;
         manager.load_test_component().await?;
+        // The harness records denials itself; ask the lifecycle manager
+        let lifecycle = manager.manager();
+        let arguments = r#"{"url": "https://denied.test/"}"#;
 
-        let non_existent_uri = "file:///non/existent/policy.yaml";
+        manager
+            .execute_component_call(TEST_COMPONENT_ID, "fetch", arguments)
+            .await?;
+        let denials = lifecycle.permission_denials(TEST_COMPONENT_ID);
+        assert_eq!(denials.total, 1);
+        assert_eq!(denials.by_type.get("network"), Some(&1));
+        assert_eq!(denials.recent[0].details["host"], "denied.test");
 
-        // Test attaching non-existent policy file
-        let result = manager
-            .attach_policy(TEST_COMPONENT_ID, non_existent_uri)
-            .await;
-        assert!(result.is_err());
+        // Reloading keeps the counts
+        manager.load_test_component().await?;
+        manager
+            .execute_component_call(TEST_COMPONENT_ID, "fetch", arguments)
+            .await?;
+        assert_eq!(lifecycle.permission_denials(TEST_COMPONENT_ID).total, 2);
+        assert_eq!(lifecycle.all_permission_denials().len(), 1);
 
+        manager.unload_component(TEST_COMPONENT_ID).await?;
+        assert_eq!(
+            lifecycle.permission_denials(TEST_COMPONENT_ID),
+            PermissionDenials::default()
+        );
+        assert!(lifecycle.all_permission_denials().is_empty());
         Ok(())
     }
 
     #[test(tokio::test)]
-    async fn test_policy_invalid_uri_scheme() -> Result<()> {
+    async fn test_component_stats_count_calls_and_errors() -> Result<()> {
         let manager = create_test_manager().await?;
         manager.load_test_component().await?;
+        let lifecycle = manager.manager();
+        let arguments = r#"{"url": "https://denied.test/"}"#;
 
-        let invalid_uri = "invalid-scheme://policy.yaml";
+        for _ in 0..2 {
+            manager
+                .execute_component_call(TEST_COMPONENT_ID, "fetch", arguments)
+                .await?;
+        }
+        assert!(manager
+            .execute_component_call(TEST_COMPONENT_ID, "fetch", "{}")
+            .await
+            .is_err());
+        // Calls of unknown tools are not counted
+        assert!(manager
+            .execute_component_call(TEST_COMPONENT_ID, "no-such-tool", arguments)
+            .await
+            .is_err());
 
-        // Test attaching policy with invalid URI scheme
-        let result = manager.attach_policy(TEST_COMPONENT_ID, invalid_uri).await;
-        assert!(result.is_err());
-        assert!(result
-            .unwrap_err()
-            .to_string()
-            .contains("Unsupported policy scheme"));
+        let stats = lifecycle.get_component_stats(TEST_COMPONENT_ID).unwrap();
+        assert_eq!((stats.calls, stats.errors), (3, 1));
+        assert_eq!(stats.permission_denials, 2);
+        assert!(stats.p50_ms <= stats.p95_ms);
+        assert!(stats.last_used > 0);
+        assert_eq!(stats.tools.keys().collect::<Vec<_>>(), vec!["fetch"]);
+        assert_eq!(lifecycle.get_all_stats().len(), 1);
 
+        manager.unload_component(TEST_COMPONENT_ID).await?;
+        assert!(lifecycle.get_component_stats(TEST_COMPONENT_ID).is_none());
+        assert!(lifecycle.get_all_stats().is_empty());
         Ok(())
     }
 
     #[test(tokio::test)]
-    async fn test_execute_component_call_with_per_component_policy() -> Result<()> {
+    async fn test_prometheus_metrics_report_calls_and_loads() -> Result<()> {
         let manager = create_test_manager().await?;
         manager.load_test_component().await?;
-
-        // Test execution with default policy (no explicit policy attached)
-        // This tests that the execution works with the default policy
-        let result = manager
+        let lifecycle = manager.manager();
+        manager
             .execute_component_call(
                 TEST_COMPONENT_ID,
                 "fetch",
-                r#"{"url": "https://example.com"}"#,
+                r#"{"url": "https://denied.test/"}"#,
             )
-            .await;
+            .await?;
 
-        // The call might fail due to network restrictions in test environment,
-        // but it should at least attempt to execute (not fail due to component not found)
-        // We just verify the call was made successfully in terms of component lookup
-        match result {
-            Ok(_) => {} // Success
-            Err(e) => {
-                // Should not be a component lookup error
-                assert!(!e.to_string().contains("Component not found"));
-            }
+        let metrics = lifecycle.prometheus_metrics().await;
+        let labels = format!(r#"component="{TEST_COMPONENT_ID}",tool="fetch""#);
+        for line in [
+            format!(r#"wassette_tool_calls_total{{{labels},outcome="success"}} 1"#),
+            format!(r#"wassette_tool_calls_total{{{labels},outcome="error"}} 0"#),
+            format!(r#"wassette_tool_call_duration_seconds_count{{{labels}}} 1"#),
+            format!(r#"wassette_permission_denials_total{{component="{TEST_COMPONENT_ID}"}} 1"#),
+            r#"wassette_component_load_duration_seconds_count{outcome="success"} 1"#.to_string(),
+            "wassette_oci_download_bytes_total 0".to_string(),
+            "wassette_active_components 1".to_string(),
+        ] {
+            assert!(
+                metrics.lines().any(|l| l == line),
+                "{line} missing from:\n{metrics}"
+            );
+        }
+        assert!(metrics.contains("# TYPE wassette_tool_call_duration_seconds histogram"));
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_calls_racing_loads_do_not_fail() -> Result<()> {
+        let manager = create_test_manager().await?;
+        manager.load_test_component().await?;
+        let arguments = r#"{"url": "https://denied.test/"}"#;
+
+        // Concurrent first calls of a component registered from metadata:
+        // all but one find it loading
+        let restarted = LifecycleManager::builder(manager.component_root())
+            .with_eager_loading(false)
+            .build()
+            .await?;
+        restarted.populate_registry_from_metadata().await?;
+        let calls = (0..8)
+            .map(|_| restarted.execute_component_call_timed(TEST_COMPONENT_ID, "fetch", arguments));
+        for result in futures::future::join_all(calls).await {
+            result?;
         }
 
+        // Calls while the component is reloaded over and over
+        let reloads = async {
+            for _ in 0..5 {
+                manager.load_test_component().await?;
+            }
+            Ok::<_, anyhow::Error>(())
+        };
+        let calls = async {
+            for _ in 0..10 {
+                manager
+                    .execute_component_call(TEST_COMPONENT_ID, "fetch", arguments)
+                    .await?;
+            }
+            Ok::<_, anyhow::Error>(())
+        };
+        tokio::try_join!(reloads, calls)?;
+
+        // A call that finds the wasm file being swapped is retried
+        let restarted = LifecycleManager::builder(manager.component_root())
+            .with_eager_loading(false)
+            .build()
+            .await?;
+        restarted.populate_registry_from_metadata().await?;
+        let wasm_path = restarted.component_path(TEST_COMPONENT_ID);
+        let swapped_path = wasm_path.with_extension("swap");
+        tokio::fs::rename(&wasm_path, &swapped_path).await?;
+        let swap_back = async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            tokio::fs::rename(&swapped_path, &wasm_path).await
+        };
+        let (result, swapped) = tokio::join!(
+            restarted.execute_component_call_timed(TEST_COMPONENT_ID, "fetch", arguments),
+            swap_back
+        );
+        swapped?;
+        let (_, timing) = result?;
+        assert!(timing.retries >= 1);
+        assert_eq!(
+            restarted
+                .get_component_usage(TEST_COMPONENT_ID)
+                .unwrap()
+                .transient_retries,
+            u64::from(timing.retries)
+        );
+
+        // Unknown components are not retried
+        let error = manager
+            .execute_component_call("missing", "fetch", arguments)
+            .await
+            .unwrap_err();
+        assert!(error.downcast_ref::<ComponentError>().is_none());
+        assert!(error.to_string().contains("Component not found"));
+
         Ok(())
     }
 
@@ -2051,10 +9326,12 @@ permissions:
 
     #[test(tokio::test)]
     async fn test_revoke_permission_storage() -> Result<()> {
-        let manager = create_test_manager().await?;
+        let manager =
+            create_test_manager_with(|builder| builder.with_absolute_storage_paths(true)).await?;
         manager.load_test_component().await?;
 
         // Grant storage permission first
+        std::fs::create_dir_all("/tmp/test")?;
         let details = serde_json::json!({"uri": "fs:///tmp/test", "access": ["read", "write"]});
         manager
             .grant_permission(TEST_COMPONENT_ID, "storage", &details)
@@ -2107,7 +9384,8 @@ permissions:
 
     #[test(tokio::test)]
     async fn test_reset_permission() -> Result<()> {
-        let manager = create_test_manager().await?;
+        let manager =
+            create_test_manager_with(|builder| builder.with_absolute_storage_paths(true)).await?;
         manager.load_test_component().await?;
 
         // Grant multiple permissions first
@@ -2116,6 +9394,7 @@ permissions:
             .grant_permission(TEST_COMPONENT_ID, "network", &network_details)
             .await?;
 
+        std::fs::create_dir_all("/tmp/test")?;
         let storage_details = serde_json::json!({"uri": "fs:///tmp/test", "access": ["read"]});
         manager
             .grant_permission(TEST_COMPONENT_ID, "storage", &storage_details)
@@ -0,0 +1,201 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! The `wassette:limits/get` host interface, through which a component reads
+//! the limits it runs under and how much of them it has used:
+//!
+//! ```wit
+//! package wassette:limits;
+//!
+//! interface get {
+//!     record host-connections {
+//!         host: string,
+//!         available: u32,
+//!         max: u32,
+//!     }
+//!
+//!     record limits {
+//!         memory-limit: option<u64>,
+//!         memory-used: u64,
+//!         http-connections: list<host-connections>,
+//!         execution-remaining-ms: option<u64>,
+//!     }
+//!
+//!     current: func() -> result<limits, string>;
+//! }
+//! ```
+//!
+//! The values are read from the resource limiter and the outbound HTTP
+//! limiter that enforce the limits. Only components whose policy sets
+//! `permissions.introspection: true` get them; for others `current` returns
+//! an error.
+
+use anyhow::Result;
+use wasmtime::component::{ComponentType, Linker, Lower};
+use wasmtime::StoreContextMut;
+
+use crate::{WasiState, WassetteWasiState};
+
+/// Name of the host interface components import to read their limits.
+pub const LIMITS_INTERFACE: &str = "wassette:limits/get";
+
+/// Connection slots to one host the component may send requests to.
+#[derive(Debug, Clone, PartialEq, Eq, ComponentType, Lower)]
+#[component(record)]
+pub(crate) struct HostConnections {
+    host: String,
+    /// Slots free right now
+    available: u32,
+    /// Slots shared by all components for the host
+    max: u32,
+}
+
+/// The limits a component instance runs under.
+#[derive(Debug, Clone, PartialEq, Eq, ComponentType, Lower)]
+#[component(record)]
+pub(crate) struct Limits {
+    /// Bytes each linear memory may grow to, if limited
+    #[component(name = "memory-limit")]
+    memory_limit: Option<u64>,
+    /// Bytes of linear memory allocated by the instance
+    #[component(name = "memory-used")]
+    memory_used: u64,
+    /// Connection slots for each allowed host
+    #[component(name = "http-connections")]
+    http_connections: Vec<HostConnections>,
+    /// Time left before the call is stopped, if it has a deadline
+    #[component(name = "execution-remaining-ms")]
+    execution_remaining_ms: Option<u64>,
+}
+
+impl Limits {
+    /// The limits of the instance `state` belongs to.
+    pub(crate) fn of(state: &WassetteWasiState<WasiState>) -> Self {
+        let limiter = state.inner.resource_limiter.as_ref();
+        Self {
+            memory_limit: limiter.and_then(|limiter| limiter.memory_limit()),
+            memory_used: limiter.map_or(0, |limiter| limiter.memory_used() as u64),
+            http_connections: state
+                .host_connection_slots()
+                .into_iter()
+                .map(|(host, available, max)| HostConnections {
+                    host,
+                    available: u32::try_from(available).unwrap_or(u32::MAX),
+                    max: u32::try_from(max).unwrap_or(u32::MAX),
+                })
+                .collect(),
+            execution_remaining_ms: state.inner.execution_deadline.map(|deadline| {
+                deadline
+                    .saturating_duration_since(std::time::Instant::now())
+                    .as_millis() as u64
+            }),
+        }
+    }
+}
+
+/// Define [`LIMITS_INTERFACE`] in `linker`.
+pub(crate) fn add_to_linker(linker: &mut Linker<WassetteWasiState<WasiState>>) -> Result<()> {
+    linker.instance(LIMITS_INTERFACE)?.func_wrap(
+        "current",
+        |store: StoreContextMut<'_, WassetteWasiState<WasiState>>, (): ()| {
+            let state = store.data();
+            let result = if state.inner.introspection {
+                Ok(Limits::of(state))
+            } else {
+                Err("Reading limits requires `permissions.introspection: true` in the component's policy".to_string())
+            };
+            Ok((result,))
+        },
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use policy::PolicyParser;
+    use wasmtime::ResourceLimiter;
+
+    use super::*;
+    use crate::http::OutboundHttpLimiter;
+    use crate::wasistate::create_wasi_state_template_from_policy;
+    use crate::OutboundHttpConfig;
+
+    fn state_for(policy: &str) -> Result<WassetteWasiState<WasiState>> {
+        let policy = PolicyParser::parse_str(policy)?;
+        let template = create_wasi_state_template_from_policy(
+            &policy,
+            std::path::Path::new("/tmp"),
+            &HashMap::new(),
+            None,
+        )?;
+        let limiter = Arc::new(OutboundHttpLimiter::new(OutboundHttpConfig {
+            max_connections_per_host: 4,
+            ..Default::default()
+        }));
+        Ok(
+            WassetteWasiState::new(template.build()?, template.allowed_hosts.clone())?
+                .with_outbound_limits(limiter, HashMap::new()),
+        )
+    }
+
+    #[test]
+    fn test_limits_come_from_the_enforcing_limiters() -> Result<()> {
+        let mut state = state_for(
+            r#"
+version: "1.0"
+permissions:
+  introspection: true
+  network:
+    allow:
+      - host: "api.example.com"
+      - host: "https://cdn.example.com"
+  resources:
+    limits:
+      memory: "1Mi"
+"#,
+        )?;
+        assert!(state.inner.introspection);
+        let limiter = state.inner.resource_limiter.as_mut().unwrap();
+        assert!(limiter.memory_growing(0, 65536, None)?);
+        assert!(!limiter.memory_growing(65536, 2 << 20, None)?);
+
+        let limits = Limits::of(&state);
+        assert_eq!(limits.memory_limit, Some(1 << 20));
+        assert_eq!(limits.memory_used, 65536);
+        assert_eq!(
+            limits.http_connections,
+            [
+                HostConnections {
+                    host: "api.example.com".to_string(),
+                    available: 4,
+                    max: 4,
+                },
+                HostConnections {
+                    host: "cdn.example.com".to_string(),
+                    available: 4,
+                    max: 4,
+                },
+            ]
+        );
+        assert_eq!(limits.execution_remaining_ms, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_is_tracked_without_a_limit_only_with_introspection() -> Result<()> {
+        let state = state_for("version: \"1.0\"\npermissions: {}\n")?;
+        assert!(!state.inner.introspection);
+        assert!(state.inner.resource_limiter.is_none());
+
+        let mut state = state_for("version: \"1.0\"\npermissions:\n  introspection: true\n")?;
+        let limiter = state.inner.resource_limiter.as_mut().unwrap();
+        assert!(limiter.memory_growing(0, 1 << 30, None)?);
+        let limits = Limits::of(&state);
+        assert_eq!(limits.memory_limit, None);
+        assert_eq!(limits.memory_used, 1 << 30);
+        Ok(())
+    }
+}
@@ -0,0 +1,240 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Where each known component is in the loading process.
+//!
+//! A component in the component directory is not necessarily compiled: at
+//! startup its tools may only be registered from cached metadata, and it may
+//! have failed to compile in the background. The registry records a
+//! [`ComponentLoadState`] per component so listings can tell callers why a
+//! component is known but not loaded, or why a registered component no
+//! longer matches its wasm file.
+//!
+//! Tools registered from cached metadata carry a hash of their schemas, so
+//! that once the component is compiled the manager can tell whether clients
+//! were briefly served schemas that differ from the wasm, see
+//! [`StaleSchema`].
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use component2json::ToolMetadata;
+use serde::Serialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::ValidationStamp;
+
+/// How often a server checks registered components for wasm files that were
+/// replaced on disk, see
+/// [`LifecycleManager::check_drift`](crate::LifecycleManager::check_drift).
+pub const DEFAULT_DRIFT_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Loading state of a component known to the lifecycle manager.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ComponentLoadState {
+    /// The component's wasm file is in the component directory but has not
+    /// been read yet
+    Discovered,
+    /// The component's tools were registered from cached metadata; it is
+    /// compiled on first use
+    MetadataRegistered,
+    /// The component's tools were registered ahead of time with
+    /// [`LifecycleManager::register_virtual_component`](crate::LifecycleManager::register_virtual_component);
+    /// it is fetched and compiled on first use
+    Virtual {
+        /// URI the component is loaded from
+        source_uri: String,
+    },
+    /// The component is being compiled and instantiated
+    Loading,
+    /// The component is compiled and its tools are callable
+    Loaded,
+    /// The last attempt to compile or instantiate the component failed
+    Failed {
+        /// Why the component could not be loaded
+        reason: String,
+    },
+    /// The component compiled but was refused because its health probe failed
+    Disabled {
+        /// Why the health probe failed
+        reason: String,
+    },
+    /// The component's wasm file changed on disk after its tools were
+    /// registered; calls are refused until the component is loaded again
+    Drifted {
+        /// How the file on disk differs from the registered one
+        reason: String,
+    },
+}
+
+impl ComponentLoadState {
+    /// Why the component is not loaded, for failed, disabled, and drifted
+    /// components.
+    pub fn reason(&self) -> Option<&str> {
+        match self {
+            Self::Failed { reason } | Self::Disabled { reason } | Self::Drifted { reason } => {
+                Some(reason)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Tool schemas that were served from cached metadata but differ from the
+/// ones the compiled component exposes, for instance because its wasm file
+/// was replaced while the stamp of the file stayed the same. The compiled
+/// schemas replace the served ones when the component is loaded, and the
+/// [`LifecycleEvent::ComponentLoaded`](crate::LifecycleEvent::ComponentLoaded)
+/// of the load tells clients to list tools again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StaleSchema {
+    /// Hash of the tool schemas that were served
+    pub served_hash: String,
+    /// Hash of the tool schemas of the compiled component
+    pub compiled_hash: String,
+    /// Tools the compiled component exposes that were not served
+    pub added_tools: Vec<String>,
+    /// Tools that were served but the compiled component does not expose
+    pub removed_tools: Vec<String>,
+    /// Tools whose served schema differs from the compiled one
+    pub changed_tools: Vec<String>,
+}
+
+/// Hash of a set of tools, their identifiers, names, and schemas, in
+/// `sha256:<hex>` form. The order of the tools does not matter.
+pub(crate) fn schema_hash(tools: &[ToolMetadata]) -> String {
+    let mut tools: Vec<String> = tools
+        .iter()
+        .map(|tool| {
+            json!({
+                "identifier": tool.identifier,
+                "name": tool.normalized_name,
+                "schema": tool.schema,
+            })
+            .to_string()
+        })
+        .collect();
+    tools.sort();
+    let mut hasher = Sha256::new();
+    for tool in tools {
+        hasher.update(tool.as_bytes());
+        hasher.update(b"\n");
+    }
+    format!("sha256:{:x}", hasher.finalize())
+}
+
+/// Progress of loading the component directory in the background with
+/// [`LifecycleManager::load_existing_components_async`](crate::LifecycleManager::load_existing_components_async).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BackgroundLoadStatus {
+    /// Whether background loading has started
+    pub started: bool,
+    /// Whether every queued component has been loaded or has failed
+    pub completed: bool,
+    /// Number of components queued for loading
+    pub queued: usize,
+    /// Number of queued components that were loaded or failed to load
+    pub processed: usize,
+}
+
+/// Whether the server has finished its first pass over the component
+/// directory, and what it loaded, as reported by
+/// [`LifecycleManager::loading_status`](crate::LifecycleManager::loading_status).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LoadStatus {
+    /// Whether background loading has processed every queued component
+    pub ready: bool,
+    /// Components that are compiled and callable
+    pub loaded: usize,
+    /// Components known in any load state, including loaded ones
+    pub known: usize,
+    /// Why each component that failed to load did so, by component ID
+    pub failures: BTreeMap<String, String>,
+    /// Why background loading stopped before processing every component
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A recorded load state together with the stamp of the wasm file a failure
+/// was observed on, the stamp of the file the registered tools came from,
+/// and the hash of the registered tools' schemas.
+#[derive(Debug, Clone)]
+pub(crate) struct LoadStateEntry {
+    pub(crate) state: ComponentLoadState,
+    pub(crate) failed_stamp: Option<ValidationStamp>,
+    pub(crate) registered_stamp: Option<ValidationStamp>,
+    pub(crate) schema_hash: Option<String>,
+    /// The last time stale schemas were found to have been served
+    pub(crate) stale_schema: Option<StaleSchema>,
+}
+
+impl LoadStateEntry {
+    /// An entry for tools registered from the file with `stamp`.
+    pub(crate) fn registered(state: ComponentLoadState, stamp: Option<ValidationStamp>) -> Self {
+        Self {
+            state,
+            failed_stamp: None,
+            registered_stamp: stamp,
+            schema_hash: None,
+            stale_schema: None,
+        }
+    }
+
+    /// The same entry, for tools whose schemas hash to `schema_hash`.
+    pub(crate) fn with_schema_hash(mut self, schema_hash: Option<String>) -> Self {
+        self.schema_hash = schema_hash;
+        self
+    }
+}
+
+impl From<ComponentLoadState> for LoadStateEntry {
+    fn from(state: ComponentLoadState) -> Self {
+        Self::registered(state, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_state_serializes_with_reason() {
+        let state = ComponentLoadState::Failed {
+            reason: "invalid magic number".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(&state).unwrap(),
+            serde_json::json!({"state": "failed", "reason": "invalid magic number"})
+        );
+        assert_eq!(state.reason(), Some("invalid magic number"));
+
+        assert_eq!(
+            serde_json::to_value(ComponentLoadState::MetadataRegistered).unwrap(),
+            serde_json::json!({"state": "metadata_registered"})
+        );
+        assert_eq!(ComponentLoadState::Loaded.reason(), None);
+    }
+
+    #[test]
+    fn test_schema_hash_ignores_tool_order() {
+        let tool = |name: &str, schema: serde_json::Value| ToolMetadata {
+            identifier: component2json::FunctionIdentifier {
+                package_name: None,
+                interface_name: None,
+                function_name: name.to_string(),
+            },
+            normalized_name: name.to_string(),
+            schema,
+        };
+        let fetch = tool("fetch", json!({"name": "fetch"}));
+        let time = tool("time", json!({"name": "time"}));
+        let hash = schema_hash(&[fetch.clone(), time.clone()]);
+        assert!(hash.starts_with("sha256:"));
+        assert_eq!(hash, schema_hash(&[time.clone(), fetch]));
+
+        let changed = tool("fetch", json!({"name": "fetch", "description": "new"}));
+        assert_ne!(hash, schema_hash(&[changed, time]));
+    }
+}
@@ -2,14 +2,52 @@
 // Licensed under the MIT license.
 
 //! A module for downloading and loading components and policies from various sources.
+use std::future::Future;
 use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Context, Result};
 use futures::TryStreamExt;
 use tokio::fs::metadata;
 use tokio::io::AsyncWriteExt;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 
+use crate::download_cache::{CacheValidators, DownloadCache};
+use crate::progress::{ProgressReporter, ProgressWriter};
+
+/// A download that was abandoned because its cancellation token was
+/// triggered.
+///
+/// Returned inside [`anyhow::Error`]; use `downcast_ref` to match on it.
+#[derive(Debug, thiserror::Error)]
+#[error("Download of '{uri}' was cancelled")]
+pub struct DownloadCancelled {
+    /// The URI that was being downloaded
+    pub uri: String,
+}
+
+/// Run `download`, dropping it as soon as `cancellation` is triggered.
+///
+/// Dropping the download aborts its HTTP or OCI stream, and the temporary
+/// directory it was writing to is removed with it.
+pub(crate) async fn cancellable<T>(
+    uri: &str,
+    cancellation: &CancellationToken,
+    download: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    tokio::select! {
+        biased;
+        _ = cancellation.cancelled() => {
+            debug!(%uri, "Download cancelled");
+            Err(DownloadCancelled {
+                uri: uri.to_string(),
+            }
+            .into())
+        }
+        result = download => result,
+    }
+}
+
 /// Represents a downloaded resource, either from a local file or a temporary one.
 pub enum DownloadedResource {
     Local(PathBuf),
@@ -69,7 +107,21 @@ impl DownloadedResource {
             .ok_or_else(|| anyhow::anyhow!("Failed to extract resource ID from path"))
     }
 
-    pub async fn copy_to(self, dest: impl AsRef<Path>) -> Result<()> {
+    /// Path of a policy file downloaded alongside the resource, if one exists.
+    fn colocated_policy_path(&self) -> Option<PathBuf> {
+        match self {
+            DownloadedResource::Local(_) => None,
+            DownloadedResource::Temp((tempdir, file)) => {
+                let wasm_stem = file.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+                let policy_path = tempdir.path().join(format!("{wasm_stem}.policy.yaml"));
+                policy_path.exists().then_some(policy_path)
+            }
+        }
+    }
+
+    /// Copy the resource into the directory `dest` as `<component_id>.wasm`,
+    /// along with a policy file downloaded alongside it.
+    pub async fn copy_to(self, dest: impl AsRef<Path>, component_id: &str) -> Result<()> {
         let meta = tokio::fs::metadata(&dest).await?;
         if !meta.is_dir() {
             bail!(
@@ -77,23 +129,18 @@ impl DownloadedResource {
                 dest.as_ref().display()
             );
         }
+        let policy_path = self.colocated_policy_path();
         match self {
             DownloadedResource::Local(path) => {
-                let dest = dest.as_ref().join(
-                    path.file_name()
-                        .context("Path to copy is missing filename")?,
-                );
+                let dest = dest.as_ref().join(format!("{component_id}.wasm"));
                 tokio::fs::copy(path, dest).await?;
             }
             DownloadedResource::Temp((tempdir, file)) => {
                 let dest_dir = dest.as_ref();
 
                 // Also check for and copy any co-located policy file
-                let wasm_stem = file.file_stem().and_then(|s| s.to_str()).unwrap_or("");
-                let policy_path = tempdir.path().join(format!("{wasm_stem}.policy.yaml"));
-
-                if policy_path.exists() {
-                    let policy_dest = dest_dir.join(format!("{wasm_stem}.policy.yaml"));
+                if let Some(policy_path) = policy_path {
+                    let policy_dest = dest_dir.join(format!("{component_id}.policy.yaml"));
                     debug!(
                         "Copying co-located policy file from {:?} to {:?}",
                         policy_path, policy_dest
@@ -104,10 +151,7 @@ impl DownloadedResource {
                 }
 
                 // Copy the main file (WASM)
-                let dest_file = dest_dir.join(
-                    file.file_name()
-                        .context("Path to copy is missing filename")?,
-                );
+                let dest_file = dest_dir.join(format!("{component_id}.wasm"));
 
                 // Copy the main WASM file
                 match tokio::fs::rename(&file, &dest_file).await {
@@ -156,7 +200,7 @@ pub trait Loadable: Sized {
     async fn from_oci_reference_with_progress(
         reference: &str,
         oci_client: &oci_client::Client,
-        show_progress: bool,
+        progress: Option<&dyn ProgressReporter>,
         auth: &oci_client::secrets::RegistryAuth,
     ) -> Result<DownloadedResource>;
     async fn from_url(url: &str, http_client: &reqwest::Client) -> Result<DownloadedResource>;
@@ -192,100 +236,108 @@ impl Loadable for ComponentResource {
     async fn from_oci_reference_with_progress(
         reference: &str,
         oci_client: &oci_client::Client,
-        show_progress: bool,
+        progress: Option<&dyn ProgressReporter>,
         auth: &oci_client::secrets::RegistryAuth,
     ) -> Result<DownloadedResource> {
         let reference: oci_client::Reference =
             reference.parse().context("Failed to parse OCI reference")?;
 
-        if show_progress {
-            eprintln!("Downloading component from {}...", reference);
-        }
+        let (manifest, _digest) = oci_client.pull_image_manifest(&reference, auth).await?;
+
+        if manifest
+            .layers
+            .iter()
+            .any(|layer| layer.media_type != oci_wasm::WASM_LAYER_MEDIA_TYPE)
+        {
+            // Multi-layer artifact detected - use our custom handler
+            info!("Multi-layer OCI artifact detected, using direct OCI client");
 
-        // First try oci-wasm for backwards compatibility with single-layer artifacts
-        let wasm_client = oci_wasm::WasmClient::from(oci_client.clone());
-        let result = wasm_client
-            .pull(&reference, auth)
-            .await;
-
-        match result {
-            Ok(data) => {
-                // Successfully pulled with oci-wasm - this is a single-layer WASM artifact
-                debug!("Successfully pulled single-layer WASM artifact");
-                if show_progress {
-                    eprintln!("✓ Downloaded {} bytes", data.layers[0].data.len());
+            // Use our new multi-layer support to get ALL layers
+            let artifact = crate::oci_multi_layer::pull_multi_layer_artifact_with_progress(
+                &reference, oci_client, progress, auth,
+            )
+            .await
+            .context("Failed to extract layers from multi-layer OCI artifact")?;
+
+            // Save the WASM data
+            let component_name = reference.repository().replace('/', "_");
+            let (downloaded_resource, mut file) =
+                DownloadedResource::new_temp_file(&component_name, Self::FILE_EXTENSION).await?;
+
+            file.write_all(&artifact.wasm_data).await?;
+            file.flush().await?;
+            file.sync_all().await?;
+            drop(file);
+
+            // If there's a policy, save it alongside the WASM in the temp directory
+            if let Some(policy_data) = artifact.policy_data {
+                info!("Saving policy layer alongside component");
+
+                // Create policy file in the same temp directory as the WASM
+                if let DownloadedResource::Temp((ref tempdir, ref _wasm_path)) = downloaded_resource
+                {
+                    let policy_path = tempdir.path().join(format!("{component_name}.policy.yaml"));
+                    tokio::fs::write(&policy_path, &policy_data)
+                        .await
+                        .context("Failed to save policy file")?;
+                    info!("Policy saved to: {:?}", policy_path);
                 }
-                let (downloaded_resource, mut file) = DownloadedResource::new_temp_file(
-                    reference.repository().replace('/', "_"),
-                    Self::FILE_EXTENSION,
-                )
-                .await?;
-
-                // Use the first layer (oci-wasm validated it's WASM)
-                file.write_all(&data.layers[0].data).await?;
-                file.flush().await?;
-                file.sync_all().await?;
-                drop(file);
-                Ok(downloaded_resource)
             }
-            Err(e) => {
-                // Check if this is a multi-layer artifact issue
-                let error_str = e.to_string();
-                if error_str.contains("Incompatible layer media type") {
-                    // Multi-layer artifact detected - use our custom handler
-                    info!("Multi-layer OCI artifact detected, using direct OCI client");
-
-                    // Use our new multi-layer support to get ALL layers
-                    let artifact = crate::oci_multi_layer::pull_multi_layer_artifact_with_progress(
-                        &reference,
-                        oci_client,
-                        show_progress,
-                        auth,
-                    )
-                    .await
-                    .context("Failed to extract layers from multi-layer OCI artifact")?;
-
-                    // Save the WASM data
-                    let component_name = reference.repository().replace('/', "_");
-                    let (downloaded_resource, mut file) =
-                        DownloadedResource::new_temp_file(&component_name, Self::FILE_EXTENSION)
-                            .await?;
-
-                    file.write_all(&artifact.wasm_data).await?;
-                    file.flush().await?;
-                    file.sync_all().await?;
-                    drop(file);
-
-                    // If there's a policy, save it alongside the WASM in the temp directory
-                    if let Some(policy_data) = artifact.policy_data {
-                        info!("Saving policy layer alongside component");
-
-                        // Create policy file in the same temp directory as the WASM
-                        if let DownloadedResource::Temp((ref tempdir, ref _wasm_path)) =
-                            downloaded_resource
-                        {
-                            let policy_path =
-                                tempdir.path().join(format!("{component_name}.policy.yaml"));
-                            tokio::fs::write(&policy_path, &policy_data)
-                                .await
-                                .context("Failed to save policy file")?;
-                            info!("Policy saved to: {:?}", policy_path);
-                        }
-                    }
 
-                    info!("Successfully extracted WASM component and policy from multi-layer artifact");
+            info!("Successfully extracted WASM component and policy from multi-layer artifact");
 
-                    Ok(downloaded_resource)
-                } else {
-                    // Some other error - propagate it
-                    Err(e)
-                }
-            }
+            return Ok(downloaded_resource);
+        }
+
+        // A single-layer WASM artifact, checked the way oci-wasm checks it,
+        // streamed to disk so its progress can be reported
+        if manifest.layers.len() != 1 {
+            bail!("Wasm components must have exactly one layer");
         }
+        if manifest.config.media_type != oci_wasm::WASM_MANIFEST_CONFIG_MEDIA_TYPE {
+            bail!(
+                "Wasm components must have a config of type {}",
+                oci_wasm::WASM_MANIFEST_CONFIG_MEDIA_TYPE
+            );
+        }
+        let layer = &manifest.layers[0];
+        let (downloaded_resource, mut file) = DownloadedResource::new_temp_file(
+            reference.repository().replace('/', "_"),
+            Self::FILE_EXTENSION,
+        )
+        .await?;
+
+        // Pulling by descriptor verifies the layer digest
+        let mut writer =
+            ProgressWriter::new(&mut file, progress, 0, u64::try_from(layer.size).ok());
+        oci_client
+            .pull_blob(&reference, layer, &mut writer)
+            .await
+            .context("Failed to pull component layer")?;
+        debug!(
+            "Successfully pulled single-layer WASM artifact: {} bytes",
+            writer.downloaded()
+        );
+        file.flush().await?;
+        file.sync_all().await?;
+        drop(file);
+        Ok(downloaded_resource)
     }
 
     async fn from_url(url: &str, http_client: &reqwest::Client) -> Result<DownloadedResource> {
         let resp = http_client.get(url).send().await?;
+        Self::from_response(url, resp, None).await
+    }
+}
+
+impl ComponentResource {
+    /// Write the body of a component download response to a temporary file,
+    /// reporting its progress against the response's `Content-Length`.
+    async fn from_response(
+        url: &str,
+        resp: reqwest::Response,
+        progress: Option<&dyn ProgressReporter>,
+    ) -> Result<DownloadedResource> {
         let status = resp.status();
         if !status.is_success() {
             let body = resp.text().await.unwrap_or_default();
@@ -296,17 +348,20 @@ impl Loadable for ComponentResource {
                 body
             );
         }
-        let name = resp
-            .url()
-            .path_segments()
-            .and_then(|mut segments| segments.next_back())
-            .context("Failed to discover name from URL")?
-            .trim_end_matches(&format!(".{}", Self::FILE_EXTENSION));
+        let name = sanitize_component_name(
+            resp.url()
+                .path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .context("Failed to discover name from URL")?
+                .trim_end_matches(&format!(".{}", Self::FILE_EXTENSION)),
+        );
         let (downloaded_resource, mut file) =
             DownloadedResource::new_temp_file(name, Self::FILE_EXTENSION).await?;
+        let total = resp.content_length();
         let stream = resp.bytes_stream();
         let mut reader = tokio_util::io::StreamReader::new(stream.map_err(std::io::Error::other));
-        tokio::io::copy(&mut reader, &mut file)
+        let mut writer = ProgressWriter::new(&mut file, progress, 0, total);
+        tokio::io::copy(&mut reader, &mut writer)
             .await
             .context("Failed to write downloaded component to temp file")?;
         file.flush().await?;
@@ -316,6 +371,28 @@ impl Loadable for ComponentResource {
     }
 }
 
+/// Component ID for the last path segment of a URL: characters other than
+/// ASCII letters, digits, `-`, and `_` become `_`, so the ID is safe to use in
+/// the names of the component's policy and metadata files.
+pub(crate) fn sanitize_component_name(segment: &str) -> String {
+    let name: String = segment
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let name = name.trim_matches('_');
+    if name.is_empty() {
+        "component".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
 /// Loadable implementation for policies
 pub struct PolicyResource;
 
@@ -339,7 +416,7 @@ impl Loadable for PolicyResource {
     async fn from_oci_reference_with_progress(
         _reference: &str,
         _oci_client: &oci_client::Client,
-        _show_progress: bool,
+        _progress: Option<&dyn ProgressReporter>,
         _auth: &oci_client::secrets::RegistryAuth,
     ) -> Result<DownloadedResource> {
         bail!("OCI references are not supported for policy resources. Use 'file://' or 'https://' schemes instead.")
@@ -378,6 +455,96 @@ impl Loadable for PolicyResource {
     }
 }
 
+/// Load a component, consulting the downloads cache before fetching remote artifacts.
+///
+/// `https://` downloads are revalidated with the server using the stored
+/// `ETag`/`Last-Modified` validators and reused on `304 Not Modified`. OCI
+/// references pinned by digest are immutable and reused without a network
+/// round-trip. Everything else is fetched as usual. Downloads, but not cache
+/// hits, are reported to `progress`.
+pub(crate) async fn load_component_with_cache(
+    uri: &str,
+    cache: &DownloadCache,
+    oci_client: &oci_wasm::WasmClient,
+    http_client: &reqwest::Client,
+    progress: Option<&dyn ProgressReporter>,
+    auth: &oci_client::secrets::RegistryAuth,
+) -> Result<DownloadedResource> {
+    let uri = uri.trim();
+    match uri.split_once("://") {
+        Some(("https", _)) => fetch_component_url_cached(uri, cache, http_client, progress).await,
+        Some(("oci", reference)) if reference.contains("@sha256:") => {
+            if let Some(blob) = cache.lookup(uri).await {
+                debug!(%uri, digest = %blob.digest, "Reusing cached OCI download");
+                return cache.materialize(&blob).await;
+            }
+            let resource = load_resource_with_progress::<ComponentResource>(
+                uri,
+                oci_client,
+                http_client,
+                progress,
+                auth,
+            )
+            .await?;
+            // Policies bundled in multi-layer artifacts are not cached, so such
+            // artifacts are always pulled again.
+            if resource.colocated_policy_path().is_none() {
+                if let Err(error) = cache
+                    .store(uri, resource.as_ref(), CacheValidators::default())
+                    .await
+                {
+                    warn!(%uri, %error, "Failed to cache downloaded component");
+                }
+            }
+            Ok(resource)
+        }
+        _ => {
+            load_resource_with_progress::<ComponentResource>(
+                uri,
+                oci_client,
+                http_client,
+                progress,
+                auth,
+            )
+            .await
+        }
+    }
+}
+
+async fn fetch_component_url_cached(
+    url: &str,
+    cache: &DownloadCache,
+    http_client: &reqwest::Client,
+    progress: Option<&dyn ProgressReporter>,
+) -> Result<DownloadedResource> {
+    let cached = cache.lookup(url).await;
+
+    let mut request = http_client.get(url);
+    if let Some(blob) = &cached {
+        if let Some(etag) = &blob.validators.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &blob.validators.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let resp = request.send().await?;
+    if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+        if let Some(blob) = cached {
+            debug!(%url, digest = %blob.digest, "Component not modified, reusing cached download");
+            return cache.materialize(&blob).await;
+        }
+    }
+
+    let validators = CacheValidators::from_headers(resp.headers());
+    let resource = ComponentResource::from_response(url, resp, progress).await?;
+    if let Err(error) = cache.store(url, resource.as_ref(), validators).await {
+        warn!(%url, %error, "Failed to cache downloaded component");
+    }
+    Ok(resource)
+}
+
 /// Generic resource loading function
 pub(crate) async fn load_resource<T: Loadable>(
     uri: &str,
@@ -385,7 +552,7 @@ pub(crate) async fn load_resource<T: Loadable>(
     http_client: &reqwest::Client,
     auth: &oci_client::secrets::RegistryAuth,
 ) -> Result<DownloadedResource> {
-    load_resource_with_progress::<T>(uri, oci_client, http_client, false, auth).await
+    load_resource_with_progress::<T>(uri, oci_client, http_client, None, auth).await
 }
 
 /// Generic resource loading function with optional progress reporting
@@ -393,7 +560,7 @@ pub(crate) async fn load_resource_with_progress<T: Loadable>(
     uri: &str,
     oci_client: &oci_wasm::WasmClient,
     http_client: &reqwest::Client,
-    show_progress: bool,
+    progress: Option<&dyn ProgressReporter>,
     auth: &oci_client::secrets::RegistryAuth,
 ) -> Result<DownloadedResource> {
     let uri = uri.trim();
@@ -405,9 +572,7 @@ pub(crate) async fn load_resource_with_progress<T: Loadable>(
 
     match scheme {
         "file" => T::from_local_file(Path::new(reference)).await,
-        "oci" => {
-            T::from_oci_reference_with_progress(reference, oci_client, show_progress, auth).await
-        }
+        "oci" => T::from_oci_reference_with_progress(reference, oci_client, progress, auth).await,
         "https" => T::from_url(uri, http_client).await,
         _ => bail!("Unsupported {} scheme: {}", T::RESOURCE_TYPE, scheme),
     }
@@ -417,6 +582,36 @@ pub(crate) async fn load_resource_with_progress<T: Loadable>(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_sanitize_component_name() {
+        assert_eq!(sanitize_component_name("time-server_js"), "time-server_js");
+        assert_eq!(sanitize_component_name("tool.v2"), "tool_v2");
+        assert_eq!(sanitize_component_name("my%20tool"), "my_20tool");
+        assert_eq!(sanitize_component_name("..."), "component");
+    }
+
+    #[tokio::test]
+    async fn test_component_download_reports_progress() {
+        let body = vec![0u8; 64 * 1024];
+        let resp = reqwest::Response::from(http::Response::new(body.clone()));
+        let recorded = crate::progress::tests::RecordedProgress::default();
+        let resource =
+            ComponentResource::from_response("https://example.com/", resp, Some(&recorded))
+                .await
+                .unwrap();
+        assert_eq!(std::fs::read(resource.as_ref()).unwrap(), body);
+
+        let recorded = recorded.0.into_inner().unwrap();
+        assert_eq!(
+            recorded.first(),
+            Some(&crate::LoadProgress::Downloading {
+                downloaded: 0,
+                total: Some(body.len() as u64),
+            })
+        );
+        assert_eq!(recorded.last().unwrap().percent(), Some(100));
+    }
+
     #[test]
     fn test_load_resource_with_progress_api_exists() {
         // Compile-time test to verify the progress-aware API exists
@@ -0,0 +1,237 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! State manifest (`components.lock.json`) recording the components that were
+//! explicitly loaded into a [`LifecycleManager`](crate::LifecycleManager).
+//!
+//! The manifest stores enough information to re-fetch every component when
+//! the component directory is lost, e.g. in an ephemeral container. It is
+//! updated on every load, unload, and policy attach/detach.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+/// Default file name of the state manifest inside the component directory.
+pub const MANIFEST_FILE_NAME: &str = "components.lock.json";
+
+/// Current manifest schema version.
+const MANIFEST_VERSION: u32 = 1;
+
+/// The set of explicitly loaded components, keyed by component ID.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComponentManifest {
+    /// Manifest schema version
+    pub version: u32,
+    /// Recorded components keyed by component ID
+    #[serde(default)]
+    pub components: BTreeMap<String, ManifestEntry>,
+}
+
+/// A single explicitly loaded component.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// URI the component was loaded from (file://, oci://, https://)
+    pub source_uri: String,
+    /// SHA-256 digest of the component artifact (`sha256:<hex>`)
+    pub digest: String,
+    /// URI of the policy attached to the component, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy_source: Option<String>,
+    /// Position of the component in the order components were first loaded,
+    /// see [`ComponentManifest::record`]. Absent for entries written before
+    /// the order was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub load_order: Option<u64>,
+}
+
+impl Default for ComponentManifest {
+    fn default() -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            components: BTreeMap::new(),
+        }
+    }
+}
+
+impl ComponentManifest {
+    /// Read a manifest from disk. A missing file yields an empty manifest.
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = match tokio::fs::read_to_string(path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to read manifest at {}", path.display()))
+            }
+        };
+
+        let manifest: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse manifest at {}", path.display()))?;
+        if manifest.version != MANIFEST_VERSION {
+            anyhow::bail!(
+                "Unsupported manifest version {} in {} (expected {})",
+                manifest.version,
+                path.display(),
+                MANIFEST_VERSION
+            );
+        }
+        Ok(manifest)
+    }
+
+    /// Insert or replace the entry of `component_id`. A replaced entry keeps
+    /// its place in the load order, a new one goes after every other.
+    pub fn record(&mut self, component_id: &str, mut entry: ManifestEntry) {
+        entry.load_order = match self.components.get(component_id) {
+            Some(existing) => existing.load_order,
+            None => Some(
+                self.components
+                    .values()
+                    .filter_map(|entry| entry.load_order)
+                    .max()
+                    .map_or(0, |order| order + 1),
+            ),
+        };
+        self.components.insert(component_id.to_string(), entry);
+    }
+
+    /// Write the manifest to disk, replacing any existing file atomically.
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.with_context(|| {
+                format!("Failed to create manifest directory {}", parent.display())
+            })?;
+        }
+
+        let json =
+            serde_json::to_string_pretty(self).context("Failed to serialize component manifest")?;
+        let tmp_path = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, json)
+            .await
+            .with_context(|| format!("Failed to write manifest to {}", tmp_path.display()))?;
+        tokio::fs::rename(&tmp_path, path)
+            .await
+            .with_context(|| format!("Failed to move manifest into place at {}", path.display()))
+    }
+}
+
+/// Summary of a [`LifecycleManager::restore_from_manifest`](crate::LifecycleManager::restore_from_manifest) run.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RestoreReport {
+    /// Components that were missing and have been re-fetched
+    pub restored: Vec<String>,
+    /// Components already present in the component directory with a matching digest
+    pub unchanged: Vec<String>,
+    /// Components that could not be restored, with the reason
+    pub failed: Vec<RestoreFailure>,
+}
+
+/// A component that could not be restored from the manifest.
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreFailure {
+    /// Component ID recorded in the manifest
+    pub component_id: String,
+    /// Human-readable failure reason
+    pub error: String,
+}
+
+/// Serializes read-modify-write updates to the manifest file.
+#[derive(Clone)]
+pub(crate) struct ManifestStore {
+    path: PathBuf,
+    lock: Arc<Mutex<()>>,
+}
+
+impl ManifestStore {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Apply `update` to the manifest on disk and persist the result.
+    pub(crate) async fn update(&self, update: impl FnOnce(&mut ComponentManifest)) -> Result<()> {
+        let _guard = self.lock.lock().await;
+        let mut manifest = ComponentManifest::load(&self.path).await?;
+        update(&mut manifest);
+        manifest.save(&self.path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(uri: &str) -> ManifestEntry {
+        ManifestEntry {
+            source_uri: uri.to_string(),
+            digest: "sha256:abc".to_string(),
+            policy_source: None,
+            load_order: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_missing_manifest_is_empty() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let manifest = ComponentManifest::load(dir.path().join(MANIFEST_FILE_NAME)).await?;
+        assert_eq!(manifest, ComponentManifest::default());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_store_update_round_trip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let store = ManifestStore::new(dir.path().join("state").join(MANIFEST_FILE_NAME));
+
+        store
+            .update(|m| {
+                m.components
+                    .insert("a".to_string(), entry("oci://example.com/a:1"));
+                m.components
+                    .insert("b".to_string(), entry("file:///tmp/b.wasm"));
+            })
+            .await?;
+        store
+            .update(|m| {
+                m.components.remove("a");
+            })
+            .await?;
+
+        let manifest = ComponentManifest::load(store.path()).await?;
+        assert_eq!(manifest.components.len(), 1);
+        assert_eq!(manifest.components["b"], entry("file:///tmp/b.wasm"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_keeps_load_order_of_replaced_entries() {
+        let mut manifest = ComponentManifest::default();
+        manifest.record("b", entry("file:///tmp/b.wasm"));
+        manifest.record("a", entry("file:///tmp/a.wasm"));
+        manifest.record("b", entry("file:///tmp/b2.wasm"));
+        assert_eq!(manifest.components["b"].load_order, Some(0));
+        assert_eq!(manifest.components["b"].source_uri, "file:///tmp/b2.wasm");
+        assert_eq!(manifest.components["a"].load_order, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_load_rejects_unknown_version() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(MANIFEST_FILE_NAME);
+        tokio::fs::write(&path, r#"{"version": 99, "components": {}}"#).await?;
+        assert!(ComponentManifest::load(&path).await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,310 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Execution metrics of components and their tools: how often they are
+//! called, how often the calls fail, and how long they take.
+//!
+//! The call counters are kept by the [`UsageTracker`] next to the last-used
+//! timestamps, so they are written to `usage.json` on the same schedule,
+//! survive restarts, and are dropped when a component is unloaded. The
+//! permission denials are those of the [`DenialTracker`].
+//!
+//! Load durations and download sizes are only counted while the process
+//! runs; together with the call metrics they make up the metrics served in
+//! the Prometheus format.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::denials::DenialTracker;
+use crate::prometheus::{self, Histogram};
+use crate::usage::{UsageRecord, UsageTracker};
+
+/// Execution metrics of one tool of a component.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ToolStats {
+    /// Number of calls
+    pub calls: u64,
+    /// Number of calls that failed
+    pub errors: u64,
+    /// Median latency of the recent calls, in milliseconds
+    pub p50_ms: u64,
+    /// 95th percentile latency of the recent calls, in milliseconds
+    pub p95_ms: u64,
+    /// Mean time spent instantiating the component, in milliseconds
+    pub mean_instantiation_ms: u64,
+    /// Time of the most recent call, as a Unix timestamp in seconds
+    pub last_used: u64,
+}
+
+/// Execution metrics of a component, over all its tools.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ComponentStats {
+    /// Number of calls to any of the component's tools
+    pub calls: u64,
+    /// Number of those calls that failed
+    pub errors: u64,
+    /// Number of permissions the component was denied, in calls or when it
+    /// was instantiated, as counted by
+    /// [`LifecycleManager::permission_denials`](crate::LifecycleManager::permission_denials)
+    pub permission_denials: u64,
+    /// Median latency of the recent calls, in milliseconds
+    pub p50_ms: u64,
+    /// 95th percentile latency of the recent calls, in milliseconds
+    pub p95_ms: u64,
+    /// Mean time spent instantiating the component, in milliseconds
+    pub mean_instantiation_ms: u64,
+    /// Time of the most recent call, as a Unix timestamp in seconds
+    pub last_used: u64,
+    /// Metrics of each tool that has been called, keyed by tool name
+    pub tools: BTreeMap<String, ToolStats>,
+}
+
+fn component_stats(record: Option<UsageRecord>, permission_denials: u64) -> ComponentStats {
+    let mut stats = ComponentStats {
+        permission_denials,
+        ..ComponentStats::default()
+    };
+    let Some(record) = record else {
+        return stats;
+    };
+    stats.last_used = record.usage.last_used;
+    let mut instantiation_ms = 0;
+    let mut latencies_ms = Vec::new();
+    for (name, calls) in record.calls {
+        stats.calls += calls.calls;
+        stats.errors += calls.errors;
+        instantiation_ms += calls.instantiation_ms;
+        latencies_ms.extend(calls.latencies_ms.iter().copied());
+
+        let mut samples = Vec::from(calls.latencies_ms);
+        let (p50_ms, p95_ms) = percentiles(&mut samples);
+        let last_used = record.usage.tools.get(&name).copied().unwrap_or_default();
+        stats.tools.insert(
+            name,
+            ToolStats {
+                calls: calls.calls,
+                errors: calls.errors,
+                p50_ms,
+                p95_ms,
+                mean_instantiation_ms: mean(calls.instantiation_ms, calls.calls),
+                last_used,
+            },
+        );
+    }
+    (stats.p50_ms, stats.p95_ms) = percentiles(&mut latencies_ms);
+    stats.mean_instantiation_ms = mean(instantiation_ms, stats.calls);
+    stats
+}
+
+/// The median and 95th percentile of `samples`, nearest rank.
+fn percentiles(samples: &mut [u64]) -> (u64, u64) {
+    if samples.is_empty() {
+        return (0, 0);
+    }
+    samples.sort_unstable();
+    let rank = |percent: usize| samples[(samples.len() * percent).div_ceil(100).max(1) - 1];
+    (rank(50), rank(95))
+}
+
+fn mean(total: u64, count: u64) -> u64 {
+    total.checked_div(count).unwrap_or(0)
+}
+
+#[derive(Default)]
+struct ProcessMetrics {
+    loads_succeeded: Histogram,
+    loads_failed: Histogram,
+    oci_download_bytes: AtomicU64,
+}
+
+/// Execution metrics keyed by component ID, computed from the usage and
+/// permission denials of the components.
+#[derive(Clone)]
+pub(crate) struct MetricsRegistry {
+    usage: UsageTracker,
+    denials: DenialTracker,
+    process: Arc<ProcessMetrics>,
+}
+
+impl MetricsRegistry {
+    pub(crate) fn new(usage: UsageTracker, denials: DenialTracker) -> Self {
+        Self {
+            usage,
+            denials,
+            process: Arc::default(),
+        }
+    }
+
+    /// Record how long compiling and registering a component took.
+    pub(crate) fn record_load(&self, duration_ms: u64, ok: bool) {
+        let loads = if ok {
+            &self.process.loads_succeeded
+        } else {
+            &self.process.loads_failed
+        };
+        loads.observe(duration_ms);
+    }
+
+    /// Count `bytes` of a component downloaded from an OCI registry.
+    pub(crate) fn record_oci_download(&self, bytes: u64) {
+        self.process
+            .oci_download_bytes
+            .fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// The metrics of `component_id`, or `None` if it has neither been
+    /// called nor denied anything.
+    pub(crate) fn get(&self, component_id: &str) -> Option<ComponentStats> {
+        let record = self.usage.record_of(component_id);
+        let denials = self.denials.get(component_id).total;
+        if record.is_none() && denials == 0 {
+            return None;
+        }
+        Some(component_stats(record, denials))
+    }
+
+    pub(crate) fn snapshot(&self) -> BTreeMap<String, ComponentStats> {
+        let mut records = self.usage.records();
+        let denials = self.denials.snapshot();
+        let ids: BTreeSet<_> = records.keys().chain(denials.keys()).cloned().collect();
+        ids.into_iter()
+            .map(|id| {
+                let record = records.remove(&id);
+                let denied = denials.get(&id).map_or(0, |denials| denials.total);
+                let stats = component_stats(record, denied);
+                (id, stats)
+            })
+            .collect()
+    }
+
+    /// Write the metrics in the Prometheus text format to `out`.
+    pub(crate) fn encode_prometheus(&self, out: &mut String) {
+        let records = self.usage.records();
+        let denials = self.denials.snapshot();
+        let tools: Vec<_> = records
+            .iter()
+            .flat_map(|(id, record)| {
+                record
+                    .calls
+                    .iter()
+                    .map(move |(tool, calls)| (id.as_str(), tool.as_str(), calls))
+            })
+            .collect();
+
+        let name = "wassette_tool_calls_total";
+        prometheus::write_header(out, name, "counter", "Tool calls by outcome.");
+        for (id, tool, calls) in &tools {
+            let labels = |outcome| [("component", *id), ("tool", *tool), ("outcome", outcome)];
+            prometheus::write_sample(out, name, &labels("success"), calls.calls - calls.errors);
+            prometheus::write_sample(out, name, &labels("error"), calls.errors);
+        }
+
+        let name = "wassette_tool_call_duration_seconds";
+        prometheus::write_header(out, name, "histogram", "Duration of tool calls.");
+        for (id, tool, calls) in &tools {
+            let labels = [("component", *id), ("tool", *tool)];
+            prometheus::write_histogram(out, name, &labels, &calls.durations);
+        }
+
+        let name = "wassette_permission_denials_total";
+        prometheus::write_header(out, name, "counter", "Permissions denied to components.");
+        let ids: BTreeSet<_> = records.keys().chain(denials.keys()).collect();
+        for id in ids {
+            let denied = denials.get(id).map_or(0, |denials| denials.total);
+            prometheus::write_sample(out, name, &[("component", id)], denied);
+        }
+
+        let name = "wassette_component_load_duration_seconds";
+        prometheus::write_header(
+            out,
+            name,
+            "histogram",
+            "Time taken to compile and register components.",
+        );
+        for (outcome, loads) in [
+            ("success", &self.process.loads_succeeded),
+            ("error", &self.process.loads_failed),
+        ] {
+            prometheus::write_histogram(out, name, &[("outcome", outcome)], &loads.record());
+        }
+
+        let name = "wassette_oci_download_bytes_total";
+        prometheus::write_header(
+            out,
+            name,
+            "counter",
+            "Bytes of components downloaded from OCI registries.",
+        );
+        let bytes = self.process.oci_download_bytes.load(Ordering::Relaxed);
+        prometheus::write_sample(out, name, &[], bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::usage::USAGE_FILE_NAME;
+    use crate::CallTiming;
+
+    fn timing(total_ms: u64, instantiation_ms: u64) -> CallTiming {
+        CallTiming {
+            total_ms,
+            instantiation_ms,
+            ..CallTiming::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stats_are_computed_from_usage_and_denials() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(USAGE_FILE_NAME);
+
+        let usage = UsageTracker::load(path.clone()).await;
+        let denials = DenialTracker::default();
+        let metrics = MetricsRegistry::new(usage.clone(), denials.clone());
+        assert!(metrics.get("fetch").is_none());
+
+        usage.record("fetch", "fetch");
+        for total_ms in 1..=20 {
+            usage.record_finished("fetch", "fetch", &timing(total_ms, 2), total_ms != 20);
+        }
+        usage.record("fetch", "head");
+        usage.record_finished("fetch", "head", &timing(100, 4), true);
+        denials.record("fetch", "network", json!({ "host": "example.com" }));
+
+        let stats = metrics.get("fetch").unwrap();
+        assert_eq!(stats.calls, 21);
+        assert_eq!(stats.errors, 1);
+        assert_eq!(stats.permission_denials, 1);
+        assert_eq!((stats.p50_ms, stats.p95_ms), (11, 20));
+        assert_eq!(stats.mean_instantiation_ms, 2);
+        assert_eq!(stats.last_used, usage.get("fetch").unwrap().last_used);
+        let fetch = &stats.tools["fetch"];
+        assert_eq!((fetch.calls, fetch.errors), (20, 1));
+        assert_eq!((fetch.p50_ms, fetch.p95_ms), (10, 19));
+        assert_eq!(stats.tools["head"].mean_instantiation_ms, 4);
+
+        // Components that were only denied something have stats too
+        denials.record("time", "storage", json!({ "path": "/etc" }));
+        assert_eq!(
+            metrics.snapshot().keys().collect::<Vec<_>>(),
+            vec!["fetch", "time"]
+        );
+        assert_eq!(metrics.get("time").unwrap().permission_denials, 1);
+
+        // The call counters are kept in the usage file
+        usage.flush().await?;
+        let reloaded =
+            MetricsRegistry::new(UsageTracker::load(path).await, DenialTracker::default());
+        let restored = reloaded.get("fetch").unwrap();
+        assert_eq!(restored.tools, stats.tools);
+        assert_eq!(restored.permission_denials, 0);
+        Ok(())
+    }
+}
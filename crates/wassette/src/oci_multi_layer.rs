@@ -14,6 +14,8 @@ use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use tracing::{debug, info, warn};
 
+use crate::progress::{ProgressReporter, ProgressWriter};
+
 /// Component metadata from the OCI config (CNCF spec)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComponentMetadata {
@@ -99,24 +101,21 @@ pub async fn pull_multi_layer_artifact(
     client: &Client,
     auth: &oci_client::secrets::RegistryAuth,
 ) -> Result<MultiLayerArtifact> {
-    pull_multi_layer_artifact_with_progress(reference, client, false, auth).await
+    pull_multi_layer_artifact_with_progress(reference, client, None, auth).await
 }
 
-/// Pull a multi-layer OCI artifact and extract all relevant layers with optional progress reporting
+/// Pull a multi-layer OCI artifact and extract all relevant layers, reporting
+/// the download of the layers together as one to `progress`
 pub async fn pull_multi_layer_artifact_with_progress(
     reference: &Reference,
     client: &Client,
-    show_progress: bool,
+    progress: Option<&dyn ProgressReporter>,
     auth: &oci_client::secrets::RegistryAuth,
 ) -> Result<MultiLayerArtifact> {
-
     // Pull just the manifest first
-    if show_progress {
-        eprintln!("Pulling manifest for {}...", reference);
-    }
     info!("Pulling OCI manifest: {}", reference);
     let (manifest, manifest_digest) = client
-        .pull_manifest(reference, &auth)
+        .pull_manifest(reference, auth)
         .await
         .context("Failed to pull OCI manifest")?;
 
@@ -190,17 +189,12 @@ pub async fn pull_multi_layer_artifact_with_progress(
         image_manifest.layers.len()
     );
 
-    if show_progress && !image_manifest.layers.is_empty() {
-        eprintln!(
-            "Downloading {} layer{}...",
-            image_manifest.layers.len(),
-            if image_manifest.layers.len() == 1 {
-                ""
-            } else {
-                "s"
-            }
-        );
-    }
+    let total = image_manifest
+        .layers
+        .iter()
+        .map(|layer| u64::try_from(layer.size).unwrap_or(0))
+        .sum();
+    let mut downloaded = 0;
 
     for (index, layer) in image_manifest.layers.iter().enumerate() {
         let media_type = &layer.media_type;
@@ -211,22 +205,14 @@ pub async fn pull_multi_layer_artifact_with_progress(
             index, media_type, layer_size, expected_digest
         );
 
-        if show_progress {
-            eprintln!(
-                "  Layer {}/{}: {} ({} bytes)",
-                index + 1,
-                image_manifest.layers.len(),
-                media_type,
-                layer_size
-            );
-        }
-
         // Pull the layer blob into a vector
         let mut blob_data = Vec::new();
+        let mut writer = ProgressWriter::new(&mut blob_data, progress, downloaded, Some(total));
         client
-            .pull_blob(reference, expected_digest.as_str(), &mut blob_data)
+            .pull_blob(reference, expected_digest.as_str(), &mut writer)
             .await
             .context(format!("Failed to pull layer {index}"))?;
+        downloaded = writer.downloaded();
 
         // Verify the layer digest
         debug!("Verifying digest for layer {}", index);
@@ -263,10 +249,6 @@ pub async fn pull_multi_layer_artifact_with_progress(
     let wasm_data =
         wasm_data.ok_or_else(|| anyhow::anyhow!("No WASM layer found in OCI artifact"))?;
 
-    if show_progress {
-        eprintln!("✓ Download complete");
-    }
-
     Ok(MultiLayerArtifact {
         wasm_data,
         policy_data,
@@ -307,7 +289,7 @@ pub async fn pull_multi_layer_artifact_secure(
 ) -> Result<MultiLayerArtifact> {
     // This uses the same implementation as pull_multi_layer_artifact
     // since we've already added digest verification there
-    pull_multi_layer_artifact_with_progress(reference, client, false, auth).await
+    pull_multi_layer_artifact_with_progress(reference, client, None, auth).await
 }
 
 #[cfg(test)]
@@ -0,0 +1,252 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Bounds on the arguments of a tool call, checked before they are parsed
+//! or converted, so an oversized or deeply nested arguments object is
+//! rejected before it costs memory or CPU.
+
+use std::io;
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Default size of the serialized arguments of a call, in bytes
+pub const DEFAULT_MAX_PARAMETER_BYTES: usize = 4 * 1024 * 1024;
+/// Default number of arrays and objects the arguments may nest
+pub const DEFAULT_MAX_PARAMETER_DEPTH: usize = 64;
+/// Default number of elements an array in the arguments may have
+pub const DEFAULT_MAX_PARAMETER_ARRAY_LENGTH: usize = 100_000;
+
+/// Limits on the arguments of a tool call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParameterLimits {
+    /// Maximum size of the arguments serialized as JSON, in bytes
+    pub max_bytes: usize,
+    /// Maximum number of arrays and objects nested in one another; the
+    /// arguments object itself is the first level
+    pub max_depth: usize,
+    /// Maximum number of elements of any array in the arguments
+    pub max_array_length: usize,
+}
+
+impl Default for ParameterLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_PARAMETER_BYTES,
+            max_depth: DEFAULT_MAX_PARAMETER_DEPTH,
+            max_array_length: DEFAULT_MAX_PARAMETER_ARRAY_LENGTH,
+        }
+    }
+}
+
+/// The arguments of a call exceed one of its [`ParameterLimits`].
+///
+/// Serializes with the exceeded limit under `limit`, e.g.
+/// `{"limit": "depth", "max": 64}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(tag = "limit", rename_all = "snake_case")]
+pub enum ParameterLimitExceeded {
+    /// The serialized arguments are too large
+    #[error("arguments are larger than the limit of {max} bytes")]
+    Size {
+        /// The limit in bytes
+        max: usize,
+    },
+    /// Arrays and objects are nested too deep
+    #[error("arguments are nested deeper than the limit of {max} levels")]
+    Depth {
+        /// The limit in levels
+        max: usize,
+    },
+    /// An array has too many elements
+    #[error("an array in the arguments has more than the limit of {max} elements")]
+    ArrayLength {
+        /// The limit in elements
+        max: usize,
+    },
+}
+
+impl ParameterLimits {
+    /// Check arguments given as a JSON string without parsing them. The
+    /// string is only scanned for brackets, so malformed JSON is left for
+    /// the parser to reject.
+    pub fn check_str(&self, parameters: &str) -> Result<(), ParameterLimitExceeded> {
+        if parameters.len() > self.max_bytes {
+            return Err(ParameterLimitExceeded::Size {
+                max: self.max_bytes,
+            });
+        }
+        // The element count of each open array, None for objects
+        let mut open: Vec<Option<usize>> = Vec::new();
+        let mut in_string = false;
+        let mut escaped = false;
+        for byte in parameters.bytes() {
+            if in_string {
+                match byte {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            if byte.is_ascii_whitespace() {
+                continue;
+            }
+            if let Some(Some(elements)) = open.last_mut() {
+                if *elements == 0 && byte != b']' {
+                    *elements = 1;
+                } else if byte == b',' {
+                    *elements += 1;
+                }
+                if *elements > self.max_array_length {
+                    return Err(ParameterLimitExceeded::ArrayLength {
+                        max: self.max_array_length,
+                    });
+                }
+            }
+            match byte {
+                b'"' => in_string = true,
+                b'[' | b'{' => {
+                    open.push((byte == b'[').then_some(0));
+                    if open.len() > self.max_depth {
+                        return Err(ParameterLimitExceeded::Depth {
+                            max: self.max_depth,
+                        });
+                    }
+                }
+                b']' | b'}' => {
+                    open.pop();
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Check parsed arguments. Their size is measured by serializing them
+    /// into a counter that stops at the limit.
+    pub fn check_value(&self, parameters: &Value) -> Result<(), ParameterLimitExceeded> {
+        let mut pending = vec![(parameters, 1)];
+        while let Some((value, depth)) = pending.pop() {
+            let children: Box<dyn Iterator<Item = &Value>> = match value {
+                Value::Array(elements) => {
+                    if elements.len() > self.max_array_length {
+                        return Err(ParameterLimitExceeded::ArrayLength {
+                            max: self.max_array_length,
+                        });
+                    }
+                    Box::new(elements.iter())
+                }
+                Value::Object(fields) => Box::new(fields.values()),
+                _ => continue,
+            };
+            if depth > self.max_depth {
+                return Err(ParameterLimitExceeded::Depth {
+                    max: self.max_depth,
+                });
+            }
+            pending.extend(children.map(|child| (child, depth + 1)));
+        }
+
+        let mut counter = SizeCounter {
+            size: 0,
+            max: self.max_bytes,
+        };
+        if serde_json::to_writer(&mut counter, parameters).is_err() {
+            return Err(ParameterLimitExceeded::Size {
+                max: self.max_bytes,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A writer that counts bytes and fails once there are more than `max`.
+struct SizeCounter {
+    size: usize,
+    max: usize,
+}
+
+impl io::Write for SizeCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.size += buf.len();
+        if self.size > self.max {
+            return Err(io::Error::other("size limit exceeded"));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nested(depth: usize) -> String {
+        format!("{}{}", "[".repeat(depth), "]".repeat(depth))
+    }
+
+    #[test]
+    fn test_large_and_deep_arguments_are_rejected() {
+        let limits = ParameterLimits::default();
+
+        let elements = vec!["0"; 1_000_000].join(",");
+        let array = format!(r#"{{"values": [{elements}]}}"#);
+        let exceeded = ParameterLimitExceeded::ArrayLength {
+            max: DEFAULT_MAX_PARAMETER_ARRAY_LENGTH,
+        };
+        assert_eq!(limits.check_str(&array), Err(exceeded));
+        let value: Value = serde_json::from_str(&array).unwrap();
+        assert_eq!(limits.check_value(&value), Err(exceeded));
+
+        // Too deep for serde_json to parse, so only the string is checked
+        let deep = format!(r#"{{"value": {}}}"#, nested(10_000));
+        assert_eq!(
+            limits.check_str(&deep),
+            Err(ParameterLimitExceeded::Depth {
+                max: DEFAULT_MAX_PARAMETER_DEPTH
+            })
+        );
+
+        let large = serde_json::json!({ "text": "x".repeat(DEFAULT_MAX_PARAMETER_BYTES) });
+        let exceeded = ParameterLimitExceeded::Size {
+            max: DEFAULT_MAX_PARAMETER_BYTES,
+        };
+        assert_eq!(limits.check_str(&large.to_string()), Err(exceeded));
+        assert_eq!(limits.check_value(&large), Err(exceeded));
+    }
+
+    #[test]
+    fn test_arguments_within_limits_are_accepted() {
+        let limits = ParameterLimits {
+            max_bytes: 1024,
+            max_depth: 3,
+            max_array_length: 2,
+        };
+        // Brackets and commas inside strings do not count
+        let arguments = r#"{"items": [{"name": "[a, b, c]"}, []], "escaped": "\"[,,,"}"#;
+        assert_eq!(limits.check_str(arguments), Ok(()));
+        let value: Value = serde_json::from_str(arguments).unwrap();
+        assert_eq!(limits.check_value(&value), Ok(()));
+
+        let too_deep = r#"{"items": [[[]]]}"#;
+        let exceeded = ParameterLimitExceeded::Depth { max: 3 };
+        assert_eq!(limits.check_str(too_deep), Err(exceeded));
+        let value: Value = serde_json::from_str(too_deep).unwrap();
+        assert_eq!(limits.check_value(&value), Err(exceeded));
+
+        let too_long = r#"{"items": [1, 2, 3]}"#;
+        let exceeded = ParameterLimitExceeded::ArrayLength { max: 2 };
+        assert_eq!(limits.check_str(too_long), Err(exceeded));
+        let value: Value = serde_json::from_str(too_long).unwrap();
+        assert_eq!(limits.check_value(&value), Err(exceeded));
+        assert_eq!(
+            serde_json::to_value(exceeded).unwrap(),
+            serde_json::json!({"limit": "array_length", "max": 2})
+        );
+    }
+}
@@ -0,0 +1,183 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Checks on host paths built from user-controlled input, so component IDs
+//! and storage URIs cannot reach outside the directory they are joined onto.
+//!
+//! `..` is rejected in its percent-encoded forms too, since some clients and
+//! tools decode paths before they reach the host.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::ComponentError;
+
+/// Fail with [`ComponentError::InvalidId`] unless `component_id` can be used
+/// as the name of files in the component directory: it must not be empty,
+/// `.` or `..`, or contain path separators or NUL, also once
+/// percent-decoded.
+pub fn validate_component_id(component_id: &str) -> Result<(), ComponentError> {
+    let decoded = percent_decode(component_id);
+    for id in [component_id, decoded.as_str()] {
+        if id.is_empty() || id == "." || id == ".." || id.contains(['/', '\\', '\0']) {
+            return Err(ComponentError::InvalidId {
+                component_id: component_id.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Whether `path` has a `..` segment, before or after percent-decoding.
+/// Both `/` and `\` separate segments.
+pub(crate) fn has_parent_segment(path: &str) -> bool {
+    [path, percent_decode(path).as_str()]
+        .iter()
+        .any(|path| path.split(['/', '\\']).any(|segment| segment == ".."))
+}
+
+/// Fail unless `path` is inside `base` once symlinks are resolved. Parts of
+/// `path` that do not exist yet are checked through their closest existing
+/// ancestor; a dangling symlink counts as existing and is rejected, since
+/// writing through it would create its target wherever it points.
+pub(crate) fn ensure_within(path: &Path, base: &Path) -> Result<()> {
+    let base = base
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", base.display()))?;
+    let mut existing = path;
+    let mut rest = PathBuf::new();
+    while existing.symlink_metadata().is_err() {
+        let (Some(parent), Some(name)) = (existing.parent(), existing.file_name()) else {
+            break;
+        };
+        rest = Path::new(name).join(rest);
+        existing = parent;
+    }
+    let resolved = match existing.canonicalize() {
+        Ok(resolved) => resolved.join(rest),
+        Err(_) if existing.is_symlink() => bail!(
+            "Path '{}' goes through '{}', a symlink whose target does not exist",
+            path.display(),
+            existing.display()
+        ),
+        Err(error) => {
+            return Err(error).with_context(|| format!("Failed to resolve {}", existing.display()))
+        }
+    };
+    if !resolved.starts_with(&base) {
+        bail!(
+            "Path '{}' resolves to '{}', outside of '{}'",
+            path.display(),
+            resolved.display(),
+            base.display()
+        );
+    }
+    Ok(())
+}
+
+/// Decode `%XX` escapes; anything that is not a valid escape is kept as is.
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_component_ids_cannot_traverse() {
+        assert!(validate_component_id("fetch_rs").is_ok());
+        assert!(validate_component_id("time-server.v2").is_ok());
+        assert!(validate_component_id("..data").is_ok());
+
+        for id in [
+            "",
+            ".",
+            "..",
+            "../etc/passwd",
+            "nested/component",
+            "..\\windows",
+            "%2e%2e",
+            "%2E%2E%2Fetc",
+            "a%2fb",
+            "a%5cb",
+            "nul\0byte",
+        ] {
+            assert!(
+                matches!(
+                    validate_component_id(id),
+                    Err(ComponentError::InvalidId { .. })
+                ),
+                "{id:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parent_segments_are_found_when_encoded() {
+        assert!(!has_parent_segment("workspace/data"));
+        assert!(!has_parent_segment("/tmp/..data/x"));
+        assert!(has_parent_segment("../../etc"));
+        assert!(has_parent_segment("workspace/../../etc"));
+        assert!(has_parent_segment("workspace\\..\\etc"));
+        assert!(has_parent_segment("%2e%2e/etc"));
+        assert!(has_parent_segment("workspace%2f%2E%2E%2fetc"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_directories_cannot_escape() -> Result<()> {
+        let base = tempfile::tempdir()?;
+        let outside = tempfile::tempdir()?;
+        std::fs::create_dir(base.path().join("inside"))?;
+        std::os::unix::fs::symlink(outside.path(), base.path().join("link"))?;
+
+        ensure_within(&base.path().join("inside/new/dir"), base.path())?;
+        let error = ensure_within(&base.path().join("link/new"), base.path()).unwrap_err();
+        assert!(error.to_string().contains("outside of"));
+        assert!(ensure_within(&base.path().join("link"), base.path()).is_err());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dangling_symlinks_are_rejected() -> Result<()> {
+        let base = tempfile::tempdir()?;
+        let outside = tempfile::tempdir()?;
+        let target = outside.path().join("missing");
+        std::os::unix::fs::symlink(&target, base.path().join("dangling"))?;
+
+        for path in ["dangling", "dangling/new/file"] {
+            let error = ensure_within(&base.path().join(path), base.path()).unwrap_err();
+            assert!(
+                error
+                    .to_string()
+                    .contains("symlink whose target does not exist"),
+                "{path}: {error}"
+            );
+        }
+        assert!(!target.exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Embedder hook with the final say on the sandbox a component runs in.
+//!
+//! The hook sees the [`WasiStateTemplate`] built from the component's policy
+//! each time the component is instantiated and can let it through, refuse the
+//! instantiation, or replace the template. Register one with
+//! [`LifecycleBuilder::with_permission_hook`](crate::LifecycleBuilder::with_permission_hook)
+//! or, for hooks that need to await (e.g. a call to an authorization
+//! service), [`LifecycleBuilder::with_async_permission_hook`](crate::LifecycleBuilder::with_async_permission_hook).
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use crate::wasistate::WasiStateTemplate;
+
+/// What a permission hook decided about a component's sandbox.
+#[derive(Clone)]
+pub enum PermissionDecision {
+    /// Build the sandbox from the policy template unchanged
+    Allow,
+    /// Refuse to instantiate the component, for the given reason
+    Deny(String),
+    /// Build the sandbox from this template instead
+    Modify(Box<WasiStateTemplate>),
+}
+
+/// Future returned by an asynchronous permission hook.
+pub type PermissionHookFuture = Pin<Box<dyn Future<Output = PermissionDecision> + Send>>;
+
+/// A permission hook; takes the component ID and the template built from its policy.
+pub(crate) type PermissionHook =
+    Arc<dyn Fn(&str, &WasiStateTemplate) -> PermissionHookFuture + Send + Sync>;
@@ -0,0 +1,270 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Component policies split into one file per permission category.
+//!
+//! Besides its policy file `<component_id>.policy.yaml`, a component can
+//! have a fragment for each permission category, stored in the component
+//! directory as `policy-fragments/<component_id>/<category>.policy.yaml`,
+//! e.g. `policy-fragments/fetch/network.policy.yaml`. Keeping fragments in a
+//! directory per component means they cannot be mistaken for the policy
+//! file of a component whose ID contains a dot, such as `fetch.network`.
+//! A fragment owns its category: the merged policy takes that section from
+//! the fragment and ignores it in the policy file, and any other sections in
+//! the fragment are ignored. Grants and revokes are written to the file that
+//! owns the rule's category, so each file can be maintained separately.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use policy::{Permissions, PolicyDocument, CURRENT_POLICY_VERSION};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// A top-level section of a policy's permissions, which a policy fragment
+/// can own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyCategory {
+    /// `permissions.network`
+    Network,
+    /// `permissions.storage`
+    Storage,
+    /// `permissions.environment`
+    Environment,
+    /// `permissions.resources`
+    Resources,
+    /// `permissions.runtime`
+    Runtime,
+    /// `permissions.ipc`
+    Ipc,
+}
+
+impl PolicyCategory {
+    /// Every category, in the order fragments are merged.
+    pub const ALL: [PolicyCategory; 6] = [
+        PolicyCategory::Network,
+        PolicyCategory::Storage,
+        PolicyCategory::Environment,
+        PolicyCategory::Resources,
+        PolicyCategory::Runtime,
+        PolicyCategory::Ipc,
+    ];
+
+    /// The category's name, as used in the section key and fragment file name.
+    pub fn name(self) -> &'static str {
+        match self {
+            PolicyCategory::Network => "network",
+            PolicyCategory::Storage => "storage",
+            PolicyCategory::Environment => "environment",
+            PolicyCategory::Resources => "resources",
+            PolicyCategory::Runtime => "runtime",
+            PolicyCategory::Ipc => "ipc",
+        }
+    }
+
+    /// The category with the given name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|category| category.name() == name)
+    }
+
+    /// Whether `permissions` has a section for this category.
+    pub(crate) fn is_set(self, permissions: &Permissions) -> bool {
+        match self {
+            PolicyCategory::Network => permissions.network.is_some(),
+            PolicyCategory::Storage => permissions.storage.is_some(),
+            PolicyCategory::Environment => permissions.environment.is_some(),
+            PolicyCategory::Resources => permissions.resources.is_some(),
+            PolicyCategory::Runtime => permissions.runtime.is_some(),
+            PolicyCategory::Ipc => permissions.ipc.is_some(),
+        }
+    }
+
+    /// Replace this category's section of `into` with the one in `from`.
+    pub(crate) fn copy_section(self, from: &Permissions, into: &mut Permissions) {
+        match self {
+            PolicyCategory::Network => into.network = from.network.clone(),
+            PolicyCategory::Storage => into.storage = from.storage.clone(),
+            PolicyCategory::Environment => into.environment = from.environment.clone(),
+            PolicyCategory::Resources => into.resources = from.resources.clone(),
+            PolicyCategory::Runtime => into.runtime = from.runtime.clone(),
+            PolicyCategory::Ipc => into.ipc = from.ipc.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for PolicyCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The parsed policy file and fragments of one component.
+#[derive(Debug, Default)]
+pub(crate) struct PolicyFiles {
+    /// The component's policy file, if it has one
+    pub(crate) main: Option<PolicyDocument>,
+    /// The component's fragments, by the category they own
+    pub(crate) fragments: BTreeMap<PolicyCategory, PolicyDocument>,
+}
+
+impl PolicyFiles {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.main.is_none() && self.fragments.is_empty()
+    }
+
+    /// The policy the files add up to, or `None` if there are none. The
+    /// version and description come from the policy file when there is one.
+    pub(crate) fn merged(&self, component_id: &str) -> Option<PolicyDocument> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut merged = self.main.clone().unwrap_or_else(|| PolicyDocument {
+            version: CURRENT_POLICY_VERSION.to_string(),
+            description: Some(format!(
+                "Auto-generated policy for component: {component_id}"
+            )),
+            permissions: Default::default(),
+            config: Default::default(),
+        });
+        for (category, fragment) in &self.fragments {
+            for other in PolicyCategory::ALL {
+                if other != *category && other.is_set(&fragment.permissions) {
+                    warn!(
+                        component_id,
+                        fragment = %category,
+                        section = %other,
+                        "Ignoring a section of a policy fragment that belongs to another category"
+                    );
+                }
+            }
+            category.copy_section(&fragment.permissions, &mut merged.permissions);
+        }
+        Some(merged)
+    }
+
+    /// The files to write so they add up to `policy`: each fragment gets its
+    /// category's section, and the policy file everything else. Sections of
+    /// the policy file that fragments own are kept as they are.
+    pub(crate) fn split(&self, policy: &PolicyDocument) -> PolicyFiles {
+        let fragments: BTreeMap<_, _> = self
+            .fragments
+            .iter()
+            .map(|(category, fragment)| {
+                let mut fragment = fragment.clone();
+                category.copy_section(&policy.permissions, &mut fragment.permissions);
+                (*category, fragment)
+            })
+            .collect();
+
+        let mut main = policy.clone();
+        let current_main = self.main.clone().unwrap_or_default();
+        for category in fragments.keys() {
+            category.copy_section(&current_main.permissions, &mut main.permissions);
+        }
+        // Without a policy file, only create one when it has rules of its own
+        let main = (self.main.is_some()
+            || fragments.is_empty()
+            || !main.config.is_empty()
+            || main.permissions.introspection
+            || PolicyCategory::ALL
+                .iter()
+                .any(|category| category.is_set(&main.permissions)))
+        .then_some(main);
+
+        PolicyFiles { main, fragments }
+    }
+}
+
+/// Directory, relative to the component directory, that holds the policy
+/// fragments of each component in a subdirectory named after it.
+pub const POLICY_FRAGMENTS_DIR: &str = "policy-fragments";
+
+/// Directory holding the fragments of a component in the component directory.
+pub(crate) fn fragments_dir(root: &Path, component_id: &str) -> PathBuf {
+    root.join(POLICY_FRAGMENTS_DIR).join(component_id)
+}
+
+/// Path of a component's fragment for `category` in the component directory.
+pub(crate) fn fragment_path(root: &Path, component_id: &str, category: PolicyCategory) -> PathBuf {
+    fragments_dir(root, component_id).join(format!("{category}.policy.yaml"))
+}
+
+/// The component and category of the fragment at `path`, if `path` is a
+/// fragment in the component directory `root`.
+pub(crate) fn fragment_from_path(root: &Path, path: &Path) -> Option<(String, PolicyCategory)> {
+    let relative = path.strip_prefix(root.join(POLICY_FRAGMENTS_DIR)).ok()?;
+    let mut parts = relative.iter();
+    let component_id = parts.next()?.to_str()?;
+    let category = parts.next()?.to_str()?.strip_suffix(".policy.yaml")?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((
+        component_id.to_string(),
+        PolicyCategory::from_name(category)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use policy::PolicyParser;
+
+    use super::*;
+
+    #[test]
+    fn test_fragments_own_their_category() -> anyhow::Result<()> {
+        let main = PolicyParser::parse_str(
+            r#"
+version: "1.0"
+permissions:
+  network:
+    allow:
+      - host: "main.example.com"
+  environment:
+    allow:
+      - key: "HOME"
+"#,
+        )?;
+        let network = PolicyParser::parse_str(
+            r#"
+version: "1.0"
+permissions:
+  network:
+    allow:
+      - host: "api.example.com"
+  storage:
+    allow:
+      - uri: "fs:///ignored"
+        access: ["read"]
+"#,
+        )?;
+        let files = PolicyFiles {
+            main: Some(main),
+            fragments: BTreeMap::from([(PolicyCategory::Network, network)]),
+        };
+
+        let merged = files.merged("fetch").unwrap();
+        let hosts = serde_json::to_value(&merged.permissions.network)?;
+        assert_eq!(hosts["allow"][0]["host"], "api.example.com");
+        assert_eq!(hosts["allow"].as_array().unwrap().len(), 1);
+        assert!(merged.permissions.environment.is_some());
+        assert!(merged.permissions.storage.is_none());
+
+        let mut granted = merged.clone();
+        granted.permissions.network = None;
+        granted.permissions.environment = None;
+        let split = files.split(&granted);
+        assert!(split.fragments[&PolicyCategory::Network]
+            .permissions
+            .network
+            .is_none());
+        let main = split.main.unwrap();
+        assert!(main.permissions.network.is_some());
+        assert!(main.permissions.environment.is_none());
+        assert!(PolicyFiles::default().merged("fetch").is_none());
+        Ok(())
+    }
+}
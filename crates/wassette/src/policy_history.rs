@@ -0,0 +1,202 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Revision history of component policy files.
+//!
+//! Whenever a component's policy file or one of its fragments is written, by
+//! attaching a policy, granting or revoking a permission, or rolling back, a
+//! copy is kept in
+//! `policy-history/<component_id>/` in the component directory. An index
+//! next to the copies records when each revision was written and by what.
+//! Only the most recent revisions are kept; revision numbers keep counting
+//! up when older ones are pruned.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::policy_fragments::PolicyCategory;
+
+/// Directory in the component directory that holds policy revisions.
+pub const POLICY_HISTORY_DIR: &str = "policy-history";
+
+/// Number of revisions kept for each component unless configured otherwise.
+pub const DEFAULT_POLICY_HISTORY_LIMIT: usize = 20;
+
+const INDEX_FILE_NAME: &str = "history.json";
+
+/// One stored revision of a component's policy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyRevision {
+    /// Revision number, increasing with every write of the policy
+    pub revision: u64,
+    /// When the revision was written, in seconds since the Unix epoch
+    pub created_at: u64,
+    /// What wrote the revision, e.g. `grant network` or `rollback to 3`
+    pub action: String,
+    /// The fragment the revision is a copy of, or `None` for the policy file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fragment: Option<PolicyCategory>,
+}
+
+/// The policy revisions of all components, stored on disk.
+#[derive(Clone)]
+pub(crate) struct PolicyHistory {
+    dir: PathBuf,
+    limit: usize,
+    // Serializes updates of the index files
+    lock: Arc<Mutex<()>>,
+}
+
+impl PolicyHistory {
+    /// History in `dir` keeping `limit` revisions per component; a limit of
+    /// zero keeps no history.
+    pub(crate) fn new(dir: PathBuf, limit: usize) -> Self {
+        Self {
+            dir,
+            limit,
+            lock: Arc::default(),
+        }
+    }
+
+    fn component_dir(&self, component_id: &str) -> PathBuf {
+        self.dir.join(component_id)
+    }
+
+    fn revision_path(&self, component_id: &str, revision: u64) -> PathBuf {
+        self.component_dir(component_id)
+            .join(format!("{revision}.policy.yaml"))
+    }
+
+    async fn read_index(&self, component_id: &str) -> Result<Vec<PolicyRevision>> {
+        let path = self.component_dir(component_id).join(INDEX_FILE_NAME);
+        match tokio::fs::read_to_string(&path).await {
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse policy history at {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e)
+                .with_context(|| format!("Failed to read policy history at {}", path.display())),
+        }
+    }
+
+    async fn write_index(&self, component_id: &str, revisions: &[PolicyRevision]) -> Result<()> {
+        let path = self.component_dir(component_id).join(INDEX_FILE_NAME);
+        let tmp_path = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, serde_json::to_string_pretty(revisions)?).await?;
+        tokio::fs::rename(&tmp_path, &path).await?;
+        Ok(())
+    }
+
+    /// Store a copy of the policy file or fragment at `policy_path` as the
+    /// component's next revision, and prune revisions beyond the limit.
+    pub(crate) async fn record(
+        &self,
+        component_id: &str,
+        policy_path: &Path,
+        fragment: Option<PolicyCategory>,
+        action: &str,
+    ) -> Result<()> {
+        if self.limit == 0 {
+            return Ok(());
+        }
+        let _guard = self.lock.lock().await;
+        tokio::fs::create_dir_all(self.component_dir(component_id)).await?;
+
+        let mut revisions = self.read_index(component_id).await?;
+        let revision = revisions.last().map_or(1, |last| last.revision + 1);
+        tokio::fs::copy(policy_path, self.revision_path(component_id, revision))
+            .await
+            .with_context(|| format!("Failed to store policy revision {revision}"))?;
+        revisions.push(PolicyRevision {
+            revision,
+            created_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            action: action.to_string(),
+            fragment,
+        });
+
+        let excess = revisions.len().saturating_sub(self.limit);
+        for pruned in revisions.drain(..excess) {
+            let path = self.revision_path(component_id, pruned.revision);
+            if let Err(e) = tokio::fs::remove_file(&path).await {
+                tracing::warn!(component_id, path = %path.display(), error = %e, "Failed to remove pruned policy revision");
+            }
+        }
+        self.write_index(component_id, &revisions).await
+    }
+
+    /// The stored revisions of a component, oldest first.
+    pub(crate) async fn list(&self, component_id: &str) -> Result<Vec<PolicyRevision>> {
+        self.read_index(component_id).await
+    }
+
+    /// A stored revision together with the path of its policy file.
+    pub(crate) async fn revision(
+        &self,
+        component_id: &str,
+        revision: u64,
+    ) -> Result<(PolicyRevision, PathBuf)> {
+        let stored = self
+            .read_index(component_id)
+            .await?
+            .into_iter()
+            .find(|stored| stored.revision == revision)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Policy revision {} of component {} not found",
+                    revision,
+                    component_id
+                )
+            })?;
+        Ok((stored, self.revision_path(component_id, revision)))
+    }
+
+    /// Delete every revision of a component.
+    pub(crate) async fn remove(&self, component_id: &str) -> Result<()> {
+        let _guard = self.lock.lock().await;
+        match tokio::fs::remove_dir_all(self.component_dir(component_id)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e).context("Failed to remove policy history"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_history_keeps_the_latest_revisions() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let policy_path = dir.path().join("component.policy.yaml");
+        let history = PolicyHistory::new(dir.path().join(POLICY_HISTORY_DIR), 2);
+
+        for action in ["attach", "grant network", "revoke network"] {
+            tokio::fs::write(&policy_path, action).await?;
+            history
+                .record("component", &policy_path, None, action)
+                .await?;
+        }
+
+        let revisions = history.list("component").await?;
+        let numbers: Vec<u64> = revisions.iter().map(|r| r.revision).collect();
+        assert_eq!(numbers, [2, 3]);
+        assert_eq!(revisions[1].action, "revoke network");
+
+        let (second, path) = history.revision("component", 2).await?;
+        assert_eq!(second.fragment, None);
+        assert_eq!(tokio::fs::read_to_string(path).await?, "grant network");
+        assert!(history.revision("component", 1).await.is_err());
+        assert!(!history.revision_path("component", 1).exists());
+
+        history.remove("component").await?;
+        assert!(history.list("component").await?.is_empty());
+        Ok(())
+    }
+}
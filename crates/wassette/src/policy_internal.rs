@@ -3,24 +3,47 @@
 
 //! Policy management structures and types
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use oci_wasm::WasmClient;
 use policy::{
     AccessType, EnvironmentPermission, NetworkHostPermission, NetworkPermission, PolicyDocument,
-    PolicyParser, StoragePermission,
+    PolicyFormat, PolicyParser, StoragePermission, TimeoutLimit, CURRENT_POLICY_VERSION,
 };
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
 use tracing::{info, instrument, warn};
 
 use crate::component_storage::ComponentStorage;
+use crate::http::{is_any_host, ANY_HOST};
 use crate::loader::{self, PolicyResource};
-use crate::{SecretsManager, WasiStateTemplate};
+use crate::policy_fragments::{self, PolicyCategory, PolicyFiles};
+use crate::policy_history::{
+    PolicyHistory, PolicyRevision, DEFAULT_POLICY_HISTORY_LIMIT, POLICY_HISTORY_DIR,
+};
+use crate::scratch::{scratch_dir_from_policy, ScratchDir, SCRATCH_DIR};
+use crate::source_policy::SourcePolicy;
+use crate::wasistate::{
+    host_memory_bytes, is_absolute_storage_path, DEFAULT_MAX_MEMORY_GRANT_FRACTION,
+};
+use crate::{ComponentError, SecretsManager, WasiStateTemplate};
+
+/// Whether two network rules allow the same host or range, ignoring settings
+/// such as a host's timeout.
+fn same_network_target(a: &NetworkPermission, b: &NetworkPermission) -> bool {
+    match (a, b) {
+        (NetworkPermission::Host(a), NetworkPermission::Host(b)) => {
+            (&a.host, a.port, &a.scheme) == (&b.host, b.port, &b.scheme)
+        }
+        _ => a == b,
+    }
+}
 
 /// Granular permission rule types
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +57,13 @@ pub enum PermissionRule {
     /// Environment variable access permission
     #[serde(rename = "environment")]
     Environment(EnvironmentPermission),
+    /// Access to every configured environment variable
+    #[serde(rename = "environment-inherit-all")]
+    EnvironmentInheritAll,
+    /// Config value passed through wasi-config, by key. The value is only
+    /// needed to grant it.
+    #[serde(rename = "config")]
+    Config(String, Option<String>),
     /// Custom permission with arbitrary data
     #[serde(rename = "custom")]
     Custom(String, serde_json::Value),
@@ -50,11 +80,45 @@ pub struct PermissionGrantRequest {
     pub details: serde_json::Value,
 }
 
+/// Outcome of granting one host in a bulk network grant
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HostGrantStatus {
+    /// The host was added to the policy
+    Granted,
+    /// The policy already allowed the host, so nothing changed
+    AlreadyGranted,
+}
+
+/// Result for one host of a bulk network grant
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostGrant {
+    /// The host, as given
+    pub host: String,
+    /// Whether the host was added or already allowed
+    pub status: HostGrantStatus,
+}
+
 /// Registry for storing policy templates associated with components
 #[derive(Default)]
 pub(crate) struct PolicyRegistry {
     /// Maps component IDs to their associated policy templates
     pub(crate) component_policies: HashMap<String, Arc<WasiStateTemplate>>,
+    /// Bumped whenever a component's template is replaced or removed
+    pub(crate) versions: HashMap<String, u64>,
+    /// How policies attached or re-fetched since startup were obtained;
+    /// other policies were read from their local copy
+    pub(crate) origins: HashMap<String, PolicyOrigin>,
+    /// Digest of the policy files on disk when each template was stored,
+    /// so the directory watcher can tell the manager's own writes from
+    /// files changed behind its back
+    pub(crate) disk_digests: HashMap<String, String>,
+}
+
+impl PolicyRegistry {
+    fn bump_version(&mut self, component_id: &str) {
+        *self.versions.entry(component_id.to_string()).or_default() += 1;
+    }
 }
 
 #[derive(Clone)]
@@ -65,6 +129,28 @@ pub(crate) struct PolicyManager {
     environment_vars: Arc<HashMap<String, String>>,
     oci_client: Arc<WasmClient>,
     http_client: Client,
+    allow_env_inherit: bool,
+    source_policy: SourcePolicy,
+    history: PolicyHistory,
+    scratch_root: PathBuf,
+    max_memory_grant_fraction: f64,
+    allow_any_host: bool,
+    allow_absolute_storage_paths: bool,
+}
+
+/// Where the active policy of a component was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyOrigin {
+    /// The policy was attached while the manager was running
+    Attached,
+    /// The policy file stored next to the component was used, e.g. after a
+    /// restart or when restoring the component, wherever it was originally
+    /// attached from
+    LocalCopy,
+    /// The local copy was missing when the component was restored, so the
+    /// policy was fetched again from its source
+    Refetched,
 }
 
 /// Information about a policy attached to a component
@@ -74,12 +160,25 @@ pub struct PolicyInfo {
     pub policy_id: String,
     /// The original URI where the policy was loaded from
     pub source_uri: String,
+    /// Whether the active policy is the local copy or was fetched from
+    /// `source_uri`
+    pub origin: PolicyOrigin,
     /// Local filesystem path where the policy is stored
     pub local_path: PathBuf,
     /// ID of the component this policy is attached to
     pub component_id: String,
     /// Timestamp when the policy was created/attached
     pub created_at: std::time::SystemTime,
+    /// Whether the policy sets `environment.inheritAll`
+    pub inherit_all_environment: bool,
+    /// The permissions of the policy file and its fragments, merged
+    pub permissions: policy::Permissions,
+    /// The file each category of `permissions` was taken from
+    pub permission_sources: BTreeMap<PolicyCategory, PathBuf>,
+    /// Config values the policy passes through wasi-config
+    pub config: BTreeMap<String, String>,
+    /// The scratch directory the policy gives the component
+    pub scratch: Option<ScratchDir>,
 }
 
 impl PolicyManager {
@@ -89,7 +188,14 @@ impl PolicyManager {
         environment_vars: Arc<HashMap<String, String>>,
         oci_client: Arc<WasmClient>,
         http_client: Client,
+        allow_env_inherit: bool,
+        source_policy: SourcePolicy,
     ) -> Self {
+        let history = PolicyHistory::new(
+            storage.root().join(POLICY_HISTORY_DIR),
+            DEFAULT_POLICY_HISTORY_LIMIT,
+        );
+        let scratch_root = storage.root().join(SCRATCH_DIR);
         Self {
             registry: Arc::new(RwLock::new(PolicyRegistry::default())),
             storage,
@@ -97,23 +203,225 @@ impl PolicyManager {
             environment_vars,
             oci_client,
             http_client,
+            allow_env_inherit,
+            source_policy,
+            history,
+            scratch_root,
+            max_memory_grant_fraction: DEFAULT_MAX_MEMORY_GRANT_FRACTION,
+            allow_any_host: false,
+            allow_absolute_storage_paths: false,
         }
     }
 
+    /// Keep `limit` revisions of each policy instead of the default.
+    pub(crate) fn with_history_limit(mut self, limit: usize) -> Self {
+        self.history = PolicyHistory::new(self.storage.root().join(POLICY_HISTORY_DIR), limit);
+        self
+    }
+
+    /// Create components' scratch directories under `root` instead of the
+    /// component directory.
+    pub(crate) fn with_scratch_root(mut self, root: PathBuf) -> Self {
+        self.scratch_root = root;
+        self
+    }
+
+    /// Refuse memory grants above `fraction` of the host's RAM instead of
+    /// the default share.
+    pub(crate) fn with_max_memory_grant_fraction(mut self, fraction: f64) -> Self {
+        self.max_memory_grant_fraction = fraction;
+        self
+    }
+
+    /// Honor and allow granting the `*` network host, which matches every
+    /// host.
+    pub(crate) fn with_any_host(mut self, allow: bool) -> Self {
+        self.allow_any_host = allow;
+        self
+    }
+
+    /// Honor and allow granting storage rules with an absolute host path.
+    pub(crate) fn with_absolute_storage_paths(mut self, allow: bool) -> Self {
+        self.allow_absolute_storage_paths = allow;
+        self
+    }
+
+    pub(crate) fn allows_env_inherit(&self) -> bool {
+        self.allow_env_inherit
+    }
+
+    fn create_template(
+        &self,
+        component_id: &str,
+        policy: &PolicyDocument,
+        secrets: Option<&HashMap<String, String>>,
+    ) -> Result<WasiStateTemplate> {
+        let mut template = crate::wasistate::create_wasi_state_template_with_env_inherit(
+            policy,
+            self.storage.root(),
+            self.environment_vars.as_ref(),
+            secrets,
+            self.allow_env_inherit,
+        )?;
+        template.scratch = scratch_dir_from_policy(policy, &self.scratch_root, component_id);
+        let allowed_hosts = template.allowed_hosts.len();
+        if !self.allow_any_host {
+            // `*` may come with a scheme or port, as in `https://*:443`
+            template.allowed_hosts.retain(|rule| !is_any_host(rule));
+        }
+        if template.allowed_hosts.len() < allowed_hosts {
+            warn!(
+                component_id,
+                "Policy allows every host with '*' but the manager does not allow it; only the other hosts are allowed"
+            );
+        }
+        if !self.allow_absolute_storage_paths {
+            if let Some(dir) = template
+                .preopened_dirs
+                .iter()
+                .find(|dir| is_absolute_storage_path(&dir.guest_path))
+            {
+                return Err(ComponentError::AbsoluteStoragePath {
+                    component_id: component_id.to_string(),
+                    uri: format!("fs://{}", dir.guest_path),
+                }
+                .into());
+            }
+        }
+        Ok(template)
+    }
+
+    /// Parse a component's YAML or JSON policy, migrating an older policy
+    /// version in memory. The file keeps its old version until the policy is
+    /// next saved.
+    fn parse_policy(component_id: &str, content: &str) -> Result<PolicyDocument> {
+        let (policy, report) = PolicyParser::parse_str_with_migration(content)?;
+        if let Some(report) = report {
+            info!(
+                component_id,
+                from_version = %report.from_version,
+                to_version = %report.to_version,
+                changes = ?report.changes,
+                "Migrated policy to the current version in memory"
+            );
+        }
+        Ok(policy)
+    }
+
     pub(crate) fn policy_path(&self, component_id: &str) -> PathBuf {
         self.storage.policy_path(component_id)
     }
 
+    /// Replace the component's policy file with `content`, written in
+    /// `format`, removing a policy file it had in the other format.
+    async fn write_main_policy(
+        &self,
+        component_id: &str,
+        content: &str,
+        format: PolicyFormat,
+    ) -> Result<()> {
+        let other_format = match format {
+            PolicyFormat::Yaml => PolicyFormat::Json,
+            PolicyFormat::Json => PolicyFormat::Yaml,
+        };
+        self.write_policy_file(&self.storage.policy_path_as(component_id, format), content)
+            .await?;
+        self.storage
+            .remove_if_exists(
+                &self.storage.policy_path_as(component_id, other_format),
+                "policy file",
+                component_id,
+            )
+            .await
+    }
+
     pub(crate) fn metadata_path(&self, component_id: &str) -> PathBuf {
         self.storage.policy_metadata_path(component_id)
     }
 
-    pub(crate) async fn cleanup(&self, component_id: &str) {
-        self.registry
-            .write()
+    pub(crate) fn fragment_path(&self, component_id: &str, category: PolicyCategory) -> PathBuf {
+        policy_fragments::fragment_path(self.storage.root(), component_id, category)
+    }
+
+    /// Path of the component's policy fragment for `fragment`, or of its
+    /// policy file for `None`.
+    fn policy_file_path(&self, component_id: &str, fragment: Option<PolicyCategory>) -> PathBuf {
+        match fragment {
+            Some(category) => self.fragment_path(component_id, category),
+            None => self.policy_path(component_id),
+        }
+    }
+
+    async fn read_policy_file(
+        &self,
+        component_id: &str,
+        fragment: Option<PolicyCategory>,
+    ) -> Result<Option<PolicyDocument>> {
+        let path = self.policy_file_path(component_id, fragment);
+        match tokio::fs::read_to_string(&path).await {
+            Ok(content) => Self::parse_policy(component_id, &content)
+                .map(Some)
+                .with_context(|| format!("Failed to parse policy file {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => {
+                Err(e).with_context(|| format!("Failed to read policy file {}", path.display()))
+            }
+        }
+    }
+
+    /// Read the component's policy file and all of its fragments.
+    pub(crate) async fn read_policy_files(&self, component_id: &str) -> Result<PolicyFiles> {
+        let mut files = PolicyFiles {
+            main: self.read_policy_file(component_id, None).await?,
+            ..PolicyFiles::default()
+        };
+        for category in PolicyCategory::ALL {
+            if let Some(fragment) = self.read_policy_file(component_id, Some(category)).await? {
+                files.fragments.insert(category, fragment);
+            }
+        }
+        Ok(files)
+    }
+
+    /// Remove the component's policy file, its fragments and the policy
+    /// metadata.
+    pub(crate) async fn remove_policy_files(&self, component_id: &str) -> Result<()> {
+        for format in [PolicyFormat::Yaml, PolicyFormat::Json] {
+            let policy_path = self.storage.policy_path_as(component_id, format);
+            self.storage
+                .remove_if_exists(&policy_path, "policy file", component_id)
+                .await?;
+        }
+        for category in PolicyCategory::ALL {
+            let fragment_path = self.fragment_path(component_id, category);
+            self.storage
+                .remove_if_exists(&fragment_path, "policy fragment", component_id)
+                .await?;
+        }
+        // Leave the directory alone if someone put other files in it
+        let _ = tokio::fs::remove_dir(policy_fragments::fragments_dir(
+            self.storage.root(),
+            component_id,
+        ))
+        .await;
+        let metadata_path = self.metadata_path(component_id);
+        self.storage
+            .remove_if_exists(&metadata_path, "policy metadata file", component_id)
             .await
-            .component_policies
-            .remove(component_id);
+    }
+
+    pub(crate) async fn cleanup(&self, component_id: &str) {
+        let mut registry = self.registry.write().await;
+        registry.component_policies.remove(component_id);
+        registry.origins.remove(component_id);
+        registry.disk_digests.remove(component_id);
+        registry.bump_version(component_id);
+    }
+
+    /// Record how the active policy of a component was obtained.
+    pub(crate) async fn set_origin(&self, component_id: &str, origin: PolicyOrigin) {
+        let mut registry = self.registry.write().await;
+        registry.origins.insert(component_id.to_string(), origin);
     }
 
     pub(crate) async fn store_template(
@@ -121,29 +429,110 @@ impl PolicyManager {
         component_id: &str,
         template: Arc<WasiStateTemplate>,
     ) {
-        self.registry
-            .write()
-            .await
+        let digest = self.disk_digest(component_id).await;
+        let mut registry = self.registry.write().await;
+        registry
             .component_policies
             .insert(component_id.to_string(), template);
+        match digest {
+            Some(digest) => registry
+                .disk_digests
+                .insert(component_id.to_string(), digest),
+            None => registry.disk_digests.remove(component_id),
+        };
+        registry.bump_version(component_id);
+    }
+
+    /// SHA-256 digest of the contents of the component's policy files and
+    /// fragments on disk, or `None` if it has none.
+    async fn disk_digest(&self, component_id: &str) -> Option<String> {
+        let paths = [PolicyFormat::Yaml, PolicyFormat::Json]
+            .map(|format| self.storage.policy_path_as(component_id, format))
+            .into_iter()
+            .chain(
+                PolicyCategory::ALL
+                    .into_iter()
+                    .map(|category| self.fragment_path(component_id, category)),
+            );
+        let mut hasher = Sha256::new();
+        let mut found = false;
+        for path in paths {
+            let content = match tokio::fs::read(&path).await {
+                Ok(content) => {
+                    found = true;
+                    content
+                }
+                Err(_) => Vec::new(),
+            };
+            hasher.update((content.len() as u64).to_le_bytes());
+            hasher.update(&content);
+        }
+        found.then(|| format!("{:x}", hasher.finalize()))
+    }
+
+    /// Whether the component's policy files on disk are the ones its
+    /// template was last built from, or written along with.
+    pub(crate) async fn is_disk_policy_current(&self, component_id: &str) -> bool {
+        let digest = self.disk_digest(component_id).await;
+        self.registry.read().await.disk_digests.get(component_id) == digest.as_ref()
+    }
+
+    /// Build the component's template again from its policy files on disk,
+    /// or fall back to the default template if it has none left. Returns
+    /// whether the component has a policy on disk; fails if a policy file
+    /// can not be read or parsed, e.g. while it is still being written.
+    pub(crate) async fn reload_from_disk(&self, component_id: &str) -> Result<bool> {
+        let files = self.read_policy_files(component_id).await?;
+        match files.merged(component_id) {
+            Some(policy) => {
+                self.update_policy_registry(component_id, &policy).await?;
+                self.set_origin(component_id, PolicyOrigin::LocalCopy).await;
+                Ok(true)
+            }
+            None => {
+                self.cleanup(component_id).await;
+                Ok(false)
+            }
+        }
     }
 
     pub(crate) async fn template_for_component(
         &self,
         component_id: &str,
     ) -> Arc<WasiStateTemplate> {
-        if let Some(existing) = self
-            .registry
+        self.versioned_template_for_component(component_id).await.0
+    }
+
+    /// The component's template together with its version, see
+    /// [`Self::template_version`].
+    pub(crate) async fn versioned_template_for_component(
+        &self,
+        component_id: &str,
+    ) -> (Arc<WasiStateTemplate>, u64) {
+        let (existing, version) = {
+            let registry = self.registry.read().await;
+            (
+                registry.component_policies.get(component_id).cloned(),
+                registry.versions.get(component_id).copied().unwrap_or(0),
+            )
+        };
+        match existing {
+            Some(template) => (template, version),
+            None => (self.build_default_template(component_id).await, version),
+        }
+    }
+
+    /// Version of a component's policy template. It changes whenever a
+    /// grant, revoke, attach, detach, reset, or reload replaces the template,
+    /// so anything built from an older template can tell it is stale.
+    pub(crate) async fn template_version(&self, component_id: &str) -> u64 {
+        self.registry
             .read()
             .await
-            .component_policies
+            .versions
             .get(component_id)
-            .cloned()
-        {
-            return existing;
-        }
-
-        self.build_default_template(component_id).await
+            .copied()
+            .unwrap_or(0)
     }
 
     /// Construct a default WASI template enriched with configured environment
@@ -164,21 +553,47 @@ impl PolicyManager {
         Arc::new(template)
     }
 
-    pub(crate) async fn attach_policy(&self, component_id: &str, policy_uri: &str) -> Result<()> {
+    pub(crate) async fn attach_policy(
+        &self,
+        component_id: &str,
+        policy_uri: &str,
+        cancellation: &CancellationToken,
+    ) -> Result<()> {
         info!(component_id, policy_uri, "Attaching policy to component");
+        self.source_policy.check(policy_uri)?;
 
-        let downloaded_policy = loader::load_resource::<PolicyResource>(
+        let downloaded_policy = loader::cancellable(
             policy_uri,
-            &self.oci_client,
-            &self.http_client,
-            &oci_client::secrets::RegistryAuth::Anonymous,
+            cancellation,
+            loader::load_resource::<PolicyResource>(
+                policy_uri,
+                &self.oci_client,
+                &self.http_client,
+                &oci_client::secrets::RegistryAuth::Anonymous,
+            ),
         )
         .await?;
 
-        let policy = PolicyParser::parse_file(downloaded_policy.as_ref())?;
+        let policy_content = tokio::fs::read_to_string(downloaded_policy.as_ref()).await?;
+        // The attached policy replaces the policy file; fragments keep
+        // owning their categories
+        let mut files = self.read_policy_files(component_id).await?;
+        files.main = Some(Self::parse_policy(component_id, &policy_content)?);
+        let policy = files
+            .merged(component_id)
+            .expect("a policy file makes the policy non-empty");
+        // Build the template first, so a policy that cannot be applied is
+        // not written
+        let secrets = self.secrets.load_component_secrets(component_id).await.ok();
+        let wasi_template = self.create_template(component_id, &policy, secrets.as_ref())?;
 
-        let policy_path = self.policy_path(component_id);
-        tokio::fs::copy(downloaded_policy.as_ref(), &policy_path).await?;
+        // The policy is stored in the format it was written in, so grants
+        // are written back in that format
+        let format = PolicyFormat::detect(&policy_content);
+        self.write_main_policy(component_id, &policy_content, format)
+            .await?;
+        self.record_revision(component_id, None, &format!("attach {policy_uri}"))
+            .await;
 
         let metadata = serde_json::json!({
             "source_uri": policy_uri,
@@ -190,17 +605,9 @@ impl PolicyManager {
         let metadata_path = self.metadata_path(component_id);
         tokio::fs::write(&metadata_path, serde_json::to_string_pretty(&metadata)?).await?;
 
-        let secrets = self.secrets.load_component_secrets(component_id).await.ok();
-
-        let wasi_template = crate::create_wasi_state_template_from_policy(
-            &policy,
-            self.storage.root(),
-            self.environment_vars.as_ref(),
-            secrets.as_ref(),
-        )?;
-
         self.store_template(component_id, Arc::new(wasi_template))
             .await;
+        self.set_origin(component_id, PolicyOrigin::Attached).await;
 
         info!(component_id, policy_uri, "Policy attached successfully");
         Ok(())
@@ -209,16 +616,7 @@ impl PolicyManager {
     pub(crate) async fn detach_policy(&self, component_id: &str) -> Result<()> {
         info!(component_id, "Detaching policy from component");
 
-        let policy_path = self.policy_path(component_id);
-        self.storage
-            .remove_if_exists(&policy_path, "policy file", component_id)
-            .await?;
-
-        let metadata_path = self.metadata_path(component_id);
-        self.storage
-            .remove_if_exists(&metadata_path, "policy metadata file", component_id)
-            .await?;
-
+        self.remove_policy_files(component_id).await?;
         self.cleanup(component_id).await;
 
         info!(component_id, "Policy detached successfully");
@@ -227,9 +625,13 @@ impl PolicyManager {
 
     pub(crate) async fn get_policy_info(&self, component_id: &str) -> Option<PolicyInfo> {
         let policy_path = self.policy_path(component_id);
-        if !tokio::fs::try_exists(&policy_path).await.unwrap_or(false) {
-            return None;
-        }
+        let local_path = std::iter::once(policy_path.clone())
+            .chain(
+                PolicyCategory::ALL
+                    .into_iter()
+                    .map(|category| self.fragment_path(component_id, category)),
+            )
+            .find(|path| path.exists())?;
 
         let metadata_path = self.metadata_path(component_id);
         let source_uri =
@@ -241,23 +643,74 @@ impl PolicyManager {
                         .unwrap_or("unknown")
                         .to_string()
                 } else {
-                    format!("file://{}", policy_path.display())
+                    format!("file://{}", local_path.display())
                 }
             } else {
-                format!("file://{}", policy_path.display())
+                format!("file://{}", local_path.display())
             };
 
-        let metadata = tokio::fs::metadata(&policy_path).await.ok()?;
+        let metadata = tokio::fs::metadata(&local_path).await.ok()?;
         let created_at = metadata
             .created()
             .unwrap_or_else(|_| std::time::SystemTime::now());
 
+        let mut config = BTreeMap::new();
+        let mut scratch = None;
+        let (permissions, permission_sources) = match self.read_policy_files(component_id).await {
+            Ok(files) => {
+                let permissions = match files.merged(component_id) {
+                    Some(policy) => {
+                        scratch =
+                            scratch_dir_from_policy(&policy, &self.scratch_root, component_id);
+                        config = policy.config;
+                        policy.permissions
+                    }
+                    None => Default::default(),
+                };
+                let permission_sources = PolicyCategory::ALL
+                    .into_iter()
+                    .filter(|category| category.is_set(&permissions))
+                    .map(|category| {
+                        let source = if files.fragments.contains_key(&category) {
+                            self.fragment_path(component_id, category)
+                        } else {
+                            policy_path.clone()
+                        };
+                        (category, source)
+                    })
+                    .collect();
+                (permissions, permission_sources)
+            }
+            Err(e) => {
+                warn!(component_id, error = %format!("{e:#}"), "Failed to read policy files");
+                Default::default()
+            }
+        };
+        let inherit_all_environment = permissions
+            .environment
+            .as_ref()
+            .is_some_and(|env| env.inherit_all);
+        let origin = self
+            .registry
+            .read()
+            .await
+            .origins
+            .get(component_id)
+            .copied()
+            .unwrap_or(PolicyOrigin::LocalCopy);
+
         Some(PolicyInfo {
             policy_id: format!("{component_id}-policy"),
             source_uri,
-            local_path: policy_path,
+            origin,
+            local_path,
             component_id: component_id.to_string(),
             created_at,
+            inherit_all_environment,
+            permissions,
+            permission_sources,
+            config,
+            scratch,
         })
     }
 
@@ -268,55 +721,67 @@ impl PolicyManager {
     ) -> Result<()> {
         let secrets = self.secrets.load_component_secrets(component_id).await.ok();
 
-        let wasi_template = crate::create_wasi_state_template_from_policy(
-            policy,
-            self.storage.root(),
-            self.environment_vars.as_ref(),
-            secrets.as_ref(),
-        )?;
+        let wasi_template = self.create_template(component_id, policy, secrets.as_ref())?;
 
         self.store_template(component_id, Arc::new(wasi_template))
             .await;
         Ok(())
     }
 
-    /// Rehydrate policy templates from a co-located policy file on disk, if
-    /// one exists for the component.
-    pub(crate) async fn restore_from_disk(&self, component_id: &str) -> Result<()> {
-        let policy_path = self.policy_path(component_id);
-        if !policy_path.exists() {
-            return Ok(());
-        }
+    /// Build the template for a component's co-located policy file and
+    /// fragments on disk, without installing it. Returns `None` if there is
+    /// no usable policy. Fails with [`ComponentError::AbsoluteStoragePath`]
+    /// if the policy mounts an absolute host path the manager does not
+    /// allow, since the component would otherwise run without it.
+    pub(crate) async fn template_from_disk(
+        &self,
+        component_id: &str,
+    ) -> Result<Option<Arc<WasiStateTemplate>>> {
+        let files = match self.read_policy_files(component_id).await {
+            Ok(files) => files,
+            Err(e) => {
+                warn!(component_id = %component_id, error = %format!("{e:#}"), "Failed to read co-located policy files");
+                return Ok(None);
+            }
+        };
+        let Some(policy) = files.merged(component_id) else {
+            return Ok(None);
+        };
 
         let secrets = self.secrets.load_component_secrets(component_id).await.ok();
 
-        match tokio::fs::read_to_string(&policy_path).await {
-            Ok(policy_content) => match PolicyParser::parse_str(&policy_content) {
-                Ok(policy) => match crate::create_wasi_state_template_from_policy(
-                    &policy,
-                    self.storage.root(),
-                    self.environment_vars.as_ref(),
-                    secrets.as_ref(),
-                ) {
-                    Ok(wasi_template) => {
-                        self.store_template(component_id, Arc::new(wasi_template))
-                            .await;
-                        info!(component_id = %component_id, "Restored policy association from co-located file");
-                    }
-                    Err(e) => {
-                        warn!(component_id = %component_id, error = %e, "Failed to create WASI template from policy");
-                    }
-                },
-                Err(e) => {
-                    warn!(component_id = %component_id, error = %e, "Failed to parse co-located policy file");
-                }
-            },
+        match self.create_template(component_id, &policy, secrets.as_ref()) {
+            Ok(wasi_template) => {
+                info!(component_id = %component_id, fragments = files.fragments.len(), "Restored policy association from co-located file");
+                Ok(Some(Arc::new(wasi_template)))
+            }
+            Err(e)
+                if matches!(
+                    e.downcast_ref::<ComponentError>(),
+                    Some(ComponentError::AbsoluteStoragePath { .. })
+                ) =>
+            {
+                Err(e)
+            }
             Err(e) => {
-                warn!(component_id = %component_id, error = %e, "Failed to read co-located policy file");
+                warn!(component_id = %component_id, error = %e, "Failed to create WASI template from policy");
+                Ok(None)
             }
         }
+    }
 
-        Ok(())
+    /// Build the template for a policy given as YAML, such as the policy of
+    /// an embedded component, without installing it.
+    pub(crate) async fn template_from_yaml(
+        &self,
+        component_id: &str,
+        yaml: &str,
+    ) -> Result<Arc<WasiStateTemplate>> {
+        let policy = Self::parse_policy(component_id, yaml)
+            .with_context(|| format!("Invalid policy for component '{component_id}'"))?;
+        let secrets = self.secrets.load_component_secrets(component_id).await.ok();
+        let wasi_template = self.create_template(component_id, &policy, secrets.as_ref())?;
+        Ok(Arc::new(wasi_template))
     }
 
     pub(crate) async fn revoke_storage_permission_by_uri(
@@ -329,7 +794,8 @@ impl PolicyManager {
         }
         let mut policy = self.load_or_create_component_policy(component_id).await?;
         self.remove_storage_permission_by_uri_from_policy(&mut policy, uri)?;
-        self.save_component_policy(component_id, &policy).await?;
+        self.save_component_policy(component_id, &policy, "revoke storage")
+            .await?;
         self.update_policy_registry(component_id, &policy).await?;
         Ok(())
     }
@@ -348,9 +814,41 @@ impl PolicyManager {
         );
         let permission_rule = self.parse_permission_rule(permission_type, details)?;
         self.validate_permission_rule(&permission_rule)?;
+        if let PermissionRule::Network(NetworkPermission::Host(network)) = &permission_rule {
+            self.check_host_grant(&network.host)?;
+        }
+        if let PermissionRule::Custom(type_name, details) = &permission_rule {
+            if type_name == "resource" {
+                if let Some(memory) = resource_limit(details, "memory").and_then(memory_limit) {
+                    self.check_memory_grant(&memory)?;
+                }
+            }
+        }
+        if let PermissionRule::Storage(storage) = &permission_rule {
+            let create_if_missing = details
+                .get("createIfMissing")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            let path = storage.uri.strip_prefix("fs://").unwrap_or(&storage.uri);
+            if is_absolute_storage_path(path) && !self.allow_absolute_storage_paths {
+                return Err(anyhow!(
+                    "Granting access to the absolute host path '{}' requires allow_absolute_storage_paths to be set in the server configuration",
+                    storage.uri
+                ));
+            }
+            if create_if_missing && !storage.access.contains(&AccessType::Write) {
+                return Err(anyhow!(
+                    "createIfMissing requires write access to '{}'",
+                    storage.uri
+                ));
+            }
+            self.ensure_storage_path(&storage.uri, create_if_missing)
+                .await?;
+        }
         let mut policy = self.load_or_create_component_policy(component_id).await?;
         self.add_permission_rule_to_policy(&mut policy, permission_rule)?;
-        self.save_component_policy(component_id, &policy).await?;
+        self.save_component_policy(component_id, &policy, &format!("grant {permission_type}"))
+            .await?;
         self.update_policy_registry(component_id, &policy).await?;
 
         info!(
@@ -360,6 +858,160 @@ impl PolicyManager {
         Ok(())
     }
 
+    /// Check that a network host to grant is a host or a wildcard policies
+    /// accept, and that `*` is only granted when the manager allows it.
+    fn check_host_grant(&self, host: &str) -> Result<()> {
+        if host == ANY_HOST {
+            if !self.allow_any_host {
+                return Err(anyhow!(
+                    "Granting access to every host with '*' requires allow_any_host to be set in the server configuration"
+                ));
+            }
+            return Ok(());
+        }
+        if let Some(domain) = host.strip_prefix("*.") {
+            if domain.is_empty() || domain.ends_with('.') || domain.contains('*') {
+                return Err(anyhow!("Invalid wildcard host '{host}'"));
+            }
+        } else if host.contains('*') {
+            return Err(anyhow!(
+                "Wildcard hosts must start with '*.', like '*.example.com': {host}"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check that a memory limit parses and does not ask for more than the
+    /// configured share of the host's RAM. Where the host's RAM is unknown,
+    /// only the format is checked.
+    fn check_memory_grant(&self, memory: &policy::MemoryLimit) -> Result<()> {
+        let bytes = memory
+            .to_bytes()
+            .map_err(|e| anyhow!("Invalid memory limit: {e}"))?;
+        if let Some(host_bytes) = host_memory_bytes() {
+            let max_bytes = (host_bytes as f64 * self.max_memory_grant_fraction) as u64;
+            if bytes > max_bytes {
+                return Err(anyhow!(
+                    "Memory limit of {bytes} bytes exceeds {}% of the host's {host_bytes} bytes of RAM ({max_bytes} bytes); raise max_memory_grant_fraction to allow it",
+                    self.max_memory_grant_fraction * 100.0
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that the host path a storage URI grants exists, or create it as
+    /// a directory only the current user can access.
+    async fn ensure_storage_path(&self, uri: &str, create_if_missing: bool) -> Result<()> {
+        let Some(host_path) = crate::wasistate::storage_host_path(uri, self.storage.root())? else {
+            return Ok(());
+        };
+        if tokio::fs::try_exists(&host_path).await? {
+            return Ok(());
+        }
+        if !create_if_missing {
+            return Err(anyhow!(
+                "Storage path does not exist: '{}' (from '{}'). Create it first, or set createIfMissing with write access to create it",
+                host_path.display(),
+                uri
+            ));
+        }
+        let mut builder = tokio::fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        builder.mode(0o700);
+        builder.create(&host_path).await.map_err(|e| {
+            anyhow!(
+                "Failed to create storage path '{}': {}",
+                host_path.display(),
+                e
+            )
+        })?;
+        info!(path = %host_path.display(), "Created directory for storage permission");
+        Ok(())
+    }
+
+    /// Grant network access to several hosts in one policy update. Every host
+    /// is validated before the policy is touched, so either all of them are
+    /// applied with a single write or none are. Hosts the policy already
+    /// allows, or that repeat earlier in `hosts`, are reported as
+    /// [`HostGrantStatus::AlreadyGranted`].
+    #[instrument(skip(self))]
+    pub async fn grant_network_hosts(
+        &self,
+        component_id: &str,
+        hosts: &[String],
+    ) -> Result<Vec<HostGrant>> {
+        if hosts.is_empty() {
+            return Err(anyhow!("No hosts to grant network permission to"));
+        }
+        let invalid: Vec<String> = hosts
+            .iter()
+            .filter_map(|host| {
+                let rule =
+                    PermissionRule::Network(NetworkPermission::Host(NetworkHostPermission {
+                        host: host.clone(),
+                        ..Default::default()
+                    }));
+                self.validate_permission_rule(&rule)
+                    .and_then(|()| self.check_host_grant(host))
+                    .err()
+                    .map(|e| format!("'{host}': {e}"))
+            })
+            .collect();
+        if !invalid.is_empty() {
+            return Err(anyhow!(
+                "Invalid hosts, nothing was granted: {}",
+                invalid.join("; ")
+            ));
+        }
+
+        let mut policy = self.load_or_create_component_policy(component_id).await?;
+        let allow_set = policy
+            .permissions
+            .network
+            .get_or_insert_with(Default::default)
+            .allow
+            .get_or_insert_with(Vec::new);
+        let results: Vec<HostGrant> = hosts
+            .iter()
+            .map(|host| {
+                let rule = NetworkPermission::Host(NetworkHostPermission {
+                    host: host.clone(),
+                    ..Default::default()
+                });
+                let status = if allow_set
+                    .iter()
+                    .any(|perm| same_network_target(perm, &rule))
+                {
+                    HostGrantStatus::AlreadyGranted
+                } else {
+                    allow_set.push(rule);
+                    HostGrantStatus::Granted
+                };
+                HostGrant {
+                    host: host.clone(),
+                    status,
+                }
+            })
+            .collect();
+
+        if results
+            .iter()
+            .any(|result| result.status == HostGrantStatus::Granted)
+        {
+            self.save_component_policy(component_id, &policy, "grant network")
+                .await?;
+            self.update_policy_registry(component_id, &policy).await?;
+        }
+        info!(
+            component_id,
+            hosts = hosts.len(),
+            "Network permissions granted successfully"
+        );
+        Ok(results)
+    }
+
     /// Parse a permission rule from the request details
     fn parse_permission_rule(
         &self,
@@ -372,8 +1024,32 @@ impl PolicyManager {
                     .get("host")
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| anyhow!("Missing 'host' field for network permission"))?;
+                let timeout = details
+                    .get("timeout")
+                    .map(|v| serde_json::from_value::<TimeoutLimit>(v.clone()))
+                    .transpose()
+                    .map_err(|e| anyhow!("Invalid 'timeout' field for network permission: {e}"))?;
+                let port = details
+                    .get("port")
+                    .map(|v| {
+                        v.as_u64()
+                            .and_then(|port| u16::try_from(port).ok())
+                            .ok_or_else(|| anyhow!("'port' must be a number from 1 to 65535"))
+                    })
+                    .transpose()?;
+                let scheme = details
+                    .get("scheme")
+                    .map(|v| {
+                        v.as_str()
+                            .map(str::to_ascii_lowercase)
+                            .ok_or_else(|| anyhow!("'scheme' must be a string"))
+                    })
+                    .transpose()?;
                 PermissionRule::Network(NetworkPermission::Host(NetworkHostPermission {
                     host: host.to_string(),
+                    port,
+                    scheme,
+                    timeout,
                 }))
             }
             "storage" => {
@@ -416,6 +1092,11 @@ impl PolicyManager {
                     })
                 }
             }
+            "environment" | "environment-variable"
+                if details.get("inherit_all").and_then(|v| v.as_bool()) == Some(true) =>
+            {
+                PermissionRule::EnvironmentInheritAll
+            }
             "environment" | "environment-variable" => {
                 let key = details
                     .get("key")
@@ -425,30 +1106,32 @@ impl PolicyManager {
                     key: key.to_string(),
                 })
             }
+            "config" => {
+                let key = details
+                    .get("key")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow!("Missing 'key' field for config value"))?;
+                let value = details.get("value").map(|v| match v {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                });
+                PermissionRule::Config(key.to_string(), value)
+            }
             "resource" => {
-                // Handle both direct memory field and nested resources.limits.memory structure
-                let memory = if let Some(memory_str) =
-                    details.get("memory").and_then(|v| v.as_str())
-                {
-                    // Direct memory field (for backward compatibility or direct API calls)
-                    memory_str
-                } else if let Some(memory_str) = details
-                    .get("resources")
-                    .and_then(|r| r.get("limits"))
-                    .and_then(|l| l.get("memory"))
-                    .and_then(|m| m.as_str())
-                {
-                    // Nested structure from CLI (resources.limits.memory)
-                    memory_str
-                } else {
-                    return Err(anyhow!("Missing 'memory' field for resource permission. Expected either 'memory' or 'resources.limits.memory'"));
-                };
+                // Handle both direct fields (for backward compatibility or
+                // direct API calls) and the nested resources.limits structure
+                // from the CLI
+                let memory = resource_limit(details, "memory").and_then(memory_limit);
+                let cpu = resource_limit(details, "cpu").and_then(|v| v.as_str());
+                if memory.is_none() && cpu.is_none() {
+                    return Err(anyhow!("Missing 'memory' or 'cpu' field for resource permission. Expected either 'memory'/'cpu' or 'resources.limits.memory'/'resources.limits.cpu'"));
+                }
 
                 // Create structured resource limits instead of hardcoded JSON
                 let resource_limits = policy::ResourceLimits {
                     limits: Some(policy::ResourceLimitValues::new(
-                        None,
-                        Some(policy::MemoryLimit::String(memory.to_string())),
+                        cpu.map(|cpu| policy::CpuLimit::String(cpu.to_string())),
+                        memory,
                     )),
                     ..Default::default()
                 };
@@ -467,24 +1150,27 @@ impl PolicyManager {
         Ok(permission_rule)
     }
 
-    /// Load or create component policy
+    /// Load the component's policy, merged from its policy file and
+    /// fragments, or create one
     pub(crate) async fn load_or_create_component_policy(
         &self,
         component_id: &str,
     ) -> Result<policy::PolicyDocument> {
-        let policy_path = self.policy_path(component_id);
-
-        if policy_path.exists() {
-            let policy_content = tokio::fs::read_to_string(&policy_path).await?;
-            Ok(PolicyParser::parse_str(&policy_content)?)
+        if let Some(policy) = self
+            .read_policy_files(component_id)
+            .await?
+            .merged(component_id)
+        {
+            Ok(policy)
         } else {
             // Create minimal policy document
             Ok(policy::PolicyDocument {
-                version: "1.0".to_string(),
+                version: CURRENT_POLICY_VERSION.to_string(),
                 description: Some(format!(
                     "Auto-generated policy for component: {component_id}"
                 )),
                 permissions: Default::default(),
+                config: Default::default(),
             })
         }
     }
@@ -505,6 +1191,25 @@ impl PolicyManager {
             PermissionRule::Environment(env) => {
                 self.add_environment_permission_to_policy(policy, env)
             }
+            PermissionRule::EnvironmentInheritAll => {
+                if !self.allow_env_inherit {
+                    return Err(anyhow!(
+                        "Granting inheritance of all environment variables requires the server to be started with --allow-env-inherit"
+                    ));
+                }
+                policy
+                    .permissions
+                    .environment
+                    .get_or_insert_with(Default::default)
+                    .inherit_all = true;
+                Ok(())
+            }
+            PermissionRule::Config(key, value) => {
+                let value =
+                    value.ok_or_else(|| anyhow!("Missing 'value' field for config value"))?;
+                policy.config.insert(key, value);
+                Ok(())
+            }
             PermissionRule::Custom(type_name, details) => {
                 if type_name == "resource" {
                     self.add_resource_permission_to_policy(policy, details)
@@ -531,9 +1236,23 @@ impl PolicyManager {
             .allow
             .get_or_insert_with(Vec::new);
 
-        // Only add if not already present (prevent duplicates)
-        if !allow_set.contains(&network) {
-            allow_set.push(network);
+        // Only add if not already present (prevent duplicates); a grant with
+        // a timeout updates the existing rule's timeout
+        match allow_set
+            .iter_mut()
+            .find(|perm| same_network_target(perm, &network))
+        {
+            Some(NetworkPermission::Host(existing)) => {
+                if let NetworkPermission::Host(NetworkHostPermission {
+                    timeout: Some(timeout),
+                    ..
+                }) = network
+                {
+                    existing.timeout = Some(timeout);
+                }
+            }
+            Some(_) => {}
+            None => allow_set.push(network),
         }
 
         Ok(())
@@ -613,27 +1332,16 @@ impl PolicyManager {
         policy: &mut PolicyDocument,
         details: serde_json::Value,
     ) -> Result<()> {
-        // Extract the memory limit from the details - handle both original CLI format and converted ResourceLimits format
-        let memory_str = if let Some(memory_str) = details
-            .get("resources")
-            .and_then(|r| r.get("limits"))
-            .and_then(|l| l.get("memory"))
-            .and_then(|m| m.as_str())
-        {
-            // Original CLI format: {"resources": {"limits": {"memory": "512Mi"}}}
-            memory_str
-        } else if let Some(memory_str) = details
-            .get("limits")
-            .and_then(|l| l.get("memory"))
-            .and_then(|m| m.as_str())
-        {
-            // Converted ResourceLimits format: {"limits": {"memory": "512Mi"}}
-            memory_str
-        } else {
+        // Extract the limits from the details - handle both original CLI format
+        // ({"resources": {"limits": {"memory": "512Mi"}}}) and converted
+        // ResourceLimits format ({"limits": {"memory": "512Mi"}})
+        let memory = resource_limit(&details, "memory").and_then(memory_limit);
+        let cpu_str = resource_limit(&details, "cpu").and_then(|v| v.as_str());
+        if memory.is_none() && cpu_str.is_none() {
             return Err(anyhow!(
-                "Invalid resource permission format: missing memory field"
+                "Invalid resource permission format: missing memory or cpu field"
             ));
-        };
+        }
 
         // Initialize resources if not present
         let resources = policy
@@ -641,36 +1349,176 @@ impl PolicyManager {
             .resources
             .get_or_insert_with(Default::default);
 
-        // Initialize limits if not present
-        let limits = resources
-            .limits
-            .get_or_insert_with(|| policy::ResourceLimitValues::new(None, None));
+        // Initialize limits if not present
+        let limits = resources
+            .limits
+            .get_or_insert_with(|| policy::ResourceLimitValues::new(None, None));
+
+        if let Some(memory) = memory {
+            limits.memory = Some(memory);
+        }
+        if let Some(cpu_str) = cpu_str {
+            limits.cpu = Some(policy::CpuLimit::String(cpu_str.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Save component policy, writing each category to the policy file or
+    /// fragment that owns it. Every file that changes is recorded as a new
+    /// revision, attributed to `action`
+    pub(crate) async fn save_component_policy(
+        &self,
+        component_id: &str,
+        policy: &PolicyDocument,
+        action: &str,
+    ) -> Result<()> {
+        let files = self.read_policy_files(component_id).await?;
+        let updated = files.split(policy);
+        if let Some(main) = updated
+            .main
+            .as_ref()
+            .filter(|main| files.main.as_ref() != Some(*main))
+        {
+            let policy_path = self.policy_path(component_id);
+            let content = match PolicyFormat::from_path(&policy_path).unwrap_or_default() {
+                PolicyFormat::Yaml => serde_yaml::to_string(main)?,
+                PolicyFormat::Json => serde_json::to_string_pretty(main)?,
+            };
+            self.write_policy_file(&policy_path, &content).await?;
+            self.record_revision(component_id, None, action).await;
+        }
+        for (category, fragment) in &updated.fragments {
+            if files.fragments.get(category) != Some(fragment) {
+                self.write_policy_file(
+                    &self.fragment_path(component_id, *category),
+                    &serde_yaml::to_string(fragment)?,
+                )
+                .await?;
+                self.record_revision(component_id, Some(*category), action)
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    async fn write_policy_file(&self, policy_path: &std::path::Path, content: &str) -> Result<()> {
+        // Write to a temporary file and rename, so a policy with several
+        // changes is never seen half-written.
+        if let Some(parent) = policy_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut tmp_path = policy_path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        tokio::fs::write(&tmp_path, content).await?;
+        tokio::fs::rename(&tmp_path, policy_path).await?;
+        Ok(())
+    }
 
-        // Set the memory limit
-        limits.memory = Some(policy::MemoryLimit::String(memory_str.to_string()));
+    /// Keep a copy of the component's current policy file, or of one of its
+    /// fragments, in its history. A failure is logged rather than failing
+    /// the change that was made.
+    async fn record_revision(
+        &self,
+        component_id: &str,
+        fragment: Option<PolicyCategory>,
+        action: &str,
+    ) {
+        let policy_path = self.policy_file_path(component_id, fragment);
+        if let Err(error) = self
+            .history
+            .record(component_id, &policy_path, fragment, action)
+            .await
+        {
+            warn!(component_id, %error, "Failed to record policy revision");
+        }
+    }
 
-        Ok(())
+    /// The stored revisions of a component's policy, oldest first.
+    pub(crate) async fn policy_history(&self, component_id: &str) -> Result<Vec<PolicyRevision>> {
+        self.history.list(component_id).await
     }
 
-    /// Save component policy to file
-    pub(crate) async fn save_component_policy(
+    /// Replace the component's policy file, or the fragment the revision is
+    /// of, with a stored revision and install the template built from the
+    /// merged policy. The restored file is recorded as a new revision, so a
+    /// rollback can itself be undone.
+    #[instrument(skip(self))]
+    pub(crate) async fn restore_policy_revision(
         &self,
         component_id: &str,
-        policy: &PolicyDocument,
+        revision: u64,
     ) -> Result<()> {
-        let policy_path = self.policy_path(component_id);
-        let policy_yaml = serde_yaml::to_string(policy)?;
-        tokio::fs::write(&policy_path, policy_yaml).await?;
+        let (stored, revision_path) = self.history.revision(component_id, revision).await?;
+        let policy_content = tokio::fs::read_to_string(&revision_path).await?;
+        let restored = Self::parse_policy(component_id, &policy_content)?;
+        let mut files = self.read_policy_files(component_id).await?;
+        match stored.fragment {
+            Some(category) => {
+                files.fragments.insert(category, restored);
+            }
+            None => files.main = Some(restored),
+        }
+        let policy = files
+            .merged(component_id)
+            .expect("a restored file makes the policy non-empty");
+        // Build the template before touching the file, so a revision that
+        // can no longer be applied leaves the current policy in place.
+        let secrets = self.secrets.load_component_secrets(component_id).await.ok();
+        let wasi_template = self.create_template(component_id, &policy, secrets.as_ref())?;
+        match stored.fragment {
+            Some(category) => {
+                self.write_policy_file(&self.fragment_path(component_id, category), &policy_content)
+                    .await?
+            }
+            None => {
+                let format = PolicyFormat::detect(&policy_content);
+                self.write_main_policy(component_id, &policy_content, format)
+                    .await?
+            }
+        }
+        self.store_template(component_id, Arc::new(wasi_template))
+            .await;
+        self.record_revision(
+            component_id,
+            stored.fragment,
+            &format!("rollback to {revision}"),
+        )
+        .await;
+        info!(component_id, revision, "Policy rolled back");
         Ok(())
     }
 
+    /// Delete the stored revisions of a component's policy.
+    pub(crate) async fn remove_history(&self, component_id: &str) -> Result<()> {
+        self.history.remove(component_id).await
+    }
+
     /// Validate permission rule
     fn validate_permission_rule(&self, rule: &PermissionRule) -> Result<()> {
         match rule {
-            PermissionRule::Network(NetworkPermission::Host(NetworkHostPermission { host })) => {
+            PermissionRule::Network(NetworkPermission::Host(NetworkHostPermission {
+                host,
+                port,
+                scheme,
+                timeout,
+            })) => {
                 if host.is_empty() {
                     return Err(anyhow!("Network host cannot be empty"));
                 }
+                if *port == Some(0) {
+                    return Err(anyhow!("Network port cannot be 0"));
+                }
+                if let Some(scheme) = scheme {
+                    if scheme != "http" && scheme != "https" {
+                        return Err(anyhow!(
+                            "Network scheme must be http or https, not '{scheme}'"
+                        ));
+                    }
+                }
+                if let Some(timeout) = timeout {
+                    timeout.to_duration()?;
+                }
             }
             PermissionRule::Storage(storage) => {
                 // TODO: the validation should verify if the uri is actually valid or not
@@ -685,6 +1533,11 @@ impl PolicyManager {
                     return Err(anyhow!("Environment variable key cannot be empty"));
                 }
             }
+            PermissionRule::Config(key, _) => {
+                if key.is_empty() {
+                    return Err(anyhow!("Config key cannot be empty"));
+                }
+            }
             _ => {}
         }
         Ok(())
@@ -706,7 +1559,8 @@ impl PolicyManager {
         self.validate_permission_rule(&permission_rule)?;
         let mut policy = self.load_or_create_component_policy(component_id).await?;
         self.remove_permission_rule_from_policy(&mut policy, permission_rule)?;
-        self.save_component_policy(component_id, &policy).await?;
+        self.save_component_policy(component_id, &policy, &format!("revoke {permission_type}"))
+            .await?;
         self.update_policy_registry(component_id, &policy).await?;
 
         info!(
@@ -720,16 +1574,7 @@ impl PolicyManager {
     #[instrument(skip(self))]
     pub async fn reset_permission(&self, component_id: &str) -> Result<()> {
         info!(component_id, "Resetting all permissions for component");
-        // Remove policy files
-        let policy_path = self.policy_path(component_id);
-        self.storage
-            .remove_if_exists(&policy_path, "policy file", component_id)
-            .await?;
-
-        let metadata_path = self.metadata_path(component_id);
-        self.storage
-            .remove_if_exists(&metadata_path, "policy metadata file", component_id)
-            .await?;
+        self.remove_policy_files(component_id).await?;
 
         // Remove from policy registry
         self.cleanup(component_id).await;
@@ -754,6 +1599,16 @@ impl PolicyManager {
             PermissionRule::Environment(env) => {
                 self.remove_environment_permission_from_policy(policy, env)
             }
+            PermissionRule::EnvironmentInheritAll => {
+                if let Some(env_perms) = &mut policy.permissions.environment {
+                    env_perms.inherit_all = false;
+                }
+                Ok(())
+            }
+            PermissionRule::Config(key, _) => {
+                policy.config.remove(&key);
+                Ok(())
+            }
             PermissionRule::Custom(type_name, details) => {
                 if type_name == "resource" {
                     self.remove_resource_permission_from_policy(policy, details)
@@ -775,7 +1630,7 @@ impl PolicyManager {
     ) -> Result<()> {
         if let Some(network_perms) = &mut policy.permissions.network {
             if let Some(allow_set) = &mut network_perms.allow {
-                allow_set.retain(|perm| perm != &network);
+                allow_set.retain(|perm| !same_network_target(perm, &network));
                 // Clean up empty structures
                 if allow_set.is_empty() {
                     network_perms.allow = None;
@@ -846,6 +1701,31 @@ impl PolicyManager {
     }
 }
 
+/// The value of the resource limit `name` in grant details, given directly,
+/// under `limits`, or under `resources.limits`.
+pub(crate) fn resource_limit<'a>(
+    details: &'a serde_json::Value,
+    name: &str,
+) -> Option<&'a serde_json::Value> {
+    details.get(name).or_else(|| {
+        details
+            .get("resources")
+            .and_then(|r| r.get("limits"))
+            .or_else(|| details.get("limits"))
+            .and_then(|l| l.get(name))
+    })
+}
+
+/// A memory limit given as a quantity string such as `512Mi`, or as a whole
+/// number of megabytes.
+pub(crate) fn memory_limit(value: &serde_json::Value) -> Option<policy::MemoryLimit> {
+    match value {
+        serde_json::Value::String(s) => Some(policy::MemoryLimit::String(s.clone())),
+        serde_json::Value::Number(n) => n.as_u64().map(policy::MemoryLimit::Number),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -927,6 +1807,223 @@ permissions: {}
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_grant_memory_permission_checks_the_quantity() -> Result<()> {
+        let manager = create_test_manager().await?;
+        manager.load_test_component().await?;
+
+        let details = serde_json::json!({"memory": "64M"});
+        let bytes = manager
+            .grant_memory_permission(TEST_COMPONENT_ID, &details)
+            .await?;
+        assert_eq!(bytes, 64_000_000);
+
+        // A plain number is read as megabytes
+        let details = serde_json::json!({"resources": {"limits": {"memory": 64}}});
+        let bytes = manager
+            .grant_memory_permission(TEST_COMPONENT_ID, &details)
+            .await?;
+        assert_eq!(bytes, 64 * 1024 * 1024);
+
+        for invalid in ["lots", "0Mi", "99999999999T"] {
+            let details = serde_json::json!({"memory": invalid});
+            let error = manager
+                .grant_memory_permission(TEST_COMPONENT_ID, &details)
+                .await
+                .unwrap_err();
+            assert!(error.to_string().contains("Invalid memory limit"));
+        }
+
+        if host_memory_bytes().is_some() {
+            let details = serde_json::json!({"memory": "1000000Ti"});
+            let error = manager
+                .grant_memory_permission(TEST_COMPONENT_ID, &details)
+                .await
+                .unwrap_err();
+            assert!(error.to_string().contains("max_memory_grant_fraction"));
+        }
+
+        let policy_content =
+            tokio::fs::read_to_string(manager.get_component_policy_path(TEST_COMPONENT_ID)).await?;
+        assert!(policy_content.contains("memory: 64"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grant_wildcard_network_hosts() -> Result<()> {
+        let manager = create_test_manager().await?;
+        manager.load_test_component().await?;
+
+        manager
+            .grant_network_hosts(TEST_COMPONENT_ID, &["*.example.com".to_string()])
+            .await?;
+        let policy_content =
+            tokio::fs::read_to_string(manager.get_component_policy_path(TEST_COMPONENT_ID)).await?;
+        assert!(policy_content.contains("*.example.com"));
+
+        let error = manager
+            .grant_network_hosts(TEST_COMPONENT_ID, &[ANY_HOST.to_string()])
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("allow_any_host"));
+        let details = serde_json::json!({"host": "exam*ple.com"});
+        let error = manager
+            .grant_permission(TEST_COMPONENT_ID, "network", &details)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Wildcard hosts"));
+
+        // A `*` already in a policy is dropped unless the manager allows it
+        let policy = PolicyParser::parse_str(
+            "version: \"1.0\"\npermissions:\n  network:\n    allow:\n      - host: \"*\"\n      - host: \"api.example.com\"\n",
+        )?;
+        let template = manager
+            .policy_manager
+            .create_template(TEST_COMPONENT_ID, &policy, None)?;
+        assert_eq!(
+            template.allowed_hosts,
+            std::collections::HashSet::from(["api.example.com".to_string()])
+        );
+        let policy_manager = manager.policy_manager.clone().with_any_host(true);
+        let template = policy_manager.create_template(TEST_COMPONENT_ID, &policy, None)?;
+        assert!(template.allowed_hosts.contains(ANY_HOST));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_absolute_storage_paths_need_opt_in() -> Result<()> {
+        let manager = create_test_manager().await?;
+        manager.load_test_component().await?;
+        let host_dir = tempfile::tempdir()?;
+        let uri = format!("fs://{}", host_dir.path().display());
+
+        let details = serde_json::json!({"uri": uri, "access": ["read"]});
+        let error = manager
+            .grant_permission(TEST_COMPONENT_ID, "storage", &details)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("allow_absolute_storage_paths"));
+        let relative = serde_json::json!({"uri": "fs://workspace", "access": ["read", "write"], "createIfMissing": true});
+        manager
+            .grant_permission(TEST_COMPONENT_ID, "storage", &relative)
+            .await?;
+
+        // A policy naming an absolute path is refused rather than applied
+        // without the mount
+        let policy = PolicyParser::parse_str(format!(
+            "version: \"1.0\"\npermissions:\n  storage:\n    allow:\n      - uri: \"{uri}\"\n        access: [\"read\"]\n      - uri: \"fs://workspace\"\n        access: [\"read\"]\n"
+        ))?;
+        let Err(error) = manager
+            .policy_manager
+            .create_template(TEST_COMPONENT_ID, &policy, None)
+        else {
+            panic!("a policy with an absolute path was applied");
+        };
+        assert!(matches!(
+            error.downcast_ref::<ComponentError>(),
+            Some(ComponentError::AbsoluteStoragePath { uri: refused, .. }) if *refused == uri
+        ));
+
+        let policy_manager = manager
+            .policy_manager
+            .clone()
+            .with_absolute_storage_paths(true);
+        let template = policy_manager.create_template(TEST_COMPONENT_ID, &policy, None)?;
+        assert_eq!(template.preopened_dirs.len(), 2);
+        policy_manager
+            .grant_permission(TEST_COMPONENT_ID, "storage", &details)
+            .await?;
+
+        // The component does not load with that policy on disk
+        let error = manager
+            .reload_component(TEST_COMPONENT_ID)
+            .await
+            .unwrap_err();
+        assert!(format!("{error:#}").contains("allow_absolute_storage_paths"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grant_network_port_and_scheme() -> Result<()> {
+        let manager = create_test_manager().await?;
+        manager.load_test_component().await?;
+
+        let https_only =
+            serde_json::json!({"host": "api.example.com", "port": 443, "scheme": "https"});
+        manager
+            .grant_permission(TEST_COMPONENT_ID, "network", &https_only)
+            .await?;
+        manager
+            .grant_permission(
+                TEST_COMPONENT_ID,
+                "network",
+                &serde_json::json!({"host": "api.example.com"}),
+            )
+            .await?;
+        let allowed_hosts = || async {
+            anyhow::Ok(
+                manager
+                    .policy_manager
+                    .template_for_component(TEST_COMPONENT_ID)
+                    .await
+                    .allowed_hosts
+                    .clone(),
+            )
+        };
+        assert_eq!(
+            allowed_hosts().await?,
+            std::collections::HashSet::from([
+                "https://api.example.com:443".to_string(),
+                "api.example.com".to_string(),
+            ])
+        );
+
+        // Revoking matches the host, port, and scheme of the rule
+        manager
+            .revoke_permission(
+                TEST_COMPONENT_ID,
+                "network",
+                &serde_json::json!({"host": "api.example.com"}),
+            )
+            .await?;
+        assert_eq!(
+            allowed_hosts().await?,
+            std::collections::HashSet::from(["https://api.example.com:443".to_string()])
+        );
+        manager
+            .revoke_permission(TEST_COMPONENT_ID, "network", &https_only)
+            .await?;
+        assert!(allowed_hosts().await?.is_empty());
+
+        let error = manager
+            .grant_permission(
+                TEST_COMPONENT_ID,
+                "network",
+                &serde_json::json!({"host": "api.example.com", "scheme": "ftp"}),
+            )
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("http or https"));
+        assert!(manager
+            .grant_permission(
+                TEST_COMPONENT_ID,
+                "network",
+                &serde_json::json!({"host": "api.example.com", "port": 70000}),
+            )
+            .await
+            .is_err());
+
+        // `*` with a port still needs allow_any_host
+        let policy = PolicyParser::parse_str(
+            "version: \"1.0\"\npermissions:\n  network:\n    allow:\n      - host: \"*\"\n        port: 443\n",
+        )?;
+        let template = manager
+            .policy_manager
+            .create_template(TEST_COMPONENT_ID, &policy, None)?;
+        assert!(template.allowed_hosts.is_empty());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_grant_permission_network() -> Result<()> {
         let manager = create_test_manager().await?;
@@ -950,11 +2047,204 @@ permissions: {}
     }
 
     #[tokio::test]
-    async fn test_grant_permission_storage() -> Result<()> {
+    async fn test_json_policy_keeps_json_format() -> Result<()> {
+        let manager = create_test_manager().await?;
+        manager.load_test_component().await?;
+
+        let policy_path = manager.component_root().join("test-policy.json");
+        tokio::fs::write(
+            &policy_path,
+            r#"{"version": "1.0", "permissions": {"network": {"allow": [{"host": "example.com"}]}}}"#,
+        )
+        .await?;
+        manager
+            .attach_policy(
+                TEST_COMPONENT_ID,
+                &format!("file://{}", policy_path.display()),
+            )
+            .await?;
+        let co_located_path = manager.get_component_policy_path(TEST_COMPONENT_ID);
+        assert_eq!(
+            co_located_path.file_name().unwrap(),
+            format!("{TEST_COMPONENT_ID}.policy.json").as_str()
+        );
+
+        let details = serde_json::json!({"host": "api.example.com"});
+        manager
+            .grant_permission(TEST_COMPONENT_ID, "network", &details)
+            .await?;
+        let content = tokio::fs::read_to_string(&co_located_path).await?;
+        let value: serde_json::Value = serde_json::from_str(&content)?;
+        let hosts = value["permissions"]["network"]["allow"].as_array().unwrap();
+        assert!(hosts.contains(&serde_json::json!({"host": "example.com"})));
+        assert!(hosts.contains(&serde_json::json!({"host": "api.example.com"})));
+
+        // The JSON policy is restored after a restart, and rolling back to
+        // the attached revision keeps it JSON
+        let restarted = crate::LifecycleManager::new(manager.component_root()).await?;
+        let template = restarted
+            .policy_manager
+            .template_for_component(TEST_COMPONENT_ID)
+            .await;
+        assert!(template.allowed_hosts.contains("api.example.com"));
+        restarted
+            .restore_policy_revision(TEST_COMPONENT_ID, 1)
+            .await?;
+        let content = tokio::fs::read_to_string(&co_located_path).await?;
+        assert!(!serde_json::from_str::<serde_json::Value>(&content)?
+            .to_string()
+            .contains("api.example.com"));
+
+        // Attaching a YAML policy replaces the JSON one
+        let yaml_path = manager.component_root().join("test-policy.yaml");
+        tokio::fs::write(&yaml_path, "version: \"1.0\"\npermissions: {}\n").await?;
+        restarted
+            .attach_policy(
+                TEST_COMPONENT_ID,
+                &format!("file://{}", yaml_path.display()),
+            )
+            .await?;
+        assert!(!co_located_path.exists());
+        assert!(restarted
+            .get_component_policy_path(TEST_COMPONENT_ID)
+            .ends_with(format!("{TEST_COMPONENT_ID}.policy.yaml")));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grant_environment_inherit_all_requires_opt_in() -> Result<()> {
+        let manager = create_test_manager().await?;
+        manager.load_test_component().await?;
+
+        let details = serde_json::json!({"inherit_all": true});
+        let err = manager
+            .grant_permission(TEST_COMPONENT_ID, "environment", &details)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("--allow-env-inherit"));
+
+        let manager = create_test_manager_with(|builder| builder.with_env_inherit(true)).await?;
+        manager.load_test_component().await?;
+        manager
+            .grant_permission(TEST_COMPONENT_ID, "environment", &details)
+            .await?;
+        let info = manager.get_policy_info(TEST_COMPONENT_ID).await.unwrap();
+        assert!(info.inherit_all_environment);
+
+        manager
+            .revoke_permission(TEST_COMPONENT_ID, "environment", &details)
+            .await?;
+        let info = manager.get_policy_info(TEST_COMPONENT_ID).await.unwrap();
+        assert!(!info.inherit_all_environment);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grant_and_revoke_config_value() -> Result<()> {
         let manager = create_test_manager().await?;
         manager.load_test_component().await?;
 
+        let err = manager
+            .grant_permission(
+                TEST_COMPONENT_ID,
+                "config",
+                &serde_json::json!({"key": "X"}),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("'value'"));
+
+        manager
+            .grant_permission(
+                TEST_COMPONENT_ID,
+                "config",
+                &serde_json::json!({"key": "ENABLE_BETA_PARSER", "value": "true"}),
+            )
+            .await?;
+        let info = manager.get_policy_info(TEST_COMPONENT_ID).await.unwrap();
+        assert_eq!(info.config["ENABLE_BETA_PARSER"], "true");
+        let template = manager
+            .policy_manager
+            .template_for_component(TEST_COMPONENT_ID)
+            .await;
+        assert_eq!(template.config_vars["ENABLE_BETA_PARSER"], "true");
+
+        manager
+            .revoke_permission(
+                TEST_COMPONENT_ID,
+                "config",
+                &serde_json::json!({"key": "ENABLE_BETA_PARSER"}),
+            )
+            .await?;
+        let template = manager
+            .policy_manager
+            .template_for_component(TEST_COMPONENT_ID)
+            .await;
+        assert!(!template.config_vars.contains_key("ENABLE_BETA_PARSER"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grant_storage_checks_host_path() -> Result<()> {
+        let manager =
+            create_test_manager_with(|builder| builder.with_absolute_storage_paths(true)).await?;
+        let storage_dir = tempfile::tempdir()?;
+        let missing = storage_dir.path().join("out/reports");
+        let uri = format!("fs://{}", missing.display());
+
+        let error = manager
+            .policy_manager
+            .grant_permission(
+                "component",
+                "storage",
+                &serde_json::json!({"uri": uri, "access": ["write"]}),
+            )
+            .await
+            .expect_err("missing path should be rejected");
+        assert!(error.to_string().contains("does not exist"));
+        assert!(!manager.get_component_policy_path("component").exists());
+
+        let error = manager
+            .policy_manager
+            .grant_permission(
+                "component",
+                "storage",
+                &serde_json::json!({"uri": uri, "access": ["read"], "createIfMissing": true}),
+            )
+            .await
+            .expect_err("read-only grants should not create paths");
+        assert!(error.to_string().contains("requires write access"));
+        assert!(!missing.exists());
+
+        manager
+            .policy_manager
+            .grant_permission(
+                "component",
+                "storage",
+                &serde_json::json!({"uri": uri, "access": ["write"], "createIfMissing": true}),
+            )
+            .await?;
+        assert!(missing.is_dir());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&missing)?.permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grant_permission_storage() -> Result<()> {
+        let manager =
+            create_test_manager_with(|builder| builder.with_absolute_storage_paths(true)).await?;
+        manager.load_test_component().await?;
+
         // Grant storage permission
+        std::fs::create_dir_all("/tmp/test")?;
         let details = serde_json::json!({"uri": "fs:///tmp/test", "access": ["read", "write"]});
         manager
             .grant_permission(TEST_COMPONENT_ID, "storage", &details)
@@ -974,10 +2264,56 @@ permissions: {}
     }
 
     #[tokio::test]
-    async fn test_grant_permission_duplicate_prevention() -> Result<()> {
+    async fn test_grant_network_permission_with_timeout() -> Result<()> {
         let manager = create_test_manager().await?;
         manager.load_test_component().await?;
 
+        manager
+            .grant_permission(
+                TEST_COMPONENT_ID,
+                "network",
+                &serde_json::json!({"host": "api.example.com"}),
+            )
+            .await?;
+        manager
+            .grant_permission(
+                TEST_COMPONENT_ID,
+                "network",
+                &serde_json::json!({"host": "api.example.com", "timeout": "5s"}),
+            )
+            .await?;
+
+        let policy_content =
+            tokio::fs::read_to_string(manager.get_component_policy_path(TEST_COMPONENT_ID)).await?;
+        assert_eq!(policy_content.matches("api.example.com").count(), 1);
+
+        let template = manager
+            .policy_manager
+            .template_for_component(TEST_COMPONENT_ID)
+            .await;
+        assert_eq!(
+            template.network_timeouts.get("api.example.com"),
+            Some(&std::time::Duration::from_secs(5))
+        );
+
+        let result = manager
+            .grant_permission(
+                TEST_COMPONENT_ID,
+                "network",
+                &serde_json::json!({"host": "api.example.com", "timeout": "soon"}),
+            )
+            .await;
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grant_permission_duplicate_prevention() -> Result<()> {
+        let manager =
+            create_test_manager_with(|builder| builder.with_absolute_storage_paths(true)).await?;
+        manager.load_test_component().await?;
+
         let network_details = serde_json::json!({"host": "api.example.com"});
         manager
             .grant_permission(TEST_COMPONENT_ID, "network", &network_details)
@@ -994,6 +2330,8 @@ permissions: {}
             .grant_permission(TEST_COMPONENT_ID, "environment", &env_details)
             .await?;
 
+        std::fs::create_dir_all("/tmp/test")?;
+        std::fs::create_dir_all("/tmp/other")?;
         let storage_details = serde_json::json!({"uri": "fs:///tmp/test", "access": ["read"]});
         manager
             .grant_permission(TEST_COMPONENT_ID, "storage", &storage_details)
@@ -1106,11 +2444,64 @@ permissions: {}
     }
 
     #[tokio::test]
-    async fn test_grant_permission_storage_access_merging() -> Result<()> {
+    async fn test_grant_network_hosts_in_bulk() -> Result<()> {
         let manager = create_test_manager().await?;
+        let component = std::env::current_dir()?.join("../component2json/testdata/fetch-rs.wasm");
+        let id = manager
+            .load_component(&format!("file://{}", component.display()))
+            .await?
+            .component_id;
+        manager
+            .grant_permission(
+                &id,
+                "network",
+                &serde_json::json!({"host": "a.example.com"}),
+            )
+            .await?;
+
+        // One invalid host rejects the whole batch
+        let hosts = ["b.example.com".to_string(), String::new()];
+        let error = manager.grant_network_hosts(&id, &hosts).await.unwrap_err();
+        assert!(error.to_string().contains("nothing was granted"));
+        let policy_path = manager.get_component_policy_path(&id);
+        let policy_content = tokio::fs::read_to_string(&policy_path).await?;
+        assert!(!policy_content.contains("b.example.com"));
+
+        let hosts = [
+            "a.example.com".to_string(),
+            "b.example.com".to_string(),
+            "c.example.com".to_string(),
+            "b.example.com".to_string(),
+        ];
+        let results = manager.grant_network_hosts(&id, &hosts).await?;
+        let statuses: Vec<_> = results.iter().map(|r| r.status.clone()).collect();
+        assert_eq!(
+            statuses,
+            [
+                HostGrantStatus::AlreadyGranted,
+                HostGrantStatus::Granted,
+                HostGrantStatus::Granted,
+                HostGrantStatus::AlreadyGranted,
+            ]
+        );
+
+        let policy = PolicyParser::parse_file(&policy_path)?;
+        let allow = policy.permissions.network.unwrap().allow.unwrap();
+        assert_eq!(allow.len(), 3);
+        let template = manager.policy_manager.template_for_component(&id).await;
+        assert!(template.allowed_hosts.contains("c.example.com"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grant_permission_storage_access_merging() -> Result<()> {
+        let manager =
+            create_test_manager_with(|builder| builder.with_absolute_storage_paths(true)).await?;
         manager.load_test_component().await?;
 
         // Grant read access first
+        std::fs::create_dir_all("/tmp/test")?;
         let read_details = serde_json::json!({"uri": "fs:///tmp/test", "access": ["read"]});
         manager
             .grant_permission(TEST_COMPONENT_ID, "storage", &read_details)
@@ -1198,7 +2589,8 @@ permissions: {}
 
     #[tokio::test]
     async fn test_grant_permission_multiple_permissions() -> Result<()> {
-        let manager = create_test_manager().await?;
+        let manager =
+            create_test_manager_with(|builder| builder.with_absolute_storage_paths(true)).await?;
         manager.load_test_component().await?;
 
         // Grant multiple different permissions
@@ -1207,6 +2599,7 @@ permissions: {}
             .grant_permission(TEST_COMPONENT_ID, "network", &network_details)
             .await?;
 
+        std::fs::create_dir_all("/tmp/test")?;
         let storage_details = serde_json::json!({"uri": "fs:///tmp/test", "access": ["read"]});
         manager
             .grant_permission(TEST_COMPONENT_ID, "storage", &storage_details)
@@ -1288,6 +2681,7 @@ permissions:
         let network_rule =
             PermissionRule::Network(NetworkPermission::Host(NetworkHostPermission {
                 host: "example.com".to_string(),
+                ..Default::default()
             }));
         let serialized = serde_json::to_string(&network_rule)?;
         assert!(serialized.contains("example.com"));
@@ -1310,6 +2704,7 @@ permissions:
         let network_perm =
             PermissionRule::Network(NetworkPermission::Host(NetworkHostPermission {
                 host: "example.com".to_string(),
+                ..Default::default()
             }));
         let storage_perm = PermissionRule::Storage(StoragePermission {
             uri: "fs:///tmp".to_string(),
@@ -1336,9 +2731,13 @@ permissions:
         // Test pattern matching works correctly
         let rule = PermissionRule::Network(NetworkPermission::Host(NetworkHostPermission {
             host: "test.com".to_string(),
+            ..Default::default()
         }));
         match rule {
-            PermissionRule::Network(NetworkPermission::Host(NetworkHostPermission { host })) => {
+            PermissionRule::Network(NetworkPermission::Host(NetworkHostPermission {
+                host,
+                ..
+            })) => {
                 assert_eq!(host, "test.com");
             }
             _ => panic!("Expected network permission"),
@@ -1359,6 +2758,7 @@ permissions:
             version: "1.0".to_string(),
             description: Some("Test policy".to_string()),
             permissions: policy::Permissions::default(),
+            config: Default::default(),
         };
 
         // Test adding resource permission
@@ -1371,7 +2771,6 @@ permissions:
         });
 
         manager
-            .manager
             .policy_manager
             .add_resource_permission_to_policy(&mut policy, resource_details)?;
 
@@ -1416,6 +2815,7 @@ permissions:
             version: "1.0".to_string(),
             description: Some("Test policy with memory limits".to_string()),
             permissions: policy::Permissions::default(),
+            config: Default::default(),
         };
 
         // First add memory resource permission
@@ -1428,7 +2828,6 @@ permissions:
         });
 
         manager
-            .manager
             .policy_manager
             .add_resource_permission_to_policy(&mut policy, resource_details.clone())?;
 
@@ -1449,7 +2848,6 @@ permissions:
 
         // Now test removing the memory resource permission
         manager
-            .manager
             .policy_manager
             .remove_resource_permission_from_policy(&mut policy, resource_details)?;
 
@@ -1472,7 +2870,6 @@ permissions:
 
         // Test that we can parse the memory resource permission rule
         let permission_rule = manager
-            .manager
             .policy_manager
             .parse_permission_rule("resource", &memory_details)?;
 
@@ -1492,7 +2889,6 @@ permissions:
 
         // Test validation doesn't fail for resource permissions
         manager
-            .manager
             .policy_manager
             .validate_permission_rule(&permission_rule)?;
 
@@ -0,0 +1,125 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Order in which background loading compiles the component directory.
+//!
+//! Components with a higher priority are compiled first; ties are broken by
+//! the most recently used component, then by ID. A component's priority is,
+//! in order of precedence: the one set with
+//! [`LifecycleManager::set_component_priority`](crate::LifecycleManager::set_component_priority),
+//! the `priority` field in its metadata, and its position in the configured
+//! priority list. Everything else has priority 0.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Priorities of the components in the component directory.
+#[derive(Clone, Default)]
+pub(crate) struct LoadPriorities {
+    configured: Arc<HashMap<String, i32>>,
+    assigned: Arc<RwLock<HashMap<String, i32>>>,
+}
+
+impl LoadPriorities {
+    /// Create priorities from a list of component IDs, highest priority
+    /// first. The listed components get priorities counting down from the
+    /// length of the list to 1.
+    pub(crate) fn new(priority_list: Vec<String>) -> Self {
+        let len = priority_list.len() as i32;
+        let configured = priority_list
+            .into_iter()
+            .enumerate()
+            // Reversed so the first occurrence of a duplicated ID wins
+            .rev()
+            .map(|(index, component_id)| (component_id, len - index as i32))
+            .collect();
+        Self {
+            configured: Arc::new(configured),
+            assigned: Arc::default(),
+        }
+    }
+
+    /// Priority used to order the component.
+    pub(crate) fn get(&self, component_id: &str) -> i32 {
+        self.assigned(component_id)
+            .or_else(|| self.configured.get(component_id).copied())
+            .unwrap_or(0)
+    }
+
+    /// Priority set for the component at runtime or read from its metadata.
+    pub(crate) fn assigned(&self, component_id: &str) -> Option<i32> {
+        self.assigned
+            .read()
+            .expect("priority lock poisoned")
+            .get(component_id)
+            .copied()
+    }
+
+    /// Set or clear the runtime priority of a component.
+    pub(crate) fn assign(&self, component_id: &str, priority: Option<i32>) {
+        let mut assigned = self.assigned.write().expect("priority lock poisoned");
+        match priority {
+            Some(priority) => assigned.insert(component_id.to_string(), priority),
+            None => assigned.remove(component_id),
+        };
+    }
+
+    /// Sort component IDs into loading order.
+    pub(crate) fn sort(
+        &self,
+        component_ids: &mut [String],
+        last_used: impl Fn(&str) -> Option<u64>,
+    ) {
+        component_ids
+            .sort_by_cached_key(|id| (Reverse(self.get(id)), Reverse(last_used(id)), id.clone()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_configured_list_orders_before_unlisted() {
+        let priorities = LoadPriorities::new(ids(&["b", "a"]));
+        assert_eq!(priorities.get("b"), 2);
+        assert_eq!(priorities.get("a"), 1);
+        assert_eq!(priorities.get("c"), 0);
+
+        let mut components = ids(&["c", "a", "b"]);
+        priorities.sort(&mut components, |_| None);
+        assert_eq!(components, ids(&["b", "a", "c"]));
+    }
+
+    #[test]
+    fn test_ties_are_broken_by_last_used() {
+        let priorities = LoadPriorities::default();
+        let mut components = ids(&["never", "old", "recent", "also-never"]);
+        priorities.sort(&mut components, |id| match id {
+            "old" => Some(100),
+            "recent" => Some(200),
+            _ => None,
+        });
+        assert_eq!(components, ids(&["recent", "old", "also-never", "never"]));
+    }
+
+    #[test]
+    fn test_assigned_priority_overrides_configured() {
+        let priorities = LoadPriorities::new(ids(&["a"]));
+        priorities.assign("a", Some(-1));
+        priorities.assign("b", Some(5));
+        assert_eq!(priorities.get("a"), -1);
+
+        let mut components = ids(&["a", "b", "c"]);
+        priorities.sort(&mut components, |_| None);
+        assert_eq!(components, ids(&["b", "c", "a"]));
+
+        priorities.assign("a", None);
+        assert_eq!(priorities.get("a"), 1);
+    }
+}
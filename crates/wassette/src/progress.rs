@@ -0,0 +1,237 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Progress of loading a component: how much of it has been downloaded, and
+//! when it is being compiled.
+//!
+//! A [`ProgressReporter`] passed to
+//! [`LifecycleManager::load_component_with_progress`](crate::LifecycleManager::load_component_with_progress)
+//! is told about each chunk written while an `oci://` or `https://`
+//! component downloads, then once more when compilation starts. Reports
+//! arrive as often as chunks do, so reporters that pass them on throttle
+//! them themselves. [`TerminalProgress`] prints them to stderr for
+//! command-line use.
+
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+
+use serde::Serialize;
+use tokio::io::AsyncWrite;
+
+/// A step in loading a component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum LoadProgress {
+    /// The component is being downloaded
+    Downloading {
+        /// Bytes downloaded so far
+        downloaded: u64,
+        /// Bytes to download, if the source told
+        total: Option<u64>,
+    },
+    /// The component was fetched and is being compiled
+    Compiling,
+}
+
+impl LoadProgress {
+    /// Share of the download completed, from 0 to 100, if its size is known.
+    pub fn percent(&self) -> Option<u64> {
+        match *self {
+            Self::Downloading {
+                downloaded,
+                total: Some(total),
+            } if total > 0 => Some(downloaded.min(total) * 100 / total),
+            _ => None,
+        }
+    }
+}
+
+/// Receives the progress of a component load.
+pub trait ProgressReporter: Send + Sync {
+    /// Called for every step; must not block.
+    fn report(&self, progress: LoadProgress);
+}
+
+/// Prints the progress of a load to stderr, every tenth of the download and
+/// when compilation starts.
+#[derive(Debug, Default)]
+pub struct TerminalProgress {
+    printed_percent: Mutex<Option<u64>>,
+}
+
+impl TerminalProgress {
+    /// A reporter that has printed nothing yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ProgressReporter for TerminalProgress {
+    fn report(&self, progress: LoadProgress) {
+        match progress {
+            LoadProgress::Downloading { downloaded, total } => {
+                let mut printed = self.printed_percent.lock().unwrap();
+                let percent = progress.percent().unwrap_or(0);
+                let due = match *printed {
+                    None => true,
+                    Some(last) => percent >= last + 10 || (percent == 100 && last < 100),
+                };
+                if !due {
+                    return;
+                }
+                *printed = Some(percent);
+                match total {
+                    Some(total) => eprintln!(
+                        "Downloading component: {percent}% ({downloaded} of {total} bytes)"
+                    ),
+                    None => eprintln!("Downloading component..."),
+                }
+            }
+            LoadProgress::Compiling => eprintln!("Compiling component..."),
+        }
+    }
+}
+
+/// Passes progress on to another reporter, if any, keeping the number of
+/// bytes downloaded.
+pub(crate) struct CountingProgress<'a> {
+    inner: Option<&'a dyn ProgressReporter>,
+    downloaded: AtomicU64,
+}
+
+impl<'a> CountingProgress<'a> {
+    pub(crate) fn new(inner: Option<&'a dyn ProgressReporter>) -> Self {
+        Self {
+            inner,
+            downloaded: AtomicU64::new(0),
+        }
+    }
+
+    /// Bytes downloaded so far.
+    pub(crate) fn downloaded(&self) -> u64 {
+        self.downloaded.load(Ordering::Relaxed)
+    }
+}
+
+impl ProgressReporter for CountingProgress<'_> {
+    fn report(&self, progress: LoadProgress) {
+        if let LoadProgress::Downloading { downloaded, .. } = progress {
+            self.downloaded.fetch_max(downloaded, Ordering::Relaxed);
+        }
+        if let Some(inner) = self.inner {
+            inner.report(progress);
+        }
+    }
+}
+
+/// Reports what is written through it as download progress, counting from
+/// `downloaded` towards `total`.
+pub(crate) struct ProgressWriter<'a, W> {
+    inner: W,
+    reporter: Option<&'a dyn ProgressReporter>,
+    downloaded: u64,
+    total: Option<u64>,
+}
+
+impl<'a, W> ProgressWriter<'a, W> {
+    pub(crate) fn new(
+        inner: W,
+        reporter: Option<&'a dyn ProgressReporter>,
+        downloaded: u64,
+        total: Option<u64>,
+    ) -> Self {
+        if let Some(reporter) = reporter {
+            reporter.report(LoadProgress::Downloading { downloaded, total });
+        }
+        Self {
+            inner,
+            reporter,
+            downloaded,
+            total,
+        }
+    }
+
+    /// Bytes counted so far, including the ones the writer started from.
+    pub(crate) fn downloaded(&self) -> u64 {
+        self.downloaded
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for ProgressWriter<'_, W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll {
+            self.downloaded += written as u64;
+            if let Some(reporter) = self.reporter {
+                reporter.report(LoadProgress::Downloading {
+                    downloaded: self.downloaded,
+                    total: self.total,
+                });
+            }
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+
+    #[derive(Default)]
+    pub(crate) struct RecordedProgress(pub(crate) Mutex<Vec<LoadProgress>>);
+
+    impl ProgressReporter for RecordedProgress {
+        fn report(&self, progress: LoadProgress) {
+            self.0.lock().unwrap().push(progress);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_progress_writer_counts_from_offset() {
+        let recorded = RecordedProgress::default();
+        let mut buffer = Vec::new();
+        let mut writer = ProgressWriter::new(&mut buffer, Some(&recorded), 10, Some(20));
+        writer.write_all(b"hello").await.unwrap();
+        writer.write_all(b"world").await.unwrap();
+        assert_eq!(writer.downloaded(), 20);
+        assert_eq!(buffer, b"helloworld");
+
+        let recorded = recorded.0.into_inner().unwrap();
+        assert_eq!(recorded.first().unwrap().percent(), Some(50));
+        assert_eq!(recorded.last().unwrap().percent(), Some(100));
+        assert!(recorded
+            .windows(2)
+            .all(|pair| pair[0].percent() <= pair[1].percent()));
+    }
+
+    #[test]
+    fn test_load_progress_percent() {
+        let downloading = |downloaded, total| LoadProgress::Downloading { downloaded, total };
+        assert_eq!(downloading(3, Some(4)).percent(), Some(75));
+        assert_eq!(downloading(5, Some(4)).percent(), Some(100));
+        assert_eq!(downloading(3, None).percent(), None);
+        assert_eq!(downloading(0, Some(0)).percent(), None);
+        assert_eq!(LoadProgress::Compiling.percent(), None);
+        assert_eq!(
+            serde_json::to_value(downloading(1, Some(2))).unwrap(),
+            serde_json::json!({"stage": "downloading", "downloaded": 1, "total": 2})
+        );
+    }
+}
@@ -0,0 +1,201 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Execution metrics in the Prometheus text exposition format, as served by
+//! [`LifecycleManager::prometheus_metrics`](crate::LifecycleManager::prometheus_metrics).
+//!
+//! Durations are kept in cumulative histograms with the default buckets of
+//! the Prometheus client libraries, and reported in seconds.
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+/// Upper bounds of the histogram buckets, in milliseconds.
+const BUCKET_BOUNDS_MS: [u64; 11] = [5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+/// Durations observed so far, by bucket.
+#[derive(Default)]
+pub(crate) struct Histogram {
+    buckets: [AtomicU64; BUCKET_BOUNDS_MS.len()],
+    count: AtomicU64,
+    sum_ms: AtomicU64,
+}
+
+/// The counts of a [`Histogram`], as written to the usage file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct HistogramRecord {
+    /// Cumulative count of each bucket
+    pub(crate) buckets: Vec<u64>,
+    pub(crate) count: u64,
+    pub(crate) sum_ms: u64,
+}
+
+impl Default for HistogramRecord {
+    fn default() -> Self {
+        Self {
+            buckets: vec![0; BUCKET_BOUNDS_MS.len()],
+            count: 0,
+            sum_ms: 0,
+        }
+    }
+}
+
+impl HistogramRecord {
+    /// The record, or an empty one if it was recorded with other bucket
+    /// bounds, whose counts can not be carried over.
+    pub(crate) fn or_empty(self) -> Self {
+        if self.buckets.len() == BUCKET_BOUNDS_MS.len() {
+            self
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Count a duration, like [`Histogram::observe`] does.
+    pub(crate) fn observe(&mut self, duration_ms: u64) {
+        for (bound, bucket) in BUCKET_BOUNDS_MS.iter().zip(&mut self.buckets) {
+            if duration_ms <= *bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum_ms += duration_ms;
+    }
+}
+
+impl Histogram {
+    pub(crate) fn observe(&self, duration_ms: u64) {
+        for (bound, bucket) in BUCKET_BOUNDS_MS.iter().zip(&self.buckets) {
+            if duration_ms <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_ms.fetch_add(duration_ms, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record(&self) -> HistogramRecord {
+        HistogramRecord {
+            buckets: self
+                .buckets
+                .iter()
+                .map(|bucket| bucket.load(Ordering::Relaxed))
+                .collect(),
+            count: self.count.load(Ordering::Relaxed),
+            sum_ms: self.sum_ms.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Write the `HELP` and `TYPE` lines of a metric family.
+pub(crate) fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// Write one sample of `name` with `labels`.
+pub(crate) fn write_sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: u64) {
+    let _ = writeln!(out, "{name}{} {value}", format_labels(labels));
+}
+
+/// Write the buckets, sum, and count of a histogram sample of `name`.
+pub(crate) fn write_histogram(
+    out: &mut String,
+    name: &str,
+    labels: &[(&str, &str)],
+    histogram: &HistogramRecord,
+) {
+    let bounds = BUCKET_BOUNDS_MS
+        .iter()
+        .map(|bound| seconds(*bound))
+        .chain(["+Inf".to_string()]);
+    let counts = histogram.buckets.iter().chain([&histogram.count]);
+    for (le, count) in bounds.zip(counts) {
+        let mut bucket_labels = labels.to_vec();
+        bucket_labels.push(("le", &le));
+        let _ = writeln!(
+            out,
+            "{name}_bucket{} {count}",
+            format_labels(&bucket_labels)
+        );
+    }
+    let labels = format_labels(labels);
+    let _ = writeln!(out, "{name}_sum{labels} {}", seconds(histogram.sum_ms));
+    let _ = writeln!(out, "{name}_count{labels} {}", histogram.count);
+}
+
+fn seconds(ms: u64) -> String {
+    (ms as f64 / 1000.0).to_string()
+}
+
+fn format_labels(labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let labels: Vec<_> = labels
+        .iter()
+        .map(|(name, value)| format!("{name}=\"{}\"", escape_label_value(value)))
+        .collect();
+    format!("{{{}}}", labels.join(","))
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_is_written_in_seconds() {
+        let histogram = Histogram::default();
+        histogram.observe(3);
+        histogram.observe(40);
+        histogram.observe(20_000);
+
+        let mut out = String::new();
+        write_histogram(
+            &mut out,
+            "calls_seconds",
+            &[("tool", "say \"hi\"")],
+            &histogram.record(),
+        );
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(
+            lines[0],
+            r#"calls_seconds_bucket{tool="say \"hi\"",le="0.005"} 1"#
+        );
+        assert_eq!(
+            lines[3],
+            r#"calls_seconds_bucket{tool="say \"hi\"",le="0.05"} 2"#
+        );
+        assert_eq!(
+            lines[10],
+            r#"calls_seconds_bucket{tool="say \"hi\"",le="10"} 2"#
+        );
+        assert_eq!(
+            lines[11],
+            r#"calls_seconds_bucket{tool="say \"hi\"",le="+Inf"} 3"#
+        );
+        assert_eq!(lines[12], r#"calls_seconds_sum{tool="say \"hi\""} 20.043"#);
+        assert_eq!(lines[13], r#"calls_seconds_count{tool="say \"hi\""} 3"#);
+
+        let mut record = HistogramRecord::default();
+        for duration_ms in [3, 40, 20_000] {
+            record.observe(duration_ms);
+        }
+        assert_eq!(record, histogram.record());
+        let other_bounds = HistogramRecord {
+            buckets: vec![1, 2],
+            count: 2,
+            sum_ms: 9,
+        };
+        assert_eq!(other_bounds.or_empty(), HistogramRecord::default());
+    }
+}
@@ -0,0 +1,434 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Searchable index of components that can be fetched and loaded.
+//!
+//! The default index is the `component-registry.json` file embedded at build
+//! time. Embedders can layer additional sources (JSON files or in-memory
+//! entries) on top of it via [`Registry::add_source`].
+
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// The component registry that ships with Wassette.
+const EMBEDDED_REGISTRY: &str = include_str!("../../../component-registry.json");
+
+static EMBEDDED_ENTRIES: LazyLock<Vec<RegistryEntry>> = LazyLock::new(|| {
+    parse_entries(EMBEDDED_REGISTRY).expect("embedded component registry must be valid JSON")
+});
+
+/// A single component listed in a registry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryEntry {
+    /// Human-readable component name
+    pub name: String,
+    /// Short description of what the component does
+    pub description: String,
+    /// URI the component can be loaded from (e.g. `oci://...`)
+    pub uri: String,
+}
+
+/// Where registry entries are read from.
+#[derive(Debug, Clone)]
+pub enum RegistrySource {
+    /// The registry embedded in the Wassette binary
+    Embedded,
+    /// A JSON file containing an array of entries
+    File(PathBuf),
+    /// Entries supplied directly by the embedder
+    Entries(Vec<RegistryEntry>),
+}
+
+/// An ordered collection of registry entries gathered from one or more sources.
+///
+/// Entries are deduplicated by URI; the first source to list a URI wins.
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    entries: Vec<RegistryEntry>,
+}
+
+impl Registry {
+    /// Create a registry containing only the embedded entries.
+    pub fn embedded() -> Self {
+        Self {
+            entries: EMBEDDED_ENTRIES.clone(),
+        }
+    }
+
+    /// Create a registry from the given sources, in order.
+    pub fn from_sources(sources: impl IntoIterator<Item = RegistrySource>) -> Result<Self> {
+        let mut registry = Self::default();
+        for source in sources {
+            registry.add_source(source)?;
+        }
+        Ok(registry)
+    }
+
+    /// Append the entries from `source` to the registry.
+    pub fn add_source(&mut self, source: RegistrySource) -> Result<()> {
+        let entries = match source {
+            RegistrySource::Embedded => EMBEDDED_ENTRIES.clone(),
+            RegistrySource::File(path) => read_entries_file(&path)?,
+            RegistrySource::Entries(entries) => entries,
+        };
+        self.extend(entries);
+        Ok(())
+    }
+
+    fn extend(&mut self, entries: Vec<RegistryEntry>) {
+        for entry in entries {
+            if !self.entries.iter().any(|e| e.uri == entry.uri) {
+                self.entries.push(entry);
+            }
+        }
+    }
+
+    /// All entries in the registry.
+    pub fn entries(&self) -> &[RegistryEntry] {
+        &self.entries
+    }
+
+    /// Search for entries matching `query`, ranked by relevance.
+    ///
+    /// The query is split on whitespace and an entry matches if any term
+    /// appears in its name, description, or URI (case-insensitive). An empty
+    /// query returns every entry in registry order.
+    pub fn search(&self, query: &str) -> Vec<RegistryEntry> {
+        let query_terms: Vec<String> = query
+            .split_whitespace()
+            .map(|term| term.to_lowercase())
+            .collect();
+
+        if query_terms.is_empty() {
+            return self.entries.clone();
+        }
+
+        let mut scored: Vec<(u32, &RegistryEntry)> = self
+            .entries
+            .iter()
+            .map(|entry| (relevance_score(entry, &query_terms), entry))
+            .filter(|(score, _)| *score > 0)
+            .collect();
+
+        // Stable sort keeps registry order for equally relevant entries
+        scored.sort_by(|a, b| b.0.cmp(&a.0));
+
+        scored.into_iter().map(|(_, entry)| entry.clone()).collect()
+    }
+
+    /// Find an entry by exact name (case-insensitive) or URI.
+    pub fn find(&self, name_or_uri: &str) -> Option<RegistryEntry> {
+        self.entries
+            .iter()
+            .find(|e| e.name.eq_ignore_ascii_case(name_or_uri) || e.uri == name_or_uri)
+            .cloned()
+    }
+
+    /// Resolve a loosely typed component name to a single registry entry.
+    ///
+    /// Resolution tries, in order: an exact name or URI match, a match on the
+    /// name with case, punctuation and whitespace ignored, entries whose
+    /// normalized name contains the query, and finally a relevance
+    /// [`search`](Self::search). The first step that yields exactly one entry
+    /// wins; a step that yields several returns them as
+    /// [`Resolution::Ambiguous`] rather than guessing.
+    pub fn resolve(&self, name: &str) -> Resolution {
+        if let Some(entry) = self.find(name.trim()) {
+            return Resolution::Found(entry);
+        }
+
+        let needle = normalize_name(name);
+        if needle.is_empty() {
+            return Resolution::NotFound;
+        }
+
+        let exact: Vec<RegistryEntry> = self
+            .entries
+            .iter()
+            .filter(|e| normalize_name(&e.name) == needle)
+            .cloned()
+            .collect();
+        if !exact.is_empty() {
+            return Resolution::from_candidates(exact);
+        }
+
+        let partial: Vec<RegistryEntry> = self
+            .entries
+            .iter()
+            .filter(|e| normalize_name(&e.name).contains(&needle))
+            .cloned()
+            .collect();
+        if !partial.is_empty() {
+            return Resolution::from_candidates(partial);
+        }
+
+        Resolution::from_candidates(self.search(name))
+    }
+}
+
+/// Outcome of resolving a component name against a [`Registry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    /// Exactly one entry matched
+    Found(RegistryEntry),
+    /// Several entries matched equally well; the caller should pick one
+    Ambiguous(Vec<RegistryEntry>),
+    /// Nothing in the registry matched
+    NotFound,
+}
+
+impl Resolution {
+    fn from_candidates(mut candidates: Vec<RegistryEntry>) -> Self {
+        match candidates.len() {
+            0 => Resolution::NotFound,
+            1 => Resolution::Found(candidates.remove(0)),
+            _ => Resolution::Ambiguous(candidates),
+        }
+    }
+}
+
+/// Lowercase `name` and strip everything but ASCII letters and digits so that
+/// `"time-server"`, `"Time Server"` and `"timeserver"` compare equal.
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Search the embedded registry for entries matching `query`.
+pub fn search(query: &str) -> Vec<RegistryEntry> {
+    Registry::embedded().search(query)
+}
+
+/// Resolve `name` against the embedded registry.
+pub fn resolve(name: &str) -> Resolution {
+    Registry::embedded().resolve(name)
+}
+
+/// Calculate a relevance score for an entry based on lowercase query terms.
+/// Higher scores indicate better matches; zero means no match.
+pub fn relevance_score(entry: &RegistryEntry, query_terms: &[String]) -> u32 {
+    let name = entry.name.to_lowercase();
+    let description = entry.description.to_lowercase();
+    let uri = entry.uri.to_lowercase();
+
+    let mut score = 0u32;
+
+    for term in query_terms {
+        // Exact name match gets highest score
+        if name == term.as_str() {
+            score += 100;
+        } else if name.starts_with(term) {
+            score += 50;
+        } else if name.contains(term) {
+            score += 20;
+        }
+
+        // Description matches get medium score
+        if description.starts_with(term) {
+            score += 15;
+        } else if description.contains(term) {
+            score += 10;
+        }
+
+        // URI matches get lower score
+        if uri.contains(term) {
+            score += 5;
+        }
+    }
+
+    score
+}
+
+fn parse_entries(json: &str) -> Result<Vec<RegistryEntry>> {
+    serde_json::from_str(json).context("Failed to parse component registry JSON")
+}
+
+fn read_entries_file(path: &Path) -> Result<Vec<RegistryEntry>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read component registry: {}", path.display()))?;
+    parse_entries(&content)
+        .with_context(|| format!("Invalid component registry: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, description: &str, uri: &str) -> RegistryEntry {
+        RegistryEntry {
+            name: name.to_string(),
+            description: description.to_string(),
+            uri: uri.to_string(),
+        }
+    }
+
+    fn sample_registry() -> Registry {
+        Registry::from_sources([RegistrySource::Entries(vec![
+            entry(
+                "Weather Server",
+                "JavaScript weather component",
+                "oci://example.com/weather-js",
+            ),
+            entry(
+                "Time Server",
+                "Rust time component",
+                "oci://example.com/time-rs",
+            ),
+            entry(
+                "Fetch",
+                "Fetch web content",
+                "oci://ghcr.io/microsoft/fetch",
+            ),
+        ])])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_embedded_registry_parses() {
+        let registry = Registry::embedded();
+        assert!(!registry.entries().is_empty());
+    }
+
+    #[test]
+    fn test_search_empty_query_returns_all() {
+        let registry = sample_registry();
+        assert_eq!(registry.search("").len(), 3);
+        assert_eq!(registry.search("   ").len(), 3);
+    }
+
+    #[test]
+    fn test_search_case_insensitive() {
+        let results = sample_registry().search("WEATHER");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "Weather Server");
+    }
+
+    #[test]
+    fn test_search_multi_term_matches_any() {
+        let results = sample_registry().search("weather rust");
+        assert_eq!(results.len(), 2);
+    }
+
+    #[test]
+    fn test_search_matches_uri() {
+        let results = sample_registry().search("ghcr.io");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "Fetch");
+    }
+
+    #[test]
+    fn test_search_ranks_name_matches_first() {
+        let registry = Registry::from_sources([RegistrySource::Entries(vec![
+            entry("Other", "Wraps the fetch tool", "oci://example.com/other"),
+            entry("Fetch", "Fetch web content", "oci://example.com/fetch"),
+        ])])
+        .unwrap();
+
+        let results = registry.search("fetch");
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].name, "Fetch");
+    }
+
+    #[test]
+    fn test_find_by_name_or_uri() {
+        let registry = sample_registry();
+        assert_eq!(
+            registry.find("weather server").unwrap().uri,
+            "oci://example.com/weather-js"
+        );
+        assert_eq!(
+            registry.find("oci://example.com/time-rs").unwrap().name,
+            "Time Server"
+        );
+        assert!(registry.find("missing").is_none());
+    }
+
+    #[test]
+    fn test_resolve_ignores_case_and_punctuation() {
+        let registry = sample_registry();
+        assert_eq!(
+            registry.resolve("time-server"),
+            Resolution::Found(entry(
+                "Time Server",
+                "Rust time component",
+                "oci://example.com/time-rs",
+            ))
+        );
+        assert!(matches!(
+            registry.resolve("oci://ghcr.io/microsoft/fetch"),
+            Resolution::Found(e) if e.name == "Fetch"
+        ));
+    }
+
+    #[test]
+    fn test_resolve_partial_name() {
+        let registry = sample_registry();
+        assert!(matches!(
+            registry.resolve("weather"),
+            Resolution::Found(e) if e.name == "Weather Server"
+        ));
+    }
+
+    #[test]
+    fn test_resolve_ambiguous_returns_candidates() {
+        let registry = sample_registry();
+        match registry.resolve("server") {
+            Resolution::Ambiguous(candidates) => {
+                let names: Vec<_> = candidates.iter().map(|e| e.name.as_str()).collect();
+                assert_eq!(names, ["Weather Server", "Time Server"]);
+            }
+            other => panic!("expected ambiguous resolution, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_resolve_falls_back_to_search() {
+        let registry = sample_registry();
+        assert!(matches!(
+            registry.resolve("javascript"),
+            Resolution::Found(e) if e.name == "Weather Server"
+        ));
+        assert_eq!(registry.resolve("nonexistent"), Resolution::NotFound);
+        assert_eq!(registry.resolve("  "), Resolution::NotFound);
+    }
+
+    #[test]
+    fn test_additional_sources_are_merged_and_deduplicated() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let extra_path = tempdir.path().join("extra.json");
+        std::fs::write(
+            &extra_path,
+            r#"[
+                {"name": "Internal Tool", "description": "Private component", "uri": "oci://internal.example.com/tool:1"},
+                {"name": "Fetch", "description": "Duplicate", "uri": "oci://ghcr.io/microsoft/fetch"}
+            ]"#,
+        )?;
+
+        let mut registry = sample_registry();
+        registry.add_source(RegistrySource::File(extra_path))?;
+
+        assert_eq!(registry.entries().len(), 4);
+        assert_eq!(registry.search("internal")[0].name, "Internal Tool");
+        assert_eq!(
+            registry.find("Fetch").unwrap().description,
+            "Fetch web content"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_file_source_is_an_error() -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let path = tempdir.path().join("bad.json");
+        std::fs::write(&path, "{not json")?;
+
+        let mut registry = Registry::default();
+        assert!(registry.add_source(RegistrySource::File(path)).is_err());
+        Ok(())
+    }
+}
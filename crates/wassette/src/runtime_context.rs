@@ -3,31 +3,304 @@
 
 //! Support utilities for sharing Wasmtime engine and linker state across lifecycle
 //! manager instances.
+//!
+//! The engine interrupts guests by epoch: a background thread advances the
+//! epoch every [`EPOCH_TICK`] for as long as the engine lives, and each call
+//! sets its deadline as a number of ticks. With [`RuntimeOptions::cpu_limits`]
+//! it also meters fuel, so a policy's CPU limit can cap the work of a call;
+//! stores start with unlimited fuel. Metering makes all guest code slower,
+//! so it is off by default.
+//!
+//! How the engine allocates instances and compiles components can be tuned
+//! with [`RuntimeOptions`].
 
-use std::sync::Arc;
+use std::path::PathBuf;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
 
-use anyhow::Result;
-use wasmtime::component::{Component, InstancePre, Linker};
-use wasmtime::Engine;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use wasmtime::component::types::ComponentItem;
+use wasmtime::component::{Component, InstancePre, Linker, LinkerInstance, ResourceType};
+use wasmtime::{Cache, CacheConfig, Engine, InstanceAllocationStrategy, PoolingAllocationConfig};
 use wasmtime_wasi_config::WasiConfig;
 
+use crate::compile_info::CompileInfo;
 use crate::{WasiState, WassetteWasiState};
 
+/// Interval at which the engine's epoch advances; execution deadlines are
+/// enforced to within this interval.
+pub(crate) const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// Number of epoch ticks that cover `timeout`, at least one.
+pub(crate) fn epoch_ticks(timeout: Duration) -> u64 {
+    let ticks = timeout.as_nanos().div_ceil(EPOCH_TICK.as_nanos()).max(1);
+    u64::try_from(ticks).unwrap_or(u64::MAX)
+}
+
+/// Component instances the pooling allocator has room for unless
+/// [`RuntimeOptions::max_instances`] says otherwise; wasmtime's default.
+pub const DEFAULT_POOL_MAX_INSTANCES: u32 = 1000;
+
+/// Core instances, memories, and tables the pool holds per component
+/// instance, since a component is made of several core modules.
+const POOL_SLOTS_PER_INSTANCE: u64 = 4;
+
+/// Address space wasmtime reserves for each linear memory slot of the pool
+/// unless a larger maximum memory size is set, and the guard after it.
+const POOL_MEMORY_RESERVATION: u64 = 4 << 30;
+const POOL_MEMORY_GUARD: u64 = 32 << 20;
+
+/// Address space the pool may reserve; half of what 48-bit virtual
+/// addressing gives a process, so the rest of the host is not starved.
+const POOL_MAX_RESERVATION: u64 = 1 << 46;
+
+/// How the engine allocates the memories and tables of component instances.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstanceAllocation {
+    /// Allocate them when an instance is created and free them after
+    #[default]
+    OnDemand,
+    /// Reserve slots for a fixed number of instances up front and reuse
+    /// them, which makes instantiation faster at the cost of address space
+    Pooling,
+}
+
+/// How much Cranelift optimizes compiled components.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OptimizationLevel {
+    /// No optimizations, for the fastest compilation
+    None,
+    /// Optimize for speed
+    #[default]
+    Speed,
+    /// Optimize for speed and code size
+    SpeedAndSize,
+}
+
+impl OptimizationLevel {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Speed => "speed",
+            Self::SpeedAndSize => "speed_and_size",
+        }
+    }
+}
+
+impl From<OptimizationLevel> for wasmtime::OptLevel {
+    fn from(level: OptimizationLevel) -> Self {
+        match level {
+            OptimizationLevel::None => wasmtime::OptLevel::None,
+            OptimizationLevel::Speed => wasmtime::OptLevel::Speed,
+            OptimizationLevel::SpeedAndSize => wasmtime::OptLevel::SpeedAndSize,
+        }
+    }
+}
+
+/// Tuning of the wasmtime engine components are compiled and run with.
+///
+/// In the config file these are the keys of the `[runtime]` table:
+///
+/// ```toml
+/// [runtime]
+/// allocation = "pooling"
+/// max_instances = 200
+/// opt_level = "speed"
+/// cache_dir = "/var/cache/wassette/wasmtime"
+/// cpu_limits = true
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuntimeOptions {
+    /// How instances are allocated
+    #[serde(default)]
+    pub allocation: InstanceAllocation,
+    /// Component instances that can exist at once under pooling allocation,
+    /// [`DEFAULT_POOL_MAX_INSTANCES`] if not set; instantiating more fails
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_instances: Option<u32>,
+    /// Largest size a linear memory can grow to under pooling allocation,
+    /// in bytes; 4 GiB if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_bytes: Option<u64>,
+    /// How much compiled code is optimized
+    #[serde(default)]
+    pub opt_level: OptimizationLevel,
+    /// Whether a component's functions are compiled on several threads
+    #[serde(default = "default_parallel_compilation")]
+    pub parallel_compilation: bool,
+    /// Directory of wasmtime's compilation cache, which reuses the code of
+    /// components compiled before, across manager instances and restarts;
+    /// no cache if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_dir: Option<PathBuf>,
+    /// Whether the engine meters fuel, which enforcing the CPU limits of
+    /// policies needs; off by default, since it slows down every call
+    #[serde(default)]
+    pub cpu_limits: bool,
+}
+
+fn default_parallel_compilation() -> bool {
+    true
+}
+
+impl Default for RuntimeOptions {
+    fn default() -> Self {
+        Self {
+            allocation: InstanceAllocation::default(),
+            max_instances: None,
+            max_memory_bytes: None,
+            opt_level: OptimizationLevel::default(),
+            parallel_compilation: default_parallel_compilation(),
+            cache_dir: None,
+            cpu_limits: false,
+        }
+    }
+}
+
+impl RuntimeOptions {
+    /// Check that the options can be turned into an engine: pool limits are
+    /// only set for pooling allocation, and the pool fits in the address
+    /// space.
+    pub fn validate(&self) -> Result<()> {
+        if self.allocation == InstanceAllocation::OnDemand {
+            if self.max_instances.is_some() || self.max_memory_bytes.is_some() {
+                bail!("max_instances and max_memory_bytes only apply to pooling allocation");
+            }
+            return Ok(());
+        }
+        let instances = self.max_instances.unwrap_or(DEFAULT_POOL_MAX_INSTANCES);
+        if instances == 0 {
+            bail!("Pooling allocation needs max_instances of at least 1");
+        }
+        if self.max_memory_bytes == Some(0) {
+            bail!("Pooling allocation needs max_memory_bytes of at least 1");
+        }
+        let reservation = self.pool_reservation();
+        if reservation.is_none_or(|bytes| bytes > POOL_MAX_RESERVATION) {
+            bail!(
+                "Pooling allocation of {instances} instances with memories of up to {} bytes \
+                 would reserve more than {POOL_MAX_RESERVATION} bytes of address space; \
+                 lower max_instances or max_memory_bytes",
+                self.pool_memory_size()
+            );
+        }
+        Ok(())
+    }
+
+    fn pool_instances(&self) -> u32 {
+        self.max_instances.unwrap_or(DEFAULT_POOL_MAX_INSTANCES)
+    }
+
+    fn pool_memory_size(&self) -> u64 {
+        self.max_memory_bytes.unwrap_or(POOL_MEMORY_RESERVATION)
+    }
+
+    /// Address space the pool reserves for linear memories, if it can be
+    /// counted in a `u64`.
+    fn pool_reservation(&self) -> Option<u64> {
+        let slot = self
+            .pool_memory_size()
+            .max(POOL_MEMORY_RESERVATION)
+            .checked_add(POOL_MEMORY_GUARD)?;
+        u64::from(self.pool_instances())
+            .checked_mul(POOL_SLOTS_PER_INSTANCE)?
+            .checked_mul(slot)
+    }
+
+    /// Pool limits for the options, which must be valid.
+    fn pooling_config(&self) -> Result<PoolingAllocationConfig> {
+        let instances = self.pool_instances();
+        let slots = u32::try_from(u64::from(instances) * POOL_SLOTS_PER_INSTANCE)
+            .context("max_instances is too large")?;
+        let mut pooling = PoolingAllocationConfig::new();
+        pooling
+            .total_component_instances(instances)
+            .total_stacks(instances)
+            .total_core_instances(slots)
+            .total_memories(slots)
+            .total_tables(slots);
+        if let Some(bytes) = self.max_memory_bytes {
+            pooling
+                .max_memory_size(usize::try_from(bytes).context("max_memory_bytes is too large")?);
+        }
+        Ok(pooling)
+    }
+}
+
+/// Advance the epoch of `engine` every [`EPOCH_TICK`] until it is dropped.
+fn spawn_epoch_ticker(engine: Weak<Engine>) -> Result<()> {
+    std::thread::Builder::new()
+        .name("wassette-epoch".to_string())
+        .spawn(move || {
+            while let Some(engine) = engine.upgrade() {
+                engine.increment_epoch();
+                drop(engine);
+                std::thread::sleep(EPOCH_TICK);
+            }
+        })?;
+    Ok(())
+}
+
 /// Encapsulates Wasmtime engine and linker setup for reuse across the lifecycle manager.
 #[derive(Clone)]
 pub struct RuntimeContext {
     engine: Arc<Engine>,
     linker: Arc<Linker<WassetteWasiState<WasiState>>>,
+    compile_info: Arc<CompileInfo>,
+    meters_fuel: bool,
 }
 
 impl RuntimeContext {
-    /// Build a runtime context with the standard configuration used by Wassette.
-    pub fn initialize() -> Result<Self> {
+    /// Build a runtime context with the configuration used by Wassette,
+    /// tuned by `options`. Fails if the options are invalid or the engine
+    /// cannot reserve what they ask for.
+    pub fn initialize(options: &RuntimeOptions) -> Result<Self> {
+        options.validate()?;
         let mut config = wasmtime::Config::new();
         config.wasm_component_model(true);
         config.async_support(true);
+        config.cranelift_opt_level(options.opt_level.into());
+        config.epoch_interruption(true);
+        config.consume_fuel(options.cpu_limits);
+        config.parallel_compilation(options.parallel_compilation);
+        if options.allocation == InstanceAllocation::Pooling {
+            config.allocation_strategy(InstanceAllocationStrategy::Pooling(
+                options.pooling_config()?,
+            ));
+        }
+        if let Some(cache_dir) = &options.cache_dir {
+            let mut cache_config = CacheConfig::new();
+            cache_config.with_directory(cache_dir);
+            let cache = Cache::new(cache_config).with_context(|| {
+                format!(
+                    "Failed to set up the compilation cache in {}",
+                    cache_dir.display()
+                )
+            })?;
+            config.cache(Some(cache));
+        }
+        let engine_flags = vec![
+            "wasm_component_model=true".to_string(),
+            "async_support=true".to_string(),
+            format!("cranelift_opt_level={}", options.opt_level.as_str()),
+            "epoch_interruption=true".to_string(),
+            format!("consume_fuel={}", options.cpu_limits),
+        ];
 
-        let engine = Arc::new(Engine::new(&config)?);
+        let engine = Arc::new(
+            Engine::new(&config).with_context(|| match options.allocation {
+                InstanceAllocation::OnDemand => "Failed to create the wasmtime engine".to_string(),
+                InstanceAllocation::Pooling => format!(
+                    "Failed to create the wasmtime engine with a pool of {} instances",
+                    options.pool_instances()
+                ),
+            })?,
+        );
+        spawn_epoch_ticker(Arc::downgrade(&engine))?;
+        let compile_info = Arc::new(CompileInfo::for_engine(&engine, engine_flags)?);
 
         let mut linker = Linker::new(engine.as_ref());
         wasmtime_wasi::p2::add_to_linker_async(&mut linker)?;
@@ -36,20 +309,129 @@ impl RuntimeContext {
             &mut linker,
             |h: &mut WassetteWasiState<WasiState>| WasiConfig::from(&h.inner.wasi_config_vars),
         )?;
+        crate::limits::add_to_linker(&mut linker)?;
+        crate::broker::add_to_linker(&mut linker)?;
 
         Ok(Self {
             engine,
             linker: Arc::new(linker),
+            compile_info,
+            meters_fuel: options.cpu_limits,
         })
     }
 
+    /// Whether stores of this engine meter fuel, see
+    /// [`RuntimeOptions::cpu_limits`].
+    pub fn meters_fuel(&self) -> bool {
+        self.meters_fuel
+    }
+
+    /// The toolchain and flags components are compiled with by this engine.
+    pub fn compile_info(&self) -> &CompileInfo {
+        &self.compile_info
+    }
+
     /// Produce a cached `InstancePre` handle for the provided component using
-    /// the shared linker configuration.
+    /// the shared linker configuration. Fails naming every import the linker
+    /// does not provide, rather than only the first.
     pub fn instantiate_pre(
         &self,
         component: &Component,
     ) -> wasmtime::Result<InstancePre<WassetteWasiState<WasiState>>> {
-        self.linker.instantiate_pre(component)
+        self.linker.instantiate_pre(component).map_err(|error| {
+            let unsupported = self.unsupported_imports(component);
+            if unsupported.is_empty() {
+                return error;
+            }
+            error.context(format!(
+                "component imports {} which this runtime does not provide",
+                unsupported.join(", ")
+            ))
+        })
+    }
+
+    /// The component's imports, each marked with whether the linker
+    /// provides it.
+    pub fn component_imports(&self, component: &Component) -> Vec<ComponentImport> {
+        let unsupported = self.unsupported_imports(component);
+        component
+            .component_type()
+            .imports(self.engine.as_ref())
+            .map(|(name, _)| ComponentImport {
+                name: name.to_string(),
+                provided: !unsupported.iter().any(|missing| missing == name),
+            })
+            .collect()
+    }
+
+    /// The imports of the component the linker does not provide, or provides
+    /// with a different type.
+    ///
+    /// The linker is the only record of what the runtime provides, and it
+    /// reports one missing import at a time, so each one it reports is
+    /// stubbed out in a copy of the linker until the component type checks.
+    fn unsupported_imports(&self, component: &Component) -> Vec<String> {
+        let engine = self.engine.as_ref();
+        let component_type = component.component_type();
+        let mut linker = self.linker.as_ref().clone();
+        linker.allow_shadowing(true);
+        let mut unsupported = Vec::new();
+        while let Err(error) = linker.substituted_component_type(component) {
+            let message = format!("{error:#}");
+            let Some((name, item)) = component_type
+                .imports(engine)
+                .find(|(name, _)| message.contains(&format!("`{name}`")))
+            else {
+                break;
+            };
+            if unsupported.iter().any(|missing| missing == name)
+                || stub_import(&mut linker.root(), engine, name, &item).is_err()
+            {
+                break;
+            }
+            unsupported.push(name.to_string());
+        }
+        unsupported
+    }
+}
+
+/// An interface or function a component imports.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComponentImport {
+    /// Name of the import, e.g. `wasi:http/outgoing-handler@0.2.0`
+    pub name: String,
+    /// Whether this runtime provides the import
+    pub provided: bool,
+}
+
+/// Define `item` in `linker` as functions that fail when called.
+fn stub_import<T: 'static>(
+    linker: &mut LinkerInstance<'_, T>,
+    engine: &Engine,
+    name: &str,
+    item: &ComponentItem,
+) -> Result<()> {
+    match item {
+        ComponentItem::ComponentFunc(_) => {
+            let import = name.to_string();
+            linker.func_new(name, move |_, _, _| {
+                bail!("import `{import}` is not provided by this runtime")
+            })
+        }
+        ComponentItem::ComponentInstance(instance) => {
+            let mut linker = linker.instance(name)?;
+            for (export, item) in instance.exports(engine) {
+                stub_import(&mut linker, engine, export, &item)?;
+            }
+            Ok(())
+        }
+        ComponentItem::Resource(_) => {
+            linker.resource(name, ResourceType::host::<()>(), |_, _| Ok(()))
+        }
+        ComponentItem::CoreFunc(_)
+        | ComponentItem::Module(_)
+        | ComponentItem::Component(_)
+        | ComponentItem::Type(_) => Ok(()),
     }
 }
 
@@ -72,3 +454,101 @@ impl std::ops::Deref for RuntimeContext {
         self.engine.as_ref()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unsupported_imports_are_named() -> Result<()> {
+        let runtime = RuntimeContext::initialize(&RuntimeOptions::default())?;
+        let component = Component::new(
+            &runtime,
+            r#"(component
+                (import "wasi:cli/environment@0.2.0" (instance
+                    (export "get-arguments" (func (result (list string))))))
+                (import "acme:storage/kv@1.0.0" (instance
+                    (export "get" (func (param "key" string) (result string)))))
+                (import "custom-hook" (func))
+            )"#,
+        )?;
+
+        let imports = runtime.component_imports(&component);
+        assert_eq!(
+            imports
+                .iter()
+                .map(|import| (import.name.as_str(), import.provided))
+                .collect::<Vec<_>>(),
+            [
+                ("wasi:cli/environment@0.2.0", true),
+                ("acme:storage/kv@1.0.0", false),
+                ("custom-hook", false),
+            ]
+        );
+
+        let Err(error) = runtime.instantiate_pre(&component) else {
+            panic!("instantiating a component with unsupported imports should fail");
+        };
+        assert_eq!(
+            error.to_string(),
+            "component imports acme:storage/kv@1.0.0, custom-hook which this runtime does not provide"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_runtime_options_are_validated() -> Result<()> {
+        let pooling = |max_instances, max_memory_bytes| RuntimeOptions {
+            allocation: InstanceAllocation::Pooling,
+            max_instances,
+            max_memory_bytes,
+            ..Default::default()
+        };
+        pooling(Some(8), Some(64 << 20)).validate()?;
+        pooling(None, None).validate()?;
+
+        let error = pooling(Some(1000), Some(1 << 40)).validate().unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Pooling allocation of 1000 instances with memories of up to 1099511627776 bytes"
+        ));
+        assert!(pooling(Some(0), None).validate().is_err());
+        let on_demand = RuntimeOptions {
+            max_instances: Some(8),
+            ..Default::default()
+        };
+        assert_eq!(
+            on_demand.validate().unwrap_err().to_string(),
+            "max_instances and max_memory_bytes only apply to pooling allocation"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_engine_is_built_from_runtime_options() -> Result<()> {
+        let cache_dir = tempfile::tempdir()?;
+        let options = RuntimeOptions {
+            allocation: InstanceAllocation::Pooling,
+            max_instances: Some(4),
+            max_memory_bytes: Some(64 << 20),
+            opt_level: OptimizationLevel::None,
+            parallel_compilation: false,
+            cache_dir: Some(cache_dir.path().to_path_buf()),
+            cpu_limits: true,
+        };
+        let runtime = RuntimeContext::initialize(&options)?;
+        assert!(runtime
+            .compile_info()
+            .engine_flags
+            .contains(&"cranelift_opt_level=none".to_string()));
+        assert!(runtime.meters_fuel());
+        assert!(!RuntimeContext::initialize(&RuntimeOptions::default())?.meters_fuel());
+        assert_ne!(
+            runtime.compile_info().fingerprint(),
+            RuntimeContext::initialize(&RuntimeOptions::default())?
+                .compile_info()
+                .fingerprint()
+        );
+        Component::new(&runtime, "(component)")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,286 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Host-managed scratch directories.
+//!
+//! A policy that sets `storage.scratch` gets a directory of its own under
+//! the scratch root, preopened read-write at `/tmp` (or the configured guest
+//! path), so a component can write intermediate files without a grant for a
+//! real host path. The directory is emptied once no call of the component is
+//! running, unless the policy sets `persist`, and removed when the component
+//! is unloaded. A call that leaves more than `maxBytes` in it fails and the
+//! directory is emptied.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Context, Result};
+use policy::PolicyDocument;
+use serde::Serialize;
+use tracing::{debug, warn};
+
+/// Name of the default scratch root inside the component directory.
+pub const SCRATCH_DIR: &str = "scratch";
+
+/// The scratch directory a component's policy asks for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScratchDir {
+    /// Directory on the host
+    pub host_path: PathBuf,
+    /// Path the component sees it at
+    pub guest_path: String,
+    /// Largest total size of the files in it, in bytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<u64>,
+    /// Whether its contents are kept between calls
+    pub persist: bool,
+}
+
+impl ScratchDir {
+    /// Create the directory, accessible only to the current user, if it does
+    /// not exist yet.
+    pub(crate) fn ensure_exists(&self) -> Result<()> {
+        let mut builder = std::fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder.create(&self.host_path).with_context(|| {
+            format!(
+                "Failed to create scratch directory '{}'",
+                self.host_path.display()
+            )
+        })
+    }
+}
+
+/// The scratch directory of `component_id` under `root`, if its policy
+/// enables one.
+pub(crate) fn scratch_dir_from_policy(
+    policy: &PolicyDocument,
+    root: &Path,
+    component_id: &str,
+) -> Option<ScratchDir> {
+    let scratch = policy.permissions.storage.as_ref()?.scratch.as_ref()?;
+    scratch.enabled.then(|| ScratchDir {
+        host_path: root.join(component_id),
+        guest_path: scratch.guest_path().to_string(),
+        max_bytes: scratch.max_bytes,
+        persist: scratch.persist,
+    })
+}
+
+/// Tracks the calls using each component's scratch directory, so it is
+/// only emptied when none is running.
+#[derive(Clone)]
+pub(crate) struct ScratchDirs {
+    root: PathBuf,
+    in_use: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+/// A call using a scratch directory, counted until dropped.
+pub(crate) struct ScratchUse {
+    component_id: String,
+    in_use: Arc<Mutex<HashMap<String, usize>>>,
+    released: bool,
+}
+
+impl ScratchUse {
+    /// Stop counting the call; true if no other call is using the directory.
+    fn release(mut self) -> bool {
+        self.released = true;
+        release(&self.in_use, &self.component_id)
+    }
+}
+
+impl Drop for ScratchUse {
+    fn drop(&mut self) {
+        if !self.released {
+            release(&self.in_use, &self.component_id);
+        }
+    }
+}
+
+fn release(in_use: &Mutex<HashMap<String, usize>>, component_id: &str) -> bool {
+    let mut in_use = in_use.lock().expect("scratch lock poisoned");
+    match in_use.get_mut(component_id) {
+        Some(count) if *count > 1 => {
+            *count -= 1;
+            false
+        }
+        _ => {
+            in_use.remove(component_id);
+            true
+        }
+    }
+}
+
+impl ScratchDirs {
+    pub(crate) fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            in_use: Arc::default(),
+        }
+    }
+
+    /// Count a call of `component_id` as using its scratch directory.
+    pub(crate) fn enter(&self, component_id: &str) -> ScratchUse {
+        *self
+            .in_use
+            .lock()
+            .expect("scratch lock poisoned")
+            .entry(component_id.to_string())
+            .or_default() += 1;
+        ScratchUse {
+            component_id: component_id.to_string(),
+            in_use: Arc::clone(&self.in_use),
+            released: false,
+        }
+    }
+
+    /// Finish a call that used `scratch`: fail it if the directory outgrew
+    /// its limit, and empty the directory when it went over the limit or,
+    /// unless it persists, when no other call is using it.
+    pub(crate) async fn finish<T>(
+        &self,
+        scratch: &ScratchDir,
+        scratch_use: ScratchUse,
+        result: Result<T>,
+    ) -> Result<T> {
+        let component_id = scratch_use.component_id.clone();
+        let last = scratch_use.release();
+        let over_limit = match scratch.max_bytes {
+            Some(max_bytes) => {
+                let used = directory_size(&scratch.host_path).await;
+                (used > max_bytes).then_some((used, max_bytes))
+            }
+            None => None,
+        };
+        if over_limit.is_some() || (last && !scratch.persist) {
+            if let Err(error) = empty_directory(&scratch.host_path).await {
+                warn!(%component_id, error = %format!("{error:#}"), "Failed to empty scratch directory");
+            }
+        }
+        if let Some((used, max_bytes)) = over_limit {
+            bail!(
+                "Component {component_id} wrote {used} bytes to its scratch directory {}, over the limit of {max_bytes} bytes; the directory was emptied",
+                scratch.guest_path
+            );
+        }
+        result
+    }
+
+    /// Remove the scratch directory of `component_id`, if it has one.
+    pub(crate) async fn remove(&self, component_id: &str) {
+        let path = self.root.join(component_id);
+        match tokio::fs::remove_dir_all(&path).await {
+            Ok(()) => debug!(%component_id, path = %path.display(), "Removed scratch directory"),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+            Err(error) => {
+                warn!(%component_id, path = %path.display(), %error, "Failed to remove scratch directory")
+            }
+        }
+    }
+}
+
+/// Total size of the files below `path`; zero if it does not exist.
+pub(crate) async fn directory_size(path: &Path) -> u64 {
+    let mut total = 0;
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+            continue;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let Ok(metadata) = entry.metadata().await else {
+                continue;
+            };
+            if metadata.is_dir() {
+                pending.push(entry.path());
+            } else {
+                total += metadata.len();
+            }
+        }
+    }
+    total
+}
+
+/// Remove everything inside `path`, keeping the directory itself.
+async fn empty_directory(path: &Path) -> Result<()> {
+    let mut entries = match tokio::fs::read_dir(path).await {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(error) => return Err(error.into()),
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_dir() {
+            tokio::fs::remove_dir_all(entry.path()).await?;
+        } else {
+            tokio::fs::remove_file(entry.path()).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn scratch(root: &Path, max_bytes: Option<u64>, persist: bool) -> ScratchDir {
+        ScratchDir {
+            host_path: root.join("fetch"),
+            guest_path: "/tmp".to_string(),
+            max_bytes,
+            persist,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scratch_is_emptied_after_the_last_call() -> Result<()> {
+        let root = TempDir::new()?;
+        let dirs = ScratchDirs::new(root.path().to_path_buf());
+        let scratch = scratch(root.path(), None, false);
+        scratch.ensure_exists()?;
+
+        let first = dirs.enter("fetch");
+        let second = dirs.enter("fetch");
+        std::fs::write(scratch.host_path.join("a.txt"), "hello")?;
+        dirs.finish(&scratch, first, Ok(())).await?;
+        assert!(scratch.host_path.join("a.txt").exists());
+        dirs.finish(&scratch, second, Ok(())).await?;
+        assert!(!scratch.host_path.join("a.txt").exists());
+        assert!(scratch.host_path.exists());
+
+        let persistent = ScratchDir {
+            persist: true,
+            ..scratch.clone()
+        };
+        std::fs::create_dir(scratch.host_path.join("sub"))?;
+        dirs.finish(&persistent, dirs.enter("fetch"), Ok(()))
+            .await?;
+        assert!(scratch.host_path.join("sub").exists());
+
+        dirs.remove("fetch").await;
+        assert!(!scratch.host_path.exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_call_over_the_limit_fails() -> Result<()> {
+        let root = TempDir::new()?;
+        let dirs = ScratchDirs::new(root.path().to_path_buf());
+        let scratch = scratch(root.path(), Some(4), true);
+        scratch.ensure_exists()?;
+        std::fs::write(scratch.host_path.join("big.bin"), [0u8; 8])?;
+
+        let error = dirs
+            .finish(&scratch, dirs.enter("fetch"), Ok(()))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("over the limit of 4 bytes"));
+        assert_eq!(directory_size(&scratch.host_path).await, 0);
+        Ok(())
+    }
+}
@@ -19,6 +19,8 @@ use anyhow::{anyhow, Context, Result};
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
+use crate::path_guard::validate_component_id;
+
 /// Cache entry for component secrets
 #[derive(Debug, Clone)]
 pub struct SecretCache {
@@ -102,6 +104,7 @@ impl SecretsManager {
         &self,
         component_id: &str,
     ) -> Result<HashMap<String, String>> {
+        validate_component_id(component_id)?;
         let secrets_path = self.get_component_secrets_path(component_id);
 
         // Check if file exists
@@ -181,6 +184,7 @@ impl SecretsManager {
         component_id: &str,
         secrets: &[(String, String)],
     ) -> Result<()> {
+        validate_component_id(component_id)?;
         self.ensure_secrets_dir().await?;
 
         let secrets_path = self.get_component_secrets_path(component_id);
@@ -230,6 +234,7 @@ impl SecretsManager {
         component_id: &str,
         keys: &[String],
     ) -> Result<()> {
+        validate_component_id(component_id)?;
         let secrets_path = self.get_component_secrets_path(component_id);
 
         if !secrets_path.exists() {
@@ -531,7 +536,7 @@ permissions:
         let loaded_secrets = manager.load_component_secrets("test").await?;
 
         // Test precedence
-        let result = extract_env_vars(&policy, &env_vars, Some(&loaded_secrets))?;
+        let result = extract_env_vars(&policy, &env_vars, Some(&loaded_secrets), false)?;
 
         // SECRET_KEY should come from env (highest precedence)
         assert_eq!(result.get("SECRET_KEY"), Some(&"from_env".to_string()));
@@ -0,0 +1,947 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Verification of component signatures before a component is installed.
+//!
+//! Signatures are checked against the public keys of a [`SignaturePolicy`],
+//! each trusted under an identity such as the publisher's email address.
+//! Keys are ECDSA P-256 public keys in PEM, as `cosign generate-key-pair`
+//! writes them. For `oci://` references the signature is the one
+//! `cosign sign --key` attaches to the registry under the
+//! `sha256-<digest>.sig` tag, and the component is then pulled by the digest
+//! the signature covers, so a tag moved in between is not loaded. For
+//! `file://` and `https://` components it is a detached signature at the
+//! component's URI with `.sig` appended, as `cosign sign-blob --key` writes
+//! it.
+//!
+//! An `oci://` component may instead be signed keylessly, by `cosign sign`
+//! with a short-lived Fulcio certificate recorded in the Rekor transparency
+//! log. Such a signature is trusted by the [`TrustedIdentity`] the
+//! certificate was issued to. The certificate chain and the Rekor bundle
+//! are verified by sigstore-rs against the policy's [`KeylessTrustRoot`]:
+//! the certificate must chain to one of its Fulcio certificates and have
+//! been valid when the signature was logged, and the bundle's signed entry
+//! timestamp must be signed by its Rekor key. Like cosign's offline
+//! verification, the bundle is trusted on that timestamp; no inclusion
+//! proof is fetched from Rekor.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use oci_client::secrets::RegistryAuth;
+use oci_client::Reference;
+use p256::ecdsa::signature::Verifier;
+use p256::ecdsa::{Signature, VerifyingKey};
+use p256::pkcs8::{DecodePublicKey, EncodePublicKey};
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::CertificateDer;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sigstore::cosign::signature_layers::CertificateSubject;
+use sigstore::cosign::verification_constraint::VerificationConstraint;
+use sigstore::cosign::{verify_constraints, ClientBuilder, CosignCapabilities, SignatureLayer};
+use sigstore::registry::{Auth, OciReference};
+use sigstore::trust::ManualTrustRoot;
+use url::Url;
+
+/// Annotation of a cosign signature layer holding the base64 signature of
+/// the layer's payload.
+const COSIGN_SIGNATURE_ANNOTATION: &str = "dev.cosignproject.cosign/signature";
+
+/// Annotation of a cosign signature layer holding the Fulcio certificate of
+/// a keyless signature.
+const COSIGN_CERTIFICATE_ANNOTATION: &str = "dev.sigstore.cosign/certificate";
+
+/// Appended to the path of a `file://` or `https://` component URI to find
+/// its detached signature.
+pub const SIGNATURE_SUFFIX: &str = ".sig";
+
+/// What happens to components whose signature does not verify.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureMode {
+    /// Signatures are not checked
+    #[default]
+    Disabled,
+    /// Signatures are checked, and components that fail are loaded with a
+    /// warning
+    WarnOnly,
+    /// Components that fail are not loaded
+    Enforce,
+}
+
+/// A public key trusted to sign components, and the identity it belongs to.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "TrustedKeyFile", into = "TrustedKeyFile")]
+pub struct TrustedKey {
+    identity: String,
+    pem: String,
+    key: VerifyingKey,
+}
+
+impl TrustedKey {
+    /// Trust the ECDSA P-256 public key in `pem` for `identity`.
+    pub fn from_pem(identity: impl Into<String>, pem: impl Into<String>) -> Result<Self> {
+        let identity = identity.into();
+        let pem = pem.into();
+        let key = VerifyingKey::from_public_key_pem(pem.trim())
+            .map_err(|error| anyhow::anyhow!("{error}"))
+            .with_context(|| format!("Invalid ECDSA P-256 public key for '{identity}'"))?;
+        Ok(Self { identity, pem, key })
+    }
+
+    /// Trust the public key in the PEM file at `path` for `identity`.
+    pub fn from_pem_file(identity: impl Into<String>, path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let pem = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read public key {}", path.display()))?;
+        Self::from_pem(identity, pem)
+    }
+
+    /// Who the key belongs to.
+    pub fn identity(&self) -> &str {
+        &self.identity
+    }
+
+    fn verifies(&self, payload: &[u8], signature: &Signature) -> bool {
+        self.key.verify(payload, signature).is_ok()
+    }
+}
+
+impl fmt::Debug for TrustedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrustedKey")
+            .field("identity", &self.identity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A trusted key as written in the config file: the PEM inline, or the path
+/// of a PEM file.
+#[derive(Serialize, Deserialize)]
+struct TrustedKeyFile {
+    identity: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    public_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    public_key_file: Option<PathBuf>,
+}
+
+impl TryFrom<TrustedKeyFile> for TrustedKey {
+    type Error = anyhow::Error;
+
+    fn try_from(file: TrustedKeyFile) -> Result<Self> {
+        match (file.public_key, file.public_key_file) {
+            (Some(pem), None) => Self::from_pem(file.identity, pem),
+            (None, Some(path)) => Self::from_pem_file(file.identity, path),
+            _ => bail!(
+                "Trusted key '{}' needs exactly one of public_key and public_key_file",
+                file.identity
+            ),
+        }
+    }
+}
+
+impl From<TrustedKey> for TrustedKeyFile {
+    fn from(key: TrustedKey) -> Self {
+        Self {
+            identity: key.identity,
+            public_key: Some(key.pem),
+            public_key_file: None,
+        }
+    }
+}
+
+/// An identity trusted to sign components keylessly, as named in the
+/// Fulcio certificate of the signature.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustedIdentity {
+    /// Email address or URI the certificate was issued to, such as the
+    /// workflow that signed a release
+    pub subject: String,
+    /// OIDC issuer that vouched for the subject, such as
+    /// `https://token.actions.githubusercontent.com`
+    pub issuer: String,
+}
+
+impl TrustedIdentity {
+    /// Trust certificates issued to `subject` on the word of `issuer`.
+    pub fn new(subject: impl Into<String>, issuer: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            issuer: issuer.into(),
+        }
+    }
+}
+
+/// A verified keyless signature layer satisfies the identity when its
+/// certificate was issued to the identity, and the signature was made with
+/// the certificate's key.
+impl VerificationConstraint for TrustedIdentity {
+    fn verify(&self, layer: &SignatureLayer) -> sigstore::errors::Result<bool> {
+        let (Some(certificate), Some(signature)) = (&layer.certificate_signature, &layer.signature)
+        else {
+            return Ok(false);
+        };
+        let (CertificateSubject::Email(subject) | CertificateSubject::Uri(subject)) =
+            &certificate.subject;
+        Ok(*subject == self.subject
+            && certificate.issuer.as_deref() == Some(self.issuer.as_str())
+            && certificate
+                .verification_key
+                .verify_signature(
+                    sigstore::crypto::Signature::Base64Encoded(signature.as_bytes()),
+                    &layer.raw_data,
+                )
+                .is_ok())
+    }
+}
+
+impl fmt::Display for TrustedIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (issued by {})", self.subject, self.issuer)
+    }
+}
+
+/// What keyless signatures are verified against: the Fulcio certificates
+/// a signing certificate must chain to, and the public key of the Rekor log
+/// it must be recorded in.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "KeylessTrustRootFile", into = "KeylessTrustRootFile")]
+pub struct KeylessTrustRoot {
+    fulcio_pem: String,
+    fulcio_certificates: Vec<CertificateDer<'static>>,
+    rekor_pem: String,
+    rekor_key: Vec<u8>,
+    rekor_log_id: String,
+}
+
+impl KeylessTrustRoot {
+    /// Trust the PEM certificates in `fulcio_certificates`, and the Rekor
+    /// log whose ECDSA P-256 public key is `rekor_public_key`.
+    pub fn from_pem(
+        fulcio_certificates: impl Into<String>,
+        rekor_public_key: impl Into<String>,
+    ) -> Result<Self> {
+        let fulcio_pem = fulcio_certificates.into();
+        let fulcio_certificates = CertificateDer::pem_slice_iter(fulcio_pem.as_bytes())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|error| anyhow!("{error}"))
+            .context("Invalid Fulcio certificates")?;
+        if fulcio_certificates.is_empty() {
+            bail!("Invalid Fulcio certificates: no certificate found");
+        }
+        let rekor_pem = rekor_public_key.into();
+        let rekor_key = VerifyingKey::from_public_key_pem(rekor_pem.trim())
+            .and_then(|key| key.to_public_key_der())
+            .map_err(|error| anyhow!("{error}"))
+            .context("Invalid ECDSA P-256 public key for Rekor")?
+            .into_vec();
+        let rekor_log_id = hex::encode(Sha256::digest(&rekor_key));
+        let root = Self {
+            fulcio_pem,
+            fulcio_certificates,
+            rekor_pem,
+            rekor_key,
+            rekor_log_id,
+        };
+        root.client().context("Invalid Fulcio certificates")?;
+        Ok(root)
+    }
+
+    /// Trust the Fulcio certificates and the Rekor public key in the PEM
+    /// files at the given paths.
+    pub fn from_pem_files(
+        fulcio_certificates: impl Into<PathBuf>,
+        rekor_public_key: impl Into<PathBuf>,
+    ) -> Result<Self> {
+        let fulcio_path = fulcio_certificates.into();
+        let rekor_path = rekor_public_key.into();
+        let fulcio_pem = std::fs::read_to_string(&fulcio_path).with_context(|| {
+            format!(
+                "Failed to read Fulcio certificates {}",
+                fulcio_path.display()
+            )
+        })?;
+        let rekor_pem = std::fs::read_to_string(&rekor_path)
+            .with_context(|| format!("Failed to read Rekor public key {}", rekor_path.display()))?;
+        Self::from_pem(fulcio_pem, rekor_pem)
+    }
+
+    /// A cosign client that trusts only signature layers whose certificate
+    /// chains to the Fulcio certificates, and whose Rekor bundle is signed
+    /// by the Rekor key.
+    fn client(&self) -> Result<sigstore::cosign::Client> {
+        let trust_root = ManualTrustRoot {
+            fulcio_certs: self.fulcio_certificates.clone(),
+            rekor_keys: BTreeMap::from([(self.rekor_log_id.clone(), self.rekor_key.clone())]),
+            ctfe_keys: BTreeMap::new(),
+        };
+        Ok(ClientBuilder::default()
+            .with_trust_repository(&trust_root)?
+            .build()?)
+    }
+}
+
+impl fmt::Debug for KeylessTrustRoot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeylessTrustRoot")
+            .field("fulcio_certificates", &self.fulcio_certificates.len())
+            .field("rekor_log_id", &self.rekor_log_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A keyless trust root as written in the config file: each part inline in
+/// PEM, or the path of a PEM file.
+#[derive(Serialize, Deserialize)]
+struct KeylessTrustRootFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fulcio_certificates: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fulcio_certificates_file: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rekor_public_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rekor_public_key_file: Option<PathBuf>,
+}
+
+impl TryFrom<KeylessTrustRootFile> for KeylessTrustRoot {
+    type Error = anyhow::Error;
+
+    fn try_from(file: KeylessTrustRootFile) -> Result<Self> {
+        let fulcio_pem = match (file.fulcio_certificates, file.fulcio_certificates_file) {
+            (Some(pem), None) => pem,
+            (None, Some(path)) => std::fs::read_to_string(&path).with_context(|| {
+                format!("Failed to read Fulcio certificates {}", path.display())
+            })?,
+            _ => bail!(
+                "The keyless trust root needs exactly one of fulcio_certificates and fulcio_certificates_file"
+            ),
+        };
+        let rekor_pem = match (file.rekor_public_key, file.rekor_public_key_file) {
+            (Some(pem), None) => pem,
+            (None, Some(path)) => std::fs::read_to_string(&path).with_context(|| {
+                format!("Failed to read Rekor public key {}", path.display())
+            })?,
+            _ => bail!(
+                "The keyless trust root needs exactly one of rekor_public_key and rekor_public_key_file"
+            ),
+        };
+        Self::from_pem(fulcio_pem, rekor_pem)
+    }
+}
+
+impl From<KeylessTrustRoot> for KeylessTrustRootFile {
+    fn from(root: KeylessTrustRoot) -> Self {
+        Self {
+            fulcio_certificates: Some(root.fulcio_pem),
+            fulcio_certificates_file: None,
+            rekor_public_key: Some(root.rekor_pem),
+            rekor_public_key_file: None,
+        }
+    }
+}
+
+/// Whether component signatures are checked, and whose signatures are
+/// trusted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignaturePolicy {
+    /// What happens to components whose signature does not verify
+    #[serde(default)]
+    pub mode: SignatureMode,
+    /// Keys a component may be signed with
+    #[serde(default)]
+    pub trusted_keys: Vec<TrustedKey>,
+    /// Identities an `oci://` component may be signed by keylessly
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trusted_identities: Vec<TrustedIdentity>,
+    /// What keyless signatures are verified against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyless: Option<KeylessTrustRoot>,
+}
+
+impl SignaturePolicy {
+    /// Load only components signed with one of `trusted_keys`.
+    pub fn enforce(trusted_keys: impl IntoIterator<Item = TrustedKey>) -> Self {
+        Self {
+            mode: SignatureMode::Enforce,
+            trusted_keys: trusted_keys.into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Check signatures against `trusted_keys`, but load components that
+    /// fail with a warning.
+    pub fn warn_only(trusted_keys: impl IntoIterator<Item = TrustedKey>) -> Self {
+        Self {
+            mode: SignatureMode::WarnOnly,
+            trusted_keys: trusted_keys.into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Also accept keyless signatures of `oci://` components by one of
+    /// `identities`, verified against `trust_root`.
+    pub fn with_trusted_identities(
+        mut self,
+        identities: impl IntoIterator<Item = TrustedIdentity>,
+        trust_root: KeylessTrustRoot,
+    ) -> Self {
+        self.trusted_identities.extend(identities);
+        self.keyless = Some(trust_root);
+        self
+    }
+
+    /// Whether signatures are checked at all.
+    pub fn is_enabled(&self) -> bool {
+        self.mode != SignatureMode::Disabled
+    }
+
+    /// Identities of the trusted keys, followed by the trusted keyless
+    /// identities.
+    pub fn identities(&self) -> Vec<String> {
+        self.trusted_keys
+            .iter()
+            .map(|key| key.identity.clone())
+            .chain(self.trusted_identities.iter().map(ToString::to_string))
+            .collect()
+    }
+
+    /// Identity of the first trusted key `signature` of `payload` verifies
+    /// with. `signature` is base64 of a DER-encoded ECDSA signature, as
+    /// cosign writes it.
+    pub(crate) fn signer(&self, payload: &[u8], signature: &str) -> Result<&str> {
+        let signature = decode_signature(signature)?;
+        self.trusted_keys
+            .iter()
+            .find(|key| key.verifies(payload, &signature))
+            .map(|key| key.identity.as_str())
+            .context("the signature was not made with a trusted key")
+    }
+
+    /// Subject of a keyless signature by a trusted identity among the
+    /// cosign signatures of the OCI artifact with `digest`, found at
+    /// `signature_reference`. sigstore-rs keeps only signatures whose Fulcio
+    /// certificate chains to the keyless trust root and was valid when the
+    /// Rekor bundle attached to it, signed by the trusted Rekor key, says it
+    /// was logged.
+    async fn keyless_signer(
+        &self,
+        signature_reference: &Reference,
+        digest: &str,
+        auth: &RegistryAuth,
+    ) -> Result<String> {
+        let trust_root = self
+            .keyless
+            .as_ref()
+            .context("the signature is keyless, and no keyless trust root is configured")?;
+        if self.trusted_identities.is_empty() {
+            bail!("the signature is keyless, and no identities are trusted to sign keylessly");
+        }
+        let auth = match auth {
+            RegistryAuth::Anonymous => Auth::Anonymous,
+            RegistryAuth::Basic(username, password) => {
+                Auth::Basic(username.clone(), password.clone())
+            }
+            RegistryAuth::Bearer(token) => Auth::Bearer(token.clone()),
+        };
+        let signature_image: OciReference = signature_reference.to_string().parse()?;
+        let layers = trust_root
+            .client()?
+            .trusted_signature_layers(&auth, digest, &signature_image)
+            .await
+            .context("failed to pull the keyless signature")?;
+        let identity = trusted_identity(&layers, &self.trusted_identities)?;
+        Ok(identity.subject.clone())
+    }
+
+    /// Verify the component at `path`, downloaded from the `file://` or
+    /// `https://` URI `uri`, against the detached signature at
+    /// [`signature_url`] of `uri`. Returns the identity of the signer.
+    pub(crate) async fn verify_detached(
+        &self,
+        http_client: &reqwest::Client,
+        uri: &str,
+        path: &Path,
+    ) -> Result<String> {
+        let signature_url = signature_url(uri)?;
+        let signature = match signature_url.scheme() {
+            "file" => {
+                let signature_path = signature_url
+                    .to_file_path()
+                    .map_err(|()| anyhow!("{signature_url} is not a local path"))?;
+                tokio::fs::read_to_string(&signature_path)
+                    .await
+                    .with_context(|| format!("no signature found at {signature_url}"))?
+            }
+            "https" => {
+                let response = http_client
+                    .get(signature_url.clone())
+                    .send()
+                    .await
+                    .with_context(|| format!("failed to fetch {signature_url}"))?;
+                if !response.status().is_success() {
+                    bail!(
+                        "no signature found at {signature_url} (status {})",
+                        response.status()
+                    );
+                }
+                response.text().await?
+            }
+            _ => bail!("signatures of '{uri}' cannot be verified"),
+        };
+        let component = tokio::fs::read(path)
+            .await
+            .context("failed to read the component")?;
+        Ok(self.signer(&component, &signature)?.to_string())
+    }
+
+    /// Verify the cosign signature of the OCI artifact `reference` points
+    /// to, made with a trusted key or keylessly by a trusted identity.
+    /// Returns the digest the signature covers and the identity of the
+    /// signer.
+    pub(crate) async fn verify_oci(
+        &self,
+        client: &oci_client::Client,
+        reference: &Reference,
+        auth: &RegistryAuth,
+    ) -> Result<(String, String)> {
+        let digest = match reference.digest() {
+            Some(digest) => digest.to_string(),
+            None => client
+                .fetch_manifest_digest(reference, auth)
+                .await
+                .context("failed to resolve the digest of the component")?,
+        };
+        let signature_reference = Reference::with_tag(
+            reference.registry().to_string(),
+            reference.repository().to_string(),
+            format!("{}.sig", digest.replace(':', "-")),
+        );
+        let (manifest, _) = client
+            .pull_image_manifest(&signature_reference, auth)
+            .await
+            .with_context(|| format!("no signature found at {signature_reference}"))?;
+
+        let mut failures = Vec::new();
+        let mut keyless = false;
+        for layer in &manifest.layers {
+            let Some(annotations) = &layer.annotations else {
+                continue;
+            };
+            let Some(signature) = annotations.get(COSIGN_SIGNATURE_ANNOTATION) else {
+                continue;
+            };
+            let mut payload = Vec::new();
+            client
+                .pull_blob(&signature_reference, layer, &mut payload)
+                .await
+                .context("failed to pull the signature payload")?;
+            if let Err(error) = check_payload_digest(&payload, &digest) {
+                failures.push(format!("{error:#}"));
+                continue;
+            }
+            match self.signer(&payload, signature) {
+                Ok(identity) => return Ok((digest, identity.to_string())),
+                Err(_) if annotations.contains_key(COSIGN_CERTIFICATE_ANNOTATION) => keyless = true,
+                Err(error) => failures.push(format!("{error:#}")),
+            }
+        }
+        if keyless {
+            match self
+                .keyless_signer(&signature_reference, &digest, auth)
+                .await
+            {
+                Ok(identity) => return Ok((digest, identity)),
+                Err(error) => failures.push(format!("{error:#}")),
+            }
+        }
+        match failures.first() {
+            Some(failure) => bail!("{failure}"),
+            None => bail!("no cosign signature found at {signature_reference}"),
+        }
+    }
+
+    /// Verify the cosign signature of the OCI artifact `reference` points
+    /// to, and that the signed artifact has a layer with `layer_digest`, so
+    /// a component obtained elsewhere can be matched to what was signed.
+    /// Returns the identity of the signer.
+    pub(crate) async fn verify_oci_layer(
+        &self,
+        client: &oci_client::Client,
+        reference: &Reference,
+        auth: &RegistryAuth,
+        layer_digest: &str,
+    ) -> Result<String> {
+        let (digest, identity) = self.verify_oci(client, reference, auth).await?;
+        let (manifest, _) = client
+            .pull_image_manifest(&reference.clone_with_digest(digest.clone()), auth)
+            .await
+            .context("failed to pull the signed manifest")?;
+        if !manifest
+            .layers
+            .iter()
+            .any(|layer| layer.digest == layer_digest)
+        {
+            bail!("the signed artifact {digest} does not contain {layer_digest}");
+        }
+        Ok(identity)
+    }
+}
+
+/// Error returned when a component's signature does not verify under
+/// [`SignatureMode::Enforce`]. Nothing is installed.
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "Signature verification failed for '{uri}': {reason}; expected a signature by {}",
+    expected_identities(.expected)
+)]
+pub struct SignatureRejected {
+    /// The URI the component was loaded from
+    pub uri: String,
+    /// Identities whose signature would have been accepted
+    pub expected: Vec<String>,
+    /// Why verification failed
+    pub reason: String,
+}
+
+fn expected_identities(expected: &[String]) -> String {
+    if expected.is_empty() {
+        "a trusted key or identity, but none are configured".to_string()
+    } else {
+        expected.join(", ")
+    }
+}
+
+/// Where the detached signature of the component at `uri` is: `uri` with
+/// [`SIGNATURE_SUFFIX`] appended to its path, keeping any query.
+fn signature_url(uri: &str) -> Result<Url> {
+    let mut url = Url::parse(uri).with_context(|| format!("'{uri}' is not a valid URI"))?;
+    let path = format!("{}{SIGNATURE_SUFFIX}", url.path());
+    url.set_path(&path);
+    Ok(url)
+}
+
+fn decode_signature(signature: &str) -> Result<Signature> {
+    let compact: String = signature.split_whitespace().collect();
+    let der = base64::engine::general_purpose::STANDARD
+        .decode(compact)
+        .context("the signature is not valid base64")?;
+    Signature::from_der(&der).context("the signature is not a DER-encoded ECDSA signature")
+}
+
+/// The first of `identities` that made one of the verified keyless
+/// signature `layers`.
+fn trusted_identity<'a>(
+    layers: &[SignatureLayer],
+    identities: &'a [TrustedIdentity],
+) -> Result<&'a TrustedIdentity> {
+    if let Some(identity) = identities.iter().find(|identity| {
+        let constraint: Box<dyn VerificationConstraint> = Box::new((*identity).clone());
+        verify_constraints(layers, std::iter::once(&constraint)).is_ok()
+    }) {
+        return Ok(identity);
+    }
+    let signers: Vec<String> = layers
+        .iter()
+        .filter_map(|layer| layer.certificate_signature.as_ref())
+        .map(|certificate| {
+            let (CertificateSubject::Email(subject) | CertificateSubject::Uri(subject)) =
+                &certificate.subject;
+            match &certificate.issuer {
+                Some(issuer) => format!("{subject} (issued by {issuer})"),
+                None => subject.clone(),
+            }
+        })
+        .collect();
+    if signers.is_empty() {
+        bail!("the keyless signature does not chain to a trusted Fulcio certificate, or is not recorded in the trusted Rekor log");
+    }
+    bail!(
+        "the keyless signature was made by {}, which is not a trusted identity",
+        signers.join(", ")
+    )
+}
+
+/// Check that a cosign simple-signing payload names the digest of the
+/// artifact being loaded, so a signature cannot be moved to another one.
+fn check_payload_digest(payload: &[u8], digest: &str) -> Result<()> {
+    let payload: Value =
+        serde_json::from_slice(payload).context("the signature payload is not JSON")?;
+    let signed = payload
+        .pointer("/critical/image/docker-manifest-digest")
+        .and_then(Value::as_str)
+        .context("the signature payload names no digest")?;
+    if signed != digest {
+        bail!("the signature covers {signed}, not {digest}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use base64::Engine;
+    use p256::ecdsa::signature::Signer;
+    use p256::ecdsa::SigningKey;
+    use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair};
+    use sigstore::cosign::signature_layers::CertificateSignature;
+    use sigstore::crypto::{CosignVerificationKey, SigningScheme};
+
+    use super::*;
+
+    /// A fixed signing key and the trusted key for it.
+    pub(crate) fn test_key(identity: &str) -> (SigningKey, TrustedKey) {
+        let signing = SigningKey::from_slice(&[7; 32]).unwrap();
+        let pem = signing
+            .verifying_key()
+            .to_public_key_pem(p256::pkcs8::LineEnding::LF)
+            .unwrap();
+        (signing, TrustedKey::from_pem(identity, pem).unwrap())
+    }
+
+    /// Base64 of the DER signature of `payload`, as cosign writes it.
+    pub(crate) fn sign(key: &SigningKey, payload: &[u8]) -> String {
+        let signature: Signature = key.sign(payload);
+        base64::engine::general_purpose::STANDARD.encode(signature.to_der())
+    }
+
+    const GITHUB_ISSUER: &str = "https://token.actions.githubusercontent.com";
+    const WORKFLOW: &str =
+        "https://github.com/acme/tools/.github/workflows/release.yml@refs/heads/main";
+    const PAYLOAD: &str = r#"{"critical":{"identity":{"docker-reference":"ghcr.io/acme/tools"},"image":{"docker-manifest-digest":"sha256:abc"},"type":"cosign container image signature"},"optional":null}"#;
+
+    /// A trust root of a self-signed Fulcio-like certificate and a Rekor
+    /// key.
+    fn trust_root() -> KeylessTrustRoot {
+        let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let root = params
+            .self_signed(&KeyPair::generate_for(&rcgen::PKCS_ECDSA_P256_SHA256).unwrap())
+            .unwrap();
+        let rekor = SigningKey::from_slice(&[9; 32])
+            .unwrap()
+            .verifying_key()
+            .to_public_key_pem(p256::pkcs8::LineEnding::LF)
+            .unwrap();
+        KeylessTrustRoot::from_pem(root.pem(), rekor).unwrap()
+    }
+
+    fn keyless_policy() -> SignaturePolicy {
+        SignaturePolicy::enforce([]).with_trusted_identities(
+            [TrustedIdentity::new(WORKFLOW, GITHUB_ISSUER)],
+            trust_root(),
+        )
+    }
+
+    /// A signature layer as sigstore-rs returns it once the certificate
+    /// chain and Rekor bundle verified: a signature of [`PAYLOAD`] with
+    /// `key`, by `subject` on the word of `issuer`.
+    fn verified_layer(key: &SigningKey, subject: &str, issuer: &str) -> SignatureLayer {
+        let spki = key.verifying_key().to_public_key_der().unwrap();
+        SignatureLayer {
+            simple_signing: serde_json::from_str(PAYLOAD).unwrap(),
+            oci_digest: "sha256:def".to_string(),
+            certificate_signature: Some(CertificateSignature {
+                verification_key: CosignVerificationKey::from_der(
+                    spki.as_bytes(),
+                    &SigningScheme::ECDSA_P256_SHA256_ASN1,
+                )
+                .unwrap(),
+                subject: CertificateSubject::Uri(subject.to_string()),
+                issuer: Some(issuer.to_string()),
+                github_workflow_trigger: None,
+                github_workflow_sha: None,
+                github_workflow_name: None,
+                github_workflow_repository: None,
+                github_workflow_ref: None,
+            }),
+            bundle: None,
+            signature: Some(sign(key, PAYLOAD.as_bytes())),
+            raw_data: PAYLOAD.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_signer_names_the_trusted_identity() {
+        let (signing, trusted) = test_key("release@example.com");
+        let policy = SignaturePolicy::enforce([trusted]);
+        let signature = sign(&signing, b"component");
+
+        assert_eq!(
+            policy.signer(b"component", &signature).unwrap(),
+            "release@example.com"
+        );
+        assert!(policy.signer(b"other component", &signature).is_err());
+        assert!(policy.signer(b"component", "not base64!").is_err());
+        assert!(SignaturePolicy::default()
+            .signer(b"component", &signature)
+            .is_err());
+    }
+
+    #[test]
+    fn test_trusted_identity_matches_subject_issuer_and_key() {
+        let policy = keyless_policy();
+        let identities = &policy.trusted_identities;
+        let key = SigningKey::from_slice(&[3; 32]).unwrap();
+
+        let layer = verified_layer(&key, WORKFLOW, GITHUB_ISSUER);
+        assert_eq!(
+            trusted_identity(&[layer], identities).unwrap().subject,
+            WORKFLOW
+        );
+
+        let other =
+            "https://github.com/mallory/tools/.github/workflows/release.yml@refs/heads/main";
+        let layer = verified_layer(&key, other, GITHUB_ISSUER);
+        assert_eq!(
+            trusted_identity(&[layer], identities)
+                .unwrap_err()
+                .to_string(),
+            format!(
+                "the keyless signature was made by {other} (issued by {GITHUB_ISSUER}), which is not a trusted identity"
+            )
+        );
+        let layer = verified_layer(&key, WORKFLOW, "https://accounts.example.com");
+        assert!(trusted_identity(&[layer], identities).is_err());
+
+        // Signed with another key than the certificate's
+        let mut layer = verified_layer(&key, WORKFLOW, GITHUB_ISSUER);
+        layer.signature = Some(sign(
+            &SigningKey::from_slice(&[4; 32]).unwrap(),
+            PAYLOAD.as_bytes(),
+        ));
+        assert!(trusted_identity(&[layer], identities).is_err());
+
+        // Dropped by sigstore-rs for not chaining to the trust root
+        let mut layer = verified_layer(&key, WORKFLOW, GITHUB_ISSUER);
+        layer.certificate_signature = None;
+        assert!(trusted_identity(&[layer], identities)
+            .unwrap_err()
+            .to_string()
+            .contains("does not chain to a trusted Fulcio certificate"));
+    }
+
+    #[tokio::test]
+    async fn test_keyless_signer_requires_a_trust_root_and_identities() {
+        let reference: Reference = "ghcr.io/acme/tools:sha256-abc.sig".parse().unwrap();
+        let mut untrusting = keyless_policy();
+        untrusting.trusted_identities.clear();
+        let error = untrusting
+            .keyless_signer(&reference, "sha256:abc", &RegistryAuth::Anonymous)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("no identities are trusted"));
+
+        let mut rootless = keyless_policy();
+        rootless.keyless = None;
+        let error = rootless
+            .keyless_signer(&reference, "sha256:abc", &RegistryAuth::Anonymous)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("no keyless trust root"));
+    }
+
+    #[test]
+    fn test_keyless_trust_root_rejects_invalid_pem() {
+        let rekor = SigningKey::from_slice(&[9; 32])
+            .unwrap()
+            .verifying_key()
+            .to_public_key_pem(p256::pkcs8::LineEnding::LF)
+            .unwrap();
+        let error = KeylessTrustRoot::from_pem("not a certificate", &rekor).unwrap_err();
+        assert!(error.to_string().contains("no certificate found"));
+        let error = KeylessTrustRoot::from_pem(
+            "-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----\n",
+            &rekor,
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "Invalid Fulcio certificates");
+    }
+
+    #[test]
+    fn test_signature_url_appends_to_the_path() {
+        assert_eq!(
+            signature_url("https://example.com/tools.wasm?token=abc#main")
+                .unwrap()
+                .as_str(),
+            "https://example.com/tools.wasm.sig?token=abc#main"
+        );
+        assert_eq!(
+            signature_url("file:///srv/my%20tools.wasm")
+                .unwrap()
+                .to_file_path()
+                .unwrap(),
+            Path::new("/srv/my tools.wasm.sig")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_detached_decodes_file_uris() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("my tools.wasm");
+        let (signing, trusted) = test_key("release@example.com");
+        std::fs::write(&path, b"component").unwrap();
+        std::fs::write(
+            dir.path().join("my tools.wasm.sig"),
+            sign(&signing, b"component"),
+        )
+        .unwrap();
+        let uri = Url::from_file_path(&path).unwrap();
+        assert!(uri.as_str().ends_with("/my%20tools.wasm"));
+
+        let identity = SignaturePolicy::enforce([trusted])
+            .verify_detached(&reqwest::Client::new(), uri.as_str(), &path)
+            .await
+            .unwrap();
+        assert_eq!(identity, "release@example.com");
+    }
+
+    #[test]
+    fn test_payload_must_name_the_digest() {
+        let payload =
+            br#"{"critical":{"image":{"docker-manifest-digest":"sha256:abc"},"type":"cosign container image signature"}}"#;
+        assert!(check_payload_digest(payload, "sha256:abc").is_ok());
+        let error = check_payload_digest(payload, "sha256:def").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the signature covers sha256:abc, not sha256:def"
+        );
+    }
+
+    #[test]
+    fn test_signature_policy_from_toml() {
+        let (_, trusted) = test_key("release@example.com");
+        let policy = SignaturePolicy::warn_only([trusted]);
+        let text = toml::to_string(&policy).unwrap();
+        let parsed: SignaturePolicy = toml::from_str(&text).unwrap();
+        assert_eq!(parsed, policy);
+
+        let error = toml::from_str::<SignaturePolicy>(
+            "mode = \"enforce\"\ntrusted_keys = [{ identity = \"ops\" }]\n",
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("exactly one of public_key"));
+
+        let policy = keyless_policy();
+        let text = toml::to_string(&policy).unwrap();
+        let parsed: SignaturePolicy = toml::from_str(&text).unwrap();
+        assert_eq!(parsed, policy);
+        assert_eq!(
+            parsed.identities(),
+            vec![format!("{WORKFLOW} (issued by {GITHUB_ISSUER})")]
+        );
+
+        let error = toml::from_str::<SignaturePolicy>(
+            "mode = \"enforce\"\n[keyless]\nrekor_public_key_file = \"rekor.pub\"\n",
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("exactly one of fulcio_certificates"));
+    }
+}
@@ -0,0 +1,273 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Host-enforced allowlist of the places components may be loaded from.
+//!
+//! Each [`AllowedSource`] names a URI scheme and, optionally, a pattern the
+//! rest of the URI must match. The pattern is matched against a normalized
+//! location rather than the raw URI: `registry/repository` for `oci://`
+//! references (the tag or digest is ignored), `host[:port]/path` for
+//! `https://` URLs, and the path for `file://` URIs. In a pattern, `*`
+//! matches within one path segment and `**` matches across segments, so
+//! `registry.internal/**` allows every repository on that registry. An empty
+//! allowlist allows every source.
+
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// A scheme, and optionally a location pattern, components may be loaded from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AllowedSource {
+    /// URI scheme, e.g. `oci`, `https`, or `file`
+    pub scheme: String,
+    /// Pattern the location must match; any location of the scheme if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_pattern: Option<String>,
+}
+
+impl AllowedSource {
+    /// Allow `scheme` URIs whose location matches `host_pattern`.
+    pub fn new(scheme: impl Into<String>, host_pattern: impl Into<String>) -> Self {
+        Self {
+            scheme: scheme.into(),
+            host_pattern: Some(host_pattern.into()),
+        }
+    }
+
+    /// Allow every URI with the given scheme.
+    pub fn any(scheme: impl Into<String>) -> Self {
+        Self {
+            scheme: scheme.into(),
+            host_pattern: None,
+        }
+    }
+
+    fn matches(&self, scheme: &str, location: Option<&str>) -> bool {
+        if !self.scheme.eq_ignore_ascii_case(scheme) {
+            return false;
+        }
+        match (&self.host_pattern, location) {
+            (None, _) => true,
+            (Some(pattern), Some(location)) => glob_match(pattern.as_bytes(), location.as_bytes()),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+impl fmt::Display for AllowedSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.host_pattern {
+            Some(pattern) => write!(f, "{}://{}", self.scheme, pattern),
+            None => write!(f, "{}://**", self.scheme),
+        }
+    }
+}
+
+/// Error returned when a component or policy URI is not covered by the
+/// allowlist.
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "Loading {resource} from '{uri}' is not allowed; allowed sources: {}",
+    .allowed.join(", ")
+)]
+pub struct SourceNotAllowed {
+    /// What was being loaded, `components` or `policies`
+    pub resource: &'static str,
+    /// The rejected URI
+    pub uri: String,
+    /// The allowed sources, formatted as `scheme://pattern`
+    pub allowed: Vec<String>,
+}
+
+/// The allowlist checked before a component or policy is fetched.
+#[derive(Debug, Clone)]
+pub(crate) struct SourcePolicy {
+    resource: &'static str,
+    allowed: Vec<AllowedSource>,
+}
+
+impl Default for SourcePolicy {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl SourcePolicy {
+    /// The allowlist for component URIs.
+    pub(crate) fn new(allowed: Vec<AllowedSource>) -> Self {
+        Self {
+            resource: "components",
+            allowed,
+        }
+    }
+
+    /// The allowlist for policy URIs.
+    pub(crate) fn for_policies(allowed: Vec<AllowedSource>) -> Self {
+        Self {
+            resource: "policies",
+            allowed,
+        }
+    }
+
+    /// Check `uri` against the allowlist without touching the network or
+    /// the filesystem.
+    pub(crate) fn check(&self, uri: &str) -> Result<(), SourceNotAllowed> {
+        if self.allowed.is_empty() {
+            return Ok(());
+        }
+        let uri = uri.trim();
+        let allowed = uri.split_once("://").is_some_and(|(scheme, rest)| {
+            let location = source_location(scheme, uri, rest);
+            self.allowed
+                .iter()
+                .any(|source| source.matches(scheme, location.as_deref()))
+        });
+        if allowed {
+            Ok(())
+        } else {
+            Err(SourceNotAllowed {
+                resource: self.resource,
+                uri: uri.to_string(),
+                allowed: self.allowed.iter().map(ToString::to_string).collect(),
+            })
+        }
+    }
+}
+
+/// The part of a URI location patterns are matched against, or `None` if it
+/// cannot be parsed.
+fn source_location(scheme: &str, uri: &str, rest: &str) -> Option<String> {
+    match scheme.to_ascii_lowercase().as_str() {
+        "oci" => {
+            let reference: oci_client::Reference = rest.parse().ok()?;
+            Some(format!(
+                "{}/{}",
+                reference.registry(),
+                reference.repository()
+            ))
+        }
+        "https" | "http" => {
+            let url = url::Url::parse(uri).ok()?;
+            let host = url.host_str()?;
+            Some(match url.port() {
+                Some(port) => format!("{host}:{port}{}", url.path()),
+                None => format!("{host}{}", url.path()),
+            })
+        }
+        "file" => Some(
+            normalize_path(Path::new(rest))
+                .to_string_lossy()
+                .into_owned(),
+        ),
+        _ => Some(rest.to_string()),
+    }
+}
+
+/// Resolve `.` and `..` lexically so a path cannot escape an allowed
+/// directory pattern.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Match `text` against a pattern where `*` matches anything but `/` and
+/// `**` matches anything.
+pub(crate) fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => (0..=text.len()).any(|i| glob_match(rest, &text[i..])),
+        [b'*', rest @ ..] => {
+            let segment = text.iter().position(|&c| c == b'/').unwrap_or(text.len());
+            (0..=segment).any(|i| glob_match(rest, &text[i..]))
+        }
+        [c, rest @ ..] => text.first() == Some(c) && glob_match(rest, &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(sources: Vec<AllowedSource>) -> SourcePolicy {
+        SourcePolicy::new(sources)
+    }
+
+    #[test]
+    fn test_empty_policy_allows_everything() {
+        let policy = SourcePolicy::default();
+        assert!(policy.check("https://example.com/a.wasm").is_ok());
+        assert!(policy.check("file:///tmp/a.wasm").is_ok());
+    }
+
+    #[test]
+    fn test_oci_registry_pattern() {
+        let policy = policy(vec![AllowedSource::new("oci", "registry.internal/**")]);
+        assert!(policy
+            .check("oci://registry.internal/team/tool:1.0")
+            .is_ok());
+        assert!(policy
+            .check("oci://registry.internal/tool@sha256:0000000000000000000000000000000000000000000000000000000000000000")
+            .is_ok());
+        assert!(policy.check("oci://ghcr.io/team/tool:1.0").is_err());
+        assert!(policy.check("https://registry.internal/tool.wasm").is_err());
+        assert!(policy.check("file:///registry.internal/tool.wasm").is_err());
+
+        let error = policy.check("oci://ghcr.io/team/tool:1.0").unwrap_err();
+        assert_eq!(error.allowed, ["oci://registry.internal/**"]);
+        assert!(error.to_string().contains("oci://registry.internal/**"));
+    }
+
+    #[test]
+    fn test_single_star_stays_in_segment() {
+        let policy = policy(vec![AllowedSource::new("oci", "registry.internal/*")]);
+        assert!(policy.check("oci://registry.internal/tool:1.0").is_ok());
+        assert!(policy
+            .check("oci://registry.internal/team/tool:1.0")
+            .is_err());
+    }
+
+    #[test]
+    fn test_https_matches_parsed_host() {
+        let policy = policy(vec![AllowedSource::new(
+            "https",
+            "downloads.example.com/**",
+        )]);
+        assert!(policy
+            .check("https://downloads.example.com/tools/a.wasm")
+            .is_ok());
+        assert!(policy
+            .check("https://downloads.example.com@evil.example/a.wasm")
+            .is_err());
+        assert!(policy
+            .check("https://evil.example/downloads.example.com/a.wasm")
+            .is_err());
+    }
+
+    #[test]
+    fn test_file_paths_are_normalized() {
+        let policy = policy(vec![AllowedSource::new("file", "/srv/components/**")]);
+        assert!(policy.check("file:///srv/components/a.wasm").is_ok());
+        assert!(policy
+            .check("file:///srv/components/../../etc/a.wasm")
+            .is_err());
+    }
+
+    #[test]
+    fn test_scheme_without_pattern() {
+        let policy = policy(vec![AllowedSource::any("oci")]);
+        assert!(policy.check("oci://ghcr.io/team/tool:1.0").is_ok());
+        assert!(policy.check("/tmp/tool.wasm").is_err());
+    }
+}
@@ -0,0 +1,220 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Exporting and importing a lifecycle manager's state as a single
+//! zstd-compressed tar archive.
+//!
+//! The archive holds a `wassette-state.json` header listing the exported
+//! components and their digests, the per-component files under
+//! `components/`, the state manifest as `components.lock.json`, and, only
+//! when explicitly requested, the component secrets under `secrets/`.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::task::spawn_blocking;
+
+/// Name of the header file inside a state archive.
+pub(crate) const HEADER_FILE_NAME: &str = "wassette-state.json";
+
+/// Archive directory holding the per-component files.
+pub(crate) const COMPONENTS_DIR: &str = "components";
+
+/// Archive directory holding the component secrets.
+pub(crate) const SECRETS_DIR: &str = "secrets";
+
+/// Current state archive format version.
+const STATE_ARCHIVE_VERSION: u32 = 1;
+
+/// zstd compression level used for state archives.
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Options for [`LifecycleManager::export_state`](crate::LifecycleManager::export_state).
+#[derive(Debug, Clone, Default)]
+pub struct StateExportOptions {
+    /// Include component secrets in plain text
+    pub include_secrets: bool,
+}
+
+/// Options for [`LifecycleManager::import_state`](crate::LifecycleManager::import_state).
+#[derive(Debug, Clone, Default)]
+pub struct StateImportOptions {
+    /// Import the secrets contained in the archive
+    pub include_secrets: bool,
+}
+
+/// Summary of a state export.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StateExportReport {
+    /// Components written to the archive
+    pub components: Vec<String>,
+    /// Components whose secrets were written to the archive
+    pub secrets: Vec<String>,
+    /// Whether the state manifest was included
+    pub manifest: bool,
+}
+
+/// Summary of a state import.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StateImportReport {
+    /// Components that were missing and have been installed from the archive
+    pub imported: Vec<String>,
+    /// Components already installed with the same digest
+    pub unchanged: Vec<String>,
+    /// Components or secrets left untouched because they differ from the archive
+    pub conflicts: Vec<StateImportIssue>,
+    /// Components that could not be installed or loaded
+    pub failed: Vec<StateImportIssue>,
+    /// Components whose secrets were imported
+    pub secrets: Vec<String>,
+}
+
+/// A component the import did not apply cleanly.
+#[derive(Debug, Clone, Serialize)]
+pub struct StateImportIssue {
+    /// Component ID from the archive
+    pub component_id: String,
+    /// Human-readable reason
+    pub reason: String,
+}
+
+/// Contents of the archive header.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct StateHeader {
+    pub version: u32,
+    /// Exported components keyed by ID, with the digest of their `.wasm` file
+    pub components: BTreeMap<String, String>,
+}
+
+impl StateHeader {
+    pub(crate) fn new(components: BTreeMap<String, String>) -> Self {
+        Self {
+            version: STATE_ARCHIVE_VERSION,
+            components,
+        }
+    }
+
+    pub(crate) async fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(HEADER_FILE_NAME);
+        let content = tokio::fs::read_to_string(&path)
+            .await
+            .context("Not a wassette state archive: header is missing")?;
+        let header: Self =
+            serde_json::from_str(&content).context("Failed to parse state archive header")?;
+        if header.version != STATE_ARCHIVE_VERSION {
+            bail!(
+                "Unsupported state archive version {} (expected {})",
+                header.version,
+                STATE_ARCHIVE_VERSION
+            );
+        }
+        for id in header.components.keys() {
+            if id.is_empty() || id.contains(['/', '\\']) || id.starts_with('.') {
+                bail!("Invalid component ID in state archive: {id}");
+            }
+        }
+        Ok(header)
+    }
+}
+
+/// Write a compressed archive at `path` containing `header` and the given
+/// files, each stored under its archive name.
+pub(crate) async fn write_archive(
+    path: &Path,
+    header: &StateHeader,
+    files: Vec<(String, PathBuf)>,
+) -> Result<()> {
+    let header = serde_json::to_vec_pretty(header)?;
+    let path = path.to_path_buf();
+    spawn_blocking(move || {
+        let file = File::create(&path)
+            .with_context(|| format!("Failed to create state archive at {}", path.display()))?;
+        let encoder = zstd::Encoder::new(file, COMPRESSION_LEVEL)?;
+        let mut builder = tar::Builder::new(encoder);
+
+        let mut entry = tar::Header::new_gnu();
+        entry.set_size(header.len() as u64);
+        entry.set_mode(0o644);
+        entry.set_cksum();
+        builder.append_data(&mut entry, HEADER_FILE_NAME, header.as_slice())?;
+
+        for (name, source) in files {
+            builder
+                .append_path_with_name(&source, &name)
+                .with_context(|| format!("Failed to add {} to archive", source.display()))?;
+        }
+
+        builder.into_inner()?.finish()?;
+        Ok(())
+    })
+    .await?
+}
+
+/// Unpack the archive at `path` into `dest`. Entries that would escape
+/// `dest` are skipped by the tar reader.
+pub(crate) async fn unpack_archive(path: &Path, dest: &Path) -> Result<()> {
+    let path = path.to_path_buf();
+    let dest = dest.to_path_buf();
+    spawn_blocking(move || {
+        let file = File::open(&path)
+            .with_context(|| format!("Failed to open state archive at {}", path.display()))?;
+        let decoder = zstd::Decoder::new(file)?;
+        tar::Archive::new(decoder)
+            .unpack(&dest)
+            .with_context(|| format!("Failed to unpack state archive {}", path.display()))
+    })
+    .await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_archive_round_trip() -> Result<()> {
+        let source = tempfile::tempdir()?;
+        let wasm = source.path().join("fetch.wasm");
+        tokio::fs::write(&wasm, b"\0asm").await?;
+
+        let archive = source.path().join("state.tar.zst");
+        let header = StateHeader::new(BTreeMap::from([(
+            "fetch".to_string(),
+            "sha256:abc".to_string(),
+        )]));
+        write_archive(
+            &archive,
+            &header,
+            vec![(format!("{COMPONENTS_DIR}/fetch.wasm"), wasm)],
+        )
+        .await?;
+
+        let dest = tempfile::tempdir()?;
+        unpack_archive(&archive, dest.path()).await?;
+        let header = StateHeader::load(dest.path()).await?;
+        assert_eq!(header.components["fetch"], "sha256:abc");
+        assert_eq!(
+            tokio::fs::read(dest.path().join(COMPONENTS_DIR).join("fetch.wasm")).await?,
+            b"\0asm"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_header_rejects_path_like_ids() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let header = StateHeader::new(BTreeMap::from([(
+            "../evil".to_string(),
+            "sha256:abc".to_string(),
+        )]));
+        tokio::fs::write(
+            dir.path().join(HEADER_FILE_NAME),
+            serde_json::to_vec(&header)?,
+        )
+        .await?;
+        assert!(StateHeader::load(dir.path()).await.is_err());
+        Ok(())
+    }
+}
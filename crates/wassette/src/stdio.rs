@@ -0,0 +1,223 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Standard output and error of components kept in memory, so what a
+//! component prints during a call can be returned with the tool result
+//! instead of ending up in the server log or on the stdio MCP transport.
+//!
+//! Where each stream goes is chosen by the component's policy under
+//! `permissions.system.stdout` and `permissions.system.stderr`, falling back
+//! to the manager's default set with
+//! [`LifecycleBuilder::with_default_stdio`](crate::LifecycleBuilder::with_default_stdio).
+//! A call may also have the lines of stderr sent to it while it runs, see
+//! [`LifecycleManager::execute_component_call_with_stderr`](crate::LifecycleManager::execute_component_call_with_stderr).
+
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+pub use policy::StdioMode;
+use serde::Serialize;
+use tokio::io::AsyncWrite;
+use tokio::sync::mpsc;
+use wasmtime_wasi::cli::{IsTerminal, StdoutStream};
+
+/// Most bytes kept of each of a call's stdout and stderr. Bytes written
+/// past it are dropped, and the captured text ends with a marker naming how
+/// many.
+pub const MAX_CAPTURED_BYTES: usize = 1024 * 1024;
+
+/// Longest stderr line sent in one piece; longer lines are split.
+pub const MAX_STDERR_LINE_BYTES: usize = 8 * 1024;
+
+/// Receives the lines a component writes to stderr during a call, without
+/// their line endings, as they are written.
+pub type StderrLines = mpsc::Sender<String>;
+
+/// What a component wrote during a call to the streams its policy captures.
+/// A stream that is not captured is empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CapturedOutput {
+    /// Text written to stdout
+    pub stdout: String,
+    /// Text written to stderr
+    pub stderr: String,
+}
+
+impl CapturedOutput {
+    /// Whether nothing was captured on either stream.
+    pub fn is_empty(&self) -> bool {
+        self.stdout.is_empty() && self.stderr.is_empty()
+    }
+}
+
+#[derive(Default)]
+struct CaptureBuffer {
+    bytes: Vec<u8>,
+    dropped: u64,
+    lines: Option<LineForwarder>,
+}
+
+/// Sends what is written line by line, and the last unterminated line when
+/// dropped. Lines the receiver has no room for are dropped, so a slow
+/// receiver never holds up the guest.
+struct LineForwarder {
+    partial: Vec<u8>,
+    lines: StderrLines,
+}
+
+impl LineForwarder {
+    fn write(&mut self, buf: &[u8]) {
+        self.partial.extend_from_slice(buf);
+        while let Some(end) = self.partial.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.partial.drain(..=end).collect();
+            self.send(&line[..end]);
+        }
+        while self.partial.len() >= MAX_STDERR_LINE_BYTES {
+            let line: Vec<u8> = self.partial.drain(..MAX_STDERR_LINE_BYTES).collect();
+            self.send(&line);
+        }
+    }
+
+    fn send(&self, line: &[u8]) {
+        let line = String::from_utf8_lossy(line);
+        let _ = self
+            .lines
+            .try_send(line.strip_suffix('\r').unwrap_or(&line).to_string());
+    }
+}
+
+impl Drop for LineForwarder {
+    fn drop(&mut self) {
+        if !self.partial.is_empty() {
+            let line = std::mem::take(&mut self.partial);
+            self.send(&line);
+        }
+    }
+}
+
+/// An in-memory stdout or stderr keeping the first `limit` bytes written,
+/// optionally sending every line on as well. Writes never fail, so a chatty
+/// component is truncated rather than trapped.
+#[derive(Clone)]
+pub(crate) struct CapturePipe {
+    buffer: Arc<Mutex<CaptureBuffer>>,
+    limit: usize,
+}
+
+impl CapturePipe {
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            buffer: Arc::default(),
+            limit,
+        }
+    }
+
+    /// Also send each line written to `lines`, if given. The last line is
+    /// sent, and the channel released, once every handle to the pipe is
+    /// dropped.
+    pub(crate) fn forwarding_lines(self, lines: Option<StderrLines>) -> Self {
+        self.buffer.lock().unwrap().lines = lines.map(|lines| LineForwarder {
+            partial: Vec::new(),
+            lines,
+        });
+        self
+    }
+
+    /// The captured bytes as text, followed by a truncation marker if any
+    /// were dropped.
+    pub(crate) fn contents(&self) -> String {
+        let buffer = self.buffer.lock().unwrap();
+        let mut text = String::from_utf8_lossy(&buffer.bytes).into_owned();
+        if buffer.dropped > 0 {
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text.push_str(&format!(
+                "[output truncated, {} more bytes dropped]",
+                buffer.dropped
+            ));
+        }
+        text
+    }
+}
+
+impl IsTerminal for CapturePipe {
+    fn is_terminal(&self) -> bool {
+        false
+    }
+}
+
+impl StdoutStream for CapturePipe {
+    fn async_stream(&self) -> Box<dyn AsyncWrite + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
+
+impl AsyncWrite for CapturePipe {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut buffer = self.buffer.lock().unwrap();
+        let kept = buf.len().min(self.limit.saturating_sub(buffer.bytes.len()));
+        buffer.bytes.extend_from_slice(&buf[..kept]);
+        buffer.dropped += (buf.len() - kept) as u64;
+        if let Some(lines) = &mut buffer.lines {
+            lines.write(buf);
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_capture_pipe_truncates_past_limit() {
+        let pipe = CapturePipe::new(8);
+        let mut stream = Pin::from(pipe.async_stream());
+        stream.write_all(b"hello ").await.unwrap();
+        assert_eq!(pipe.contents(), "hello ");
+
+        // Writes past the limit succeed, so the guest keeps running
+        stream.write_all(b"world, again").await.unwrap();
+        assert_eq!(
+            pipe.contents(),
+            "hello wo\n[output truncated, 10 more bytes dropped]"
+        );
+        assert!(!pipe.is_terminal());
+    }
+
+    #[tokio::test]
+    async fn test_capture_pipe_forwards_lines() {
+        let (lines, mut received) = mpsc::channel(8);
+        let pipe = CapturePipe::new(0).forwarding_lines(Some(lines));
+        let mut stream = Pin::from(pipe.async_stream());
+        stream.write_all(b"step 1\r\nstep").await.unwrap();
+        stream.write_all(b" 2\nlast").await.unwrap();
+        assert_eq!(received.recv().await.as_deref(), Some("step 1"));
+        assert_eq!(received.recv().await.as_deref(), Some("step 2"));
+        assert!(received.try_recv().is_err());
+
+        // The unterminated line is sent once the pipe is gone, which closes
+        // the channel
+        drop(stream);
+        drop(pipe);
+        assert_eq!(received.recv().await.as_deref(), Some("last"));
+        assert_eq!(received.recv().await, None);
+    }
+}
@@ -0,0 +1,291 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Helpers for testing components against wassette's sandbox, enabled with
+//! the `testing` feature.
+//!
+//! A [`TestHarness`] owns a [`LifecycleManager`] whose component directory,
+//! secrets, and policies live in a temporary directory that is removed when
+//! the harness is dropped. Components are loaded from a path or from bytes,
+//! policies are attached from inline YAML, and tools are called with JSON:
+//!
+//! ```no_run
+//! # async fn example() -> anyhow::Result<()> {
+//! use wassette::testing::TestHarness;
+//!
+//! let harness = TestHarness::new().await?;
+//! let component_id = harness.load_path("target/wasm32-wasip2/release/fetch_rs.wasm").await?;
+//! harness
+//!     .attach_policy_yaml(
+//!         &component_id,
+//!         r#"
+//! version: "1.0"
+//! permissions:
+//!   network:
+//!     allow:
+//!       - host: "example.com"
+//! "#,
+//!     )
+//!     .await?;
+//!
+//! let result: serde_json::Value = harness
+//!     .call("fetch", serde_json::json!({"url": "https://api.github.com"}))
+//!     .await?;
+//! let denial = harness.assert_permission_denied(&component_id, "network");
+//! assert_eq!(denial.details["host"], "api.github.com");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{bail, Context, Result};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+use crate::{LifecycleBuilder, LifecycleEvent, LifecycleManager};
+
+/// A permission denial recorded while a [`TestHarness`] was running, as
+/// announced with [`LifecycleEvent::PermissionDenied`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PermissionDenial {
+    /// Component identifier
+    pub component_id: String,
+    /// Permission type (`network`, `storage`, or `hook`)
+    pub permission_type: String,
+    /// What was refused, e.g. the host and URI of a network request
+    pub details: Value,
+}
+
+/// A lifecycle manager in a temporary directory, with helpers for loading
+/// components, attaching policies, and calling tools.
+///
+/// Dereferences to the [`LifecycleManager`] for everything else.
+pub struct TestHarness {
+    manager: LifecycleManager,
+    events: Mutex<broadcast::Receiver<LifecycleEvent>>,
+    denials: Mutex<Vec<PermissionDenial>>,
+    tempdir: tempfile::TempDir,
+}
+
+impl TestHarness {
+    /// A harness with the default lifecycle manager settings.
+    pub async fn new() -> Result<Self> {
+        Self::with_builder(|builder| builder).await
+    }
+
+    /// A harness whose lifecycle manager is built with `configure` applied
+    /// to the builder. The component and secrets directories are set to the
+    /// harness's temporary directory before `configure` runs.
+    pub async fn with_builder(
+        configure: impl FnOnce(LifecycleBuilder) -> LifecycleBuilder,
+    ) -> Result<Self> {
+        let tempdir = tempfile::tempdir()?;
+        let component_dir = tempdir.path().join("components");
+        tokio::fs::create_dir_all(&component_dir).await?;
+        tokio::fs::create_dir_all(tempdir.path().join("sources")).await?;
+
+        let builder = LifecycleManager::builder(&component_dir)
+            .with_secrets_dir(tempdir.path().join("secrets"));
+        let manager = configure(builder).build().await?;
+        Ok(Self {
+            events: Mutex::new(manager.subscribe()),
+            denials: Mutex::default(),
+            manager,
+            tempdir,
+        })
+    }
+
+    /// The lifecycle manager under test.
+    pub fn manager(&self) -> &LifecycleManager {
+        &self.manager
+    }
+
+    /// Directory for files the harness writes before loading them, such as
+    /// components passed as bytes and inline policies.
+    fn sources_dir(&self) -> PathBuf {
+        self.tempdir.path().join("sources")
+    }
+
+    /// Load the component at `path`, returning its ID.
+    pub async fn load_path(&self, path: impl AsRef<Path>) -> Result<String> {
+        let path = std::path::absolute(path.as_ref())?;
+        let outcome = self
+            .manager
+            .load_component(&format!("file://{}", path.display()))
+            .await?;
+        Ok(outcome.component_id)
+    }
+
+    /// Load a component from its wasm bytes under `component_id`.
+    pub async fn load_bytes(&self, component_id: &str, wasm: &[u8]) -> Result<String> {
+        let path = self.sources_dir().join(format!("{component_id}.wasm"));
+        tokio::fs::write(&path, wasm)
+            .await
+            .with_context(|| format!("Failed to write component to {}", path.display()))?;
+        self.load_path(path).await
+    }
+
+    /// Attach the policy in `yaml` to a loaded component.
+    pub async fn attach_policy_yaml(&self, component_id: &str, yaml: &str) -> Result<()> {
+        policy::PolicyParser::parse_str(yaml).context("Invalid policy")?;
+        let path = self
+            .sources_dir()
+            .join(format!("{component_id}.policy.yaml"));
+        tokio::fs::write(&path, yaml).await?;
+        self.manager
+            .attach_policy(component_id, &format!("file://{}", path.display()))
+            .await
+    }
+
+    /// Call `tool` with `arguments` and deserialize its result. A result
+    /// that is not JSON is passed on as a JSON string.
+    pub async fn call<T: DeserializeOwned>(&self, tool: &str, arguments: Value) -> Result<T> {
+        let component_id = self.manager.get_component_id_for_tool(tool).await?;
+        let result = self
+            .manager
+            .execute_component_call(&component_id, tool, &arguments.to_string())
+            .await;
+        self.collect_denials();
+        let result = result?;
+        let value = serde_json::from_str(&result).unwrap_or(Value::String(result));
+        serde_json::from_value(value)
+            .with_context(|| format!("Unexpected result from tool '{tool}'"))
+    }
+
+    /// The permission denials recorded since the harness was created,
+    /// oldest first.
+    pub fn permission_denials(&self) -> Vec<PermissionDenial> {
+        self.collect_denials();
+        self.denials.lock().unwrap().clone()
+    }
+
+    /// Forget the permission denials recorded so far.
+    pub fn clear_permission_denials(&self) {
+        self.collect_denials();
+        self.denials.lock().unwrap().clear();
+    }
+
+    /// Return the most recent denial of `permission_type` for a component,
+    /// panicking with the recorded denials if there is none.
+    #[track_caller]
+    pub fn assert_permission_denied(
+        &self,
+        component_id: &str,
+        permission_type: &str,
+    ) -> PermissionDenial {
+        let denials = self.permission_denials();
+        match denials.iter().rev().find(|denial| {
+            denial.component_id == component_id && denial.permission_type == permission_type
+        }) {
+            Some(denial) => denial.clone(),
+            None => panic!(
+                "expected a {permission_type} permission denial for component '{component_id}', recorded denials: {denials:?}"
+            ),
+        }
+    }
+
+    fn collect_denials(&self) {
+        let mut events = self.events.lock().unwrap();
+        let mut denials = self.denials.lock().unwrap();
+        loop {
+            match events.try_recv() {
+                Ok(LifecycleEvent::PermissionDenied {
+                    component_id,
+                    permission_type,
+                    details,
+                }) => denials.push(PermissionDenial {
+                    component_id,
+                    permission_type,
+                    details,
+                }),
+                Ok(_) | Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+impl Deref for TestHarness {
+    type Target = LifecycleManager;
+
+    fn deref(&self) -> &Self::Target {
+        &self.manager
+    }
+}
+
+/// Build the Rust component crate in `crate_dir` for `wasm32-wasip2` in
+/// release mode, unless it was built already, and return the path of the
+/// `<artifact_name>.wasm` it produces.
+pub fn build_rust_component(crate_dir: impl AsRef<Path>, artifact_name: &str) -> Result<PathBuf> {
+    // Builds of the same crate from parallel tests would wait on each other
+    // for cargo's lock anyway
+    static BUILD_LOCK: Mutex<()> = Mutex::new(());
+
+    let crate_dir = crate_dir.as_ref();
+    let component_path = crate_dir
+        .join("target/wasm32-wasip2/release")
+        .join(format!("{artifact_name}.wasm"));
+    let _guard = BUILD_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if !component_path.exists() {
+        let status = std::process::Command::new("cargo")
+            .current_dir(crate_dir)
+            .args(["build", "--release", "--target", "wasm32-wasip2"])
+            .status()
+            .context("Failed to execute cargo build")?;
+        if !status.success() {
+            bail!("Failed to compile component in {}", crate_dir.display());
+        }
+    }
+    if !component_path.exists() {
+        bail!(
+            "Component file not found after build: {}",
+            component_path.display()
+        );
+    }
+    Ok(component_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{build_example_component, TEST_COMPONENT_ID};
+
+    #[tokio::test]
+    async fn test_harness_records_denied_requests() -> Result<()> {
+        let harness = TestHarness::new().await?;
+        let wasm = tokio::fs::read(build_example_component().await?).await?;
+        let component_id = harness.load_bytes(TEST_COMPONENT_ID, &wasm).await?;
+        assert_eq!(component_id, TEST_COMPONENT_ID);
+        harness
+            .attach_policy_yaml(
+                &component_id,
+                r#"
+version: "1.0"
+permissions:
+  network:
+    allow:
+      - host: "example.com"
+"#,
+            )
+            .await?;
+        assert!(harness
+            .attach_policy_yaml(&component_id, "permissions: [")
+            .await
+            .is_err());
+
+        let result: Value = harness
+            .call("fetch", serde_json::json!({"url": "https://denied.test/"}))
+            .await?;
+        assert!(result.to_string().contains("HttpRequestDenied"));
+
+        let denial = harness.assert_permission_denied(&component_id, "network");
+        assert_eq!(denial.details["host"], "denied.test");
+        harness.clear_permission_denials();
+        assert!(harness.permission_denials().is_empty());
+        Ok(())
+    }
+}
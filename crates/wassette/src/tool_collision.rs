@@ -0,0 +1,99 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Handling of tools that share a name with a tool of another component.
+//!
+//! Tools are called by name, so when two components export a tool with the
+//! same name neither of them can be called until one is unloaded. A
+//! [`ToolCollisionStrategy`] decides what happens when a component being
+//! loaded exports such a tool: it is registered anyway, the load is
+//! refused, or the tool is registered under a name prefixed with the
+//! component's ID.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// What to do with a tool of a component being loaded when another loaded
+/// component already exports a tool of the same name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolCollisionStrategy {
+    /// Register the tool under the shared name. Calls to that name fail
+    /// until one of the components is unloaded.
+    #[default]
+    Allow,
+    /// Refuse to load the component.
+    Reject,
+    /// Register the tool as `<component_id>_<tool>`.
+    Prefix,
+}
+
+impl fmt::Display for ToolCollisionStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Allow => "allow",
+            Self::Reject => "reject",
+            Self::Prefix => "prefix",
+        })
+    }
+}
+
+/// A tool of a loaded component whose name another component already uses.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ToolCollision {
+    /// Name the component exports the tool under
+    pub tool: String,
+    /// Components that already export a tool of that name
+    pub existing_components: Vec<String>,
+    /// Name the tool was registered under, unless the load was refused
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registered_as: Option<String>,
+}
+
+impl fmt::Display for ToolCollision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (exported by {})",
+            self.tool,
+            self.existing_components.join(", ")
+        )
+    }
+}
+
+/// The tool collisions found while loading a component and the strategy
+/// that was applied to them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ToolCollisions {
+    /// Strategy applied to the colliding tools
+    pub strategy: ToolCollisionStrategy,
+    /// Colliding tools, sorted by name
+    pub tools: Vec<ToolCollision>,
+}
+
+/// Name a colliding tool of `component_id` is registered under with
+/// [`ToolCollisionStrategy::Prefix`]. Characters tool names may not contain
+/// are replaced with `_`.
+pub(crate) fn prefixed_tool_name(component_id: &str, tool: &str) -> String {
+    let prefix: String = component_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{prefix}_{tool}")
+}
+
+/// Format a list of collisions for an error message.
+pub(crate) fn describe_collisions(collisions: &[ToolCollision]) -> String {
+    collisions
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
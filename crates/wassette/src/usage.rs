@@ -0,0 +1,373 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Last-used timestamps for components and their tools (`usage.json`), how
+//! often their calls had to be retried, and the call counters the
+//! [execution metrics](crate::metrics) are computed from.
+//!
+//! Usage is updated in memory on every tool call and written to the
+//! component directory at most every 30 seconds, so it survives restarts
+//! without touching the disk on every call. Anything that needs to know which
+//! components are idle reads this data through the
+//! [`LifecycleManager`](crate::LifecycleManager).
+
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::prometheus::HistogramRecord;
+use crate::CallTiming;
+
+/// File name of the usage file inside the component directory.
+pub const USAGE_FILE_NAME: &str = "usage.json";
+
+/// Number of recent call latencies kept for each tool to compute its
+/// percentiles from.
+pub const LATENCY_SAMPLES: usize = 256;
+
+/// Current usage file schema version.
+const USAGE_VERSION: u32 = 1;
+
+/// Minimum time between two writes of the usage file.
+const USAGE_FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Last-used timestamps of a component, as Unix timestamps in seconds.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComponentUsage {
+    /// Time of the most recent call to any of the component's tools
+    pub last_used: u64,
+    /// Time of the most recent call, keyed by tool name
+    #[serde(default)]
+    pub tools: BTreeMap<String, u64>,
+    /// Number of times a call was retried because the component was briefly
+    /// unavailable while it was being loaded or replaced
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub transient_retries: u64,
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+/// Counters of the finished calls to a tool.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ToolCalls {
+    pub(crate) calls: u64,
+    pub(crate) errors: u64,
+    /// Total time spent instantiating, so the mean survives restarts
+    pub(crate) instantiation_ms: u64,
+    /// Latencies of the most recent calls, newest first
+    #[serde(default)]
+    pub(crate) latencies_ms: VecDeque<u64>,
+    #[serde(default)]
+    pub(crate) durations: HistogramRecord,
+}
+
+/// Usage of a component as kept in memory and in the usage file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct UsageRecord {
+    #[serde(flatten)]
+    pub(crate) usage: ComponentUsage,
+    /// Call counters, keyed by tool name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) calls: BTreeMap<String, ToolCalls>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct UsageFile {
+    version: u32,
+    #[serde(default)]
+    components: BTreeMap<String, UsageRecord>,
+}
+
+struct UsageState {
+    components: BTreeMap<String, UsageRecord>,
+    dirty: bool,
+    last_flush: Instant,
+}
+
+/// In-memory usage table backed by the usage file.
+#[derive(Clone)]
+pub(crate) struct UsageTracker {
+    path: PathBuf,
+    state: Arc<Mutex<UsageState>>,
+    write_lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+async fn read_usage_file(path: &Path) -> Result<BTreeMap<String, UsageRecord>> {
+    let content = match tokio::fs::read_to_string(path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => {
+            return Err(e)
+                .with_context(|| format!("Failed to read usage file at {}", path.display()))
+        }
+    };
+    let file: UsageFile = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse usage file at {}", path.display()))?;
+    if file.version != USAGE_VERSION {
+        anyhow::bail!(
+            "Unsupported usage file version {} in {} (expected {})",
+            file.version,
+            path.display(),
+            USAGE_VERSION
+        );
+    }
+    let mut components = file.components;
+    for record in components.values_mut() {
+        for calls in record.calls.values_mut() {
+            calls.latencies_ms.truncate(LATENCY_SAMPLES);
+            calls.durations = std::mem::take(&mut calls.durations).or_empty();
+        }
+    }
+    Ok(components)
+}
+
+impl UsageTracker {
+    /// Load the usage file at `path`. Usage data is best effort, so an
+    /// unreadable file is logged and replaced on the next flush.
+    pub(crate) async fn load(path: PathBuf) -> Self {
+        let components = match read_usage_file(&path).await {
+            Ok(components) => components,
+            Err(error) => {
+                warn!(%error, "Ignoring unreadable usage file");
+                BTreeMap::new()
+            }
+        };
+        Self {
+            path,
+            state: Arc::new(Mutex::new(UsageState {
+                components,
+                dirty: false,
+                last_flush: Instant::now(),
+            })),
+            write_lock: Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    /// Record a call to `tool_name` of `component_id`, once the call found
+    /// the tool, writing the usage file in the background when the flush
+    /// interval has passed. Only calls recorded here have their outcome
+    /// counted by [`Self::record_finished`].
+    pub(crate) fn record(&self, component_id: &str, tool_name: &str) {
+        let now = unix_now();
+        let mut state = self.state.lock().expect("usage lock poisoned");
+        let record = state
+            .components
+            .entry(component_id.to_string())
+            .or_default();
+        record.usage.last_used = now;
+        record.usage.tools.insert(tool_name.to_string(), now);
+        record.calls.entry(tool_name.to_string()).or_default();
+        self.changed(state);
+    }
+
+    /// Count a finished call to `tool_name` of `component_id`, with how long
+    /// it took and whether it failed.
+    pub(crate) fn record_finished(
+        &self,
+        component_id: &str,
+        tool_name: &str,
+        timing: &CallTiming,
+        ok: bool,
+    ) {
+        let mut state = self.state.lock().expect("usage lock poisoned");
+        let Some(calls) = state
+            .components
+            .get_mut(component_id)
+            .and_then(|record| record.calls.get_mut(tool_name))
+        else {
+            return;
+        };
+        calls.calls += 1;
+        if !ok {
+            calls.errors += 1;
+        }
+        calls.instantiation_ms += timing.instantiation_ms;
+        calls.latencies_ms.push_front(timing.total_ms);
+        calls.latencies_ms.truncate(LATENCY_SAMPLES);
+        calls.durations.observe(timing.total_ms);
+        self.changed(state);
+    }
+
+    /// Mark the usage as changed, and write it in the background when the
+    /// flush interval has passed.
+    fn changed(&self, mut state: std::sync::MutexGuard<'_, UsageState>) {
+        state.dirty = true;
+        let flush_due = state.last_flush.elapsed() >= USAGE_FLUSH_INTERVAL;
+        drop(state);
+        if flush_due {
+            let tracker = self.clone();
+            tokio::spawn(async move {
+                if let Err(error) = tracker.flush().await {
+                    warn!(%error, "Failed to write usage file");
+                }
+            });
+        }
+    }
+
+    /// Count `retries` transient retries of a call to `component_id`. They
+    /// are written with the next flush.
+    pub(crate) fn record_retries(&self, component_id: &str, retries: u32) {
+        let mut state = self.state.lock().expect("usage lock poisoned");
+        state
+            .components
+            .entry(component_id.to_string())
+            .or_default()
+            .usage
+            .transient_retries += u64::from(retries);
+        state.dirty = true;
+    }
+
+    /// Forget the usage of an unloaded component.
+    pub(crate) async fn remove(&self, component_id: &str) -> Result<()> {
+        let removed = {
+            let mut state = self.state.lock().expect("usage lock poisoned");
+            let removed = state.components.remove(component_id).is_some();
+            state.dirty |= removed;
+            removed
+        };
+        if removed {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn get(&self, component_id: &str) -> Option<ComponentUsage> {
+        let state = self.state.lock().expect("usage lock poisoned");
+        state
+            .components
+            .get(component_id)
+            .map(|record| record.usage.clone())
+    }
+
+    pub(crate) fn snapshot(&self) -> BTreeMap<String, ComponentUsage> {
+        let state = self.state.lock().expect("usage lock poisoned");
+        state
+            .components
+            .iter()
+            .map(|(id, record)| (id.clone(), record.usage.clone()))
+            .collect()
+    }
+
+    /// The usage and call counters of `component_id`.
+    pub(crate) fn record_of(&self, component_id: &str) -> Option<UsageRecord> {
+        let state = self.state.lock().expect("usage lock poisoned");
+        state.components.get(component_id).cloned()
+    }
+
+    /// The usage and call counters of every component that has been called.
+    pub(crate) fn records(&self) -> BTreeMap<String, UsageRecord> {
+        let state = self.state.lock().expect("usage lock poisoned");
+        state.components.clone()
+    }
+
+    /// Write pending usage updates to disk, replacing the file atomically.
+    pub(crate) async fn flush(&self) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        let file = {
+            let mut state = self.state.lock().expect("usage lock poisoned");
+            if !state.dirty {
+                return Ok(());
+            }
+            state.dirty = false;
+            state.last_flush = Instant::now();
+            UsageFile {
+                version: USAGE_VERSION,
+                components: state.components.clone(),
+            }
+        };
+
+        let json = serde_json::to_string_pretty(&file).context("Failed to serialize usage")?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        let result = async {
+            tokio::fs::write(&tmp_path, json)
+                .await
+                .with_context(|| format!("Failed to write usage to {}", tmp_path.display()))?;
+            tokio::fs::rename(&tmp_path, &self.path)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to move usage file into place at {}",
+                        self.path.display()
+                    )
+                })
+        }
+        .await;
+        if result.is_err() {
+            // Keep the updates so the next flush retries them.
+            self.state.lock().expect("usage lock poisoned").dirty = true;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_usage_round_trip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(USAGE_FILE_NAME);
+
+        let tracker = UsageTracker::load(path.clone()).await;
+        assert!(tracker.get("fetch").is_none());
+
+        tracker.record("fetch", "fetch");
+        tracker.record("time", "get-time");
+        tracker.record_retries("fetch", 2);
+        let usage = tracker.get("fetch").unwrap();
+        assert!(usage.last_used > 0);
+        assert_eq!(usage.tools["fetch"], usage.last_used);
+        assert_eq!(usage.transient_retries, 2);
+
+        // Only calls to tools that were found are counted
+        let timing = CallTiming {
+            total_ms: 7,
+            instantiation_ms: 2,
+            ..CallTiming::default()
+        };
+        tracker.record_finished("fetch", "fetch", &timing, false);
+        tracker.record_finished("fetch", "head", &timing, true);
+        let calls = &tracker.record_of("fetch").unwrap().calls;
+        assert_eq!(calls.keys().collect::<Vec<_>>(), vec!["fetch"]);
+        assert_eq!((calls["fetch"].calls, calls["fetch"].errors), (1, 1));
+        assert_eq!(calls["fetch"].latencies_ms, [7]);
+
+        tracker.flush().await?;
+        let reloaded = UsageTracker::load(path.clone()).await;
+        assert_eq!(reloaded.records(), tracker.records());
+
+        reloaded.remove("time").await?;
+        let reloaded = UsageTracker::load(path).await;
+        assert_eq!(
+            reloaded.snapshot().keys().collect::<Vec<_>>(),
+            vec!["fetch"]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_usage_file_starts_empty() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(USAGE_FILE_NAME);
+        tokio::fs::write(&path, "not json").await?;
+
+        let tracker = UsageTracker::load(path).await;
+        assert!(tracker.snapshot().is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Components whose tools are hidden from tool listings (`visibility.json`).
+//!
+//! A hidden component stays loaded and its tools can still be called by
+//! name, but the MCP server leaves them out of `tools/list`. This is meant
+//! for utility components that other tools depend on but that should not be
+//! offered to the model directly. The set is written to the component
+//! directory on every change, so it survives restarts even for components
+//! that have not been compiled yet.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// File name of the visibility file inside the component directory.
+pub const VISIBILITY_FILE_NAME: &str = "visibility.json";
+
+/// Current visibility file schema version.
+const VISIBILITY_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct VisibilityFile {
+    version: u32,
+    #[serde(default)]
+    hidden: BTreeSet<String>,
+}
+
+/// In-memory set of hidden components backed by the visibility file.
+#[derive(Clone)]
+pub(crate) struct HiddenComponents {
+    path: PathBuf,
+    hidden: Arc<RwLock<BTreeSet<String>>>,
+    write_lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+async fn read_visibility_file(path: &Path) -> Result<BTreeSet<String>> {
+    let content = match tokio::fs::read_to_string(path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeSet::new()),
+        Err(e) => {
+            return Err(e)
+                .with_context(|| format!("Failed to read visibility file at {}", path.display()))
+        }
+    };
+    let file: VisibilityFile = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse visibility file at {}", path.display()))?;
+    if file.version != VISIBILITY_VERSION {
+        anyhow::bail!(
+            "Unsupported visibility file version {} in {} (expected {})",
+            file.version,
+            path.display(),
+            VISIBILITY_VERSION
+        );
+    }
+    Ok(file.hidden)
+}
+
+impl HiddenComponents {
+    /// Load the visibility file at `path`. An unreadable file is logged and
+    /// treated as empty, so a corrupt file never hides tools by accident.
+    pub(crate) async fn load(path: PathBuf) -> Self {
+        let hidden = match read_visibility_file(&path).await {
+            Ok(hidden) => hidden,
+            Err(error) => {
+                warn!(%error, "Ignoring unreadable visibility file");
+                BTreeSet::new()
+            }
+        };
+        Self {
+            path,
+            hidden: Arc::new(RwLock::new(hidden)),
+            write_lock: Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    pub(crate) fn is_hidden(&self, component_id: &str) -> bool {
+        self.hidden
+            .read()
+            .expect("visibility lock poisoned")
+            .contains(component_id)
+    }
+
+    /// Hide or show a component, writing the visibility file if anything
+    /// changed. Returns whether the component's visibility changed.
+    pub(crate) async fn set(&self, component_id: &str, hidden: bool) -> Result<bool> {
+        let _guard = self.write_lock.lock().await;
+        let snapshot = {
+            let mut set = self.hidden.write().expect("visibility lock poisoned");
+            let changed = if hidden {
+                set.insert(component_id.to_string())
+            } else {
+                set.remove(component_id)
+            };
+            if !changed {
+                return Ok(false);
+            }
+            set.clone()
+        };
+
+        let file = VisibilityFile {
+            version: VISIBILITY_VERSION,
+            hidden: snapshot,
+        };
+        let json = serde_json::to_string_pretty(&file).context("Failed to serialize visibility")?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        let result = async {
+            tokio::fs::write(&tmp_path, json)
+                .await
+                .with_context(|| format!("Failed to write visibility to {}", tmp_path.display()))?;
+            tokio::fs::rename(&tmp_path, &self.path)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to move visibility file into place at {}",
+                        self.path.display()
+                    )
+                })
+        }
+        .await;
+        if result.is_err() {
+            // Keep memory and disk in agreement.
+            let mut set = self.hidden.write().expect("visibility lock poisoned");
+            if hidden {
+                set.remove(component_id);
+            } else {
+                set.insert(component_id.to_string());
+            }
+        }
+        result.map(|()| true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_hidden_components_persist() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(VISIBILITY_FILE_NAME);
+
+        let hidden = HiddenComponents::load(path.clone()).await;
+        assert!(!hidden.is_hidden("helper"));
+        assert!(hidden.set("helper", true).await?);
+        assert!(!hidden.set("helper", true).await?);
+        assert!(hidden.is_hidden("helper"));
+
+        let reloaded = HiddenComponents::load(path.clone()).await;
+        assert!(reloaded.is_hidden("helper"));
+        assert!(reloaded.set("helper", false).await?);
+        assert!(!HiddenComponents::load(path).await.is_hidden("helper"));
+        Ok(())
+    }
+}
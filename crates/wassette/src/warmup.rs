@@ -0,0 +1,116 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Per-component warm-up run in the background after a component is loaded.
+//!
+//! The first call into a freshly compiled component pays for instantiation and
+//! any lazy initialization the guest performs. A warm-up performs a number of
+//! instantiations and can invoke a cheap tool, so that cost is paid before
+//! the first real request arrives. The instances are dropped afterwards,
+//! unless the instance pool is enabled and can take them.
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+
+fn default_instances() -> u32 {
+    1
+}
+
+/// Warm-up settings for a single component.
+///
+/// In the wassette config file this is keyed by component ID:
+///
+/// ```toml
+/// [warmup.fetch_rs]
+/// instances = 2
+/// tool = "ping"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WarmupConfig {
+    /// Number of instantiations to perform
+    #[serde(default = "default_instances")]
+    pub instances: u32,
+    /// Name of a tool exported by the component to invoke with no arguments
+    /// after the instantiations, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+}
+
+impl Default for WarmupConfig {
+    fn default() -> Self {
+        Self {
+            instances: default_instances(),
+            tool: None,
+        }
+    }
+}
+
+/// Timing of a completed warm-up run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WarmupReport {
+    /// Number of instantiations that succeeded
+    pub instances: u32,
+    /// Total time spent instantiating, in milliseconds
+    pub instantiation_ms: u64,
+    /// Tool that was invoked, if configured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    /// Time the tool call took, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_ms: Option<u64>,
+    /// Wall-clock time of the whole warm-up, including time spent waiting
+    /// for other warm-ups to finish
+    pub total_ms: u64,
+    /// Why the warm-up stopped early
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Serializes warm-ups so they only use spare capacity and never compete
+/// with each other for CPU while components are still loading.
+#[derive(Clone)]
+pub(crate) struct WarmupScheduler {
+    permits: Arc<Semaphore>,
+}
+
+impl WarmupScheduler {
+    pub(crate) fn new() -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(1)),
+        }
+    }
+
+    pub(crate) fn permits(&self) -> Arc<Semaphore> {
+        Arc::clone(&self.permits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warmup_config_defaults() {
+        let warmup: std::collections::HashMap<String, WarmupConfig> = serde_json::from_str(
+            r#"{"fetch_rs": {"tool": "ping"}, "time_server": {"instances": 3}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            warmup["fetch_rs"],
+            WarmupConfig {
+                instances: 1,
+                tool: Some("ping".to_string()),
+            }
+        );
+        assert_eq!(
+            warmup["time_server"],
+            WarmupConfig {
+                instances: 3,
+                tool: None,
+            }
+        );
+    }
+}
@@ -4,43 +4,144 @@
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use policy::{AccessType, PolicyDocument};
+use anyhow::Context;
+use policy::{
+    AccessType, CallPermission, ClockSettings, NetworkHostPermission, NetworkPermission,
+    PermissionList, PolicyDocument, StdioMode,
+};
 use wasmtime::component::ResourceTable;
 use wasmtime_wasi::{WasiCtx, WasiCtxBuilder, WasiCtxView};
 use wasmtime_wasi_config::WasiConfigVariables;
 use wasmtime_wasi_http::{WasiHttpCtx, WasiHttpView};
 
+use crate::broker::Broker;
+use crate::path_guard::{ensure_within, has_parent_segment};
+use crate::scratch::ScratchDir;
+use crate::stdio::{CapturePipe, CapturedOutput, StderrLines, MAX_CAPTURED_BYTES};
+
+/// Fuel a call gets for each core of its policy's `cpu` limit, so `500m`
+/// allows half as much work as `1`. A unit of fuel is roughly one
+/// WebAssembly instruction.
+pub const FUEL_PER_CPU_CORE: u64 = 1_000_000_000;
+
+/// Default share of the host's RAM a component may be granted as its memory
+/// limit.
+pub const DEFAULT_MAX_MEMORY_GRANT_FRACTION: f64 = 0.75;
+
 /// Represents a permission-related error that occurred during component execution
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, thiserror::Error)]
 pub enum PermissionError {
     /// Network access was denied for a specific host
+    #[error("network access to host '{host}'{} was denied", denied_on(scheme.as_deref(), *port))]
     NetworkDenied {
         /// The host that was denied
         host: String,
         /// The full URI that was requested
         uri: String,
+        /// The scheme of the request, when the host is allowed but not over
+        /// this scheme
+        scheme: Option<String>,
+        /// The port of the request, when the host is allowed but not on this
+        /// port
+        port: Option<u16>,
     },
     /// Storage access was denied for a specific path
+    #[error("{access_type} access to '{path}' was denied")]
     StorageDenied {
         /// The path that was denied
         path: String,
         /// The access type that was requested (read/write)
         access_type: String,
     },
+    /// An allowed network request did not get a response in time
+    #[error("request to host '{host}' timed out after {}ms", timeout.as_millis())]
+    NetworkTimeout {
+        /// The host that timed out
+        host: String,
+        /// The full URI that was requested
+        uri: String,
+        /// The timeout that was exceeded
+        timeout: Duration,
+    },
+    /// The embedder's permission hook refused to instantiate the component
+    #[error("instantiation was denied by the permission hook: {reason}")]
+    Denied {
+        /// The reason given by the hook
+        reason: String,
+    },
+    /// A storage rule refers to a host path that does not exist
+    #[error("storage rule '{uri}' refers to host path '{}', which does not exist", host_path.display())]
+    StoragePathMissing {
+        /// URI of the storage rule
+        uri: String,
+        /// The host path the URI resolves to
+        host_path: PathBuf,
+    },
+    /// A brokered call to a component tool the policy does not allow
+    #[error("call to tool '{tool}' of component '{component}' was denied")]
+    CallDenied {
+        /// The component that was called
+        component: String,
+        /// The tool that was called
+        tool: String,
+    },
+}
+
+/// ` over http on port 8080`, naming what a network denial was about
+/// beyond the host.
+fn denied_on(scheme: Option<&str>, port: Option<u16>) -> String {
+    let mut target = String::new();
+    if let Some(scheme) = scheme {
+        target.push_str(&format!(" over {scheme}"));
+    }
+    if let Some(port) = port {
+        target.push_str(&format!(" on port {port}"));
+    }
+    target
 }
 
 impl PermissionError {
     /// Get a user-friendly error message with instructions on how to fix
     pub fn to_user_message(&self, component_id: &str) -> String {
         match self {
-            PermissionError::NetworkDenied { host, uri } => {
+            PermissionError::NetworkDenied {
+                host,
+                uri,
+                scheme,
+                port,
+            } if scheme.is_some() || port.is_some() => {
+                let mut grant = format!(
+                    "grant-network-permission --component-id=\"{component_id}\" --host=\"{host}\""
+                );
+                if let Some(port) = port {
+                    grant.push_str(&format!(" --port={port}"));
+                }
+                if let Some(scheme) = scheme {
+                    grant.push_str(&format!(" --scheme=\"{scheme}\""));
+                }
                 format!(
+                    "Network permission denied: Component '{component_id}' attempted to access '{uri}', but host '{host}' is not allowed{}.\n\n\
+                    To grant network access, use:\n  {grant}",
+                    denied_on(scheme.as_deref(), *port)
+                )
+            }
+            PermissionError::NetworkDenied { host, uri, .. } => {
+                let message = format!(
                     "Network permission denied: Component '{}' attempted to access '{}' but does not have permission for host '{}'.\n\n\
                     To grant network access, use:\n  \
                     grant-network-permission --component-id=\"{}\" --host=\"{}\"",
                     component_id, uri, host, component_id, host
-                )
+                );
+                match crate::http::narrowest_wildcard(host) {
+                    Some(wildcard) => format!(
+                        "{message}\n\n\
+                        To allow its sibling subdomains as well, use:\n  \
+                        grant-network-permission --component-id=\"{component_id}\" --host=\"{wildcard}\""
+                    ),
+                    None => message,
+                }
             }
             PermissionError::StorageDenied { path, access_type } => {
                 format!(
@@ -50,20 +151,164 @@ impl PermissionError {
                     component_id, access_type, path, component_id, path, access_type
                 )
             }
+            PermissionError::NetworkTimeout { host, uri, timeout } => {
+                format!(
+                    "Network request timed out: Component '{}' requested '{}' but host '{}' did not respond within {}ms.\n\n\
+                    To allow more time, set a timeout on the network rule in the component policy:\n  \
+                    network:\n    allow:\n      - host: \"{}\"\n        timeout: \"2m\"",
+                    component_id, uri, host, timeout.as_millis(), host
+                )
+            }
+            PermissionError::Denied { reason } => {
+                format!(
+                    "Permission denied: Component '{}' was not allowed to run: {}",
+                    component_id, reason
+                )
+            }
+            PermissionError::StoragePathMissing { uri, host_path } => {
+                format!(
+                    "Storage path missing: Component '{}' is granted '{}', but the host path '{}' does not exist.\n\n\
+                    Create the directory, or grant the permission again and let wassette create it:\n  \
+                    grant-storage-permission --component-id=\"{}\" --uri=\"{}\" --access=\"read,write\" --createIfMissing=true",
+                    component_id,
+                    uri,
+                    host_path.display(),
+                    component_id,
+                    uri
+                )
+            }
+            PermissionError::CallDenied { component, tool } => {
+                format!(
+                    "Call permission denied: Component '{}' attempted to call tool '{}' of component '{}' but does not have permission.\n\n\
+                    To allow the call, add a call rule to the component policy:\n  \
+                    call:\n    allow:\n      - component: \"{}\"\n        tools: [\"{}\"]",
+                    component_id, tool, component, component, tool
+                )
+            }
         }
     }
 }
 
+/// Number of grants listed when a permission error reports what the
+/// component was allowed.
+const GRANT_SUMMARY_LIMIT: usize = 10;
+
+impl PermissionError {
+    /// The permission type and details announced for this error with
+    /// [`LifecycleEvent::PermissionDenied`](crate::LifecycleEvent::PermissionDenied),
+    /// or `None` for errors that are not denials.
+    pub(crate) fn denial_details(&self) -> Option<(&'static str, serde_json::Value)> {
+        match self {
+            PermissionError::NetworkDenied {
+                host,
+                uri,
+                scheme,
+                port,
+            } => {
+                let mut details = serde_json::json!({ "host": host, "uri": uri });
+                if let Some(scheme) = scheme {
+                    details["scheme"] = serde_json::json!(scheme);
+                }
+                if let Some(port) = port {
+                    details["port"] = serde_json::json!(port);
+                }
+                Some(("network", details))
+            }
+            PermissionError::StorageDenied { path, access_type } => Some((
+                "storage",
+                serde_json::json!({ "path": path, "access": access_type }),
+            )),
+            PermissionError::Denied { reason } => {
+                Some(("hook", serde_json::json!({ "reason": reason })))
+            }
+            PermissionError::CallDenied { component, tool } => Some((
+                "call",
+                serde_json::json!({ "component": component, "tool": tool }),
+            )),
+            PermissionError::NetworkTimeout { .. } | PermissionError::StoragePathMissing { .. } => {
+                None
+            }
+        }
+    }
+
+    /// Like [`Self::to_user_message`], followed by the grants of `template`
+    /// that relate to the denial, so a near miss such as `example.com`
+    /// against `www.example.com` shows up without another round trip.
+    pub fn to_user_message_with_grants(
+        &self,
+        component_id: &str,
+        template: &WasiStateTemplate,
+    ) -> String {
+        let message = self.to_user_message(component_id);
+        let (label, mut grants): (&str, Vec<String>) = match self {
+            PermissionError::NetworkDenied { .. } => (
+                "Allowed hosts",
+                template.allowed_hosts.iter().cloned().collect(),
+            ),
+            PermissionError::StorageDenied { .. } => (
+                "Granted storage",
+                template
+                    .preopened_dirs
+                    .iter()
+                    .map(|dir| format!("fs://{} ({})", dir.guest_path, dir.access_description()))
+                    .chain(
+                        template
+                            .scratch
+                            .iter()
+                            .map(|scratch| format!("fs://{} (scratch)", scratch.guest_path)),
+                    )
+                    .collect(),
+            ),
+            _ => return message,
+        };
+        if grants.is_empty() {
+            return format!("{message}\n\n{label}: none");
+        }
+        grants.sort();
+        let total = grants.len();
+        grants.truncate(GRANT_SUMMARY_LIMIT);
+        let mut summary = grants.join(", ");
+        if total > GRANT_SUMMARY_LIMIT {
+            summary.push_str(&format!(" and {} more", total - GRANT_SUMMARY_LIMIT));
+        }
+        format!("{message}\n\n{label}: {summary}")
+    }
+}
+
 /// Custom resource limiter that stores the limits
 #[derive(Clone)]
 pub struct CustomResourceLimiter {
     limits: wasmtime::StoreLimits,
+    /// The memory limit `limits` was built from
+    memory_limit: Option<u64>,
+    /// Bytes of linear memory the store has been allowed to grow to
+    memory_used: usize,
 }
 
 impl CustomResourceLimiter {
     /// Create a new CustomResourceLimiter with the given limits
     pub fn new(limits: wasmtime::StoreLimits) -> Self {
-        Self { limits }
+        Self {
+            limits,
+            memory_limit: None,
+            memory_used: 0,
+        }
+    }
+
+    /// Record the memory limit `limits` enforces, so it can be reported.
+    pub(crate) fn with_memory_limit(mut self, memory_limit: Option<u64>) -> Self {
+        self.memory_limit = memory_limit;
+        self
+    }
+
+    /// The memory limit in bytes, if one is enforced.
+    pub fn memory_limit(&self) -> Option<u64> {
+        self.memory_limit
+    }
+
+    /// Bytes of linear memory currently allocated by the store.
+    pub fn memory_used(&self) -> usize {
+        self.memory_used
     }
 }
 
@@ -74,7 +319,11 @@ impl wasmtime::ResourceLimiter for CustomResourceLimiter {
         desired: usize,
         _maximum: Option<usize>,
     ) -> anyhow::Result<bool> {
-        self.limits.memory_growing(current, desired, _maximum)
+        let allowed = self.limits.memory_growing(current, desired, _maximum)?;
+        if allowed {
+            self.memory_used += desired.saturating_sub(current);
+        }
+        Ok(allowed)
     }
 
     fn table_growing(
@@ -93,8 +342,42 @@ pub struct WasiState {
     pub http: wasmtime_wasi_http::WasiHttpCtx,
     pub wasi_config_vars: WasiConfigVariables,
     pub resource_limiter: Option<CustomResourceLimiter>,
+    /// Whether the component may read its limits through `wassette:limits/get`
+    pub introspection: bool,
+    /// Routes the component's calls through `wassette:broker/call`; set by
+    /// the lifecycle manager when the policy allows calls
+    pub(crate) broker: Option<Broker>,
+    /// When the running call is stopped, if it has a deadline
+    pub(crate) execution_deadline: Option<Instant>,
     /// Tracks the last permission error that occurred during execution
     pub last_permission_error: Arc<Mutex<Option<PermissionError>>>,
+    /// Captured stdout, if the component's stdout is captured
+    pub(crate) stdout_capture: Option<CapturePipe>,
+    /// Captured stderr, if the component's stderr is captured
+    pub(crate) stderr_capture: Option<CapturePipe>,
+}
+
+impl WasiState {
+    /// What the component wrote so far to the streams that are captured.
+    pub fn captured_output(&self) -> CapturedOutput {
+        let contents = |pipe: &Option<CapturePipe>| {
+            pipe.as_ref().map(CapturePipe::contents).unwrap_or_default()
+        };
+        CapturedOutput {
+            stdout: contents(&self.stdout_capture),
+            stderr: contents(&self.stderr_capture),
+        }
+    }
+}
+
+/// Where a stream goes: nowhere if it is not allowed, otherwise where the
+/// policy says, or `default` if the policy does not say.
+fn stdio_mode(allowed: bool, mode: Option<StdioMode>, default: StdioMode) -> StdioMode {
+    if allowed {
+        mode.unwrap_or(default)
+    } else {
+        StdioMode::Deny
+    }
 }
 
 impl wasmtime_wasi::WasiView for WasiState {
@@ -117,15 +400,58 @@ impl WasiHttpView for WasiState {
 }
 
 impl WasiStateTemplate {
-    /// Creates a new `WasiState` from the template.
+    /// Creates a new `WasiState` from the template. Streams the template
+    /// leaves to the host are inherited.
     pub fn build(&self) -> anyhow::Result<WasiState> {
+        self.build_with_default_stdio(StdioMode::Inherit)
+    }
+
+    /// Creates a new `WasiState` from the template, sending stdout and
+    /// stderr where `default_stdio` says unless the template decides.
+    /// Captured streams keep up to [`MAX_CAPTURED_BYTES`] each.
+    pub fn build_with_default_stdio(&self, default_stdio: StdioMode) -> anyhow::Result<WasiState> {
+        self.build_for_call(default_stdio, None)
+    }
+
+    /// Same as [`Self::build_with_default_stdio`], also sending the lines of
+    /// stderr to `stderr_lines`, if given, in place of the host's stderr.
+    pub(crate) fn build_for_call(
+        &self,
+        default_stdio: StdioMode,
+        stderr_lines: Option<StderrLines>,
+    ) -> anyhow::Result<WasiState> {
         let mut ctx_builder = WasiCtxBuilder::new();
-        if self.allow_stdout {
-            ctx_builder.inherit_stdout();
-        }
-        if self.allow_stderr {
-            ctx_builder.inherit_stderr();
-        }
+        let stdout_capture = match stdio_mode(self.allow_stdout, self.stdout, default_stdio) {
+            StdioMode::Capture => {
+                let pipe = CapturePipe::new(MAX_CAPTURED_BYTES);
+                ctx_builder.stdout(pipe.clone());
+                Some(pipe)
+            }
+            StdioMode::Inherit => {
+                ctx_builder.inherit_stdout();
+                None
+            }
+            StdioMode::Deny => None,
+        };
+        let stderr_capture = match stdio_mode(self.allow_stderr, self.stderr, default_stdio) {
+            StdioMode::Capture => {
+                let pipe = CapturePipe::new(MAX_CAPTURED_BYTES).forwarding_lines(stderr_lines);
+                ctx_builder.stderr(pipe.clone());
+                Some(pipe)
+            }
+            StdioMode::Inherit => {
+                match stderr_lines {
+                    Some(lines) => {
+                        ctx_builder.stderr(CapturePipe::new(0).forwarding_lines(Some(lines)));
+                    }
+                    None => {
+                        ctx_builder.inherit_stderr();
+                    }
+                }
+                None
+            }
+            StdioMode::Deny => None,
+        };
         ctx_builder.inherit_args();
         if self.allow_args {
             ctx_builder.inherit_args();
@@ -142,12 +468,44 @@ impl WasiStateTemplate {
         }
         ctx_builder.allow_udp(self.network_perms.allow_udp);
         for preopened_dir in &self.preopened_dirs {
-            ctx_builder.preopened_dir(
-                preopened_dir.host_path.as_path(),
-                preopened_dir.guest_path.as_str(),
-                preopened_dir.dir_perms,
-                preopened_dir.file_perms,
-            )?;
+            let uri = format!("fs://{}", preopened_dir.guest_path);
+            if !preopened_dir.host_path.exists() {
+                return Err(PermissionError::StoragePathMissing {
+                    uri,
+                    host_path: preopened_dir.host_path.clone(),
+                }
+                .into());
+            }
+            ctx_builder
+                .preopened_dir(
+                    preopened_dir.host_path.as_path(),
+                    preopened_dir.guest_path.as_str(),
+                    preopened_dir.dir_perms,
+                    preopened_dir.file_perms,
+                )
+                .with_context(|| {
+                    format!(
+                        "Failed to open host path '{}' for storage rule '{}'",
+                        preopened_dir.host_path.display(),
+                        uri
+                    )
+                })?;
+        }
+        if let Some(scratch) = &self.scratch {
+            scratch.ensure_exists()?;
+            ctx_builder
+                .preopened_dir(
+                    scratch.host_path.as_path(),
+                    scratch.guest_path.as_str(),
+                    wasmtime_wasi::DirPerms::all(),
+                    wasmtime_wasi::FilePerms::all(),
+                )
+                .with_context(|| {
+                    format!(
+                        "Failed to open scratch directory '{}'",
+                        scratch.host_path.display()
+                    )
+                })?;
         }
 
         // Inject forwarded config variables as real WASI environment variables so that
@@ -155,17 +513,34 @@ impl WasiStateTemplate {
         for (k, v) in &self.config_vars {
             ctx_builder.env(k, v);
         }
+        if let Some(fixed_time) = self.fixed_time {
+            ctx_builder.wall_clock(FixedWallClock(fixed_time));
+        }
 
         Ok(WasiState {
             ctx: ctx_builder.build(),
             table: wasmtime_wasi::ResourceTable::default(),
             http: WasiHttpCtx::new(),
             wasi_config_vars: WasiConfigVariables::from_iter(self.config_vars.clone()),
+            // A component that reads its limits gets a limiter even without a
+            // memory limit, so its memory use is tracked
             resource_limiter: self
                 .store_limits
                 .as_ref()
-                .map(|limits| CustomResourceLimiter::new(limits.clone())),
+                .map(|limits| {
+                    CustomResourceLimiter::new(limits.clone()).with_memory_limit(self.memory_limit)
+                })
+                .or_else(|| {
+                    self.introspection.then(|| {
+                        CustomResourceLimiter::new(wasmtime::StoreLimitsBuilder::new().build())
+                    })
+                }),
+            introspection: self.introspection,
+            broker: None,
+            execution_deadline: None,
             last_permission_error: Arc::new(Mutex::new(None)),
+            stdout_capture,
+            stderr_capture,
         })
     }
 }
@@ -179,6 +554,19 @@ pub struct PreopenedDir {
     pub file_perms: wasmtime_wasi::FilePerms,
 }
 
+impl PreopenedDir {
+    /// The access the directory is opened with, e.g. `read, write`.
+    fn access_description(&self) -> &'static str {
+        let read = self.file_perms.contains(wasmtime_wasi::FilePerms::READ);
+        let write = self.file_perms.contains(wasmtime_wasi::FilePerms::WRITE);
+        match (read, write) {
+            (true, true) => "read, write",
+            (false, true) => "write",
+            _ => "read",
+        }
+    }
+}
+
 /// A struct that presents the network permissions passed to wasmtime_wasi::WasiContextBuilder
 #[derive(Default, Clone)]
 pub struct NetworkPermissions {
@@ -195,6 +583,12 @@ pub struct WasiStateTemplate {
     pub allow_stdout: bool,
     /// Whether to allow stderr access
     pub allow_stderr: bool,
+    /// Where stdout goes, when allowed; the lifecycle manager's default
+    /// when unset
+    pub stdout: Option<StdioMode>,
+    /// Where stderr goes, when allowed; the lifecycle manager's default
+    /// when unset
+    pub stderr: Option<StdioMode>,
     /// Whether to allow command line arguments access
     pub allow_args: bool,
     /// Network permissions configuration
@@ -205,10 +599,30 @@ pub struct WasiStateTemplate {
     pub preopened_dirs: Vec<PreopenedDir>,
     /// Allowed network hosts for HTTP requests
     pub allowed_hosts: HashSet<String>,
+    /// Networks, in CIDR notation, HTTP requests to an IP address may not
+    /// go to
+    pub denied_networks: HashSet<String>,
+    /// Per-rule outbound HTTP timeouts, keyed by the allowed host entry
+    pub network_timeouts: HashMap<String, Duration>,
     /// Memory limit in bytes for the component
     pub memory_limit: Option<u64>,
     /// Store limits for wasmtime (built from memory_limit)
     pub store_limits: Option<wasmtime::StoreLimits>,
+    /// Instantiation timeout overriding the lifecycle manager default
+    pub instantiation_timeout: Option<Duration>,
+    /// Execution timeout overriding the lifecycle manager default
+    pub execution_timeout: Option<Duration>,
+    /// Fuel each call may consume, from the policy's CPU limit
+    pub fuel_limit: Option<u64>,
+    /// Whether the component may read its limits through `wassette:limits/get`
+    pub introspection: bool,
+    /// Host-managed scratch directory, preopened read-write
+    pub scratch: Option<ScratchDir>,
+    /// Components and tools the component may call through
+    /// `wassette:broker/call`
+    pub calls: Option<PermissionList<CallPermission>>,
+    /// Time since the Unix epoch the component's wall clock is frozen at
+    pub fixed_time: Option<Duration>,
 }
 
 impl Default for WasiStateTemplate {
@@ -216,13 +630,24 @@ impl Default for WasiStateTemplate {
         Self {
             allow_stdout: true,
             allow_stderr: true,
+            stdout: None,
+            stderr: None,
             allow_args: true,
             network_perms: NetworkPermissions::default(),
             config_vars: HashMap::new(),
             preopened_dirs: Vec::new(),
             allowed_hosts: HashSet::new(),
+            denied_networks: HashSet::new(),
+            network_timeouts: HashMap::new(),
             memory_limit: None,
             store_limits: None,
+            instantiation_timeout: None,
+            execution_timeout: None,
+            fuel_limit: None,
+            introspection: false,
+            scratch: None,
+            calls: None,
+            fixed_time: None,
         }
     }
 }
@@ -234,11 +659,45 @@ pub fn create_wasi_state_template_from_policy(
     environment_vars: &HashMap<String, String>,
     secrets: Option<&HashMap<String, String>>,
 ) -> anyhow::Result<WasiStateTemplate> {
-    let env_vars = extract_env_vars(policy, environment_vars, secrets)?;
+    create_wasi_state_template_with_env_inherit(
+        policy,
+        component_dir,
+        environment_vars,
+        secrets,
+        false,
+    )
+}
+
+/// Same as [`create_wasi_state_template_from_policy`], but honors
+/// `environment.inheritAll` when `allow_env_inherit` is set.
+pub(crate) fn create_wasi_state_template_with_env_inherit(
+    policy: &PolicyDocument,
+    component_dir: &Path,
+    environment_vars: &HashMap<String, String>,
+    secrets: Option<&HashMap<String, String>>,
+    allow_env_inherit: bool,
+) -> anyhow::Result<WasiStateTemplate> {
+    let mut config_vars = extract_env_vars(policy, environment_vars, secrets, allow_env_inherit)?;
+    // Values from the policy's `config` section win over environment variables
+    config_vars.extend(policy.config.clone());
+    let clock = extract_clock_settings(policy);
+    if let Some(timezone) = clock.and_then(|clock| clock.timezone.as_ref()) {
+        config_vars.insert("TZ".to_string(), timezone.clone());
+    }
+    let fixed_time = clock
+        .map(ClockSettings::fixed_time_since_epoch)
+        .transpose()?
+        .flatten();
+    let system = policy.permissions.system.as_ref();
     let network_perms = extract_network_perms(policy);
     let preopened_dirs = extract_storage_permissions(policy, component_dir)?;
     let allowed_hosts = extract_allowed_hosts(policy);
+    let denied_networks = extract_denied_networks(policy);
+    let network_timeouts = extract_network_timeouts(policy)?;
     let memory_limit = extract_memory_limit(policy)?;
+    let instantiation_timeout = extract_instantiation_timeout(policy)?;
+    let execution_timeout = extract_execution_timeout(policy)?;
+    let fuel_limit = extract_fuel_limit(policy)?;
     let store_limits = memory_limit
         .map(|limit| -> anyhow::Result<wasmtime::StoreLimits> {
             let limit_usize = limit.try_into().map_err(|_| {
@@ -252,19 +711,43 @@ pub fn create_wasi_state_template_from_policy(
 
     Ok(WasiStateTemplate {
         network_perms,
-        config_vars: env_vars,
+        config_vars,
         preopened_dirs,
         allowed_hosts,
+        denied_networks,
+        network_timeouts,
         memory_limit,
         store_limits,
+        instantiation_timeout,
+        execution_timeout,
+        fuel_limit,
+        introspection: policy.permissions.introspection,
+        calls: policy.permissions.call.clone(),
+        fixed_time,
+        stdout: system.and_then(|system| system.stdout),
+        stderr: system.and_then(|system| system.stderr),
         ..Default::default()
     })
 }
 
+/// A wall clock that always reads the same time.
+struct FixedWallClock(Duration);
+
+impl wasmtime_wasi::HostWallClock for FixedWallClock {
+    fn resolution(&self) -> Duration {
+        Duration::from_nanos(1)
+    }
+
+    fn now(&self) -> Duration {
+        self.0
+    }
+}
+
 pub(crate) fn extract_env_vars(
     policy: &PolicyDocument,
     environment_vars: &HashMap<String, String>,
     secrets: Option<&HashMap<String, String>>,
+    allow_env_inherit: bool,
 ) -> anyhow::Result<HashMap<String, String>> {
     let mut env_vars = HashMap::new();
 
@@ -273,12 +756,19 @@ pub(crate) fn extract_env_vars(
         env_vars.extend(secrets_map.clone());
     }
 
-    // Add inherited environment vars (middle precedence)
-    // Note: This would require passing process environment, but for now
-    // we'll just add configured environment_vars which act as inherited
-
     // Add policy-allowed environment variables (highest precedence)
     if let Some(env_perms) = &policy.permissions.environment {
+        // A trusted component may take the whole configured map. This is
+        // never the raw process environment, only what the host configured.
+        if env_perms.inherit_all {
+            if allow_env_inherit {
+                env_vars.extend(environment_vars.clone());
+                return Ok(env_vars);
+            }
+            tracing::warn!(
+                "Policy requests environment.inheritAll but environment inheritance is not allowed; only allowed keys are passed through"
+            );
+        }
         if let Some(env_allow_vec) = &env_perms.allow {
             for env_allow in env_allow_vec {
                 if let Some(value) = environment_vars.get(&env_allow.key) {
@@ -312,11 +802,19 @@ pub(crate) fn extract_allowed_hosts(policy: &PolicyDocument) -> HashSet<String>
     if let Some(network_perms) = &policy.permissions.network {
         if let Some(allow_list) = &network_perms.allow {
             for allow_entry in allow_list {
-                // The policy uses serde_json::Value, so we need to extract the host field
-                if let Ok(json_value) = serde_json::to_value(allow_entry) {
-                    if let Some(host) = json_value.get("host").and_then(|h| h.as_str()) {
-                        allowed_hosts.insert(host.to_string());
+                match allow_entry {
+                    NetworkPermission::Host(host) => {
+                        allowed_hosts.insert(crate::http::allowed_host_rule(host));
                     }
+                    // An invalid network would otherwise be read as a host
+                    NetworkPermission::Cidr(cidr) => match cidr.cidr.parse::<ipnet::IpNet>() {
+                        Ok(_) => {
+                            allowed_hosts.insert(cidr.cidr.clone());
+                        }
+                        Err(error) => {
+                            tracing::warn!(cidr = %cidr.cidr, %error, "Ignoring invalid CIDR network rule");
+                        }
+                    },
                 }
             }
         }
@@ -325,6 +823,50 @@ pub(crate) fn extract_allowed_hosts(policy: &PolicyDocument) -> HashSet<String>
     allowed_hosts
 }
 
+/// Extract the CIDR entries of the network deny list from the policy
+/// document
+pub(crate) fn extract_denied_networks(policy: &PolicyDocument) -> HashSet<String> {
+    policy
+        .permissions
+        .network
+        .as_ref()
+        .and_then(|network_perms| network_perms.deny.as_ref())
+        .into_iter()
+        .flatten()
+        .filter_map(|deny_entry| match deny_entry {
+            NetworkPermission::Cidr(cidr) => Some(cidr.cidr.clone()),
+            NetworkPermission::Host(_) => None,
+        })
+        .collect()
+}
+
+/// Extract per-rule HTTP timeouts from the network allow list, keyed by the
+/// allowed host entry of their rule
+pub(crate) fn extract_network_timeouts(
+    policy: &PolicyDocument,
+) -> anyhow::Result<HashMap<String, Duration>> {
+    let mut timeouts = HashMap::new();
+
+    let allow_list = policy
+        .permissions
+        .network
+        .as_ref()
+        .and_then(|network| network.allow.as_ref());
+    for entry in allow_list.into_iter().flatten() {
+        if let NetworkPermission::Host(
+            rule @ NetworkHostPermission {
+                timeout: Some(timeout),
+                ..
+            },
+        ) = entry
+        {
+            timeouts.insert(crate::http::allowed_host_rule(rule), timeout.to_duration()?);
+        }
+    }
+
+    Ok(timeouts)
+}
+
 pub(crate) fn extract_storage_permissions(
     policy: &PolicyDocument,
     component_dir: &Path,
@@ -333,12 +875,10 @@ pub(crate) fn extract_storage_permissions(
     if let Some(storage) = &policy.permissions.storage {
         if let Some(allow) = &storage.allow {
             for storage_permission in allow {
-                if storage_permission.uri.starts_with("fs://") {
-                    let uri = storage_permission.uri.strip_prefix("fs://").unwrap();
-                    let path = Path::new(uri);
+                if let Some(host_path) = storage_host_path(&storage_permission.uri, component_dir)?
+                {
                     let (file_perms, dir_perms) = calculate_permissions(&storage_permission.access);
-                    let guest_path = path.to_string_lossy().to_string();
-                    let host_path = component_dir.join(path);
+                    let guest_path = storage_permission.uri["fs://".len()..].to_string();
                     preopened_dirs.push(PreopenedDir {
                         host_path,
                         guest_path,
@@ -352,6 +892,74 @@ pub(crate) fn extract_storage_permissions(
     Ok(preopened_dirs)
 }
 
+/// The host path an `fs://` storage URI grants access to: absolute paths as
+/// given, relative paths inside `component_dir`. `None` for other schemes.
+///
+/// An absolute path is an explicit mount of that host path, resolved through
+/// symlinks when it exists. Windows drive paths (`fs://C:/data` or
+/// `fs:///C:/data`) are absolute too, and only accepted on Windows. Paths
+/// with a `..` segment, also percent-encoded, are rejected, and so are
+/// relative paths that leave `component_dir` through a symlink.
+pub(crate) fn storage_host_path(
+    uri: &str,
+    component_dir: &Path,
+) -> anyhow::Result<Option<PathBuf>> {
+    let Some(path) = uri.strip_prefix("fs://") else {
+        return Ok(None);
+    };
+    if has_parent_segment(path) {
+        anyhow::bail!("Storage URI '{uri}' must not contain '..'");
+    }
+    if let Some(drive_path) = windows_drive_path(path) {
+        if !cfg!(windows) {
+            anyhow::bail!(
+                "Storage URI '{uri}' names a Windows drive, which this host does not have"
+            );
+        }
+        return absolute_host_path(Path::new(drive_path), uri).map(Some);
+    }
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return absolute_host_path(path, uri).map(Some);
+    }
+    let host_path = component_dir.join(path);
+    if component_dir.exists() {
+        ensure_within(&host_path, component_dir)
+            .with_context(|| format!("Storage URI '{uri}' escapes the component directory"))?;
+    }
+    Ok(Some(host_path))
+}
+
+/// Whether the path of an `fs://` storage URI, the part after the scheme, is
+/// an absolute host path such as `/data` or `C:/data` rather than a path in
+/// the component directory.
+pub(crate) fn is_absolute_storage_path(path: &str) -> bool {
+    path.starts_with(['/', '\\']) || windows_drive_path(path).is_some()
+}
+
+/// `path` without the leading `/` of `/C:/data` if it starts with a drive
+/// letter followed by `:/` or `:\`.
+fn windows_drive_path(path: &str) -> Option<&str> {
+    let path = path.strip_prefix('/').unwrap_or(path);
+    let bytes = path.as_bytes();
+    (bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && matches!(bytes[2], b'/' | b'\\'))
+    .then_some(path)
+}
+
+/// `path` with symlinks resolved, or as given when it does not exist yet.
+/// On Windows the result keeps its drive letter form, such as `C:\data`,
+/// rather than the verbatim `\\?\C:\data` that canonicalizing returns.
+fn absolute_host_path(path: &Path, uri: &str) -> anyhow::Result<PathBuf> {
+    match dunce::canonicalize(path) {
+        Ok(path) => Ok(path),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(path.to_path_buf()),
+        Err(e) => Err(e).with_context(|| format!("Failed to resolve storage URI '{uri}'")),
+    }
+}
+
 pub(crate) fn calculate_permissions(
     access_types: &[AccessType],
 ) -> (wasmtime_wasi::FilePerms, wasmtime_wasi::DirPerms) {
@@ -398,6 +1006,73 @@ pub(crate) fn extract_memory_limit(policy: &PolicyDocument) -> anyhow::Result<Op
     Ok(None)
 }
 
+/// Total RAM of the host in bytes, read from `/proc/meminfo`; `None` where
+/// that is not available, such as outside Linux
+pub(crate) fn host_memory_bytes() -> Option<u64> {
+    parse_mem_total(&std::fs::read_to_string("/proc/meminfo").ok()?)
+}
+
+fn parse_mem_total(meminfo: &str) -> Option<u64> {
+    let total = meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemTotal:"))?;
+    let kib: u64 = total.trim().strip_suffix("kB")?.trim().parse().ok()?;
+    kib.checked_mul(1024)
+}
+
+/// Extract the fuel each call may consume from the policy's CPU limit, at
+/// [`FUEL_PER_CPU_CORE`] units per core
+pub(crate) fn extract_fuel_limit(policy: &PolicyDocument) -> anyhow::Result<Option<u64>> {
+    let Some(resources) = &policy.permissions.resources else {
+        return Ok(None);
+    };
+    let cores = match resources
+        .limits
+        .as_ref()
+        .and_then(|limits| limits.cpu.as_ref())
+    {
+        Some(cpu) => Some(cpu.to_cores()?),
+        // Fall back to the legacy cpu field, given in cores
+        None => resources.cpu,
+    };
+    Ok(cores.map(|cores| (cores * FUEL_PER_CPU_CORE as f64).ceil().max(1.0) as u64))
+}
+
+/// Extract the instantiation timeout from the policy document
+pub(crate) fn extract_clock_settings(policy: &PolicyDocument) -> Option<&ClockSettings> {
+    policy
+        .permissions
+        .system
+        .as_ref()
+        .and_then(|system| system.clock.as_ref())
+}
+
+pub(crate) fn extract_instantiation_timeout(
+    policy: &PolicyDocument,
+) -> anyhow::Result<Option<Duration>> {
+    policy
+        .permissions
+        .resources
+        .as_ref()
+        .and_then(|resources| resources.timeouts.as_ref())
+        .and_then(|timeouts| timeouts.instantiation.as_ref())
+        .map(|timeout| timeout.to_duration())
+        .transpose()
+}
+
+pub(crate) fn extract_execution_timeout(
+    policy: &PolicyDocument,
+) -> anyhow::Result<Option<Duration>> {
+    policy
+        .permissions
+        .resources
+        .as_ref()
+        .and_then(|resources| resources.limits.as_ref())
+        .and_then(|limits| limits.timeout.as_ref())
+        .map(|timeout| timeout.to_duration())
+        .transpose()
+}
+
 #[cfg(test)]
 mod tests {
     use policy::{AccessType, PolicyParser};
@@ -536,7 +1211,7 @@ permissions:
             let mut env_vars = HashMap::new();
             env_vars.insert("TEST_VAR".to_string(), "isolated_value".to_string());
 
-            let extracted_vars = extract_env_vars(&policy, &env_vars, None).unwrap();
+            let extracted_vars = extract_env_vars(&policy, &env_vars, None, false).unwrap();
             assert_eq!(
                 extracted_vars.get("TEST_VAR"),
                 Some(&"isolated_value".to_string())
@@ -551,7 +1226,7 @@ permissions:
 
         temp_env::with_vars(vec![("TEST_VAR", None::<&str>)], || {
             let env_vars = HashMap::new(); // Empty environment
-            let extracted_vars = extract_env_vars(&policy, &env_vars, None).unwrap();
+            let extracted_vars = extract_env_vars(&policy, &env_vars, None, false).unwrap();
             assert!(!extracted_vars.contains_key("TEST_VAR"));
         });
     }
@@ -560,7 +1235,7 @@ permissions:
     fn test_extract_environment_variables_no_permissions() {
         let policy = create_zero_permission_policy();
         let env_vars = HashMap::new();
-        let extracted_vars = extract_env_vars(&policy, &env_vars, None).unwrap();
+        let extracted_vars = extract_env_vars(&policy, &env_vars, None, false).unwrap();
         assert!(extracted_vars.is_empty());
     }
 
@@ -575,10 +1250,36 @@ permissions:
 "#;
         let policy = PolicyParser::parse_str(yaml_content).unwrap();
         let env_vars = HashMap::new();
-        let extracted_vars = extract_env_vars(&policy, &env_vars, None).unwrap();
+        let extracted_vars = extract_env_vars(&policy, &env_vars, None, false).unwrap();
         assert!(extracted_vars.is_empty());
     }
 
+    #[test]
+    fn test_extract_environment_variables_inherit_all() {
+        let yaml_content = r#"
+version: "1.0"
+description: "Trusted component inheriting the configured environment"
+permissions:
+  environment:
+    inheritAll: true
+    allow:
+      - key: "API_URL"
+"#;
+        let policy = PolicyParser::parse_str(yaml_content).unwrap();
+        let env_vars = HashMap::from([
+            ("API_URL".to_string(), "https://example.com".to_string()),
+            ("API_TOKEN".to_string(), "token".to_string()),
+        ]);
+
+        let extracted_vars = extract_env_vars(&policy, &env_vars, None, true).unwrap();
+        assert_eq!(extracted_vars, env_vars);
+
+        // Without the host opting in, only the allow list applies
+        let extracted_vars = extract_env_vars(&policy, &env_vars, None, false).unwrap();
+        assert_eq!(extracted_vars.len(), 1);
+        assert!(extracted_vars.contains_key("API_URL"));
+    }
+
     #[test]
     fn test_extract_network_permissions_with_allow() {
         let policy = create_test_policy();
@@ -616,6 +1317,33 @@ permissions:
         assert!(!network_perms.allow_ip_name_lookup);
     }
 
+    #[test]
+    fn test_extract_cidr_network_rules() {
+        let yaml_content = r#"
+version: "1.0"
+description: "Policy with CIDR network rules"
+permissions:
+  network:
+    allow:
+      - host: "api.example.com"
+      - cidr: "10.0.0.0/8"
+      - cidr: "10.0.0.0/33"
+    deny:
+      - cidr: "10.1.0.0/16"
+      - host: "evil.example.com"
+"#;
+        let policy = PolicyParser::parse_str(yaml_content).unwrap();
+
+        assert_eq!(
+            extract_allowed_hosts(&policy),
+            HashSet::from(["api.example.com".to_string(), "10.0.0.0/8".to_string()])
+        );
+        assert_eq!(
+            extract_denied_networks(&policy),
+            HashSet::from(["10.1.0.0/16".to_string()])
+        );
+    }
+
     #[test]
     fn test_extract_storage_permissions() {
         let temp_dir = TempDir::new().unwrap();
@@ -719,6 +1447,110 @@ permissions:
         );
     }
 
+    fn storage_policy(uri: &str) -> PolicyDocument {
+        PolicyParser::parse_str(format!(
+            "version: \"1.0\"\npermissions:\n  storage:\n    allow:\n      - uri: '{uri}'\n        access: [\"read\"]\n"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_extract_storage_permissions_rejects_traversal() {
+        let temp_dir = TempDir::new().unwrap();
+        let component_dir = temp_dir.path();
+
+        for uri in [
+            "fs://../../etc",
+            "fs://workspace/../../etc",
+            "fs:///data/../etc",
+            "fs://%2e%2e/%2e%2e/etc",
+            "fs://workspace%2f..%2f..%2fetc",
+            "fs://..\\..\\etc",
+        ] {
+            let Err(error) = extract_storage_permissions(&storage_policy(uri), component_dir)
+            else {
+                panic!("{uri} should be rejected");
+            };
+            assert!(error.to_string().contains("must not contain '..'"), "{uri}");
+        }
+
+        // Absolute paths name a host path explicitly
+        let dirs =
+            extract_storage_permissions(&storage_policy("fs:///data"), component_dir).unwrap();
+        assert_eq!(dirs[0].host_path, PathBuf::from("/data"));
+    }
+
+    #[test]
+    fn test_extract_storage_permissions_absolute_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        let component_dir = temp_dir.path();
+        let host_dir = TempDir::new().unwrap();
+        let host_path = host_dir.path().canonicalize().unwrap();
+
+        let uri = format!("fs://{}", host_path.display());
+        let dirs = extract_storage_permissions(&storage_policy(&uri), component_dir).unwrap();
+        assert_eq!(dirs[0].host_path, host_path);
+        assert!(!dirs[0].host_path.starts_with(component_dir));
+
+        // Relative paths stay inside the component directory
+        let dirs =
+            extract_storage_permissions(&storage_policy("fs://data"), component_dir).unwrap();
+        assert_eq!(dirs[0].host_path, component_dir.join("data"));
+
+        #[cfg(unix)]
+        {
+            let link = TempDir::new().unwrap();
+            let link = link.path().join("link");
+            std::os::unix::fs::symlink(&host_path, &link).unwrap();
+            let uri = format!("fs://{}", link.display());
+            let dirs = extract_storage_permissions(&storage_policy(&uri), component_dir).unwrap();
+            assert_eq!(dirs[0].host_path, host_path);
+        }
+    }
+
+    #[test]
+    fn test_storage_host_path_windows_drives() {
+        assert_eq!(windows_drive_path("C:/data"), Some("C:/data"));
+        assert_eq!(windows_drive_path("/d:\\data"), Some("d:\\data"));
+        assert_eq!(windows_drive_path("data/C:/x"), None);
+        assert_eq!(windows_drive_path("/C:"), None);
+
+        let temp_dir = TempDir::new().unwrap();
+        let component_dir = temp_dir.path();
+        assert!(storage_host_path("fs://C:/data/../Windows", component_dir).is_err());
+        // Whether or not `C:\data` exists, it comes back without the verbatim `\\?\` prefix
+        let expected = dunce::canonicalize("C:/data").unwrap_or_else(|_| PathBuf::from("C:/data"));
+        for uri in ["fs://C:/data", "fs:///C:/data"] {
+            let host_path = storage_host_path(uri, component_dir);
+            if cfg!(windows) {
+                let host_path = host_path.unwrap().unwrap();
+                assert!(!host_path.to_string_lossy().starts_with(r"\\?\"), "{uri}");
+                assert_eq!(host_path, expected, "{uri}");
+            } else {
+                let error = host_path.unwrap_err();
+                assert!(error.to_string().contains("Windows drive"), "{uri}");
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_storage_permissions_rejects_symlink_escape() {
+        let temp_dir = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        let component_dir = temp_dir.path();
+        std::os::unix::fs::symlink(outside.path(), component_dir.join("link")).unwrap();
+
+        let Err(error) =
+            extract_storage_permissions(&storage_policy("fs://link/data"), component_dir)
+        else {
+            panic!("a symlink out of the component directory should be rejected");
+        };
+        assert!(error
+            .to_string()
+            .contains("escapes the component directory"));
+    }
+
     #[test]
     fn test_create_wasi_state_template_from_policy() {
         let temp_dir = TempDir::new().unwrap();
@@ -754,6 +1586,8 @@ permissions:
         assert!(template.preopened_dirs.is_empty());
         assert!(template.allow_stdout);
         assert!(template.allow_stderr);
+        assert_eq!(template.stdout, None);
+        assert_eq!(template.stderr, None);
         assert!(template.allow_args);
         assert_eq!(template.memory_limit, None);
     }
@@ -791,6 +1625,113 @@ permissions:
         assert_eq!(memory_limit_none, None);
     }
 
+    #[test]
+    fn test_extract_instantiation_timeout() {
+        let yaml_content = r#"
+version: "1.0"
+description: "Policy with instantiation timeout"
+permissions:
+  resources:
+    limits:
+      memory: "512Mi"
+    timeouts:
+      instantiation: "30s"
+"#;
+        let policy = PolicyParser::parse_str(yaml_content).unwrap();
+        assert_eq!(
+            extract_instantiation_timeout(&policy).unwrap(),
+            Some(Duration::from_secs(30))
+        );
+
+        let policy = create_zero_permission_policy();
+        assert_eq!(extract_instantiation_timeout(&policy).unwrap(), None);
+    }
+
+    #[test]
+    fn test_extract_execution_timeout() {
+        let yaml_content = r#"
+version: "1.0"
+permissions:
+  resources:
+    limits:
+      timeout: "2m"
+"#;
+        let policy = PolicyParser::parse_str(yaml_content).unwrap();
+        assert_eq!(
+            extract_execution_timeout(&policy).unwrap(),
+            Some(Duration::from_secs(120))
+        );
+
+        let policy = create_zero_permission_policy();
+        assert_eq!(extract_execution_timeout(&policy).unwrap(), None);
+    }
+
+    #[test]
+    fn test_extract_fuel_limit() {
+        let yaml_content = r#"
+version: "1.0"
+permissions:
+  resources:
+    limits:
+      cpu: "250m"
+"#;
+        let policy = PolicyParser::parse_str(yaml_content).unwrap();
+        assert_eq!(
+            extract_fuel_limit(&policy).unwrap(),
+            Some(FUEL_PER_CPU_CORE / 4)
+        );
+
+        let yaml_content = r#"
+version: "1.0"
+permissions:
+  resources:
+    cpu: 2
+"#;
+        let policy = PolicyParser::parse_str(yaml_content).unwrap();
+        assert_eq!(
+            extract_fuel_limit(&policy).unwrap(),
+            Some(2 * FUEL_PER_CPU_CORE)
+        );
+
+        let policy = create_zero_permission_policy();
+        assert_eq!(extract_fuel_limit(&policy).unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_mem_total() {
+        let meminfo = "MemTotal:       16318904 kB\nMemFree:         1024000 kB\n";
+        assert_eq!(parse_mem_total(meminfo), Some(16318904 * 1024));
+        assert_eq!(parse_mem_total("MemFree: 1024 kB\n"), None);
+    }
+
+    #[test]
+    fn test_extract_network_timeouts() {
+        let yaml_content = r#"
+version: "1.0"
+description: "Policy with network timeouts"
+permissions:
+  network:
+    allow:
+      - host: "api.example.com"
+      - host: "https://slow.example.com"
+        timeout: "2m"
+      - host: "fast.example.com"
+        timeout: 5
+"#;
+        let policy = PolicyParser::parse_str(yaml_content).unwrap();
+        let timeouts = extract_network_timeouts(&policy).unwrap();
+
+        assert_eq!(timeouts.len(), 2);
+        assert_eq!(
+            timeouts.get("https://slow.example.com"),
+            Some(&Duration::from_secs(120))
+        );
+        assert_eq!(
+            timeouts.get("fast.example.com"),
+            Some(&Duration::from_secs(5))
+        );
+    }
+
     #[test]
     fn test_create_wasi_state_template_with_memory_limit() {
         let temp_dir = TempDir::new().unwrap();
@@ -922,6 +1863,92 @@ permissions:
         );
     }
 
+    #[test]
+    fn test_wasi_state_template_names_missing_storage_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let component_dir = temp_dir.path();
+        let policy = create_test_policy();
+        let template =
+            create_wasi_state_template_from_policy(&policy, component_dir, &HashMap::new(), None)
+                .unwrap();
+
+        let error = template.build().err().expect("missing path should fail");
+        let permission_error = error.downcast_ref::<PermissionError>().unwrap();
+        let PermissionError::StoragePathMissing { uri, host_path } = permission_error else {
+            panic!("unexpected error: {permission_error:?}");
+        };
+        assert_eq!(uri, "fs://test/path");
+        assert_eq!(host_path, &component_dir.join("test/path"));
+        let message = permission_error.to_user_message("my-component");
+        assert!(message.contains("'my-component'"));
+        assert!(message.contains(&component_dir.join("test/path").display().to_string()));
+        assert!(message.contains("--access=\"read,write\" --createIfMissing=true"));
+
+        for dir in ["test/path", "write/path", "readwrite/path"] {
+            std::fs::create_dir_all(component_dir.join(dir)).unwrap();
+        }
+        assert!(template.build().is_ok());
+    }
+
+    #[test]
+    fn test_permission_errors_list_current_grants() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut template = create_wasi_state_template_from_policy(
+            &create_test_policy(),
+            temp_dir.path(),
+            &HashMap::new(),
+            None,
+        )
+        .unwrap();
+
+        let storage_denied = PermissionError::StorageDenied {
+            path: "fs://other/path".to_string(),
+            access_type: "write".to_string(),
+        };
+        let message = storage_denied.to_user_message_with_grants("my-component", &template);
+        assert!(message.ends_with(
+            "Granted storage: fs://readwrite/path (read, write), fs://test/path (read), fs://write/path (write)"
+        ));
+
+        let network_denied = PermissionError::NetworkDenied {
+            host: "www.example.com".to_string(),
+            uri: "https://www.example.com/".to_string(),
+            scheme: None,
+            port: None,
+        };
+        let message = network_denied.to_user_message_with_grants("my-component", &template);
+        assert!(message.starts_with(&network_denied.to_user_message("my-component")));
+        assert!(message.contains(r#"--host="*.example.com""#));
+        assert!(message.ends_with("Allowed hosts: api.example.com"));
+
+        template.allowed_hosts = (0..12).map(|i| format!("host{i:02}.example.com")).collect();
+        let message = network_denied.to_user_message_with_grants("my-component", &template);
+        assert!(message.contains("host09.example.com and 2 more"));
+        assert!(!message.contains("host10.example.com"));
+
+        template.allowed_hosts.clear();
+        let message = network_denied.to_user_message_with_grants("my-component", &template);
+        assert!(message.ends_with("Allowed hosts: none"));
+
+        let port_denied = PermissionError::NetworkDenied {
+            host: "internal.corp".to_string(),
+            uri: "http://internal.corp:8080/admin".to_string(),
+            scheme: None,
+            port: Some(8080),
+        };
+        assert_eq!(
+            port_denied.to_string(),
+            "network access to host 'internal.corp' on port 8080 was denied"
+        );
+        let message = port_denied.to_user_message("my-component");
+        assert!(message.contains(r#"--host="internal.corp" --port=8080"#));
+        assert!(!message.contains("sibling subdomains"));
+        assert_eq!(
+            port_denied.denial_details().unwrap().1["port"],
+            serde_json::json!(8080)
+        );
+    }
+
     #[test]
     fn test_wasi_state_template_builds_with_multiple_env_vars() {
         let temp_dir = TempDir::new().unwrap();
@@ -1107,6 +2134,38 @@ permissions:
         // was properly implemented and the WASI context was created successfully.
     }
 
+    #[test]
+    fn test_policy_config_values_override_environment() {
+        let temp_dir = TempDir::new().unwrap();
+        let policy = PolicyParser::parse_str(
+            r#"
+version: "1.0"
+permissions:
+  environment:
+    allow:
+      - key: "ENABLE_BETA_PARSER"
+      - key: "LOG_LEVEL"
+config:
+  ENABLE_BETA_PARSER: "true"
+  REGION: "eu-west-1"
+"#,
+        )
+        .unwrap();
+
+        let env_vars = HashMap::from([
+            ("ENABLE_BETA_PARSER".to_string(), "false".to_string()),
+            ("LOG_LEVEL".to_string(), "debug".to_string()),
+        ]);
+        let template =
+            create_wasi_state_template_from_policy(&policy, temp_dir.path(), &env_vars, None)
+                .unwrap();
+
+        assert_eq!(template.config_vars.len(), 3);
+        assert_eq!(template.config_vars["ENABLE_BETA_PARSER"], "true");
+        assert_eq!(template.config_vars["REGION"], "eu-west-1");
+        assert_eq!(template.config_vars["LOG_LEVEL"], "debug");
+    }
+
     proptest! {
         #[test]
         fn test_calculate_permissions_union_property(
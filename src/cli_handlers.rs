@@ -3,22 +3,26 @@
 
 //! CLI command handlers for wassette
 
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use mcp_server::components::{
-    handle_list_components, handle_load_component_cli, handle_unload_component_cli,
+    handle_deactivate_component, handle_install_component, handle_list_components,
+    handle_load_component, handle_unload_component,
 };
 use mcp_server::tools::{
-    handle_get_policy, handle_grant_environment_variable_permission,
-    handle_grant_memory_permission, handle_grant_network_permission,
-    handle_grant_storage_permission, handle_reset_permission,
-    handle_revoke_environment_variable_permission, handle_revoke_network_permission,
-    handle_revoke_storage_permission,
+    handle_get_policy, handle_grant_config_value, handle_grant_cpu_permission,
+    handle_grant_environment_variable_permission, handle_grant_memory_permission,
+    handle_grant_network_permission, handle_grant_storage_permission, handle_reset_permission,
+    handle_revoke_config_value, handle_revoke_environment_variable_permission,
+    handle_revoke_network_permission, handle_revoke_storage_permission,
 };
-use mcp_server::LifecycleManager;
-use rmcp::model::CallToolRequestParam;
+use mcp_server::{handle_tools_list, LifecycleManager};
+use rmcp::model::{CallToolRequestParam, ListToolsResult, Tool};
 use serde_json::{Map, Value};
+use tokio_util::sync::CancellationToken;
+use wassette::{HealthCheckConfig, LifecycleBuilder};
 
 use crate::config;
 use crate::format::{print_result, OutputFormat};
@@ -39,9 +43,18 @@ pub async fn handle_tool_cli_command(
     };
 
     let result = match tool {
-        ToolName::LoadComponent => handle_load_component_cli(&req, lifecycle_manager).await?,
-        ToolName::UnloadComponent => handle_unload_component_cli(&req, lifecycle_manager).await?,
-        ToolName::ListComponents => handle_list_components(lifecycle_manager).await?,
+        ToolName::LoadComponent => {
+            handle_load_component(&req, lifecycle_manager, &CancellationToken::new(), None).await?
+        }
+        ToolName::UnloadComponent => handle_unload_component(&req, lifecycle_manager).await?,
+        ToolName::DeactivateComponent => {
+            handle_deactivate_component(&req, lifecycle_manager).await?
+        }
+        ToolName::ListComponents => handle_list_components(&req, lifecycle_manager).await?,
+        ToolName::InstallComponent => {
+            handle_install_component(&req, lifecycle_manager, &CancellationToken::new(), None)
+                .await?
+        }
         ToolName::GetPolicy => handle_get_policy(&req, lifecycle_manager).await?,
         ToolName::GrantStoragePermission => {
             handle_grant_storage_permission(&req, lifecycle_manager).await?
@@ -55,6 +68,9 @@ pub async fn handle_tool_cli_command(
         ToolName::GrantMemoryPermission => {
             handle_grant_memory_permission(&req, lifecycle_manager).await?
         }
+        ToolName::GrantCpuPermission => {
+            handle_grant_cpu_permission(&req, lifecycle_manager).await?
+        }
         ToolName::RevokeStoragePermission => {
             handle_revoke_storage_permission(&req, lifecycle_manager).await?
         }
@@ -64,6 +80,8 @@ pub async fn handle_tool_cli_command(
         ToolName::RevokeEnvironmentVariablePermission => {
             handle_revoke_environment_variable_permission(&req, lifecycle_manager).await?
         }
+        ToolName::GrantConfigValue => handle_grant_config_value(&req, lifecycle_manager).await?,
+        ToolName::RevokeConfigValue => handle_revoke_config_value(&req, lifecycle_manager).await?,
         ToolName::ResetPermission => handle_reset_permission(&req, lifecycle_manager).await?,
     };
 
@@ -83,47 +101,96 @@ pub async fn handle_tool_cli_command(
 /// For CLI responsiveness, we create an unloaded lifecycle manager which
 /// initializes engine/linker without compiling/scanning all components.
 /// Component metadata or lazy loads are used by individual handlers.
-pub async fn create_lifecycle_manager(component_dir: Option<PathBuf>) -> Result<LifecycleManager> {
-    let config = if let Some(dir) = component_dir {
-        config::Config {
-            component_dir: dir,
-            secrets_dir: config::get_secrets_dir().unwrap_or_else(|_| {
-                eprintln!("WARN: Unable to determine default secrets directory, using `secrets` directory in the current working directory");
-                PathBuf::from("./secrets")
-            }),
-            environment_vars: std::collections::HashMap::new(),
-            bind_address: "127.0.0.1:9001".to_string(),
-            registry_credentials: std::collections::HashMap::new(),
-        }
-    } else {
-        config::Config::from_serve(&crate::commands::Serve {
-            component_dir: None,
-            transport: Default::default(),
-            env_vars: vec![],
-            env_file: None,
-            disable_builtin_tools: false,
-            bind_address: None,
-            manifest: None,
-        })
-        .context("Failed to load configuration")?
-    };
+pub async fn create_lifecycle_manager(
+    component_dir: Option<PathBuf>,
+    verbose: bool,
+) -> Result<LifecycleManager> {
+    create_lifecycle_manager_with_source_override(component_dir, verbose, false).await
+}
+
+/// Same as [`create_lifecycle_manager`], but ignores the configured
+/// `allowed_sources` when `override_source_policy` is set, for commands that
+/// load components and take `--override-source-policy`.
+pub async fn create_lifecycle_manager_with_source_override(
+    component_dir: Option<PathBuf>,
+    verbose: bool,
+    override_source_policy: bool,
+) -> Result<LifecycleManager> {
+    let config =
+        config::Config::for_command(component_dir).context("Failed to load configuration")?;
+    if verbose {
+        config.print_resolved_dirs();
+    }
 
-    // Use unloaded manager for fast CLI startup, but preserve custom secrets dir
-    let config::Config {
-        component_dir,
-        secrets_dir,
-        environment_vars,
-        bind_address: _,
-        registry_credentials,
-    } = config;
-
-    LifecycleManager::builder(component_dir)
-        .with_environment_vars(environment_vars)
-        .with_secrets_dir(secrets_dir)
-        .with_registry_credentials(registry_credentials)
-        .with_oci_client(oci_client::Client::default())
-        .with_http_client(reqwest::Client::default())
-        .with_eager_loading(false)
-        .build()
-        .await
+    // Use unloaded manager for fast CLI startup
+    let mut builder = LifecycleBuilder::from_config(config.lifecycle)
+        // Local CLI commands do not load the component directory, so there is
+        // nothing to warm up, check, watch, or collect in the background.
+        .with_warmup(HashMap::new())
+        .with_health_checks(HealthCheckConfig::default())
+        .with_auto_reload_on_drift(false)
+        .with_dir_watch(false)
+        .with_startup_gc(false)
+        // Only the server and `component gc` prune the downloads directory,
+        // so commands that only read leave the cache alone.
+        .with_startup_downloads_prune(false)
+        // Only the server records registry changes, so numbering stays
+        // consistent while it runs.
+        .without_changelog()
+        .with_eager_loading(false);
+    if override_source_policy {
+        builder = builder
+            .with_allowed_sources(Vec::new())
+            .with_allowed_policy_sources(Vec::new());
+    }
+    builder.build().await
+}
+
+/// The tool called `name`, as `tools/list` describes it. Component tools are
+/// registered from their cached metadata first, without compiling anything.
+pub async fn find_tool(lifecycle_manager: &LifecycleManager, name: &str) -> Result<Tool> {
+    lifecycle_manager.populate_registry_from_metadata().await?;
+    let tools: ListToolsResult =
+        serde_json::from_value(handle_tools_list(lifecycle_manager, false, false).await?)?;
+    if let Some(tool) = tools.tools.into_iter().find(|tool| tool.name == name) {
+        return Ok(tool);
+    }
+    let names = lifecycle_manager.list_tool_names().await;
+    if names.is_empty() {
+        anyhow::bail!("Tool not found: {name}. No component tools are registered");
+    }
+    anyhow::bail!(
+        "Tool not found: {name}. Component tools: {}",
+        names.join(", ")
+    )
+}
+
+/// Read the hosts for `permission grant network --hosts-file`: one host per
+/// line, with blank lines and `#` comments ignored.
+pub fn read_hosts_file(path: &Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read hosts file {}", path.display()))?;
+    let hosts: Vec<String> = content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|host| !host.is_empty())
+        .map(str::to_string)
+        .collect();
+    if hosts.is_empty() {
+        anyhow::bail!("Hosts file {} does not list any hosts", path.display());
+    }
+    Ok(hosts)
+}
+
+/// The details of a network rule for `permission grant network` and
+/// `permission revoke network`, with the port and scheme only when given.
+pub fn network_rule_details(host: &str, port: Option<u16>, scheme: &Option<String>) -> Value {
+    let mut details = serde_json::json!({ "host": host });
+    if let Some(port) = port {
+        details["port"] = port.into();
+    }
+    if let Some(scheme) = scheme {
+        details["scheme"] = scheme.as_str().into();
+    }
+    details
 }
@@ -26,6 +26,25 @@ pub enum Shell {
     Elvish,
 }
 
+/// Sort orders for component listings
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ComponentSort {
+    /// Alphabetically by component ID
+    Id,
+    /// Most recently called first; never-called components last
+    LastUsed,
+}
+
+impl ComponentSort {
+    /// Value accepted by the `sort` argument of `list-components`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ComponentSort::Id => "id",
+            ComponentSort::LastUsed => "last-used",
+        }
+    }
+}
+
 #[derive(Parser, Debug)]
 #[command(
     name = "wassette-mcp-server",
@@ -41,6 +60,10 @@ pub struct Cli {
     #[arg(long)]
     pub component_dir: Option<std::path::PathBuf>,
 
+    /// Print the component and secrets directories the command resolved
+    #[arg(long, short = 'v', global = true)]
+    pub verbose: bool,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
@@ -74,10 +97,83 @@ pub enum Commands {
     /// Inspect a WebAssembly component and display its JSON schema (for debugging).
     Inspect {
         /// Component ID to inspect
+        #[arg(value_parser = crate::parse_component_id)]
         component_id: String,
         /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
         #[arg(long)]
         component_dir: Option<PathBuf>,
+        /// Compile the component the way the server loads it and show the tools
+        /// it would register, instead of the tools saved when it was last loaded
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Show the server version, component and tool counts, and loading status.
+    #[command(after_help = "EXAMPLES:
+    # Summarize the component directory
+    wassette status
+
+    # Read the same report a running server returns from get-server-info
+    wassette status -o json")]
+    Status {
+        /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
+        #[arg(long)]
+        component_dir: Option<PathBuf>,
+        /// Output format
+        #[arg(short = 'o', long = "output-format", default_value = "json")]
+        output_format: OutputFormat,
+    },
+    /// Check the component directory, the state manifest, and that every component loads.
+    #[command(after_help = "EXAMPLES:
+    # Fail a CI job when any check reports an error
+    wassette doctor -o json > doctor.json")]
+    Doctor {
+        /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
+        #[arg(long)]
+        component_dir: Option<PathBuf>,
+        /// Output format
+        #[arg(short = 'o', long = "output-format", default_value = "json")]
+        output_format: OutputFormat,
+    },
+    /// Bundle installed components, their policies, and the state manifest into one archive.
+    #[command(after_help = "EXAMPLES:
+    # Back up the component directory
+    wassette export-state -o state.tar.zst
+
+    # Include secrets for a full host migration (prompts for confirmation)
+    wassette export-state -o state.tar.zst --include-secrets")]
+    ExportState {
+        /// Path of the archive to write (zstd-compressed tar)
+        #[arg(short = 'o', long)]
+        output: PathBuf,
+        /// Include component secrets, unencrypted
+        #[arg(long)]
+        include_secrets: bool,
+        /// Skip the confirmation prompt when including secrets
+        #[arg(long)]
+        yes: bool,
+        /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
+        #[arg(long)]
+        component_dir: Option<PathBuf>,
+        /// Output format
+        #[arg(long = "output-format", default_value = "json")]
+        output_format: OutputFormat,
+    },
+    /// Install the components from an archive written by export-state.
+    #[command(after_help = "EXAMPLES:
+    # Restore components; already installed components are skipped or reported as conflicts
+    wassette import-state state.tar.zst")]
+    ImportState {
+        /// Archive written by export-state
+        archive: PathBuf,
+        /// Import the secrets contained in the archive
+        #[arg(long)]
+        include_secrets: bool,
+        /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
+        #[arg(long)]
+        component_dir: Option<PathBuf>,
+        /// Output format
+        #[arg(short = 'o', long = "output-format", default_value = "json")]
+        output_format: OutputFormat,
     },
     /// Manage tools (list, read, invoke).
     Tool {
@@ -119,6 +215,30 @@ pub struct Run {
     #[arg(long)]
     #[serde(default)]
     pub disable_builtin_tools: bool,
+
+    /// Refuse to register the tools of components whose health check fails
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub require_healthy_components: bool,
+
+    /// Honor `environment.inheritAll` in component policies, passing every
+    /// configured environment variable to those components
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_env_inherit: bool,
+
+    /// Reload components whose wasm file is replaced on disk while the
+    /// server runs, instead of refusing their calls until they are loaded again
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub auto_reload_on_drift: bool,
+
+    /// Hide and reject the built-in tools that load, unload, or install
+    /// components or change permissions. Also settable with `read_only` in
+    /// the configuration file
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub read_only: bool,
 }
 
 /// Configuration for serving remotely over HTTP transports
@@ -147,6 +267,43 @@ pub struct Serve {
     #[serde(default)]
     pub disable_builtin_tools: bool,
 
+    /// Refuse to register the tools of components whose health check fails
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub require_healthy_components: bool,
+
+    /// Honor `environment.inheritAll` in component policies, passing every
+    /// configured environment variable to those components
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_env_inherit: bool,
+
+    /// Reload components whose wasm file is replaced on disk while the
+    /// server runs, instead of refusing their calls until they are loaded again
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub auto_reload_on_drift: bool,
+
+    /// Watch the component directory while the server runs: load wasm files
+    /// copied into it, reload replaced ones, unregister removed ones, and
+    /// apply changed policy files
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub watch: bool,
+
+    /// Hide and reject the built-in tools that load, unload, or install
+    /// components or change permissions. Also settable with `read_only` in
+    /// the configuration file
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub read_only: bool,
+
+    /// Refuse to load components whose signature does not verify against
+    /// the trusted keys of `signature_policy` in the configuration file
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub require_signatures: bool,
+
     /// Bind address for HTTP-based transports (SSE and StreamableHttp). Defaults to 127.0.0.1:9001
     #[arg(long)]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -156,6 +313,26 @@ pub struct Serve {
     #[arg(long)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub manifest: Option<PathBuf>,
+
+    /// Path to a state manifest (components.lock.json) that is reconciled
+    /// against the component directory at startup and kept up to date as
+    /// components are loaded and unloaded. Also settable with `state_manifest`
+    /// in the configuration file
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_manifest: Option<PathBuf>,
+
+    /// Serve Prometheus metrics at /metrics on the HTTP transport, or on a
+    /// separate listener when `--metrics-addr` is given
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub metrics: bool,
+
+    /// Address of a separate Prometheus metrics listener; implies `--metrics`. Also settable
+    /// with the WASSETTE_METRICS_ADDR environment variable
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics_addr: Option<String>,
 }
 
 /// HTTP transport options for the Serve command
@@ -179,6 +356,16 @@ pub enum Transport {
     StreamableHttp,
 }
 
+impl Transport {
+    /// Name of the transport, as reported by `get-server-info`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Transport::Sse => "sse",
+            Transport::StreamableHttp => "streamable-http",
+        }
+    }
+}
+
 impl From<&HttpTransportFlags> for Transport {
     fn from(f: &HttpTransportFlags) -> Self {
         match (f.sse, f.streamable_http) {
@@ -198,17 +385,150 @@ pub enum ComponentCommands {
         /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
         #[arg(long)]
         component_dir: Option<PathBuf>,
+        /// Load the component even if its source is not in `allowed_sources`
+        #[arg(long)]
+        override_source_policy: bool,
     },
     /// Unload a WebAssembly component.
     Unload {
         /// Component ID to unload
+        #[arg(value_parser = crate::parse_component_id)]
         id: String,
+        /// Skip the component's on-unload hook, e.g. when the hook itself misbehaves
+        #[arg(long)]
+        skip_hooks: bool,
         /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
         #[arg(long)]
         component_dir: Option<PathBuf>,
     },
+    /// Evict a loaded component from memory, keeping its files, policy, and tools.
+    Deactivate {
+        /// Component ID to deactivate
+        #[arg(value_parser = crate::parse_component_id)]
+        component_id: String,
+        /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
+        #[arg(long)]
+        component_dir: Option<PathBuf>,
+    },
+    /// Install a component from the registry by name.
+    #[command(after_help = "EXAMPLES:
+    # Install by exact registry name
+    wassette component install \"Time Server\"
+
+    # Names are matched loosely; ambiguous names list the candidates
+    wassette component install fetch")]
+    Install {
+        /// Registry entry name (see 'wassette registry search')
+        name: String,
+        /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
+        #[arg(long)]
+        component_dir: Option<PathBuf>,
+        /// Load the component even if its source is not in `allowed_sources`
+        #[arg(long)]
+        override_source_policy: bool,
+    },
     /// List all loaded components.
     List {
+        /// Order of the listed components
+        #[arg(long, value_enum)]
+        sort: Option<ComponentSort>,
+        /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
+        #[arg(long)]
+        component_dir: Option<PathBuf>,
+        /// Output format
+        #[arg(short = 'o', long = "output-format", default_value = "json")]
+        output_format: OutputFormat,
+    },
+    /// Print a component's package documentation as Markdown.
+    Docs {
+        /// Component ID to show docs for
+        #[arg(value_parser = crate::parse_component_id)]
+        component_id: String,
+        /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
+        #[arg(long)]
+        component_dir: Option<PathBuf>,
+    },
+    /// Show when each component and its tools were last called, how often their calls failed and
+    /// how long they took, and which toolchain compiled it.
+    #[command(after_help = "EXAMPLES:
+    # Find components that have not been used recently
+    wassette component stats --sort last-used -o table")]
+    Stats {
+        /// Order of the listed components
+        #[arg(long, value_enum, default_value = "last-used")]
+        sort: ComponentSort,
+        /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
+        #[arg(long)]
+        component_dir: Option<PathBuf>,
+        /// Output format
+        #[arg(short = 'o', long = "output-format", default_value = "json")]
+        output_format: OutputFormat,
+    },
+    /// Compile components and report which ones fail to load.
+    #[command(
+        name = "load-status",
+        after_help = "EXAMPLES:
+    # Fail a CI job when any component does not load
+    wassette component load-status -o json > load-status.json
+
+    # Check a single component
+    wassette component load-status fetch-rs"
+    )]
+    LoadStatus {
+        /// Component ID to check; every component in the component directory if omitted
+        #[arg(value_parser = crate::parse_component_id)]
+        component_id: Option<String>,
+        /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
+        #[arg(long)]
+        component_dir: Option<PathBuf>,
+        /// Output format
+        #[arg(short = 'o', long = "output-format", default_value = "json")]
+        output_format: OutputFormat,
+    },
+    /// Re-fetch components recorded in the state manifest that are missing from the component directory.
+    Restore {
+        /// Path to the state manifest. Defaults to components.lock.json in the component directory
+        #[arg(long)]
+        manifest: Option<PathBuf>,
+        /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
+        #[arg(long)]
+        component_dir: Option<PathBuf>,
+        /// Output format
+        #[arg(short = 'o', long = "output-format", default_value = "json")]
+        output_format: OutputFormat,
+    },
+    /// Relocate components from an old component directory into the resolved one.
+    #[command(after_help = "EXAMPLES:
+    # Move components installed under ./components to the platform data directory
+    wassette component migrate-dir --from ./components --move")]
+    MigrateDir {
+        /// Component directory to migrate from
+        #[arg(long)]
+        from: PathBuf,
+        /// Remove the files from the old directory after relocating them
+        #[arg(long = "move", conflicts_with = "copy")]
+        move_files: bool,
+        /// Leave the files in the old directory (the default)
+        #[arg(long)]
+        copy: bool,
+        /// Directory to migrate into. Defaults to $XDG_DATA_HOME/wassette/components
+        #[arg(long)]
+        component_dir: Option<PathBuf>,
+        /// Output format
+        #[arg(short = 'o', long = "output-format", default_value = "json")]
+        output_format: OutputFormat,
+    },
+    /// Remove cached data that is no longer needed.
+    Gc {
+        /// List what would be removed without removing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Remove temporary download files older than this many seconds. Defaults to 3600
+        #[arg(long)]
+        temp_max_age_secs: Option<u64>,
+        /// Also prune downloaded blobs not referenced by any installed component or the state manifest
+        #[arg(long)]
+        downloads: bool,
         /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
         #[arg(long)]
         component_dir: Option<PathBuf>,
@@ -223,6 +543,19 @@ pub enum PolicyCommands {
     /// Get policy information for a component.
     Get {
         /// Component ID to get policy for
+        #[arg(value_parser = crate::parse_component_id)]
+        component_id: String,
+        /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
+        #[arg(long)]
+        component_dir: Option<PathBuf>,
+        /// Output format
+        #[arg(short = 'o', long = "output-format", default_value = "json")]
+        output_format: OutputFormat,
+    },
+    /// List the stored revisions of a component's policy.
+    History {
+        /// Component ID to list policy revisions for
+        #[arg(value_parser = crate::parse_component_id)]
         component_id: String,
         /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
         #[arg(long)]
@@ -231,6 +564,37 @@ pub enum PolicyCommands {
         #[arg(short = 'o', long = "output-format", default_value = "json")]
         output_format: OutputFormat,
     },
+    /// Roll a component's policy back to a stored revision.
+    Rollback {
+        /// Component ID to roll the policy back for
+        #[arg(value_parser = crate::parse_component_id)]
+        component_id: String,
+        /// Revision to restore, as listed by `wassette policy history`
+        #[arg(long = "to")]
+        revision: u64,
+        /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
+        #[arg(long)]
+        component_dir: Option<PathBuf>,
+        /// Output format
+        #[arg(short = 'o', long = "output-format", default_value = "json")]
+        output_format: OutputFormat,
+    },
+    /// Rewrite a policy file written for an older policy version in the current format.
+    Migrate {
+        /// Path to the policy file
+        file: PathBuf,
+        /// Output format
+        #[arg(short = 'o', long = "output-format", default_value = "json")]
+        output_format: OutputFormat,
+    },
+    /// Check that a YAML or JSON policy file is valid.
+    Validate {
+        /// Path to the policy file
+        file: PathBuf,
+        /// Output format
+        #[arg(short = 'o', long = "output-format", default_value = "json")]
+        output_format: OutputFormat,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -248,6 +612,7 @@ pub enum PermissionCommands {
     /// Reset all permissions for a component.
     Reset {
         /// Component ID to reset permissions for
+        #[arg(value_parser = crate::parse_component_id)]
         component_id: String,
         /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
         #[arg(long)]
@@ -266,15 +631,22 @@ pub enum GrantPermissionCommands {
     wassette permission grant storage my-component fs:///tmp/output --access read,write
 
     # Grant write-only access to a workspace
-    wassette permission grant storage my-component fs:///home/user/workspace --access write")]
+    wassette permission grant storage my-component fs:///home/user/workspace --access write
+
+    # Grant access to a directory, creating it if it does not exist
+    wassette permission grant storage my-component fs:///tmp/output --access write --create")]
     Storage {
         /// Component ID to grant permission to
+        #[arg(value_parser = crate::parse_component_id)]
         component_id: String,
         /// URI of the storage resource (e.g., fs:///path/to/directory)
         uri: String,
         /// Access level (read, write, or read,write)
         #[arg(long, value_delimiter = ',')]
         access: Vec<String>,
+        /// Create the directory if it does not exist, instead of failing; needs write access
+        #[arg(long)]
+        create: bool,
         /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
         #[arg(long)]
         component_dir: Option<PathBuf>,
@@ -288,12 +660,29 @@ pub enum GrantPermissionCommands {
     wassette permission grant network my-component backup.example.com
 
     # Grant access to a CDN
-    wassette permission grant network my-component cdn.example.com")]
+    wassette permission grant network my-component cdn.example.com
+
+    # Grant access to an API over HTTPS on port 443 only
+    wassette permission grant network my-component api.example.com --port 443 --scheme https
+
+    # Grant every host listed in a file (one per line, # starts a comment)
+    wassette permission grant network my-component --hosts-file hosts.txt")]
     Network {
         /// Component ID to grant permission to
+        #[arg(value_parser = crate::parse_component_id)]
         component_id: String,
         /// Host to grant access to
-        host: String,
+        #[arg(required_unless_present = "hosts_file")]
+        host: Option<String>,
+        /// File listing hosts to grant access to in one policy update, one per line
+        #[arg(long, conflicts_with = "host")]
+        hosts_file: Option<PathBuf>,
+        /// Only allow requests to this port; any port when omitted
+        #[arg(long, conflicts_with = "hosts_file")]
+        port: Option<u16>,
+        /// Only allow requests over this scheme (http or https); any scheme when omitted
+        #[arg(long, conflicts_with = "hosts_file", value_parser = ["http", "https"])]
+        scheme: Option<String>,
         /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
         #[arg(long)]
         component_dir: Option<PathBuf>,
@@ -313,6 +702,7 @@ pub enum GrantPermissionCommands {
     )]
     EnvironmentVariable {
         /// Component ID to grant permission to
+        #[arg(value_parser = crate::parse_component_id)]
         component_id: String,
         /// Environment variable key
         key: String,
@@ -320,6 +710,25 @@ pub enum GrantPermissionCommands {
         #[arg(long)]
         component_dir: Option<PathBuf>,
     },
+    /// Set a config value passed to a component through wasi-config.
+    #[command(after_help = "EXAMPLES:
+    # Turn on a feature flag
+    wassette permission grant config my-component ENABLE_BETA_PARSER true
+
+    # Set the region a component talks to
+    wassette permission grant config my-component REGION eu-west-1")]
+    Config {
+        /// Component ID to set the config value for
+        #[arg(value_parser = crate::parse_component_id)]
+        component_id: String,
+        /// Config key
+        key: String,
+        /// Config value
+        value: String,
+        /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
+        #[arg(long)]
+        component_dir: Option<PathBuf>,
+    },
     /// Grant memory permission to a component.
     #[command(after_help = "EXAMPLES:
     # Grant 512 MiB memory limit
@@ -329,11 +738,36 @@ pub enum GrantPermissionCommands {
     wassette permission grant memory my-component 1Gi
 
     # Grant 2048 KiB memory limit
-    wassette permission grant memory my-component 2048Ki")]
+    wassette permission grant memory my-component 2048Ki
+
+    # Grant 2 GB (decimal) memory limit
+    wassette permission grant memory my-component 2G
+
+    # Grant 256 MiB memory limit, given as a plain number of megabytes
+    wassette permission grant memory my-component 256")]
     Memory {
         /// Component ID to grant permission to
+        #[arg(value_parser = crate::parse_component_id)]
+        component_id: String,
+        /// Memory limit: a quantity with a binary (Ki, Mi, Gi, Ti) or decimal
+        /// (k, M, G, T) suffix, or a plain number of megabytes
+        limit: String,
+        /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
+        #[arg(long)]
+        component_dir: Option<PathBuf>,
+    },
+    /// Grant a CPU limit to a component, enforced as fuel per call.
+    #[command(after_help = "EXAMPLES:
+    # Grant half a core
+    wassette permission grant cpu my-component 500m
+
+    # Grant two cores
+    wassette permission grant cpu my-component 2")]
+    Cpu {
+        /// Component ID to grant permission to
+        #[arg(value_parser = crate::parse_component_id)]
         component_id: String,
-        /// Memory limit (e.g., 512Mi, 1Gi, 2048Ki)
+        /// CPU limit in cores or millicores (e.g., 1, 500m)
         limit: String,
         /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
         #[arg(long)]
@@ -346,6 +780,7 @@ pub enum RevokePermissionCommands {
     /// Revoke storage permission from a component.
     Storage {
         /// Component ID to revoke permission from
+        #[arg(value_parser = crate::parse_component_id)]
         component_id: String,
         /// URI of the storage resource (e.g., fs:///path/to/directory)
         uri: String,
@@ -356,9 +791,16 @@ pub enum RevokePermissionCommands {
     /// Revoke network permission from a component.
     Network {
         /// Component ID to revoke permission from
+        #[arg(value_parser = crate::parse_component_id)]
         component_id: String,
         /// Host to revoke access from
         host: String,
+        /// Port of the rule to revoke; matches only rules granted with this port
+        #[arg(long)]
+        port: Option<u16>,
+        /// Scheme of the rule to revoke; matches only rules granted with this scheme
+        #[arg(long, value_parser = ["http", "https"])]
+        scheme: Option<String>,
         /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
         #[arg(long)]
         component_dir: Option<PathBuf>,
@@ -367,6 +809,7 @@ pub enum RevokePermissionCommands {
     #[command(name = "environment-variable")]
     EnvironmentVariable {
         /// Component ID to revoke permission from
+        #[arg(value_parser = crate::parse_component_id)]
         component_id: String,
         /// Environment variable key
         key: String,
@@ -374,6 +817,17 @@ pub enum RevokePermissionCommands {
         #[arg(long)]
         component_dir: Option<PathBuf>,
     },
+    /// Remove a config value passed to a component through wasi-config.
+    Config {
+        /// Component ID to remove the config value from
+        #[arg(value_parser = crate::parse_component_id)]
+        component_id: String,
+        /// Config key
+        key: String,
+        /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
+        #[arg(long)]
+        component_dir: Option<PathBuf>,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -381,10 +835,14 @@ pub enum SecretCommands {
     /// List secrets for a component.
     List {
         /// Component ID to list secrets for
+        #[arg(value_parser = crate::parse_component_id)]
         component_id: String,
         /// Show secret values (prompts for confirmation)
         #[arg(long)]
         show_values: bool,
+        /// Only list the keys, the same way the list-secret-keys tool does
+        #[arg(long, conflicts_with = "show_values")]
+        keys_only: bool,
         /// Skip confirmation prompt when showing values
         #[arg(long)]
         yes: bool,
@@ -398,6 +856,7 @@ pub enum SecretCommands {
     /// Set secrets for a component.
     Set {
         /// Component ID to set secrets for
+        #[arg(value_parser = crate::parse_component_id)]
         component_id: String,
         /// Secrets in KEY=VALUE format. Can be specified multiple times.
         #[arg(value_parser = crate::parse_env_var)]
@@ -409,6 +868,7 @@ pub enum SecretCommands {
     /// Delete secrets for a component.
     Delete {
         /// Component ID to delete secrets from
+        #[arg(value_parser = crate::parse_component_id)]
         component_id: String,
         /// Secret keys to delete
         keys: Vec<String>,
@@ -441,12 +901,22 @@ pub enum ToolCommands {
         output_format: OutputFormat,
     },
     /// Invoke a tool with parameters.
+    #[command(after_help = "EXAMPLES:
+    # Pass arguments as JSON
+    wassette tool invoke fetch --args '{\"url\": \"https://example.com\"}'
+
+    # Pass arguments one at a time, converted to the types in the tool's input schema
+    wassette tool invoke fetch --arg url=https://example.com
+    wassette tool invoke search --arg query=wasm --arg limit=5 --arg tags=rust,wasi")]
     Invoke {
         /// Name of the tool to invoke
         name: String,
-        /// Arguments in JSON format (e.g., '{"key": "value"}')
+        /// Arguments in JSON format (e.g., '{"key": "value"}'). Wins over --arg for the same key
         #[arg(long)]
         args: Option<String>,
+        /// Argument in KEY=VALUE format, converted to the type the tool's input schema gives it. Can be specified multiple times
+        #[arg(long, value_name = "KEY=VALUE", value_parser = crate::tool_args::parse_key_value)]
+        arg: Vec<(String, String)>,
         /// Directory where components are stored. Defaults to $XDG_DATA_HOME/wassette/components
         #[arg(long)]
         component_dir: Option<PathBuf>,
@@ -460,8 +930,11 @@ pub enum ToolCommands {
 pub enum RegistryCommands {
     /// Search for components in the registry.
     Search {
-        /// Search query (matches against component name and description)
+        /// Search query (matches against component name, description and URI)
         query: Option<String>,
+        /// Additional registry JSON files to search alongside the built-in registry
+        #[arg(long = "registry-file")]
+        registry_files: Vec<PathBuf>,
         /// Output format
         #[arg(short = 'o', long = "output-format", default_value = "json")]
         output_format: OutputFormat,
@@ -470,8 +943,14 @@ pub enum RegistryCommands {
     Get {
         /// Component name or URI from the registry
         component: String,
+        /// Additional registry JSON files to search alongside the built-in registry
+        #[arg(long = "registry-file")]
+        registry_files: Vec<PathBuf>,
         /// Directory where plugins are stored. Defaults to $XDG_DATA_HOME/wassette/components
         #[arg(long)]
         plugin_dir: Option<PathBuf>,
+        /// Load the component even if its source is not in `allowed_sources`
+        #[arg(long)]
+        override_source_policy: bool,
     },
 }
@@ -1,41 +1,26 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
-use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use anyhow::Context;
 use etcetera::BaseStrategy;
 use figment::providers::{Env, Format, Serialized, Toml};
+use mcp_server::{CallLimitConfig, RedactionRules, ResultStoreConfig};
+use rmcp::model::LoggingLevel;
 use serde::{Deserialize, Serialize};
-pub use wassette::RegistryCredential;
+use wassette::{LifecycleBuilder, LifecycleConfig};
 
 use crate::commands::{Run, Serve};
 
-/// Get the default component directory path based on the OS
-pub fn get_component_dir() -> Result<PathBuf, anyhow::Error> {
-    let dir_strategy = etcetera::choose_base_strategy().context("Unable to get home directory")?;
-    Ok(dir_strategy.data_dir().join("wassette").join("components"))
-}
-
-/// Get the default secrets directory path based on the OS
-pub fn get_secrets_dir() -> Result<PathBuf, anyhow::Error> {
-    let dir_strategy = etcetera::choose_base_strategy().context("Unable to get home directory")?;
-    Ok(dir_strategy.config_dir().join("wassette").join("secrets"))
-}
-
-fn default_component_dir() -> PathBuf {
-    get_component_dir().unwrap_or_else(|_| {
-        eprintln!("WARN: Unable to determine default component directory, using `components` directory in the current working directory");
-        PathBuf::from("./components")
-    })
-}
-
-fn default_secrets_dir() -> PathBuf {
-    get_secrets_dir().unwrap_or_else(|_| {
-        eprintln!("WARN: Unable to determine default secrets directory, using `secrets` directory in the current working directory");
-        PathBuf::from("./secrets")
-    })
+/// Where `wassette serve` serves Prometheus metrics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetricsEndpoint {
+    /// At `/metrics` next to the MCP endpoint of the HTTP transport.
+    Transport,
+    /// At `/metrics` on a listener of its own, at this address.
+    Listener(String),
 }
 
 fn default_bind_address() -> String {
@@ -47,36 +32,190 @@ fn default_bind_address() -> String {
 }
 
 /// Configuration for the Wasette MCP server
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Deserialize, Serialize)]
 pub struct Config {
-    /// Directory where components are stored
-    #[serde(default = "default_component_dir")]
-    pub component_dir: PathBuf,
-
-    /// Directory where secrets are stored
-    #[serde(default = "default_secrets_dir")]
-    pub secrets_dir: PathBuf,
-
-    /// Environment variables to be made available to components
-    #[serde(default)]
-    pub environment_vars: HashMap<String, String>,
+    /// Settings of the lifecycle manager, read from the same keys as
+    /// [`LifecycleConfig::from_file`]: `component_dir`, `secrets_dir`,
+    /// `environment_vars`, `registry_credentials`, `warmup`, the timeouts and
+    /// limits, the allowed sources, `signature_policy`, `instance_pool`,
+    /// `runtime`, `strict_validation`, `startup_gc`, and the rest. See
+    /// `docs/reference/configuration-files.md` for every key.
+    #[serde(flatten)]
+    pub lifecycle: LifecycleConfig,
 
     /// Bind address for HTTP-based transports (SSE and StreamableHttp)
     /// Configured via PORT and BIND_HOST environment variables or CLI/config file
     #[serde(default = "default_bind_address", rename = "bind_address")]
     pub bind_address: String,
 
-    /// Per-registry authentication credentials.
+    /// Hide and reject the built-in tools that load, unload, or install
+    /// components or change permissions, so clients can only call component
+    /// tools and inspect the server.
+    #[serde(default)]
+    pub read_only: bool,
+
+    /// Report where the time of each component tool call went in the
+    /// result's `_meta.wassette.timing`. Off by default so results stay
+    /// unchanged for existing clients.
+    #[serde(default)]
+    pub call_timing: bool,
+
+    /// Level at which what components write to stderr during a tool call is
+    /// sent to the calling client as MCP log messages. Defaults to `info`:
+    ///
+    /// ```toml
+    /// component_log_level = "warning"
+    /// ```
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component_log_level: Option<LoggingLevel>,
+
+    /// Serve tool results above a size threshold as MCP resources instead
+    /// of inline text. Off unless `threshold_bytes` is set:
+    ///
+    /// ```toml
+    /// [large_results]
+    /// threshold_bytes = 1048576
+    /// retention_secs = 3600
+    /// ```
+    #[serde(default)]
+    pub large_results: LargeResultSettings,
+
+    /// Tool arguments left out of the log lines and error messages of tool
+    /// calls. Unset `patterns` keep the default, which redacts every field
+    /// whose name contains `password`, `secret`, `token`, or `key`:
     ///
-    /// The key is the registry hostname (e.g. `"ghcr.io"`).  Configure this
-    /// in `config.toml` to enable pulling from private OCI registries:
+    /// ```toml
+    /// [redaction]
+    /// fields = ["cookie"]
+    /// pointers = ["/headers/*/value"]
+    /// allow = ["key_id"]
+    /// ```
+    #[serde(default)]
+    pub redaction: RedactionSettings,
+
+    /// Ceiling on tool calls running at once across all sessions. Off
+    /// unless `max_concurrent_calls` is set:
     ///
     /// ```toml
-    /// [registry_credentials]
-    /// "ghcr.io" = { username = "myuser", password = "mytoken" }
+    /// [call_limit]
+    /// max_concurrent_calls = 64
+    /// queue_timeout_secs = 30
+    /// exempt_builtin_tools = false
     /// ```
     #[serde(default)]
-    pub registry_credentials: HashMap<String, RegistryCredential>,
+    pub call_limit: CallLimitSettings,
+
+    /// State manifest (`components.lock.json`) reconciled against the
+    /// component directory at startup and updated as components are loaded
+    /// and unloaded. `wassette serve --state-manifest` sets it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_manifest: Option<PathBuf>,
+
+    /// Serve execution metrics in the Prometheus text format at `/metrics`
+    /// on the HTTP transport, or on a listener of their own when
+    /// `metrics_addr` is set. `wassette serve --metrics` turns it on.
+    #[serde(default)]
+    pub metrics: bool,
+
+    /// Address of a separate Prometheus metrics listener, e.g.
+    /// `0.0.0.0:9464`. Setting it turns metrics on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics_addr: Option<String>,
+}
+
+/// Settings for serving large tool results as resources.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct LargeResultSettings {
+    /// Results whose text is larger than this many bytes are served as
+    /// resources. Unset keeps every result inline.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold_bytes: Option<usize>,
+    /// Seconds a result can be read before it is deleted. Defaults to 3600.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_secs: Option<u64>,
+    /// Directory the results are written to. Defaults to `results` in the
+    /// component directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<PathBuf>,
+}
+
+impl LargeResultSettings {
+    /// The result store config, or `None` when large results stay inline.
+    pub fn store_config(&self, component_dir: &Path) -> Option<ResultStoreConfig> {
+        let threshold_bytes = self.threshold_bytes?;
+        let defaults = ResultStoreConfig::new(component_dir);
+        Some(ResultStoreConfig {
+            dir: self.dir.clone().unwrap_or(defaults.dir),
+            threshold_bytes,
+            retention: self
+                .retention_secs
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.retention),
+        })
+    }
+}
+
+/// Settings for limiting concurrent tool calls.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct CallLimitSettings {
+    /// Tool calls allowed to run at once. Unset leaves calls unlimited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_calls: Option<usize>,
+    /// Seconds a call waits for a free slot before it is refused. Defaults
+    /// to 30.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue_timeout_secs: Option<u64>,
+    /// Let the built-in tools that only read, and unloading or deactivating
+    /// a component, run without a slot. Defaults to false.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exempt_builtin_tools: Option<bool>,
+}
+
+impl CallLimitSettings {
+    /// The call limit, or `None` when calls are unlimited.
+    pub fn limit_config(&self) -> Option<CallLimitConfig> {
+        let defaults = CallLimitConfig::new(self.max_concurrent_calls?);
+        Some(CallLimitConfig {
+            queue_timeout: self
+                .queue_timeout_secs
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.queue_timeout),
+            exempt_builtin_tools: self
+                .exempt_builtin_tools
+                .unwrap_or(defaults.exempt_builtin_tools),
+            ..defaults
+        })
+    }
+}
+
+/// Rules for redacting tool arguments; see [`RedactionRules`].
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct RedactionSettings {
+    /// Field names redacted wherever they appear, compared exactly.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<String>,
+    /// Regular expressions matched against field names. Unset keeps the
+    /// default pattern; an empty list turns it off.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patterns: Option<Vec<String>>,
+    /// JSON pointers to redacted values; `*` matches any segment.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pointers: Vec<String>,
+    /// Field names, or JSON pointers, that are never redacted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+}
+
+impl From<RedactionSettings> for RedactionRules {
+    fn from(settings: RedactionSettings) -> Self {
+        let defaults = RedactionRules::default();
+        RedactionRules {
+            fields: settings.fields,
+            patterns: settings.patterns.unwrap_or(defaults.patterns),
+            pointers: settings.pointers,
+            allow: settings.allow,
+        }
+    }
 }
 
 impl Config {
@@ -124,6 +263,7 @@ impl Config {
     pub fn from_run(run_config: &Run) -> Result<Self, anyhow::Error> {
         // Start with the base config using existing logic
         let mut config = Self::new(run_config)?;
+        let mut environment_vars = config.lifecycle.environment_vars().clone();
 
         // Load environment variables from file if specified
         if let Some(env_file) = &run_config.env_file {
@@ -133,21 +273,24 @@ impl Config {
 
             // Merge file environment variables (they have lower precedence than CLI args)
             for (key, value) in file_env_vars {
-                config.environment_vars.insert(key, value);
+                environment_vars.insert(key, value);
             }
         }
 
         // Apply CLI environment variables (highest precedence)
         for (key, value) in &run_config.env_vars {
-            config.environment_vars.insert(key.clone(), value.clone());
+            environment_vars.insert(key.clone(), value.clone());
         }
 
         // Also include system environment variables that aren't overridden
         // This maintains backward compatibility
         for (key, value) in std::env::vars() {
-            config.environment_vars.entry(key).or_insert(value);
+            environment_vars.entry(key).or_insert(value);
         }
 
+        config.lifecycle = LifecycleBuilder::from_config(config.lifecycle)
+            .with_environment_vars(environment_vars)
+            .build_config()?;
         Ok(config)
     }
 
@@ -155,6 +298,7 @@ impl Config {
     pub fn from_serve(serve_config: &Serve) -> Result<Self, anyhow::Error> {
         // Start with the base config using existing logic
         let mut config = Self::new(serve_config)?;
+        let mut environment_vars = config.lifecycle.environment_vars().clone();
 
         // Load environment variables from file if specified
         if let Some(env_file) = &serve_config.env_file {
@@ -164,23 +308,71 @@ impl Config {
 
             // Merge file environment variables (they have lower precedence than CLI args)
             for (key, value) in file_env_vars {
-                config.environment_vars.insert(key, value);
+                environment_vars.insert(key, value);
             }
         }
 
         // Apply CLI environment variables (highest precedence)
         for (key, value) in &serve_config.env_vars {
-            config.environment_vars.insert(key.clone(), value.clone());
+            environment_vars.insert(key.clone(), value.clone());
         }
 
         // Also include system environment variables that aren't overridden
         // This maintains backward compatibility
         for (key, value) in std::env::vars() {
-            config.environment_vars.entry(key).or_insert(value);
+            environment_vars.entry(key).or_insert(value);
         }
 
+        config.lifecycle = LifecycleBuilder::from_config(config.lifecycle)
+            .with_environment_vars(environment_vars)
+            .build_config()?;
         Ok(config)
     }
+
+    /// Creates the config used by one-shot CLI commands. Directories are
+    /// resolved exactly as for `serve`: the `--component-dir` flag, then
+    /// `WASSETTE_` environment variables, then the configuration file, then
+    /// the platform data and config directories.
+    pub fn for_command(component_dir: Option<PathBuf>) -> Result<Self, anyhow::Error> {
+        Self::from_serve(&Serve {
+            component_dir,
+            transport: Default::default(),
+            env_vars: vec![],
+            env_file: None,
+            disable_builtin_tools: false,
+            require_healthy_components: false,
+            allow_env_inherit: false,
+            auto_reload_on_drift: false,
+            watch: false,
+            read_only: false,
+            require_signatures: false,
+            bind_address: None,
+            manifest: None,
+            state_manifest: None,
+            metrics: false,
+            metrics_addr: None,
+        })
+    }
+
+    /// Where to serve Prometheus metrics, if they are served.
+    pub fn metrics_endpoint(&self) -> Option<MetricsEndpoint> {
+        match &self.metrics_addr {
+            Some(address) => Some(MetricsEndpoint::Listener(address.clone())),
+            None => self.metrics.then_some(MetricsEndpoint::Transport),
+        }
+    }
+
+    /// Print the resolved component and secrets directories to stderr, for `--verbose`.
+    pub fn print_resolved_dirs(&self) {
+        eprintln!(
+            "Component directory: {}",
+            self.lifecycle.component_dir().display()
+        );
+        eprintln!(
+            "Secrets directory: {}",
+            self.lifecycle.secrets_dir().display()
+        );
+    }
 }
 
 #[cfg(test)]
@@ -189,6 +381,10 @@ mod tests {
     use std::fs;
 
     use tempfile::TempDir;
+    use wassette::{
+        AllowedSource, ComponentFilter, DownloadsRetention, OutboundHttpConfig, ParameterLimits,
+        SignatureMode,
+    };
 
     use super::*;
 
@@ -199,6 +395,10 @@ mod tests {
             env_vars: vec![],
             env_file: None,
             disable_builtin_tools: false,
+            require_healthy_components: false,
+            allow_env_inherit: false,
+            auto_reload_on_drift: false,
+            read_only: false,
         }
     }
 
@@ -209,6 +409,10 @@ mod tests {
             env_vars: vec![],
             env_file: None,
             disable_builtin_tools: false,
+            require_healthy_components: false,
+            allow_env_inherit: false,
+            auto_reload_on_drift: false,
+            read_only: false,
         }
     }
 
@@ -219,8 +423,17 @@ mod tests {
             env_vars: vec![],
             env_file: None,
             disable_builtin_tools: false,
+            require_healthy_components: false,
+            allow_env_inherit: false,
+            auto_reload_on_drift: false,
+            watch: false,
+            read_only: false,
+            require_signatures: false,
             bind_address: None,
             manifest: None,
+            state_manifest: None,
+            metrics: false,
+            metrics_addr: None,
         }
     }
 
@@ -231,8 +444,17 @@ mod tests {
             env_vars: vec![],
             env_file: None,
             disable_builtin_tools: false,
+            require_healthy_components: false,
+            allow_env_inherit: false,
+            auto_reload_on_drift: false,
+            watch: false,
+            read_only: false,
+            require_signatures: false,
             bind_address: None,
             manifest: None,
+            state_manifest: None,
+            metrics: false,
+            metrics_addr: None,
         }
     }
 
@@ -272,7 +494,10 @@ mod tests {
             .expect("Failed to create config");
 
         // Should use CLI config values since no config file exists
-        assert_eq!(config.component_dir, PathBuf::from("/test/component/dir"));
+        assert_eq!(
+            config.lifecycle.component_dir(),
+            Path::new("/test/component/dir")
+        );
     }
 
     #[test]
@@ -289,7 +514,10 @@ component_dir = "/config/component/dir"
         let config =
             Config::new_from_path(&serve_config, &config_file).expect("Failed to create config");
 
-        assert_eq!(config.component_dir, PathBuf::from("/test/component/dir"));
+        assert_eq!(
+            config.lifecycle.component_dir(),
+            Path::new("/test/component/dir")
+        );
     }
 
     #[test]
@@ -305,7 +533,10 @@ component_dir = "/config/component/dir"
         let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
             .expect("Failed to create config");
 
-        assert_eq!(config.component_dir, PathBuf::from("/config/component/dir"));
+        assert_eq!(
+            config.lifecycle.component_dir(),
+            Path::new("/config/component/dir")
+        );
     }
 
     #[test]
@@ -318,7 +549,10 @@ component_dir = "/config/component/dir"
             .expect("Failed to create config");
 
         // Should use CLI config values as defaults
-        assert_eq!(config.component_dir, PathBuf::from("/test/component/dir"));
+        assert_eq!(
+            config.lifecycle.component_dir(),
+            Path::new("/test/component/dir")
+        );
     }
 
     #[test]
@@ -336,7 +570,10 @@ component_dir = "/config/component/dir"
             .expect("Failed to create config");
 
         // component_dir should come from config file
-        assert_eq!(config.component_dir, PathBuf::from("/config/component/dir"));
+        assert_eq!(
+            config.lifecycle.component_dir(),
+            Path::new("/config/component/dir")
+        );
     }
 
     #[test]
@@ -352,7 +589,10 @@ component_dir = "/config/component/dir"
         let config = Config::new(&serve_config).expect("Failed to create config");
 
         // Should use CLI defaults since no config file exists
-        assert_eq!(config.component_dir, PathBuf::from("/test/component/dir"));
+        assert_eq!(
+            config.lifecycle.component_dir(),
+            Path::new("/test/component/dir")
+        );
     }
 
     #[test]
@@ -390,7 +630,10 @@ policy_file = "custom_policy.yaml"
 
         let config = Config::new(&empty_test_cli_config()).expect("Failed to create config");
 
-        assert_eq!(config.component_dir, PathBuf::from("/custom/component/dir"));
+        assert_eq!(
+            config.lifecycle.component_dir(),
+            Path::new("/custom/component/dir")
+        );
     }
 
     #[test]
@@ -407,6 +650,411 @@ policy_file = "custom_policy.yaml"
         });
     }
 
+    #[test]
+    fn test_metrics_endpoint_from_config_file_or_cli() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("config.toml");
+
+        fs::write(&config_file, "").unwrap();
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        assert_eq!(config.metrics_endpoint(), None);
+
+        let config = Config::new_from_path(
+            &Serve {
+                metrics: true,
+                ..empty_test_cli_config()
+            },
+            &config_file,
+        )
+        .expect("Failed to create config");
+        assert_eq!(config.metrics_endpoint(), Some(MetricsEndpoint::Transport));
+
+        // An address turns metrics on by itself, on a listener of their own
+        fs::write(&config_file, "metrics_addr = \"0.0.0.0:9100\"\n").unwrap();
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        assert_eq!(
+            config.metrics_endpoint(),
+            Some(MetricsEndpoint::Listener("0.0.0.0:9100".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_read_only_from_config_file_or_cli() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("config.toml");
+
+        // An unset CLI flag must not override the configuration file.
+        fs::write(&config_file, "read_only = true\n").unwrap();
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        assert!(config.read_only);
+
+        fs::write(&config_file, "").unwrap();
+        let config = Config::new_from_path(
+            &Serve {
+                read_only: true,
+                ..empty_test_cli_config()
+            },
+            &config_file,
+        )
+        .expect("Failed to create config");
+        assert!(config.read_only);
+    }
+
+    #[test]
+    fn test_state_manifest_from_config_file_or_cli() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("config.toml");
+
+        fs::write(
+            &config_file,
+            "state_manifest = \"/data/components.lock.json\"\n",
+        )
+        .unwrap();
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        assert_eq!(
+            config.state_manifest,
+            Some(PathBuf::from("/data/components.lock.json"))
+        );
+
+        // A provisioning manifest is never taken for a state manifest.
+        fs::write(&config_file, "").unwrap();
+        let config = Config::new_from_path(
+            &Serve {
+                manifest: Some(PathBuf::from("provision.json")),
+                ..empty_test_cli_config()
+            },
+            &config_file,
+        )
+        .expect("Failed to create config");
+        assert_eq!(config.state_manifest, None);
+
+        let config = Config::new_from_path(
+            &Serve {
+                state_manifest: Some(PathBuf::from("/state/lock.json")),
+                ..empty_test_cli_config()
+            },
+            &config_file,
+        )
+        .expect("Failed to create config");
+        assert_eq!(
+            config.state_manifest,
+            Some(PathBuf::from("/state/lock.json"))
+        );
+    }
+
+    #[test]
+    fn test_outbound_http_from_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("config.toml");
+
+        fs::write(
+            &config_file,
+            "[outbound_http]\nrequest_timeout_secs = 5\nmax_connections_per_host = 2\nmax_response_bytes = 1024\n",
+        )
+        .unwrap();
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        let outbound_http = config.lifecycle.outbound_http();
+
+        assert_eq!(outbound_http.request_timeout, Duration::from_secs(5));
+        assert_eq!(outbound_http.max_connections_per_host, 2);
+        assert_eq!(outbound_http.max_response_bytes, Some(1024));
+        assert_eq!(
+            outbound_http.connect_timeout,
+            OutboundHttpConfig::default().connect_timeout
+        );
+    }
+
+    #[test]
+    fn test_parameter_limits_from_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("config.toml");
+
+        fs::write(
+            &config_file,
+            "[parameter_limits]
+max_depth = 8
+",
+        )
+        .unwrap();
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        let parameter_limits = config.lifecycle.parameter_limits();
+
+        assert_eq!(parameter_limits.max_depth, 8);
+        assert_eq!(
+            parameter_limits.max_bytes,
+            ParameterLimits::default().max_bytes
+        );
+    }
+
+    #[test]
+    fn test_load_priority_from_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("config.toml");
+
+        fs::write(&config_file, "load_priority = [\"fetch_rs\", \"time\"]\n").unwrap();
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        assert_eq!(config.lifecycle.load_priority(), ["fetch_rs", "time"]);
+    }
+
+    #[test]
+    fn test_component_log_level_from_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("config.toml");
+
+        fs::write(&config_file, "component_log_level = \"warning\"\n").unwrap();
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        assert_eq!(config.component_log_level, Some(LoggingLevel::Warning));
+    }
+
+    #[test]
+    fn test_large_results_from_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("config.toml");
+
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        assert!(config
+            .large_results
+            .store_config(config.lifecycle.component_dir())
+            .is_none());
+
+        fs::write(
+            &config_file,
+            "[large_results]\nthreshold_bytes = 4096\nretention_secs = 60\n",
+        )
+        .unwrap();
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        let store_config = config
+            .large_results
+            .store_config(config.lifecycle.component_dir())
+            .unwrap();
+        assert_eq!(store_config.threshold_bytes, 4096);
+        assert_eq!(store_config.retention, Duration::from_secs(60));
+        assert_eq!(
+            store_config.dir,
+            config.lifecycle.component_dir().join("results")
+        );
+    }
+
+    #[test]
+    fn test_redaction_from_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("config.toml");
+
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        assert_eq!(
+            RedactionRules::from(config.redaction),
+            RedactionRules::default()
+        );
+
+        fs::write(
+            &config_file,
+            "[redaction]\nfields = [\"cookie\"]\npatterns = []\nallow = [\"/auth/key_id\"]\n",
+        )
+        .unwrap();
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        let rules = RedactionRules::from(config.redaction);
+        assert_eq!(rules.fields, ["cookie"]);
+        assert!(rules.patterns.is_empty());
+        assert_eq!(rules.allow, ["/auth/key_id"]);
+    }
+
+    #[test]
+    fn test_downloads_retention_from_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("config.toml");
+
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        assert_eq!(
+            config.lifecycle.downloads_retention(),
+            DownloadsRetention::default()
+        );
+
+        fs::write(
+            &config_file,
+            "[downloads]\nmax_age_secs = 3600\nmax_total_bytes = 0\n",
+        )
+        .unwrap();
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        let retention = config.lifecycle.downloads_retention();
+        assert_eq!(retention.max_age, Some(Duration::from_secs(3600)));
+        assert_eq!(retention.max_total_bytes, None);
+    }
+
+    #[test]
+    fn test_call_limit_from_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("config.toml");
+
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        assert!(config.call_limit.limit_config().is_none());
+
+        fs::write(
+            &config_file,
+            "[call_limit]\nmax_concurrent_calls = 8\nexempt_builtin_tools = true\n",
+        )
+        .unwrap();
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        let limit = config.call_limit.limit_config().unwrap();
+        assert_eq!(limit.max_concurrent_calls, 8);
+        assert_eq!(limit.queue_timeout, Duration::from_secs(30));
+        assert!(limit.exempt_builtin_tools);
+    }
+
+    #[test]
+    fn test_scratch_dir_from_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("config.toml");
+
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        assert_eq!(
+            config.lifecycle.scratch_dir(),
+            config.lifecycle.component_dir().join("scratch")
+        );
+
+        fs::write(&config_file, "scratch_dir = \"/var/tmp/wassette\"\n").unwrap();
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        assert_eq!(
+            config.lifecycle.scratch_dir(),
+            Path::new("/var/tmp/wassette")
+        );
+    }
+
+    #[test]
+    fn test_changelog_file_from_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("config.toml");
+
+        fs::write(
+            &config_file,
+            "changelog_file = \"/var/log/wassette/changes.jsonl\"\n",
+        )
+        .unwrap();
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        assert_eq!(
+            config.lifecycle.changelog_path(),
+            Some(Path::new("/var/log/wassette/changes.jsonl"))
+        );
+    }
+
+    #[test]
+    fn test_signature_policy_from_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("config.toml");
+
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        assert!(!config.lifecycle.signature_policy().is_enabled());
+
+        fs::write(
+            &config_file,
+            "[signature_policy]\nmode = \"warn_only\"\ntrusted_keys = []\n",
+        )
+        .unwrap();
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        assert_eq!(
+            config.lifecycle.signature_policy().mode,
+            SignatureMode::WarnOnly
+        );
+        assert!(config.lifecycle.signature_policy().trusted_keys.is_empty());
+    }
+
+    #[test]
+    fn test_component_filter_from_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("config.toml");
+
+        fs::write(
+            &config_file,
+            "[component_filter]\nallow = [\"fetch-rs\", \"time-*\"]\ndeny = [\"*-debug\"]\n",
+        )
+        .unwrap();
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        assert_eq!(
+            config.lifecycle.component_filter(),
+            &ComponentFilter::allow(["fetch-rs", "time-*"]).and_deny(["*-debug"])
+        );
+        assert!(!config.lifecycle.component_filter().allows("time-debug"));
+    }
+
+    #[test]
+    fn test_allowed_sources_from_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("config.toml");
+
+        fs::write(
+            &config_file,
+            "allowed_sources = [{ scheme = \"oci\", hostPattern = \"registry.internal/**\" }, { scheme = \"file\" }]\n",
+        )
+        .unwrap();
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        assert_eq!(
+            config.lifecycle.allowed_sources(),
+            [
+                AllowedSource::new("oci", "registry.internal/**"),
+                AllowedSource::any("file"),
+            ]
+        );
+        // Policies are held to the component sources unless set
+        assert_eq!(
+            config.lifecycle.allowed_policy_sources(),
+            config.lifecycle.allowed_sources()
+        );
+
+        fs::write(
+            &config_file,
+            "allowed_policy_sources = [{ scheme = \"https\" }]\n",
+        )
+        .unwrap();
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        assert_eq!(
+            config.lifecycle.allowed_policy_sources(),
+            [AllowedSource::any("https")]
+        );
+    }
+
+    #[test]
+    fn test_lifecycle_settings_without_flags_from_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("config.toml");
+
+        fs::write(
+            &config_file,
+            "instance_pool = 4\nstrict_validation = false\nstartup_gc = true\nrequire_healthy_components = true\nallow_env_inherit = true\n",
+        )
+        .unwrap();
+        let config = Config::new_from_path(&empty_test_cli_config(), &config_file)
+            .expect("Failed to create config");
+        assert_eq!(config.lifecycle.instance_pool(), 4);
+        assert!(!config.lifecycle.strict_validation());
+        assert!(config.lifecycle.startup_gc());
+        // Flags that are not passed leave the configuration file alone
+        assert!(config.lifecycle.health_checks().require_healthy);
+        assert!(config.lifecycle.allow_env_inherit());
+    }
+
     #[test]
     fn test_bind_address_from_config_file() {
         temp_env::with_vars_unset(vec!["PORT", "BIND_HOST"], || {
@@ -443,8 +1091,17 @@ bind_address = "0.0.0.0:8080"
             env_vars: vec![],
             env_file: None,
             disable_builtin_tools: false,
+            require_healthy_components: false,
+            allow_env_inherit: false,
+            auto_reload_on_drift: false,
+            watch: false,
+            read_only: false,
+            require_signatures: false,
             bind_address: Some("192.168.1.100:9090".to_string()),
             manifest: None,
+            state_manifest: None,
+            metrics: false,
+            metrics_addr: None,
         };
 
         let config =
@@ -6,7 +6,7 @@
 use anyhow::Result;
 use clap::ValueEnum;
 use rmcp::model::CallToolResult;
-use serde_json::{Map, Value};
+use serde_json::Value;
 
 /// Output format options for CLI commands
 #[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
@@ -31,88 +31,1569 @@ pub fn format_as_yaml(value: &Value) -> Result<String> {
 }
 
 /// Format a JSON value as a table string
+///
+/// Component listings and component stats get one row per component;
+/// anything else becomes a key/value table with nested fields flattened into dotted keys.
 pub fn format_as_table(value: &Value) -> Result<String> {
     // Check if this is a component list output
-    if let Some(obj) = value.as_object() {
-        if let Some(components) = obj.get("components").and_then(|v| v.as_array()) {
-            let mut table = String::new();
-            table.push_str("ID                    | Tools Count\n");
-            table.push_str("----------------------|-------------\n");
-
-            for component in components {
-                if let Some(comp_obj) = component.as_object() {
-                    let id = comp_obj
-                        .get("id")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("unknown");
-                    let tools_count = comp_obj
-                        .get("tools_count")
-                        .and_then(|v| v.as_u64())
-                        .unwrap_or(0);
-                    table.push_str(&format!("{id:<21} | {tools_count}\n"));
-                }
+    if let Some(components) = value
+        .get("components")
+        .and_then(|v| v.as_array())
+        .filter(|components| is_component_list(components))
+    {
+        let mut table = String::new();
+        table.push_str("ID                    | Tools Count\n");
+        table.push_str("----------------------|-------------\n");
+
+        for component in components {
+            if let Some(comp_obj) = component.as_object() {
+                let id = comp_obj
+                    .get("id")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("unknown");
+                let tools_count = comp_obj
+                    .get("tools_count")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0);
+                table.push_str(&format!("{id:<21} | {tools_count}\n"));
             }
-            return Ok(table);
         }
+        return Ok(table);
     }
 
-    // Default generic table format
-    let mut table = String::new();
-    table.push_str("Key                   | Value\n");
-    table.push_str("----------------------|--------\n");
-
-    fn add_to_table(obj: &Map<String, Value>, table: &mut String) {
-        for (key, value) in obj {
-            let value_str = match value {
-                Value::String(s) => s.clone(),
-                Value::Number(n) => n.to_string(),
-                Value::Bool(b) => b.to_string(),
-                Value::Null => "null".to_string(),
-                Value::Array(_) => "[array]".to_string(),
-                Value::Object(_) => "[object]".to_string(),
-            };
-            table.push_str(&format!("{key:<21} | {value_str}\n"));
+    // Check if this is a component stats output
+    if let Some(components) = value
+        .get("components")
+        .and_then(|v| v.as_array())
+        .filter(|components| is_component_stats(components))
+    {
+        let mut table = String::new();
+        table.push_str(
+            "ID                    | Calls    | Errors   | Denials  | p50 ms   | p95 ms   | Last Used\n",
+        );
+        table.push_str(
+            "----------------------|----------|----------|----------|----------|----------|------------\n",
+        );
+
+        for component in components {
+            let id = component
+                .get("id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("unknown");
+            let count = |key: &str| component.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+            let last_used = component
+                .get("last_used")
+                .and_then(|v| v.as_u64())
+                .map_or_else(|| "never".to_string(), |last_used| last_used.to_string());
+            table.push_str(&format!(
+                "{id:<21} | {:<8} | {:<8} | {:<8} | {:<8} | {:<8} | {last_used}\n",
+                count("calls"),
+                count("errors"),
+                count("permission_denials"),
+                count("p50_ms"),
+                count("p95_ms"),
+            ));
         }
+        return Ok(table);
     }
 
+    // Default generic table format
+    let mut rows = Vec::new();
+    if matches!(value, Value::Object(_) | Value::Array(_)) {
+        flatten_into_rows(String::new(), value, &mut rows);
+    }
+    // Widen the key column for long flattened keys
+    let width = rows
+        .iter()
+        .map(|(key, _)| key.len())
+        .max()
+        .unwrap_or(0)
+        .max(21);
+
+    let mut table = String::new();
+    table.push_str(&format!("{:<width$} | Value\n", "Key"));
+    table.push_str(&format!("{}|--------\n", "-".repeat(width + 1)));
+
     match value {
-        Value::Object(obj) => add_to_table(obj, &mut table),
+        Value::Object(_) | Value::Array(_) => {
+            for (key, value_str) in rows {
+                table.push_str(&format!("{key:<width$} | {value_str}\n"));
+            }
+        }
         _ => table.push_str(&format!("Value: {value}\n")),
     }
 
     Ok(table)
 }
 
-/// Print the result of a tool call with the specified format
-pub fn print_result(result: &CallToolResult, output_format: OutputFormat) -> Result<()> {
+/// Whether a `components` array is a component listing (as opposed to, for
+/// example, registry search results, which have no tool counts).
+fn is_component_list(components: &[Value]) -> bool {
+    components
+        .iter()
+        .all(|component| component.get("id").is_some() && component.get("tools_count").is_some())
+}
+
+/// Whether a `components` array holds the execution stats of components, as
+/// listed by `wassette component stats`.
+fn is_component_stats(components: &[Value]) -> bool {
+    components
+        .iter()
+        .all(|component| component.get("id").is_some() && component.get("calls").is_some())
+}
+
+/// Collect `(key, value)` rows for every scalar in `value`, naming nested
+/// fields `parent.child` and array elements `parent[index]`.
+fn flatten_into_rows(key: String, value: &Value, rows: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(obj) if !obj.is_empty() => {
+            for (child, child_value) in obj {
+                let child_key = if key.is_empty() {
+                    child.clone()
+                } else {
+                    format!("{key}.{child}")
+                };
+                flatten_into_rows(child_key, child_value, rows);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for (index, item) in items.iter().enumerate() {
+                flatten_into_rows(format!("{key}[{index}]"), item, rows);
+            }
+        }
+        _ => rows.push((key, scalar_to_string(value))),
+    }
+}
+
+fn scalar_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Null => "null".to_string(),
+        Value::Array(_) => "[]".to_string(),
+        Value::Object(_) => "{}".to_string(),
+    }
+}
+
+/// Replace string values that hold a JSON object or array with the parsed
+/// value, so payloads that were encoded twice render as nested data in a
+/// table.
+fn expand_embedded_json(value: Value) -> Value {
+    match value {
+        Value::String(text) => {
+            let trimmed = text.trim_start();
+            if trimmed.starts_with('{') || trimmed.starts_with('[') {
+                if let Ok(parsed @ (Value::Object(_) | Value::Array(_))) =
+                    serde_json::from_str::<Value>(&text)
+                {
+                    return expand_embedded_json(parsed);
+                }
+            }
+            Value::String(text)
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(expand_embedded_json).collect()),
+        Value::Object(obj) => Value::Object(
+            obj.into_iter()
+                .map(|(key, value)| (key, expand_embedded_json(value)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+/// Render a JSON value in the specified format
+///
+/// JSON and YAML keep the value as the tool returned it, so scripts see
+/// strings as strings; only the table expands JSON embedded in strings.
+fn render_value(value: Value, output_format: OutputFormat) -> Result<String> {
+    match output_format {
+        // Always pretty-print JSON for better readability
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(&value)?),
+        OutputFormat::Yaml => format_as_yaml(&value),
+        OutputFormat::Table => format_as_table(&expand_embedded_json(value)),
+    }
+}
+
+/// Render the result of a tool call in the specified format
+///
+/// Structured content is preferred when the tool returned any. Otherwise
+/// each text item that holds JSON is rendered natively in the selected
+/// format, and any other text is returned as is.
+pub fn render_result(result: &CallToolResult, output_format: OutputFormat) -> Result<String> {
+    if let Some(structured) = &result.structured_content {
+        return render_value(structured.clone(), output_format);
+    }
+
+    let mut rendered = Vec::new();
     for content in &result.content {
         // Check if we can get text content from the annotated content
         if let Some(text_content) = content.as_text() {
-            // Try to parse as JSON first
-            if let Ok(json_value) = serde_json::from_str::<Value>(&text_content.text) {
-                match output_format {
-                    OutputFormat::Json => {
-                        // Always pretty-print JSON for better readability
-                        println!("{}", serde_json::to_string_pretty(&json_value)?);
-                    }
-                    OutputFormat::Yaml => {
-                        // Convert JSON to YAML
-                        println!("{}", format_as_yaml(&json_value)?);
-                    }
-                    OutputFormat::Table => {
-                        // Format as table
-                        println!("{}", format_as_table(&json_value)?);
-                    }
-                }
-            } else {
+            match serde_json::from_str::<Value>(&text_content.text) {
+                Ok(json_value) => rendered.push(render_value(json_value, output_format)?),
                 // If it's not JSON, just print the text
-                println!("{}", text_content.text);
+                Err(_) => rendered.push(text_content.text.clone()),
             }
         } else {
             // Handle other content types by serializing to JSON
-            println!("Content: {}", serde_json::to_string_pretty(content)?);
+            rendered.push(format!(
+                "Content: {}",
+                serde_json::to_string_pretty(content)?
+            ));
         }
     }
 
+    Ok(rendered.join("\n"))
+}
+
+/// Print the result of a tool call with the specified format
+pub fn print_result(result: &CallToolResult, output_format: OutputFormat) -> Result<()> {
+    let rendered = render_result(result, output_format)?;
+    if !rendered.is_empty() {
+        println!("{rendered}");
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use mcp_server::{handle_tools_call, LifecycleManager};
+    use rmcp::model::{CallToolRequestParam, Content};
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn text_result(value: Value) -> CallToolResult {
+        CallToolResult {
+            content: vec![Content::text(value.to_string())],
+            structured_content: None,
+            is_error: None,
+            meta: None,
+        }
+    }
+
+    /// Check the rendering of `result` in every output format.
+    fn assert_renders(result: &CallToolResult, json: &str, yaml: &str, table: &str) {
+        assert_eq!(render_result(result, OutputFormat::Json).unwrap(), json);
+        assert_eq!(render_result(result, OutputFormat::Yaml).unwrap(), yaml);
+        assert_eq!(render_result(result, OutputFormat::Table).unwrap(), table);
+    }
+
+    /// `result` with the values at the JSON `pointers` replaced by `value`,
+    /// for fields such as timings that differ between runs.
+    fn pin_fields(result: &CallToolResult, pointers: &[&str], value: Value) -> CallToolResult {
+        let mut json: Value =
+            serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        for pointer in pointers {
+            *json.pointer_mut(pointer).unwrap() = value.clone();
+        }
+        text_result(json)
+    }
+
+    /// Call a built-in tool the way a client of the server would.
+    async fn call_tool(
+        lifecycle_manager: &LifecycleManager,
+        name: &str,
+        args: Value,
+    ) -> CallToolResult {
+        let req = CallToolRequestParam {
+            name: name.to_string().into(),
+            arguments: args.as_object().cloned(),
+        };
+        let result = handle_tools_call(req, lifecycle_manager, false, false)
+            .await
+            .unwrap();
+        serde_json::from_value(result).unwrap()
+    }
+
+    /// A component directory with the fetch-rs test component loaded through
+    /// `load-component`, and the result of the load.
+    async fn manager_with_fetch() -> (TempDir, LifecycleManager, CallToolResult) {
+        let tempdir = tempfile::tempdir().unwrap();
+        let lifecycle_manager = LifecycleManager::new_unloaded(&tempdir).await.unwrap();
+        let component = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("crates/component2json/testdata/fetch-rs.wasm");
+        let result = call_tool(
+            &lifecycle_manager,
+            "load-component",
+            json!({ "path": format!("file://{}", component.display()) }),
+        )
+        .await;
+        (tempdir, lifecycle_manager, result)
+    }
+
+    #[tokio::test]
+    async fn test_render_load_component() {
+        let (_tempdir, _lifecycle_manager, result) = manager_with_fetch().await;
+        assert_renders(
+            &result,
+            r#"{
+  "added_tools": [
+    "fetch"
+  ],
+  "changed_tools": [],
+  "id": "fetch-rs",
+  "removed_tools": [],
+  "status": "component loaded successfully",
+  "tools": [
+    "fetch"
+  ]
+}"#,
+            "added_tools:
+- fetch
+changed_tools: []
+id: fetch-rs
+removed_tools: []
+status: component loaded successfully
+tools:
+- fetch
+",
+            "Key                   | Value
+----------------------|--------
+added_tools[0]        | fetch
+changed_tools         | []
+id                    | fetch-rs
+removed_tools         | []
+status                | component loaded successfully
+tools[0]              | fetch
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_list_components() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        let result = call_tool(&lifecycle_manager, "list-components", json!({})).await;
+        assert_renders(
+            &result,
+            r#"{
+  "components": [
+    {
+      "docs_excerpt": "An example world for the component to target.",
+      "health": null,
+      "hidden": false,
+      "id": "fetch-rs",
+      "last_used": null,
+      "load_state": {
+        "state": "loaded"
+      },
+      "schema": {
+        "tools": [
+          {
+            "description": "Fetch data from a URL and return the response body as a String",
+            "inputSchema": {
+              "properties": {
+                "url": {
+                  "type": "string"
+                }
+              },
+              "required": [
+                "url"
+              ],
+              "type": "object"
+            },
+            "name": "fetch",
+            "outputSchema": {
+              "properties": {
+                "result": {
+                  "oneOf": [
+                    {
+                      "properties": {
+                        "ok": {
+                          "type": "string"
+                        }
+                      },
+                      "required": [
+                        "ok"
+                      ],
+                      "type": "object"
+                    },
+                    {
+                      "properties": {
+                        "err": {
+                          "type": "string"
+                        }
+                      },
+                      "required": [
+                        "err"
+                      ],
+                      "type": "object"
+                    }
+                  ]
+                }
+              },
+              "required": [
+                "result"
+              ],
+              "type": "object"
+            }
+          }
+        ]
+      },
+      "tools_count": 1
+    }
+  ],
+  "total": 1
+}"#,
+            "components:
+- docs_excerpt: An example world for the component to target.
+  health: null
+  hidden: false
+  id: fetch-rs
+  last_used: null
+  load_state:
+    state: loaded
+  schema:
+    tools:
+    - description: Fetch data from a URL and return the response body as a String
+      inputSchema:
+        properties:
+          url:
+            type: string
+        required:
+        - url
+        type: object
+      name: fetch
+      outputSchema:
+        properties:
+          result:
+            oneOf:
+            - properties:
+                ok:
+                  type: string
+              required:
+              - ok
+              type: object
+            - properties:
+                err:
+                  type: string
+              required:
+              - err
+              type: object
+        required:
+        - result
+        type: object
+  tools_count: 1
+total: 1
+",
+            "ID                    | Tools Count
+----------------------|-------------
+fetch-rs              | 1
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_get_policy() {
+        let (tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        call_tool(
+            &lifecycle_manager,
+            "grant-network-permission",
+            json!({"component_id": "fetch-rs", "details": {"host": "api.example.com"}}),
+        )
+        .await;
+        let result = call_tool(
+            &lifecycle_manager,
+            "get-policy",
+            json!({"component_id": "fetch-rs"}),
+        )
+        .await;
+
+        // The policy lives in the temporary component directory and records
+        // when it was written
+        let policy: Value =
+            serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let created_at = policy["policy_info"]["created_at"].to_string();
+        let dir = tempdir.path().display().to_string();
+        let render = |output_format| {
+            render_result(&result, output_format)
+                .unwrap()
+                .replace(&dir, "/components")
+                .replace(&created_at, "1760601600")
+        };
+
+        assert_eq!(
+            render(OutputFormat::Json),
+            r#"{
+  "component_id": "fetch-rs",
+  "permission_sources": {
+    "network": "/components/fetch-rs.policy.yaml"
+  },
+  "permissions": {
+    "environment": null,
+    "ipc": null,
+    "network": {
+      "allow": [
+        {
+          "host": "api.example.com"
+        }
+      ],
+      "deny": null
+    },
+    "resources": null,
+    "runtime": null,
+    "storage": null
+  },
+  "policy_info": {
+    "created_at": 1760601600,
+    "local_path": "/components/fetch-rs.policy.yaml",
+    "origin": "local_copy",
+    "policy_id": "fetch-rs-policy",
+    "source_uri": "file:///components/fetch-rs.policy.yaml"
+  },
+  "status": "policy found"
+}"#
+        );
+        assert_eq!(
+            render(OutputFormat::Yaml),
+            "component_id: fetch-rs
+permission_sources:
+  network: /components/fetch-rs.policy.yaml
+permissions:
+  environment: null
+  ipc: null
+  network:
+    allow:
+    - host: api.example.com
+    deny: null
+  resources: null
+  runtime: null
+  storage: null
+policy_info:
+  created_at: 1760601600
+  local_path: /components/fetch-rs.policy.yaml
+  origin: local_copy
+  policy_id: fetch-rs-policy
+  source_uri: file:///components/fetch-rs.policy.yaml
+status: policy found
+"
+        );
+        assert_eq!(
+            render(OutputFormat::Table),
+            "Key                               | Value
+----------------------------------|--------
+component_id                      | fetch-rs
+permission_sources.network        | /components/fetch-rs.policy.yaml
+permissions.environment           | null
+permissions.ipc                   | null
+permissions.network.allow[0].host | api.example.com
+permissions.network.deny          | null
+permissions.resources             | null
+permissions.runtime               | null
+permissions.storage               | null
+policy_info.created_at            | 1760601600
+policy_info.local_path            | /components/fetch-rs.policy.yaml
+policy_info.origin                | local_copy
+policy_info.policy_id             | fetch-rs-policy
+policy_info.source_uri            | file:///components/fetch-rs.policy.yaml
+status                            | policy found
+"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_grant_permission() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        let result = call_tool(
+            &lifecycle_manager,
+            "grant-network-permission",
+            json!({"component_id": "fetch-rs", "details": {"host": "api.example.com"}}),
+        )
+        .await;
+        assert_renders(
+            &result,
+            r#"{
+  "component_id": "fetch-rs",
+  "details": {
+    "host": "api.example.com"
+  },
+  "permission_type": "network",
+  "status": "permission granted successfully"
+}"#,
+            "component_id: fetch-rs
+details:
+  host: api.example.com
+permission_type: network
+status: permission granted successfully
+",
+            "Key                   | Value
+----------------------|--------
+component_id          | fetch-rs
+details.host          | api.example.com
+permission_type       | network
+status                | permission granted successfully
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_get_load_status() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        let result = call_tool(&lifecycle_manager, "get-load-status", json!({})).await;
+        assert_renders(
+            &result,
+            r#"{
+  "components": [
+    {
+      "id": "fetch-rs",
+      "load_state": {
+        "state": "loaded"
+      }
+    }
+  ],
+  "failures": [],
+  "schemaVersion": 1,
+  "total": 1
+}"#,
+            "components:
+- id: fetch-rs
+  load_state:
+    state: loaded
+failures: []
+schemaVersion: 1
+total: 1
+",
+            "Key                            | Value
+-------------------------------|--------
+components[0].id               | fetch-rs
+components[0].load_state.state | loaded
+failures                       | []
+schemaVersion                  | 1
+total                          | 1
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_search_components() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let lifecycle_manager = LifecycleManager::new_unloaded(&tempdir).await.unwrap();
+        let result = call_tool(
+            &lifecycle_manager,
+            "search-components",
+            json!({"query": "eval"}),
+        )
+        .await;
+        assert_renders(
+            &result,
+            r#"{
+  "components": [
+    {
+      "description": "A Python expression evaluation component",
+      "name": "Python Eval",
+      "uri": "oci://ghcr.io/microsoft/eval-py:latest"
+    }
+  ],
+  "status": "Component list found"
+}"#,
+            "components:
+- description: A Python expression evaluation component
+  name: Python Eval
+  uri: oci://ghcr.io/microsoft/eval-py:latest
+status: Component list found
+",
+            "Key                       | Value
+--------------------------|--------
+components[0].description | A Python expression evaluation component
+components[0].name        | Python Eval
+components[0].uri         | oci://ghcr.io/microsoft/eval-py:latest
+status                    | Component list found
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_unload_component() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        let result = call_tool(
+            &lifecycle_manager,
+            "unload-component",
+            json!({"id": "fetch-rs"}),
+        )
+        .await;
+        assert_renders(
+            &result,
+            r#"{
+  "id": "fetch-rs",
+  "status": "component unloaded successfully"
+}"#,
+            "id: fetch-rs
+status: component unloaded successfully
+",
+            "Key                   | Value
+----------------------|--------
+id                    | fetch-rs
+status                | component unloaded successfully
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_deactivate_component() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        let result = call_tool(
+            &lifecycle_manager,
+            "deactivate-component",
+            json!({"component_id": "fetch-rs"}),
+        )
+        .await;
+        assert_renders(
+            &result,
+            r#"{
+  "component_id": "fetch-rs",
+  "status": "component deactivated"
+}"#,
+            "component_id: fetch-rs
+status: component deactivated
+",
+            "Key                   | Value
+----------------------|--------
+component_id          | fetch-rs
+status                | component deactivated
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_get_permission_denials() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        let result = call_tool(
+            &lifecycle_manager,
+            "get-permission-denials",
+            json!({"component_id": "fetch-rs"}),
+        )
+        .await;
+        assert_renders(
+            &result,
+            r#"{
+  "by_type": {},
+  "component_id": "fetch-rs",
+  "recent": [],
+  "total": 0
+}"#,
+            "by_type: {}
+component_id: fetch-rs
+recent: []
+total: 0
+",
+            "Key                   | Value
+----------------------|--------
+by_type               | {}
+component_id          | fetch-rs
+recent                | []
+total                 | 0
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_get_changes() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        let result = call_tool(&lifecycle_manager, "get-changes", json!({})).await;
+        let result = pin_fields(&result, &["/changes/0/recorded_at"], json!(1760601600));
+        assert_renders(
+            &result,
+            r#"{
+  "changes": [
+    {
+      "component_id": "fetch-rs",
+      "recorded_at": 1760601600,
+      "seq": 1,
+      "tools": [
+        "fetch"
+      ],
+      "type": "component_loaded"
+    }
+  ],
+  "last_seq": 1
+}"#,
+            "changes:
+- component_id: fetch-rs
+  recorded_at: 1760601600
+  seq: 1
+  tools:
+  - fetch
+  type: component_loaded
+last_seq: 1
+",
+            "Key                     | Value
+------------------------|--------
+changes[0].component_id | fetch-rs
+changes[0].recorded_at  | 1760601600
+changes[0].seq          | 1
+changes[0].tools[0]     | fetch
+changes[0].type         | component_loaded
+last_seq                | 1
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_get_server_info() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        let result = call_tool(&lifecycle_manager, "get-server-info", json!({})).await;
+        let result = pin_fields(&result, &["/uptime_secs"], json!(0));
+        assert_renders(
+            &result,
+            r#"{
+  "background_loading": {
+    "completed": false,
+    "processed": 0,
+    "queued": 0,
+    "started": false
+  },
+  "build_info": null,
+  "components": {
+    "known": 1,
+    "loaded": 1
+  },
+  "tools": 1,
+  "transports": [],
+  "uptime_secs": 0,
+  "version": "0.1.0"
+}"#,
+            "background_loading:
+  completed: false
+  processed: 0
+  queued: 0
+  started: false
+build_info: null
+components:
+  known: 1
+  loaded: 1
+tools: 1
+transports: []
+uptime_secs: 0
+version: 0.1.0
+",
+            "Key                          | Value
+-----------------------------|--------
+background_loading.completed | false
+background_loading.processed | 0
+background_loading.queued    | 0
+background_loading.started   | false
+build_info                   | null
+components.known             | 1
+components.loaded            | 1
+tools                        | 1
+transports                   | []
+uptime_secs                  | 0
+version                      | 0.1.0
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_set_component_priority() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        let result = call_tool(
+            &lifecycle_manager,
+            "set-component-priority",
+            json!({"component_id": "fetch-rs", "priority": 5}),
+        )
+        .await;
+        assert_renders(
+            &result,
+            r#"{
+  "component_id": "fetch-rs",
+  "priority": 5,
+  "status": "component priority set"
+}"#,
+            "component_id: fetch-rs
+priority: 5
+status: component priority set
+",
+            "Key                   | Value
+----------------------|--------
+component_id          | fetch-rs
+priority              | 5
+status                | component priority set
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_set_component_visibility() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        let result = call_tool(
+            &lifecycle_manager,
+            "set-component-visibility",
+            json!({"component_id": "fetch-rs", "hidden": true}),
+        )
+        .await;
+        assert_renders(
+            &result,
+            r#"{
+  "component_id": "fetch-rs",
+  "hidden": true,
+  "status": "component hidden"
+}"#,
+            "component_id: fetch-rs
+hidden: true
+status: component hidden
+",
+            "Key                   | Value
+----------------------|--------
+component_id          | fetch-rs
+hidden                | true
+status                | component hidden
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_get_component_docs() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        let result = call_tool(
+            &lifecycle_manager,
+            "get-component-docs",
+            json!({"component_id": "fetch-rs"}),
+        )
+        .await;
+        assert_renders(
+            &result,
+            r###"{
+  "component_id": "fetch-rs",
+  "docs": "## World `fetch`\n\nAn example world for the component to target.\n\n### `fetch`\n\nFetch data from a URL and return the response body as a String",
+  "status": "docs found"
+}"###,
+            "component_id: fetch-rs
+docs: |-
+  ## World `fetch`
+
+  An example world for the component to target.
+
+  ### `fetch`
+
+  Fetch data from a URL and return the response body as a String
+status: docs found
+",
+            "Key                   | Value
+----------------------|--------
+component_id          | fetch-rs
+docs                  | ## World `fetch`
+
+An example world for the component to target.
+
+### `fetch`
+
+Fetch data from a URL and return the response body as a String
+status                | docs found
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_list_secret_keys() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        let result = call_tool(
+            &lifecycle_manager,
+            "list-secret-keys",
+            json!({"component_id": "fetch-rs"}),
+        )
+        .await;
+        assert_renders(
+            &result,
+            r#"{
+  "component_id": "fetch-rs",
+  "keys": []
+}"#,
+            "component_id: fetch-rs
+keys: []
+",
+            "Key                   | Value
+----------------------|--------
+component_id          | fetch-rs
+keys                  | []
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_check_network_access() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        let result = call_tool(
+            &lifecycle_manager,
+            "check-network-access",
+            json!({"component_id": "fetch-rs", "url": "https://api.example.com/v1"}),
+        )
+        .await;
+        assert_renders(
+            &result,
+            r#"{
+  "allowed": false,
+  "grant_command": "wassette permission grant network fetch-rs api.example.com",
+  "host": "api.example.com",
+  "method": "GET",
+  "url": "https://api.example.com/v1"
+}"#,
+            "allowed: false
+grant_command: wassette permission grant network fetch-rs api.example.com
+host: api.example.com
+method: GET
+url: https://api.example.com/v1
+",
+            "Key                   | Value
+----------------------|--------
+allowed               | false
+grant_command         | wassette permission grant network fetch-rs api.example.com
+host                  | api.example.com
+method                | GET
+url                   | https://api.example.com/v1
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_invoke_batch() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        let result = call_tool(
+            &lifecycle_manager,
+            "invoke-batch",
+            json!({
+                "tool": "fetch",
+                "arguments": [{"url": "https://api.example.com"}, "not an object"],
+                "concurrency": 1
+            }),
+        )
+        .await;
+        let result = pin_fields(
+            &result,
+            &[
+                "/results/0/duration_ms",
+                "/results/1/duration_ms",
+                "/summary/duration_ms",
+            ],
+            json!(0),
+        );
+        assert_renders(
+            &result,
+            r#"{
+  "component_id": "fetch-rs",
+  "results": [
+    {
+      "duration_ms": 0,
+      "index": 0,
+      "result": {
+        "err": "ErrorCode::HttpRequestDenied"
+      }
+    },
+    {
+      "duration_ms": 0,
+      "error": "Arguments must be a JSON object",
+      "index": 1
+    }
+  ],
+  "summary": {
+    "duration_ms": 0,
+    "failed": 1,
+    "succeeded": 1,
+    "total": 2
+  },
+  "tool": "fetch"
+}"#,
+            "component_id: fetch-rs
+results:
+- duration_ms: 0
+  index: 0
+  result:
+    err: ErrorCode::HttpRequestDenied
+- duration_ms: 0
+  error: Arguments must be a JSON object
+  index: 1
+summary:
+  duration_ms: 0
+  failed: 1
+  succeeded: 1
+  total: 2
+tool: fetch
+",
+            "Key                    | Value
+-----------------------|--------
+component_id           | fetch-rs
+results[0].duration_ms | 0
+results[0].index       | 0
+results[0].result.err  | ErrorCode::HttpRequestDenied
+results[1].duration_ms | 0
+results[1].error       | Arguments must be a JSON object
+results[1].index       | 1
+summary.duration_ms    | 0
+summary.failed         | 1
+summary.succeeded      | 1
+summary.total          | 2
+tool                   | fetch
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_grant_storage_permission() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        let result = call_tool(
+            &lifecycle_manager,
+            "grant-storage-permission",
+            json!({
+                "component_id": "fetch-rs",
+                "details": {
+                    "uri": "fs://data",
+                    "access": ["read", "write"],
+                    "createIfMissing": true
+                }
+            }),
+        )
+        .await;
+        assert_renders(
+            &result,
+            r#"{
+  "component_id": "fetch-rs",
+  "details": {
+    "access": [
+      "read",
+      "write"
+    ],
+    "createIfMissing": true,
+    "uri": "fs://data"
+  },
+  "permission_type": "storage",
+  "status": "permission granted successfully"
+}"#,
+            "component_id: fetch-rs
+details:
+  access:
+  - read
+  - write
+  createIfMissing: true
+  uri: fs://data
+permission_type: storage
+status: permission granted successfully
+",
+            "Key                     | Value
+------------------------|--------
+component_id            | fetch-rs
+details.access[0]       | read
+details.access[1]       | write
+details.createIfMissing | true
+details.uri             | fs://data
+permission_type         | storage
+status                  | permission granted successfully
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_grant_environment_variable_permission() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        let result = call_tool(
+            &lifecycle_manager,
+            "grant-environment-variable-permission",
+            json!({"component_id": "fetch-rs", "details": {"key": "API_KEY"}}),
+        )
+        .await;
+        assert_renders(
+            &result,
+            r#"{
+  "component_id": "fetch-rs",
+  "details": {
+    "key": "API_KEY"
+  },
+  "permission_type": "environment variable",
+  "status": "permission granted successfully"
+}"#,
+            "component_id: fetch-rs
+details:
+  key: API_KEY
+permission_type: environment variable
+status: permission granted successfully
+",
+            "Key                   | Value
+----------------------|--------
+component_id          | fetch-rs
+details.key           | API_KEY
+permission_type       | environment variable
+status                | permission granted successfully
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_grant_cpu_permission() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        let result = call_tool(
+            &lifecycle_manager,
+            "grant-cpu-permission",
+            json!({"component_id": "fetch-rs", "details": {"cpu": "500m"}}),
+        )
+        .await;
+        assert_renders(
+            &result,
+            r#"{
+  "component_id": "fetch-rs",
+  "details": {
+    "cpu": "500m"
+  },
+  "permission_type": "cpu",
+  "status": "permission granted successfully"
+}"#,
+            "component_id: fetch-rs
+details:
+  cpu: 500m
+permission_type: cpu
+status: permission granted successfully
+",
+            "Key                   | Value
+----------------------|--------
+component_id          | fetch-rs
+details.cpu           | 500m
+permission_type       | cpu
+status                | permission granted successfully
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_grant_config_value() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        let result = call_tool(
+            &lifecycle_manager,
+            "grant-config-value",
+            json!({
+                "component_id": "fetch-rs",
+                "details": {"key": "ENABLE_BETA_PARSER", "value": "true"}
+            }),
+        )
+        .await;
+        assert_renders(
+            &result,
+            r#"{
+  "component_id": "fetch-rs",
+  "details": {
+    "key": "ENABLE_BETA_PARSER",
+    "value": "true"
+  },
+  "permission_type": "config value",
+  "status": "permission granted successfully"
+}"#,
+            "component_id: fetch-rs
+details:
+  key: ENABLE_BETA_PARSER
+  value: 'true'
+permission_type: config value
+status: permission granted successfully
+",
+            "Key                   | Value
+----------------------|--------
+component_id          | fetch-rs
+details.key           | ENABLE_BETA_PARSER
+details.value         | true
+permission_type       | config value
+status                | permission granted successfully
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_revoke_storage_permission() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        call_tool(
+            &lifecycle_manager,
+            "grant-storage-permission",
+            json!({
+                "component_id": "fetch-rs",
+                "details": {
+                    "uri": "fs://data",
+                    "access": ["read", "write"],
+                    "createIfMissing": true
+                }
+            }),
+        )
+        .await;
+        let result = call_tool(
+            &lifecycle_manager,
+            "revoke-storage-permission",
+            json!({"component_id": "fetch-rs", "details": {"uri": "fs://data"}}),
+        )
+        .await;
+        assert_renders(
+            &result,
+            r#"{
+  "component_id": "fetch-rs",
+  "message": "All access (read and write) to the specified URI has been revoked",
+  "status": "permission revoked successfully",
+  "uri": "fs://data"
+}"#,
+            "component_id: fetch-rs
+message: All access (read and write) to the specified URI has been revoked
+status: permission revoked successfully
+uri: fs://data
+",
+            "Key                   | Value
+----------------------|--------
+component_id          | fetch-rs
+message               | All access (read and write) to the specified URI has been revoked
+status                | permission revoked successfully
+uri                   | fs://data
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_revoke_network_permission() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        call_tool(
+            &lifecycle_manager,
+            "grant-network-permission",
+            json!({"component_id": "fetch-rs", "details": {"host": "api.example.com"}}),
+        )
+        .await;
+        let result = call_tool(
+            &lifecycle_manager,
+            "revoke-network-permission",
+            json!({"component_id": "fetch-rs", "details": {"host": "api.example.com"}}),
+        )
+        .await;
+        assert_renders(
+            &result,
+            r#"{
+  "component_id": "fetch-rs",
+  "details": {
+    "host": "api.example.com"
+  },
+  "permission_type": "network",
+  "status": "permission revoked"
+}"#,
+            "component_id: fetch-rs
+details:
+  host: api.example.com
+permission_type: network
+status: permission revoked
+",
+            "Key                   | Value
+----------------------|--------
+component_id          | fetch-rs
+details.host          | api.example.com
+permission_type       | network
+status                | permission revoked
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_revoke_environment_variable_permission() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        call_tool(
+            &lifecycle_manager,
+            "grant-environment-variable-permission",
+            json!({"component_id": "fetch-rs", "details": {"key": "API_KEY"}}),
+        )
+        .await;
+        let result = call_tool(
+            &lifecycle_manager,
+            "revoke-environment-variable-permission",
+            json!({"component_id": "fetch-rs", "details": {"key": "API_KEY"}}),
+        )
+        .await;
+        assert_renders(
+            &result,
+            r#"{
+  "component_id": "fetch-rs",
+  "details": {
+    "key": "API_KEY"
+  },
+  "permission_type": "environment variable",
+  "status": "permission revoked"
+}"#,
+            "component_id: fetch-rs
+details:
+  key: API_KEY
+permission_type: environment variable
+status: permission revoked
+",
+            "Key                   | Value
+----------------------|--------
+component_id          | fetch-rs
+details.key           | API_KEY
+permission_type       | environment variable
+status                | permission revoked
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_revoke_config_value() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        call_tool(
+            &lifecycle_manager,
+            "grant-config-value",
+            json!({
+                "component_id": "fetch-rs",
+                "details": {"key": "ENABLE_BETA_PARSER", "value": "true"}
+            }),
+        )
+        .await;
+        let result = call_tool(
+            &lifecycle_manager,
+            "revoke-config-value",
+            json!({"component_id": "fetch-rs", "details": {"key": "ENABLE_BETA_PARSER"}}),
+        )
+        .await;
+        assert_renders(
+            &result,
+            r#"{
+  "component_id": "fetch-rs",
+  "details": {
+    "key": "ENABLE_BETA_PARSER"
+  },
+  "permission_type": "config value",
+  "status": "permission revoked"
+}"#,
+            "component_id: fetch-rs
+details:
+  key: ENABLE_BETA_PARSER
+permission_type: config value
+status: permission revoked
+",
+            "Key                   | Value
+----------------------|--------
+component_id          | fetch-rs
+details.key           | ENABLE_BETA_PARSER
+permission_type       | config value
+status                | permission revoked
+",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_reset_permission() {
+        let (_tempdir, lifecycle_manager, _) = manager_with_fetch().await;
+        call_tool(
+            &lifecycle_manager,
+            "grant-network-permission",
+            json!({"component_id": "fetch-rs", "details": {"host": "api.example.com"}}),
+        )
+        .await;
+        let result = call_tool(
+            &lifecycle_manager,
+            "reset-permission",
+            json!({"component_id": "fetch-rs"}),
+        )
+        .await;
+        assert_renders(
+            &result,
+            r#"{
+  "component_id": "fetch-rs",
+  "status": "permissions reset successfully"
+}"#,
+            "component_id: fetch-rs
+status: permissions reset successfully
+",
+            "Key                   | Value
+----------------------|--------
+component_id          | fetch-rs
+status                | permissions reset successfully
+",
+        );
+    }
+
+    #[test]
+    fn test_render_component_stats() {
+        let result = text_result(json!({
+            "components": [{
+                "id": "fetch",
+                "last_used": 1760000000,
+                "calls": 12,
+                "errors": 1,
+                "permission_denials": 2,
+                "p50_ms": 40,
+                "p95_ms": 310,
+                "tool_stats": {}
+            }, {
+                "id": "time",
+                "last_used": null,
+                "calls": 0,
+                "errors": 0,
+                "permission_denials": 0,
+                "p50_ms": 0,
+                "p95_ms": 0,
+                "tool_stats": {}
+            }],
+            "total": 2
+        }));
+        assert_eq!(
+            render_result(&result, OutputFormat::Table).unwrap(),
+            "ID                    | Calls    | Errors   | Denials  | p50 ms   | p95 ms   | Last Used
+----------------------|----------|----------|----------|----------|----------|------------
+fetch                 | 12       | 1        | 2        | 40       | 310      | 1760000000
+time                  | 0        | 0        | 0        | 0        | 0        | never
+"
+        );
+    }
+
+    #[test]
+    fn test_render_prefers_structured_content() {
+        let result = CallToolResult {
+            content: vec![Content::text(r#"{"title":"Example"}"#)],
+            structured_content: Some(json!({"result": {"title": "Example"}})),
+            is_error: None,
+            meta: None,
+        };
+        assert_renders(
+            &result,
+            r#"{
+  "result": {
+    "title": "Example"
+  }
+}"#,
+            "result:
+  title: Example
+",
+            "Key                   | Value
+----------------------|--------
+result.title          | Example
+",
+        );
+    }
+
+    #[test]
+    fn test_render_expands_embedded_json_in_tables() {
+        let result = text_result(json!({
+            "result": r#"{"title":"Example","tags":["a","b"]}"#
+        }));
+        // JSON and YAML keep the string the tool returned
+        assert_renders(
+            &result,
+            r#"{
+  "result": "{\"title\":\"Example\",\"tags\":[\"a\",\"b\"]}"
+}"#,
+            r#"result: '{"title":"Example","tags":["a","b"]}'
+"#,
+            "Key                   | Value
+----------------------|--------
+result.tags[0]        | a
+result.tags[1]        | b
+result.title          | Example
+",
+        );
+    }
+
+    #[test]
+    fn test_render_plain_text_as_is() {
+        let result = CallToolResult {
+            content: vec![Content::text("Error: Component not found: fetch")],
+            structured_content: None,
+            is_error: Some(true),
+            meta: None,
+        };
+        let text = "Error: Component not found: fetch";
+        assert_renders(&result, text, text, text);
+
+        // Strings that merely start like JSON stay strings
+        let result = text_result(json!({"message": "[not json"}));
+        assert_eq!(
+            render_result(&result, OutputFormat::Yaml).unwrap(),
+            "message: '[not json'\n"
+        );
+    }
+}
@@ -5,45 +5,68 @@
 
 #![warn(missing_docs)]
 
+use std::sync::Arc;
+use std::time::Duration;
+
 use anyhow::{bail, Context, Result};
 use clap::{CommandFactory, Parser};
 use clap_complete::{generate, shells};
-use mcp_server::{handle_tools_list, LifecycleManager};
+use mcp_server::{
+    handle_tools_list, server_info_report, LifecycleManager, McpServer, ResultStore, ServerDetails,
+};
 use rmcp::service::serve_server;
+use rmcp::transport::sse_server::SseServerConfig;
 use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
 use rmcp::transport::streamable_http_server::StreamableHttpService;
 use rmcp::transport::{stdio as stdio_transport, SseServer};
 use serde_json::{json, Map};
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
 use tracing_subscriber::layer::SubscriberExt as _;
 use tracing_subscriber::util::SubscriberInitExt as _;
+use wassette::{
+    GcOptions, LifecycleBuilder, LoadStatus, SignatureMode, StateExportOptions, StateImportOptions,
+    StdioMode, DEFAULT_DOWNLOADS_PRUNE_INTERVAL, DEFAULT_DRIFT_CHECK_INTERVAL,
+    DEFAULT_GC_TEMP_MAX_AGE,
+};
 
 mod cli_handlers;
 mod commands;
 mod config;
 mod format;
 mod manifest;
+mod migrate;
 mod permission_synthesis;
 mod provisioning_controller;
-mod registry;
-mod server;
+mod tool_args;
 mod tools;
 mod utils;
 
-use cli_handlers::{create_lifecycle_manager, handle_tool_cli_command};
+use cli_handlers::{
+    create_lifecycle_manager, create_lifecycle_manager_with_source_override, find_tool,
+    handle_tool_cli_command, network_rule_details, read_hosts_file,
+};
 use commands::{
-    Cli, Commands, ComponentCommands, GrantPermissionCommands, PermissionCommands, PolicyCommands,
-    RegistryCommands, RevokePermissionCommands, SecretCommands, Shell, ToolCommands, Transport,
+    Cli, Commands, ComponentCommands, ComponentSort, GrantPermissionCommands, PermissionCommands,
+    PolicyCommands, RegistryCommands, RevokePermissionCommands, SecretCommands, Shell,
+    ToolCommands, Transport,
 };
+use config::MetricsEndpoint;
 use format::{print_result, OutputFormat};
-use server::McpServer;
+use migrate::{migrate_component_dir, MigrationMode};
 use tools::ToolName;
-use utils::{format_build_info, load_component_registry, parse_env_var};
+use utils::{format_build_info, load_component_registry, parse_component_id, parse_env_var};
 
 // Health and info endpoint handlers
 mod endpoints {
-    use axum::http::StatusCode;
+    use std::sync::Arc;
+
+    use axum::http::{header, StatusCode};
+    use axum::response::IntoResponse;
     use axum::Json;
     use serde_json::{json, Value};
+    use tokio::sync::RwLock;
+    use wassette::LoadStatus;
 
     /// Health check endpoint - returns 200 OK if server is running
     pub async fn health() -> StatusCode {
@@ -57,6 +80,67 @@ mod endpoints {
         }))
     }
 
+    /// Liveness probe - returns 200 OK once the listener is up
+    pub async fn healthz() -> StatusCode {
+        StatusCode::OK
+    }
+
+    /// Readiness probe - returns 503 Service Unavailable until background
+    /// loading has finished its first pass, then 200 OK, with the loaded
+    /// component counts and any load failures
+    pub async fn readyz(
+        lifecycle_manager: wassette::LifecycleManager,
+        load_status: Arc<RwLock<LoadStatus>>,
+    ) -> (StatusCode, Json<LoadStatus>) {
+        let published = load_status.read().await.clone();
+        let mut status = lifecycle_manager.loading_status().await;
+        status.ready = published.ready;
+        status.error = published.error;
+        let code = if status.ready {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+        (code, Json(status))
+    }
+
+    /// Prometheus endpoint - returns the execution metrics of the components
+    /// in the Prometheus text exposition format, followed by the tool calls
+    /// running and waiting for a slot when concurrent calls are limited
+    pub async fn prometheus(server: mcp_server::McpServer) -> impl IntoResponse {
+        let mut body = server.lifecycle_manager().prometheus_metrics().await;
+        if let Some(load) = server.call_load() {
+            for (name, help, value) in [
+                (
+                    "wassette_tool_call_limit",
+                    "Tool calls allowed to run at once.",
+                    load.limit,
+                ),
+                (
+                    "wassette_tool_calls_in_flight",
+                    "Tool calls running.",
+                    load.in_flight,
+                ),
+                (
+                    "wassette_tool_calls_queued",
+                    "Tool calls waiting for a slot.",
+                    load.queued,
+                ),
+            ] {
+                body.push_str(&format!(
+                    "# HELP {name} {help}\n# TYPE {name} gauge\n{name} {value}\n"
+                ));
+            }
+        }
+        (
+            [(
+                header::CONTENT_TYPE,
+                "text/plain; version=0.0.4; charset=utf-8",
+            )],
+            body,
+        )
+    }
+
     /// Build info endpoint - returns build information
     pub async fn info() -> Json<Value> {
         let build_info = crate::utils::format_build_info();
@@ -67,6 +151,79 @@ mod endpoints {
     }
 }
 
+/// Builds the lifecycle manager of `wassette run` and `wassette serve` from
+/// `config`, with `configure` applying what differs between the two. Components
+/// are not loaded eagerly, so the background loader is the single source of
+/// tool registration, and the component directory is reconciled with the state
+/// manifest when one is configured.
+async fn build_lifecycle_manager(
+    config: &config::Config,
+    configure: impl FnOnce(LifecycleBuilder) -> LifecycleBuilder,
+) -> Result<LifecycleManager> {
+    let mut builder =
+        LifecycleBuilder::from_config(config.lifecycle.clone()).with_eager_loading(false);
+    if let Some(state_manifest) = &config.state_manifest {
+        builder = builder.with_manifest_path(state_manifest);
+    }
+    let lifecycle_manager = configure(builder).build().await?;
+
+    if let Some(state_manifest) = &config.state_manifest {
+        tracing::info!(
+            "Reconciling components with state manifest: {}",
+            state_manifest.display()
+        );
+        let report = lifecycle_manager
+            .restore_from_manifest(state_manifest)
+            .await
+            .context("Failed to restore components from state manifest")?;
+        for failure in &report.failed {
+            tracing::warn!(
+                "Failed to restore component {}: {}",
+                failure.component_id,
+                failure.error
+            );
+        }
+    }
+    Ok(lifecycle_manager)
+}
+
+/// Builds the MCP server of `wassette run` and `wassette serve` in front of
+/// `lifecycle_manager`, reporting `transport` in its server details.
+async fn build_mcp_server(
+    config: &config::Config,
+    lifecycle_manager: &LifecycleManager,
+    transport: String,
+    disable_builtin_tools: bool,
+) -> Result<McpServer> {
+    let mut server = McpServer::builder(lifecycle_manager.clone())
+        .with_server_details(
+            ServerDetails::new(env!("CARGO_PKG_VERSION"))
+                .with_build_info(format_build_info())
+                .with_transports([transport]),
+        )
+        .with_disable_builtin_tools(disable_builtin_tools)
+        .with_read_only(config.read_only)
+        .with_call_timing(config.call_timing)
+        .with_redaction_rules(config.redaction.clone().into());
+    if let Some(level) = config.component_log_level {
+        server = server.with_component_log_level(level);
+    }
+    if let Some(call_limit) = config.call_limit.limit_config() {
+        server = server.with_call_limit(call_limit);
+    }
+    if let Some(store_config) = config
+        .large_results
+        .store_config(lifecycle_manager.component_root())
+    {
+        server = server.with_result_store(
+            ResultStore::new(store_config)
+                .await
+                .context("Failed to set up the large result store")?,
+        );
+    }
+    server.build()
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -97,54 +254,45 @@ async fn main() -> Result<()> {
                     )
                     .init();
 
-                let config =
-                    config::Config::from_run(cfg).context("Failed to load configuration")?;
-
-                // Build the lifecycle manager without eagerly loading components so the
-                // background loader is the single source of tool registration.
-                let config::Config {
-                    component_dir,
-                    secrets_dir,
-                    environment_vars,
-                    bind_address: _,
-                    registry_credentials,
-                } = config;
-
-                let lifecycle_manager = LifecycleManager::builder(component_dir)
-                    .with_environment_vars(environment_vars)
-                    .with_secrets_dir(secrets_dir)
-                    .with_registry_credentials(registry_credentials)
-                    .with_oci_client(oci_client::Client::default())
-                    .with_http_client(reqwest::Client::default())
-                    .with_eager_loading(false)
-                    .build()
-                    .await?;
-
-                let server = McpServer::new(lifecycle_manager.clone(), cfg.disable_builtin_tools);
+                let config = config::Config::from_run(&commands::Run {
+                    component_dir: cfg
+                        .component_dir
+                        .clone()
+                        .or_else(|| cli.component_dir.clone()),
+                    ..cfg.clone()
+                })
+                .context("Failed to load configuration")?;
+                if cli.verbose {
+                    config.print_resolved_dirs();
+                }
 
-                // Start background component loading
-                let server_clone = server.clone();
+                let lifecycle_manager = build_lifecycle_manager(&config, |builder| {
+                    // Component output must not reach the MCP transport on stdout
+                    builder.with_default_stdio(StdioMode::Capture)
+                })
+                .await?;
+                let server = build_mcp_server(
+                    &config,
+                    &lifecycle_manager,
+                    "stdio".to_string(),
+                    cfg.disable_builtin_tools,
+                )
+                .await?;
+
+                // Start background component loading; the server notifies clients
+                // of each loaded component through the lifecycle event bus.
                 let lifecycle_manager_clone = lifecycle_manager.clone();
                 tokio::spawn(async move {
-                    let notify_fn = move || {
-                        // Notify clients when a new component is loaded (if peer is available)
-                        if let Some(peer) = server_clone.get_peer() {
-                            let peer_clone = peer.clone();
-                            tokio::spawn(async move {
-                                if let Err(e) = peer_clone.notify_tool_list_changed().await {
-                                    tracing::warn!("Failed to notify tool list changed: {}", e);
-                                }
-                            });
-                        }
-                    };
-
                     if let Err(e) = lifecycle_manager_clone
-                        .load_existing_components_async(None, Some(notify_fn))
+                        .load_existing_components_async(None)
                         .await
                     {
                         tracing::error!("Background component loading failed: {}", e);
                     }
                 });
+                // Notice component files replaced on disk while the server runs
+                lifecycle_manager.spawn_drift_check(DEFAULT_DRIFT_CHECK_INTERVAL);
+                lifecycle_manager.spawn_downloads_prune(DEFAULT_DOWNLOADS_PRUNE_INTERVAL);
 
                 tracing::info!("Starting MCP server with stdio transport. Components will load in the background.");
                 let transport = stdio_transport();
@@ -153,6 +301,9 @@ async fn main() -> Result<()> {
                 tokio::signal::ctrl_c().await?;
                 let _ = running_service.cancel().await;
 
+                if let Err(e) = lifecycle_manager.flush_usage().await {
+                    tracing::warn!("Failed to write component usage: {}", e);
+                }
                 tracing::info!("MCP server shutting down");
             }
             Commands::Serve(cfg) => {
@@ -169,8 +320,17 @@ async fn main() -> Result<()> {
                     .with(tracing_subscriber::fmt::layer())
                     .init();
 
-                let config =
-                    config::Config::from_serve(cfg).context("Failed to load configuration")?;
+                let config = config::Config::from_serve(&commands::Serve {
+                    component_dir: cfg
+                        .component_dir
+                        .clone()
+                        .or_else(|| cli.component_dir.clone()),
+                    ..cfg.clone()
+                })
+                .context("Failed to load configuration")?;
+                if cli.verbose {
+                    config.print_resolved_dirs();
+                }
 
                 // Parse and validate manifest if provided
                 let manifest = if let Some(manifest_path) = &cfg.manifest {
@@ -192,28 +352,20 @@ async fn main() -> Result<()> {
                     None
                 };
 
-                // Build the lifecycle manager without eagerly loading components so the
-                // background loader is the single source of tool registration.
-                let config::Config {
-                    component_dir,
-                    secrets_dir,
-                    environment_vars,
-                    bind_address,
-                    registry_credentials,
-                } = config;
-
-                // Keep a clone of component_dir for provisioning
-                let component_dir_path = component_dir.clone();
-
-                let lifecycle_manager = LifecycleManager::builder(component_dir)
-                    .with_environment_vars(environment_vars)
-                    .with_secrets_dir(secrets_dir)
-                    .with_registry_credentials(registry_credentials)
-                    .with_oci_client(oci_client::Client::default())
-                    .with_http_client(reqwest::Client::default())
-                    .with_eager_loading(false)
-                    .build()
-                    .await?;
+                let metrics_endpoint = config.metrics_endpoint();
+
+                let lifecycle_manager = build_lifecycle_manager(&config, |mut builder| {
+                    if cfg.watch {
+                        builder = builder.with_dir_watch(true);
+                    }
+                    if cfg.require_signatures {
+                        let mut signature_policy = config.lifecycle.signature_policy().clone();
+                        signature_policy.mode = SignatureMode::Enforce;
+                        builder = builder.with_signature_policy(signature_policy);
+                    }
+                    builder
+                })
+                .await?;
 
                 // Provision components from manifest if provided
                 if let Some(manifest) = &manifest {
@@ -223,7 +375,7 @@ async fn main() -> Result<()> {
                         manifest,
                         &lifecycle_manager,
                         lifecycle_manager.secrets_manager(),
-                        &component_dir_path,
+                        config.lifecycle.component_dir(),
                     );
 
                     provisioner
@@ -234,50 +386,90 @@ async fn main() -> Result<()> {
                     tracing::info!("All components provisioned successfully");
                 }
 
-                let server = McpServer::new(lifecycle_manager.clone(), cfg.disable_builtin_tools);
-
-                // Start background component loading
-                let server_clone = server.clone();
+                let transport: Transport = (&cfg.transport).into();
+                let bind_address = config.bind_address.clone();
+                let server = build_mcp_server(
+                    &config,
+                    &lifecycle_manager,
+                    format!("{} ({bind_address})", transport.name()),
+                    cfg.disable_builtin_tools,
+                )
+                .await?;
+
+                // Start background component loading; the server notifies clients
+                // of each loaded component through the lifecycle event bus, and
+                // the readiness probe reports ready once the first pass is done.
+                let load_status = Arc::new(RwLock::new(LoadStatus::default()));
                 let lifecycle_manager_clone = lifecycle_manager.clone();
+                let published_status = load_status.clone();
                 tokio::spawn(async move {
-                    let notify_fn = move || {
-                        // Notify clients when a new component is loaded (if peer is available)
-                        if let Some(peer) = server_clone.get_peer() {
-                            let peer_clone = peer.clone();
-                            tokio::spawn(async move {
-                                if let Err(e) = peer_clone.notify_tool_list_changed().await {
-                                    tracing::warn!("Failed to notify tool list changed: {}", e);
-                                }
-                            });
-                        }
-                    };
-
-                    if let Err(e) = lifecycle_manager_clone
-                        .load_existing_components_async(None, Some(notify_fn))
-                        .await
-                    {
+                    let result = lifecycle_manager_clone
+                        .load_existing_components_async(None)
+                        .await;
+                    let mut status = lifecycle_manager_clone.loading_status().await;
+                    if let Err(e) = result {
                         tracing::error!("Background component loading failed: {}", e);
+                        status.error = Some(format!("{e:#}"));
                     }
+                    *published_status.write().await = status;
                 });
+                // Notice component files replaced on disk while the server runs
+                lifecycle_manager.spawn_drift_check(DEFAULT_DRIFT_CHECK_INTERVAL);
+                lifecycle_manager.spawn_downloads_prune(DEFAULT_DOWNLOADS_PRUNE_INTERVAL);
+
+                // With an address of their own, Prometheus metrics get a separate
+                // listener, so they can be scraped on a port not exposed to MCP clients
+                let metrics_route = |server: mcp_server::McpServer| {
+                    axum::routing::get(move || endpoints::prometheus(server.clone()))
+                };
+                if let Some(MetricsEndpoint::Listener(metrics_address)) = &metrics_endpoint {
+                    let router =
+                        axum::Router::new().route("/metrics", metrics_route(server.clone()));
+                    let listener = tokio::net::TcpListener::bind(metrics_address)
+                        .await
+                        .with_context(|| {
+                            format!("Failed to bind the metrics listener to {metrics_address}")
+                        })?;
+                    tokio::spawn(async move {
+                        if let Err(e) = axum::serve(listener, router).await {
+                            tracing::error!("Metrics listener failed: {}", e);
+                        }
+                    });
+                    tracing::info!(
+                        "Prometheus metrics available at http://{}/metrics",
+                        metrics_address
+                    );
+                }
 
-                let transport: Transport = (&cfg.transport).into();
                 match transport {
                     Transport::StreamableHttp => {
                         tracing::info!(
                         "Starting MCP server on {} with streamable HTTP transport. Components will load in the background.",
                         bind_address
                     );
+                        let metrics_server = server.clone();
+                        let readyz_manager = lifecycle_manager.clone();
                         let service = StreamableHttpService::new(
                             move || Ok(server.clone()),
                             LocalSessionManager::default().into(),
                             Default::default(),
                         );
 
-                        let router = axum::Router::new()
+                        let mut router = axum::Router::new()
                             .nest_service("/mcp", service)
                             .route("/health", axum::routing::get(endpoints::health))
                             .route("/ready", axum::routing::get(endpoints::ready))
+                            .route("/healthz", axum::routing::get(endpoints::healthz))
+                            .route(
+                                "/readyz",
+                                axum::routing::get(move || {
+                                    endpoints::readyz(readyz_manager.clone(), load_status.clone())
+                                }),
+                            )
                             .route("/info", axum::routing::get(endpoints::info));
+                        if metrics_endpoint == Some(MetricsEndpoint::Transport) {
+                            router = router.route("/metrics", metrics_route(metrics_server));
+                        }
                         let tcp_listener = tokio::net::TcpListener::bind(&bind_address).await?;
 
                         // Spawn the server in a background task
@@ -298,7 +490,17 @@ async fn main() -> Result<()> {
                             "Readiness check available at http://{}/ready",
                             bind_address
                         );
+                        tracing::info!(
+                            "Liveness and readiness probes available at http://{0}/healthz and http://{0}/readyz",
+                            bind_address
+                        );
                         tracing::info!("Build info available at http://{}/info", bind_address);
+                        if metrics_endpoint == Some(MetricsEndpoint::Transport) {
+                            tracing::info!(
+                                "Prometheus metrics available at http://{}/metrics",
+                                bind_address
+                            );
+                        }
 
                         // Wait for the server task to complete
                         let _ = server_handle.await;
@@ -309,16 +511,40 @@ async fn main() -> Result<()> {
                         bind_address
                     );
 
-                        let ct = SseServer::serve(bind_address.parse().unwrap())
-                            .await?
-                            .with_service(move || server.clone());
+                        let (sse_server, mut router) = SseServer::new(SseServerConfig {
+                            bind: bind_address.parse().unwrap(),
+                            sse_path: "/sse".to_string(),
+                            post_path: "/message".to_string(),
+                            ct: CancellationToken::new(),
+                            sse_keep_alive: None,
+                        });
+                        if metrics_endpoint == Some(MetricsEndpoint::Transport) {
+                            router = router.route("/metrics", metrics_route(server.clone()));
+                        }
+                        let tcp_listener = tokio::net::TcpListener::bind(&bind_address).await?;
+                        let shutdown = sse_server.config.ct.child_token();
+                        tokio::spawn(async move {
+                            if let Err(e) = axum::serve(tcp_listener, router)
+                                .with_graceful_shutdown(shutdown.cancelled_owned())
+                                .await
+                            {
+                                tracing::error!("SSE server failed: {}", e);
+                            }
+                        });
+                        let ct = sse_server.with_service(move || server.clone());
 
                         tracing::info!(
                             "MCP server is ready and listening on http://{}/sse",
                             bind_address
                         );
+                        if metrics_endpoint == Some(MetricsEndpoint::Transport) {
+                            tracing::info!(
+                                "Prometheus metrics available at http://{}/metrics",
+                                bind_address
+                            );
+                        }
                         tracing::info!(
-                            "Note: Health endpoints (/health, /ready, /info) are only available with --streamable-http transport. \
+                            "Note: Health endpoints (/health, /ready, /healthz, /readyz, /info) are only available with --streamable-http transport. \
                             SSE transport is designed solely for event streaming and does not provide a general HTTP request/response interface."
                         );
 
@@ -327,15 +553,24 @@ async fn main() -> Result<()> {
                     }
                 }
 
+                if let Err(e) = lifecycle_manager.flush_usage().await {
+                    tracing::warn!("Failed to write component usage: {}", e);
+                }
                 tracing::info!("MCP server shutting down");
             }
             Commands::Component { command } => match command {
                 ComponentCommands::Load {
                     path,
                     component_dir,
+                    override_source_policy,
                 } => {
                     let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
-                    let lifecycle_manager = create_lifecycle_manager(component_dir).await?;
+                    let lifecycle_manager = create_lifecycle_manager_with_source_override(
+                        component_dir,
+                        cli.verbose,
+                        *override_source_policy,
+                    )
+                    .await?;
                     let mut args = Map::new();
                     args.insert("path".to_string(), json!(path));
                     handle_tool_cli_command(
@@ -346,11 +581,19 @@ async fn main() -> Result<()> {
                     )
                     .await?;
                 }
-                ComponentCommands::Unload { id, component_dir } => {
+                ComponentCommands::Unload {
+                    id,
+                    skip_hooks,
+                    component_dir,
+                } => {
                     let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
-                    let lifecycle_manager = create_lifecycle_manager(component_dir).await?;
+                    let lifecycle_manager =
+                        create_lifecycle_manager(component_dir, cli.verbose).await?;
                     let mut args = Map::new();
                     args.insert("id".to_string(), json!(id));
+                    if *skip_hooks {
+                        args.insert("skip_hooks".to_string(), json!(true));
+                    }
                     handle_tool_cli_command(
                         &lifecycle_manager,
                         "unload-component",
@@ -359,13 +602,57 @@ async fn main() -> Result<()> {
                     )
                     .await?;
                 }
+                ComponentCommands::Deactivate {
+                    component_id,
+                    component_dir,
+                } => {
+                    let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
+                    let lifecycle_manager =
+                        create_lifecycle_manager(component_dir, cli.verbose).await?;
+                    let mut args = Map::new();
+                    args.insert("component_id".to_string(), json!(component_id));
+                    handle_tool_cli_command(
+                        &lifecycle_manager,
+                        "deactivate-component",
+                        args,
+                        OutputFormat::Json,
+                    )
+                    .await?;
+                }
+                ComponentCommands::Install {
+                    name,
+                    component_dir,
+                    override_source_policy,
+                } => {
+                    let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
+                    let lifecycle_manager = create_lifecycle_manager_with_source_override(
+                        component_dir,
+                        cli.verbose,
+                        *override_source_policy,
+                    )
+                    .await?;
+                    let mut args = Map::new();
+                    args.insert("name".to_string(), json!(name));
+                    handle_tool_cli_command(
+                        &lifecycle_manager,
+                        "install-component",
+                        args,
+                        OutputFormat::Json,
+                    )
+                    .await?;
+                }
                 ComponentCommands::List {
+                    sort,
                     component_dir,
                     output_format,
                 } => {
                     let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
-                    let lifecycle_manager = create_lifecycle_manager(component_dir).await?;
-                    let args = Map::new();
+                    let lifecycle_manager =
+                        create_lifecycle_manager(component_dir, cli.verbose).await?;
+                    let mut args = Map::new();
+                    if let Some(sort) = sort {
+                        args.insert("sort".to_string(), json!(sort.as_str()));
+                    }
                     handle_tool_cli_command(
                         &lifecycle_manager,
                         "list-components",
@@ -374,6 +661,242 @@ async fn main() -> Result<()> {
                     )
                     .await?;
                 }
+                ComponentCommands::Restore {
+                    manifest,
+                    component_dir,
+                    output_format,
+                } => {
+                    let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
+                    let lifecycle_manager =
+                        create_lifecycle_manager(component_dir, cli.verbose).await?;
+                    let manifest_path = manifest
+                        .clone()
+                        .unwrap_or_else(|| lifecycle_manager.manifest_path().to_path_buf());
+                    let report = lifecycle_manager
+                        .restore_from_manifest(&manifest_path)
+                        .await?;
+
+                    let result = json!({
+                        "status": if report.failed.is_empty() { "success" } else { "partial" },
+                        "manifest": manifest_path,
+                        "restored": report.restored,
+                        "unchanged": report.unchanged,
+                        "failed": report.failed,
+                    });
+
+                    print_result(
+                        &rmcp::model::CallToolResult {
+                            content: vec![rmcp::model::Content::text(
+                                serde_json::to_string_pretty(&result)?,
+                            )],
+                            structured_content: None,
+                            is_error: None,
+                            meta: None,
+                        },
+                        *output_format,
+                    )?;
+                }
+                ComponentCommands::MigrateDir {
+                    from,
+                    move_files,
+                    copy: _,
+                    component_dir,
+                    output_format,
+                } => {
+                    let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
+                    let config = config::Config::for_command(component_dir)
+                        .context("Failed to load configuration")?;
+                    if cli.verbose {
+                        config.print_resolved_dirs();
+                    }
+                    let mode = if *move_files {
+                        MigrationMode::Move
+                    } else {
+                        MigrationMode::Copy
+                    };
+                    let report =
+                        migrate_component_dir(from, config.lifecycle.component_dir(), mode).await?;
+
+                    print_result(
+                        &rmcp::model::CallToolResult {
+                            content: vec![rmcp::model::Content::text(
+                                serde_json::to_string_pretty(&report)?,
+                            )],
+                            structured_content: None,
+                            is_error: None,
+                            meta: None,
+                        },
+                        *output_format,
+                    )?;
+                }
+                ComponentCommands::Docs {
+                    component_id,
+                    component_dir,
+                } => {
+                    let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
+                    let lifecycle_manager =
+                        create_lifecycle_manager(component_dir, cli.verbose).await?;
+                    lifecycle_manager
+                        .ensure_component_loaded(component_id)
+                        .await?;
+                    match lifecycle_manager.get_component_docs(component_id).await {
+                        Some(docs) => println!("{docs}"),
+                        None => eprintln!("Component '{component_id}' has no documentation."),
+                    }
+                }
+                ComponentCommands::Stats {
+                    sort,
+                    component_dir,
+                    output_format,
+                } => {
+                    let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
+                    let lifecycle_manager =
+                        create_lifecycle_manager(component_dir, cli.verbose).await?;
+                    let usage = lifecycle_manager.component_usage();
+                    let stats = lifecycle_manager.get_all_stats();
+
+                    let mut ids = lifecycle_manager.list_components_known().await;
+                    ids.sort();
+                    if *sort == ComponentSort::LastUsed {
+                        // Stable sort keeps never-used components in ID order at the end.
+                        ids.sort_by_key(|id| std::cmp::Reverse(usage.get(id).map(|u| u.last_used)));
+                    }
+
+                    let mut components = Vec::with_capacity(ids.len());
+                    for id in &ids {
+                        let usage = usage.get(id);
+                        let stats = stats.get(id).cloned().unwrap_or_default();
+                        components.push(json!({
+                            "id": id,
+                            "last_used": usage.map(|u| u.last_used),
+                            "tools": usage.map(|u| u.tools.clone()).unwrap_or_default(),
+                            "transient_retries": usage.map(|u| u.transient_retries).unwrap_or_default(),
+                            "calls": stats.calls,
+                            "errors": stats.errors,
+                            "permission_denials": stats.permission_denials,
+                            "p50_ms": stats.p50_ms,
+                            "p95_ms": stats.p95_ms,
+                            "mean_instantiation_ms": stats.mean_instantiation_ms,
+                            "tool_stats": stats.tools,
+                            "compiled_with": lifecycle_manager.component_compile_info(id).await,
+                        }));
+                    }
+                    let result = json!({
+                        "components": components,
+                        "total": components.len(),
+                        "runtime": lifecycle_manager.runtime_compile_info(),
+                    });
+
+                    print_result(
+                        &rmcp::model::CallToolResult {
+                            content: vec![rmcp::model::Content::text(
+                                serde_json::to_string_pretty(&result)?,
+                            )],
+                            structured_content: None,
+                            is_error: None,
+                            meta: None,
+                        },
+                        *output_format,
+                    )?;
+                }
+                ComponentCommands::LoadStatus {
+                    component_id,
+                    component_dir,
+                    output_format,
+                } => {
+                    let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
+                    let lifecycle_manager =
+                        create_lifecycle_manager(component_dir, cli.verbose).await?;
+
+                    // Compile each component so failures are recorded in its load state
+                    let ids = match component_id {
+                        Some(component_id) => vec![component_id.clone()],
+                        None => lifecycle_manager.list_components_known().await,
+                    };
+                    for id in &ids {
+                        if let Err(error) = lifecycle_manager.ensure_component_loaded(id).await {
+                            tracing::debug!(component_id = %id, error = %format!("{error:#}"), "Component failed to load");
+                        }
+                    }
+                    let result = mcp_server::components::load_status_report(
+                        &lifecycle_manager,
+                        component_id.as_deref(),
+                    )
+                    .await?;
+                    let failed = result["failures"]
+                        .as_array()
+                        .is_some_and(|failures| !failures.is_empty());
+
+                    print_result(
+                        &rmcp::model::CallToolResult {
+                            content: vec![rmcp::model::Content::text(
+                                serde_json::to_string_pretty(&result)?,
+                            )],
+                            structured_content: None,
+                            is_error: None,
+                            meta: None,
+                        },
+                        *output_format,
+                    )?;
+                    if failed {
+                        std::process::exit(1);
+                    }
+                }
+                ComponentCommands::Gc {
+                    dry_run,
+                    temp_max_age_secs,
+                    downloads,
+                    component_dir,
+                    output_format,
+                } => {
+                    let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
+                    let lifecycle_manager =
+                        create_lifecycle_manager(component_dir, cli.verbose).await?;
+                    // Prune the downloads directory as the server does at startup;
+                    // `--downloads` prunes it below, and a dry run leaves it alone
+                    if !*dry_run && !*downloads {
+                        lifecycle_manager.prune_downloads().await?;
+                    }
+                    let report = lifecycle_manager
+                        .garbage_collect(GcOptions {
+                            temp_max_age: temp_max_age_secs
+                                .map_or(DEFAULT_GC_TEMP_MAX_AGE, Duration::from_secs),
+                            dry_run: *dry_run,
+                        })
+                        .await?;
+
+                    let mut result = json!({
+                        "status": "success",
+                        "dry_run": report.dry_run,
+                        "precompiled": report.precompiled,
+                        "metadata": report.metadata,
+                        "temp_files": report.temp_files,
+                        "files_removed": report.files_removed,
+                        "bytes_freed": report.bytes_freed,
+                    });
+                    if *downloads {
+                        let report = lifecycle_manager.gc_downloads(*dry_run).await?;
+                        result["downloads"] = json!({
+                            "removed": report.removed,
+                            "expired": report.expired,
+                            "evicted": report.evicted,
+                            "retained": report.retained,
+                            "reclaimed_bytes": report.reclaimed_bytes,
+                        });
+                    }
+
+                    print_result(
+                        &rmcp::model::CallToolResult {
+                            content: vec![rmcp::model::Content::text(
+                                serde_json::to_string_pretty(&result)?,
+                            )],
+                            structured_content: None,
+                            is_error: None,
+                            meta: None,
+                        },
+                        *output_format,
+                    )?;
+                }
             },
             Commands::Policy { command } => match command {
                 PolicyCommands::Get {
@@ -382,12 +905,142 @@ async fn main() -> Result<()> {
                     output_format,
                 } => {
                     let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
-                    let lifecycle_manager = create_lifecycle_manager(component_dir).await?;
+                    let lifecycle_manager =
+                        create_lifecycle_manager(component_dir, cli.verbose).await?;
                     let mut args = Map::new();
                     args.insert("component_id".to_string(), json!(component_id));
                     handle_tool_cli_command(&lifecycle_manager, "get-policy", args, *output_format)
                         .await?;
                 }
+                PolicyCommands::History {
+                    component_id,
+                    component_dir,
+                    output_format,
+                } => {
+                    let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
+                    let lifecycle_manager =
+                        create_lifecycle_manager(component_dir, cli.verbose).await?;
+                    let revisions = lifecycle_manager.policy_history(component_id).await?;
+                    let result = json!({
+                        "component_id": component_id,
+                        "revisions": revisions,
+                    });
+                    print_result(
+                        &rmcp::model::CallToolResult {
+                            content: vec![rmcp::model::Content::text(
+                                serde_json::to_string_pretty(&result)?,
+                            )],
+                            structured_content: None,
+                            is_error: None,
+                            meta: None,
+                        },
+                        *output_format,
+                    )?;
+                }
+                PolicyCommands::Rollback {
+                    component_id,
+                    revision,
+                    component_dir,
+                    output_format,
+                } => {
+                    let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
+                    let lifecycle_manager =
+                        create_lifecycle_manager(component_dir, cli.verbose).await?;
+                    lifecycle_manager
+                        .ensure_component_loaded(component_id)
+                        .await?;
+                    lifecycle_manager
+                        .restore_policy_revision(component_id, *revision)
+                        .await?;
+                    let result = json!({
+                        "status": "success",
+                        "component_id": component_id,
+                        "restored_revision": revision,
+                    });
+                    print_result(
+                        &rmcp::model::CallToolResult {
+                            content: vec![rmcp::model::Content::text(
+                                serde_json::to_string_pretty(&result)?,
+                            )],
+                            structured_content: None,
+                            is_error: None,
+                            meta: None,
+                        },
+                        *output_format,
+                    )?;
+                }
+                PolicyCommands::Migrate {
+                    file,
+                    output_format,
+                } => {
+                    let content = tokio::fs::read_to_string(file).await.with_context(|| {
+                        format!("Failed to read policy file {}", file.display())
+                    })?;
+                    let (policy, report) = policy::PolicyParser::parse_str_with_migration(&content)
+                        .with_context(|| {
+                            format!("Failed to load policy file {}", file.display())
+                        })?;
+                    let result = match report {
+                        Some(report) => {
+                            policy::PolicyParser::write_file(&policy, file).with_context(|| {
+                                format!("Failed to write policy file {}", file.display())
+                            })?;
+                            json!({
+                                "status": "migrated",
+                                "file": file,
+                                "from_version": report.from_version,
+                                "to_version": report.to_version,
+                                "changes": report.changes,
+                            })
+                        }
+                        None => json!({
+                            "status": "up-to-date",
+                            "file": file,
+                            "version": policy.version,
+                        }),
+                    };
+                    print_result(
+                        &rmcp::model::CallToolResult {
+                            content: vec![rmcp::model::Content::text(
+                                serde_json::to_string_pretty(&result)?,
+                            )],
+                            structured_content: None,
+                            is_error: None,
+                            meta: None,
+                        },
+                        *output_format,
+                    )?;
+                }
+                PolicyCommands::Validate {
+                    file,
+                    output_format,
+                } => {
+                    let content = tokio::fs::read_to_string(file).await.with_context(|| {
+                        format!("Failed to read policy file {}", file.display())
+                    })?;
+                    let format = policy::PolicyFormat::from_path(file)
+                        .unwrap_or_else(|| policy::PolicyFormat::detect(&content));
+                    let (policy, report) = policy::PolicyParser::parse_str_as(&content, format)
+                        .with_context(|| format!("Invalid policy file {}", file.display()))?;
+                    let result = json!({
+                        "status": "valid",
+                        "file": file,
+                        "format": format.extension(),
+                        "version": policy.version,
+                        "needs_migration": report.is_some(),
+                    });
+                    print_result(
+                        &rmcp::model::CallToolResult {
+                            content: vec![rmcp::model::Content::text(
+                                serde_json::to_string_pretty(&result)?,
+                            )],
+                            structured_content: None,
+                            is_error: None,
+                            meta: None,
+                        },
+                        *output_format,
+                    )?;
+                }
             },
             Commands::Permission { command } => match command {
                 PermissionCommands::Grant { permission } => match permission {
@@ -395,18 +1048,21 @@ async fn main() -> Result<()> {
                         component_id,
                         uri,
                         access,
+                        create,
                         component_dir,
                     } => {
                         let component_dir =
                             component_dir.clone().or_else(|| cli.component_dir.clone());
-                        let lifecycle_manager = create_lifecycle_manager(component_dir).await?;
+                        let lifecycle_manager =
+                            create_lifecycle_manager(component_dir, cli.verbose).await?;
                         let mut args = Map::new();
                         args.insert("component_id".to_string(), json!(component_id));
                         args.insert(
                             "details".to_string(),
                             json!({
                                 "uri": uri,
-                                "access": access
+                                "access": access,
+                                "createIfMissing": create
                             }),
                         );
                         handle_tool_cli_command(
@@ -420,46 +1076,81 @@ async fn main() -> Result<()> {
                     GrantPermissionCommands::Network {
                         component_id,
                         host,
+                        hosts_file,
+                        port,
+                        scheme,
                         component_dir,
                     } => {
+                        let details = match hosts_file {
+                            Some(path) => json!({ "hosts": read_hosts_file(path)? }),
+                            None => network_rule_details(
+                                host.as_deref().unwrap_or_default(),
+                                *port,
+                                scheme,
+                            ),
+                        };
                         let component_dir =
                             component_dir.clone().or_else(|| cli.component_dir.clone());
-                        let lifecycle_manager = create_lifecycle_manager(component_dir).await?;
+                        let lifecycle_manager =
+                            create_lifecycle_manager(component_dir, cli.verbose).await?;
+                        let mut args = Map::new();
+                        args.insert("component_id".to_string(), json!(component_id));
+                        args.insert("details".to_string(), details);
+                        handle_tool_cli_command(
+                            &lifecycle_manager,
+                            "grant-network-permission",
+                            args,
+                            OutputFormat::Json,
+                        )
+                        .await?;
+                    }
+                    GrantPermissionCommands::EnvironmentVariable {
+                        component_id,
+                        key,
+                        component_dir,
+                    } => {
+                        let component_dir =
+                            component_dir.clone().or_else(|| cli.component_dir.clone());
+                        let lifecycle_manager =
+                            create_lifecycle_manager(component_dir, cli.verbose).await?;
                         let mut args = Map::new();
                         args.insert("component_id".to_string(), json!(component_id));
                         args.insert(
                             "details".to_string(),
                             json!({
-                                "host": host
+                                "key": key
                             }),
                         );
                         handle_tool_cli_command(
                             &lifecycle_manager,
-                            "grant-network-permission",
+                            "grant-environment-variable-permission",
                             args,
                             OutputFormat::Json,
                         )
                         .await?;
                     }
-                    GrantPermissionCommands::EnvironmentVariable {
+                    GrantPermissionCommands::Config {
                         component_id,
                         key,
+                        value,
                         component_dir,
                     } => {
                         let component_dir =
                             component_dir.clone().or_else(|| cli.component_dir.clone());
-                        let lifecycle_manager = create_lifecycle_manager(component_dir).await?;
+                        let lifecycle_manager =
+                            create_lifecycle_manager(component_dir, cli.verbose).await?;
                         let mut args = Map::new();
                         args.insert("component_id".to_string(), json!(component_id));
                         args.insert(
                             "details".to_string(),
                             json!({
-                                "key": key
+                                "key": key,
+                                "value": value
                             }),
                         );
                         handle_tool_cli_command(
                             &lifecycle_manager,
-                            "grant-environment-variable-permission",
+                            "grant-config-value",
                             args,
                             OutputFormat::Json,
                         )
@@ -472,7 +1163,13 @@ async fn main() -> Result<()> {
                     } => {
                         let component_dir =
                             component_dir.clone().or_else(|| cli.component_dir.clone());
-                        let lifecycle_manager = create_lifecycle_manager(component_dir).await?;
+                        let lifecycle_manager =
+                            create_lifecycle_manager(component_dir, cli.verbose).await?;
+                        // A plain integer is a number of megabytes
+                        let limit = match limit.parse::<u64>() {
+                            Ok(megabytes) => json!(megabytes),
+                            Err(_) => json!(limit),
+                        };
                         let mut args = Map::new();
                         args.insert("component_id".to_string(), json!(component_id));
                         args.insert(
@@ -493,6 +1190,35 @@ async fn main() -> Result<()> {
                         )
                         .await?;
                     }
+                    GrantPermissionCommands::Cpu {
+                        component_id,
+                        limit,
+                        component_dir,
+                    } => {
+                        let component_dir =
+                            component_dir.clone().or_else(|| cli.component_dir.clone());
+                        let lifecycle_manager =
+                            create_lifecycle_manager(component_dir, cli.verbose).await?;
+                        let mut args = Map::new();
+                        args.insert("component_id".to_string(), json!(component_id));
+                        args.insert(
+                            "details".to_string(),
+                            json!({
+                                "resources": {
+                                    "limits": {
+                                        "cpu": limit
+                                    }
+                                }
+                            }),
+                        );
+                        handle_tool_cli_command(
+                            &lifecycle_manager,
+                            "grant-cpu-permission",
+                            args,
+                            OutputFormat::Json,
+                        )
+                        .await?;
+                    }
                 },
                 PermissionCommands::Revoke { permission } => match permission {
                     RevokePermissionCommands::Storage {
@@ -502,7 +1228,8 @@ async fn main() -> Result<()> {
                     } => {
                         let component_dir =
                             component_dir.clone().or_else(|| cli.component_dir.clone());
-                        let lifecycle_manager = create_lifecycle_manager(component_dir).await?;
+                        let lifecycle_manager =
+                            create_lifecycle_manager(component_dir, cli.verbose).await?;
                         let mut args = Map::new();
                         args.insert("component_id".to_string(), json!(component_id));
                         args.insert(
@@ -522,18 +1249,19 @@ async fn main() -> Result<()> {
                     RevokePermissionCommands::Network {
                         component_id,
                         host,
+                        port,
+                        scheme,
                         component_dir,
                     } => {
                         let component_dir =
                             component_dir.clone().or_else(|| cli.component_dir.clone());
-                        let lifecycle_manager = create_lifecycle_manager(component_dir).await?;
+                        let lifecycle_manager =
+                            create_lifecycle_manager(component_dir, cli.verbose).await?;
                         let mut args = Map::new();
                         args.insert("component_id".to_string(), json!(component_id));
                         args.insert(
                             "details".to_string(),
-                            json!({
-                                "host": host
-                            }),
+                            network_rule_details(host, *port, scheme),
                         );
                         handle_tool_cli_command(
                             &lifecycle_manager,
@@ -550,7 +1278,8 @@ async fn main() -> Result<()> {
                     } => {
                         let component_dir =
                             component_dir.clone().or_else(|| cli.component_dir.clone());
-                        let lifecycle_manager = create_lifecycle_manager(component_dir).await?;
+                        let lifecycle_manager =
+                            create_lifecycle_manager(component_dir, cli.verbose).await?;
                         let mut args = Map::new();
                         args.insert("component_id".to_string(), json!(component_id));
                         args.insert(
@@ -567,13 +1296,39 @@ async fn main() -> Result<()> {
                         )
                         .await?;
                     }
+                    RevokePermissionCommands::Config {
+                        component_id,
+                        key,
+                        component_dir,
+                    } => {
+                        let component_dir =
+                            component_dir.clone().or_else(|| cli.component_dir.clone());
+                        let lifecycle_manager =
+                            create_lifecycle_manager(component_dir, cli.verbose).await?;
+                        let mut args = Map::new();
+                        args.insert("component_id".to_string(), json!(component_id));
+                        args.insert(
+                            "details".to_string(),
+                            json!({
+                                "key": key
+                            }),
+                        );
+                        handle_tool_cli_command(
+                            &lifecycle_manager,
+                            "revoke-config-value",
+                            args,
+                            OutputFormat::Json,
+                        )
+                        .await?;
+                    }
                 },
                 PermissionCommands::Reset {
                     component_id,
                     component_dir,
                 } => {
                     let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
-                    let lifecycle_manager = create_lifecycle_manager(component_dir).await?;
+                    let lifecycle_manager =
+                        create_lifecycle_manager(component_dir, cli.verbose).await?;
                     let mut args = Map::new();
                     args.insert("component_id".to_string(), json!(component_id));
                     handle_tool_cli_command(
@@ -589,11 +1344,27 @@ async fn main() -> Result<()> {
                 SecretCommands::List {
                     component_id,
                     show_values,
+                    keys_only,
                     yes,
                     component_dir,
                     output_format,
                 } => {
-                    let lifecycle_manager = create_lifecycle_manager(component_dir.clone()).await?;
+                    let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
+                    let lifecycle_manager =
+                        create_lifecycle_manager(component_dir, cli.verbose).await?;
+
+                    if *keys_only {
+                        let mut args = Map::new();
+                        args.insert("component_id".to_string(), json!(component_id));
+                        handle_tool_cli_command(
+                            &lifecycle_manager,
+                            "list-secret-keys",
+                            args,
+                            *output_format,
+                        )
+                        .await?;
+                        return Ok(());
+                    }
 
                     // Prompt for confirmation if showing values
                     if *show_values && !*yes {
@@ -648,7 +1419,9 @@ async fn main() -> Result<()> {
                     secrets,
                     component_dir,
                 } => {
-                    let lifecycle_manager = create_lifecycle_manager(component_dir.clone()).await?;
+                    let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
+                    let lifecycle_manager =
+                        create_lifecycle_manager(component_dir, cli.verbose).await?;
                     lifecycle_manager
                         .set_component_secrets(component_id, secrets)
                         .await?;
@@ -676,7 +1449,9 @@ async fn main() -> Result<()> {
                     keys,
                     component_dir,
                 } => {
-                    let lifecycle_manager = create_lifecycle_manager(component_dir.clone()).await?;
+                    let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
+                    let lifecycle_manager =
+                        create_lifecycle_manager(component_dir, cli.verbose).await?;
                     lifecycle_manager
                         .delete_component_secrets(component_id, keys)
                         .await?;
@@ -706,9 +1481,13 @@ async fn main() -> Result<()> {
                     output_format,
                 } => {
                     let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
-                    let lifecycle_manager = create_lifecycle_manager(component_dir).await?;
+                    let lifecycle_manager =
+                        create_lifecycle_manager(component_dir, cli.verbose).await?;
+                    // List component tools from their cached metadata,
+                    // without compiling the components
+                    lifecycle_manager.populate_registry_from_metadata().await?;
 
-                    let result = handle_tools_list(&lifecycle_manager, false).await?;
+                    let result = handle_tools_list(&lifecycle_manager, false, false).await?;
 
                     let tools_result: rmcp::model::ListToolsResult =
                         serde_json::from_value(result)?;
@@ -740,17 +1519,9 @@ async fn main() -> Result<()> {
                     output_format,
                 } => {
                     let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
-                    let lifecycle_manager = create_lifecycle_manager(component_dir).await?;
-
-                    let result = handle_tools_list(&lifecycle_manager, false).await?;
-                    let tools_result: rmcp::model::ListToolsResult =
-                        serde_json::from_value(result)?;
-
-                    let tool = tools_result
-                        .tools
-                        .iter()
-                        .find(|t| t.name == name.as_str())
-                        .ok_or_else(|| anyhow::anyhow!("Tool not found: {}", name))?;
+                    let lifecycle_manager =
+                        create_lifecycle_manager(component_dir, cli.verbose).await?;
+                    let tool = find_tool(&lifecycle_manager, name).await?;
 
                     let content = serde_json::to_string_pretty(&json!({
                         "name": tool.name,
@@ -772,24 +1543,32 @@ async fn main() -> Result<()> {
                 ToolCommands::Invoke {
                     name,
                     args,
+                    arg,
                     component_dir,
                     output_format,
                 } => {
                     let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
-                    let lifecycle_manager = create_lifecycle_manager(component_dir).await?;
+                    let lifecycle_manager =
+                        create_lifecycle_manager(component_dir, cli.verbose).await?;
 
-                    let arguments = if let Some(args_str) = args {
+                    let mut arguments = if arg.is_empty() {
+                        serde_json::Map::new()
+                    } else {
+                        let tool = find_tool(&lifecycle_manager, name).await?;
+                        tool_args::coerce_args(name, &tool.input_schema, arg)?
+                    };
+
+                    // Arguments from --args win over the --arg shorthand
+                    if let Some(args_str) = args {
                         let parsed: serde_json::Value = serde_json::from_str(args_str)
                             .context("Failed to parse arguments as JSON")?;
 
                         if let serde_json::Value::Object(map) = parsed {
-                            map
+                            arguments.extend(map);
                         } else {
                             bail!("Arguments must be a JSON object");
                         }
-                    } else {
-                        serde_json::Map::new()
-                    };
+                    }
 
                     if let Ok(tool_name) = ToolName::try_from(name.as_str()) {
                         handle_tool_cli_command(
@@ -824,21 +1603,190 @@ async fn main() -> Result<()> {
                     }
                 }
             },
+            Commands::ExportState {
+                output,
+                include_secrets,
+                yes,
+                component_dir,
+                output_format,
+            } => {
+                if *include_secrets && !*yes {
+                    print!(
+                        "Secrets will be written unencrypted to {}. Continue? [y/N]: ",
+                        output.display()
+                    );
+                    std::io::Write::flush(&mut std::io::stdout())?;
+                    let mut input = String::new();
+                    std::io::stdin().read_line(&mut input)?;
+                    if !input.trim().eq_ignore_ascii_case("y") {
+                        println!("Cancelled.");
+                        return Ok(());
+                    }
+                }
+
+                let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
+                let lifecycle_manager =
+                    create_lifecycle_manager(component_dir, cli.verbose).await?;
+                let report = lifecycle_manager
+                    .export_state(
+                        output,
+                        StateExportOptions {
+                            include_secrets: *include_secrets,
+                        },
+                    )
+                    .await?;
+
+                let result = json!({
+                    "status": "success",
+                    "archive": output,
+                    "components": report.components,
+                    "secrets": report.secrets,
+                    "manifest": report.manifest,
+                });
+
+                print_result(
+                    &rmcp::model::CallToolResult {
+                        content: vec![rmcp::model::Content::text(serde_json::to_string_pretty(
+                            &result,
+                        )?)],
+                        structured_content: None,
+                        is_error: None,
+                        meta: None,
+                    },
+                    *output_format,
+                )?;
+            }
+            Commands::ImportState {
+                archive,
+                include_secrets,
+                component_dir,
+                output_format,
+            } => {
+                let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
+                let lifecycle_manager =
+                    create_lifecycle_manager(component_dir, cli.verbose).await?;
+                let report = lifecycle_manager
+                    .import_state(
+                        archive,
+                        StateImportOptions {
+                            include_secrets: *include_secrets,
+                        },
+                    )
+                    .await?;
+
+                let clean = report.conflicts.is_empty() && report.failed.is_empty();
+                let result = json!({
+                    "status": if clean { "success" } else { "partial" },
+                    "imported": report.imported,
+                    "unchanged": report.unchanged,
+                    "conflicts": report.conflicts,
+                    "failed": report.failed,
+                    "secrets": report.secrets,
+                });
+
+                print_result(
+                    &rmcp::model::CallToolResult {
+                        content: vec![rmcp::model::Content::text(serde_json::to_string_pretty(
+                            &result,
+                        )?)],
+                        structured_content: None,
+                        is_error: None,
+                        meta: None,
+                    },
+                    *output_format,
+                )?;
+            }
+            Commands::Status {
+                component_dir,
+                output_format,
+            } => {
+                let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
+                let lifecycle_manager =
+                    create_lifecycle_manager(component_dir, cli.verbose).await?;
+                // Count components from their cached metadata, as a server
+                // does before background loading compiles them
+                lifecycle_manager.populate_registry_from_metadata().await?;
+
+                let details = ServerDetails::new(env!("CARGO_PKG_VERSION"))
+                    .with_build_info(format_build_info());
+                let result = server_info_report(&lifecycle_manager, &details).await;
+
+                print_result(
+                    &rmcp::model::CallToolResult {
+                        content: vec![rmcp::model::Content::text(serde_json::to_string_pretty(
+                            &result,
+                        )?)],
+                        structured_content: None,
+                        is_error: None,
+                        meta: None,
+                    },
+                    *output_format,
+                )?;
+            }
+            Commands::Doctor {
+                component_dir,
+                output_format,
+            } => {
+                let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
+                let lifecycle_manager =
+                    create_lifecycle_manager(component_dir, cli.verbose).await?;
+                let report = mcp_server::doctor_report(&lifecycle_manager).await?;
+
+                print_result(
+                    &rmcp::model::CallToolResult {
+                        content: vec![rmcp::model::Content::text(serde_json::to_string_pretty(
+                            &report,
+                        )?)],
+                        structured_content: None,
+                        is_error: None,
+                        meta: None,
+                    },
+                    *output_format,
+                )?;
+                if report.has_errors() {
+                    std::process::exit(1);
+                }
+            }
             Commands::Inspect {
                 component_id,
                 component_dir,
+                strict,
             } => {
                 let component_dir = component_dir.clone().or_else(|| cli.component_dir.clone());
-                let lifecycle_manager = create_lifecycle_manager(component_dir).await?;
+                let lifecycle_manager =
+                    create_lifecycle_manager(component_dir, cli.verbose).await?;
 
                 // Get the component schema from the lifecycle manager
-                let schema = lifecycle_manager
-                    .get_component_schema(component_id)
-                    .await
-                    .context(format!(
-                    "Component '{}' not found. Use 'component load' to load the component first.",
-                    component_id
-                ))?;
+                let saved = lifecycle_manager.get_component_schema(component_id).await;
+                let schema = if *strict {
+                    let compiled = lifecycle_manager
+                        .inspect_component(component_id)
+                        .await
+                        .with_context(|| format!("Failed to compile component '{component_id}'"))?;
+                    if saved.as_ref().is_some_and(|saved| *saved != compiled) {
+                        eprintln!(
+                            "warning: the tools saved for '{component_id}' differ from the ones it exposes; load it again to update them"
+                        );
+                    }
+                    compiled
+                } else {
+                    saved.context(format!(
+                        "Component '{}' not found. Use 'component load' to load the component first.",
+                        component_id
+                    ))?
+                };
+
+                // Display the imports, unless the component file is gone
+                if let Ok(imports) = lifecycle_manager.component_imports(component_id).await {
+                    println!("imports:");
+                    for import in imports {
+                        if import.provided {
+                            println!("  {}", import.name);
+                        } else {
+                            println!("  {} (not provided by this runtime)", import.name);
+                        }
+                    }
+                }
 
                 // Display tools information
                 if let Some(arr) = schema["tools"].as_array() {
@@ -863,6 +1811,18 @@ async fn main() -> Result<()> {
                             "output schema: {}",
                             serde_json::to_string_pretty(&output_schema)?
                         );
+                        if let Some(examples) = tool_info["_meta"]["examples"].as_array() {
+                            println!("examples:");
+                            for example in examples {
+                                let arguments = serde_json::to_string(&example["arguments"])?;
+                                match example["description"].as_str() {
+                                    Some(description) if !description.is_empty() => {
+                                        println!("  {description}: {arguments}")
+                                    }
+                                    _ => println!("  {arguments}"),
+                                }
+                            }
+                        }
                     }
                 } else {
                     println!("No tools found in component");
@@ -871,10 +1831,11 @@ async fn main() -> Result<()> {
             Commands::Registry { command } => match command {
                 RegistryCommands::Search {
                     query,
+                    registry_files,
                     output_format,
                 } => {
-                    let components = load_component_registry()?;
-                    let results = registry::search_components(&components, query.as_deref());
+                    let registry = load_component_registry(registry_files)?;
+                    let results = registry.search(query.as_deref().unwrap_or(""));
 
                     let result = json!({
                         "status": "success",
@@ -896,23 +1857,28 @@ async fn main() -> Result<()> {
                 }
                 RegistryCommands::Get {
                     component,
+                    registry_files,
                     plugin_dir,
+                    override_source_policy,
                 } => {
-                    let components = load_component_registry()?;
+                    let registry = load_component_registry(registry_files)?;
 
                     // Find the component by name or URI
-                    let registry_component =
-                        registry::find_component_by_name_or_uri(&components, component)
-                            .ok_or_else(|| {
-                                anyhow::anyhow!(
-                                    "Component '{}' not found in registry. Use 'wassette registry search' to list available components.",
-                                    component
-                                )
-                            })?;
+                    let registry_component = registry.find(component).ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Component '{}' not found in registry. Use 'wassette registry search' to list available components.",
+                            component
+                        )
+                    })?;
 
                     // Use the existing load-component functionality
                     let plugin_dir = plugin_dir.clone().or_else(|| cli.component_dir.clone());
-                    let lifecycle_manager = create_lifecycle_manager(plugin_dir).await?;
+                    let lifecycle_manager = create_lifecycle_manager_with_source_override(
+                        plugin_dir,
+                        cli.verbose,
+                        *override_source_policy,
+                    )
+                    .await?;
                     let mut args = Map::new();
                     args.insert("path".to_string(), json!(registry_component.uri));
                     handle_tool_cli_command(
@@ -1004,6 +1970,28 @@ mod cli_tests {
         matches!(cli.command, Some(Commands::Serve(_)));
     }
 
+    #[test]
+    fn test_component_unload_skip_hooks_parsing() {
+        let args = vec![
+            "wassette",
+            "component",
+            "unload",
+            "test-component",
+            "--skip-hooks",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        if let Some(Commands::Component {
+            command: ComponentCommands::Unload { id, skip_hooks, .. },
+        }) = cli.command
+        {
+            assert_eq!(id, "test-component");
+            assert!(skip_hooks);
+        } else {
+            panic!("Expected component unload command");
+        }
+    }
+
     #[test]
     fn test_permission_grant_storage_parsing() {
         let args = vec![
@@ -1026,6 +2014,7 @@ mod cli_tests {
                             component_id,
                             uri,
                             access,
+                            create,
                             ..
                         },
                 },
@@ -1034,6 +2023,7 @@ mod cli_tests {
             assert_eq!(component_id, "test-component");
             assert_eq!(uri, "fs:///tmp/test");
             assert_eq!(access, vec!["read", "write"]);
+            assert!(!create);
         } else {
             panic!("Expected storage grant command");
         }
@@ -1068,6 +2058,48 @@ mod cli_tests {
         }
     }
 
+    #[test]
+    fn test_permission_grant_network_port_and_scheme_parsing() {
+        let args = vec![
+            "wassette",
+            "permission",
+            "grant",
+            "network",
+            "test-component",
+            "api.example.com",
+            "--port",
+            "443",
+            "--scheme",
+            "https",
+        ];
+        let cli = Cli::try_parse_from(args.clone()).unwrap();
+
+        if let Some(Commands::Permission {
+            command:
+                PermissionCommands::Grant {
+                    permission:
+                        GrantPermissionCommands::Network {
+                            host, port, scheme, ..
+                        },
+                },
+        }) = cli.command
+        {
+            assert_eq!(
+                network_rule_details(host.as_deref().unwrap(), port, &scheme),
+                json!({"host": "api.example.com", "port": 443, "scheme": "https"})
+            );
+        } else {
+            panic!("Expected network grant command");
+        }
+
+        let mut ftp = args.clone();
+        ftp[9] = "ftp";
+        assert!(Cli::try_parse_from(ftp).is_err());
+        let mut hosts_file = args;
+        hosts_file.splice(5..6, ["--hosts-file", "hosts.txt"]);
+        assert!(Cli::try_parse_from(hosts_file).is_err());
+    }
+
     #[test]
     fn test_autocomplete_parsing() {
         // Test autocomplete bash
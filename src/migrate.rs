@@ -0,0 +1,373 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Relocating an existing component directory into the resolved one.
+//!
+//! Users who started with a project-local directory such as `./components`
+//! can move their installed components to the platform data directory
+//! without reinstalling them. Only files wassette itself writes are
+//! relocated; the downloads cache is left behind since it can be refetched.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use wassette::{
+    ComponentManifest, MANIFEST_FILE_NAME, POLICY_FRAGMENTS_DIR, POLICY_HISTORY_DIR,
+    USAGE_FILE_NAME, VISIBILITY_FILE_NAME,
+};
+
+/// Suffixes of the per-component files kept in a component directory.
+const COMPONENT_FILE_SUFFIXES: &[&str] = &[
+    ".wasm",
+    ".cwasm",
+    ".metadata.json",
+    ".policy.yaml",
+    ".policy.json",
+    ".policy.meta.json",
+];
+
+/// Whether files are copied or moved into the new directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MigrationMode {
+    Copy,
+    Move,
+}
+
+/// File left in place because the destination already had one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkippedFile {
+    pub file: String,
+    pub reason: String,
+}
+
+/// Outcome of [`migrate_component_dir`].
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationReport {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub mode: MigrationMode,
+    /// Files relocated into the new directory
+    pub migrated: Vec<String>,
+    pub skipped: Vec<SkippedFile>,
+    /// Manifest entries whose `file://` sources were rewritten to the new directory
+    pub rewritten_sources: Vec<String>,
+}
+
+fn is_component_file(name: &str) -> bool {
+    name == USAGE_FILE_NAME
+        || name == VISIBILITY_FILE_NAME
+        || COMPONENT_FILE_SUFFIXES
+            .iter()
+            .any(|suffix| name.len() > suffix.len() && name.ends_with(suffix))
+}
+
+fn transfer(source: &Path, dest: &Path, mode: MigrationMode) -> Result<()> {
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    match mode {
+        MigrationMode::Copy => {
+            std::fs::copy(source, dest)?;
+        }
+        MigrationMode::Move => {
+            // Renaming fails across filesystems, so fall back to copy and delete.
+            if std::fs::rename(source, dest).is_err() {
+                std::fs::copy(source, dest)?;
+                std::fs::remove_file(source)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Files accepted by `keep` in the per-component subdirectories of
+/// `root/dir`, as paths relative to `root`.
+fn per_component_files(root: &Path, dir: &str, keep: impl Fn(&str) -> bool) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    let dir_path = root.join(dir);
+    if !dir_path.is_dir() {
+        return Ok(names);
+    }
+    for component in std::fs::read_dir(&dir_path)? {
+        let component = component?;
+        if !component.file_type()?.is_dir() {
+            continue;
+        }
+        let Some(component_id) = component.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        for entry in std::fs::read_dir(component.path())? {
+            let entry = entry?;
+            let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            if entry.file_type()?.is_file() && keep(&name) {
+                names.push(format!("{dir}/{component_id}/{name}"));
+            }
+        }
+    }
+    Ok(names)
+}
+
+/// Rewrite a `file://` URI that points inside `from` so it points at the same
+/// file under `to`. Returns `None` for URIs outside the old directory.
+fn rewrite_file_uri(uri: &str, from: &[PathBuf], to: &Path) -> Option<String> {
+    let path = Path::new(uri.strip_prefix("file://")?);
+    from.iter()
+        .find_map(|old| path.strip_prefix(old).ok())
+        .map(|relative| format!("file://{}", to.join(relative).display()))
+}
+
+/// Relocate the component files in `from` into `to`, including policy
+/// fragments and policy history, and carry the state manifest over,
+/// rewriting `file://` sources that pointed into `from`.
+///
+/// Files that already exist in `to` are never overwritten; they are reported
+/// as skipped. Entries already present in a manifest at `to` win over those
+/// from the old manifest.
+pub async fn migrate_component_dir(
+    from: &Path,
+    to: &Path,
+    mode: MigrationMode,
+) -> Result<MigrationReport> {
+    if !from.is_dir() {
+        bail!(
+            "Directory to migrate from does not exist: {}",
+            from.display()
+        );
+    }
+    std::fs::create_dir_all(to)
+        .with_context(|| format!("Failed to create component directory at {}", to.display()))?;
+
+    let from_canonical = from.canonicalize()?;
+    let to_canonical = to.canonicalize()?;
+    if from_canonical == to_canonical {
+        bail!(
+            "{} is already the component directory, nothing to migrate",
+            from.display()
+        );
+    }
+
+    let mut report = MigrationReport {
+        from: from.to_path_buf(),
+        to: to.to_path_buf(),
+        mode,
+        migrated: Vec::new(),
+        skipped: Vec::new(),
+        rewritten_sources: Vec::new(),
+    };
+
+    let mut names = Vec::new();
+    for entry in std::fs::read_dir(from)
+        .with_context(|| format!("Failed to read directory {}", from.display()))?
+    {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        if let Some(name) = entry.file_name().to_str().filter(|n| is_component_file(n)) {
+            names.push(name.to_string());
+        }
+    }
+    // Policy fragments and policy history are kept in a directory per component
+    names.extend(per_component_files(from, POLICY_FRAGMENTS_DIR, |name| {
+        name.ends_with(".policy.yaml")
+    })?);
+    names.extend(per_component_files(from, POLICY_HISTORY_DIR, |_| true)?);
+    names.sort();
+
+    for name in names {
+        let dest = to.join(&name);
+        if dest.exists() {
+            report.skipped.push(SkippedFile {
+                file: name,
+                reason: "already exists in the component directory".to_string(),
+            });
+            continue;
+        }
+        transfer(&from.join(&name), &dest, mode)
+            .with_context(|| format!("Failed to migrate {name} to {}", to.display()))?;
+        report.migrated.push(name);
+    }
+
+    let old_manifest_path = from.join(MANIFEST_FILE_NAME);
+    if old_manifest_path.exists() {
+        let old_manifest = ComponentManifest::load(&old_manifest_path).await?;
+        let new_manifest_path = to.join(MANIFEST_FILE_NAME);
+        let mut manifest = ComponentManifest::load(&new_manifest_path).await?;
+        let old_dirs = [from.to_path_buf(), from_canonical];
+
+        for (id, mut entry) in old_manifest.components {
+            if manifest.components.contains_key(&id) {
+                continue;
+            }
+            let mut rewritten = false;
+            if let Some(uri) = rewrite_file_uri(&entry.source_uri, &old_dirs, to) {
+                entry.source_uri = uri;
+                rewritten = true;
+            }
+            if let Some(uri) = entry
+                .policy_source
+                .as_deref()
+                .and_then(|source| rewrite_file_uri(source, &old_dirs, to))
+            {
+                entry.policy_source = Some(uri);
+                rewritten = true;
+            }
+            if rewritten {
+                report.rewritten_sources.push(id.clone());
+            }
+            manifest.components.insert(id, entry);
+        }
+
+        manifest.save(&new_manifest_path).await?;
+        if mode == MigrationMode::Move {
+            std::fs::remove_file(&old_manifest_path)?;
+        }
+        report.migrated.push(MANIFEST_FILE_NAME.to_string());
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+    use wassette::ManifestEntry;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_migrate_component_dir() {
+        let old = TempDir::new().unwrap();
+        let new = TempDir::new().unwrap();
+        for name in [
+            "fetch.wasm",
+            "fetch.cwasm",
+            "fetch.metadata.json",
+            "fetch.policy.yaml",
+            "fetch.policy.json",
+            "fetch.policy.meta.json",
+            "time.wasm",
+            "notes.txt",
+            USAGE_FILE_NAME,
+            VISIBILITY_FILE_NAME,
+        ] {
+            std::fs::write(old.path().join(name), name).unwrap();
+        }
+        let fragments = old.path().join(POLICY_FRAGMENTS_DIR).join("fetch");
+        std::fs::create_dir_all(&fragments).unwrap();
+        std::fs::write(fragments.join("network.policy.yaml"), "network").unwrap();
+        let history = old.path().join(POLICY_HISTORY_DIR).join("fetch");
+        std::fs::create_dir_all(&history).unwrap();
+        std::fs::write(history.join("history.json"), "[]").unwrap();
+        std::fs::write(history.join("1.policy.yaml"), "revision 1").unwrap();
+        let time_history = old.path().join(POLICY_HISTORY_DIR).join("time");
+        std::fs::create_dir_all(&time_history).unwrap();
+        std::fs::write(time_history.join("1.policy.yaml"), "old revision").unwrap();
+        let new_time_history = new.path().join(POLICY_HISTORY_DIR).join("time");
+        std::fs::create_dir_all(&new_time_history).unwrap();
+        std::fs::write(new_time_history.join("1.policy.yaml"), "new revision").unwrap();
+        std::fs::write(new.path().join("time.wasm"), "newer").unwrap();
+
+        let mut manifest = ComponentManifest::default();
+        manifest.components.insert(
+            "fetch".to_string(),
+            ManifestEntry {
+                source_uri: format!("file://{}/fetch.wasm", old.path().display()),
+                digest: "sha256:abc".to_string(),
+                policy_source: None,
+                load_order: None,
+            },
+        );
+        manifest.components.insert(
+            "time".to_string(),
+            ManifestEntry {
+                source_uri: "oci://ghcr.io/example/time:latest".to_string(),
+                digest: "sha256:def".to_string(),
+                policy_source: None,
+                load_order: None,
+            },
+        );
+        manifest
+            .save(old.path().join(MANIFEST_FILE_NAME))
+            .await
+            .unwrap();
+
+        let report = migrate_component_dir(old.path(), new.path(), MigrationMode::Move)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            report.migrated,
+            vec![
+                "fetch.cwasm",
+                "fetch.metadata.json",
+                "fetch.policy.json",
+                "fetch.policy.meta.json",
+                "fetch.policy.yaml",
+                "fetch.wasm",
+                "policy-fragments/fetch/network.policy.yaml",
+                "policy-history/fetch/1.policy.yaml",
+                "policy-history/fetch/history.json",
+                USAGE_FILE_NAME,
+                VISIBILITY_FILE_NAME,
+                MANIFEST_FILE_NAME,
+            ]
+        );
+        let skipped: Vec<_> = report.skipped.iter().map(|s| s.file.as_str()).collect();
+        assert_eq!(
+            skipped,
+            vec!["policy-history/time/1.policy.yaml", "time.wasm"]
+        );
+        assert_eq!(report.rewritten_sources, vec!["fetch"]);
+
+        assert!(!old.path().join("fetch.wasm").exists());
+        assert!(!old.path().join("fetch.policy.json").exists());
+        assert!(new.path().join("fetch.policy.json").is_file());
+        assert!(new
+            .path()
+            .join("policy-fragments/fetch/network.policy.yaml")
+            .is_file());
+        assert!(new
+            .path()
+            .join("policy-history/fetch/history.json")
+            .is_file());
+        assert!(!old
+            .path()
+            .join("policy-history/fetch/1.policy.yaml")
+            .exists());
+        assert_eq!(
+            std::fs::read_to_string(new_time_history.join("1.policy.yaml")).unwrap(),
+            "new revision"
+        );
+        assert!(old.path().join("notes.txt").exists());
+        assert_eq!(
+            std::fs::read_to_string(new.path().join("time.wasm")).unwrap(),
+            "newer"
+        );
+
+        let migrated = ComponentManifest::load(new.path().join(MANIFEST_FILE_NAME))
+            .await
+            .unwrap();
+        assert_eq!(
+            migrated.components["fetch"].source_uri,
+            format!("file://{}", new.path().join("fetch.wasm").display())
+        );
+        assert_eq!(
+            migrated.components["time"].source_uri,
+            "oci://ghcr.io/example/time:latest"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_migrate_into_same_dir_fails() {
+        let dir = TempDir::new().unwrap();
+        let err = migrate_component_dir(dir.path(), dir.path(), MigrationMode::Copy)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("nothing to migrate"));
+    }
+}
@@ -5,6 +5,7 @@ use anyhow::{Context, Result};
 use policy::{
     AccessType as PolicyAccessType, EnvironmentPermission, EnvironmentPermissions,
     NetworkHostPermission, NetworkPermission, PermissionList, PolicyDocument, StoragePermission,
+    StoragePermissions,
 };
 
 use crate::manifest::{AccessType, InlinePermissions};
@@ -28,6 +29,7 @@ pub fn synthesize_policy_from_inline(
         for rule in &network_perms.allow {
             network_allow.push(NetworkPermission::Host(NetworkHostPermission {
                 host: rule.host.clone(),
+                ..Default::default()
             }));
         }
 
@@ -56,9 +58,10 @@ pub fn synthesize_policy_from_inline(
             });
         }
 
-        policy.permissions.storage = Some(PermissionList {
+        policy.permissions.storage = Some(StoragePermissions {
             allow: Some(storage_allow),
             deny: None,
+            scratch: None,
         });
     }
 
@@ -73,6 +76,7 @@ pub fn synthesize_policy_from_inline(
 
         policy.permissions.environment = Some(EnvironmentPermissions {
             allow: Some(env_allow),
+            ..Default::default()
         });
     }
 
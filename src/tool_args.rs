@@ -0,0 +1,188 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! `key=value` shorthand for tool arguments on the command line.
+//!
+//! Each value is converted to the type its property has in the tool's input
+//! schema: numbers, integers, and booleans are parsed, an array of scalars
+//! is split on commas, and an object is read as JSON. Keys the schema does
+//! not list are rejected rather than passed through.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{Map, Value};
+
+/// Parse a `KEY=VALUE` command line argument.
+pub fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some(("", _)) => Err("Argument key cannot be empty".to_string()),
+        Some((key, value)) => Ok((key.to_string(), value.to_string())),
+        None => Err("Argument must be in KEY=VALUE format".to_string()),
+    }
+}
+
+/// Build the argument object for `tool` from `pairs`, converting each value
+/// to the type `input_schema` gives its key. A key given more than once
+/// takes its last value.
+pub fn coerce_args(
+    tool: &str,
+    input_schema: &Map<String, Value>,
+    pairs: &[(String, String)],
+) -> Result<Map<String, Value>> {
+    let properties = input_schema.get("properties").and_then(Value::as_object);
+    let mut arguments = Map::new();
+    for (key, raw) in pairs {
+        let Some(property) = properties.and_then(|properties| properties.get(key)) else {
+            let mut known: Vec<&str> = properties
+                .map(|properties| properties.keys().map(String::as_str).collect())
+                .unwrap_or_default();
+            known.sort_unstable();
+            bail!(
+                "Tool '{tool}' has no argument '{key}' (arguments: {})",
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            );
+        };
+        let value = coerce_value(raw, property)
+            .with_context(|| format!("Invalid value for argument '{key}' of tool '{tool}'"))?;
+        arguments.insert(key.clone(), value);
+    }
+    Ok(arguments)
+}
+
+/// Convert `raw` to the type `schema` describes. A property without a type
+/// is passed as a string.
+pub fn coerce_value(raw: &str, schema: &Value) -> Result<Value> {
+    match schema_type(schema) {
+        Some("integer") => raw
+            .parse::<i64>()
+            .map(Value::from)
+            .map_err(|_| anyhow!("'{raw}' is not an integer")),
+        Some("number") => raw
+            .parse::<serde_json::Number>()
+            .map(Value::Number)
+            .map_err(|_| anyhow!("'{raw}' is not a number")),
+        Some("boolean") => match raw {
+            "true" => Ok(Value::Bool(true)),
+            "false" => Ok(Value::Bool(false)),
+            _ => bail!("'{raw}' is not a boolean, use true or false"),
+        },
+        Some("array") => {
+            let items = schema.get("items").unwrap_or(&Value::Null);
+            if matches!(schema_type(items), Some("array" | "object")) {
+                bail!("Arrays of arrays or objects must be passed with --args");
+            }
+            if raw.is_empty() {
+                return Ok(Value::Array(Vec::new()));
+            }
+            raw.split(',')
+                .map(|item| coerce_value(item.trim(), items))
+                .collect::<Result<Vec<_>>>()
+                .map(Value::Array)
+        }
+        Some("object") => match serde_json::from_str(raw) {
+            Ok(value @ Value::Object(_)) => Ok(value),
+            _ => bail!("'{raw}' is not a JSON object"),
+        },
+        Some("null") if raw == "null" => Ok(Value::Null),
+        Some("null") => bail!("'{raw}' is not null"),
+        _ => Ok(Value::String(raw.to_string())),
+    }
+}
+
+/// The type a schema declares. Of a list of types, the first one other than
+/// `null` is used.
+fn schema_type(schema: &Value) -> Option<&str> {
+    match schema.get("type")? {
+        Value::String(ty) => Some(ty),
+        Value::Array(types) => {
+            let mut types = types.iter().filter_map(Value::as_str);
+            let first = types.clone().next();
+            types.find(|ty| *ty != "null").or(first)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema() -> Map<String, Value> {
+        json!({
+            "type": "object",
+            "properties": {
+                "url": {"type": "string"},
+                "retries": {"type": "integer"},
+                "ratio": {"type": "number"},
+                "verbose": {"type": "boolean"},
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "ports": {"type": "array", "items": {"type": "integer"}},
+                "headers": {"type": "object"},
+                "limit": {"type": ["integer", "null"]}
+            }
+        })
+        .as_object()
+        .unwrap()
+        .clone()
+    }
+
+    fn pairs(args: &[&str]) -> Vec<(String, String)> {
+        args.iter()
+            .map(|arg| parse_key_value(arg).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_args_are_coerced_to_schema_types() {
+        let arguments = coerce_args(
+            "fetch",
+            &schema(),
+            &pairs(&[
+                "url=https://example.com/?a=b",
+                "retries=3",
+                "ratio=0.5",
+                "verbose=true",
+                "tags=a, b",
+                "ports=80,443",
+                r#"headers={"accept": "text/html"}"#,
+                "limit=10",
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            Value::Object(arguments),
+            json!({
+                "url": "https://example.com/?a=b",
+                "retries": 3,
+                "ratio": 0.5,
+                "verbose": true,
+                "tags": ["a", "b"],
+                "ports": [80, 443],
+                "headers": {"accept": "text/html"},
+                "limit": 10
+            })
+        );
+    }
+
+    #[test]
+    fn test_unknown_keys_and_bad_values_are_rejected() {
+        let error = coerce_args("fetch", &schema(), &pairs(&["uri=x"])).unwrap_err();
+        assert!(error.to_string().contains("has no argument 'uri'"));
+        assert!(error.to_string().contains("retries, tags, url, verbose"));
+
+        let error = coerce_args("fetch", &schema(), &pairs(&["retries=three"])).unwrap_err();
+        assert!(format!("{error:#}").contains("'three' is not an integer"));
+
+        let error = coerce_args("fetch", &schema(), &pairs(&["ports=80,http"])).unwrap_err();
+        assert!(format!("{error:#}").contains("'http' is not an integer"));
+
+        assert!(coerce_args("fetch", &schema(), &pairs(&["verbose=yes"])).is_err());
+        assert!(parse_key_value("novalue").is_err());
+        assert!(parse_key_value("=value").is_err());
+    }
+}
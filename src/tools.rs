@@ -10,15 +10,20 @@ use anyhow::Result;
 pub enum ToolName {
     LoadComponent,
     UnloadComponent,
+    DeactivateComponent,
     ListComponents,
+    InstallComponent,
     GetPolicy,
     GrantStoragePermission,
     GrantNetworkPermission,
     GrantEnvironmentVariablePermission,
     GrantMemoryPermission,
+    GrantCpuPermission,
     RevokeStoragePermission,
     RevokeNetworkPermission,
     RevokeEnvironmentVariablePermission,
+    GrantConfigValue,
+    RevokeConfigValue,
     ResetPermission,
 }
 
@@ -28,17 +33,22 @@ impl ToolName {
         match self {
             Self::LoadComponent => Self::LOAD_COMPONENT,
             Self::UnloadComponent => Self::UNLOAD_COMPONENT,
+            Self::DeactivateComponent => Self::DEACTIVATE_COMPONENT,
             Self::ListComponents => Self::LIST_COMPONENTS,
+            Self::InstallComponent => Self::INSTALL_COMPONENT,
             Self::GetPolicy => Self::GET_POLICY,
             Self::GrantStoragePermission => Self::GRANT_STORAGE_PERMISSION,
             Self::GrantNetworkPermission => Self::GRANT_NETWORK_PERMISSION,
             Self::GrantEnvironmentVariablePermission => Self::GRANT_ENVIRONMENT_VARIABLE_PERMISSION,
             Self::GrantMemoryPermission => Self::GRANT_MEMORY_PERMISSION,
+            Self::GrantCpuPermission => Self::GRANT_CPU_PERMISSION,
             Self::RevokeStoragePermission => Self::REVOKE_STORAGE_PERMISSION,
             Self::RevokeNetworkPermission => Self::REVOKE_NETWORK_PERMISSION,
             Self::RevokeEnvironmentVariablePermission => {
                 Self::REVOKE_ENVIRONMENT_VARIABLE_PERMISSION
             }
+            Self::GrantConfigValue => Self::GRANT_CONFIG_VALUE,
+            Self::RevokeConfigValue => Self::REVOKE_CONFIG_VALUE,
             Self::ResetPermission => Self::RESET_PERMISSION,
         }
     }
@@ -46,17 +56,22 @@ impl ToolName {
     // String constants for tool names
     const LOAD_COMPONENT: &'static str = "load-component";
     const UNLOAD_COMPONENT: &'static str = "unload-component";
+    const DEACTIVATE_COMPONENT: &'static str = "deactivate-component";
     const LIST_COMPONENTS: &'static str = "list-components";
+    const INSTALL_COMPONENT: &'static str = "install-component";
     const GET_POLICY: &'static str = "get-policy";
     const GRANT_STORAGE_PERMISSION: &'static str = "grant-storage-permission";
     const GRANT_NETWORK_PERMISSION: &'static str = "grant-network-permission";
     const GRANT_ENVIRONMENT_VARIABLE_PERMISSION: &'static str =
         "grant-environment-variable-permission";
     const GRANT_MEMORY_PERMISSION: &'static str = "grant-memory-permission";
+    const GRANT_CPU_PERMISSION: &'static str = "grant-cpu-permission";
     const REVOKE_STORAGE_PERMISSION: &'static str = "revoke-storage-permission";
     const REVOKE_NETWORK_PERMISSION: &'static str = "revoke-network-permission";
     const REVOKE_ENVIRONMENT_VARIABLE_PERMISSION: &'static str =
         "revoke-environment-variable-permission";
+    const GRANT_CONFIG_VALUE: &'static str = "grant-config-value";
+    const REVOKE_CONFIG_VALUE: &'static str = "revoke-config-value";
     const RESET_PERMISSION: &'static str = "reset-permission";
 }
 
@@ -67,7 +82,9 @@ impl TryFrom<&str> for ToolName {
         match value {
             Self::LOAD_COMPONENT => Ok(Self::LoadComponent),
             Self::UNLOAD_COMPONENT => Ok(Self::UnloadComponent),
+            Self::DEACTIVATE_COMPONENT => Ok(Self::DeactivateComponent),
             Self::LIST_COMPONENTS => Ok(Self::ListComponents),
+            Self::INSTALL_COMPONENT => Ok(Self::InstallComponent),
             Self::GET_POLICY => Ok(Self::GetPolicy),
             Self::GRANT_STORAGE_PERMISSION => Ok(Self::GrantStoragePermission),
             Self::GRANT_NETWORK_PERMISSION => Ok(Self::GrantNetworkPermission),
@@ -75,11 +92,14 @@ impl TryFrom<&str> for ToolName {
                 Ok(Self::GrantEnvironmentVariablePermission)
             }
             Self::GRANT_MEMORY_PERMISSION => Ok(Self::GrantMemoryPermission),
+            Self::GRANT_CPU_PERMISSION => Ok(Self::GrantCpuPermission),
             Self::REVOKE_STORAGE_PERMISSION => Ok(Self::RevokeStoragePermission),
             Self::REVOKE_NETWORK_PERMISSION => Ok(Self::RevokeNetworkPermission),
             Self::REVOKE_ENVIRONMENT_VARIABLE_PERMISSION => {
                 Ok(Self::RevokeEnvironmentVariablePermission)
             }
+            Self::GRANT_CONFIG_VALUE => Ok(Self::GrantConfigValue),
+            Self::REVOKE_CONFIG_VALUE => Ok(Self::RevokeConfigValue),
             Self::RESET_PERMISSION => Ok(Self::ResetPermission),
             _ => Err(anyhow::anyhow!("Unknown tool name: {}", value)),
         }
@@ -114,10 +134,18 @@ mod tests {
             ToolName::try_from("unload-component").unwrap(),
             ToolName::UnloadComponent
         );
+        assert_eq!(
+            ToolName::try_from("deactivate-component").unwrap(),
+            ToolName::DeactivateComponent
+        );
         assert_eq!(
             ToolName::try_from("list-components").unwrap(),
             ToolName::ListComponents
         );
+        assert_eq!(
+            ToolName::try_from("install-component").unwrap(),
+            ToolName::InstallComponent
+        );
         assert_eq!(
             ToolName::try_from("get-policy").unwrap(),
             ToolName::GetPolicy
@@ -138,6 +166,10 @@ mod tests {
             ToolName::try_from("grant-memory-permission").unwrap(),
             ToolName::GrantMemoryPermission
         );
+        assert_eq!(
+            ToolName::try_from("grant-cpu-permission").unwrap(),
+            ToolName::GrantCpuPermission
+        );
         assert_eq!(
             ToolName::try_from("revoke-storage-permission").unwrap(),
             ToolName::RevokeStoragePermission
@@ -150,6 +182,14 @@ mod tests {
             ToolName::try_from("revoke-environment-variable-permission").unwrap(),
             ToolName::RevokeEnvironmentVariablePermission
         );
+        assert_eq!(
+            ToolName::try_from("grant-config-value").unwrap(),
+            ToolName::GrantConfigValue
+        );
+        assert_eq!(
+            ToolName::try_from("revoke-config-value").unwrap(),
+            ToolName::RevokeConfigValue
+        );
         assert_eq!(
             ToolName::try_from("reset-permission").unwrap(),
             ToolName::ResetPermission
@@ -163,7 +203,12 @@ mod tests {
     fn test_tool_name_as_str() {
         assert_eq!(ToolName::LoadComponent.as_str(), "load-component");
         assert_eq!(ToolName::UnloadComponent.as_str(), "unload-component");
+        assert_eq!(
+            ToolName::DeactivateComponent.as_str(),
+            "deactivate-component"
+        );
         assert_eq!(ToolName::ListComponents.as_str(), "list-components");
+        assert_eq!(ToolName::InstallComponent.as_str(), "install-component");
         assert_eq!(ToolName::GetPolicy.as_str(), "get-policy");
         assert_eq!(
             ToolName::GrantStoragePermission.as_str(),
@@ -181,6 +226,10 @@ mod tests {
             ToolName::GrantMemoryPermission.as_str(),
             "grant-memory-permission"
         );
+        assert_eq!(
+            ToolName::GrantCpuPermission.as_str(),
+            "grant-cpu-permission"
+        );
         assert_eq!(
             ToolName::RevokeStoragePermission.as_str(),
             "revoke-storage-permission"
@@ -193,6 +242,8 @@ mod tests {
             ToolName::RevokeEnvironmentVariablePermission.as_str(),
             "revoke-environment-variable-permission"
         );
+        assert_eq!(ToolName::GrantConfigValue.as_str(), "grant-config-value");
+        assert_eq!(ToolName::RevokeConfigValue.as_str(), "revoke-config-value");
         assert_eq!(ToolName::ResetPermission.as_str(), "reset-permission");
     }
 
@@ -201,15 +252,20 @@ mod tests {
         let test_cases = [
             ToolName::LoadComponent,
             ToolName::UnloadComponent,
+            ToolName::DeactivateComponent,
             ToolName::ListComponents,
+            ToolName::InstallComponent,
             ToolName::GetPolicy,
             ToolName::GrantStoragePermission,
             ToolName::GrantNetworkPermission,
             ToolName::GrantEnvironmentVariablePermission,
             ToolName::GrantMemoryPermission,
+            ToolName::GrantCpuPermission,
             ToolName::RevokeStoragePermission,
             ToolName::RevokeNetworkPermission,
             ToolName::RevokeEnvironmentVariablePermission,
+            ToolName::GrantConfigValue,
+            ToolName::RevokeConfigValue,
             ToolName::ResetPermission,
         ];
 
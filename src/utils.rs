@@ -8,7 +8,7 @@ use std::path::PathBuf;
 
 use anyhow::{bail, Context, Result};
 
-use crate::registry;
+use wassette::registry::{Registry, RegistrySource};
 
 mod built_info {
     include!(concat!(env!("OUT_DIR"), "/built.rs"));
@@ -28,6 +28,14 @@ pub fn parse_env_var(s: &str) -> Result<(String, String), String> {
     }
 }
 
+/// Parse a component ID argument, rejecting IDs that could not name a file
+/// in the component directory
+pub fn parse_component_id(s: &str) -> Result<String, String> {
+    wassette::validate_component_id(s)
+        .map(|()| s.to_string())
+        .map_err(|e| e.to_string())
+}
+
 /// Load environment variables from a file (supports .env format)
 pub fn load_env_file(path: &PathBuf) -> Result<HashMap<String, String>, anyhow::Error> {
     use std::fs;
@@ -79,10 +87,12 @@ pub fn load_env_file(path: &PathBuf) -> Result<HashMap<String, String>, anyhow::
     Ok(env_vars)
 }
 
-/// Load and parse the component registry JSON
-pub fn load_component_registry() -> Result<Vec<registry::RegistryComponent>> {
-    const COMPONENT_REGISTRY: &str = include_str!("../component-registry.json");
-    registry::parse_registry(COMPONENT_REGISTRY).context("Failed to parse component registry")
+/// Build the component registry from the embedded list plus any extra registry files
+pub fn load_component_registry(extra_sources: &[PathBuf]) -> Result<Registry> {
+    Registry::from_sources(
+        std::iter::once(RegistrySource::Embedded)
+            .chain(extra_sources.iter().cloned().map(RegistrySource::File)),
+    )
 }
 
 /// Formats build information similar to agentgateway's version output
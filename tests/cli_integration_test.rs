@@ -298,9 +298,10 @@ async fn test_cli_permission_grant_storage() -> Result<()> {
             "grant",
             "storage",
             component_id,
-            "fs:///tmp/test",
+            "fs://workspace",
             "--access",
             "read,write",
+            "--create",
         ])
         .await?;
 
@@ -429,9 +430,10 @@ async fn test_cli_permission_revoke_and_reset() -> Result<()> {
             "grant",
             "storage",
             component_id,
-            "fs:///tmp/test",
+            "fs://workspace",
             "--access",
-            "read",
+            "read,write",
+            "--create",
         ])
         .await?;
 
@@ -447,7 +449,7 @@ async fn test_cli_permission_revoke_and_reset() -> Result<()> {
             "revoke",
             "storage",
             component_id,
-            "fs:///tmp/test",
+            "fs://workspace",
         ])
         .await?;
 
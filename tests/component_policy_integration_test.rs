@@ -18,7 +18,9 @@ async fn setup_lifecycle_manager() -> Result<(Arc<LifecycleManager>, TempDir)> {
     let component_dir = temp_dir.path().join("components");
     tokio::fs::create_dir_all(&component_dir).await?;
 
-    let manager = LifecycleManager::new(&component_dir)
+    let manager = LifecycleManager::builder(&component_dir)
+        .with_absolute_storage_paths(true)
+        .build()
         .await
         .context("Failed to create lifecycle manager")?;
 
@@ -42,6 +44,7 @@ async fn test_component_lifecycle_with_policies() -> Result<()> {
     assert!(components.contains(&component_id));
 
     // Grant storage permission
+    std::fs::create_dir_all("/tmp/test")?;
     manager
         .grant_permission(
             &component_id,
@@ -144,6 +147,7 @@ async fn test_multiple_component_management() -> Result<()> {
     assert!(components.contains(&component_id_1));
 
     // Grant different permissions
+    std::fs::create_dir_all("/tmp/data")?;
     manager
         .grant_permission(
             &component_id_1,
@@ -3,7 +3,8 @@
 
 use anyhow::{Context, Result};
 use tempfile::TempDir;
-use wassette::LifecycleManager;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use wassette::{LifecycleManager, OutboundHttpConfig};
 
 mod common;
 use common::build_fetch_component;
@@ -248,3 +249,141 @@ async fn test_fetch_with_scheme_specific_permissions() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_fetch_timeout_reports_host_and_duration() -> Result<()> {
+    let (manager, _tempdir) = setup_lifecycle_manager().await?;
+    let component_path = build_fetch_component().await?;
+
+    let component_id = manager
+        .load_component(&format!("file://{}", component_path.to_str().unwrap()))
+        .await?
+        .component_id;
+
+    // A server that accepts connections but never answers
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let port = listener.local_addr()?.port();
+    let server = tokio::spawn(async move {
+        let mut connections = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            connections.push(stream);
+        }
+    });
+
+    manager
+        .grant_permission(
+            &component_id,
+            "network",
+            &serde_json::json!({"host": "127.0.0.1", "timeout": "500ms"}),
+        )
+        .await?;
+
+    let error = manager
+        .execute_component_call(
+            &component_id,
+            "fetch",
+            &serde_json::json!({"url": format!("http://127.0.0.1:{port}/")}).to_string(),
+        )
+        .await
+        .expect_err("request to an unresponsive host should time out");
+    server.abort();
+
+    let error_msg = error.to_string();
+    assert!(
+        error_msg.contains("timed out") && error_msg.contains("127.0.0.1"),
+        "Expected timeout error naming the host, got: {error_msg}"
+    );
+    assert!(
+        error_msg.contains("500ms"),
+        "Expected timeout error to include the duration, got: {error_msg}"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fetch_of_oversized_streamed_response_fails_in_guest() -> Result<()> {
+    const LIMIT: u64 = 1 << 20;
+    const BODY_SIZE: usize = 64 << 20;
+    const CHUNK_SIZE: usize = 64 << 10;
+
+    let tempdir = tempfile::tempdir()?;
+    let manager = LifecycleManager::builder(tempdir.path())
+        .with_outbound_http(OutboundHttpConfig {
+            max_response_bytes: Some(LIMIT),
+            ..Default::default()
+        })
+        .build()
+        .await?;
+    let component_path = build_fetch_component().await?;
+    let component_id = manager
+        .load_component(&format!("file://{}", component_path.to_str().unwrap()))
+        .await?
+        .component_id;
+
+    // A server that streams a chunked body without a Content-Length, so
+    // only the limit checked while streaming can stop it. Returns how many
+    // body bytes it got to send before the connection was dropped.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let port = listener.local_addr()?.port();
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await?;
+        let mut request = Vec::new();
+        let mut buf = [0u8; 1024];
+        while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+            let read = stream.read(&mut buf).await?;
+            anyhow::ensure!(read > 0, "connection closed before the request ended");
+            request.extend_from_slice(&buf[..read]);
+        }
+        stream
+            .write_all(
+                b"HTTP/1.1 200 OK\r\ncontent-type: application/octet-stream\r\ntransfer-encoding: chunked\r\n\r\n",
+            )
+            .await?;
+        let chunk = [b'x'; CHUNK_SIZE];
+        let mut sent = 0;
+        while sent < BODY_SIZE {
+            let written = async {
+                stream
+                    .write_all(format!("{CHUNK_SIZE:x}\r\n").as_bytes())
+                    .await?;
+                stream.write_all(&chunk).await?;
+                stream.write_all(b"\r\n").await
+            };
+            if written.await.is_err() {
+                break;
+            }
+            sent += CHUNK_SIZE;
+        }
+        Ok(sent)
+    });
+
+    manager
+        .grant_permission(
+            &component_id,
+            "network",
+            &serde_json::json!({"host": "127.0.0.1"}),
+        )
+        .await?;
+
+    let response = manager
+        .execute_component_call(
+            &component_id,
+            "fetch",
+            &serde_json::json!({"url": format!("http://127.0.0.1:{port}/large")}).to_string(),
+        )
+        .await?;
+    assert!(
+        response.contains("\"err\"") && response.contains("HttpResponseBodySize"),
+        "Expected the guest to see the response size error, got: {response}"
+    );
+
+    // The host stopped reading at the limit instead of buffering the body
+    let sent = tokio::time::timeout(std::time::Duration::from_secs(60), server).await???;
+    assert!(
+        sent < BODY_SIZE / 4,
+        "Expected the download to stop near the limit, but {sent} bytes were sent"
+    );
+
+    Ok(())
+}
@@ -27,7 +27,9 @@ async fn setup_lifecycle_manager() -> Result<(Arc<LifecycleManager>, TempDir)> {
     let tempdir = tempfile::tempdir()?;
 
     let manager = Arc::new(
-        LifecycleManager::new(&tempdir)
+        LifecycleManager::builder(&tempdir)
+            .with_absolute_storage_paths(true)
+            .build()
             .await
             .context("Failed to create LifecycleManager")?,
     );
@@ -50,6 +52,7 @@ async fn test_filesystem_component_integration() -> Result<()> {
     let mut child = tokio::process::Command::new(&binary_path)
         .args(["run", &component_dir_arg])
         .env("RUST_LOG", "off")
+        .env("WASSETTE_ALLOW_ABSOLUTE_STORAGE_PATHS", "true")
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -23,7 +23,9 @@ async fn setup_lifecycle_manager() -> Result<(Arc<LifecycleManager>, TempDir)> {
     let tempdir = tempfile::tempdir()?;
 
     let manager = Arc::new(
-        LifecycleManager::new(&tempdir)
+        LifecycleManager::builder(&tempdir)
+            .with_absolute_storage_paths(true)
+            .build()
             .await
             .context("Failed to create LifecycleManager")?,
     );
@@ -45,6 +47,7 @@ async fn test_grant_permission_storage_basic() -> Result<()> {
         .component_id;
 
     // Test granting storage permission
+    std::fs::create_dir_all("/tmp/test")?;
     let result = manager
         .grant_permission(
             &component_id,
@@ -90,6 +93,7 @@ async fn test_grant_permission_multiple_permissions() -> Result<()> {
         )
         .await;
 
+    std::fs::create_dir_all("/tmp/test")?;
     let storage_result = manager
         .grant_permission(
             &component_id,
@@ -163,6 +167,7 @@ async fn test_grant_permission_storage_access_merging() -> Result<()> {
         .component_id;
 
     // Grant read access first
+    std::fs::create_dir_all("/tmp/test")?;
     let read_result = manager
         .grant_permission(
             &component_id,
@@ -377,7 +382,10 @@ async fn test_grant_permission_policy_persistence() -> Result<()> {
     assert!(policy_info.is_some());
 
     // Create a new manager with the same directory to test persistence
-    let new_manager = wassette::LifecycleManager::new(_tempdir.path()).await?;
+    let new_manager = wassette::LifecycleManager::builder(_tempdir.path())
+        .with_absolute_storage_paths(true)
+        .build()
+        .await?;
 
     // Load the same component
     let new_component_id = new_manager
@@ -473,6 +481,9 @@ async fn test_grant_permission_complex_storage_permissions() -> Result<()> {
         ("fs:///tmp/workspace", vec!["read", "write"]),
     ];
 
+    std::fs::create_dir_all("/tmp/cache")?;
+    std::fs::create_dir_all("/tmp/output")?;
+    std::fs::create_dir_all("/tmp/workspace")?;
     for (uri, access) in &storage_configs {
         let result = manager
             .grant_permission(
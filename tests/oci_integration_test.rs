@@ -264,8 +264,12 @@ mod multi_layer_oci_tests {
             ..Default::default()
         });
 
-        let artifact =
-            wassette::oci_multi_layer::pull_multi_layer_artifact(&reference, &client).await?;
+        let artifact = wassette::oci_multi_layer::pull_multi_layer_artifact(
+            &reference,
+            &client,
+            &oci_client::secrets::RegistryAuth::Anonymous,
+        )
+        .await?;
 
         // Verify WASM component was downloaded
         assert!(!artifact.wasm_data.is_empty());
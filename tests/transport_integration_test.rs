@@ -2,6 +2,7 @@
 // Licensed under the MIT license.
 #![allow(clippy::uninlined_format_args)]
 
+use std::future::Future;
 use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
 use std::process::Stdio;
 use std::sync::Arc;
@@ -15,6 +16,10 @@ use hyper::service::service_fn;
 use hyper::{Request, Response};
 use hyper_util::rt::TokioIo;
 use oci_wasm::WasmClient;
+use rmcp::model::CallToolRequestParam;
+use rmcp::service::{NotificationContext, RunningService};
+use rmcp::transport::{SseClientTransport, StreamableHttpClientTransport};
+use rmcp::{RoleClient, ServiceExt};
 use tempfile::TempDir;
 use test_log::test;
 use testcontainers::core::WaitFor;
@@ -357,20 +362,20 @@ async fn test_load_component_from_oci() -> Result<()> {
     let (config, layer) = oci_wasm::WasmConfig::from_component(component_path, None).await?;
 
     // Create OCI client and push the component
-    let oci_client = oci_client::Client::new(oci_client::client::ClientConfig {
-        protocol: oci_client::client::ClientProtocol::Http,
+    let oci_client = oci_client_wasm::Client::new(oci_client_wasm::client::ClientConfig {
+        protocol: oci_client_wasm::client::ClientProtocol::Http,
         ..Default::default()
     });
 
     let wasm_client = WasmClient::new(oci_client);
     let reference = format!("{registry_url}/fetch_rs:latest");
-    let oci_reference: oci_client::Reference = reference.parse()?;
+    let oci_reference: oci_client_wasm::Reference = reference.parse()?;
 
     // Push to registry
     wasm_client
         .push(
             &oci_reference,
-            &oci_client::secrets::RegistryAuth::Anonymous,
+            &oci_client_wasm::secrets::RegistryAuth::Anonymous,
             layer,
             config,
             None,
@@ -944,6 +949,279 @@ async fn test_http_transport() -> Result<()> {
     Ok(())
 }
 
+#[test(tokio::test)]
+async fn test_metrics_are_opt_in_on_http_transports() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let component_dir_arg = format!("--component-dir={}", temp_dir.path().display());
+    let binary_path = std::env::current_dir()
+        .context("Failed to get current directory")?
+        .join("target/debug/wassette");
+    let client = reqwest::Client::new();
+
+    for (transport, metrics) in [
+        ("--sse", true),
+        ("--streamable-http", true),
+        ("--streamable-http", false),
+    ] {
+        let port = find_open_port().await?;
+        let bind_address_arg = format!("--bind-address=127.0.0.1:{port}");
+        let mut args = vec!["serve", transport, &component_dir_arg, &bind_address_arg];
+        if metrics {
+            args.push("--metrics");
+        }
+        let mut child = tokio::process::Command::new(&binary_path)
+            .args(&args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to start wassette with HTTP transport")?;
+
+        let url = format!("http://127.0.0.1:{port}/metrics");
+        let mut response = None;
+        for _ in 0..50 {
+            if let Ok(r) = client.get(&url).send().await {
+                response = Some(r);
+                break;
+            }
+            sleep(Duration::from_millis(200)).await;
+        }
+        let response = response.context("Server did not start listening")?;
+
+        if metrics {
+            assert_eq!(response.status(), 200, "{transport} should serve /metrics");
+            let body = response.text().await?;
+            assert!(body.contains("# TYPE wassette_active_components gauge"));
+        } else {
+            assert_eq!(
+                response.status(),
+                404,
+                "{transport} should not serve /metrics without --metrics"
+            );
+        }
+
+        child.kill().await.ok();
+    }
+
+    Ok(())
+}
+
+/// A client that forwards the tool list change notifications it gets.
+#[derive(Clone)]
+struct NotifiedClient(tokio::sync::mpsc::UnboundedSender<()>);
+
+impl rmcp::ClientHandler for NotifiedClient {
+    fn on_tool_list_changed(
+        &self,
+        _context: NotificationContext<RoleClient>,
+    ) -> impl Future<Output = ()> + Send + '_ {
+        let _ = self.0.send(());
+        std::future::ready(())
+    }
+}
+
+type Notifications = tokio::sync::mpsc::UnboundedReceiver<()>;
+
+/// Connect a client over `transport`. The server only notifies clients
+/// that have sent a request, so the client lists the tools once.
+async fn connect_client<T, E, A>(
+    transport: T,
+) -> Result<(RunningService<RoleClient, NotifiedClient>, Notifications)>
+where
+    T: rmcp::transport::IntoTransport<RoleClient, E, A>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+    let client = NotifiedClient(sender).serve(transport).await?;
+    client.list_tools(None).await?;
+    Ok((client, receiver))
+}
+
+async fn notified(notifications: &mut Notifications) -> bool {
+    tokio::time::timeout(Duration::from_secs(60), notifications.recv())
+        .await
+        .is_ok_and(|notification| notification.is_some())
+}
+
+/// Start `wassette serve` with `transport` on a free port and wait until it
+/// accepts connections.
+async fn start_http_server(
+    transport: &str,
+    component_dir: &TempDir,
+) -> Result<(tokio::process::Child, u16)> {
+    let port = find_open_port().await?;
+    let binary_path = std::env::current_dir()
+        .context("Failed to get current directory")?
+        .join("target/debug/wassette");
+    let child = tokio::process::Command::new(&binary_path)
+        .args([
+            "serve",
+            transport,
+            &format!("--component-dir={}", component_dir.path().display()),
+            &format!("--bind-address=127.0.0.1:{port}"),
+        ])
+        .env("RUST_LOG", "off")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .context("Failed to start wassette with HTTP transport")?;
+    for _ in 0..50 {
+        if tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .is_ok()
+        {
+            return Ok((child, port));
+        }
+        sleep(Duration::from_millis(200)).await;
+    }
+    anyhow::bail!("Server did not start listening on port {port}")
+}
+
+/// Load a component through `first` and unload it through `second`, and
+/// check that both clients are notified of each change.
+async fn assert_both_clients_notified(
+    (first, mut first_notifications): (RunningService<RoleClient, NotifiedClient>, Notifications),
+    (second, mut second_notifications): (RunningService<RoleClient, NotifiedClient>, Notifications),
+) -> Result<()> {
+    let component_path = build_fetch_component().await?;
+    let component_id = component_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .context("Component path has no file name")?;
+    let result = first
+        .call_tool(CallToolRequestParam {
+            name: "load-component".into(),
+            arguments: serde_json::json!({"path": format!("file://{}", component_path.display())})
+                .as_object()
+                .cloned(),
+        })
+        .await?;
+    assert_ne!(result.is_error, Some(true), "{result:?}");
+    assert!(notified(&mut first_notifications).await);
+    assert!(notified(&mut second_notifications).await);
+
+    let tools = second.list_tools(None).await?.tools;
+    assert!(tools.iter().any(|tool| tool.name == "fetch"));
+
+    let result = second
+        .call_tool(CallToolRequestParam {
+            name: "unload-component".into(),
+            arguments: serde_json::json!({"id": component_id}).as_object().cloned(),
+        })
+        .await?;
+    assert_ne!(result.is_error, Some(true), "{result:?}");
+    assert!(notified(&mut first_notifications).await);
+    assert!(notified(&mut second_notifications).await);
+
+    let tools = first.list_tools(None).await?.tools;
+    assert!(!tools.iter().any(|tool| tool.name == "fetch"));
+
+    first.cancel().await?;
+    second.cancel().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_sse_clients_are_notified_of_tool_changes() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let (mut child, port) = start_http_server("--sse", &temp_dir).await?;
+    let url = format!("http://127.0.0.1:{port}/sse");
+
+    let (first, second) = tokio::try_join!(
+        async { connect_client(SseClientTransport::start(url.as_str()).await?).await },
+        async { connect_client(SseClientTransport::start(url.as_str()).await?).await },
+    )?;
+    assert_both_clients_notified(first, second).await?;
+
+    child.kill().await.ok();
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_streamable_http_clients_are_notified_of_tool_changes() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let (mut child, port) = start_http_server("--streamable-http", &temp_dir).await?;
+    let url = format!("http://127.0.0.1:{port}/mcp");
+
+    let (first, second) = tokio::try_join!(
+        connect_client(StreamableHttpClientTransport::from_uri(url.as_str())),
+        connect_client(StreamableHttpClientTransport::from_uri(url.as_str())),
+    )?;
+    assert_both_clients_notified(first, second).await?;
+
+    child.kill().await.ok();
+    Ok(())
+}
+
+/// Put the fetch component in `component_dir`, so the server loads it in
+/// the background when it starts.
+async fn stage_fetch_component(component_dir: &TempDir) -> Result<()> {
+    let component_path = build_fetch_component().await?;
+    let file_name = component_path
+        .file_name()
+        .context("Component path has no file name")?;
+    tokio::fs::copy(&component_path, component_dir.path().join(file_name)).await?;
+    Ok(())
+}
+
+/// Check that `client`, connected while the server was still loading the
+/// component directory, is notified once the component has loaded.
+async fn assert_notified_of_background_load(
+    (client, mut notifications): (RunningService<RoleClient, NotifiedClient>, Notifications),
+) -> Result<()> {
+    let info = client
+        .call_tool(CallToolRequestParam {
+            name: "get-server-info".into(),
+            arguments: None,
+        })
+        .await?
+        .structured_content
+        .context("get-server-info returned no structured content")?;
+    assert_eq!(
+        info["background_loading"]["completed"],
+        serde_json::json!(false),
+        "the component finished loading before the client connected: {info}"
+    );
+
+    assert!(notified(&mut notifications).await);
+    let tools = client.list_tools(None).await?.tools;
+    assert!(tools.iter().any(|tool| tool.name == "fetch"));
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_sse_client_is_notified_of_background_load() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    stage_fetch_component(&temp_dir).await?;
+    let (mut child, port) = start_http_server("--sse", &temp_dir).await?;
+    let url = format!("http://127.0.0.1:{port}/sse");
+
+    let client = connect_client(SseClientTransport::start(url.as_str()).await?).await?;
+    assert_notified_of_background_load(client).await?;
+
+    child.kill().await.ok();
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_streamable_http_client_is_notified_of_background_load() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    stage_fetch_component(&temp_dir).await?;
+    let (mut child, port) = start_http_server("--streamable-http", &temp_dir).await?;
+    let url = format!("http://127.0.0.1:{port}/mcp");
+
+    let client = connect_client(StreamableHttpClientTransport::from_uri(url.as_str())).await?;
+    assert_notified_of_background_load(client).await?;
+
+    child.kill().await.ok();
+    Ok(())
+}
+
 #[test(tokio::test)]
 async fn test_default_stdio_transport() -> Result<()> {
     // Create a temporary directory for this test to avoid loading existing components